@@ -0,0 +1,239 @@
+//! The LZ4 block format.
+//!
+//! A block is a sequence of literal runs, each followed by a back reference
+//! to data already decompressed. See the [LZ4 block format description] for
+//! the details.
+//!
+//! [LZ4 block format description]: https://github.com/lz4/lz4/blob/dev/doc/lz4_Block_format.md
+
+use alloc::{vec, vec::Vec};
+
+use crate::DecompressError;
+
+/// Minimum length of a match.
+const MIN_MATCH: usize = 4;
+/// The last match must start at least this many bytes before the end.
+const MF_LIMIT: usize = 12;
+/// The last bytes of a block are always literals.
+const LAST_LITERALS: usize = 5;
+/// Maximum distance of a back reference.
+const MAX_OFFSET: usize = 0xffff;
+
+const HASH_LOG: u32 = 12;
+
+fn read_u32(input: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes(input[pos..pos + 4].try_into().unwrap())
+}
+
+fn hash(seq: u32) -> usize {
+    (seq.wrapping_mul(2_654_435_761) >> (32 - HASH_LOG)) as usize
+}
+
+/// Appends `n` to `out` as the continuation of a length field.
+fn push_len(out: &mut Vec<u8>, mut n: usize) {
+    while n >= 0xff {
+        out.push(0xff);
+        n -= 0xff;
+    }
+    out.push(u8::try_from(n).unwrap());
+}
+
+/// Appends a sequence of `literals`, followed by a match of `(offset, len)`.
+fn push_sequence(out: &mut Vec<u8>, literals: &[u8], matched: Option<(usize, usize)>) {
+    let lit_len = literals.len();
+    let match_len = matched.map_or(0, |(_, len)| len - MIN_MATCH);
+    let token = (lit_len.min(15) << 4) | match_len.min(15);
+    out.push(u8::try_from(token).unwrap());
+    if lit_len >= 15 {
+        push_len(out, lit_len - 15);
+    }
+    out.extend_from_slice(literals);
+    if let Some((offset, _)) = matched {
+        out.extend_from_slice(&u16::try_from(offset).unwrap().to_le_bytes());
+        if match_len >= 15 {
+            push_len(out, match_len - 15);
+        }
+    }
+}
+
+/// Compresses `input` as a single block and appends it to `out`.
+///
+/// The block does not refer to any data before `input`, so it can be
+/// decompressed on its own.
+pub fn compress(input: &[u8], out: &mut Vec<u8>) {
+    let mut table = vec![0_usize; 1 << HASH_LOG];
+    let mut anchor = 0;
+    let mut pos = 0;
+
+    while pos + MF_LIMIT <= input.len() {
+        let seq = read_u32(input, pos);
+        let slot = &mut table[hash(seq)];
+        let candidate = *slot;
+        *slot = pos;
+
+        if candidate >= pos || pos - candidate > MAX_OFFSET || read_u32(input, candidate) != seq {
+            // skip faster through data that does not compress
+            pos += 1 + ((pos - anchor) >> 6);
+            continue;
+        }
+
+        let offset = pos - candidate;
+        let mut start = pos;
+        while start > anchor && start > offset && input[start - 1] == input[start - 1 - offset] {
+            start -= 1;
+        }
+        let mut end = pos + MIN_MATCH;
+        while end < input.len() - LAST_LITERALS && input[end] == input[end - offset] {
+            end += 1;
+        }
+
+        push_sequence(out, &input[anchor..start], Some((offset, end - start)));
+        anchor = end;
+        pos = end;
+    }
+
+    push_sequence(out, &input[anchor..], None);
+}
+
+/// Reads the continuation of a length field whose first part is `n`.
+fn read_len(input: &[u8], pos: &mut usize, mut n: usize) -> Result<usize, DecompressError> {
+    if n == 15 {
+        loop {
+            let b = *input.get(*pos).ok_or(DecompressError::Truncated)?;
+            *pos += 1;
+            n = n
+                .checked_add(usize::from(b))
+                .ok_or(DecompressError::TooLarge)?;
+            if b != 0xff {
+                break;
+            }
+        }
+    }
+    Ok(n)
+}
+
+/// Decompresses the block `input` and appends the data to `out`.
+///
+/// Back references may refer to the data already in `out`, which is how
+/// blocks that depend on the previous ones are decompressed. At most
+/// `max_len` bytes are appended.
+pub fn decompress(input: &[u8], out: &mut Vec<u8>, max_len: usize) -> Result<(), DecompressError> {
+    let limit = out.len().saturating_add(max_len);
+    let mut pos = 0;
+    loop {
+        let token = *input.get(pos).ok_or(DecompressError::Truncated)?;
+        pos += 1;
+
+        let lit_len = read_len(input, &mut pos, usize::from(token >> 4))?;
+        let literals = pos
+            .checked_add(lit_len)
+            .and_then(|end| input.get(pos..end))
+            .ok_or(DecompressError::Truncated)?;
+        if lit_len > limit - out.len() {
+            return Err(DecompressError::TooLarge);
+        }
+        out.extend_from_slice(literals);
+        pos += lit_len;
+        if pos == input.len() {
+            return Ok(());
+        }
+
+        let (Some(&lo), Some(&hi)) = (input.get(pos), input.get(pos + 1)) else {
+            return Err(DecompressError::Truncated);
+        };
+        let offset = usize::from(u16::from_le_bytes([lo, hi]));
+        pos += 2;
+        if offset == 0 || offset > out.len() {
+            return Err(DecompressError::InvalidOffset);
+        }
+        let match_len = read_len(input, &mut pos, usize::from(token & 0xf))? + MIN_MATCH;
+        if match_len > limit - out.len() {
+            return Err(DecompressError::TooLarge);
+        }
+
+        let start = out.len() - offset;
+        if offset >= match_len {
+            out.extend_from_within(start..start + match_len);
+        } else {
+            // the match overlaps the data it produces
+            for i in start..start + match_len {
+                out.push(out[i]);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(data: &[u8]) -> usize {
+        let mut compressed = Vec::new();
+        compress(data, &mut compressed);
+        let mut out = Vec::new();
+        decompress(&compressed, &mut out, data.len()).unwrap();
+        assert_eq!(out, data);
+        compressed.len()
+    }
+
+    #[test]
+    fn compress_round_trip() {
+        assert_eq!(round_trip(b""), 1);
+        round_trip(b"a");
+        round_trip(b"hello, world");
+
+        let repeated = b"ov6 ".repeat(1000);
+        assert!(round_trip(&repeated) < 100);
+        assert!(round_trip(&vec![0; 100_000]) < 1000);
+
+        let mut state = 1_u32;
+        let random = core::iter::repeat_with(|| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state.to_le_bytes()[0]
+        })
+        .take(10_000)
+        .collect::<Vec<_>>();
+        assert!(round_trip(&random) < random.len() + random.len() / 200 + 16);
+    }
+
+    #[test]
+    fn decompress_overlapping() {
+        // "ab" followed by a match of 7 bytes at offset 2
+        let block = [0x23, b'a', b'b', 0x02, 0x00, 0x00];
+        let mut out = Vec::new();
+        decompress(&block, &mut out, 100).unwrap();
+        assert_eq!(out, b"ababababa");
+    }
+
+    #[test]
+    fn decompress_with_prefix() {
+        let mut out = b"abcd".to_vec();
+        // no literals, then a match of 4 bytes at offset 4
+        decompress(&[0x00, 0x04, 0x00, 0x00], &mut out, 100).unwrap();
+        assert_eq!(out, b"abcdabcd");
+    }
+
+    #[test]
+    fn decompress_errors() {
+        let mut out = Vec::new();
+        assert_eq!(
+            decompress(&[], &mut out, 100),
+            Err(DecompressError::Truncated)
+        );
+        assert_eq!(
+            decompress(&[0x20, b'a'], &mut out, 100),
+            Err(DecompressError::Truncated)
+        );
+        assert_eq!(
+            decompress(&[0x10, b'a', 0x02, 0x00, 0x00], &mut out, 100),
+            Err(DecompressError::InvalidOffset)
+        );
+        out.clear();
+        assert_eq!(
+            decompress(&[0x1f, b'a', 0x01, 0x00, 0xff, 0x00], &mut out, 100),
+            Err(DecompressError::TooLarge)
+        );
+    }
+}
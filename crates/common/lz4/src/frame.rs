@@ -0,0 +1,418 @@
+//! The LZ4 frame format.
+//!
+//! A frame wraps blocks with a header and checksums, and is what the `lz4`
+//! command line tool reads and writes. See the [LZ4 frame format description]
+//! for the details.
+//!
+//! [LZ4 frame format description]: https://github.com/lz4/lz4/blob/dev/doc/lz4_Frame_format.md
+
+use alloc::vec::Vec;
+
+use crate::{DecompressError, block, xxh32::Xxh32};
+
+const MAGIC: u32 = 0x184D_2204;
+
+const FLG_VERSION: u8 = 0b0100_0000;
+const FLG_VERSION_MASK: u8 = 0b1100_0000;
+const FLG_BLOCK_INDEPENDENT: u8 = 0b0010_0000;
+const FLG_BLOCK_CHECKSUM: u8 = 0b0001_0000;
+const FLG_CONTENT_SIZE: u8 = 0b0000_1000;
+const FLG_CONTENT_CHECKSUM: u8 = 0b0000_0100;
+const FLG_RESERVED: u8 = 0b0000_0010;
+const FLG_DICT_ID: u8 = 0b0000_0001;
+
+const BD_RESERVED: u8 = 0b1000_1111;
+/// Block maximum size field of 64 KiB.
+const BD_64K: u8 = 4 << 4;
+
+/// Set in a block size if the block is stored uncompressed.
+const UNCOMPRESSED: u32 = 1 << 31;
+
+/// Maximum size of the blocks written by [`FrameEncoder`].
+pub const BLOCK_SIZE: usize = 64 * 1024;
+
+/// Size of the history linked blocks may refer to.
+const WINDOW_SIZE: usize = 64 * 1024;
+
+fn header_checksum(descriptor: &[u8]) -> u8 {
+    (crate::xxh32(descriptor, 0) >> 8).to_le_bytes()[0]
+}
+
+/// Compresses a stream of data into a frame.
+///
+/// Blocks are independent of each other and are followed by a checksum of
+/// the whole content.
+#[derive(Debug)]
+pub struct FrameEncoder {
+    hasher: Xxh32,
+    block: Vec<u8>,
+    compressed: Vec<u8>,
+}
+
+impl FrameEncoder {
+    /// Creates an encoder and appends the frame header to `out`.
+    #[must_use]
+    pub fn new(out: &mut Vec<u8>) -> Self {
+        let descriptor = [
+            FLG_VERSION | FLG_BLOCK_INDEPENDENT | FLG_CONTENT_CHECKSUM,
+            BD_64K,
+        ];
+        out.extend_from_slice(&MAGIC.to_le_bytes());
+        out.extend_from_slice(&descriptor);
+        out.push(header_checksum(&descriptor));
+        Self {
+            hasher: Xxh32::new(0),
+            block: Vec::with_capacity(BLOCK_SIZE),
+            compressed: Vec::new(),
+        }
+    }
+
+    /// Compresses `data` and appends the complete blocks to `out`.
+    ///
+    /// Data that does not fill a block is kept until more data is given, or
+    /// until [`FrameEncoder::finish`] is called.
+    pub fn encode(&mut self, mut data: &[u8], out: &mut Vec<u8>) {
+        while !data.is_empty() {
+            let len = usize::min(BLOCK_SIZE - self.block.len(), data.len());
+            self.block.extend_from_slice(&data[..len]);
+            data = &data[len..];
+            if self.block.len() == BLOCK_SIZE {
+                self.flush_block(out);
+            }
+        }
+    }
+
+    fn flush_block(&mut self, out: &mut Vec<u8>) {
+        if self.block.is_empty() {
+            return;
+        }
+        self.hasher.update(&self.block);
+        self.compressed.clear();
+        block::compress(&self.block, &mut self.compressed);
+        if self.compressed.len() < self.block.len() {
+            let size = u32::try_from(self.compressed.len()).unwrap();
+            out.extend_from_slice(&size.to_le_bytes());
+            out.extend_from_slice(&self.compressed);
+        } else {
+            let size = u32::try_from(self.block.len()).unwrap() | UNCOMPRESSED;
+            out.extend_from_slice(&size.to_le_bytes());
+            out.extend_from_slice(&self.block);
+        }
+        self.block.clear();
+    }
+
+    /// Appends the remaining data and the end of the frame to `out`.
+    pub fn finish(mut self, out: &mut Vec<u8>) {
+        self.flush_block(out);
+        out.extend_from_slice(&0_u32.to_le_bytes());
+        out.extend_from_slice(&self.hasher.finish().to_le_bytes());
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Magic,
+    Descriptor,
+    DescriptorRest { len: usize },
+    BlockSize,
+    Block { len: usize, compressed: bool },
+    BlockChecksum { expected: u32 },
+    ContentChecksum,
+}
+
+impl State {
+    /// Returns the number of bytes needed to leave the state.
+    fn needed(self) -> usize {
+        match self {
+            Self::Magic | Self::BlockSize | Self::BlockChecksum { .. } | Self::ContentChecksum => 4,
+            Self::Descriptor => 2,
+            Self::DescriptorRest { len } | Self::Block { len, .. } => len,
+        }
+    }
+}
+
+/// Decompresses a stream of frames.
+///
+/// The input can be given in pieces of any size. Frames written one after
+/// another are decompressed as one stream.
+#[derive(Debug)]
+pub struct FrameDecoder {
+    state: State,
+    pending: Vec<u8>,
+    flags: u8,
+    descriptor: Xxh32,
+    block_max: usize,
+    content_size: Option<u64>,
+    content_len: u64,
+    hasher: Xxh32,
+    window: Vec<u8>,
+    frames: usize,
+}
+
+impl Default for FrameDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameDecoder {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            state: State::Magic,
+            pending: Vec::new(),
+            flags: 0,
+            descriptor: Xxh32::new(0),
+            block_max: 0,
+            content_size: None,
+            content_len: 0,
+            hasher: Xxh32::new(0),
+            window: Vec::new(),
+            frames: 0,
+        }
+    }
+
+    /// Decompresses `input` and appends the data to `out`.
+    pub fn decode(&mut self, mut input: &[u8], out: &mut Vec<u8>) -> Result<(), DecompressError> {
+        while !input.is_empty() {
+            let needed = self.state.needed();
+            if self.pending.is_empty() && input.len() >= needed {
+                // avoid copying a whole block into the pending buffer
+                let (data, rest) = input.split_at(needed);
+                self.step(data, out)?;
+                input = rest;
+                continue;
+            }
+
+            let len = usize::min(needed - self.pending.len(), input.len());
+            self.pending.extend_from_slice(&input[..len]);
+            input = &input[len..];
+            if self.pending.len() == needed {
+                let pending = core::mem::take(&mut self.pending);
+                let res = self.step(&pending, out);
+                self.pending = pending;
+                self.pending.clear();
+                res?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the input ended at the end of a frame.
+    pub fn finish(&self) -> Result<(), DecompressError> {
+        if self.frames == 0 || self.state != State::Magic || !self.pending.is_empty() {
+            return Err(DecompressError::Truncated);
+        }
+        Ok(())
+    }
+
+    fn step(&mut self, data: &[u8], out: &mut Vec<u8>) -> Result<(), DecompressError> {
+        match self.state {
+            State::Magic => {
+                if read_u32(data) != MAGIC {
+                    return Err(DecompressError::InvalidMagic);
+                }
+                self.state = State::Descriptor;
+            }
+            State::Descriptor => {
+                let [flags, bd] = data.try_into().unwrap();
+                if flags & FLG_VERSION_MASK != FLG_VERSION
+                    || flags & (FLG_RESERVED | FLG_DICT_ID) != 0
+                    || bd & BD_RESERVED != 0
+                {
+                    return Err(DecompressError::Unsupported);
+                }
+                self.block_max = match bd >> 4 {
+                    4 => 64 * 1024,
+                    5 => 256 * 1024,
+                    6 => 1024 * 1024,
+                    7 => 4 * 1024 * 1024,
+                    _ => return Err(DecompressError::Unsupported),
+                };
+                self.flags = flags;
+                self.descriptor = Xxh32::new(0);
+                self.descriptor.update(data);
+                let len = if flags & FLG_CONTENT_SIZE != 0 { 9 } else { 1 };
+                self.state = State::DescriptorRest { len };
+            }
+            State::DescriptorRest { .. } => {
+                let (checksum, fields) = data.split_last().unwrap();
+                self.descriptor.update(fields);
+                if (self.descriptor.finish() >> 8).to_le_bytes()[0] != *checksum {
+                    return Err(DecompressError::HeaderChecksum);
+                }
+                self.content_size =
+                    (!fields.is_empty()).then(|| u64::from_le_bytes(fields.try_into().unwrap()));
+                self.content_len = 0;
+                self.hasher = Xxh32::new(0);
+                self.window.clear();
+                self.state = State::BlockSize;
+            }
+            State::BlockSize => {
+                let size = read_u32(data);
+                if size == 0 {
+                    if self.flags & FLG_CONTENT_CHECKSUM != 0 {
+                        self.state = State::ContentChecksum;
+                    } else {
+                        self.end_frame()?;
+                    }
+                    return Ok(());
+                }
+                let len = (size & !UNCOMPRESSED) as usize;
+                if len > self.block_max {
+                    return Err(DecompressError::TooLarge);
+                }
+                let compressed = size & UNCOMPRESSED == 0;
+                self.state = State::Block { len, compressed };
+            }
+            State::Block { compressed, .. } => {
+                self.decode_block(data, compressed, out)?;
+                self.state = if self.flags & FLG_BLOCK_CHECKSUM != 0 {
+                    State::BlockChecksum {
+                        expected: crate::xxh32(data, 0),
+                    }
+                } else {
+                    State::BlockSize
+                };
+            }
+            State::BlockChecksum { expected } => {
+                if read_u32(data) != expected {
+                    return Err(DecompressError::BlockChecksum);
+                }
+                self.state = State::BlockSize;
+            }
+            State::ContentChecksum => {
+                if read_u32(data) != self.hasher.finish() {
+                    return Err(DecompressError::ContentChecksum);
+                }
+                self.end_frame()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn decode_block(
+        &mut self,
+        data: &[u8],
+        compressed: bool,
+        out: &mut Vec<u8>,
+    ) -> Result<(), DecompressError> {
+        if self.flags & FLG_BLOCK_INDEPENDENT != 0 {
+            self.window.clear();
+        } else if self.window.len() > WINDOW_SIZE {
+            // keep the history the next block may refer to
+            self.window.drain(..self.window.len() - WINDOW_SIZE);
+        }
+        let start = self.window.len();
+        if compressed {
+            block::decompress(data, &mut self.window, self.block_max)?;
+        } else {
+            self.window.extend_from_slice(data);
+        }
+
+        let decoded = &self.window[start..];
+        self.hasher.update(decoded);
+        self.content_len += decoded.len() as u64;
+        out.extend_from_slice(decoded);
+        Ok(())
+    }
+
+    fn end_frame(&mut self) -> Result<(), DecompressError> {
+        if self
+            .content_size
+            .is_some_and(|size| size != self.content_len)
+        {
+            return Err(DecompressError::ContentSize);
+        }
+        self.frames += 1;
+        self.state = State::Magic;
+        Ok(())
+    }
+}
+
+fn read_u32(data: &[u8]) -> u32 {
+    u32::from_le_bytes(data.try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compress;
+
+    fn decode_by(frame: &[u8], chunk_size: usize) -> Result<Vec<u8>, DecompressError> {
+        let mut decoder = FrameDecoder::new();
+        let mut out = Vec::new();
+        for chunk in frame.chunks(chunk_size) {
+            decoder.decode(chunk, &mut out)?;
+        }
+        decoder.finish()?;
+        Ok(out)
+    }
+
+    #[test]
+    fn round_trip() {
+        let data = (0..200_000_u32)
+            .flat_map(|n| (n / 7).to_le_bytes())
+            .collect::<Vec<_>>();
+        let frame = compress(&data);
+        assert!(frame.len() < data.len() / 2);
+        for chunk_size in [1, 5, 1000, frame.len()] {
+            assert_eq!(decode_by(&frame, chunk_size).unwrap(), data);
+        }
+
+        let empty = compress(b"");
+        assert_eq!(empty.len(), 15);
+        assert_eq!(decode_by(&empty, 1).unwrap(), b"");
+    }
+
+    #[test]
+    fn concatenated_frames() {
+        let mut frames = compress(b"hello, ");
+        frames.extend(compress(b"world"));
+        assert_eq!(decode_by(&frames, 3).unwrap(), b"hello, world");
+    }
+
+    #[test]
+    fn header_fields() {
+        // a frame with a content size, a block checksum and no content
+        // checksum, holding one uncompressed block
+        let descriptor = [FLG_VERSION | FLG_BLOCK_CHECKSUM | FLG_CONTENT_SIZE, BD_64K];
+        let mut header = descriptor.to_vec();
+        header.extend_from_slice(&5_u64.to_le_bytes());
+        let mut frame = MAGIC.to_le_bytes().to_vec();
+        frame.extend_from_slice(&header);
+        frame.push(header_checksum(&header));
+        frame.extend_from_slice(&(5 | UNCOMPRESSED).to_le_bytes());
+        frame.extend_from_slice(b"hello");
+        frame.extend_from_slice(&crate::xxh32(b"hello", 0).to_le_bytes());
+        frame.extend_from_slice(&0_u32.to_le_bytes());
+        assert_eq!(decode_by(&frame, 2).unwrap(), b"hello");
+
+        let mut broken = frame.clone();
+        broken[4 + header.len()] ^= 1;
+        assert_eq!(decode_by(&broken, 2), Err(DecompressError::HeaderChecksum));
+
+        let mut broken = frame.clone();
+        broken[frame.len() - 8] ^= 1;
+        assert_eq!(decode_by(&broken, 2), Err(DecompressError::BlockChecksum));
+    }
+
+    #[test]
+    fn errors() {
+        let frame = compress(b"hello, world");
+        assert_eq!(
+            decode_by(&frame[..frame.len() - 1], 4),
+            Err(DecompressError::Truncated)
+        );
+        assert_eq!(decode_by(b"", 1), Err(DecompressError::Truncated));
+        assert_eq!(
+            decode_by(b"\0\0\0\0", 1),
+            Err(DecompressError::InvalidMagic)
+        );
+
+        let mut broken = frame;
+        let len = broken.len();
+        broken[len - 1] ^= 1;
+        assert_eq!(decode_by(&broken, 4), Err(DecompressError::ContentChecksum));
+    }
+}
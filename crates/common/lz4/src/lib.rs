@@ -0,0 +1,79 @@
+//! LZ4 compression.
+//!
+//! Implements the LZ4 block format and the LZ4 frame format, compatible with
+//! the `lz4` command line tool. Only the heap is needed, so the crate is used
+//! by both ov6 user programs and the host tools.
+
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::fmt;
+
+pub use self::{
+    frame::{BLOCK_SIZE, FrameDecoder, FrameEncoder},
+    xxh32::{Xxh32, xxh32},
+};
+
+pub mod block;
+mod frame;
+mod xxh32;
+
+/// An error decompressing LZ4 data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecompressError {
+    /// The input ended in the middle of a block or a frame.
+    Truncated,
+    /// A back reference points before the start of the data.
+    InvalidOffset,
+    /// A block is larger than allowed.
+    TooLarge,
+    /// The input does not start with the frame magic number.
+    InvalidMagic,
+    /// The frame uses a version or a feature that is not supported.
+    Unsupported,
+    HeaderChecksum,
+    BlockChecksum,
+    ContentChecksum,
+    /// The content size in the frame header does not match the data.
+    ContentSize,
+}
+
+impl fmt::Display for DecompressError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            Self::Truncated => "unexpected end of data",
+            Self::InvalidOffset => "invalid match offset",
+            Self::TooLarge => "block too large",
+            Self::InvalidMagic => "not in LZ4 format",
+            Self::Unsupported => "unsupported LZ4 frame",
+            Self::HeaderChecksum => "header checksum mismatch",
+            Self::BlockChecksum => "block checksum mismatch",
+            Self::ContentChecksum => "content checksum mismatch",
+            Self::ContentSize => "content size mismatch",
+        };
+        f.write_str(msg)
+    }
+}
+
+impl core::error::Error for DecompressError {}
+
+/// Compresses `data` into a single frame.
+#[must_use]
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut encoder = FrameEncoder::new(&mut out);
+    encoder.encode(data, &mut out);
+    encoder.finish(&mut out);
+    out
+}
+
+/// Decompresses the frames in `data`.
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, DecompressError> {
+    let mut decoder = FrameDecoder::new();
+    let mut out = Vec::new();
+    decoder.decode(data, &mut out)?;
+    decoder.finish()?;
+    Ok(out)
+}
@@ -0,0 +1,147 @@
+//! The xxHash32 checksum used by the LZ4 frame format.
+
+const PRIME1: u32 = 0x9E37_79B1;
+const PRIME2: u32 = 0x85EB_CA77;
+const PRIME3: u32 = 0xC2B2_AE3D;
+const PRIME4: u32 = 0x27D4_EB2F;
+const PRIME5: u32 = 0x1656_67B1;
+
+/// Size of the stripes the input is processed in.
+const STRIPE_SIZE: usize = 16;
+
+/// Streaming xxHash32 hasher.
+#[derive(Debug, Clone)]
+pub struct Xxh32 {
+    seed: u32,
+    acc: [u32; 4],
+    buf: [u8; STRIPE_SIZE],
+    buf_len: usize,
+    total_len: u64,
+}
+
+impl Xxh32 {
+    /// Creates a hasher with the given `seed`.
+    #[must_use]
+    pub const fn new(seed: u32) -> Self {
+        Self {
+            seed,
+            acc: [
+                seed.wrapping_add(PRIME1).wrapping_add(PRIME2),
+                seed.wrapping_add(PRIME2),
+                seed,
+                seed.wrapping_sub(PRIME1),
+            ],
+            buf: [0; STRIPE_SIZE],
+            buf_len: 0,
+            total_len: 0,
+        }
+    }
+
+    /// Feeds `data` to the hasher.
+    pub fn update(&mut self, mut data: &[u8]) {
+        self.total_len += data.len() as u64;
+
+        if self.buf_len > 0 {
+            let len = usize::min(STRIPE_SIZE - self.buf_len, data.len());
+            self.buf[self.buf_len..][..len].copy_from_slice(&data[..len]);
+            self.buf_len += len;
+            data = &data[len..];
+            if self.buf_len < STRIPE_SIZE {
+                return;
+            }
+            let stripe = self.buf;
+            self.process(&stripe);
+            self.buf_len = 0;
+        }
+
+        let mut stripes = data.chunks_exact(STRIPE_SIZE);
+        for stripe in &mut stripes {
+            self.process(stripe);
+        }
+        let rest = stripes.remainder();
+        self.buf[..rest.len()].copy_from_slice(rest);
+        self.buf_len = rest.len();
+    }
+
+    fn process(&mut self, stripe: &[u8]) {
+        for (acc, lane) in self.acc.iter_mut().zip(stripe.chunks_exact(4)) {
+            *acc = round(*acc, read_u32(lane));
+        }
+    }
+
+    /// Returns the hash of the data fed so far.
+    #[must_use]
+    #[expect(clippy::cast_possible_truncation)]
+    pub fn finish(&self) -> u32 {
+        let [v1, v2, v3, v4] = self.acc;
+        let mut h = if self.total_len >= STRIPE_SIZE as u64 {
+            v1.rotate_left(1)
+                .wrapping_add(v2.rotate_left(7))
+                .wrapping_add(v3.rotate_left(12))
+                .wrapping_add(v4.rotate_left(18))
+        } else {
+            self.seed.wrapping_add(PRIME5)
+        };
+        // only the lower 32 bits of the length are mixed in
+        h = h.wrapping_add(self.total_len as u32);
+
+        let mut words = self.buf[..self.buf_len].chunks_exact(4);
+        for word in &mut words {
+            h = h.wrapping_add(read_u32(word).wrapping_mul(PRIME3));
+            h = h.rotate_left(17).wrapping_mul(PRIME4);
+        }
+        for &b in words.remainder() {
+            h = h.wrapping_add(u32::from(b).wrapping_mul(PRIME5));
+            h = h.rotate_left(11).wrapping_mul(PRIME1);
+        }
+
+        h ^= h >> 15;
+        h = h.wrapping_mul(PRIME2);
+        h ^= h >> 13;
+        h = h.wrapping_mul(PRIME3);
+        h ^= h >> 16;
+        h
+    }
+}
+
+fn round(acc: u32, lane: u32) -> u32 {
+    acc.wrapping_add(lane.wrapping_mul(PRIME2))
+        .rotate_left(13)
+        .wrapping_mul(PRIME1)
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes(bytes.try_into().unwrap())
+}
+
+/// Returns the xxHash32 of `data`.
+#[must_use]
+pub fn xxh32(data: &[u8], seed: u32) -> u32 {
+    let mut hasher = Xxh32::new(seed);
+    hasher.update(data);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_values() {
+        assert_eq!(xxh32(b"", 0), 0x02CC_5D05);
+        assert_eq!(xxh32(b"abc", 0), 0x32D1_53FF);
+    }
+
+    #[test]
+    fn streaming() {
+        let data = (0..=255).cycle().take(1000).collect::<Vec<u8>>();
+        let expected = xxh32(&data, 1);
+        for chunk_size in [1, 3, 15, 16, 17, 100] {
+            let mut hasher = Xxh32::new(1);
+            for chunk in data.chunks(chunk_size) {
+                hasher.update(chunk);
+            }
+            assert_eq!(hasher.finish(), expected, "chunk size {chunk_size}");
+        }
+    }
+}
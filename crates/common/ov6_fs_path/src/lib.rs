@@ -0,0 +1,412 @@
+//! Directory lookup and path resolution of ov6 file system.
+//!
+//! A directory is a file containing a sequence of [`DirEntry`]s. Entries
+//! whose inode number is zero are free. This crate implements the operations
+//! on directories and paths over abstract interfaces, so that the kernel and
+//! the tools building or checking file system images share them:
+//!
+//! * [`DirContent`] gives access to the entries of a directory, on which
+//!   [`entries()`], [`next_entry()`], [`lookup()`], [`lookup_ino()`],
+//!   [`is_empty()`] and [`link_slot()`] work.
+//! * [`Walk`] looks up a name in a directory inode, on which [`resolve()`]
+//!   walks a path.
+
+#![cfg_attr(not(test), no_std)]
+
+use ov6_fs_types::{DirEntry, InodeNo};
+use ov6_types::{
+    os_str::OsStr,
+    path::{Component, Path},
+};
+
+/// Size of a directory entry in bytes.
+pub const DIR_ENTRY_SIZE: usize = size_of::<DirEntry>();
+
+/// Contents of a directory.
+pub trait DirContent {
+    /// The error type returned when reading the directory fails.
+    type Error;
+
+    /// Returns the size of the directory in bytes.
+    fn size(&self) -> usize;
+
+    /// Reads the directory entry at byte offset `off`.
+    fn read_entry(&mut self, off: usize) -> Result<DirEntry, Self::Error>;
+}
+
+/// An iterator over the entries of a directory, returned by [`entries()`].
+pub struct Entries<'a, D> {
+    dir: &'a mut D,
+    off: usize,
+}
+
+impl<D> Iterator for Entries<'_, D>
+where
+    D: DirContent,
+{
+    /// The byte offset of the entry and the entry.
+    type Item = Result<(usize, DirEntry), D::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.off + DIR_ENTRY_SIZE > self.dir.size() {
+            return None;
+        }
+        let off = self.off;
+        self.off += DIR_ENTRY_SIZE;
+        Some(self.dir.read_entry(off).map(|de| (off, de)))
+    }
+}
+
+/// Returns an iterator over all the entries of `dir`, including free ones.
+pub fn entries<D>(dir: &mut D) -> Entries<'_, D>
+where
+    D: DirContent,
+{
+    Entries { dir, off: 0 }
+}
+
+/// Returns the first used entry of `dir` at or after the byte offset `off`,
+/// and the offset of the entry.
+///
+/// An offset in the middle of an entry starts from the next entry. Returns
+/// `None` if there is no such entry.
+pub fn next_entry<D>(dir: &mut D, off: usize) -> Result<Option<(usize, DirEntry)>, D::Error>
+where
+    D: DirContent,
+{
+    let mut it = Entries {
+        dir,
+        off: off.next_multiple_of(DIR_ENTRY_SIZE),
+    };
+    for res in &mut it {
+        let (off, de) = res?;
+        if de.ino().is_some() {
+            return Ok(Some((off, de)));
+        }
+    }
+    Ok(None)
+}
+
+/// Returns `true` if `name` is `"."` or `".."`.
+#[must_use]
+pub fn is_dot_or_dot_dot(name: &OsStr) -> bool {
+    name == "." || name == ".."
+}
+
+/// Looks up for a directory entry by given `name`.
+///
+/// Names longer than the entry are compared by their prefix, as they are
+/// stored truncated. Returns the inode number of the entry and its byte
+/// offset, or `None` if the entry is not found.
+pub fn lookup<D>(dir: &mut D, name: &OsStr) -> Result<Option<(InodeNo, usize)>, D::Error>
+where
+    D: DirContent,
+{
+    for res in entries(dir) {
+        let (off, de) = res?;
+        if let Some(ino) = de.ino()
+            && de.is_same_name(name)
+        {
+            return Ok(Some((ino, off)));
+        }
+    }
+    Ok(None)
+}
+
+/// Looks up for a directory entry that refers to the inode `ino`.
+///
+/// `"."` and `".."` are skipped. Returns `None` if no other entry refers to
+/// `ino`.
+pub fn lookup_ino<D>(dir: &mut D, ino: InodeNo) -> Result<Option<DirEntry>, D::Error>
+where
+    D: DirContent,
+{
+    for res in entries(dir) {
+        let (_off, de) = res?;
+        if de.ino() == Some(ino) && !is_dot_or_dot_dot(de.name()) {
+            return Ok(Some(de));
+        }
+    }
+    Ok(None)
+}
+
+/// Returns `true` if the directory is empty except for `"."` and `".."`.
+///
+/// Every entry is checked, so the directory is not considered empty even if
+/// `"."` and `".."` are not the first two entries.
+pub fn is_empty<D>(dir: &mut D) -> Result<bool, D::Error>
+where
+    D: DirContent,
+{
+    for res in entries(dir) {
+        let (_off, de) = res?;
+        if de.ino().is_some() && !is_dot_or_dot_dot(de.name()) {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Returns the byte offset where a new entry named `name` should be written.
+///
+/// The directory is searched for `name` and for a free entry in a single
+/// pass. The first free entry is reused; if there is none, the entry is
+/// appended at the end of the directory. Returns `None` if an entry named
+/// `name` already exists.
+pub fn link_slot<D>(dir: &mut D, name: &OsStr) -> Result<Option<usize>, D::Error>
+where
+    D: DirContent,
+{
+    let mut free_off = None;
+    for res in entries(dir) {
+        let (off, de) = res?;
+        if de.ino().is_none() {
+            free_off.get_or_insert(off);
+        } else if de.is_same_name(name) {
+            return Ok(None);
+        }
+    }
+    Ok(Some(free_off.unwrap_or_else(|| dir.size())))
+}
+
+/// Inodes that a path can be walked through.
+pub trait Walk {
+    /// A reference to an inode.
+    type Inode: Clone;
+    /// The error type returned when a lookup fails.
+    type Error;
+
+    /// Returns `true` if `a` and `b` refer to the same inode.
+    fn is_same(&self, a: &Self::Inode, b: &Self::Inode) -> bool;
+
+    /// Looks up `name` in the directory `dir`.
+    ///
+    /// `name` may be `"."` or `".."`, which are looked up as entries of
+    /// `dir`. Fails if `dir` is not a directory or has no entry named `name`.
+    fn lookup(&mut self, dir: &Self::Inode, name: &OsStr) -> Result<Self::Inode, Self::Error>;
+}
+
+/// Looks up and returns the inode for a given path.
+///
+/// Absolute paths are resolved starting from `root`, relative ones from
+/// `cwd`, and `..` never climbs above `root`.
+pub fn resolve<W>(
+    walk: &mut W,
+    root: &W::Inode,
+    cwd: W::Inode,
+    path: &Path,
+) -> Result<W::Inode, W::Error>
+where
+    W: Walk,
+{
+    let mut components = path.components().peekable();
+    let mut ip = if components.next_if_eq(&Component::RootDir).is_some() {
+        root.clone()
+    } else {
+        cwd
+    };
+
+    for comp in components {
+        if comp == Component::ParentDir && walk.is_same(&ip, root) {
+            // `..` of the root directory is the root directory itself.
+            continue;
+        }
+        ip = walk.lookup(&ip, comp.as_os_str())?;
+    }
+
+    Ok(ip)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use dataview::PodMethods as _;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Eq)]
+    enum Error {
+        NotFound,
+        NotDir,
+    }
+
+    fn entry(name: &str, ino: u32) -> DirEntry {
+        let mut de = DirEntry::zeroed();
+        de.set_name(OsStr::new(name));
+        de.set_ino((ino != 0).then_some(InodeNo::new(ino)));
+        de
+    }
+
+    struct Dir(Vec<DirEntry>);
+
+    impl DirContent for Dir {
+        type Error = Error;
+
+        fn size(&self) -> usize {
+            self.0.len() * DIR_ENTRY_SIZE
+        }
+
+        fn read_entry(&mut self, off: usize) -> Result<DirEntry, Self::Error> {
+            assert_eq!(off % DIR_ENTRY_SIZE, 0);
+            let de = &self.0[off / DIR_ENTRY_SIZE];
+            let mut copy = DirEntry::zeroed();
+            copy.set_name(de.name());
+            copy.set_ino(de.ino());
+            Ok(copy)
+        }
+    }
+
+    /// A file system with directories only; other inodes are files.
+    struct Fs(HashMap<u32, Vec<(&'static str, u32)>>);
+
+    impl Walk for Fs {
+        type Error = Error;
+        type Inode = u32;
+
+        fn is_same(&self, a: &u32, b: &u32) -> bool {
+            a == b
+        }
+
+        fn lookup(&mut self, dir: &u32, name: &OsStr) -> Result<u32, Error> {
+            let entries = self.0.get(dir).ok_or(Error::NotDir)?;
+            let mut dir = Dir(entries.iter().map(|&(n, ino)| entry(n, ino)).collect());
+            let (ino, _off) = super::lookup(&mut dir, name)?.ok_or(Error::NotFound)?;
+            Ok(ino.value())
+        }
+    }
+
+    fn sample_fs() -> Fs {
+        Fs(HashMap::from([
+            (1, vec![(".", 1), ("..", 1), ("bin", 2), ("home", 3)]),
+            (2, vec![(".", 2), ("..", 1), ("sh", 4)]),
+            (3, vec![(".", 3), ("..", 1), ("user", 5)]),
+            (5, vec![(".", 5), ("..", 3), ("file", 6)]),
+        ]))
+    }
+
+    fn resolve(path: &str, cwd: u32) -> Result<u32, Error> {
+        super::resolve(&mut sample_fs(), &1, cwd, Path::new(path))
+    }
+
+    #[test]
+    fn test_entries() {
+        let mut dir = Dir(vec![entry(".", 1), entry("", 0), entry("a", 2)]);
+        let found = entries(&mut dir)
+            .map(|res| res.map(|(off, de)| (off, de.ino().map(|ino| ino.value()))))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            found,
+            [
+                (0, Some(1)),
+                (DIR_ENTRY_SIZE, None),
+                (DIR_ENTRY_SIZE * 2, Some(2))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_next_entry() {
+        let mut dir = Dir(vec![entry(".", 1), entry("", 0), entry("a", 2)]);
+        let next = |dir: &mut Dir, off| {
+            next_entry(dir, off)
+                .unwrap()
+                .map(|(off, de)| (off, de.ino().unwrap().value()))
+        };
+        assert_eq!(next(&mut dir, 0), Some((0, 1)));
+        assert_eq!(next(&mut dir, 1), Some((DIR_ENTRY_SIZE * 2, 2)));
+        assert_eq!(
+            next(&mut dir, DIR_ENTRY_SIZE),
+            Some((DIR_ENTRY_SIZE * 2, 2))
+        );
+        assert_eq!(next(&mut dir, DIR_ENTRY_SIZE * 3), None);
+    }
+
+    #[test]
+    fn test_lookup() {
+        let mut dir = Dir(vec![
+            entry(".", 1),
+            entry("..", 1),
+            entry("", 0),
+            entry("abcdefghijklmn", 3),
+            entry("file", 2),
+        ]);
+        assert_eq!(
+            lookup(&mut dir, OsStr::new("file")),
+            Ok(Some((InodeNo::new(2), DIR_ENTRY_SIZE * 4)))
+        );
+        assert_eq!(lookup(&mut dir, OsStr::new("fil")), Ok(None));
+        // names are truncated to the size of the entry
+        assert_eq!(
+            lookup(&mut dir, OsStr::new("abcdefghijklmnopq")),
+            Ok(Some((InodeNo::new(3), DIR_ENTRY_SIZE * 3)))
+        );
+        // free entries never match
+        assert_eq!(lookup(&mut dir, OsStr::new("")), Ok(None));
+    }
+
+    #[test]
+    fn test_lookup_ino() {
+        let mut dir = Dir(vec![entry(".", 1), entry("..", 2), entry("a", 2)]);
+        let de = lookup_ino(&mut dir, InodeNo::new(2)).unwrap().unwrap();
+        assert_eq!(de.name(), "a");
+        assert!(lookup_ino(&mut dir, InodeNo::new(1)).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_is_empty() {
+        let mut dir = Dir(vec![entry(".", 1), entry("..", 1), entry("", 0)]);
+        assert_eq!(is_empty(&mut dir), Ok(true));
+        let mut dir = Dir(vec![entry("a", 2), entry(".", 1), entry("..", 1)]);
+        assert_eq!(is_empty(&mut dir), Ok(false));
+    }
+
+    #[test]
+    fn test_link_slot() {
+        let mut dir = Dir(vec![entry(".", 1), entry("", 0), entry("a", 2)]);
+        assert_eq!(
+            link_slot(&mut dir, OsStr::new("b")),
+            Ok(Some(DIR_ENTRY_SIZE))
+        );
+        assert_eq!(link_slot(&mut dir, OsStr::new("a")), Ok(None));
+        let mut dir = Dir(vec![entry(".", 1), entry("a", 2)]);
+        assert_eq!(
+            link_slot(&mut dir, OsStr::new("b")),
+            Ok(Some(DIR_ENTRY_SIZE * 2))
+        );
+    }
+
+    #[test]
+    fn test_resolve() {
+        assert_eq!(resolve("/", 3), Ok(1));
+        assert_eq!(resolve("/bin/sh", 3), Ok(4));
+        assert_eq!(resolve("user/file", 3), Ok(6));
+        assert_eq!(resolve("", 3), Ok(3));
+        assert_eq!(resolve(".", 3), Ok(3));
+        assert_eq!(resolve("../bin/./sh", 3), Ok(4));
+        assert_eq!(resolve("//home//user/", 2), Ok(5));
+    }
+
+    #[test]
+    fn test_resolve_root_parent() {
+        // `..` stops at the root directory
+        assert_eq!(resolve("/../..", 3), Ok(1));
+        assert_eq!(resolve("../../../bin", 3), Ok(2));
+        let mut fs = sample_fs();
+        // with another root, `..` stops there even if the directory has a
+        // parent
+        assert_eq!(
+            super::resolve(&mut fs, &3, 5, Path::new("../../user/file")),
+            Ok(6)
+        );
+        assert_eq!(super::resolve(&mut fs, &3, 5, Path::new("/..")), Ok(3));
+    }
+
+    #[test]
+    fn test_resolve_error() {
+        assert_eq!(resolve("/nothing", 1), Err(Error::NotFound));
+        assert_eq!(resolve("/bin/sh/x", 1), Err(Error::NotDir));
+        assert_eq!(resolve("/bin/sh/..", 1), Err(Error::NotDir));
+    }
+}
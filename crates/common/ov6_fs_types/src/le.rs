@@ -0,0 +1,67 @@
+//! Little-endian integers.
+
+use core::fmt;
+
+use dataview::Pod;
+
+/// Integer stored in little-endian byte order.
+///
+/// The integer fields of the on-disk structures have this type, so that a file
+/// system image has the same layout regardless of the byte order of the
+/// machine that reads or writes it.
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+#[repr(transparent)]
+pub struct Le<T>(T);
+
+unsafe impl<T> Pod for Le<T> where T: Pod {}
+
+macro_rules! impl_le {
+    ($($ty:ty),*) => {
+        $(
+            impl Le<$ty> {
+                /// Creates a new `Le` holding `value`.
+                #[must_use]
+                pub const fn new(value: $ty) -> Self {
+                    Self(value.to_le())
+                }
+
+                /// Returns the value in native byte order.
+                #[must_use]
+                pub const fn get(self) -> $ty {
+                    <$ty>::from_le(self.0)
+                }
+
+                /// Sets the value.
+                pub const fn set(&mut self, value: $ty) {
+                    self.0 = value.to_le();
+                }
+            }
+
+            impl From<$ty> for Le<$ty> {
+                fn from(value: $ty) -> Self {
+                    Self::new(value)
+                }
+            }
+
+            impl From<Le<$ty>> for $ty {
+                fn from(value: Le<$ty>) -> Self {
+                    value.get()
+                }
+            }
+
+            impl fmt::Debug for Le<$ty> {
+                fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    fmt::Debug::fmt(&self.get(), f)
+                }
+            }
+
+            impl fmt::Display for Le<$ty> {
+                fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    fmt::Display::fmt(&self.get(), f)
+                }
+            }
+        )*
+    };
+}
+
+impl_le!(i16, u16, u32, u64);
@@ -8,6 +8,7 @@
 //! |  1                             | 1                  | Super Block | [`SuperBlock`]                                |
 //! | `sb.logstart`                  | `1 + sb.nlog`      | Log         | [`LogHeader`] & `[u8; BLOCK_SIZE]` (log body) |
 //! | `sb.inodestart`                | `sb.ninodes / IPB` | inode table | [`InodeBlock`]                                |
+//! | `sb.ibmapstart`                | `sb.ninodes / BPB` | inode map   | [`BmapBlock`]                                 |
 //! | `sb.bmapstart`                 | `sb.size / BPB`    | bitmap      | [`BmapBlock`]                                 |
 //! | `sb.bmapstart + sb.size / BPB` | `sb.nblocks`       | data blocks | [`[u8; BLOCK_SIZE]`] (data)                   |
 
@@ -17,7 +18,12 @@ use core::{fmt, mem};
 
 use dataview::{Pod, PodMethods as _};
 use ov6_types::os_str::OsStr;
-use safe_cast::{SafeInto as _, to_u32};
+use safe_cast::{NarrowInto as _, SafeFrom as _, SafeInto as _, TryNarrow as _, to_u32};
+
+pub use self::le::Le;
+
+mod le;
+pub mod xv6;
 
 /// Block size in bytes.
 pub const FS_BLOCK_SIZE: usize = 1024;
@@ -101,26 +107,64 @@ impl InodeNo {
 #[repr(C)]
 pub struct SuperBlock {
     /// Magic number. Must be [`Self::FS_MAGIC`].
-    pub magic: u32,
+    pub magic: Le<u32>,
     /// Size of the file system image in blocks.
-    pub size: u32,
+    pub size: Le<u32>,
     /// Number of data blocks.
-    pub nblocks: u32,
+    pub nblocks: Le<u32>,
     /// Number of inodes.
-    pub ninodes: u32,
+    pub ninodes: Le<u32>,
     /// Number of log blocks.
-    pub nlog: u32,
+    pub nlog: Le<u32>,
     /// Block number of the first log block.
-    pub logstart: u32,
+    pub logstart: Le<u32>,
     /// Block number of the first inode block.
-    pub inodestart: u32,
+    pub inodestart: Le<u32>,
     /// Block number of the first free map block.
-    pub bmapstart: u32,
+    pub bmapstart: Le<u32>,
+    /// Mount state. [`Self::STATE_CLEAN`] or [`Self::STATE_DIRTY`].
+    pub state: Le<u32>,
+    /// Number of times the file system has been mounted.
+    pub mount_count: Le<u32>,
+    /// Time of the last mount in seconds since the Unix epoch.
+    pub last_mount_time: Le<u64>,
+    /// UUID of the file system.
+    pub uuid: FsUuid,
+    /// Human-readable label, padded with NULs.
+    pub label: [u8; FS_LABEL_MAX],
+    /// Version of the on-disk format.
+    ///
+    /// Images made before the version was introduced have 0, which is the
+    /// same format as version 1 without any features.
+    pub version: Le<u32>,
+    /// Optional features used by the file system.
+    pub features: FsFeatures,
+    /// Block number of the first inode map block.
+    ///
+    /// Valid only if [`FsFeatures::RO_COMPAT_INODE_BITMAP`] is set.
+    pub ibmapstart: Le<u32>,
+    /// Reserved. Must be zero.
+    pub reserved: Le<u32>,
+    /// Inodes that are unlinked but still open, or zero for free slots.
+    ///
+    /// The inodes left here by a crash are freed on the next mount. Valid
+    /// only if [`FsFeatures::RO_COMPAT_ORPHAN_LIST`] is set.
+    pub orphans: [Le<u32>; MAX_ORPHANS],
 }
+const _: () = const { assert!(size_of::<SuperBlock>() <= FS_BLOCK_SIZE) };
+
+/// Maximum number of inodes in the orphan list of the super block.
+pub const MAX_ORPHANS: usize = 64;
 
 impl SuperBlock {
     /// Magic number for the file system.
     pub const FS_MAGIC: u32 = 0x1020_3040;
+    /// Latest version of the on-disk format.
+    pub const FS_VERSION: u32 = 1;
+    /// The file system is not mounted, or was unmounted cleanly.
+    pub const STATE_CLEAN: u32 = 0;
+    /// The file system is mounted, or was not unmounted cleanly.
+    pub const STATE_DIRTY: u32 = 1;
     /// Block number of the super block.
     pub const SUPER_BLOCK_NO: BlockNo = BlockNo::new(1);
 
@@ -128,32 +172,227 @@ impl SuperBlock {
     #[must_use]
     pub fn inode_block(&self, inode_no: InodeNo) -> BlockNo {
         let block_index = inode_no.0 / to_u32!(INODE_PER_BLOCK);
-        BlockNo::new(self.inodestart + block_index)
+        BlockNo::new(self.inodestart.get() + block_index)
     }
 
     /// Returns the block number containing the specified bitmap.
     #[must_use]
     pub fn bmap_block(&self, bn: u32) -> BlockNo {
         let block_index = bn / to_u32!(BITS_PER_BLOCK);
-        BlockNo::new(self.bmapstart + block_index)
+        BlockNo::new(self.bmapstart.get() + block_index)
+    }
+
+    /// Returns the block number of the inode map containing the specified
+    /// inode.
+    #[must_use]
+    pub fn ibmap_block(&self, inode_no: InodeNo) -> BlockNo {
+        let block_index = inode_no.0 / to_u32!(BITS_PER_BLOCK);
+        BlockNo::new(self.ibmapstart.get() + block_index)
+    }
+
+    /// Returns `true` if allocated inodes are recorded in the inode map.
+    ///
+    /// Otherwise, an inode is allocated if its type is not zero.
+    #[must_use]
+    pub fn has_inode_bitmap(&self) -> bool {
+        self.features.ro_compat.get() & FsFeatures::RO_COMPAT_INODE_BITMAP != 0
+    }
+
+    /// Returns `true` if unlinked inodes that are still open are recorded in
+    /// [`Self::orphans`].
+    #[must_use]
+    pub fn has_orphan_list(&self) -> bool {
+        self.features.ro_compat.get() & FsFeatures::RO_COMPAT_ORPHAN_LIST != 0
+    }
+
+    /// Returns `true` if the inode `ino` is in the orphan list.
+    #[must_use]
+    pub fn is_orphan(&self, ino: InodeNo) -> bool {
+        self.has_orphan_list() && self.orphans.iter().any(|o| o.get() == ino.0)
     }
 
-    /// Returns the maximum length of the log in blocks.
+    /// Returns the maximum number of blocks a log transaction can hold.
+    ///
+    /// The log area consists of the header block followed by the log body,
+    /// so this is one less than `nlog`.
     #[must_use]
     pub fn max_log_len(&self) -> usize {
-        self.nlog.safe_into()
+        usize::safe_from(self.nlog.get()).saturating_sub(1)
     }
 
     /// Returns the block number of the log header.
     #[must_use]
     pub fn log_header_block(&self) -> BlockNo {
-        BlockNo::new(self.logstart)
+        BlockNo::new(self.logstart.get())
     }
 
     /// Returns the block number of the log body at the given index.
+    ///
+    /// The log body starts just after the log header.
     #[must_use]
     pub fn log_body_block(&self, i: u32) -> BlockNo {
-        BlockNo::new(self.logstart + i)
+        BlockNo::new(self.logstart.get() + 1 + i)
+    }
+
+    /// Returns the label of the file system.
+    #[must_use]
+    pub fn label(&self) -> &OsStr {
+        decode_label(&self.label)
+    }
+
+    /// Returns the block number of the first data block.
+    #[must_use]
+    pub fn data_start(&self) -> u32 {
+        self.size.get() - self.nblocks.get()
+    }
+
+    /// Returns `true` if the file system was made by the original xv6.
+    ///
+    /// xv6 writes only the fields up to [`Self::bmapstart`] and zeroes the
+    /// rest of the block, while ov6 always sets [`Self::version`] and the UUID
+    /// since they were introduced.
+    #[must_use]
+    pub fn is_xv6(&self) -> bool {
+        self.magic.get() == Self::FS_MAGIC
+            && self.as_bytes()[xv6::SUPER_BLOCK_SIZE..]
+                .iter()
+                .all(|&b| b == 0)
+    }
+
+    /// Returns how this implementation can use the file system.
+    ///
+    /// xv6 file systems are only read, as ov6 would write inodes and directory
+    /// entries that xv6 does not understand.
+    #[must_use]
+    pub fn compatibility(&self) -> Compatibility {
+        if self.is_xv6() {
+            return Compatibility::ReadOnly;
+        }
+        if self.version.get() > Self::FS_VERSION
+            || self.features.incompat.get() & !FsFeatures::SUPPORTED_INCOMPAT != 0
+        {
+            return Compatibility::Incompatible;
+        }
+        if self.features.ro_compat.get() & !FsFeatures::SUPPORTED_RO_COMPAT != 0 {
+            return Compatibility::ReadOnly;
+        }
+        Compatibility::ReadWrite
+    }
+}
+
+/// Feature flags of a file system.
+///
+/// A feature changing the on-disk format sets a bit in one of the masks,
+/// depending on what an implementation that does not know it can still do
+/// with the file system.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Pod)]
+#[repr(C)]
+pub struct FsFeatures {
+    /// Features that can be ignored by implementations not knowing them.
+    pub compat: Le<u32>,
+    /// Features that must be known to write the file system, but not to read
+    /// it.
+    pub ro_compat: Le<u32>,
+    /// Features that must be known to read the file system.
+    pub incompat: Le<u32>,
+}
+
+impl FsFeatures {
+    /// Allocated inodes are recorded in the inode map.
+    pub const RO_COMPAT_INODE_BITMAP: u32 = 1 << 0;
+    /// Unlinked inodes that are still open are recorded in the super block.
+    pub const RO_COMPAT_ORPHAN_LIST: u32 = 1 << 1;
+    /// Compatible features supported by this implementation.
+    pub const SUPPORTED_COMPAT: u32 = 0;
+    /// Incompatible features supported by this implementation.
+    pub const SUPPORTED_INCOMPAT: u32 = 0;
+    /// Read-only compatible features supported by this implementation.
+    pub const SUPPORTED_RO_COMPAT: u32 = Self::RO_COMPAT_INODE_BITMAP | Self::RO_COMPAT_ORPHAN_LIST;
+}
+
+/// How a file system can be used by an implementation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compatibility {
+    /// All the features are supported.
+    ReadWrite,
+    /// Some features needed to write the file system are not supported.
+    ReadOnly,
+    /// The version is too new, or some features needed to read the file
+    /// system are not supported.
+    Incompatible,
+}
+
+/// Maximum length of a file system label in bytes.
+pub const FS_LABEL_MAX: usize = 16;
+
+/// Encodes `label` into a NUL-padded label field.
+///
+/// Returns `None` if `label` is longer than [`FS_LABEL_MAX`] or contains NUL.
+#[must_use]
+pub fn encode_label(label: &[u8]) -> Option<[u8; FS_LABEL_MAX]> {
+    if label.len() > FS_LABEL_MAX || label.contains(&0) {
+        return None;
+    }
+    let mut field = [0; FS_LABEL_MAX];
+    field[..label.len()].copy_from_slice(label);
+    Some(field)
+}
+
+/// Decodes a NUL-padded label field.
+#[must_use]
+pub fn decode_label(field: &[u8; FS_LABEL_MAX]) -> &OsStr {
+    let len = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    OsStr::from_bytes(&field[..len])
+}
+
+/// UUID identifying a file system.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Pod)]
+#[repr(transparent)]
+pub struct FsUuid([u8; 16]);
+
+impl fmt::Display for FsUuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, b) in self.0.iter().enumerate() {
+            if matches!(i, 4 | 6 | 8 | 10) {
+                write!(f, "-")?;
+            }
+            write!(f, "{b:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FsUuid {
+    /// The nil UUID, used by file systems created without a UUID.
+    pub const NIL: Self = Self([0; 16]);
+
+    /// Creates a new `FsUuid` from its bytes.
+    #[must_use]
+    pub const fn from_bytes(bytes: [u8; 16]) -> Self {
+        Self(bytes)
+    }
+
+    /// Returns the bytes of the UUID.
+    #[must_use]
+    pub const fn as_bytes(&self) -> &[u8; 16] {
+        &self.0
+    }
+
+    /// Parses the hyphenated form `xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx`.
+    #[must_use]
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.as_bytes();
+        if s.len() != 36 || [8, 13, 18, 23].iter().any(|&i| s[i] != b'-') {
+            return None;
+        }
+        let mut digits = s.iter().filter(|&&c| c != b'-');
+        let mut bytes = [0; 16];
+        for b in &mut bytes {
+            let hi = char::from(*digits.next()?).to_digit(16)?;
+            let lo = char::from(*digits.next()?).to_digit(16)?;
+            *b = u8::try_narrow((hi << 4) | lo).ok()?;
+        }
+        Some(Self(bytes))
     }
 }
 
@@ -164,8 +403,8 @@ const MAX_LOG_COUNT: usize = FS_BLOCK_SIZE / size_of::<u32>() - 1;
 #[derive(Pod)]
 #[repr(C)]
 pub struct LogHeader {
-    len: u32,
-    block_indices: [u32; MAX_LOG_COUNT],
+    len: Le<u32>,
+    block_indices: [Le<u32>; MAX_LOG_COUNT],
 }
 const _: () = const { assert!(size_of::<LogHeader>() == FS_BLOCK_SIZE) };
 
@@ -173,7 +412,7 @@ impl LogHeader {
     /// Returns the length of log entries.
     #[must_use]
     pub fn len(&self) -> usize {
-        self.len.safe_into()
+        self.len.get().safe_into()
     }
 
     /// Returns `true` if log entry is empty.
@@ -188,16 +427,16 @@ impl LogHeader {
     ///
     /// Panics if the `len` is greater than `u32::MAX`.
     pub fn set_len(&mut self, len: usize) {
-        self.len = u32::try_from(len).unwrap();
+        self.len.set(len.narrow_into());
     }
 
     #[must_use]
-    pub fn block_indices(&self) -> &[u32] {
+    pub fn block_indices(&self) -> &[Le<u32>] {
         &self.block_indices[..self.len()]
     }
 
     #[must_use]
-    pub fn block_indices_mut(&mut self) -> &mut [u32] {
+    pub fn block_indices_mut(&mut self) -> &mut [Le<u32>] {
         let len = self.len();
         &mut self.block_indices[..len]
     }
@@ -209,29 +448,31 @@ pub const T_DIR: u16 = 1;
 pub const T_FILE: u16 = 2;
 /// Device
 pub const T_DEVICE: u16 = 3;
+/// Local socket
+pub const T_SOCK: u16 = 4;
 
 #[derive(Pod)]
 #[repr(C)]
 pub struct Inode {
     /// File type
-    pub ty: u16,
+    pub ty: Le<u16>,
     /// Major device number ([`T_DEVICE`] only)
-    pub major: u16,
+    pub major: Le<u16>,
     /// Minor device number ([`T_DEVICE`] only)
-    pub minor: u16,
+    pub minor: Le<u16>,
     /// Number of links to inode in file system
-    pub nlink: u16,
+    pub nlink: Le<u16>,
     /// Size of file (bytes)
-    pub size: u32,
+    pub size: Le<u32>,
     /// Data block addresses
-    pub addrs: [u32; NUM_DIRECT_REFS + 1],
+    pub addrs: [Le<u32>; NUM_DIRECT_REFS + 1],
 }
 
 impl Inode {
     /// Returns `true` if inode is free.
     #[must_use]
     pub fn is_free(&self) -> bool {
-        self.ty == 0
+        self.ty.get() == 0
     }
 
     /// Allocates this `Inode` with the specified type.
@@ -240,9 +481,9 @@ impl Inode {
     ///
     /// Panics if `ty` is invalid.
     pub fn allocate(&mut self, ty: u16) {
-        assert_eq!(self.ty, 0);
+        assert!(self.is_free());
         *self = Self::zeroed();
-        self.ty = ty;
+        self.ty.set(ty);
     }
 
     /// Writes inode content addresses.
@@ -254,9 +495,9 @@ impl Inode {
         for (dst, src) in self.addrs.iter_mut().zip(addrs) {
             if let Some(bn) = src {
                 assert_ne!(bn.0, 0);
-                *dst = bn.0;
+                dst.set(bn.0);
             } else {
-                *dst = 0;
+                dst.set(0);
             }
         }
     }
@@ -264,10 +505,10 @@ impl Inode {
     /// Reads inode content addresses.
     pub fn read_addrs(&self, addrs: &mut [Option<BlockNo>; NUM_DIRECT_REFS + 1]) {
         for (dst, src) in addrs.iter_mut().zip(&self.addrs) {
-            if *src == 0 {
+            if src.get() == 0 {
                 *dst = None;
             } else {
-                *dst = Some(BlockNo(*src));
+                *dst = Some(BlockNo(src.get()));
             }
         }
     }
@@ -284,12 +525,12 @@ const _: () = const { assert!(size_of::<InodeBlock>() == FS_BLOCK_SIZE) };
 impl InodeBlock {
     #[must_use]
     pub fn inode(&self, ino: InodeNo) -> &Inode {
-        &self.0[ino.value() as usize % INODE_PER_BLOCK]
+        &self.0[usize::safe_from(ino.value()) % INODE_PER_BLOCK]
     }
 
     #[must_use]
     pub fn inode_mut(&mut self, ino: InodeNo) -> &mut Inode {
-        &mut self.0[ino.value() as usize % INODE_PER_BLOCK]
+        &mut self.0[usize::safe_from(ino.value()) % INODE_PER_BLOCK]
     }
 }
 
@@ -337,16 +578,17 @@ impl BmapBlock {
 
 #[derive(Pod)]
 #[repr(transparent)]
-pub struct IndirectBlock([u32; NUM_INDIRECT_REFS]);
+pub struct IndirectBlock([Le<u32>; NUM_INDIRECT_REFS]);
 
 impl IndirectBlock {
     /// Retrieves the block number of the `i`th indirect block.
     #[must_use]
     pub fn get(&self, i: usize) -> Option<BlockNo> {
-        if self.0[i] == 0 {
+        let bn = self.0[i].get();
+        if bn == 0 {
             None
         } else {
-            Some(BlockNo::new(self.0[i]))
+            Some(BlockNo::new(bn))
         }
     }
 
@@ -356,17 +598,17 @@ impl IndirectBlock {
     ///
     /// Panics if block number of `bn` is zero.
     pub fn set(&mut self, i: usize, bn: Option<BlockNo>) {
-        self.0[i] = bn.map_or(0, |bn| {
+        self.0[i].set(bn.map_or(0, |bn| {
             assert_ne!(bn.value(), 0);
             bn.value()
-        });
+        }));
     }
 
     /// Cleas the all indirect block reference, returning all block numbers as
     /// an iterator.
     pub fn drain(&mut self) -> impl Iterator<Item = Option<BlockNo>> + '_ {
         self.0.iter_mut().map(|bn| {
-            let bn = mem::take(bn);
+            let bn = mem::take(bn).get();
             if bn == 0 {
                 None
             } else {
@@ -382,7 +624,7 @@ pub const DIR_SIZE: usize = 14;
 #[repr(C)]
 #[derive(Debug, Pod)]
 pub struct DirEntry {
-    ino: u16,
+    ino: Le<u16>,
     name: [u8; DIR_SIZE],
 }
 
@@ -390,10 +632,11 @@ impl DirEntry {
     /// Returns the inode number of the directory entry.
     #[must_use]
     pub fn ino(&self) -> Option<InodeNo> {
-        if self.ino == 0 {
+        let ino = self.ino.get();
+        if ino == 0 {
             None
         } else {
-            Some(InodeNo::new(self.ino.into()))
+            Some(InodeNo::new(ino.into()))
         }
     }
 
@@ -406,9 +649,9 @@ impl DirEntry {
     pub fn set_ino(&mut self, ino: Option<InodeNo>) {
         if let Some(ino) = ino {
             assert_ne!(ino.0, 0);
-            self.ino = ino.0.try_into().unwrap();
+            self.ino.set(ino.0.narrow_into());
         } else {
-            self.ino = 0;
+            self.ino.set(0);
         }
     }
 
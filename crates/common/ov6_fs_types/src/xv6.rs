@@ -0,0 +1,136 @@
+//! Data types of the original xv6 file system.
+//!
+//! ov6 file systems started as xv6 ones, and the xv6 layout is still a subset
+//! of the ov6 one: the blocks are placed the same way, and the inodes and
+//! directory entries have the same sizes and offsets. What differs is that
+//! xv6 declares the inode fields as signed integers, and that the xv6 super
+//! block ends after [`SuperBlock::bmapstart`], leaving the fields ov6 added
+//! zeroed.
+//!
+//! The types here describe the structures as xv6 does, and convert them into
+//! the ov6 ones, rejecting values that xv6 would not write.
+
+use dataview::{Pod, PodMethods as _};
+
+use crate::{FS_BLOCK_SIZE, InodeNo, Le, NUM_DIRECT_REFS, T_DEVICE};
+
+/// Size of the super block written by xv6 in bytes.
+///
+/// The rest of the super block is zeroed.
+pub const SUPER_BLOCK_SIZE: usize = size_of::<SuperBlock>();
+
+/// Super block of an xv6 file system.
+#[derive(Pod)]
+#[repr(C)]
+pub struct SuperBlock {
+    /// Magic number. Must be [`crate::SuperBlock::FS_MAGIC`].
+    pub magic: Le<u32>,
+    /// Size of the file system image in blocks.
+    pub size: Le<u32>,
+    /// Number of data blocks.
+    pub nblocks: Le<u32>,
+    /// Number of inodes.
+    pub ninodes: Le<u32>,
+    /// Number of log blocks.
+    pub nlog: Le<u32>,
+    /// Block number of the first log block.
+    pub logstart: Le<u32>,
+    /// Block number of the first inode block.
+    pub inodestart: Le<u32>,
+    /// Block number of the first free map block.
+    pub bmapstart: Le<u32>,
+}
+
+impl SuperBlock {
+    /// Converts the super block into an ov6 one.
+    ///
+    /// The fields xv6 does not have are zeroed, as xv6 leaves them on disk.
+    #[must_use]
+    pub fn to_ov6(&self) -> crate::SuperBlock {
+        let mut sb = crate::SuperBlock::zeroed();
+        sb.as_bytes_mut()[..SUPER_BLOCK_SIZE].copy_from_slice(self.as_bytes());
+        sb
+    }
+}
+
+/// On-disk inode of an xv6 file system.
+#[derive(Pod)]
+#[repr(C)]
+pub struct Inode {
+    /// File type
+    pub ty: Le<i16>,
+    /// Major device number ([`T_DEVICE`] only)
+    pub major: Le<i16>,
+    /// Minor device number ([`T_DEVICE`] only)
+    pub minor: Le<i16>,
+    /// Number of links to inode in file system
+    pub nlink: Le<i16>,
+    /// Size of file (bytes)
+    pub size: Le<u32>,
+    /// Data block addresses
+    pub addrs: [Le<u32>; NUM_DIRECT_REFS + 1],
+}
+const _: () = const { assert!(size_of::<Inode>() == size_of::<crate::Inode>()) };
+
+impl Inode {
+    /// Converts the inode into an ov6 one.
+    ///
+    /// Returns `None` if the type is not one xv6 knows, or if any of the
+    /// other fields is negative.
+    #[must_use]
+    pub fn to_ov6(&self) -> Option<crate::Inode> {
+        let field = |v: Le<i16>| u16::try_from(v.get()).ok().map(Le::<u16>::new);
+        let ty = field(self.ty)?;
+        if ty.get() > T_DEVICE {
+            return None;
+        }
+        Some(crate::Inode {
+            ty,
+            major: field(self.major)?,
+            minor: field(self.minor)?,
+            nlink: field(self.nlink)?,
+            size: self.size,
+            addrs: self.addrs,
+        })
+    }
+}
+
+/// Inodes per block.
+pub const INODE_PER_BLOCK: usize = FS_BLOCK_SIZE / size_of::<Inode>();
+
+#[derive(Pod)]
+#[repr(transparent)]
+pub struct InodeBlock([Inode; INODE_PER_BLOCK]);
+const _: () = const { assert!(size_of::<InodeBlock>() == FS_BLOCK_SIZE) };
+
+impl InodeBlock {
+    #[must_use]
+    pub fn inode(&self, ino: InodeNo) -> &Inode {
+        &self.0[ino.as_index() % INODE_PER_BLOCK]
+    }
+}
+
+/// Maximum length of a name in a directory entry.
+pub const DIR_SIZE: usize = 14;
+
+/// Directory entry of an xv6 file system.
+#[derive(Pod)]
+#[repr(C)]
+pub struct DirEntry {
+    /// Inode number, or zero if the entry is free.
+    pub inum: Le<u16>,
+    /// Name, padded with NULs unless it is [`DIR_SIZE`] bytes long.
+    pub name: [u8; DIR_SIZE],
+}
+const _: () = const { assert!(size_of::<DirEntry>() == size_of::<crate::DirEntry>()) };
+
+impl DirEntry {
+    /// Converts the directory entry into an ov6 one.
+    #[must_use]
+    pub fn to_ov6(&self) -> crate::DirEntry {
+        crate::DirEntry {
+            ino: self.inum,
+            name: self.name,
+        }
+    }
+}
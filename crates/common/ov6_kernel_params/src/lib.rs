@@ -9,13 +9,34 @@ pub const NCPU: usize = 8;
 /// Maximum major device number
 pub const NDEV: usize = 10;
 
-/// Open files per process.
-pub const NOFILE: usize = 16;
+/// Maximum number of open files per process.
+pub const NOFILE: usize = 1024;
 
-/// Open files per system.
-pub const NFILE: usize = 100;
+/// Number of pages in the buffer of a pipe.
+pub const PIPE_PAGES: usize = 4;
 
-/// Maximum number of active i-nodes
+/// Maximum number of local sockets bound to paths.
+pub const NLOCAL_SOCKET: usize = 8;
+
+/// Maximum number of pending connections of a local socket.
+pub const MAX_LISTEN_BACKLOG: usize = 8;
+
+/// Maximum number of message queues.
+pub const NMSG_QUEUE: usize = 8;
+
+/// Maximum number of messages in a message queue.
+pub const MSG_QUEUE_DEPTH: usize = 16;
+
+/// Maximum number of semaphores.
+pub const NSEMAPHORE: usize = 16;
+
+/// Maximum number of file system types.
+pub const NFSTYPE: usize = 4;
+
+/// Maximum number of mounted file systems.
+pub const NMOUNT: usize = 4;
+
+/// Number of in-memory i-nodes above which unreferenced ones are evicted
 pub const NINODE: usize = 50;
 
 /// Max # of blocks any FS op writes.
@@ -27,12 +48,38 @@ pub const LOG_SIZE: usize = MAX_OP_BLOCKS * 3;
 /// Size of disk block cache.
 pub const NBUF: usize = MAX_OP_BLOCKS * 3;
 
+/// Maximum number of pages in the file page cache.
+pub const NPAGE_CACHE: usize = 128;
+
+/// Number of pages read ahead by sequential reads.
+pub const READAHEAD_PAGES: usize = 8;
+
+/// Number of entries in the name lookup cache.
+pub const NNAME_CACHE: usize = 64;
+
+/// Maximum number of program segments in the exec text cache.
+pub const NTEXT_CACHE: usize = 16;
+
+/// Maximum number of pages held by the exec text cache.
+pub const TEXT_CACHE_PAGES: usize = 512;
+
+/// Size limit of a tmpfs in pages, unless given when it is mounted.
+pub const TMPFS_DEFAULT_PAGES: usize = 256;
+
 /// Maximum file path name.
 pub const MAX_PATH: usize = 128;
 
+/// Number of audit records kept by the kernel.
+pub const NAUDIT: usize = 32;
+
 /// User stack pages
 pub const USER_STACK_PAGES: usize = 2;
 
+/// Mounts the root file system read-only.
+///
+/// It can be remounted writable at run time.
+pub const ROOT_READ_ONLY: bool = false;
+
 /// Size of file system image in blocks
 pub const FS_SIZE: usize = 2000;
 
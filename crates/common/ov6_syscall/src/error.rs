@@ -12,9 +12,9 @@ pub enum SyscallError {
     // ESRCH
     #[error("no such process")]
     ProcessNotFound = 3,
-    // // EINTR
-    // #[error("interrupted system call")]
-    // Interrupted = 4,
+    // EINTR
+    #[error("interrupted system call")]
+    Interrupted = 4,
     // EIO
     #[error("input/output error")]
     Io = 5,
@@ -75,9 +75,9 @@ pub enum SyscallError {
     // EMFILE
     #[error("too many open files")]
     TooManyOpenFiles = 24,
-    // // ENOTTY
-    // #[error("inappropriate I/O control operation")]
-    // NoTty = 25,
+    // ENOTTY
+    #[error("inappropriate I/O control operation")]
+    NoTty = 25,
     // ETXTBSY
     #[error("text file busy")]
     ExecutableFileBusy = 26,
@@ -120,10 +120,19 @@ pub enum SyscallError {
     // ENOTEMPTY
     #[error("directory not empty")]
     DirectoryNotEmpty = 39,
+    // EIDRM
+    #[error("identifier removed")]
+    IdentifierRemoved = 43,
+    // ENOTSOCK
+    #[error("socket operation on non-socket")]
+    NotASocket = 88,
     #[error("message too long")]
     MessageTooLong = 90,
     #[error("address already in use")]
     AddrInUse = 98,
+    // ECONNREFUSED
+    #[error("connection refused")]
+    ConnectionRefused = 111,
     #[error("unknown error")]
     Unknown = -1,
 }
@@ -12,8 +12,9 @@ use core::{
 
 use bitflags::bitflags;
 use dataview::Pod;
-use ov6_types::process::ProcId;
-use strum::{Display, EnumString, FromRepr};
+use ov6_types::{fs::RawFd, process::ProcId};
+use safe_cast::SafeInto as _;
+use strum::{Display, EnumCount, EnumString, FromRepr};
 
 pub mod error;
 mod register;
@@ -25,6 +26,39 @@ pub const USYSCALL_ADDR: usize = 0x2F_FFFF_F000;
 #[repr(C)]
 pub struct USyscallData {
     pub pid: ProcId,
+    _reserved: u32,
+    pub layout: UserLayout,
+}
+
+impl USyscallData {
+    #[must_use]
+    pub const fn new(pid: ProcId, layout: UserLayout) -> Self {
+        Self {
+            pid,
+            _reserved: 0,
+            layout,
+        }
+    }
+}
+
+/// Layout of the user address space.
+///
+/// Regions are given as start addresses. Addresses at or above `trapframe`
+/// are reserved by the kernel, and a system call given a buffer that reaches
+/// them fails with `BadAddress`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Pod)]
+#[repr(C)]
+pub struct UserLayout {
+    /// Start of the heap, right after the program segments.
+    pub heap_start: usize,
+    pub stack_bottom: usize,
+    pub stack_top: usize,
+    /// Page holding [`USyscallData`].
+    pub usyscall: usize,
+    pub trapframe: usize,
+    pub trampoline: usize,
+    /// End of the user address space.
+    pub max_va: usize,
 }
 
 bitflags! {
@@ -36,12 +70,176 @@ bitflags! {
         const READ_WRITE = 0x002;
         const CREATE = 0x200;
         const TRUNC = 0x400;
+        /// Transfers block-aligned data between user memory and the disk
+        /// without going through the caches.
+        const DIRECT = 0x4000;
+        /// Creates an unnamed file in the directory of the path, which is
+        /// freed when closed unless linked with `Flink`.
+        const TMPFILE = 0x8000;
+        /// Closes the file descriptor on `exec`.
+        ///
+        /// Also accepted by `Pipe2` and `Dup3`.
+        const CLOEXEC = 0x1_0000;
+    }
+}
+
+/// Special directory file descriptor of the `*at` system calls, which
+/// resolves relative paths from the current directory.
+pub const AT_FDCWD: RawFd = RawFd::new(usize::MAX);
+
+bitflags! {
+    /// Flags of the `*at` system calls.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[repr(transparent)]
+    pub struct AtFlags: usize {
+        /// Removes a directory instead of a file, in `Unlinkat`.
+        const REMOVE_DIR = 1 << 0;
+        /// Does not follow a symbolic link at the end of the path, in
+        /// `Faccessat`. ov6 has no symbolic links, so this has no effect
+        /// yet.
+        const SYMLINK_NOFOLLOW = 1 << 1;
+    }
+}
+
+bitflags! {
+    /// Permissions checked by the `Access` and `Faccessat` system calls.
+    ///
+    /// The empty set only checks that the file exists.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[repr(transparent)]
+    pub struct AccessMode: usize {
+        /// Execute permission of a file, or search permission of a
+        /// directory.
+        const EXECUTE = 1 << 0;
+        /// Write permission.
+        const WRITE = 1 << 1;
+        /// Read permission.
+        const READ = 1 << 2;
+    }
+}
+
+bitflags! {
+    /// Flags of a file descriptor, as opposed to the flags of the open file
+    /// shared by its duplicates.
+    ///
+    /// The flags are copied by `fork`, but not by `Dup` and `Dup2`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[repr(transparent)]
+    pub struct FdFlags: usize {
+        /// Closes the file descriptor on `exec`.
+        const CLOEXEC = 1 << 0;
+    }
+}
+
+bitflags! {
+    /// Per-process capabilities gating privileged system calls.
+    ///
+    /// Capabilities are inherited across `fork` and `exec`.
+    /// Once dropped, a capability can never be regained.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[repr(transparent)]
+    pub struct Capabilities: usize {
+        const REBOOT = 1 << 0;
+        const HALT = 1 << 1;
+        const ABORT = 1 << 2;
+        const MKNOD = 1 << 3;
+        const CHROOT = 1 << 4;
+        const SET_PRIORITY = 1 << 5;
+        const AUDIT = 1 << 6;
+        const RAW_IO = 1 << 7;
+        const SET_HOSTNAME = 1 << 8;
+        const KERNEL_SYMBOLS = 1 << 9;
+    }
+}
+
+bitflags! {
+    /// Flags of a mounted file system.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[repr(transparent)]
+    pub struct MountFlags: usize {
+        /// The file system cannot be modified.
+        const READ_ONLY = 1 << 0;
+    }
+}
+
+bitflags! {
+    /// Flags of `MsgGet`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[repr(transparent)]
+    pub struct MsgQueueFlags: usize {
+        /// Creates a queue if no queue has the key.
+        const CREATE = 1 << 0;
+        /// Fails if a queue has the key already. Used with `CREATE`.
+        const EXCLUSIVE = 1 << 1;
+        /// Sending to the full queue fails instead of waiting for a message
+        /// to be received.
+        const NONBLOCKING_SEND = 1 << 2;
     }
 }
 
+bitflags! {
+    /// Flags of `EventFd`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[repr(transparent)]
+    pub struct EventFdFlags: usize {
+        /// A read decrements the counter by 1 instead of resetting it to 0.
+        const SEMAPHORE = 1 << 0;
+        /// Reading the zero counter, or writing the counter to overflow,
+        /// fails instead of waiting.
+        const NONBLOCKING = 1 << 1;
+    }
+}
+
+/// Number of `u64` words in a system call filter bitmap.
+///
+/// System call codes are numbered from 1 without gaps, so the bitmap has a
+/// bit for every code up to the highest one.
+pub const SYSCALL_FILTER_WORDS: usize = SyscallCode::COUNT / 64 + 1;
+
+/// Bitmap of the system calls allowed by a system call filter.
+///
+/// Bit `code % 64` of word `code / 64` stands for the system call `code`.
+pub type SyscallFilterMask = [u64; SYSCALL_FILTER_WORDS];
+
+/// Action taken when a process invokes a system call rejected by its
+/// system call filter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, FromRepr)]
+#[repr(usize)]
+pub enum SyscallFilterAction {
+    /// The system call fails with [`SyscallError::NotPermitted`].
+    ///
+    /// [`SyscallError::NotPermitted`]: error::SyscallError::NotPermitted
+    Deny = 0,
+    /// The calling process is killed.
+    Kill = 1,
+}
+
+/// Action taken when a process receives an interrupt from the terminal.
+///
+/// An interrupt is sent to the foreground process group of the console when
+/// the interrupt character (Ctrl-C) is typed. A process can also catch it by
+/// registering a handler with the `SetInterruptHandler` system call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromRepr)]
+#[repr(usize)]
+pub enum InterruptAction {
+    /// The process is killed.
+    Terminate = 0,
+    /// The interrupt is discarded.
+    Ignore = 1,
+}
+
+/// Version of the layout of [`Stat`].
+///
+/// Incremented when the layout changes, so that a program built for another
+/// layout can detect it.
+pub const STAT_VERSION: u64 = 1;
+
+/// Metadata of a file, returned by `Fstat` and `Fstatat`.
 #[repr(C)]
 #[derive(Debug, Pod)]
 pub struct Stat {
+    /// Layout version, [`STAT_VERSION`]
+    pub version: u64,
     /// File system's disk device
     pub dev: u32,
     /// Inode number
@@ -50,9 +248,14 @@ pub struct Stat {
     pub ty: u16,
     /// Number of links to file
     pub nlink: u16,
-    pub padding: [u8; 4],
+    /// Number of disk blocks allocated to the file, including indirect blocks
+    pub blocks: u32,
     /// Size of file in bytes
     pub size: u64,
+    /// Major device number of a device file, or zero
+    pub major: u32,
+    /// Minor device number of a device file, or zero
+    pub minor: u32,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, FromRepr)]
@@ -61,6 +264,29 @@ pub enum StatType {
     Dir = 1,
     File,
     Dev,
+    Socket,
+}
+
+/// Statistics of a file system, returned by `Fstatfs`.
+#[repr(C)]
+#[derive(Debug, Pod)]
+pub struct StatFs {
+    /// File system's disk device
+    pub dev: u32,
+    /// Block size in bytes
+    pub block_size: u32,
+    /// Total number of blocks
+    pub blocks: u32,
+    /// Number of free blocks
+    pub free_blocks: u32,
+    /// Total number of inodes
+    pub inodes: u32,
+    /// Number of free inodes
+    pub free_inodes: u32,
+    /// UUID of the file system
+    pub uuid: [u8; 16],
+    /// Label of the file system, padded with NULs
+    pub label: [u8; 16],
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -73,14 +299,523 @@ pub enum WaitTarget {
 #[repr(C)]
 pub struct MemoryInfo {
     pub free_pages: usize,
+    /// Number of free pages that are already zeroed.
+    ///
+    /// Included in `free_pages`.
+    pub zeroed_pages: usize,
     pub total_pages: usize,
     pub page_size: usize,
+    /// Number of processes killed by the kernel to free memory.
+    pub oom_kills: usize,
+}
+
+/// Number of size classes of the kernel heap.
+pub const HEAP_SIZE_CLASSES: usize = 7;
+
+/// Usage of a size class of the kernel heap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Pod)]
+#[repr(C)]
+pub struct HeapClassInfo {
+    /// Size of the objects in bytes
+    pub object_size: usize,
+    /// Number of pages holding the objects
+    pub pages: usize,
+    /// Number of objects in use
+    pub allocated: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Pod)]
+#[repr(C)]
+pub struct HeapInfo {
+    pub classes: [HeapClassInfo; HEAP_SIZE_CLASSES],
+    /// Number of pages of the objects larger than any size class
+    pub large_pages: usize,
 }
 
+/// Statistics of the page cache of file contents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Pod)]
+#[repr(C)]
+pub struct PageCacheInfo {
+    /// Number of reads served from cached pages
+    pub hits: usize,
+    /// Number of pages filled on reads
+    pub misses: usize,
+    /// Number of pages filled by read-ahead
+    pub readahead: usize,
+    /// Number of reads that did not fill the page cache
+    pub bypassed: usize,
+    /// Number of pages dropped right after read
+    pub dropped: usize,
+}
+
+/// Statistics of the cache of directory lookups.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Pod)]
+#[repr(C)]
+pub struct NameCacheInfo {
+    /// Number of lookups that found the inode in the cache
+    pub hits: usize,
+    /// Number of lookups that found in the cache that the name does not exist
+    pub negative_hits: usize,
+    /// Number of lookups that searched the directory
+    pub misses: usize,
+    /// Number of cached names dropped because the directory was modified
+    pub invalidations: usize,
+}
+
+/// Statistics of the disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Pod)]
+#[repr(C)]
+pub struct DiskInfo {
+    /// Number of read requests sent to the disk
+    pub reads: usize,
+    /// Number of write requests sent to the disk
+    pub writes: usize,
+}
+
+/// Number of interrupt sources (IRQs) of the interrupt controller.
+pub const NIRQ: usize = 64;
+
+/// Statistics of device interrupts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Pod)]
+#[repr(C)]
+pub struct InterruptInfo {
+    /// Number of interrupts served, indexed by IRQ
+    pub counts: [usize; NIRQ],
+    /// Number of interrupts with no IRQ pending or no handler registered
+    pub spurious: usize,
+}
+
+/// Statistics of the open files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Pod)]
+#[repr(C)]
+pub struct FileInfo {
+    /// Number of file objects open in the system
+    pub open: usize,
+    /// Largest number of file objects open at the same time
+    pub peak: usize,
+}
+
+/// Receive statistics of the network device and stack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Pod)]
+#[repr(C)]
+pub struct NetInfo {
+    /// Number of packets received from the device
+    pub rx_packets: usize,
+    /// Number of times the receive ring was polled again because it was still
+    /// busy after a batch of packets
+    pub rx_polls: usize,
+    /// Number of packets the device dropped because the receive ring was full
+    pub rx_missed: usize,
+    /// Number of datagrams dropped because no port was bound, the port queue
+    /// was full, or no buffer was available
+    pub rx_dropped: usize,
+}
+
+/// Disk I/O statistics of a process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Pod)]
+#[repr(C)]
+pub struct IoStats {
+    /// Number of blocks read from the disk by the process
+    pub blocks_read: u64,
+    /// Number of blocks written to the disk by the process
+    pub blocks_written: u64,
+    /// Number of times the process waited for its I/O limit
+    pub throttled: u64,
+    /// Maximum number of blocks transferred per second, or 0 if unlimited
+    pub limit: u64,
+}
+
+/// Maximum number of CPUs reported in [`LoadInfo`].
+pub const MAX_CPUS: usize = 8;
+
+/// Set of CPUs a process is allowed to run on.
+///
+/// CPU `n` is represented by bit `n`, for `n` below [`MAX_CPUS`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct CpuSet(usize);
+
+impl CpuSet {
+    /// The set of all CPUs.
+    pub const ALL: Self = Self((1 << MAX_CPUS) - 1);
+    /// The set of no CPUs.
+    pub const EMPTY: Self = Self(0);
+
+    /// Creates a set from its bit representation.
+    ///
+    /// Returns `None` if `bits` has bits set for CPUs at or above
+    /// [`MAX_CPUS`].
+    #[must_use]
+    pub const fn from_bits(bits: usize) -> Option<Self> {
+        if bits & !Self::ALL.0 != 0 {
+            return None;
+        }
+        Some(Self(bits))
+    }
+
+    /// Returns the bit representation of the set.
+    #[must_use]
+    pub const fn bits(self) -> usize {
+        self.0
+    }
+
+    /// Creates a set of the single CPU `cpu`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cpu` is not below [`MAX_CPUS`].
+    #[must_use]
+    pub const fn single(cpu: usize) -> Self {
+        assert!(cpu < MAX_CPUS);
+        Self(1 << cpu)
+    }
+
+    /// Returns the set of CPUs below `n`.
+    #[must_use]
+    pub const fn first(n: usize) -> Self {
+        if n >= MAX_CPUS {
+            return Self::ALL;
+        }
+        Self((1 << n) - 1)
+    }
+
+    #[must_use]
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    #[must_use]
+    pub const fn contains(self, cpu: usize) -> bool {
+        cpu < MAX_CPUS && self.0 & (1 << cpu) != 0
+    }
+
+    /// Adds the CPU `cpu` to the set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cpu` is not below [`MAX_CPUS`].
+    pub const fn insert(&mut self, cpu: usize) {
+        self.0 |= Self::single(cpu).0;
+    }
+
+    #[must_use]
+    pub const fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    /// Returns the IDs of the CPUs in the set, in ascending order.
+    pub fn iter(self) -> impl Iterator<Item = usize> {
+        (0..MAX_CPUS).filter(move |&cpu| self.contains(cpu))
+    }
+}
+
+/// Number of fractional bits of the load averages in [`LoadInfo`].
+pub const LOAD_FSHIFT: u32 = 16;
+
+/// Uptime, load averages and CPU utilization.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Pod)]
+#[repr(C)]
+pub struct LoadInfo {
+    /// Time since boot, in nanoseconds
+    pub uptime_nanos: u64,
+    /// Average numbers of runnable and running processes over the last 1, 5
+    /// and 15 minutes, with [`LOAD_FSHIFT`] fractional bits
+    pub averages: [u64; 3],
+    /// Number of timer ticks per second
+    pub ticks_per_sec: u64,
+    /// Number of CPUs
+    pub num_cpus: usize,
+    /// Number of ticks each CPU spent running a process, indexed by CPU ID
+    pub busy_ticks: [u64; MAX_CPUS],
+    /// Number of ticks each CPU spent idle, indexed by CPU ID
+    pub idle_ticks: [u64; MAX_CPUS],
+}
+
+/// Version of the layout of [`SystemInfo`].
+///
+/// Incremented when the layout changes, so that a program built for another
+/// layout can detect it.
+pub const SYSTEM_INFO_VERSION: u64 = 1;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Pod)]
 #[repr(C)]
 pub struct SystemInfo {
+    /// Layout version, [`SYSTEM_INFO_VERSION`]
+    pub version: u64,
     pub memory: MemoryInfo,
+    pub heap: HeapInfo,
+    pub page_cache: PageCacheInfo,
+    pub name_cache: NameCacheInfo,
+    pub disk: DiskInfo,
+    pub interrupts: InterruptInfo,
+    pub net: NetInfo,
+    pub files: FileInfo,
+    pub load: LoadInfo,
+}
+
+/// Maximum size of a write to a pipe that is guaranteed to be atomic.
+///
+/// A write of at most this many bytes is never interleaved with writes by
+/// other writers of the pipe. It blocks until the pipe has room for all of
+/// the bytes instead of writing a part of them.
+pub const PIPE_BUF: usize = 4096;
+
+/// Maximum length of the host name.
+pub const HOST_NAME_MAX: usize = 64;
+
+/// Nice value of the processes with the highest priority.
+pub const NICE_MIN: isize = -20;
+
+/// Nice value of the processes with the lowest priority.
+pub const NICE_MAX: isize = 19;
+
+/// File mode creation mask of the first process.
+pub const UMASK_DEFAULT: u16 = 0o022;
+
+/// Permission bits that can be set in the file mode creation mask.
+pub const UMASK_BITS: u16 = 0o777;
+
+/// Maximum size of a message sent to a message queue.
+pub const MSG_SIZE_MAX: usize = 256;
+
+/// Maximum length of the path recorded in an [`AuditRecord`].
+pub const AUDIT_PATH_MAX: usize = 64;
+
+/// A record of an operation denied by the kernel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Pod)]
+#[repr(C)]
+pub struct AuditRecord {
+    /// Sequence number of the record, starting from 1
+    pub seq: u64,
+    /// Process ID of the process that issued the system call
+    pub pid: u32,
+    /// System call number
+    pub syscall: u32,
+    /// Error number returned to the process
+    pub errno: u32,
+    /// Length of `path`, or 0 if no path is recorded
+    pub path_len: u32,
+    /// Path argument of the system call (truncated to `AUDIT_PATH_MAX`)
+    pub path: [u8; AUDIT_PATH_MAX],
+}
+
+impl AuditRecord {
+    #[must_use]
+    pub const fn zeroed() -> Self {
+        Self {
+            seq: 0,
+            pid: 0,
+            syscall: 0,
+            errno: 0,
+            path_len: 0,
+            path: [0; AUDIT_PATH_MAX],
+        }
+    }
+
+    #[must_use]
+    pub fn syscall(&self) -> Option<SyscallCode> {
+        SyscallCode::from_repr(self.syscall.safe_into())
+    }
+
+    #[must_use]
+    pub fn error(&self) -> Option<error::SyscallError> {
+        isize::try_from(self.errno)
+            .ok()
+            .and_then(error::SyscallError::from_repr)
+    }
+
+    #[must_use]
+    pub fn path(&self) -> Option<&[u8]> {
+        let len = usize::min(self.path_len.safe_into(), AUDIT_PATH_MAX);
+        (len > 0).then(|| &self.path[..len])
+    }
+}
+
+/// Device-specific operation requested by the `ioctl` system call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromRepr)]
+#[repr(usize)]
+pub enum IoctlRequest {
+    /// Returns the terminal window size encoded by [`WindowSize::to_raw`].
+    GetWindowSize = 1,
+    /// Sets the terminal window size encoded by [`WindowSize::to_raw`].
+    SetWindowSize = 2,
+    /// Returns the current [`TerminalMode`].
+    GetMode = 3,
+    /// Sets the [`TerminalMode`].
+    SetMode = 4,
+    /// Returns the ID of the foreground process group, or 0 if there is none.
+    GetForeground = 5,
+    /// Sets the ID of the foreground process group.
+    ///
+    /// Interrupts typed on the terminal are sent to the processes of the
+    /// group.
+    SetForeground = 6,
+}
+
+/// Input mode of a terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromRepr)]
+#[repr(usize)]
+pub enum TerminalMode {
+    /// Input is line-buffered and edited by the kernel.
+    ///
+    /// Input characters are echoed back, and `read()` returns when a whole line
+    /// has arrived.
+    Cooked = 0,
+    /// Input is passed to the process as is.
+    ///
+    /// Input characters are not echoed back, control characters have no
+    /// special meaning, and `read()` returns as soon as any input is
+    /// available.
+    Raw = 1,
+}
+
+/// Operation requested by the `fcntl` system call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromRepr)]
+#[repr(usize)]
+pub enum FcntlRequest {
+    /// Returns the [`AccessHint`] of the file descriptor.
+    GetFdAccessHint = 1,
+    /// Sets the [`AccessHint`] of the file descriptor.
+    SetFdAccessHint = 2,
+    /// Returns the [`AccessHint`] of the file, shared by all of its file
+    /// descriptors.
+    GetFileAccessHint = 3,
+    /// Sets the [`AccessHint`] of the file, shared by all of its file
+    /// descriptors.
+    SetFileAccessHint = 4,
+    /// Returns the [`FdFlags`] of the file descriptor.
+    GetFdFlags = 5,
+    /// Sets the [`FdFlags`] of the file descriptor.
+    SetFdFlags = 6,
+}
+
+/// Operation requested by the `Ptrace` system call.
+///
+/// Except for [`PtraceRequest::TraceMe`], the target is a child of the
+/// caller, and the requests other than [`PtraceRequest::Attach`] and
+/// [`PtraceRequest::Wait`] require it to be traced and stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromRepr)]
+#[repr(usize)]
+pub enum PtraceRequest {
+    /// Makes the caller traced by its parent. The target must be the caller.
+    TraceMe = 1,
+    /// Starts tracing the target. It stops with [`PtraceStop::Attach`] the
+    /// next time it returns to user space.
+    Attach = 2,
+    /// Stops tracing the target and resumes it.
+    Detach = 3,
+    /// Resumes the target.
+    Continue = 4,
+    /// Resumes the target until the next system call entry or exit.
+    Syscall = 5,
+    /// Resumes the target for a single instruction.
+    Step = 6,
+    /// Waits for the target to stop or exit, and returns the
+    /// [`PtraceStop`].
+    Wait = 7,
+    /// Returns the word at the address in the target.
+    PeekData = 8,
+    /// Writes the word to the address in the target, even if the page is
+    /// read-only.
+    PokeData = 9,
+}
+
+/// Reason of a stop of a traced process, returned by
+/// [`PtraceRequest::Wait`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromRepr)]
+#[repr(usize)]
+pub enum PtraceStop {
+    /// The tracer attached to the process.
+    Attach = 1,
+    /// The process replaced its image by `exec`, and is about to run the
+    /// first instruction of the new program.
+    Exec = 2,
+    /// The process is entering a system call.
+    SyscallEntry = 3,
+    /// The process has returned from a system call.
+    SyscallExit = 4,
+    /// The process executed `ebreak`. The program counter points to it.
+    Breakpoint = 5,
+    /// The process executed a single instruction.
+    Step = 6,
+    /// The process exited. It is not stopped, and must be waited for with
+    /// `Wait`.
+    Exited = 7,
+}
+
+/// Registers of a traced process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Pod)]
+#[repr(C)]
+pub struct PtraceRegs {
+    /// Program counter
+    pub pc: usize,
+    /// General purpose registers `x0` to `x31`. `x0` is always 0.
+    pub x: [usize; 32],
+}
+
+impl PtraceRegs {
+    #[must_use]
+    pub const fn zeroed() -> Self {
+        Self { pc: 0, x: [0; 32] }
+    }
+}
+
+/// Expected access pattern of a file, used to tune the page cache.
+///
+/// The hint of a file descriptor takes precedence over the hint of the file,
+/// unless it is [`AccessHint::Normal`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, FromRepr)]
+#[repr(usize)]
+pub enum AccessHint {
+    /// No particular access pattern.
+    #[default]
+    Normal = 0,
+    /// Read from the start to the end. The pages following the read page are
+    /// read ahead into the page cache.
+    Sequential = 1,
+    /// Read at random offsets. Pages not in the page cache are read without
+    /// filling it.
+    Random = 2,
+    /// Read only once. The pages are dropped from the page cache as soon as
+    /// they are read.
+    NoReuse = 3,
+}
+
+/// Size of a terminal window, in characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WindowSize {
+    pub rows: u16,
+    pub cols: u16,
+}
+
+impl WindowSize {
+    /// Encodes the window size into a single `ioctl` argument.
+    #[must_use]
+    pub fn to_raw(self) -> usize {
+        (usize::from(self.rows) << 16) | usize::from(self.cols)
+    }
+
+    /// Decodes the window size from an `ioctl` argument.
+    ///
+    /// Returns `None` if `raw` has bits set outside the encoded fields.
+    #[must_use]
+    pub fn from_raw(raw: usize) -> Option<Self> {
+        let raw = u32::try_from(raw).ok()?;
+        let [r1, r0, c1, c0] = raw.to_be_bytes();
+        Some(Self {
+            rows: u16::from_be_bytes([r1, r0]),
+            cols: u16::from_be_bytes([c1, c0]),
+        })
+    }
+}
+
+/// System shutdown requested to the init process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownRequest {
+    /// Powers off the system with the exit code.
+    Halt(u16),
+    /// Restarts the system.
+    Reboot,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Pod)]
@@ -108,7 +843,7 @@ impl From<SocketAddrV4Pod> for SocketAddrV4 {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, FromRepr, EnumString, Display)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromRepr, EnumString, EnumCount, Display)]
 #[repr(usize)]
 #[strum(serialize_all = "snake_case")]
 #[strum(ascii_case_insensitive)]
@@ -147,6 +882,70 @@ pub enum SyscallCode {
     Trace,
     DumpKernelPageTable,
     DumpUserPageTable,
+    Chroot,
+    DropCaps,
+    SetSyscallFilter,
+    ReadAuditLog,
+    Ioctl,
+    RequestShutdown,
+    WaitShutdownRequest,
+    Sync,
+    Fstatfs,
+    Rmdir,
+    Readv,
+    Writev,
+    SendFile,
+    Pread,
+    Pwrite,
+    ResizeFs,
+    InjectAllocFault,
+    Fcntl,
+    Remount,
+    Splice,
+    SocketPair,
+    LocalBind,
+    LocalListen,
+    LocalAccept,
+    LocalConnect,
+    MsgGet,
+    MsgSend,
+    MsgRecv,
+    MsgRemove,
+    SemOpen,
+    SemWait,
+    SemPost,
+    SemClose,
+    EventFd,
+    SetProcessGroup,
+    GetProcessGroup,
+    SetInterruptAction,
+    SetInterruptHandler,
+    SetIoLimit,
+    GetIoStats,
+    GetHostname,
+    SetHostname,
+    Flink,
+    Dup2,
+    Pipe2,
+    Dup3,
+    SetNice,
+    SetAffinity,
+    GetAffinity,
+    Getcwd,
+    Openat,
+    Fstatat,
+    Unlinkat,
+    Mkdirat,
+    Fchdir,
+    Umask,
+    Access,
+    Faccessat,
+    Ptrace,
+    PtraceGetRegs,
+    PtraceSetRegs,
+    GetKernelSymbols,
+    Getcpu,
+    Mount,
 }
 
 /// A trait representing a system call.
@@ -255,6 +1054,19 @@ where
         }
     }
 
+    /// Creates a `UserMutRef` from a raw address.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that the provided address is valid.
+    #[must_use]
+    pub const unsafe fn from_raw(addr: usize) -> Self {
+        Self {
+            addr,
+            _phantom: PhantomData,
+        }
+    }
+
     /// Returns the address of the mutable user reference.
     #[must_use]
     pub fn addr(&self) -> usize {
@@ -582,6 +1394,32 @@ pub enum RegisterDecodeError {
     InvalidSyscallErrorNo(isize),
     #[error("invalid open flags: {0:#x}")]
     InvalidOpenFlags(usize),
+    #[error("invalid capabilities: {0:#x}")]
+    InvalidCapabilities(usize),
+    #[error("invalid mount flags: {0:#x}")]
+    InvalidMountFlags(usize),
+    #[error("invalid message queue flags: {0:#x}")]
+    InvalidMsgQueueFlags(usize),
+    #[error("invalid event fd flags: {0:#x}")]
+    InvalidEventFdFlags(usize),
+    #[error("invalid CPU set: {0:#x}")]
+    InvalidCpuSet(usize),
+    #[error("invalid at flags: {0:#x}")]
+    InvalidAtFlags(usize),
+    #[error("invalid access mode: {0:#x}")]
+    InvalidAccessMode(usize),
+    #[error("invalid syscall filter action: {0}")]
+    InvalidSyscallFilterAction(usize),
+    #[error("invalid interrupt action: {0}")]
+    InvalidInterruptAction(usize),
+    #[error("invalid ioctl request: {0}")]
+    InvalidIoctlRequest(usize),
+    #[error("invalid fcntl request: {0}")]
+    InvalidFcntlRequest(usize),
+    #[error("invalid ptrace request: {0}")]
+    InvalidPtraceRequest(usize),
+    #[error("invalid shutdown request: {0:#x}")]
+    InvalidShutdownRequest(usize),
     #[error("invalid result designator: {0:#x}")]
     InvalidDesignator(usize),
     #[error("unexpected zero")]
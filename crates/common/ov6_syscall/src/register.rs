@@ -10,8 +10,10 @@ use ov6_types::{fs::RawFd, process::ProcId};
 use safe_cast::SafeInto as _;
 
 use crate::{
-    OpenFlags, Register, RegisterDecodeError, RegisterValue, UserMutRef, UserMutSlice, UserRef,
-    UserSlice, WaitTarget, error::SyscallError,
+    AccessMode, AtFlags, Capabilities, CpuSet, EventFdFlags, FcntlRequest, InterruptAction,
+    IoctlRequest, MountFlags, MsgQueueFlags, OpenFlags, PtraceRequest, Register,
+    RegisterDecodeError, RegisterValue, ShutdownRequest, SyscallFilterAction, UserMutRef,
+    UserMutSlice, UserRef, UserSlice, WaitTarget, error::SyscallError,
 };
 
 impl<T, const N: usize> Register<T, N> {
@@ -303,6 +305,196 @@ impl RegisterValue for OpenFlags {
     }
 }
 
+impl RegisterValue for Capabilities {
+    type DecodeError = RegisterDecodeError;
+    type Repr = Register<Self, 1>;
+
+    fn encode(self) -> Self::Repr {
+        self.bits().encode().map_type()
+    }
+
+    fn try_decode(repr: Self::Repr) -> Result<Self, Self::DecodeError> {
+        let bits = repr.map_type().try_decode()?;
+        Self::from_bits(bits).ok_or(RegisterDecodeError::InvalidCapabilities(bits))
+    }
+}
+
+impl RegisterValue for MountFlags {
+    type DecodeError = RegisterDecodeError;
+    type Repr = Register<Self, 1>;
+
+    fn encode(self) -> Self::Repr {
+        self.bits().encode().map_type()
+    }
+
+    fn try_decode(repr: Self::Repr) -> Result<Self, Self::DecodeError> {
+        let bits = repr.map_type().try_decode()?;
+        Self::from_bits(bits).ok_or(RegisterDecodeError::InvalidMountFlags(bits))
+    }
+}
+
+impl RegisterValue for MsgQueueFlags {
+    type DecodeError = RegisterDecodeError;
+    type Repr = Register<Self, 1>;
+
+    fn encode(self) -> Self::Repr {
+        self.bits().encode().map_type()
+    }
+
+    fn try_decode(repr: Self::Repr) -> Result<Self, Self::DecodeError> {
+        let bits = repr.map_type().try_decode()?;
+        Self::from_bits(bits).ok_or(RegisterDecodeError::InvalidMsgQueueFlags(bits))
+    }
+}
+
+impl RegisterValue for EventFdFlags {
+    type DecodeError = RegisterDecodeError;
+    type Repr = Register<Self, 1>;
+
+    fn encode(self) -> Self::Repr {
+        self.bits().encode().map_type()
+    }
+
+    fn try_decode(repr: Self::Repr) -> Result<Self, Self::DecodeError> {
+        let bits = repr.map_type().try_decode()?;
+        Self::from_bits(bits).ok_or(RegisterDecodeError::InvalidEventFdFlags(bits))
+    }
+}
+
+impl RegisterValue for AtFlags {
+    type DecodeError = RegisterDecodeError;
+    type Repr = Register<Self, 1>;
+
+    fn encode(self) -> Self::Repr {
+        self.bits().encode().map_type()
+    }
+
+    fn try_decode(repr: Self::Repr) -> Result<Self, Self::DecodeError> {
+        let bits = repr.map_type().try_decode()?;
+        Self::from_bits(bits).ok_or(RegisterDecodeError::InvalidAtFlags(bits))
+    }
+}
+
+impl RegisterValue for AccessMode {
+    type DecodeError = RegisterDecodeError;
+    type Repr = Register<Self, 1>;
+
+    fn encode(self) -> Self::Repr {
+        self.bits().encode().map_type()
+    }
+
+    fn try_decode(repr: Self::Repr) -> Result<Self, Self::DecodeError> {
+        let bits = repr.map_type().try_decode()?;
+        Self::from_bits(bits).ok_or(RegisterDecodeError::InvalidAccessMode(bits))
+    }
+}
+
+impl RegisterValue for CpuSet {
+    type DecodeError = RegisterDecodeError;
+    type Repr = Register<Self, 1>;
+
+    fn encode(self) -> Self::Repr {
+        self.bits().encode().map_type()
+    }
+
+    fn try_decode(repr: Self::Repr) -> Result<Self, Self::DecodeError> {
+        let bits = repr.map_type().try_decode()?;
+        Self::from_bits(bits).ok_or(RegisterDecodeError::InvalidCpuSet(bits))
+    }
+}
+
+impl RegisterValue for SyscallFilterAction {
+    type DecodeError = RegisterDecodeError;
+    type Repr = Register<Self, 1>;
+
+    fn encode(self) -> Self::Repr {
+        (self as usize).encode().map_type()
+    }
+
+    fn try_decode(repr: Self::Repr) -> Result<Self, Self::DecodeError> {
+        let n = repr.map_type().try_decode()?;
+        Self::from_repr(n).ok_or(RegisterDecodeError::InvalidSyscallFilterAction(n))
+    }
+}
+
+impl RegisterValue for InterruptAction {
+    type DecodeError = RegisterDecodeError;
+    type Repr = Register<Self, 1>;
+
+    fn encode(self) -> Self::Repr {
+        (self as usize).encode().map_type()
+    }
+
+    fn try_decode(repr: Self::Repr) -> Result<Self, Self::DecodeError> {
+        let n = repr.map_type().try_decode()?;
+        Self::from_repr(n).ok_or(RegisterDecodeError::InvalidInterruptAction(n))
+    }
+}
+
+impl RegisterValue for IoctlRequest {
+    type DecodeError = RegisterDecodeError;
+    type Repr = Register<Self, 1>;
+
+    fn encode(self) -> Self::Repr {
+        (self as usize).encode().map_type()
+    }
+
+    fn try_decode(repr: Self::Repr) -> Result<Self, Self::DecodeError> {
+        let n = repr.map_type().try_decode()?;
+        Self::from_repr(n).ok_or(RegisterDecodeError::InvalidIoctlRequest(n))
+    }
+}
+
+impl RegisterValue for FcntlRequest {
+    type DecodeError = RegisterDecodeError;
+    type Repr = Register<Self, 1>;
+
+    fn encode(self) -> Self::Repr {
+        (self as usize).encode().map_type()
+    }
+
+    fn try_decode(repr: Self::Repr) -> Result<Self, Self::DecodeError> {
+        let n = repr.map_type().try_decode()?;
+        Self::from_repr(n).ok_or(RegisterDecodeError::InvalidFcntlRequest(n))
+    }
+}
+
+impl RegisterValue for PtraceRequest {
+    type DecodeError = RegisterDecodeError;
+    type Repr = Register<Self, 1>;
+
+    fn encode(self) -> Self::Repr {
+        (self as usize).encode().map_type()
+    }
+
+    fn try_decode(repr: Self::Repr) -> Result<Self, Self::DecodeError> {
+        let n = repr.map_type().try_decode()?;
+        Self::from_repr(n).ok_or(RegisterDecodeError::InvalidPtraceRequest(n))
+    }
+}
+
+impl RegisterValue for ShutdownRequest {
+    type DecodeError = RegisterDecodeError;
+    type Repr = Register<Self, 1>;
+
+    fn encode(self) -> Self::Repr {
+        let n = match self {
+            Self::Halt(code) => usize::from(code),
+            Self::Reboot => 1 << 16,
+        };
+        n.encode().map_type()
+    }
+
+    fn try_decode(repr: Self::Repr) -> Result<Self, Self::DecodeError> {
+        let n: usize = repr.map_type().try_decode()?;
+        match n {
+            0..=0xffff => Ok(Self::Halt(u16::try_from(n)?)),
+            0x1_0000 => Ok(Self::Reboot),
+            _ => Err(RegisterDecodeError::InvalidShutdownRequest(n)),
+        }
+    }
+}
+
 impl RegisterValue for Ipv4Addr {
     type DecodeError = RegisterDecodeError;
     type Repr = Register<Self, 1>;
@@ -522,9 +714,13 @@ where
 impl_value!([] Result<(), SyscallError>, RegisterDecodeError, 2, result_encode_01, result_decode_01);
 impl_value!([] Result<Infallible, SyscallError>, RegisterDecodeError, 2, result_encode_01, result_decode_01);
 impl_value!([] Result<usize, SyscallError>, RegisterDecodeError, 2, result_encode_11, result_decode_11);
+impl_value!([] Result<u16, SyscallError>, RegisterDecodeError, 2, result_encode_11, result_decode_11);
 impl_value!([] Result<Option<ProcId>, SyscallError>, RegisterDecodeError, 2, result_encode_11, result_decode_11);
 impl_value!([] Result<ProcId, SyscallError>, RegisterDecodeError, 2, result_encode_11, result_decode_11);
 impl_value!([] Result<RawFd, SyscallError>, RegisterDecodeError, 2, result_encode_11, result_decode_11);
+impl_value!([] Result<Capabilities, SyscallError>, RegisterDecodeError, 2, result_encode_11, result_decode_11);
+impl_value!([] Result<CpuSet, SyscallError>, RegisterDecodeError, 2, result_encode_11, result_decode_11);
+impl_value!([] Result<ShutdownRequest, SyscallError>, RegisterDecodeError, 2, result_encode_11, result_decode_11);
 
 fn tuple1_encode<T, const N: usize>((v0,): (T,)) -> Register<(T,), N>
 where
@@ -629,6 +825,32 @@ where
     Ok((v0, v1))
 }
 
+fn tuple_encode_111<T, U, V>((v0, v1, v2): (T, U, V)) -> Register<(T, U, V), 3>
+where
+    T: RegisterValue<Repr = Register<T, 1>>,
+    U: RegisterValue<Repr = Register<U, 1>>,
+    V: RegisterValue<Repr = Register<V, 1>>,
+{
+    let [a0] = v0.encode().a;
+    let [a1] = v1.encode().a;
+    let [a2] = v2.encode().a;
+    Register::new([a0, a1, a2])
+}
+
+fn tuple_decode_111<T, U, V, E>(repr: Register<(T, U, V), 3>) -> Result<(T, U, V), E>
+where
+    T: RegisterValue<Repr = Register<T, 1>>,
+    U: RegisterValue<Repr = Register<U, 1>>,
+    V: RegisterValue<Repr = Register<V, 1>>,
+    E: From<T::DecodeError> + From<U::DecodeError> + From<V::DecodeError>,
+{
+    let [a0, a1, a2] = repr.a;
+    let v0 = Register::new([a0]).try_decode()?;
+    let v1 = Register::new([a1]).try_decode()?;
+    let v2 = Register::new([a2]).try_decode()?;
+    Ok((v0, v1, v2))
+}
+
 fn tuple_encode_211<T, U, V>((v0, v1, v2): (T, U, V)) -> Register<(T, U, V), 4>
 where
     T: RegisterValue<Repr = Register<T, 2>>,
@@ -681,6 +903,118 @@ where
     Ok((v0, v1, v2))
 }
 
+fn tuple_encode_121<T, U, V>((v0, v1, v2): (T, U, V)) -> Register<(T, U, V), 4>
+where
+    T: RegisterValue<Repr = Register<T, 1>>,
+    U: RegisterValue<Repr = Register<U, 2>>,
+    V: RegisterValue<Repr = Register<V, 1>>,
+{
+    let [a0] = v0.encode().a;
+    let [a1, a2] = v1.encode().a;
+    let [a3] = v2.encode().a;
+    Register::new([a0, a1, a2, a3])
+}
+
+fn tuple_decode_121<T, U, V, E>(repr: Register<(T, U, V), 4>) -> Result<(T, U, V), E>
+where
+    T: RegisterValue<Repr = Register<T, 1>>,
+    U: RegisterValue<Repr = Register<U, 2>>,
+    V: RegisterValue<Repr = Register<V, 1>>,
+    E: From<T::DecodeError> + From<U::DecodeError> + From<V::DecodeError>,
+{
+    let [a0, a1, a2, a3] = repr.a;
+    let v0 = Register::new([a0]).try_decode()?;
+    let v1 = Register::new([a1, a2]).try_decode()?;
+    let v2 = Register::new([a3]).try_decode()?;
+    Ok((v0, v1, v2))
+}
+
+fn tuple_encode_221<T, U, V>((v0, v1, v2): (T, U, V)) -> Register<(T, U, V), 5>
+where
+    T: RegisterValue<Repr = Register<T, 2>>,
+    U: RegisterValue<Repr = Register<U, 2>>,
+    V: RegisterValue<Repr = Register<V, 1>>,
+{
+    let [a0, a1] = v0.encode().a;
+    let [a2, a3] = v1.encode().a;
+    let [a4] = v2.encode().a;
+    Register::new([a0, a1, a2, a3, a4])
+}
+
+fn tuple_decode_221<T, U, V, E>(repr: Register<(T, U, V), 5>) -> Result<(T, U, V), E>
+where
+    T: RegisterValue<Repr = Register<T, 2>>,
+    U: RegisterValue<Repr = Register<U, 2>>,
+    V: RegisterValue<Repr = Register<V, 1>>,
+    E: From<T::DecodeError> + From<U::DecodeError> + From<V::DecodeError>,
+{
+    let [a0, a1, a2, a3, a4] = repr.a;
+    let v0 = Register::new([a0, a1]).try_decode()?;
+    let v1 = Register::new([a2, a3]).try_decode()?;
+    let v2 = Register::new([a4]).try_decode()?;
+    Ok((v0, v1, v2))
+}
+
+fn tuple_encode_1111<T, U, V, W>((v0, v1, v2, v3): (T, U, V, W)) -> Register<(T, U, V, W), 4>
+where
+    T: RegisterValue<Repr = Register<T, 1>>,
+    U: RegisterValue<Repr = Register<U, 1>>,
+    V: RegisterValue<Repr = Register<V, 1>>,
+    W: RegisterValue<Repr = Register<W, 1>>,
+{
+    let [a0] = v0.encode().a;
+    let [a1] = v1.encode().a;
+    let [a2] = v2.encode().a;
+    let [a3] = v3.encode().a;
+    Register::new([a0, a1, a2, a3])
+}
+
+fn tuple_decode_1111<T, U, V, W, E>(repr: Register<(T, U, V, W), 4>) -> Result<(T, U, V, W), E>
+where
+    T: RegisterValue<Repr = Register<T, 1>>,
+    U: RegisterValue<Repr = Register<U, 1>>,
+    V: RegisterValue<Repr = Register<V, 1>>,
+    W: RegisterValue<Repr = Register<W, 1>>,
+    E: From<T::DecodeError> + From<U::DecodeError> + From<V::DecodeError> + From<W::DecodeError>,
+{
+    let [a0, a1, a2, a3] = repr.a;
+    let v0 = Register::new([a0]).try_decode()?;
+    let v1 = Register::new([a1]).try_decode()?;
+    let v2 = Register::new([a2]).try_decode()?;
+    let v3 = Register::new([a3]).try_decode()?;
+    Ok((v0, v1, v2, v3))
+}
+
+fn tuple_encode_1211<T, U, V, W>((v0, v1, v2, v3): (T, U, V, W)) -> Register<(T, U, V, W), 5>
+where
+    T: RegisterValue<Repr = Register<T, 1>>,
+    U: RegisterValue<Repr = Register<U, 2>>,
+    V: RegisterValue<Repr = Register<V, 1>>,
+    W: RegisterValue<Repr = Register<W, 1>>,
+{
+    let [a0] = v0.encode().a;
+    let [a1, a2] = v1.encode().a;
+    let [a3] = v2.encode().a;
+    let [a4] = v3.encode().a;
+    Register::new([a0, a1, a2, a3, a4])
+}
+
+fn tuple_decode_1211<T, U, V, W, E>(repr: Register<(T, U, V, W), 5>) -> Result<(T, U, V, W), E>
+where
+    T: RegisterValue<Repr = Register<T, 1>>,
+    U: RegisterValue<Repr = Register<U, 2>>,
+    V: RegisterValue<Repr = Register<V, 1>>,
+    W: RegisterValue<Repr = Register<W, 1>>,
+    E: From<T::DecodeError> + From<U::DecodeError> + From<V::DecodeError> + From<W::DecodeError>,
+{
+    let [a0, a1, a2, a3, a4] = repr.a;
+    let v0 = Register::new([a0]).try_decode()?;
+    let v1 = Register::new([a1, a2]).try_decode()?;
+    let v2 = Register::new([a3]).try_decode()?;
+    let v3 = Register::new([a4]).try_decode()?;
+    Ok((v0, v1, v2, v3))
+}
+
 impl_value!(
     [](u16,),
     RegisterDecodeError,
@@ -697,6 +1031,7 @@ impl_value!(
     tuple1_decode
 );
 impl_value!([](u64,), Infallible, 1, tuple1_encode, tuple1_decode);
+impl_value!([](usize,), Infallible, 1, tuple1_encode, tuple1_decode);
 impl_value!([](isize,), Infallible, 1, tuple1_encode, tuple1_decode);
 impl_value!(
     [](Duration,),
@@ -706,6 +1041,20 @@ impl_value!(
     tuple1_decode
 );
 impl_value!([](RawFd,), Infallible, 1, tuple1_encode, tuple1_decode);
+impl_value!(
+    [](Capabilities,),
+    RegisterDecodeError,
+    1,
+    tuple1_encode,
+    tuple1_decode
+);
+impl_value!(
+    [](MountFlags,),
+    RegisterDecodeError,
+    1,
+    tuple1_encode,
+    tuple1_decode
+);
 impl_value!(
     [](ProcId,),
     RegisterDecodeError,
@@ -713,14 +1062,83 @@ impl_value!(
     tuple1_encode,
     tuple1_decode
 );
+impl_value!(
+    [](InterruptAction,),
+    RegisterDecodeError,
+    1,
+    tuple1_encode,
+    tuple1_decode
+);
+impl_value!(
+    [](ShutdownRequest,),
+    RegisterDecodeError,
+    1,
+    tuple1_encode,
+    tuple1_decode
+);
 impl_value!([T: ?Sized](UserRef<T>,), Infallible, 1, tuple1_encode, tuple1_decode);
 impl_value!([T: ?Sized](UserMutRef<T>,), Infallible, 1, tuple1_encode, tuple1_decode);
 impl_value!([T](UserSlice<T>,), Infallible, 2, tuple1_encode, tuple1_decode);
 impl_value!([T](UserMutSlice<T>,), Infallible, 2, tuple1_encode, tuple1_decode);
 
+impl_value!(
+    [](u64, usize),
+    Infallible,
+    2,
+    tuple_encode_11,
+    tuple_decode_11
+);
+impl_value!(
+    [](u64, MsgQueueFlags),
+    RegisterDecodeError,
+    2,
+    tuple_encode_11,
+    tuple_decode_11
+);
+impl_value!(
+    [](u64, EventFdFlags),
+    RegisterDecodeError,
+    2,
+    tuple_encode_11,
+    tuple_decode_11
+);
+impl_value!([T: ?Sized] (ProcId, UserMutRef<T>), RegisterDecodeError, 2, tuple_encode_11, tuple_decode_11);
+impl_value!([T: ?Sized] (ProcId, UserRef<T>), RegisterDecodeError, 2, tuple_encode_11, tuple_decode_11);
+impl_value!(
+    [](ProcId, ProcId),
+    RegisterDecodeError,
+    2,
+    tuple_encode_11,
+    tuple_decode_11
+);
+impl_value!(
+    [](ProcId, CpuSet),
+    RegisterDecodeError,
+    2,
+    tuple_encode_11,
+    tuple_decode_11
+);
 impl_value!([T: ?Sized] (RawFd, UserMutRef<T>), Infallible, 2, tuple_encode_11, tuple_decode_11);
+impl_value!([T: ?Sized] (UserMutRef<T>, OpenFlags), RegisterDecodeError, 2, tuple_encode_11, tuple_decode_11);
 impl_value!([T: ?Sized] (RawFd, UserRef<T>), Infallible, 2, tuple_encode_11, tuple_decode_11);
+impl_value!(
+    [](RawFd, usize),
+    Infallible,
+    2,
+    tuple_encode_11,
+    tuple_decode_11
+);
+impl_value!(
+    [](RawFd, RawFd),
+    Infallible,
+    2,
+    tuple_encode_11,
+    tuple_decode_11
+);
 impl_value!([T] (UserSlice<T>, OpenFlags), RegisterDecodeError, 3, tuple_encode_21, tuple_decode_21);
+impl_value!([T] (UserSlice<T>, AccessMode), RegisterDecodeError, 3, tuple_encode_21, tuple_decode_21);
+impl_value!([T] (UserSlice<T>, SyscallFilterAction), RegisterDecodeError, 3, tuple_encode_21, tuple_decode_21);
+impl_value!([T] (UserMutSlice<T>, usize), Infallible, 3, tuple_encode_21, tuple_decode_21);
 impl_value!([T: ?Sized](WaitTarget, UserMutRef<T>,), RegisterDecodeError, 3, tuple_encode_21, tuple_decode_21);
 impl_value!([T: ?Sized] (Duration, UserRef<T>), RegisterDecodeError, 3, tuple_encode_21, tuple_decode_21);
 impl_value!([T, U] (UserSlice<T>, UserSlice<U>), Infallible, 4, tuple_encode_22, tuple_decode_22);
@@ -729,6 +1147,50 @@ impl_value!([T] (RawFd, UserSlice<T>), Infallible, 3, tuple_encode_12, tuple_dec
 impl_value!([T] (RawFd, UserMutSlice<T>), Infallible, 3, tuple_encode_12, tuple_decode_12);
 impl_value!([T: ?Sized, U] (UserRef<T>, UserSlice<U>), Infallible, 3, tuple_encode_12, tuple_decode_12);
 
+impl_value!(
+    [](RawFd, IoctlRequest, usize),
+    RegisterDecodeError,
+    3,
+    tuple_encode_111,
+    tuple_decode_111
+);
+impl_value!(
+    [](RawFd, FcntlRequest, usize),
+    RegisterDecodeError,
+    3,
+    tuple_encode_111,
+    tuple_decode_111
+);
+impl_value!(
+    [](RawFd, RawFd, usize),
+    RegisterDecodeError,
+    3,
+    tuple_encode_111,
+    tuple_decode_111
+);
+impl_value!(
+    [](RawFd, RawFd, OpenFlags),
+    RegisterDecodeError,
+    3,
+    tuple_encode_111,
+    tuple_decode_111
+);
 impl_value!([T] (UserSlice<T>, u32, u16), RegisterDecodeError, 4, tuple_encode_211, tuple_decode_211);
 impl_value!([T: ?Sized, U] (u16, UserMutRef<T>, UserMutSlice<U>), RegisterDecodeError, 4, tuple_encode_112, tuple_decode_112);
 impl_value!([T] (u16, SocketAddrV4, UserSlice<T>), RegisterDecodeError, 4, tuple_encode_112, tuple_decode_112);
+impl_value!([T] (RawFd, UserSlice<T>, usize), Infallible, 4, tuple_encode_121, tuple_decode_121);
+impl_value!([T] (RawFd, UserMutSlice<T>, usize), Infallible, 4, tuple_encode_121, tuple_decode_121);
+impl_value!([T] (RawFd, UserSlice<T>, OpenFlags), RegisterDecodeError, 4, tuple_encode_121, tuple_decode_121);
+impl_value!([T] (RawFd, UserSlice<T>, AtFlags), RegisterDecodeError, 4, tuple_encode_121, tuple_decode_121);
+impl_value!([T, U: ?Sized] (RawFd, UserSlice<T>, UserMutRef<U>), Infallible, 4, tuple_encode_121, tuple_decode_121);
+impl_value!([T] (usize, UserSlice<T>, usize), Infallible, 4, tuple_encode_121, tuple_decode_121);
+impl_value!([T, U] (UserSlice<T>, UserSlice<U>, usize), Infallible, 5, tuple_encode_221, tuple_decode_221);
+impl_value!([T, U: ?Sized] (usize, UserMutSlice<T>, UserMutRef<U>), Infallible, 4, tuple_encode_121, tuple_decode_121);
+impl_value!([T] (RawFd, UserSlice<T>, AccessMode, AtFlags), RegisterDecodeError, 5, tuple_encode_1211, tuple_decode_1211);
+impl_value!(
+    [](PtraceRequest, ProcId, usize, usize),
+    RegisterDecodeError,
+    4,
+    tuple_encode_1111,
+    tuple_decode_1111
+);
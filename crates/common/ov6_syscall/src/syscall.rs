@@ -3,8 +3,11 @@ use core::{convert::Infallible, net::SocketAddrV4, time::Duration};
 use ov6_types::{fs::RawFd, process::ProcId};
 
 use crate::{
-    OpenFlags, SocketAddrV4Pod, Stat, Syscall, SyscallCode, SystemInfo, UserMutRef, UserMutSlice,
-    UserRef, UserSlice, WaitTarget, error::SyscallError,
+    AccessMode, AtFlags, AuditRecord, Capabilities, CpuSet, EventFdFlags, FcntlRequest,
+    InterruptAction, IoStats, IoctlRequest, MountFlags, MsgQueueFlags, OpenFlags, PtraceRegs,
+    PtraceRequest, ShutdownRequest, SocketAddrV4Pod, Stat, StatFs, Syscall, SyscallCode,
+    SyscallFilterAction, SystemInfo, UserMutRef, UserMutSlice, UserRef, UserSlice, WaitTarget,
+    error::SyscallError,
 };
 
 macro_rules! syscall {
@@ -56,4 +59,68 @@ syscall! {
     struct Trace(fn(u64) -> ());
     struct DumpKernelPageTable(fn() -> ());
     struct DumpUserPageTable(fn() -> ());
+    struct Chroot(fn(UserSlice<u8>) -> Result<(), SyscallError>);
+    struct DropCaps(fn(Capabilities) -> Result<Capabilities, SyscallError>);
+    struct SetSyscallFilter(fn(UserSlice<u64>, SyscallFilterAction) -> Result<(), SyscallError>);
+    struct ReadAuditLog(fn(UserMutSlice<AuditRecord>) -> Result<usize, SyscallError>);
+    struct Ioctl(fn(RawFd, IoctlRequest, usize) -> Result<usize, SyscallError>);
+    struct RequestShutdown(fn(ShutdownRequest) -> Result<(), SyscallError>);
+    struct WaitShutdownRequest(fn() -> Result<ShutdownRequest, SyscallError>);
+    struct Sync(fn() -> Result<(), SyscallError>);
+    struct Fstatfs(fn(RawFd, UserMutRef<StatFs>) -> Result<(), SyscallError>);
+    struct Rmdir(fn(UserSlice<u8>) -> Result<(), SyscallError>);
+    struct Readv(fn(RawFd, UserSlice<UserMutSlice<u8>>) -> Result<usize, SyscallError>);
+    struct Writev(fn(RawFd, UserSlice<UserSlice<u8>>) -> Result<usize, SyscallError>);
+    struct SendFile(fn(RawFd, RawFd, usize) -> Result<usize, SyscallError>);
+    struct Pread(fn(RawFd, UserMutSlice<u8>, usize) -> Result<usize, SyscallError>);
+    struct Pwrite(fn(RawFd, UserSlice<u8>, usize) -> Result<usize, SyscallError>);
+    struct ResizeFs(fn(u64) -> Result<usize, SyscallError>);
+    struct InjectAllocFault(fn(u64) -> Result<(), SyscallError>);
+    struct Fcntl(fn(RawFd, FcntlRequest, usize) -> Result<usize, SyscallError>);
+    struct Remount(fn(MountFlags) -> Result<(), SyscallError>);
+    struct Splice(fn(RawFd, RawFd, usize) -> Result<usize, SyscallError>);
+    struct SocketPair(fn(UserMutRef<[RawFd; 2]>) -> Result<(), SyscallError>);
+    struct LocalBind(fn(UserSlice<u8>) -> Result<RawFd, SyscallError>);
+    struct LocalListen(fn(RawFd, usize) -> Result<(), SyscallError>);
+    struct LocalAccept(fn(RawFd) -> Result<RawFd, SyscallError>);
+    struct LocalConnect(fn(UserSlice<u8>) -> Result<RawFd, SyscallError>);
+    struct MsgGet(fn(u64, MsgQueueFlags) -> Result<usize, SyscallError>);
+    struct MsgSend(fn(usize, UserSlice<u8>, usize) -> Result<(), SyscallError>);
+    struct MsgRecv(fn(usize, UserMutSlice<u8>, UserMutRef<usize>) -> Result<usize, SyscallError>);
+    struct MsgRemove(fn(usize) -> Result<(), SyscallError>);
+    struct SemOpen(fn(u64, usize) -> Result<RawFd, SyscallError>);
+    struct SemWait(fn(RawFd) -> Result<(), SyscallError>);
+    struct SemPost(fn(RawFd) -> Result<(), SyscallError>);
+    struct SemClose(fn(RawFd) -> Result<(), SyscallError>);
+    struct EventFd(fn(u64, EventFdFlags) -> Result<RawFd, SyscallError>);
+    struct SetProcessGroup(fn(ProcId, ProcId) -> Result<(), SyscallError>);
+    struct GetProcessGroup(fn(ProcId) -> Result<ProcId, SyscallError>);
+    struct SetInterruptAction(fn(InterruptAction) -> Result<(), SyscallError>);
+    struct SetInterruptHandler(fn(UserRef<extern "C" fn () -> ()>) -> Result<(), SyscallError>);
+    struct SetIoLimit(fn(u64) -> Result<(), SyscallError>);
+    struct GetIoStats(fn(ProcId, UserMutRef<IoStats>) -> Result<(), SyscallError>);
+    struct GetHostname(fn(UserMutSlice<u8>) -> Result<usize, SyscallError>);
+    struct SetHostname(fn(UserSlice<u8>) -> Result<(), SyscallError>);
+    struct Flink(fn(RawFd, UserSlice<u8>) -> Result<(), SyscallError>);
+    struct Dup2(fn(RawFd, RawFd) -> Result<RawFd, SyscallError>);
+    struct Pipe2(fn(UserMutRef<[RawFd; 2]>, OpenFlags) -> Result<(), SyscallError>);
+    struct Dup3(fn(RawFd, RawFd, OpenFlags) -> Result<RawFd, SyscallError>);
+    struct SetNice(fn(isize) -> Result<(), SyscallError>);
+    struct SetAffinity(fn(ProcId, CpuSet) -> Result<(), SyscallError>);
+    struct GetAffinity(fn(ProcId) -> Result<CpuSet, SyscallError>);
+    struct Getcwd(fn(UserMutSlice<u8>) -> Result<usize, SyscallError>);
+    struct Openat(fn(RawFd, UserSlice<u8>, OpenFlags) -> Result<RawFd, SyscallError>);
+    struct Fstatat(fn(RawFd, UserSlice<u8>, UserMutRef<Stat>) -> Result<(), SyscallError>);
+    struct Unlinkat(fn(RawFd, UserSlice<u8>, AtFlags) -> Result<(), SyscallError>);
+    struct Mkdirat(fn(RawFd, UserSlice<u8>) -> Result<(), SyscallError>);
+    struct Fchdir(fn(RawFd) -> Result<(), SyscallError>);
+    struct Umask(fn(u16) -> Result<u16, SyscallError>);
+    struct Access(fn(UserSlice<u8>, AccessMode) -> Result<(), SyscallError>);
+    struct Faccessat(fn(RawFd, UserSlice<u8>, AccessMode, AtFlags) -> Result<(), SyscallError>);
+    struct Ptrace(fn(PtraceRequest, ProcId, usize, usize) -> Result<usize, SyscallError>);
+    struct PtraceGetRegs(fn(ProcId, UserMutRef<PtraceRegs>) -> Result<(), SyscallError>);
+    struct PtraceSetRegs(fn(ProcId, UserRef<PtraceRegs>) -> Result<(), SyscallError>);
+    struct GetKernelSymbols(fn(UserMutSlice<u8>, usize) -> Result<usize, SyscallError>);
+    struct Getcpu(fn() -> usize);
+    struct Mount(fn(UserSlice<u8>, UserSlice<u8>, usize) -> Result<(), SyscallError>);
 }
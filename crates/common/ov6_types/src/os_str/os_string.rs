@@ -42,6 +42,15 @@ impl OsString {
         self.inner
     }
 
+    /// Converts to a `String` if the contents are valid UTF-8.
+    ///
+    /// On failure, ownership of the original `OsString` is returned.
+    pub fn into_string(self) -> Result<String, Self> {
+        String::from_utf8(self.inner).map_err(|e| Self {
+            inner: e.into_bytes(),
+        })
+    }
+
     #[must_use]
     pub fn as_os_str(&self) -> &OsStr {
         OsStr::from_inner(&self.inner)
@@ -232,13 +241,38 @@ impl IndexMut<RangeFull> for OsString {
 
 impl PartialEq<str> for OsString {
     fn eq(&self, other: &str) -> bool {
-        *self == *Self::from(other)
+        *self.as_os_str() == *other
+    }
+}
+
+impl PartialEq<&str> for OsString {
+    fn eq(&self, other: &&str) -> bool {
+        *self.as_os_str() == **other
+    }
+}
+
+impl PartialEq<OsString> for str {
+    fn eq(&self, other: &OsString) -> bool {
+        *other.as_os_str() == *self
+    }
+}
+
+impl PartialEq<OsString> for &str {
+    fn eq(&self, other: &OsString) -> bool {
+        *other.as_os_str() == **self
     }
 }
 
 impl PartialOrd<str> for OsString {
     fn partial_cmp(&self, other: &str) -> Option<cmp::Ordering> {
-        self.as_os_str().partial_cmp(Self::from(other).as_os_str())
+        self.as_os_str().partial_cmp(other)
+    }
+}
+
+impl fmt::Write for OsString {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push(s);
+        Ok(())
     }
 }
 
@@ -321,3 +355,45 @@ impl<'a> TryFrom<&'a OsStr> for &'a str {
         str::from_utf8(&value.inner)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use core::fmt::Write as _;
+
+    use super::*;
+
+    #[test]
+    fn test_push_and_eq() {
+        let mut s = OsString::from("foo");
+        s.push("/");
+        s.push(OsStr::from_bytes(b"bar"));
+        assert_eq!(s, "foo/bar");
+        assert_eq!("foo/bar", s);
+        assert_eq!(s, *"foo/bar");
+        assert_ne!(s, "foo");
+    }
+
+    #[test]
+    fn test_into_string() {
+        let s = OsString::from(String::from("hello"));
+        assert_eq!(s.into_string().unwrap(), "hello");
+
+        let s = OsString::from_vec(vec![b'a', 0xff]);
+        let s = s.into_string().unwrap_err();
+        assert_eq!(s.as_bytes(), b"a\xff");
+    }
+
+    #[test]
+    fn test_display_lossy() {
+        let s = OsString::from_vec(vec![b'a', 0xff, b'b']);
+        assert_eq!(s.to_string_lossy(), "a\u{fffd}b");
+        assert_eq!(format!("{}", s.display()), "a\u{fffd}b");
+    }
+
+    #[test]
+    fn test_write_fmt() {
+        let mut s = OsString::new();
+        write!(s, "{}-{}", 1, "two").unwrap();
+        assert_eq!(s, "1-two");
+    }
+}
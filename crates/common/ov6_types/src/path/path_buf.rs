@@ -2,6 +2,7 @@ use alloc::{
     borrow::{Cow, ToOwned},
     collections::TryReserveError,
     string::String,
+    vec::Vec,
 };
 use core::{
     borrow::Borrow,
@@ -12,7 +13,7 @@ use core::{
     str::FromStr,
 };
 
-use super::Path;
+use super::{Component, Path};
 use crate::os_str::{OsStr, OsString};
 
 #[derive(Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -56,7 +57,7 @@ impl PathBuf {
             self.inner.push(path.as_os_str());
             return;
         }
-        if !self.inner.as_bytes().ends_with(b"/") {
+        if !self.inner.is_empty() && !self.inner.as_bytes().ends_with(b"/") {
             self.inner.push("/");
         }
         self.inner.push(path);
@@ -314,6 +315,39 @@ impl Path {
         buf
     }
 
+    /// Resolves `.` and `..` components lexically, without accessing the
+    /// file system.
+    ///
+    /// `..` at the root directory refers to the root itself, as in the
+    /// kernel path walker. Leading `..` components of a relative path are
+    /// kept. An empty result is returned as `.`.
+    #[must_use]
+    pub fn normalize(&self) -> PathBuf {
+        let mut comps = Vec::new();
+        for comp in self.components() {
+            match comp {
+                Component::RootDir => {
+                    comps.clear();
+                    comps.push(comp);
+                }
+                Component::CurDir => {}
+                Component::ParentDir => match comps.last() {
+                    Some(Component::Normal(_)) => {
+                        comps.pop();
+                    }
+                    Some(Component::RootDir) => {}
+                    _ => comps.push(comp),
+                },
+                Component::Normal(_) => comps.push(comp),
+            }
+        }
+
+        if comps.is_empty() {
+            return PathBuf::from(".");
+        }
+        comps.into_iter().collect()
+    }
+
     #[must_use]
     pub fn with_file_name<S>(&self, file_name: S) -> PathBuf
     where
@@ -457,3 +491,29 @@ impl_cmp_os_str!(<'a> &'a Path, OsString);
 impl_cmp_os_str!(<'a> Cow<'a, Path>, OsStr);
 impl_cmp_os_str!(<'a, 'b> Cow<'a, Path>, &'b OsStr);
 impl_cmp_os_str!(<'a> Cow<'a, Path>, OsString);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_join() {
+        assert_eq!(Path::new("/home").join("user"), Path::new("/home/user"));
+        assert_eq!(Path::new("/home/").join("user"), Path::new("/home/user"));
+        assert_eq!(Path::new("home").join("/etc"), Path::new("/etc"));
+        assert_eq!(Path::new("").join("user"), Path::new("user"));
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(Path::new("/a/./b/../c").normalize(), Path::new("/a/c"));
+        assert_eq!(Path::new("/a/b/").normalize(), Path::new("/a/b"));
+        assert_eq!(Path::new("//a//b").normalize(), Path::new("/a/b"));
+        assert_eq!(Path::new("/..").normalize(), Path::new("/"));
+        assert_eq!(Path::new("/../a/..").normalize(), Path::new("/"));
+        assert_eq!(Path::new("a/..").normalize(), Path::new("."));
+        assert_eq!(Path::new("./a/../../b").normalize(), Path::new("../b"));
+        assert_eq!(Path::new("../..").normalize(), Path::new("../.."));
+        assert_eq!(Path::new("").normalize(), Path::new("."));
+    }
+}
@@ -1,5 +1,7 @@
 #![cfg_attr(not(test), no_std)]
 
+use core::fmt;
+
 #[must_use]
 #[expect(clippy::cast_possible_truncation)]
 pub const fn to_u8(n: usize) -> u8 {
@@ -28,6 +30,79 @@ pub const fn to_u64(n: usize) -> u64 {
     n as u64
 }
 
+#[must_use]
+pub const fn try_to_u8(n: usize) -> Option<u8> {
+    if n <= u8::MAX as usize {
+        Some(to_u8(n))
+    } else {
+        None
+    }
+}
+
+#[must_use]
+pub const fn try_to_u16(n: usize) -> Option<u16> {
+    if n <= u16::MAX as usize {
+        Some(to_u16(n))
+    } else {
+        None
+    }
+}
+
+#[must_use]
+pub const fn try_to_u32(n: usize) -> Option<u32> {
+    if n <= u32::MAX as usize {
+        Some(to_u32(n))
+    } else {
+        None
+    }
+}
+
+#[must_use]
+#[expect(clippy::cast_possible_truncation)]
+pub const fn try_to_u64(n: usize) -> Option<u64> {
+    if n <= u64::MAX as usize {
+        Some(to_u64(n))
+    } else {
+        None
+    }
+}
+
+/// Converts the constant `N` to `u8`.
+///
+/// The conversion is checked at compile time, even when called from a
+/// non-const function.
+#[must_use]
+pub const fn const_u8<const N: usize>() -> u8 {
+    const { to_u8(N) }
+}
+
+/// Converts the constant `N` to `u16`.
+///
+/// The conversion is checked at compile time, even when called from a
+/// non-const function.
+#[must_use]
+pub const fn const_u16<const N: usize>() -> u16 {
+    const { to_u16(N) }
+}
+
+/// Converts the constant `N` to `u32`.
+///
+/// The conversion is checked at compile time, even when called from a
+/// non-const function.
+#[must_use]
+pub const fn const_u32<const N: usize>() -> u32 {
+    const { to_u32(N) }
+}
+
+/// Converts the constant `N` to `u64`.
+///
+/// The conversion is checked at compile time, even when called from a
+/// non-const function.
+#[must_use]
+pub const fn const_u64<const N: usize>() -> u64 {
+    const { to_u64(N) }
+}
+
 #[macro_export]
 macro_rules! to_u8 {
     ($n:expr) => {
@@ -139,6 +214,113 @@ where
     }
 }
 
+/// Error returned when a value does not fit in the target type of a narrowing
+/// conversion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NarrowError<T> {
+    value: T,
+    target: &'static str,
+}
+
+impl<T> NarrowError<T> {
+    /// Returns the value that failed to be converted.
+    pub fn into_value(self) -> T {
+        self.value
+    }
+
+    /// Returns the name of the target type of the conversion.
+    #[must_use]
+    pub fn target(&self) -> &'static str {
+        self.target
+    }
+}
+
+impl<T> fmt::Display for NarrowError<T>
+where
+    T: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} does not fit in {}", self.value, self.target)
+    }
+}
+
+impl<T> core::error::Error for NarrowError<T> where T: fmt::Debug + fmt::Display {}
+
+/// Checked conversion to a type that may not hold all the values of `T`.
+pub trait TryNarrow<T>: Sized {
+    /// Converts `value`, or returns an error holding it if it does not fit.
+    fn try_narrow(value: T) -> Result<Self, NarrowError<T>>;
+
+    /// Converts `value`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `value` does not fit in `Self`.
+    #[track_caller]
+    fn narrow(value: T) -> Self
+    where
+        T: fmt::Debug + fmt::Display,
+    {
+        match Self::try_narrow(value) {
+            Ok(value) => value,
+            Err(e) => panic!("{e}"),
+        }
+    }
+}
+
+/// Checked conversion into `T`, the counterpart of [`TryNarrow`].
+pub trait NarrowInto<T>: Sized {
+    fn try_narrow_into(self) -> Result<T, NarrowError<Self>>;
+
+    #[track_caller]
+    fn narrow_into(self) -> T
+    where
+        Self: fmt::Debug + fmt::Display;
+}
+
+impl<T, U> NarrowInto<U> for T
+where
+    U: TryNarrow<T>,
+{
+    fn try_narrow_into(self) -> Result<U, NarrowError<Self>> {
+        U::try_narrow(self)
+    }
+
+    #[track_caller]
+    fn narrow_into(self) -> U
+    where
+        Self: fmt::Debug + fmt::Display,
+    {
+        U::narrow(self)
+    }
+}
+
+macro_rules! impl_try_narrow {
+    ($($from:ty => $($to:ty),+;)*) => {
+        $($(
+            impl TryNarrow<$from> for $to {
+                fn try_narrow(value: $from) -> Result<Self, NarrowError<$from>> {
+                    <$to>::try_from(value).map_err(|_| NarrowError {
+                        value,
+                        target: stringify!($to),
+                    })
+                }
+            }
+        )+)*
+    };
+}
+
+impl_try_narrow! {
+    u16 => u8;
+    u32 => u8, u16;
+    u64 => u8, u16, u32, usize;
+    usize => u8, u16, u32, u64;
+    i16 => i8;
+    i32 => i8, i16;
+    i64 => i8, i16, i32, isize;
+    isize => i8, i16, i32, i64;
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -178,4 +360,40 @@ mod tests {
             assert_eq!(result, n.try_into().unwrap());
         }
     }
+
+    #[test]
+    fn test_try_narrow() {
+        assert_eq!(u8::try_narrow(255_u32), Ok(255));
+        assert_eq!(u16::try_narrow(0_usize), Ok(0));
+        assert_eq!(u32::try_narrow(u64::from(u32::MAX)), Ok(u32::MAX));
+        assert_eq!(i8::try_narrow(-128_i64), Ok(-128));
+
+        let err = u8::try_narrow(256_u32).unwrap_err();
+        assert_eq!(err.target(), "u8");
+        assert_eq!(err.into_value(), 256);
+        let err = i16::try_narrow(-40000_i32).unwrap_err();
+        assert_eq!(err.to_string(), "-40000 does not fit in i16");
+    }
+
+    #[test]
+    fn test_narrow_into() {
+        let n: u16 = 1000_usize.narrow_into();
+        assert_eq!(n, 1000);
+        let r: Result<u16, _> = 70000_usize.try_narrow_into();
+        assert_eq!(r.unwrap_err().into_value(), 70000);
+    }
+
+    #[test]
+    #[should_panic(expected = "70000 does not fit in u16")]
+    fn test_narrow_panics() {
+        let _: u16 = 70000_usize.narrow_into();
+    }
+
+    #[test]
+    fn test_try_to() {
+        assert_eq!(try_to_u8(255), Some(255));
+        assert_eq!(try_to_u8(256), None);
+        assert_eq!(try_to_u16(65536), None);
+        assert_eq!(const_u32::<42>(), 42);
+    }
 }
@@ -33,6 +33,17 @@ pub trait BlockDevice<const BLOCK_SIZE: usize> {
     /// Returns `Ok(())` if the write operation is successful, or an error of
     /// type `Self::Error` if it fails.
     fn write(&self, block_index: usize, data: &[u8; BLOCK_SIZE]) -> Result<(), Self::Error>;
+
+    /// Waits until the blocks written so far are stored on the device.
+    ///
+    /// Writes issued between two flushes may reach the device in any order,
+    /// but all of them reach it before the writes issued after the flush.
+    ///
+    /// The default implementation does nothing, which is correct for devices
+    /// that complete each write before returning.
+    fn flush(&self) -> Result<(), Self::Error> {
+        Ok(())
+    }
 }
 
 /// A LRU (Least Recently Used) cache for block I/O.
@@ -189,6 +200,16 @@ where
         };
         buf
     }
+
+    /// Waits until the blocks written so far are stored on the device.
+    ///
+    /// See [`BlockDevice::flush()`].
+    pub fn flush(&self) -> Result<(), Device::Error>
+    where
+        Device: BlockDevice<BLOCK_SIZE>,
+    {
+        self.device.flush()
+    }
 }
 
 impl<'list, Device, LruMutex, BlockMutex, const BLOCK_SIZE: usize, A>
@@ -325,6 +346,26 @@ where
         self.data.dirty
     }
 
+    /// Returns `true` if the block cache holds the data of the block.
+    pub fn is_valid(&self) -> bool {
+        self.data.valid
+    }
+
+    /// Reads the block from disk into `data` without caching it.
+    pub fn read_uncached(&self, data: &mut [u8; BLOCK_SIZE]) -> Result<(), Device::Error> {
+        self.device.read(self.index, data)
+    }
+
+    /// Writes `data` to the block on disk without caching it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if cached data is valid, since it would become stale.
+    pub fn write_uncached(&self, data: &[u8; BLOCK_SIZE]) -> Result<(), Device::Error> {
+        assert!(!self.data.valid);
+        self.device.write(self.index, data)
+    }
+
     pub fn try_validate(
         self,
     ) -> Result<BlockGuard<'list, 'block, Device, LruMutex, BlockMutex, BLOCK_SIZE, true, A>, Self>
@@ -402,6 +443,7 @@ mod tests {
     #[derive(Clone)]
     struct MockDevice {
         data: Vec<Arc<Mutex<MockData>>>,
+        flush: Arc<Mutex<usize>>,
     }
 
     struct MockData {
@@ -436,6 +478,7 @@ mod tests {
                 })
                 .take(size)
                 .collect(),
+                flush: Arc::default(),
             }
         }
     }
@@ -456,6 +499,11 @@ mod tests {
             mock.data.copy_from_slice(data);
             Ok(())
         }
+
+        fn flush(&self) -> Result<(), Self::Error> {
+            *self.flush.lock().unwrap() += 1;
+            Ok(())
+        }
     }
 
     #[test]
@@ -501,6 +549,99 @@ mod tests {
         assert_eq!(device.data[0].lock().unwrap().write, 1);
     }
 
+    #[test]
+    fn test_block_io_cache_uncached() {
+        let device = MockDevice::new(10);
+        let cache = BlockIoCache::new(device.clone(), 5);
+
+        {
+            let mut block = cache.get(0);
+            let block = block.lock();
+            assert!(!block.is_valid());
+            block.write_uncached(&[2; 512]).unwrap();
+            let mut data = [0; 512];
+            block.read_uncached(&mut data).unwrap();
+            assert_eq!(data, [2; 512]);
+            // uncached transfers do not fill the cache.
+            assert!(!block.is_valid());
+        }
+
+        {
+            let mut block = cache.get(0);
+            let Ok(block) = block.lock().read();
+            assert!(block.is_valid());
+            assert_eq!(block.bytes(), &[2; 512]);
+        }
+
+        assert_eq!(device.data[0].lock().unwrap().read, 2);
+        assert_eq!(device.data[0].lock().unwrap().write, 1);
+    }
+
+    #[test]
+    fn test_block_io_cache_dirty() {
+        let device = MockDevice::new(10);
+        let cache = BlockIoCache::new(device.clone(), 5);
+
+        let mut block = cache.get(0);
+        let Ok(mut guard) = block.lock().read();
+        assert!(!guard.is_dirty());
+        guard.bytes_mut()[0] = 1;
+        assert!(guard.is_dirty());
+        guard.write().unwrap();
+        assert!(!guard.is_dirty());
+        drop(guard);
+
+        // overwriting the whole block does not read it from the device.
+        let mut block = cache.get(1);
+        let guard = block.lock().set_data(&[3; 512]);
+        assert!(guard.is_dirty());
+        drop(guard);
+        let mut block = cache.get(2);
+        let guard = block.lock().zeroed();
+        assert!(guard.is_dirty());
+        drop(guard);
+
+        assert_eq!(device.data[0].lock().unwrap().read, 1);
+        assert_eq!(device.data[1].lock().unwrap().read, 0);
+        assert_eq!(device.data[2].lock().unwrap().read, 0);
+        // dirty blocks are not written back until requested.
+        assert_eq!(device.data[1].lock().unwrap().write, 0);
+        assert_eq!(device.data[1].lock().unwrap().data, [0; 512]);
+    }
+
+    #[test]
+    fn test_block_io_cache_recycle() {
+        let device = MockDevice::new(10);
+        let cache = BlockIoCache::new(device.clone(), 1);
+
+        {
+            let mut block = cache.get(0);
+            let block = block.lock().set_data(&[1; 512]);
+            assert!(block.is_valid());
+        }
+
+        // the buffer of block 0 is reused for block 1, whose data is read
+        // from the device.
+        {
+            let mut block = cache.get(1);
+            let block = block.lock();
+            assert!(!block.is_valid());
+            let Ok(block) = block.read();
+            assert_eq!(block.bytes(), &[0; 512]);
+        }
+        assert_eq!(device.data[1].lock().unwrap().read, 1);
+    }
+
+    #[test]
+    fn test_block_io_cache_flush() {
+        let device = MockDevice::new(10);
+        let cache = BlockIoCache::new(device.clone(), 5);
+
+        cache.flush().unwrap();
+        cache.flush().unwrap();
+        assert_eq!(*device.flush.lock().unwrap(), 2);
+    }
+
     #[test]
     fn test_block_io_cache_exhaustion() {
         let device = MockDevice::new(10);
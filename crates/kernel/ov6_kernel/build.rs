@@ -5,4 +5,10 @@ fn main() {
     let linker_script = manifest_dir.join("kernel.ld");
     println!("cargo:rerun-if-changed={}", linker_script.display());
     println!("cargo::rustc-link-arg=-T{}", linker_script.display());
+    // link as a position-independent executable, with the relocations applied
+    // for the link-time address as well, so that the kernel runs at its load
+    // address as is and can move itself to a random one (KASLR).
+    println!("cargo::rustc-link-arg=--pie");
+    println!("cargo::rustc-link-arg=--no-dynamic-linker");
+    println!("cargo::rustc-link-arg=--apply-dynamic-relocs");
 }
@@ -0,0 +1,66 @@
+//! Audit log of operations denied by the kernel.
+//!
+//! Records are kept in a fixed-size ring; the oldest record is overwritten
+//! once the ring is full.
+
+use ov6_kernel_params::NAUDIT;
+use ov6_syscall::{AUDIT_PATH_MAX, AuditRecord, SyscallCode, error::SyscallError};
+use ov6_types::{path::Path, process::ProcId};
+use safe_cast::{SafeInto as _, to_u32};
+
+use crate::sync::SpinLock;
+
+static AUDIT_LOG: SpinLock<AuditLog> = SpinLock::new(AuditLog::new());
+
+struct AuditLog {
+    records: [AuditRecord; NAUDIT],
+    next_seq: u64,
+}
+
+impl AuditLog {
+    const fn new() -> Self {
+        Self {
+            records: [const { AuditRecord::zeroed() }; NAUDIT],
+            next_seq: 1,
+        }
+    }
+
+    fn slot(seq: u64) -> usize {
+        let n: u64 = NAUDIT.safe_into();
+        ((seq - 1) % n).safe_into()
+    }
+
+    fn push(&mut self, mut record: AuditRecord) {
+        record.seq = self.next_seq;
+        self.records[Self::slot(record.seq)] = record;
+        self.next_seq += 1;
+    }
+
+    fn find(&self, seq: u64) -> Option<AuditRecord> {
+        let n: u64 = NAUDIT.safe_into();
+        let oldest = u64::max(self.next_seq.saturating_sub(n), 1);
+        let seq = u64::max(seq, oldest);
+        (seq < self.next_seq).then(|| self.records[Self::slot(seq)])
+    }
+}
+
+/// Appends a record of a denied system call to the audit log.
+pub fn record(pid: ProcId, syscall: SyscallCode, error: SyscallError, path: Option<&Path>) {
+    let mut record = AuditRecord::zeroed();
+    record.pid = pid.into();
+    record.syscall = to_u32(syscall as usize);
+    record.errno = to_u32(error as usize);
+    if let Some(path) = path {
+        let path = path.as_os_str().as_bytes();
+        let len = usize::min(path.len(), AUDIT_PATH_MAX);
+        record.path[..len].copy_from_slice(&path[..len]);
+        record.path_len = to_u32(len);
+    }
+    AUDIT_LOG.lock().push(record);
+}
+
+/// Returns the oldest record still in the log whose sequence number is `seq`
+/// or later.
+pub fn find(seq: u64) -> Option<AuditRecord> {
+    AUDIT_LOG.lock().find(seq)
+}
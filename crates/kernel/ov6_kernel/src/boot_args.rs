@@ -0,0 +1,160 @@
+//! Kernel boot options.
+//!
+//! The options are read from the `bootargs` property of the `/chosen` node of
+//! the device tree, whose address the boot loader passes in `a1`. With QEMU,
+//! they are given by `-append` (the `QEMU_APPEND` make variable). Options are
+//! words separated by spaces.
+//!
+//! The `rng-seed` property of the same node, random bytes that QEMU fills in
+//! at every boot, is kept as well as a source of entropy.
+//!
+//! The device tree lies in memory that the page allocator takes over, so the
+//! options are copied out before it is initialized.
+
+use core::{
+    ptr, slice, str,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use arrayvec::ArrayVec;
+use once_init::OnceInit;
+use safe_cast::SafeFrom as _;
+
+use crate::println;
+
+/// Maximum length of the boot options.
+const MAX_BOOT_ARGS: usize = 256;
+
+/// Maximum length of the random seed kept.
+const MAX_RNG_SEED: usize = 64;
+
+const FDT_MAGIC: u32 = 0xd00d_feed;
+const FDT_HEADER_SIZE: usize = 40;
+const FDT_BEGIN_NODE: u32 = 1;
+const FDT_END_NODE: u32 = 2;
+const FDT_PROP: u32 = 3;
+const FDT_NOP: u32 = 4;
+
+/// Physical address of the device tree, saved by the boot code.
+static DTB_ADDR: AtomicUsize = AtomicUsize::new(0);
+
+static BOOT_ARGS: OnceInit<ArrayVec<u8, MAX_BOOT_ARGS>> = OnceInit::new();
+static RNG_SEED: OnceInit<ArrayVec<u8, MAX_RNG_SEED>> = OnceInit::new();
+
+/// Records the address of the device tree passed by the boot loader.
+///
+/// Called in machine mode by each hart.
+pub fn set_dtb_addr(addr: usize) {
+    DTB_ADDR.store(addr, Ordering::Relaxed);
+}
+
+/// Reads the boot options from the device tree.
+///
+/// Must be called while the memory holding the device tree is still
+/// untouched, with paging disabled.
+pub fn init() {
+    let addr = DTB_ADDR.load(Ordering::Relaxed);
+    let mut args = ArrayVec::new();
+    let mut seed = ArrayVec::new();
+    if addr != 0 {
+        let bootargs = unsafe { find_chosen_prop(addr, b"bootargs") }.unwrap_or_default();
+        let bootargs = bootargs.strip_suffix(&[0]).unwrap_or(bootargs);
+        if args.try_extend_from_slice(bootargs).is_err() {
+            println!("boot options too long, ignored");
+        }
+        let rng_seed = unsafe { find_chosen_prop(addr, b"rng-seed") }.unwrap_or_default();
+        seed.extend(rng_seed.iter().copied().take(MAX_RNG_SEED));
+    }
+    if !args.is_empty() {
+        println!(
+            "boot options: {}",
+            str::from_utf8(&args).unwrap_or("(invalid)")
+        );
+    }
+    BOOT_ARGS.init(args);
+    RNG_SEED.init(seed);
+}
+
+/// Returns `true` if the option `name` is given.
+pub fn flag(name: &str) -> bool {
+    BOOT_ARGS
+        .get()
+        .split(|&b| b == b' ')
+        .any(|arg| arg == name.as_bytes())
+}
+
+/// Returns the random seed given by the boot loader.
+///
+/// Empty if the boot loader gives none.
+pub fn rng_seed() -> &'static [u8] {
+    RNG_SEED.get()
+}
+
+fn read_u32(fdt: &[u8], pos: usize) -> Option<u32> {
+    let bytes = fdt.get(pos..pos.checked_add(4)?)?;
+    Some(u32::from_be_bytes(bytes.try_into().unwrap()))
+}
+
+fn read_offset(fdt: &[u8], pos: usize) -> Option<usize> {
+    read_u32(fdt, pos).map(usize::safe_from)
+}
+
+/// Returns the NUL-terminated string at `pos`, without the NUL.
+fn read_str(fdt: &[u8], pos: usize) -> Option<&[u8]> {
+    let s = fdt.get(pos..)?;
+    let len = s.iter().position(|&b| b == 0)?;
+    Some(&s[..len])
+}
+
+/// Returns the value of the property `prop` of `/chosen` in the flattened
+/// device tree at `addr`.
+///
+/// # Safety
+///
+/// `addr` must point to a readable device tree.
+unsafe fn find_chosen_prop(addr: usize, prop: &[u8]) -> Option<&'static [u8]> {
+    let base = ptr::with_exposed_provenance::<u8>(addr);
+    let header = unsafe { slice::from_raw_parts(base, FDT_HEADER_SIZE) };
+    if read_u32(header, 0)? != FDT_MAGIC {
+        return None;
+    }
+    let total_size = read_offset(header, 4)?;
+    let fdt = unsafe { slice::from_raw_parts(base, total_size) };
+    let strings = read_offset(fdt, 12)?;
+
+    let mut pos = read_offset(fdt, 8)?;
+    let mut depth = 0_usize;
+    let mut in_chosen = false;
+    loop {
+        let token = read_u32(fdt, pos)?;
+        pos += 4;
+        match token {
+            FDT_BEGIN_NODE => {
+                let name = read_str(fdt, pos)?;
+                pos = (pos + name.len() + 1).next_multiple_of(4);
+                depth += 1;
+                // the root node is at depth 1
+                if depth == 2 {
+                    in_chosen = name == b"chosen";
+                }
+            }
+            FDT_END_NODE => depth = depth.checked_sub(1)?,
+            FDT_PROP => {
+                let len = read_offset(fdt, pos)?;
+                let name_offset = read_offset(fdt, pos + 4)?;
+                pos += 8;
+                let value = fdt.get(pos..pos.checked_add(len)?)?;
+                pos = (pos + len).next_multiple_of(4);
+                if in_chosen
+                    && depth == 2
+                    && read_str(fdt, strings.checked_add(name_offset)?)? == prop
+                {
+                    return Some(value);
+                }
+            }
+            FDT_NOP => {}
+            // FDT_END or a broken tree
+            _ => return None,
+        }
+    }
+}
@@ -4,6 +4,9 @@
 //! UART interface. It supports line-based input and special control characters
 //! for editing and process management.
 //!
+//! In raw mode (see [`TerminalMode::Raw`]), input characters are passed to
+//! `read()` as is and none of the following characters are special.
+//!
 //! Special input characters:
 //!
 //! * `newline` (`\n`) -- end of line
@@ -11,6 +14,14 @@
 //! * `control-u` (`CTRL_U`) -- kill line
 //! * `control-d` (`CTRL_D`) -- end of file
 //! * `control-p` (`CTRL_P`) -- print process list
+//! * `control-c` (`CTRL_C`) -- discard the line and interrupt the foreground
+//!   process group
+
+use core::num::NonZero;
+
+use ov6_syscall::{IoctlRequest, TerminalMode, WindowSize};
+use ov6_types::process::ProcId;
+use safe_cast::SafeInto as _;
 
 use crate::{
     error::KernelError,
@@ -42,6 +53,7 @@ const CTRL_H: u8 = ctrl(b'H');
 const CTRL_U: u8 = ctrl(b'U');
 const CTRL_D: u8 = ctrl(b'D');
 const CTRL_P: u8 = ctrl(b'P');
+const CTRL_C: u8 = ctrl(b'C');
 
 /// Send one character to the UART.
 ///
@@ -76,6 +88,10 @@ struct Cons {
     w: usize,
     /// Edit index.
     e: usize,
+    /// Input mode.
+    mode: TerminalMode,
+    /// Process group that receives interrupts.
+    foreground: Option<ProcId>,
 }
 
 static CONSOLE_BUFFER: SpinLock<Cons> = SpinLock::new(Cons {
@@ -83,6 +99,8 @@ static CONSOLE_BUFFER: SpinLock<Cons> = SpinLock::new(Cons {
     r: 0,
     w: 0,
     e: 0,
+    mode: TerminalMode::Cooked,
+    foreground: None,
 });
 static CONSOLE_BUFFER_WRITTEN: SpinLockCondVar = SpinLockCondVar::new();
 
@@ -91,7 +109,7 @@ static CONSOLE_BUFFER_WRITTEN: SpinLockCondVar = SpinLockCondVar::new();
 /// This function handles user `write()` calls to the console. It ensures that
 /// only one process can write to the console at a time, preventing interleaved
 /// or corrupted output.
-fn write(src: &GenericSlice<u8>) -> Result<usize, KernelError> {
+fn write(src: &GenericSlice<u8>, _off: usize) -> Result<usize, KernelError> {
     static CONSOLE_WRITE_LOCK: SleepLock<()> = SleepLock::new(());
 
     // ensure that only one process can write to the console at a time,
@@ -117,13 +135,21 @@ fn write(src: &GenericSlice<u8>) -> Result<usize, KernelError> {
 /// This function handles user `read()` calls to the console. It copies up to a
 /// whole input line to the provided buffer. The `user_dst` parameter indicates
 /// whether the destination is a user or kernel address.
-fn read(dst: &mut GenericMutSlice<u8>) -> Result<usize, KernelError> {
+///
+/// Waiting for input is interrupted when the caller catches an interrupt.
+fn read(dst: &mut GenericMutSlice<u8>, _off: usize) -> Result<usize, KernelError> {
     let mut i = 0;
     let mut cons = CONSOLE_BUFFER.lock();
     while i < dst.len() {
         // wait until interrupt handler has put some
         // input into cons.buffer.
         while cons.r == cons.w {
+            if proc::ProcShared::current().lock().interrupt_pending() {
+                if i > 0 {
+                    return Ok(i);
+                }
+                return Err(KernelError::Interrupted);
+            }
             match CONSOLE_BUFFER_WRITTEN.wait(cons) {
                 Ok(guard) => cons = guard,
                 Err((_guard, WaitError::WaitingProcessAlreadyKilled)) => {
@@ -135,6 +161,16 @@ fn read(dst: &mut GenericMutSlice<u8>) -> Result<usize, KernelError> {
         let c = cons.buf[cons.r % cons.buf.len()];
         cons.r += 1;
 
+        if cons.mode == TerminalMode::Raw {
+            UserPageTable::copy_k2x_bytes(&mut dst.skip_mut(i).take_mut(1), &[c]);
+            i += 1;
+            // return whatever has arrived without waiting for more input.
+            if cons.r == cons.w {
+                break;
+            }
+            continue;
+        }
+
         // end-of-file
         if c == CTRL_D {
             if i == 0 {
@@ -164,6 +200,50 @@ fn read(dst: &mut GenericMutSlice<u8>) -> Result<usize, KernelError> {
     Ok(i)
 }
 
+/// Window size reported by the console.
+///
+/// The UART cannot detect the size of the terminal, so this is only a hint set
+/// by user programs (e.g. from the shell startup script).
+static WINDOW_SIZE: SpinLock<WindowSize> = SpinLock::new(WindowSize { rows: 24, cols: 80 });
+
+/// Handles user `ioctl()` calls to the console.
+fn ioctl(req: IoctlRequest, arg: usize) -> Result<usize, KernelError> {
+    match req {
+        IoctlRequest::GetWindowSize => Ok(WINDOW_SIZE.lock().to_raw()),
+        IoctlRequest::SetWindowSize => {
+            let size = WindowSize::from_raw(arg).ok_or(KernelError::InvalidIoctlArgument(arg))?;
+            *WINDOW_SIZE.lock() = size;
+            Ok(0)
+        }
+        IoctlRequest::GetMode => Ok(CONSOLE_BUFFER.lock().mode as usize),
+        IoctlRequest::SetMode => {
+            let mode =
+                TerminalMode::from_repr(arg).ok_or(KernelError::InvalidIoctlArgument(arg))?;
+            let mut cons = CONSOLE_BUFFER.lock();
+            // make the partially edited line available to `read()`.
+            if cons.w != cons.e {
+                cons.w = cons.e;
+                CONSOLE_BUFFER_WRITTEN.notify();
+            }
+            cons.mode = mode;
+            Ok(0)
+        }
+        IoctlRequest::GetForeground => Ok(CONSOLE_BUFFER
+            .lock()
+            .foreground
+            .map_or(0, |pgid| pgid.get().get().safe_into())),
+        IoctlRequest::SetForeground => {
+            let pgid = u32::try_from(arg)
+                .ok()
+                .and_then(NonZero::new)
+                .map(ProcId::new)
+                .ok_or(KernelError::InvalidIoctlArgument(arg))?;
+            CONSOLE_BUFFER.lock().foreground = Some(pgid);
+            Ok(0)
+        }
+    }
+}
+
 /// Handles console input interrupts.
 ///
 /// This function is called by `uart::handle_interrupts()` for input characters.
@@ -173,9 +253,33 @@ fn read(dst: &mut GenericMutSlice<u8>) -> Result<usize, KernelError> {
 pub fn handle_interrupt(c: u8) {
     let mut cons = CONSOLE_BUFFER.lock();
 
+    if cons.mode == TerminalMode::Raw {
+        if cons.e - cons.r < cons.buf.len() {
+            let idx = cons.e % cons.buf.len();
+            cons.buf[idx] = c;
+            cons.e += 1;
+            cons.w = cons.e;
+            CONSOLE_BUFFER_WRITTEN.notify();
+        }
+        return;
+    }
+
     match c {
         // Prints process list.
         CTRL_P => proc::ops::dump(),
+        // Interrupts the foreground process group.
+        CTRL_C => {
+            for c in "^C\n".chars() {
+                put_char(c);
+            }
+            // discards the line being edited.
+            cons.e = cons.w;
+            if let Some(pgid) = cons.foreground {
+                proc::ops::interrupt_group(pgid);
+            }
+            // wake up `read()` to return the interruption.
+            CONSOLE_BUFFER_WRITTEN.notify();
+        }
         // Kills line.
         CTRL_U => {
             while cons.e != cons.w && cons.buf[(cons.e - 1) % cons.buf.len()] != b'\n' {
@@ -215,10 +319,25 @@ pub fn handle_interrupt(c: u8) {
 
 /// Initializes the console subsystem.
 ///
-/// This function initializes the UART and registers the console as a device
-/// for reading and writing.
+/// This function initializes the UART so that the kernel can print while it
+/// boots.
 pub fn init() {
     uart::init();
+}
 
-    file::register_device(DeviceNo::CONSOLE, Device { read, write });
+/// Registers the console as a device for reading, writing, and device
+/// control.
+///
+/// Called after paging is enabled, so that the registered functions are
+/// called at their virtual addresses.
+pub fn init_device() {
+    file::register_device(
+        DeviceNo::CONSOLE,
+        Device {
+            read,
+            write,
+            ioctl: Some(ioctl),
+            block: false,
+        },
+    );
 }
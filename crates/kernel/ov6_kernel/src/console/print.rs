@@ -9,6 +9,7 @@ use core::{
 use crate::{
     console,
     device::test,
+    kallsyms::Symbolized,
     sync::{SpinLock, SpinLockGuard},
 };
 
@@ -95,7 +96,7 @@ fn print_backtrace() {
     while !fp.is_null() {
         let ra = unsafe { *fp.sub(1) };
         if !ra.is_null() {
-            println!("{ra:#p}");
+            println!("{}", Symbolized(ra.addr()));
         }
         let prev_fp = unsafe { *fp.sub(2) };
         fp = prev_fp.cast();
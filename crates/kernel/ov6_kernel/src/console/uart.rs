@@ -10,8 +10,8 @@ use super::print::PANICKED;
 use crate::{
     console,
     error::KernelError,
-    interrupt,
-    memory::layout::UART0,
+    interrupt::{self, plic},
+    memory::layout::{UART0, UART0_IRQ},
     sync::{SpinLock, SpinLockCondVar},
 };
 
@@ -162,6 +162,8 @@ pub fn init() {
         // enable transmit and receive interrupts.
         write_reg(IER, IER_TX_ENABLE | IER_RX_ENABLE);
     }
+
+    plic::register(UART0_IRQ, handle_interrupt);
 }
 
 /// Adds a character to the output buffer and starts sending if the UART is
@@ -1,7 +1,7 @@
 use core::{
     arch::asm,
     ptr::NonNull,
-    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
 };
 
 use ov6_types::process::ProcId;
@@ -17,6 +17,10 @@ pub struct Cpu {
     proc: SpinLock<Option<(ProcId, NonNull<Proc>)>>,
     /// `true` if this CPU is idle.
     idle: AtomicBool,
+    /// Number of timer ticks this CPU spent running a process.
+    busy_ticks: AtomicU64,
+    /// Number of timer ticks this CPU spent idle.
+    idle_ticks: AtomicU64,
 }
 
 unsafe impl Sync for Cpu {}
@@ -58,11 +62,24 @@ pub fn is_idle(id: usize) -> bool {
     CPUS[id].idle.load(Ordering::Relaxed)
 }
 
+/// Returns the numbers of timer ticks the CPU `id` spent running a process
+/// and idle.
+pub fn ticks(id: usize) -> (u64, u64) {
+    assert!(id < NCPU);
+    let cpu = &CPUS[id];
+    (
+        cpu.busy_ticks.load(Ordering::Relaxed),
+        cpu.idle_ticks.load(Ordering::Relaxed),
+    )
+}
+
 impl Cpu {
     const fn new() -> Self {
         Self {
             proc: SpinLock::new(None),
             idle: AtomicBool::new(false),
+            busy_ticks: AtomicU64::new(0),
+            idle_ticks: AtomicU64::new(0),
         }
     }
 
@@ -80,6 +97,16 @@ impl Cpu {
         self.idle.store(idle, Ordering::Relaxed);
     }
 
+    /// Counts a timer tick as busy if a process is running on this CPU.
+    pub fn account_tick(&self) {
+        let ticks = if self.proc().is_some() {
+            &self.busy_ticks
+        } else {
+            &self.idle_ticks
+        };
+        ticks.fetch_add(1, Ordering::Relaxed);
+    }
+
     pub fn set_proc(&self, p: Option<(ProcId, &Proc)>) {
         assert!(!interrupt::is_enabled());
 
@@ -1,13 +1,22 @@
 use alloc::boxed::Box;
-use core::{array, pin::Pin, ptr};
+use core::{
+    array,
+    pin::Pin,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
 use bitflags::{Flags as _, bitflags};
 use dataview::{Pod, PodMethods as _};
 use once_init::OnceInit;
+use ov6_syscall::NetInfo;
 use safe_cast::{SafeFrom as _, SafeInto as _};
 
 use crate::{
-    memory::{PAGE_SIZE, page::PageFrameAllocator},
+    interrupt::{
+        deferred::{self, Work},
+        plic,
+    },
+    memory::{PAGE_SIZE, PhysAddr, layout::E1000_IRQ, page::PageFrameAllocator},
     net,
     sync::{SpinLock, SpinLockGuard},
 };
@@ -30,12 +39,14 @@ pub(crate) unsafe fn init(regs: *mut u32) {
     DRIVER.init(SpinLock::new(Driver {
         registers: regs,
         rx_ring: RxRing(array::from_fn(|i| RxDesc {
-            addr: rx_bufs[i].as_ref().unwrap().as_ptr().addr().safe_into(),
+            addr: PhysAddr::from(rx_bufs[i].as_ref().unwrap().as_ptr())
+                .addr()
+                .safe_into(),
             ..RxDesc::zeroed()
         })),
         rx_bufs,
         tx_ring: TxRing(array::from_fn(|i| TxDesc {
-            addr: tx_bufs[i].as_ptr().addr().safe_into(),
+            addr: PhysAddr::from(tx_bufs[i].as_ptr()).addr().safe_into(),
             status: TxdStat::Dd,
             ..TxDesc::zeroed()
         })),
@@ -55,7 +66,7 @@ pub(crate) unsafe fn init(regs: *mut u32) {
 
     // [E1000 14.5] Transmit initialization
     unsafe {
-        let tx_ring_addr = ptr::from_ref(&driver.tx_ring).addr();
+        let tx_ring_addr = PhysAddr::from(&driver.tx_ring).addr();
         let tx_ring_size = size_of_val(&driver.tx_ring);
         assert!(tx_ring_addr.trailing_zeros() >= 4);
         driver.write_reg(TdbaL, (tx_ring_addr & 0xffff_ffff).try_into().unwrap());
@@ -68,7 +79,7 @@ pub(crate) unsafe fn init(regs: *mut u32) {
 
     // [E1000 14.4] Receive initialization
     unsafe {
-        let rx_ring_addr = ptr::from_ref(&driver.rx_ring).addr();
+        let rx_ring_addr = PhysAddr::from(&driver.rx_ring).addr();
         let rx_ring_size = size_of_val(&driver.rx_ring);
         assert!(rx_ring_addr.trailing_zeros() >= 4);
         driver.write_reg(RdbaL, (rx_ring_addr & 0xffff_ffff).try_into().unwrap());
@@ -134,16 +145,83 @@ pub(crate) unsafe fn init(regs: *mut u32) {
         // interrupt after every packet (no timer)
         driver.write_reg(Radv, 0);
         // RXDW -- Receiver Descriptor Write Back
-        driver.write_reg(Ims, 1 << 7);
+        driver.write_reg(Ims, RX_INTERRUPTS);
+    }
+
+    deferred::register(Work::NetReceive, receive_packets);
+    plic::register(E1000_IRQ, handle_interrupt);
+}
+
+/// Resets the device, stopping transmission and reception.
+///
+/// Does nothing if the device is not present.
+pub fn reset() {
+    let Ok(driver) = DRIVER.try_get() else {
+        return;
+    };
+    let mut driver = driver.lock();
+    unsafe {
+        // disable interrupts
+        driver.write_reg(Register::Ims, 0);
+        driver.set_reg_flag(Register::Ctl, CtlBits::RST.bits());
     }
 }
 
+/// Receive interrupts requested from the device.
+const RX_INTERRUPTS: u32 = 1 << 7;
+
+/// Maximum number of packets passed to the network stack by one run of
+/// [`receive_packets()`].
+const RX_BUDGET: usize = 8;
+
+static RX_PACKETS: AtomicUsize = AtomicUsize::new(0);
+static RX_POLLS: AtomicUsize = AtomicUsize::new(0);
+static RX_MISSED: AtomicUsize = AtomicUsize::new(0);
+
 pub fn handle_interrupt() {
     let mut driver = DRIVER.get().lock();
     unsafe {
+        // no more receive interrupts until the RX ring is drained.
+        driver.write_reg(Register::Imc, RX_INTERRUPTS);
         driver.write_reg(Register::Icr, u32::MAX);
     }
-    receive(driver);
+    drop(driver);
+
+    deferred::schedule(Work::NetReceive);
+}
+
+/// Passes the received packets to the network stack.
+///
+/// While the RX ring is busy, receive interrupts stay masked and the
+/// driver polls the ring instead: at most [`RX_BUDGET`] packets are processed
+/// per run, and the work is scheduled again if packets remain, so that other
+/// interrupts and deferred work are served in between.
+fn receive_packets() {
+    let driver = DRIVER.get().lock();
+    let (mut driver, drained) = receive(driver, RX_BUDGET);
+
+    // the missed packets count register is cleared on read.
+    let missed = unsafe { driver.read_reg(Register::Mpc) };
+    RX_MISSED.fetch_add(missed.safe_into(), Ordering::Relaxed);
+
+    if drained {
+        unsafe {
+            driver.write_reg(Register::Ims, RX_INTERRUPTS);
+        }
+    } else {
+        RX_POLLS.fetch_add(1, Ordering::Relaxed);
+        deferred::schedule(Work::NetReceive);
+    }
+}
+
+/// Returns the receive statistics of the device.
+pub fn info() -> NetInfo {
+    NetInfo {
+        rx_packets: RX_PACKETS.load(Ordering::Relaxed),
+        rx_polls: RX_POLLS.load(Ordering::Relaxed),
+        rx_missed: RX_MISSED.load(Ordering::Relaxed),
+        rx_dropped: 0,
+    }
 }
 
 pub fn transmitter() -> Option<Transmitter<'static>> {
@@ -186,15 +264,22 @@ impl Transmitter<'_> {
     }
 }
 
-fn receive(mut driver: SpinLockGuard<'_, Driver>) -> SpinLockGuard<'_, Driver> {
-    loop {
+/// Passes at most `budget` received packets to the network stack.
+///
+/// Returns `true` as the second value if the RX ring has been drained.
+fn receive(
+    mut driver: SpinLockGuard<'_, Driver>,
+    budget: usize,
+) -> (SpinLockGuard<'_, Driver>, bool) {
+    for _ in 0..budget {
         let tail = unsafe { driver.read_reg(Register::Rdt) };
         let index = (usize::safe_from(tail) + 1) % RX_RING_SIZE;
         let desc = &mut driver.rx_ring.0[index];
         if !desc.status.contains(RxdStat::Dd) {
-            return driver;
+            return (driver, true);
         }
         let length = desc.length;
+        RX_PACKETS.fetch_add(1, Ordering::Relaxed);
 
         let mut buf = driver.rx_bufs[index].take().unwrap();
         drop(driver);
@@ -209,6 +294,11 @@ fn receive(mut driver: SpinLockGuard<'_, Driver>) -> SpinLockGuard<'_, Driver> {
             driver.write_reg(Register::Rdt, u32::try_from(index).unwrap());
         }
     }
+
+    let tail = unsafe { driver.read_reg(Register::Rdt) };
+    let index = (usize::safe_from(tail) + 1) % RX_RING_SIZE;
+    let drained = !driver.rx_ring.0[index].status.contains(RxdStat::Dd);
+    (driver, drained)
 }
 
 const TX_RING_SIZE: usize = 16;
@@ -268,6 +358,8 @@ enum Register {
     Icr = 0x000C0,
     /// Interrupt Mask Set - RW
     Ims = 0x000D0,
+    /// Interrupt Mask Clear - W
+    Imc = 0x000D8,
     /// RX Control - RW
     Rctl = 0x00100,
     /// TX Control - RW
@@ -300,6 +392,8 @@ enum Register {
     Tdh = 0x03810,
     /// TX Descriptor Tail - RW
     Tdt = 0x03818,
+    /// Missed Packets Count - R (cleared on read)
+    Mpc = 0x04010,
     /// Multicast Table Array - RW Array
     Mta = 0x05200,
     /// Receive Address - RW Array
@@ -0,0 +1,20 @@
+//! Goldfish real-time clock.
+
+use core::{ptr, time::Duration};
+
+use crate::memory::layout::GOLDFISH_RTC;
+
+const TIME_LOW: usize = 0x00;
+const TIME_HIGH: usize = 0x04;
+
+fn read_reg(offset: usize) -> u32 {
+    unsafe { ptr::with_exposed_provenance::<u32>(GOLDFISH_RTC + offset).read_volatile() }
+}
+
+/// Returns the wall-clock time as the duration since the Unix epoch.
+pub fn now() -> Duration {
+    // reading TIME_LOW latches TIME_HIGH.
+    let low = read_reg(TIME_LOW);
+    let high = read_reg(TIME_HIGH);
+    Duration::from_nanos((u64::from(high) << 32) | u64::from(low))
+}
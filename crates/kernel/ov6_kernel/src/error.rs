@@ -1,5 +1,7 @@
+use core::alloc::AllocError;
+
 use ov6_fs_types::InodeNo;
-use ov6_syscall::{RegisterDecodeError, error::SyscallError};
+use ov6_syscall::{Capabilities, RegisterDecodeError, SyscallCode, error::SyscallError};
 use ov6_types::{fs::RawFd, process::ProcId};
 
 use crate::{
@@ -12,14 +14,42 @@ use crate::{
 pub(crate) enum KernelError {
     #[error("no free process found")]
     NoFreeProc,
+    #[error("no free timer found")]
+    NoFreeTimer,
     #[error("no free page found")]
     NoFreePage,
+    #[error("out of memory")]
+    NoMemory,
     #[error("no child process")]
     NoWaitTarget,
     #[error("process not found: {0}")]
     ProcessNotFound(ProcId),
+    #[error("process group not found: {0}")]
+    ProcessGroupNotFound(ProcId),
+    #[error("cannot kill kernel task: {0}")]
+    KillKernelTask(ProcId),
+    #[error("process is not traced: {0}")]
+    NotTraced(ProcId),
+    #[error("process is already traced: {0}")]
+    AlreadyTraced(ProcId),
+    #[error("traced process is not stopped: {0}")]
+    TraceeNotStopped(ProcId),
+    #[error("interrupted by terminal")]
+    Interrupted,
     #[error("device not found: {0}")]
     DeviceNotFound(DeviceNo),
+    #[error("device already mounted: {0}")]
+    DeviceAlreadyMounted(DeviceNo),
+    #[error("file system type not found")]
+    FsTypeNotFound,
+    #[error("file system type already registered")]
+    FsTypeAlreadyRegistered,
+    #[error("no free file system type table entry")]
+    NoFreeFsType,
+    #[error("no free mount table entry")]
+    NoFreeMount,
+    #[error("directory is a mount point")]
+    MountPointBusy,
     #[error("too large virtual address: {0:#x}")]
     TooLargeVirtualAddress(usize),
     #[error("virtual address underflow")]
@@ -28,6 +58,14 @@ pub(crate) enum KernelError {
     VirtualPageNotMapped(VirtAddr),
     #[error("inaccessible page: {0:#x}")]
     InaccessiblePage(VirtAddr),
+    #[error("address reserved by the kernel: {0:#x}")]
+    ReservedVirtualAddress(VirtAddr),
+    #[error("user buffer too large")]
+    UserBufferTooLarge,
+    #[error("user buffer wraps around: addr={0:#x}, size={1:#x}")]
+    UserBufferWrapsAround(usize, usize),
+    #[error("misaligned user pointer: addr={0:#x}, align={1}")]
+    MisalignedUserPointer(usize, usize),
     #[error("virtual address with different permission: va={0:#x}, flags={1:?},{2:?}")]
     VirtualAddressWithUnexpectedPerm(VirtAddr, PtEntryFlags, PtEntryFlags),
     #[error("heap size overflow")]
@@ -52,10 +90,26 @@ pub(crate) enum KernelError {
     DirectoryNotEmpty,
     #[error("write offset too large")]
     WriteOffsetTooLarge,
+    #[error("positional I/O on non-seekable file")]
+    PositionalIoNotSupported,
+    #[error("splice without a pipe or within one pipe")]
+    InvalidSplice,
+    #[error("unaligned block device I/O")]
+    UnalignedBlockIo,
+    #[error("shrink file system")]
+    ShrinkFs,
+    #[error("file system too large: max {0} blocks")]
+    ResizeFsTooLarge(u32),
+    #[error("read-only file system")]
+    ReadOnlyFs,
     #[error("unlink root directory")]
     UnlinkRootDir,
     #[error("unlink dot directories")]
     UnlinkDots,
+    #[error("unlink directory")]
+    UnlinkDirectory,
+    #[error("rmdir non-directory")]
+    RmdirNonDirectory,
     #[error("create root directory")]
     CreateRootDir,
     #[error("create already exist entry")]
@@ -68,20 +122,20 @@ pub(crate) enum KernelError {
     LinkToNonDirectory,
     #[error("link already exists entry")]
     LinkAlreadyExists,
+    #[error("link directory")]
+    LinkDirectory,
+    #[error("too many links")]
+    TooManyLinks,
     #[error("stat on non-file-system entry")]
     StatOnNonFsEntry,
     #[error("broken pipe")]
     BrokenPipe,
     #[error("file too large")]
     FileTooLarge,
-    #[error("no free file table entry")]
-    NoFreeFileTableEntry,
     #[error("no free file descriptor table entry")]
     NoFreeFileDescriptorTableEntry,
     #[error("no free inode in-memory table entry")]
     NoFreeInodeInMemoryTableEntry,
-    #[error("no free inode data im-memory table entry")]
-    NoFreeInodeDataInMemoryTableEntry,
     #[error("corraputed inode type: inode={0}, type={1}")]
     CorruptedInodeType(InodeNo, u16),
     #[error("storage out of blocks")]
@@ -90,8 +144,18 @@ pub(crate) enum KernelError {
     StorageOutOfInodes,
     #[error("open directory as writable")]
     OpenDirAsWritable,
+    #[error("access not permitted for the file type")]
+    AccessDenied,
     #[error("chdir to non-directory")]
     ChdirNotDir,
+    #[error("chroot to non-directory")]
+    ChrootNotDir,
+    #[error("mount on non-directory")]
+    MountNotDir,
+    #[error("current directory is outside the root directory")]
+    CwdUnreachable,
+    #[error("directory file descriptor not referring to a file: {0}")]
+    DirFdNotInode(RawFd),
     #[error("argument list too large")]
     ArgumentListTooLarge,
     #[error("invalid executable")]
@@ -112,22 +176,120 @@ pub(crate) enum KernelError {
     NoFreePort,
     #[error("port not bound")]
     PortNotBound,
+    #[error("open of a local socket")]
+    OpenSocket,
+    #[error("not a bound local socket")]
+    NotBoundSocket,
+    #[error("local socket not listening")]
+    NotListening,
+    #[error("no local socket listening")]
+    ConnectionRefused,
+    #[error("listen backlog full")]
+    ListenBacklogFull,
+    #[error("no free local socket slot")]
+    NoFreeListener,
+    #[error("invalid listen backlog: {0}")]
+    InvalidBacklog(usize),
+    #[error("no message queue with the key")]
+    MsgQueueKeyNotFound,
+    #[error("message queue with the key already exists")]
+    MsgQueueAlreadyExists,
+    #[error("no free message queue")]
+    NoFreeMsgQueue,
+    #[error("message queue not found: {0:#x}")]
+    MsgQueueNotFound(usize),
+    #[error("message queue removed")]
+    MsgQueueRemoved,
+    #[error("message queue full")]
+    MsgQueueFull,
+    #[error("too large message: {0}")]
+    TooLargeMessage(usize),
+    #[error("message too large for the buffer: {0}")]
+    MessageBufferTooSmall(usize),
+    #[error("no free semaphore")]
+    NoFreeSemaphore,
+    #[error("not a semaphore")]
+    NotSemaphore,
+    #[error("semaphore value overflow")]
+    SemaphoreOverflow,
+    #[error("event counter not ready")]
+    EventNotReady,
+    #[error("invalid event counter value: {0:#x}")]
+    InvalidEventValue(u64),
+    #[error("too small event counter buffer: {0}")]
+    InvalidEventBuffer(usize),
+    #[error("invalid host name")]
+    InvalidHostname,
+    #[error("invalid nice value: {0}")]
+    InvalidNice(isize),
+    #[error("no online CPU in CPU set: {0:#x}")]
+    InvalidCpuSet(usize),
+    #[error("unnamed file opened without write access or with create")]
+    InvalidTmpfileOpen,
+    #[error("link of a file descriptor not referring to a file")]
+    FlinkNonInodeFile,
+    #[error("invalid file descriptor flags: {0:#x}")]
+    InvalidFdFlags(usize),
+    #[error("file descriptor duplicated onto itself")]
+    DupSameFd,
+    #[error("missing capabilities: {0:?}")]
+    MissingCapability(Capabilities),
+    #[error("syscall rejected by filter: {0}")]
+    SyscallFiltered(SyscallCode),
+    #[error("invalid syscall filter size: {0}")]
+    InvalidSyscallFilterSize(usize),
+    #[error("unknown syscall in filter: {0}")]
+    InvalidSyscallFilterCode(usize),
+    #[error("ioctl not supported by the file")]
+    IoctlNotSupported,
+    #[error("invalid ioctl argument: {0:#x}")]
+    InvalidIoctlArgument(usize),
+    #[error("access hints not supported by the file")]
+    AccessHintNotSupported,
+    #[error("invalid access hint: {0}")]
+    InvalidAccessHint(usize),
+    #[error("system is shutting down")]
+    ShuttingDown,
 }
 
 impl From<KernelError> for SyscallError {
+    #[expect(clippy::too_many_lines)]
     fn from(error: KernelError) -> Self {
         match error {
-            KernelError::NoFreeProc | KernelError::NoSendBuffer | KernelError::NoFreePort => {
-                Self::ResourceTempolaryUnavailable
+            KernelError::NoFreeProc
+            | KernelError::NoFreeTimer
+            | KernelError::NoFreeMount
+            | KernelError::NoFreeFsType
+            | KernelError::NoSendBuffer
+            | KernelError::NoFreePort
+            | KernelError::ListenBacklogFull
+            | KernelError::NoFreeListener
+            | KernelError::MsgQueueFull
+            | KernelError::EventNotReady => Self::ResourceTempolaryUnavailable,
+            KernelError::NoFreePage | KernelError::NoMemory => Self::OutOfMemory,
+            KernelError::MissingCapability(_)
+            | KernelError::SyscallFiltered(_)
+            | KernelError::ProcessGroupNotFound(_)
+            | KernelError::KillKernelTask(_)
+            | KernelError::NotTraced(_)
+            | KernelError::AlreadyTraced(_)
+            | KernelError::LinkDirectory => Self::NotPermitted,
+            KernelError::ProcessNotFound(_) | KernelError::TraceeNotStopped(_) => {
+                Self::ProcessNotFound
             }
-            KernelError::NoFreePage => Self::OutOfMemory,
-            KernelError::ProcessNotFound(_) => Self::ProcessNotFound,
-            KernelError::DeviceNotFound(_) => Self::DeviceNotFound,
+            KernelError::Interrupted => Self::Interrupted,
+            KernelError::DeviceNotFound(_)
+            | KernelError::FsTypeNotFound
+            | KernelError::OpenSocket => Self::DeviceNotFound,
             KernelError::NoWaitTarget => Self::NoChildProcess,
             KernelError::TooLargeVirtualAddress(_)
             | KernelError::VirtualAddressUnderflow
             | KernelError::VirtualPageNotMapped(_)
             | KernelError::InaccessiblePage(_)
+            | KernelError::ReservedVirtualAddress(_)
+            | KernelError::UserBufferTooLarge
+            | KernelError::UserBufferWrapsAround(_, _)
+            | KernelError::MisalignedUserPointer(_, _)
             | KernelError::VirtualAddressWithUnexpectedPerm(_, _, _) => Self::BadAddress,
             KernelError::FileDescriptorNotFound(_, _)
             | KernelError::FileDescriptorNotReadable
@@ -136,34 +298,79 @@ impl From<KernelError> for SyscallError {
             KernelError::PathTooLong => Self::InvalidFilename,
             KernelError::NonDirectoryPathComponent
             | KernelError::ChdirNotDir
-            | KernelError::LinkToNonDirectory => Self::NotADirectory,
-            KernelError::FsEntryNotFound => Self::FsEntryNotFound,
+            | KernelError::ChrootNotDir
+            | KernelError::MountNotDir
+            | KernelError::DirFdNotInode(_)
+            | KernelError::LinkToNonDirectory
+            | KernelError::RmdirNonDirectory => Self::NotADirectory,
+            KernelError::FsEntryNotFound
+            | KernelError::MsgQueueKeyNotFound
+            | KernelError::CwdUnreachable => Self::FsEntryNotFound,
             KernelError::DirectoryNotEmpty => Self::DirectoryNotEmpty,
-            KernelError::WriteOffsetTooLarge => Self::NotSeekable,
-            KernelError::UnlinkRootDir => Self::ResourceBusy,
+            KernelError::WriteOffsetTooLarge
+            | KernelError::PositionalIoNotSupported
+            | KernelError::AccessHintNotSupported => Self::NotSeekable,
+            KernelError::UnlinkRootDir
+            | KernelError::ShuttingDown
+            | KernelError::DeviceAlreadyMounted(_)
+            | KernelError::MountPointBusy => Self::ResourceBusy,
             KernelError::HeapSizeOverflow
             | KernelError::HeapSizeUnderflow
             | KernelError::UnlinkDots
             | KernelError::NullInPath
-            | KernelError::PortNotBound => Self::InvalidInput,
+            | KernelError::PortNotBound
+            | KernelError::UnalignedBlockIo
+            | KernelError::InvalidSplice
+            | KernelError::ShrinkFs
+            | KernelError::InvalidIoctlArgument(_)
+            | KernelError::InvalidAccessHint(_)
+            | KernelError::InvalidBacklog(_)
+            | KernelError::NotListening
+            | KernelError::MsgQueueNotFound(_)
+            | KernelError::NotSemaphore
+            | KernelError::SemaphoreOverflow
+            | KernelError::InvalidEventValue(_)
+            | KernelError::InvalidEventBuffer(_)
+            | KernelError::InvalidHostname
+            | KernelError::InvalidNice(_)
+            | KernelError::InvalidCpuSet(_)
+            | KernelError::InvalidTmpfileOpen
+            | KernelError::FlinkNonInodeFile
+            | KernelError::InvalidFdFlags(_)
+            | KernelError::InvalidSyscallFilterSize(_)
+            | KernelError::InvalidSyscallFilterCode(_)
+            | KernelError::DupSameFd => Self::InvalidInput,
             KernelError::CreateRootDir
             | KernelError::CreateAlreadyExists
             | KernelError::LinkRootDir
-            | KernelError::LinkAlreadyExists => Self::AlreadyExists,
+            | KernelError::LinkAlreadyExists
+            | KernelError::MsgQueueAlreadyExists
+            | KernelError::FsTypeAlreadyRegistered => Self::AlreadyExists,
             KernelError::LinkCrossDevices => Self::CrossesDevices,
+            KernelError::ReadOnlyFs => Self::ReadOnlyFilesystem,
+            KernelError::TooManyLinks => Self::TooManyLinks,
             KernelError::BrokenPipe => Self::BrokenPipe,
             KernelError::FileTooLarge => Self::FileTooLarge,
-            KernelError::NoFreeFileTableEntry
-            | KernelError::NoFreeInodeInMemoryTableEntry
-            | KernelError::NoFreeInodeDataInMemoryTableEntry => Self::TooManyOpenFilesSystem,
+            KernelError::NoFreeInodeInMemoryTableEntry => Self::TooManyOpenFilesSystem,
             KernelError::NoFreeFileDescriptorTableEntry => Self::TooManyOpenFiles,
+            KernelError::IoctlNotSupported => Self::NoTty,
             KernelError::CorruptedInodeType(_, _) => Self::Io,
-            KernelError::StorageOutOfBlocks | KernelError::StorageOutOfInodes => Self::StorageFull,
-            KernelError::OpenDirAsWritable => Self::IsADirectory,
+            KernelError::StorageOutOfBlocks
+            | KernelError::StorageOutOfInodes
+            | KernelError::ResizeFsTooLarge(_)
+            | KernelError::NoFreeMsgQueue
+            | KernelError::NoFreeSemaphore => Self::StorageFull,
+            KernelError::OpenDirAsWritable | KernelError::UnlinkDirectory => Self::IsADirectory,
+            KernelError::AccessDenied => Self::PermissionDenied,
             KernelError::ArgumentListTooLarge => Self::ArgumentListTooLong,
             KernelError::InvalidExecutable => Self::ExecFormat,
-            KernelError::TooLargeUdpPacket => Self::MessageTooLong,
+            KernelError::TooLargeUdpPacket
+            | KernelError::TooLargeMessage(_)
+            | KernelError::MessageBufferTooSmall(_) => Self::MessageTooLong,
+            KernelError::MsgQueueRemoved => Self::IdentifierRemoved,
             KernelError::PortAlreadyBound => Self::AddrInUse,
+            KernelError::NotBoundSocket => Self::NotASocket,
+            KernelError::ConnectionRefused => Self::ConnectionRefused,
             KernelError::NotInSignalHandler
             | KernelError::SyscallDecode(_)
             | KernelError::CallerProcessAlreadyKilled => Self::Unknown,
@@ -186,3 +393,9 @@ impl From<WaitError> for KernelError {
         }
     }
 }
+
+impl From<AllocError> for KernelError {
+    fn from(AllocError: AllocError) -> Self {
+        Self::NoMemory
+    }
+}
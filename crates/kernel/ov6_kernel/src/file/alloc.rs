@@ -1,48 +1,40 @@
 use alloc::sync::Arc;
 use core::{
     alloc::{AllocError, Allocator, Layout},
-    mem::MaybeUninit,
     ops::Deref,
     ptr::NonNull,
+    sync::atomic::{AtomicUsize, Ordering},
 };
 
-use once_init::OnceInit;
-use ov6_kernel_params::NFILE;
-use slab_allocator::{ArcInnerLayout, SlabAllocator};
+use ov6_syscall::FileInfo;
 
 use super::FileData;
-use crate::{error::KernelError, sync::SpinLock};
-
-type FileDataLayout = ArcInnerLayout<FileData>;
-
-static ALLOCATOR: OnceInit<SpinLock<SlabAllocator<FileDataLayout>>> = OnceInit::new();
+use crate::{
+    error::KernelError,
+    memory::{fallible, heap::HeapAllocator},
+};
 
-pub(super) fn init() {
-    static mut FILE_DATA_MEMORY: [MaybeUninit<FileDataLayout>; NFILE] =
-        [const { MaybeUninit::uninit() }; NFILE];
+/// Number of file objects allocated.
+static OPEN: AtomicUsize = AtomicUsize::new(0);
 
-    unsafe {
-        let start = (&raw mut FILE_DATA_MEMORY[0]).cast::<FileDataLayout>();
-        let end = start.add(NFILE);
-        let alloc = SlabAllocator::new(start..end);
-        ALLOCATOR.init(SpinLock::new(alloc));
-    }
-}
+/// Largest number of file objects allocated at the same time.
+static PEAK: AtomicUsize = AtomicUsize::new(0);
 
+/// Allocator of file objects from the kernel heap, counting them.
 #[derive(Clone)]
 struct FileAllocator;
 
 unsafe impl Allocator for FileAllocator {
     fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
-        assert_eq!(layout, Layout::new::<FileDataLayout>());
-        let Some(ptr) = ALLOCATOR.get().lock().allocate() else {
-            return Err(AllocError);
-        };
-        Ok(NonNull::slice_from_raw_parts(ptr.cast(), layout.size()))
+        let ptr = HeapAllocator.allocate(layout)?;
+        let open = OPEN.fetch_add(1, Ordering::Relaxed) + 1;
+        PEAK.fetch_max(open, Ordering::Relaxed);
+        Ok(ptr)
     }
 
-    unsafe fn deallocate(&self, ptr: NonNull<u8>, _layout: Layout) {
-        unsafe { ALLOCATOR.get().lock().deallocate(ptr.cast()) }
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        unsafe { HeapAllocator.deallocate(ptr, layout) }
+        OPEN.fetch_sub(1, Ordering::Relaxed);
     }
 }
 
@@ -59,8 +51,15 @@ impl Deref for FileDataArc {
 
 impl FileDataArc {
     pub(super) fn try_new(data: FileData) -> Result<Self, KernelError> {
-        let data = Arc::try_new_in(data, FileAllocator)
-            .map_err(|AllocError| KernelError::NoFreeFileTableEntry)?;
+        let data = fallible::try_new_arc_in(data, FileAllocator)?;
         Ok(Self(data))
     }
 }
+
+/// Returns the statistics of the open files.
+pub fn info() -> FileInfo {
+    FileInfo {
+        open: OPEN.load(Ordering::Relaxed),
+        peak: PEAK.load(Ordering::Relaxed),
+    }
+}
@@ -1,35 +1,14 @@
-use ov6_fs_types::{T_DEVICE, T_DIR, T_FILE};
-use ov6_syscall::{Stat, StatType};
+use ov6_syscall::Stat;
 
 use crate::{
     error::KernelError,
-    fs::{self, Inode},
+    fs::{Inode, vfs},
 };
 
 pub(super) fn close_inode(inode: Inode) {
-    let tx = fs::force_begin_tx();
-    inode.into_tx(&tx).put();
+    vfs::put(inode);
 }
 
 pub(super) fn stat_inode(inode: &Inode) -> Result<Stat, KernelError> {
-    let tx = fs::begin_readonly_tx();
-    let mut ip = inode.clone().into_tx(&tx);
-    let lip = ip.wait_lock()?;
-    let ty = match lip.ty() {
-        T_DIR => StatType::Dir,
-        T_FILE => StatType::File,
-        T_DEVICE => StatType::Dev,
-        ty => return Err(KernelError::CorruptedInodeType(lip.ino(), ty)),
-    };
-    let st = Stat {
-        dev: lip.dev().value(),
-        ino: lip.ino().value(),
-        ty: ty as u16,
-        nlink: lip.nlink(),
-        padding: [0; 4],
-        size: u64::from(lip.size()),
-    };
-    drop(lip);
-    drop(ip);
-    Ok(st)
+    vfs::stat(inode)
 }
@@ -1,20 +1,32 @@
-use ov6_syscall::{Stat, UserMutSlice, UserSlice};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use ov6_syscall::{IoctlRequest, Stat};
 
 use super::{File, FileData, FileDataArc, SpecificData};
 use crate::{
     error::KernelError,
     fs::{DeviceNo, Inode},
-    memory::{
-        addr::{GenericMutSlice, GenericSlice, Validated},
-        vm_user::UserPageTable,
-    },
+    memory::addr::{GenericMutSlice, GenericSlice},
     param::NDEV,
     sync::SpinLock,
 };
 
+type ReadFn = fn(dst: &mut GenericMutSlice<u8>, off: usize) -> Result<usize, KernelError>;
+type WriteFn = fn(src: &GenericSlice<u8>, off: usize) -> Result<usize, KernelError>;
+type IoctlFn = fn(req: IoctlRequest, arg: usize) -> Result<usize, KernelError>;
+
 pub struct Device {
-    pub read: fn(dst: &mut GenericMutSlice<u8>) -> Result<usize, KernelError>,
-    pub write: fn(src: &GenericSlice<u8>) -> Result<usize, KernelError>,
+    /// Reads from the device at the byte offset `off`.
+    pub read: ReadFn,
+    /// Writes to the device at the byte offset `off`.
+    pub write: WriteFn,
+    /// Handles device-specific requests, or `None` if the device has none.
+    pub ioctl: Option<IoctlFn>,
+    /// Whether the device is a block device.
+    ///
+    /// Block devices are addressed by the file offset and support positional
+    /// I/O. Other devices are always given the offset 0.
+    pub block: bool,
 }
 
 struct DeviceTable {
@@ -43,9 +55,18 @@ pub fn register_device(no: DeviceNo, dev: Device) {
     DEVICE_TABLE.lock().register(no, dev);
 }
 
+/// Returns `true` if `major` is a registered block device.
+pub fn is_block_device(major: DeviceNo) -> bool {
+    DEVICE_TABLE
+        .lock()
+        .get_device(major)
+        .is_some_and(|dev| dev.block)
+}
+
 pub(super) struct DeviceFile {
     major: DeviceNo,
     inode: Inode,
+    off: AtomicUsize,
 }
 
 pub(super) fn new_file(
@@ -57,7 +78,11 @@ pub(super) fn new_file(
     let data = FileDataArc::try_new(FileData {
         readable,
         writable,
-        data: Some(SpecificData::Device(DeviceFile { major, inode })),
+        data: Some(SpecificData::Device(DeviceFile {
+            major,
+            inode,
+            off: AtomicUsize::new(0),
+        })),
     })?;
     Ok(File { data })
 }
@@ -71,29 +96,61 @@ impl DeviceFile {
         super::common::stat_inode(&self.inode)
     }
 
-    pub(super) fn read(
-        &self,
-        pt: &mut UserPageTable,
-        dst: &mut Validated<UserMutSlice<u8>>,
-    ) -> Result<usize, KernelError> {
-        let read = DEVICE_TABLE
-            .lock()
+    fn ops(&self) -> Result<(ReadFn, WriteFn, bool), KernelError> {
+        let table = DEVICE_TABLE.lock();
+        let dev = table
             .get_device(self.major)
-            .ok_or(KernelError::DeviceNotFound(self.major))?
-            .read;
-        read(&mut (pt, dst).into())
+            .ok_or(KernelError::DeviceNotFound(self.major))?;
+        Ok((dev.read, dev.write, dev.block))
+    }
+
+    pub(super) fn read(&self, mut dst: GenericMutSlice<u8>) -> Result<usize, KernelError> {
+        let (read, _, block) = self.ops()?;
+        if !block {
+            return read(&mut dst, 0);
+        }
+        let n = read(&mut dst, self.off.load(Ordering::Relaxed))?;
+        self.off.fetch_add(n, Ordering::Relaxed);
+        Ok(n)
     }
 
-    pub(super) fn write(
+    pub(super) fn read_at(
         &self,
-        pt: &UserPageTable,
-        src: &Validated<UserSlice<u8>>,
+        mut dst: GenericMutSlice<u8>,
+        off: usize,
     ) -> Result<usize, KernelError> {
-        let write = DEVICE_TABLE
+        let (read, _, block) = self.ops()?;
+        if !block {
+            return Err(KernelError::PositionalIoNotSupported);
+        }
+        read(&mut dst, off)
+    }
+
+    pub(super) fn write(&self, src: GenericSlice<u8>) -> Result<usize, KernelError> {
+        let (_, write, block) = self.ops()?;
+        if !block {
+            return write(&src, 0);
+        }
+        let n = write(&src, self.off.load(Ordering::Relaxed))?;
+        self.off.fetch_add(n, Ordering::Relaxed);
+        Ok(n)
+    }
+
+    pub(super) fn write_at(&self, src: GenericSlice<u8>, off: usize) -> Result<usize, KernelError> {
+        let (_, write, block) = self.ops()?;
+        if !block {
+            return Err(KernelError::PositionalIoNotSupported);
+        }
+        write(&src, off)
+    }
+
+    pub(super) fn ioctl(&self, req: IoctlRequest, arg: usize) -> Result<usize, KernelError> {
+        let ioctl = DEVICE_TABLE
             .lock()
             .get_device(self.major)
             .ok_or(KernelError::DeviceNotFound(self.major))?
-            .write;
-        write(&(pt, src).into())
+            .ioctl
+            .ok_or(KernelError::IoctlNotSupported)?;
+        ioctl(req, arg)
     }
 }
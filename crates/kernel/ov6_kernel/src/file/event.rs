@@ -0,0 +1,104 @@
+//! Event counters.
+//!
+//! An event file holds a 64-bit counter. Writing a value adds it to the
+//! counter, and reading returns the counter and resets it to 0, so that a
+//! process can wait for the notifications from others through a file.
+
+use ov6_syscall::EventFdFlags;
+
+use super::{File, FileData, FileDataArc, SpecificData};
+use crate::{
+    error::KernelError,
+    memory::{
+        addr::{GenericMutSlice, GenericSlice},
+        vm_user::UserPageTable,
+    },
+    sync::{SpinLock, SpinLockCondVar},
+};
+
+/// Size of the value read from or written to an event file.
+const VALUE_SIZE: usize = size_of::<u64>();
+
+/// Largest value of the counter.
+const MAX_COUNTER: u64 = u64::MAX - 1;
+
+pub(super) struct EventFile {
+    flags: EventFdFlags,
+    counter: SpinLock<u64>,
+    /// Notified when the counter is changed.
+    cond: SpinLockCondVar,
+}
+
+pub(super) fn new_file(initial: u64, flags: EventFdFlags) -> Result<File, KernelError> {
+    if initial > MAX_COUNTER {
+        return Err(KernelError::InvalidEventValue(initial));
+    }
+    let event = EventFile {
+        flags,
+        counter: SpinLock::new(initial),
+        cond: SpinLockCondVar::new(),
+    };
+    let data = FileDataArc::try_new(FileData {
+        readable: true,
+        writable: true,
+        data: Some(SpecificData::Event(event)),
+    })?;
+    Ok(File { data })
+}
+
+impl EventFile {
+    /// Waits until the counter is non-zero, and reads it.
+    ///
+    /// With [`EventFdFlags::SEMAPHORE`], reads 1 and decrements the counter.
+    /// Otherwise, reads the counter and resets it to 0.
+    pub(super) fn read(&self, mut dst: GenericMutSlice<u8>) -> Result<usize, KernelError> {
+        if dst.len() < VALUE_SIZE {
+            return Err(KernelError::InvalidEventBuffer(dst.len()));
+        }
+
+        let mut counter = self.counter.lock();
+        while *counter == 0 {
+            if self.flags.contains(EventFdFlags::NONBLOCKING) {
+                return Err(KernelError::EventNotReady);
+            }
+            counter = self.cond.wait(counter).map_err(|(_guard, e)| e)?;
+        }
+        let value = if self.flags.contains(EventFdFlags::SEMAPHORE) {
+            1
+        } else {
+            *counter
+        };
+        *counter -= value;
+        self.cond.notify();
+        drop(counter);
+
+        UserPageTable::copy_k2x_bytes(&mut dst.take_mut(VALUE_SIZE), &value.to_ne_bytes());
+        Ok(VALUE_SIZE)
+    }
+
+    /// Adds the written value to the counter.
+    ///
+    /// Waits until the counter has room for the value.
+    pub(super) fn write(&self, src: GenericSlice<u8>) -> Result<usize, KernelError> {
+        if src.len() < VALUE_SIZE {
+            return Err(KernelError::InvalidEventBuffer(src.len()));
+        }
+        let mut bytes = [0; VALUE_SIZE];
+        UserPageTable::copy_x2k_bytes(&mut bytes, &src.take(VALUE_SIZE));
+        let value = u64::from_ne_bytes(bytes);
+        if value > MAX_COUNTER {
+            return Err(KernelError::InvalidEventValue(value));
+        }
+
+        let mut counter = self.counter.lock();
+        while *counter > MAX_COUNTER - value {
+            if self.flags.contains(EventFdFlags::NONBLOCKING) {
+                return Err(KernelError::EventNotReady);
+            }
+            counter = self.cond.wait(counter).map_err(|(_guard, e)| e)?;
+        }
+        *counter += value;
+        self.cond.notify();
+        Ok(VALUE_SIZE)
+    }
+}
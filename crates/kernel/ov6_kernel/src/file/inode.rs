@@ -1,27 +1,45 @@
 use core::sync::atomic::{AtomicUsize, Ordering};
 
-use ov6_syscall::{Stat, UserMutSlice, UserSlice};
+use ov6_syscall::{AccessHint, FcntlRequest, Stat};
 
 use super::{File, FileData, FileDataArc, SpecificData};
 use crate::{
     error::KernelError,
-    fs::{self, FS_BLOCK_SIZE, Inode},
-    memory::{addr::Validated, vm_user::UserPageTable},
-    param::MAX_OP_BLOCKS,
+    fs::{
+        Inode,
+        vfs::{self, FileOps, OpenFile},
+    },
+    memory::addr::{GenericMutSlice, GenericSlice},
+    proc,
 };
 
 pub(super) struct InodeFile {
-    inode: Inode,
-    off: AtomicUsize,
+    file: OpenFile,
+    /// Operations of the file system the file is in.
+    ops: &'static dyn FileOps,
+    /// [`AccessHint`] of the file descriptor.
+    access_hint: AtomicUsize,
 }
 
-pub fn new_file(inode: Inode, readable: bool, writable: bool) -> Result<File, KernelError> {
+pub fn new_file(
+    inode: Inode,
+    readable: bool,
+    writable: bool,
+    direct: bool,
+) -> Result<File, KernelError> {
+    // file systems are never unmounted, so the inode's one is still there.
+    let ops = vfs::file_ops(inode.dev()).unwrap();
     let data = FileDataArc::try_new(FileData {
         readable,
         writable,
         data: Some(SpecificData::Inode(InodeFile {
-            inode,
-            off: AtomicUsize::new(0),
+            file: OpenFile {
+                inode,
+                off: AtomicUsize::new(0),
+                direct,
+            },
+            ops,
+            access_hint: AtomicUsize::new(AccessHint::Normal as usize),
         })),
     })?;
     Ok(File { data })
@@ -29,65 +47,61 @@ pub fn new_file(inode: Inode, readable: bool, writable: bool) -> Result<File, Ke
 
 impl InodeFile {
     pub(super) fn close(self) {
-        super::common::close_inode(self.inode);
+        super::common::close_inode(self.file.inode);
+    }
+
+    pub(super) fn inode(&self) -> &Inode {
+        &self.file.inode
     }
 
     pub(super) fn stat(&self) -> Result<Stat, KernelError> {
-        super::common::stat_inode(&self.inode)
+        super::common::stat_inode(&self.file.inode)
     }
 
-    pub(super) fn read(
-        &self,
-        pt: &mut UserPageTable,
-        dst: &mut Validated<UserMutSlice<u8>>,
-    ) -> Result<usize, KernelError> {
-        let tx = fs::begin_readonly_tx();
-        let mut ip = self.inode.clone().into_tx(&tx);
-        let mut lip = ip.wait_lock()?;
-        let res = lip.read((pt, dst).into(), self.off.load(Ordering::Relaxed));
-        if let Ok(sz) = res {
-            self.off.fetch_add(sz, Ordering::Relaxed);
+    fn access_hint(&self) -> AccessHint {
+        AccessHint::from_repr(self.access_hint.load(Ordering::Relaxed)).unwrap()
+    }
+
+    pub(super) fn fcntl(&self, req: FcntlRequest, arg: usize) -> Result<usize, KernelError> {
+        let hint = || AccessHint::from_repr(arg).ok_or(KernelError::InvalidAccessHint(arg));
+        match req {
+            FcntlRequest::GetFdAccessHint => Ok(self.access_hint() as usize),
+            FcntlRequest::SetFdAccessHint => {
+                self.access_hint.store(hint()? as usize, Ordering::Relaxed);
+                Ok(0)
+            }
+            FcntlRequest::GetFileAccessHint => Ok(self.ops.access_hint(&self.file.inode)? as usize),
+            FcntlRequest::SetFileAccessHint => {
+                self.ops.set_access_hint(&self.file.inode, hint()?)?;
+                Ok(0)
+            }
+            // handled by the file descriptor table
+            FcntlRequest::GetFdFlags | FcntlRequest::SetFdFlags => unreachable!(),
         }
-        res
     }
 
-    pub(super) fn write(
+    pub(super) fn read(&self, dst: GenericMutSlice<u8>) -> Result<usize, KernelError> {
+        proc::io::throttle(dst.len());
+        self.ops.read(&self.file, dst, None, self.access_hint())
+    }
+
+    pub(super) fn read_at(
         &self,
-        pt: &UserPageTable,
-        src: &Validated<UserSlice<u8>>,
+        dst: GenericMutSlice<u8>,
+        off: usize,
     ) -> Result<usize, KernelError> {
-        // write a few blocks at a time to avoid exceeding
-        // the maximum log transaction size, including
-        // i-node, indirect block, allocation blocks,
-        // and 2 blocks of slop for non-aligned writes.
-        // this really belongs lower down, since write_inode()
-        // might be writing a device like the console.
-        let max = ((MAX_OP_BLOCKS - 1 - 1 - 2) / 2) * FS_BLOCK_SIZE;
-        let mut i = 0;
-        while i < src.len() {
-            let src = src.skip(i);
-            let len = usize::min(src.len(), max);
-            let src = src.take(len);
-
-            let tx = fs::begin_tx()?;
-            let mut ip = self.inode.clone().into_tx(&tx);
-            let mut lip = ip.force_wait_lock();
-            let res = lip.write((pt, src).into(), self.off.load(Ordering::Relaxed));
-            if let Ok(sz) = res {
-                self.off.fetch_add(sz, Ordering::Relaxed);
-            }
-            lip.unlock();
-            ip.put();
-            tx.end();
+        proc::io::throttle(dst.len());
+        self.ops
+            .read(&self.file, dst, Some(off), self.access_hint())
+    }
 
-            match res {
-                Err(e) => return Err(e),
-                Ok(n) if n != src.len() => break,
-                Ok(_) => {}
-            }
+    pub(super) fn write(&self, src: GenericSlice<u8>) -> Result<usize, KernelError> {
+        proc::io::throttle(src.len());
+        self.ops.write(&self.file, src, None)
+    }
 
-            i += src.len();
-        }
-        Ok(src.len())
+    pub(super) fn write_at(&self, src: GenericSlice<u8>, off: usize) -> Result<usize, KernelError> {
+        proc::io::throttle(src.len());
+        self.ops.write(&self.file, src, Some(off))
     }
 }
@@ -1,22 +1,35 @@
-use ov6_syscall::{Stat, UserMutSlice, UserSlice};
+use ov6_syscall::{EventFdFlags, FcntlRequest, IoctlRequest, Stat, UserMutSlice, UserSlice};
 
-pub use self::device::{Device, register_device};
-use self::{alloc::FileDataArc, device::DeviceFile, inode::InodeFile, pipe::PipeFile};
+use self::{
+    alloc::FileDataArc,
+    device::DeviceFile,
+    event::EventFile,
+    inode::InodeFile,
+    pipe::PipeFile,
+    semaphore::SemaphoreFile,
+    socket::{BoundSocketFile, SocketFile},
+};
+pub use self::{
+    alloc::info,
+    device::{Device, is_block_device, register_device},
+};
 use crate::{
     error::KernelError,
-    fs::{DeviceNo, Inode},
-    memory::{addr::Validated, vm_user::UserPageTable},
+    fs::{DeviceNo, Inode, InodeNo},
+    memory::{
+        addr::{GenericMutSlice, GenericSlice, Validated},
+        vm_user::UserPageTable,
+    },
 };
 
 mod alloc;
 mod common;
 mod device;
+mod event;
 mod inode;
 mod pipe;
-
-pub fn init() {
-    alloc::init();
-}
+mod semaphore;
+mod socket;
 
 #[derive(Clone)]
 pub struct File {
@@ -31,6 +44,10 @@ struct FileData {
 
 enum SpecificData {
     Pipe(PipeFile),
+    Socket(SocketFile),
+    BoundSocket(BoundSocketFile),
+    Semaphore(SemaphoreFile),
+    Event(EventFile),
     Inode(InodeFile),
     Device(DeviceFile),
 }
@@ -39,9 +56,12 @@ impl Drop for FileData {
     fn drop(&mut self) {
         match self.data.take() {
             Some(SpecificData::Pipe(pipe)) => pipe.close(self.writable),
+            Some(SpecificData::Socket(socket)) => socket.close(),
+            Some(SpecificData::BoundSocket(socket)) => socket.close(),
+            Some(SpecificData::Semaphore(sem)) => sem.close(),
             Some(SpecificData::Inode(inode)) => inode.close(),
             Some(SpecificData::Device(device)) => device.close(),
-            None => {}
+            Some(SpecificData::Event(_)) | None => {}
         }
     }
 }
@@ -51,6 +71,37 @@ impl File {
         pipe::new_file()
     }
 
+    /// Creates a pair of connected bidirectional sockets.
+    pub fn new_socket_pair() -> Result<(Self, Self), KernelError> {
+        socket::new_pair()
+    }
+
+    /// Creates a socket bound to `inode`, whose number is `ino` on device
+    /// `dev`.
+    pub fn new_bound_socket(
+        dev: DeviceNo,
+        ino: InodeNo,
+        inode: Inode,
+    ) -> Result<Self, KernelError> {
+        socket::bind(dev, ino, inode)
+    }
+
+    /// Connects to the socket bound to the inode `ino` on device `dev`.
+    pub fn connect_socket(dev: DeviceNo, ino: InodeNo) -> Result<Self, KernelError> {
+        socket::connect(dev, ino)
+    }
+
+    /// Opens the semaphore of `key`, creating it with `value` if it does not
+    /// exist.
+    pub fn open_semaphore(key: u64, value: usize) -> Result<Self, KernelError> {
+        semaphore::open(key, value)
+    }
+
+    /// Creates an event counter of `initial`.
+    pub fn new_event(initial: u64, flags: EventFdFlags) -> Result<Self, KernelError> {
+        event::new_file(initial, flags)
+    }
+
     pub fn new_device(
         major: DeviceNo,
         inode: Inode,
@@ -60,8 +111,13 @@ impl File {
         device::new_file(major, inode, readable, writable)
     }
 
-    pub fn new_inode(inode: Inode, readable: bool, writable: bool) -> Result<Self, KernelError> {
-        inode::new_file(inode, readable, writable)
+    pub fn new_inode(
+        inode: Inode,
+        readable: bool,
+        writable: bool,
+        direct: bool,
+    ) -> Result<Self, KernelError> {
+        inode::new_file(inode, readable, writable, direct)
     }
 
     /// Increments ref count for the file.
@@ -82,7 +138,13 @@ impl File {
         match &self.data.data {
             Some(SpecificData::Inode(inode)) => inode.stat(),
             Some(SpecificData::Device(device)) => device.stat(),
-            Some(SpecificData::Pipe(_)) => Err(KernelError::StatOnNonFsEntry),
+            Some(SpecificData::BoundSocket(socket)) => socket.stat(),
+            Some(
+                SpecificData::Pipe(_)
+                | SpecificData::Socket(_)
+                | SpecificData::Semaphore(_)
+                | SpecificData::Event(_),
+            ) => Err(KernelError::StatOnNonFsEntry),
             None => unreachable!(),
         }
     }
@@ -95,15 +157,51 @@ impl File {
         pt: &mut UserPageTable,
         dst: &mut Validated<UserMutSlice<u8>>,
     ) -> Result<usize, KernelError> {
+        self.read_generic((pt, dst).into())
+    }
+
+    /// Reads from file `f` into the kernel buffer `dst`.
+    pub fn read_kernel(&self, dst: &mut [u8]) -> Result<usize, KernelError> {
+        self.read_generic(dst.into())
+    }
+
+    fn read_generic(&self, dst: GenericMutSlice<u8>) -> Result<usize, KernelError> {
         if !self.data.readable {
             return Err(KernelError::FileDescriptorNotReadable);
         }
 
         match &self.data.data {
-            Some(SpecificData::Pipe(pipe)) => pipe.read(pt, dst),
-            Some(SpecificData::Inode(inode)) => inode.read(pt, dst),
-            Some(SpecificData::Device(device)) => device.read(pt, dst),
-            None => unreachable!(),
+            Some(SpecificData::Pipe(pipe)) => pipe.read(dst),
+            Some(SpecificData::Socket(socket)) => socket.read(dst),
+            Some(SpecificData::Event(event)) => event.read(dst),
+            Some(SpecificData::Inode(inode)) => inode.read(dst),
+            Some(SpecificData::Device(device)) => device.read(dst),
+            Some(SpecificData::BoundSocket(_) | SpecificData::Semaphore(_)) | None => {
+                unreachable!()
+            }
+        }
+    }
+
+    /// Reads from file `f` at offset `off`, without changing the file offset.
+    pub fn read_at(
+        &self,
+        pt: &mut UserPageTable,
+        dst: &mut Validated<UserMutSlice<u8>>,
+        off: usize,
+    ) -> Result<usize, KernelError> {
+        if !self.data.readable {
+            return Err(KernelError::FileDescriptorNotReadable);
+        }
+
+        match &self.data.data {
+            Some(SpecificData::Inode(inode)) => inode.read_at((pt, dst).into(), off),
+            Some(SpecificData::Device(device)) => device.read_at((pt, dst).into(), off),
+            Some(SpecificData::Pipe(_) | SpecificData::Socket(_) | SpecificData::Event(_)) => {
+                Err(KernelError::PositionalIoNotSupported)
+            }
+            Some(SpecificData::BoundSocket(_) | SpecificData::Semaphore(_)) | None => {
+                unreachable!()
+            }
         }
     }
 
@@ -115,15 +213,162 @@ impl File {
         pt: &UserPageTable,
         src: &Validated<UserSlice<u8>>,
     ) -> Result<usize, KernelError> {
+        self.write_generic((pt, src).into())
+    }
+
+    /// Writes the kernel buffer `src` to file `f`.
+    pub fn write_kernel(&self, src: &[u8]) -> Result<usize, KernelError> {
+        self.write_generic(src.into())
+    }
+
+    fn write_generic(&self, src: GenericSlice<u8>) -> Result<usize, KernelError> {
         if !self.data.writable {
             return Err(KernelError::FileDescriptorNotWritable);
         }
 
         match &self.data.data {
-            Some(SpecificData::Pipe(pipe)) => pipe.write(pt, src),
-            Some(SpecificData::Inode(inode)) => inode.write(pt, src),
-            Some(SpecificData::Device(device)) => device.write(pt, src),
+            Some(SpecificData::Pipe(pipe)) => pipe.write(src),
+            Some(SpecificData::Socket(socket)) => socket.write(src),
+            Some(SpecificData::Event(event)) => event.write(src),
+            Some(SpecificData::Inode(inode)) => inode.write(src),
+            Some(SpecificData::Device(device)) => device.write(src),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Moves up to `count` bytes from file `src` to file `dst` without copying
+    /// them through user space.
+    ///
+    /// Either `src` or `dst` must be a pipe, whose buffer is passed to the
+    /// other file directly.
+    pub fn splice(src: &Self, dst: &Self, count: usize) -> Result<usize, KernelError> {
+        if !src.data.readable {
+            return Err(KernelError::FileDescriptorNotReadable);
+        }
+        if !dst.data.writable {
+            return Err(KernelError::FileDescriptorNotWritable);
+        }
+
+        match (&src.data.data, &dst.data.data) {
+            (Some(SpecificData::Pipe(src_pipe)), Some(SpecificData::Pipe(dst_pipe)))
+                if src_pipe.is_same(dst_pipe) =>
+            {
+                Err(KernelError::InvalidSplice)
+            }
+            (Some(SpecificData::Pipe(pipe)), _) => {
+                pipe.splice_out(count, |buf| dst.write_kernel(buf))
+            }
+            (_, Some(SpecificData::Pipe(pipe))) => {
+                pipe.splice_in(count, |buf| src.read_kernel(buf))
+            }
+            (Some(_), Some(_)) => Err(KernelError::InvalidSplice),
             _ => unreachable!(),
         }
     }
+
+    /// Writes to file `f` at offset `off`, without changing the file offset.
+    pub fn write_at(
+        &self,
+        pt: &UserPageTable,
+        src: &Validated<UserSlice<u8>>,
+        off: usize,
+    ) -> Result<usize, KernelError> {
+        if !self.data.writable {
+            return Err(KernelError::FileDescriptorNotWritable);
+        }
+
+        match &self.data.data {
+            Some(SpecificData::Inode(inode)) => inode.write_at((pt, src).into(), off),
+            Some(SpecificData::Device(device)) => device.write_at((pt, src).into(), off),
+            Some(SpecificData::Pipe(_) | SpecificData::Socket(_) | SpecificData::Event(_)) => {
+                Err(KernelError::PositionalIoNotSupported)
+            }
+            Some(SpecificData::BoundSocket(_) | SpecificData::Semaphore(_)) | None => {
+                unreachable!()
+            }
+        }
+    }
+
+    /// Performs a device-specific operation on file `f`.
+    pub fn ioctl(&self, req: IoctlRequest, arg: usize) -> Result<usize, KernelError> {
+        match &self.data.data {
+            Some(SpecificData::Device(device)) => device.ioctl(req, arg),
+            Some(
+                SpecificData::Pipe(_)
+                | SpecificData::Socket(_)
+                | SpecificData::BoundSocket(_)
+                | SpecificData::Semaphore(_)
+                | SpecificData::Event(_)
+                | SpecificData::Inode(_),
+            ) => Err(KernelError::IoctlNotSupported),
+            None => unreachable!(),
+        }
+    }
+
+    /// Gets or sets the access hints of file `f`.
+    pub fn fcntl(&self, req: FcntlRequest, arg: usize) -> Result<usize, KernelError> {
+        match &self.data.data {
+            Some(SpecificData::Inode(inode)) => inode.fcntl(req, arg),
+            Some(
+                SpecificData::Pipe(_)
+                | SpecificData::Socket(_)
+                | SpecificData::BoundSocket(_)
+                | SpecificData::Semaphore(_)
+                | SpecificData::Event(_)
+                | SpecificData::Device(_),
+            ) => Err(KernelError::AccessHintNotSupported),
+            None => unreachable!(),
+        }
+    }
+
+    /// Starts accepting connections on bound socket `f`, keeping up to
+    /// `backlog` of them pending.
+    pub fn listen(&self, backlog: usize) -> Result<(), KernelError> {
+        match &self.data.data {
+            Some(SpecificData::BoundSocket(socket)) => socket.listen(backlog),
+            Some(_) => Err(KernelError::NotBoundSocket),
+            None => unreachable!(),
+        }
+    }
+
+    /// Waits for a connection to bound socket `f` and returns its socket.
+    pub fn accept(&self) -> Result<Self, KernelError> {
+        match &self.data.data {
+            Some(SpecificData::BoundSocket(socket)) => socket.accept(),
+            Some(_) => Err(KernelError::NotBoundSocket),
+            None => unreachable!(),
+        }
+    }
+
+    /// Waits until semaphore `f` is positive, and decrements it.
+    pub fn sem_wait(&self) -> Result<(), KernelError> {
+        match &self.data.data {
+            Some(SpecificData::Semaphore(sem)) => sem.wait(),
+            Some(_) => Err(KernelError::NotSemaphore),
+            None => unreachable!(),
+        }
+    }
+
+    /// Increments semaphore `f`.
+    pub fn sem_post(&self) -> Result<(), KernelError> {
+        match &self.data.data {
+            Some(SpecificData::Semaphore(sem)) => sem.post(),
+            Some(_) => Err(KernelError::NotSemaphore),
+            None => unreachable!(),
+        }
+    }
+
+    /// Returns the inode of file `f`, if it is a file in a file system.
+    pub fn inode(&self) -> Option<&Inode> {
+        match &self.data.data {
+            Some(SpecificData::Inode(inode)) => Some(inode.inode()),
+            Some(_) => None,
+            None => unreachable!(),
+        }
+    }
+
+    /// Returns `true` if `f` is a semaphore.
+    pub fn is_semaphore(&self) -> bool {
+        matches!(self.data.data, Some(SpecificData::Semaphore(_)))
+    }
 }
@@ -1,27 +1,48 @@
-use alloc::sync::Arc;
+use alloc::{boxed::Box, sync::Arc};
+use core::{cell::UnsafeCell, ptr, slice};
 
-use ov6_syscall::{UserMutSlice, UserSlice};
+use arrayvec::ArrayVec;
+use ov6_syscall::PIPE_BUF;
 
 use super::{File, FileData, FileDataArc, SpecificData};
 use crate::{
     error::KernelError,
-    memory::{addr::Validated, page::PageFrameAllocator, vm_user::UserPageTable},
-    sync::{SpinLock, SpinLockCondVar},
+    memory::{
+        PAGE_SIZE,
+        addr::{GenericMutSlice, GenericSlice},
+        fallible,
+        page::PageFrameAllocator,
+        vm_user::UserPageTable,
+    },
+    param::PIPE_PAGES,
+    sync::{SleepLock, SpinLock, SpinLockCondVar},
 };
 
-const PIPE_SIZE: usize = 512;
+const PIPE_SIZE: usize = PIPE_PAGES * PAGE_SIZE;
+const _: () = assert!(PIPE_BUF <= PIPE_SIZE);
 
 #[derive(Clone)]
 pub(super) struct PipeFile(Arc<PipeData, PageFrameAllocator>);
 
 struct PipeData {
+    /// Serializes the readers.
+    ///
+    /// Held while the bytes being read are accessed, which may be outside
+    /// `data` lock while splicing.
+    reader: SleepLock<()>,
+    /// Serializes the writers, so that a write is not interleaved with
+    /// others.
+    ///
+    /// Held while the bytes being written are accessed, which may be outside
+    /// `data` lock while splicing.
+    writer: SleepLock<()>,
     reader_cond: SpinLockCondVar,
     writer_cond: SpinLockCondVar,
+    ring: Ring,
     data: SpinLock<PipeDataLocked>,
 }
 
 struct PipeDataLocked {
-    data: [u8; PIPE_SIZE],
     /// Number of bytes read
     nread: usize,
     /// Number of bytes written
@@ -32,21 +53,45 @@ struct PipeDataLocked {
     write_open: bool,
 }
 
+type Page = Box<UnsafeCell<[u8; PAGE_SIZE]>, PageFrameAllocator>;
+
+/// Buffer of a pipe, made of pages used as a ring.
+///
+/// The bytes between `nread` and `nwrite` are accessed only by the reader,
+/// and the other bytes only by the writer. Since the two never overlap, the
+/// pages are accessed through raw pointers instead of the `data` lock.
+struct Ring {
+    pages: ArrayVec<Page, PIPE_PAGES>,
+}
+
+// SAFETY: the reader and the writer access disjoint parts of the pages.
+unsafe impl Sync for Ring {}
+
+impl Ring {
+    fn new() -> Result<Self, KernelError> {
+        let mut pages = ArrayVec::new();
+        for _ in 0..PIPE_PAGES {
+            let page = fallible::try_new_zeroed_box_in(PageFrameAllocator)?;
+            pages.push(unsafe { page.assume_init() });
+        }
+        Ok(Self { pages })
+    }
+
+    /// Returns the contiguous bytes at position `pos` in the ring, up to `len`
+    /// bytes.
+    ///
+    /// The returned bytes end at the end of the page containing `pos`.
+    fn segment(&self, pos: usize, len: usize) -> *mut [u8] {
+        let pos = pos % PIPE_SIZE;
+        let offset = pos % PAGE_SIZE;
+        let len = usize::min(len, PAGE_SIZE - offset);
+        let page = self.pages[pos / PAGE_SIZE].get().cast::<u8>();
+        ptr::slice_from_raw_parts_mut(page.wrapping_add(offset), len)
+    }
+}
+
 pub(super) fn new_file() -> Result<(File, File), KernelError> {
-    let pipe = PipeFile(Arc::new_in(
-        PipeData {
-            reader_cond: SpinLockCondVar::new(),
-            writer_cond: SpinLockCondVar::new(),
-            data: SpinLock::new(PipeDataLocked {
-                data: [0; PIPE_SIZE],
-                nread: 0,
-                nwrite: 0,
-                read_open: true,
-                write_open: true,
-            }),
-        },
-        PageFrameAllocator,
-    ));
+    let pipe = PipeFile::new()?;
 
     let f0 = File {
         data: FileDataArc::try_new(FileData {
@@ -67,6 +112,28 @@ pub(super) fn new_file() -> Result<(File, File), KernelError> {
 }
 
 impl PipeFile {
+    pub(super) fn new() -> Result<Self, KernelError> {
+        let data = PipeData {
+            reader: SleepLock::new(()),
+            writer: SleepLock::new(()),
+            reader_cond: SpinLockCondVar::new(),
+            writer_cond: SpinLockCondVar::new(),
+            ring: Ring::new()?,
+            data: SpinLock::new(PipeDataLocked {
+                nread: 0,
+                nwrite: 0,
+                read_open: true,
+                write_open: true,
+            }),
+        };
+        Ok(Self(fallible::try_new_arc_in(data, PageFrameAllocator)?))
+    }
+
+    /// Returns `true` if `self` and `other` are the ends of the same pipe.
+    pub(super) fn is_same(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+
     pub(super) fn close(&self, writable: bool) {
         let mut pi = self.0.data.lock();
         if writable {
@@ -78,12 +145,14 @@ impl PipeFile {
         }
     }
 
-    pub(super) fn write(
-        &self,
-        pt: &UserPageTable,
-        src: &Validated<UserSlice<u8>>,
-    ) -> Result<usize, KernelError> {
+    /// Writes `src` to the pipe.
+    ///
+    /// A write of at most [`PIPE_BUF`] bytes waits until the pipe has room for
+    /// all of them, so that the reader never sees a part of it.
+    pub(super) fn write(&self, src: GenericSlice<u8>) -> Result<usize, KernelError> {
+        let _writer = self.0.writer.wait_lock()?;
         let mut nwritten = 0;
+        let atomic = src.len() <= PIPE_BUF;
 
         let mut pipe = self.0.data.lock();
         while nwritten < src.len() {
@@ -93,29 +162,32 @@ impl PipeFile {
                 }
                 return Err(KernelError::BrokenPipe);
             }
-            if pipe.nwrite == pipe.nread + PIPE_SIZE {
+            let free = pipe.nread + PIPE_SIZE - pipe.nwrite;
+            // an atomic write needs room for all of the remaining bytes.
+            let room = if atomic { src.len() - nwritten } else { 1 };
+            if free < room {
                 self.0.reader_cond.notify();
                 pipe = self.0.writer_cond.wait(pipe).map_err(|(_guard, e)| e)?;
                 continue;
             }
 
-            let mut byte = [0];
-            pt.copy_u2k_bytes(&mut byte, &src.skip(nwritten).take(1));
+            let segment = self.0.ring.segment(pipe.nwrite, free);
+            let len = usize::min(segment.len(), src.len() - nwritten);
+            // SAFETY: the bytes after `nwrite` are not accessed by the reader,
+            // and the other writers are excluded by `writer` lock.
+            let dst = unsafe { slice::from_raw_parts_mut(segment.cast::<u8>(), len) };
+            UserPageTable::copy_x2k_bytes(dst, &src.skip(nwritten).take(len));
 
-            let idx = pipe.nwrite % PIPE_SIZE;
-            pipe.data[idx] = byte[0];
-            pipe.nwrite += 1;
-            nwritten += 1;
+            pipe.nwrite += len;
+            nwritten += len;
         }
         self.0.reader_cond.notify();
         Ok(nwritten)
     }
 
-    pub(super) fn read(
-        &self,
-        pt: &mut UserPageTable,
-        dst: &mut Validated<UserMutSlice<u8>>,
-    ) -> Result<usize, KernelError> {
+    pub(super) fn read(&self, mut dst: GenericMutSlice<u8>) -> Result<usize, KernelError> {
+        let _reader = self.0.reader.wait_lock()?;
+
         let mut pipe = self.0.data.lock();
         while pipe.nread == pipe.nwrite && pipe.write_open {
             pipe = self.0.reader_cond.wait(pipe).map_err(|(_guard, e)| e)?;
@@ -125,13 +197,124 @@ impl PipeFile {
             if pipe.nread == pipe.nwrite {
                 break;
             }
-            let ch = pipe.data[pipe.nread % PIPE_SIZE];
-            pipe.nread += 1;
+            let segment = self.0.ring.segment(pipe.nread, pipe.nwrite - pipe.nread);
+            let len = usize::min(segment.len(), dst.len() - nread);
+            // SAFETY: the bytes between `nread` and `nwrite` are not accessed by
+            // the writer, and the other readers are excluded by `reader` lock.
+            let src = unsafe { slice::from_raw_parts(segment.cast::<u8>(), len) };
+            UserPageTable::copy_k2x_bytes(&mut dst.skip_mut(nread).take_mut(len), src);
 
-            pt.copy_k2u_bytes(&mut dst.skip_mut(nread).take_mut(1), &[ch]);
-            nread += 1;
+            pipe.nread += len;
+            nread += len;
         }
         self.0.writer_cond.notify();
         Ok(nread)
     }
+
+    /// Moves up to `count` bytes out of the pipe by passing the pipe buffer to
+    /// `write` directly.
+    ///
+    /// Waits until the pipe has data, as [`Self::read()`] does. `write`
+    /// returns the number of bytes it consumed.
+    pub(super) fn splice_out<F>(&self, count: usize, mut write: F) -> Result<usize, KernelError>
+    where
+        F: FnMut(&[u8]) -> Result<usize, KernelError>,
+    {
+        let _reader = self.0.reader.wait_lock()?;
+
+        let mut pipe = self.0.data.lock();
+        while pipe.nread == pipe.nwrite && pipe.write_open {
+            pipe = self.0.reader_cond.wait(pipe).map_err(|(_guard, e)| e)?;
+        }
+        let mut total = 0;
+        while total < count && pipe.nread < pipe.nwrite {
+            let len = usize::min(count - total, pipe.nwrite - pipe.nread);
+            let segment = self.0.ring.segment(pipe.nread, len);
+            drop(pipe);
+
+            // The lock is released while writing, since `write` may sleep.
+            // SAFETY: the bytes between `nread` and `nwrite` are not accessed
+            // by the writer, and the other readers are excluded by `reader`
+            // lock.
+            let src = unsafe { &*segment };
+            let res = write(src);
+
+            pipe = self.0.data.lock();
+            match res {
+                Ok(0) => break,
+                Ok(n) => {
+                    pipe.nread += n;
+                    total += n;
+                    self.0.writer_cond.notify();
+                    if n < src.len() {
+                        break;
+                    }
+                }
+                Err(_) if total > 0 => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(total)
+    }
+
+    /// Moves up to `count` bytes into the pipe by passing the pipe buffer to
+    /// `read` directly.
+    ///
+    /// Waits until the pipe has space, as [`Self::write()`] does. `read`
+    /// returns the number of bytes it filled, which is 0 at the end of its
+    /// input.
+    pub(super) fn splice_in<F>(&self, count: usize, mut read: F) -> Result<usize, KernelError>
+    where
+        F: FnMut(&mut [u8]) -> Result<usize, KernelError>,
+    {
+        let _writer = self.0.writer.wait_lock()?;
+
+        let mut pipe = self.0.data.lock();
+        let mut total = 0;
+        while total < count {
+            if !pipe.read_open {
+                if total > 0 {
+                    break;
+                }
+                return Err(KernelError::BrokenPipe);
+            }
+            if pipe.nwrite == pipe.nread + PIPE_SIZE {
+                if total > 0 {
+                    break;
+                }
+                pipe = self.0.writer_cond.wait(pipe).map_err(|(_guard, e)| e)?;
+                continue;
+            }
+
+            let free = pipe.nread + PIPE_SIZE - pipe.nwrite;
+            let segment = self
+                .0
+                .ring
+                .segment(pipe.nwrite, usize::min(count - total, free));
+            drop(pipe);
+
+            // The lock is released while reading, since `read` may sleep.
+            // SAFETY: the bytes after `nwrite` are not accessed by the reader,
+            // and the other writers are excluded by `writer` lock.
+            let dst = unsafe { &mut *segment };
+            let len = dst.len();
+            let res = read(dst);
+
+            pipe = self.0.data.lock();
+            match res {
+                Ok(0) => break,
+                Ok(n) => {
+                    pipe.nwrite += n;
+                    total += n;
+                    self.0.reader_cond.notify();
+                    if n < len {
+                        break;
+                    }
+                }
+                Err(_) if total > 0 => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(total)
+    }
 }
@@ -0,0 +1,98 @@
+//! Counting semaphores.
+//!
+//! A semaphore is identified by a key chosen by the processes sharing it. It
+//! is accessed through a file, so that it is inherited across `fork` like
+//! the other files. The semaphore occupies a slot of the semaphore table
+//! while any file refers to it.
+
+use super::{File, FileData, FileDataArc, SpecificData};
+use crate::{
+    error::KernelError,
+    param::NSEMAPHORE,
+    sync::{SpinLock, SpinLockCondVar},
+};
+
+struct Slot {
+    /// Key of the semaphore, or `None` if the slot is free.
+    key: Option<u64>,
+    /// Number of files referring to the semaphore.
+    refs: usize,
+    value: usize,
+}
+
+static SLOTS: SpinLock<[Slot; NSEMAPHORE]> = SpinLock::new(
+    [const {
+        Slot {
+            key: None,
+            refs: 0,
+            value: 0,
+        }
+    }; NSEMAPHORE],
+);
+
+/// Notified when the semaphore is posted.
+static POSTED: [SpinLockCondVar; NSEMAPHORE] = [const { SpinLockCondVar::new() }; NSEMAPHORE];
+
+pub(super) struct SemaphoreFile {
+    slot: usize,
+}
+
+/// Opens the semaphore of `key`.
+///
+/// If no semaphore has the key, a semaphore of `value` is created.
+pub(super) fn open(key: u64, value: usize) -> Result<File, KernelError> {
+    let mut slots = SLOTS.lock();
+    let slot = if let Some(i) = slots.iter().position(|s| s.key == Some(key)) {
+        i
+    } else {
+        let i = slots
+            .iter()
+            .position(|s| s.key.is_none())
+            .ok_or(KernelError::NoFreeSemaphore)?;
+        slots[i].key = Some(key);
+        slots[i].value = value;
+        i
+    };
+    slots[slot].refs += 1;
+    drop(slots);
+
+    let data = FileDataArc::try_new(FileData {
+        readable: false,
+        writable: false,
+        data: Some(SpecificData::Semaphore(SemaphoreFile { slot })),
+    })?;
+    Ok(File { data })
+}
+
+impl SemaphoreFile {
+    pub(super) fn close(&self) {
+        let mut slots = SLOTS.lock();
+        let slot = &mut slots[self.slot];
+        slot.refs -= 1;
+        if slot.refs == 0 {
+            slot.key = None;
+        }
+    }
+
+    /// Waits until the value is positive, and decrements it.
+    pub(super) fn wait(&self) -> Result<(), KernelError> {
+        let mut slots = SLOTS.lock();
+        while slots[self.slot].value == 0 {
+            slots = POSTED[self.slot].wait(slots).map_err(|(_guard, e)| e)?;
+        }
+        slots[self.slot].value -= 1;
+        Ok(())
+    }
+
+    /// Increments the value, waking the waiters.
+    pub(super) fn post(&self) -> Result<(), KernelError> {
+        let mut slots = SLOTS.lock();
+        let slot = &mut slots[self.slot];
+        slot.value = slot
+            .value
+            .checked_add(1)
+            .ok_or(KernelError::SemaphoreOverflow)?;
+        POSTED[self.slot].notify();
+        Ok(())
+    }
+}
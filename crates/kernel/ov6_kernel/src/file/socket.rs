@@ -0,0 +1,191 @@
+//! Local stream sockets.
+//!
+//! A connected socket is bidirectional, and is made of two pipe buffers: the
+//! one it reads from and the one the peer reads from.
+//!
+//! A socket can be bound to a path, where an inode of type [`T_SOCK`] is
+//! created. While the bound socket is open, it occupies a slot of the bound
+//! socket table, keyed by the inode. Connecting to the path creates a
+//! connected pair of sockets, and queues one of them in the slot until it is
+//! accepted.
+//!
+//! [`T_SOCK`]: crate::fs::T_SOCK
+
+use arrayvec::ArrayVec;
+use ov6_syscall::Stat;
+
+use super::{File, FileData, FileDataArc, SpecificData, pipe::PipeFile};
+use crate::{
+    error::KernelError,
+    fs::{DeviceNo, Inode, InodeNo},
+    memory::addr::{GenericMutSlice, GenericSlice},
+    param::{MAX_LISTEN_BACKLOG, NLOCAL_SOCKET},
+    sync::{SpinLock, SpinLockCondVar},
+};
+
+pub(super) struct SocketFile {
+    rx: PipeFile,
+    tx: PipeFile,
+}
+
+/// Creates a pair of sockets connected to each other.
+pub(super) fn new_pair() -> Result<(File, File), KernelError> {
+    let a_to_b = PipeFile::new()?;
+    let b_to_a = PipeFile::new()?;
+
+    let a = new_file(SocketFile {
+        rx: b_to_a.clone(),
+        tx: a_to_b.clone(),
+    })?;
+    let b = new_file(SocketFile {
+        rx: a_to_b,
+        tx: b_to_a,
+    })?;
+
+    Ok((a, b))
+}
+
+fn new_file(socket: SocketFile) -> Result<File, KernelError> {
+    Ok(File {
+        data: FileDataArc::try_new(FileData {
+            readable: true,
+            writable: true,
+            data: Some(SpecificData::Socket(socket)),
+        })?,
+    })
+}
+
+impl SocketFile {
+    pub(super) fn close(&self) {
+        self.rx.close(false);
+        self.tx.close(true);
+    }
+
+    pub(super) fn write(&self, src: GenericSlice<u8>) -> Result<usize, KernelError> {
+        self.tx.write(src)
+    }
+
+    pub(super) fn read(&self, dst: GenericMutSlice<u8>) -> Result<usize, KernelError> {
+        self.rx.read(dst)
+    }
+}
+
+struct Slot {
+    /// Inode that the socket is bound to, or `None` if the slot is free.
+    key: Option<(DeviceNo, InodeNo)>,
+    /// Maximum number of pending connections, or 0 if not listening.
+    backlog: usize,
+    /// Accepting ends of the connections not accepted yet.
+    pending: ArrayVec<File, MAX_LISTEN_BACKLOG>,
+}
+
+static SLOTS: SpinLock<[Slot; NLOCAL_SOCKET]> = SpinLock::new(
+    [const {
+        Slot {
+            key: None,
+            backlog: 0,
+            pending: ArrayVec::new_const(),
+        }
+    }; NLOCAL_SOCKET],
+);
+
+/// Notified when a connection is queued in the slot, or the slot is freed.
+static PENDING_COND: [SpinLockCondVar; NLOCAL_SOCKET] =
+    [const { SpinLockCondVar::new() }; NLOCAL_SOCKET];
+
+/// A socket bound to a path.
+pub(super) struct BoundSocketFile {
+    slot: usize,
+    inode: Inode,
+}
+
+/// Binds a new socket to the inode `inode`, whose number is `ino` on device
+/// `dev`.
+pub(super) fn bind(dev: DeviceNo, ino: InodeNo, inode: Inode) -> Result<File, KernelError> {
+    let mut slots = SLOTS.lock();
+    let slot = slots.iter().position(|s| s.key.is_none());
+    if let Some(i) = slot {
+        slots[i].key = Some((dev, ino));
+    }
+    drop(slots);
+
+    let Some(slot) = slot else {
+        super::common::close_inode(inode);
+        return Err(KernelError::NoFreeListener);
+    };
+
+    let data = FileDataArc::try_new(FileData {
+        readable: false,
+        writable: false,
+        data: Some(SpecificData::BoundSocket(BoundSocketFile { slot, inode })),
+    })?;
+    Ok(File { data })
+}
+
+/// Connects to the socket bound to the inode `ino` on device `dev`.
+///
+/// Returns the connecting end of a new connection, whose other end is
+/// queued until it is accepted.
+pub(super) fn connect(dev: DeviceNo, ino: InodeNo) -> Result<File, KernelError> {
+    let (file, peer) = new_pair()?;
+
+    let mut slots = SLOTS.lock();
+    let (i, slot) = slots
+        .iter_mut()
+        .enumerate()
+        .find(|(_, s)| s.key == Some((dev, ino)) && s.backlog > 0)
+        .ok_or(KernelError::ConnectionRefused)?;
+    if slot.pending.len() >= slot.backlog {
+        return Err(KernelError::ListenBacklogFull);
+    }
+    slot.pending.push(peer);
+    PENDING_COND[i].notify();
+    Ok(file)
+}
+
+impl BoundSocketFile {
+    pub(super) fn close(self) {
+        let pending = {
+            let mut slots = SLOTS.lock();
+            let slot = &mut slots[self.slot];
+            slot.key = None;
+            slot.backlog = 0;
+            PENDING_COND[self.slot].notify();
+            slot.pending.take()
+        };
+        // the connecting ends see the end of file once the pending ends are
+        // dropped.
+        drop(pending);
+        super::common::close_inode(self.inode);
+    }
+
+    pub(super) fn stat(&self) -> Result<Stat, KernelError> {
+        super::common::stat_inode(&self.inode)
+    }
+
+    /// Starts accepting connections, keeping up to `backlog` of them pending.
+    pub(super) fn listen(&self, backlog: usize) -> Result<(), KernelError> {
+        if !(1..=MAX_LISTEN_BACKLOG).contains(&backlog) {
+            return Err(KernelError::InvalidBacklog(backlog));
+        }
+        SLOTS.lock()[self.slot].backlog = backlog;
+        Ok(())
+    }
+
+    /// Waits for a connection and returns its accepting end.
+    pub(super) fn accept(&self) -> Result<File, KernelError> {
+        let mut slots = SLOTS.lock();
+        loop {
+            let slot = &mut slots[self.slot];
+            if slot.backlog == 0 {
+                return Err(KernelError::NotListening);
+            }
+            if !slot.pending.is_empty() {
+                return Ok(slot.pending.remove(0));
+            }
+            slots = PENDING_COND[self.slot]
+                .wait(slots)
+                .map_err(|(_guard, e)| e)?;
+        }
+    }
+}
@@ -15,6 +15,7 @@ use slab_allocator::SlabAllocator;
 use super::{DeviceNo, repr::FS_BLOCK_SIZE, virtio_disk};
 use crate::{
     param::NBUF,
+    proc::{self, io::IoDirection},
     sync::{SleepLock, SpinLock},
 };
 
@@ -24,23 +25,26 @@ impl BlockDevice<FS_BLOCK_SIZE> for VirtioDiskDevice {
     type Error = Infallible;
 
     fn read(&self, block_index: usize, data: &mut [u8; FS_BLOCK_SIZE]) -> Result<(), Self::Error> {
+        proc::io::account(IoDirection::Read);
         virtio_disk::read(block_index * FS_BLOCK_SIZE, data);
         Ok(())
     }
 
     fn write(&self, block_index: usize, data: &[u8; FS_BLOCK_SIZE]) -> Result<(), Self::Error> {
+        proc::io::account(IoDirection::Write);
         virtio_disk::write(block_index * FS_BLOCK_SIZE, data);
         Ok(())
     }
 }
 
-type BlockMutex = SleepLock<BlockData<FS_BLOCK_SIZE>>;
-type LruMutex = SpinLock<LruMap<BlockMutex, BlockAllocator>>;
+pub(super) type BlockMutex = SleepLock<BlockData<FS_BLOCK_SIZE>>;
+pub(super) type LruMutex = SpinLock<LruMap<BlockMutex, BlockAllocator>>;
+pub(super) type Cache = BlockIoCache<VirtioDiskDevice, LruMutex>;
 
 type LruMapAllocLayout = block_io::LruMapALlocLayout<BlockMutex, BlockAllocator>;
 type LruValueAllocLayout = block_io::LruValueAllocLayout<BlockMutex>;
 
-static VIRTIO_DISK_CACHE: OnceInit<BlockIoCache<VirtioDiskDevice, LruMutex>> = OnceInit::new();
+static VIRTIO_DISK_CACHE: OnceInit<Cache> = OnceInit::new();
 static LRU_MAP_ALLOCATOR: OnceInit<SpinLock<SlabAllocator<LruMapAllocLayout>>> = OnceInit::new();
 static LRU_VALUE_ALLOCATOR: OnceInit<SpinLock<SlabAllocator<LruValueAllocLayout>>> =
     OnceInit::new();
@@ -87,14 +91,19 @@ pub(super) fn init() {
     ));
 }
 
-/// Gets the block buffer with the given device number and block number.
-pub(super) fn get(dev: DeviceNo, block_index: usize) -> BlockRef {
+/// Gets the block cache of the given device number.
+pub(super) fn cache(dev: DeviceNo) -> &'static Cache {
     match dev {
-        DeviceNo::ROOT => VIRTIO_DISK_CACHE.get().get(block_index),
+        DeviceNo::ROOT => VIRTIO_DISK_CACHE.get(),
         _ => panic!("unknown device: dev={}", dev.value()),
     }
 }
 
+/// Gets the block buffer with the given device number and block number.
+pub(super) fn get(dev: DeviceNo, block_index: usize) -> BlockRef {
+    cache(dev).get(block_index)
+}
+
 #[derive(Clone)]
 pub(super) struct BlockAllocator;
 
@@ -0,0 +1,128 @@
+//! Quick consistency check of the file system.
+//!
+//! Run when mounting a file system that was not cleanly unmounted. Only the
+//! inode table, the inode map and the block bitmap are inspected; directory
+//! contents are not traversed.
+
+use core::{array, fmt};
+
+use super::{
+    BlockNo, DeviceNo, InodeNo, SuperBlock, Tx, inode_map,
+    repr::{
+        self, BITS_PER_BLOCK, NUM_DIRECT_REFS, NUM_INDIRECT_REFS, T_DEVICE, T_DIR, T_FILE, T_SOCK,
+    },
+};
+use crate::println;
+
+/// Summary of [`quick_check()`].
+#[derive(Debug, Default)]
+pub(super) struct CheckReport {
+    /// Number of allocated inodes.
+    pub(super) inodes: usize,
+    /// Number of data blocks referenced by the inodes.
+    pub(super) blocks: usize,
+    /// Number of inconsistencies found.
+    pub(super) problems: usize,
+}
+
+struct Checker<'a> {
+    tx: &'a Tx<'a, true>,
+    dev: DeviceNo,
+    sb: &'a SuperBlock,
+    report: CheckReport,
+}
+
+impl Checker<'_> {
+    fn problem(&mut self, ino: InodeNo, args: fmt::Arguments<'_>) {
+        println!("fs: check: inode {ino}: {args}");
+        self.report.problems += 1;
+    }
+
+    fn is_allocated(&self, bn: BlockNo) -> bool {
+        let mut br = self.tx.get_block(self.dev, self.sb.bmap_block(bn.value()));
+        let Ok(bg) = br.lock().read();
+        bg.data::<repr::BmapBlock>()
+            .is_allocated(bn.as_index() % BITS_PER_BLOCK)
+    }
+
+    /// Checks a block referenced by the inode `ino`.
+    ///
+    /// Returns `false` if the block number is out of the data area.
+    fn check_block(&mut self, ino: InodeNo, bn: BlockNo) -> bool {
+        if !(self.sb.data_start()..self.sb.size.get()).contains(&bn.value()) {
+            self.problem(ino, format_args!("block {bn} out of data area"));
+            return false;
+        }
+        if !self.is_allocated(bn) {
+            self.problem(ino, format_args!("block {bn} marked free in bitmap"));
+        }
+        self.report.blocks += 1;
+        true
+    }
+
+    fn check_inode(&mut self, ino: InodeNo, inode: &repr::Inode) {
+        let ty = inode.ty.get();
+        if ![T_DIR, T_FILE, T_DEVICE, T_SOCK].contains(&ty) {
+            self.problem(ino, format_args!("invalid type {ty}"));
+            return;
+        }
+        if ino == InodeNo::ROOT && ty != T_DIR {
+            self.problem(ino, format_args!("root is not a directory"));
+        }
+        // orphans are freed after the check.
+        if inode.nlink.get() == 0 && !self.sb.is_orphan(ino) {
+            self.problem(ino, format_args!("allocated but not linked"));
+        }
+
+        let mut addrs = [None; NUM_DIRECT_REFS + 1];
+        inode.read_addrs(&mut addrs);
+        for bn in addrs[..NUM_DIRECT_REFS].iter().flatten() {
+            self.check_block(ino, *bn);
+        }
+        if let Some(ind_bn) = addrs[NUM_DIRECT_REFS]
+            && self.check_block(ino, ind_bn)
+        {
+            let tx = self.tx;
+            let mut br = tx.get_block(self.dev, ind_bn);
+            let Ok(bg) = br.lock().read();
+            let ind = bg.data::<repr::IndirectBlock>();
+            let ind_addrs: [_; NUM_INDIRECT_REFS] = array::from_fn(|i| ind.get(i));
+            drop(bg);
+            for bn in ind_addrs.iter().flatten() {
+                self.check_block(ino, *bn);
+            }
+        }
+    }
+}
+
+/// Checks that every allocated inode has a valid type and refers only to
+/// data blocks marked as allocated, and that the inode map agrees with the
+/// inode types.
+pub(super) fn quick_check(tx: &Tx<true>, dev: DeviceNo, sb: &SuperBlock) -> CheckReport {
+    let mut checker = Checker {
+        tx,
+        dev,
+        sb,
+        report: CheckReport::default(),
+    };
+
+    for inum in 1..sb.ninodes.get() {
+        let ino = InodeNo::new(inum);
+        let mut br = tx.get_block(dev, sb.inode_block(ino));
+        let Ok(bg) = br.lock().read();
+        let inode = bg.data::<repr::InodeBlock>().inode(ino);
+        if sb.has_inode_bitmap() && inode_map::is_marked(tx, dev, sb, ino) == inode.is_free() {
+            checker.problem(ino, format_args!("inode map does not match the type"));
+        }
+        if inode.is_free() {
+            if ino == InodeNo::ROOT {
+                checker.problem(ino, format_args!("root is free"));
+            }
+            continue;
+        }
+        checker.report.inodes += 1;
+        checker.check_inode(ino, inode);
+    }
+
+    checker.report
+}
@@ -1,7 +1,7 @@
 use safe_cast::{SafeInto as _, to_u32};
 
 use super::{
-    BlockNo, DeviceNo, SUPER_BLOCK, Tx,
+    BlockNo, DeviceNo, SUPER_BLOCK, Tx, fs_size,
     repr::{self, BITS_PER_BLOCK},
 };
 use crate::error::KernelError;
@@ -16,11 +16,12 @@ fn block_zero(tx: &Tx<false>, dev: DeviceNo, block_no: BlockNo) {
 /// Returns None if out of disk space.
 pub fn alloc(tx: &Tx<false>, dev: DeviceNo) -> Result<BlockNo, KernelError> {
     let sb = SUPER_BLOCK.get();
-    for bn0 in (0..sb.size).step_by(BITS_PER_BLOCK) {
+    let size = fs_size();
+    for bn0 in (0..size).step_by(BITS_PER_BLOCK) {
         let mut br = tx.get_block(dev, sb.bmap_block(bn0));
         let Ok(mut bg) = br.lock().read();
         let Some(bni) = (0..to_u32!(BITS_PER_BLOCK))
-            .take_while(|bni| bn0 + *bni < sb.size)
+            .take_while(|bni| bn0 + *bni < size)
             .find(|&bni| {
                 !bg.data::<repr::BmapBlock>().is_allocated(bni.safe_into()) // block is free (bit = 0)
             })
@@ -0,0 +1,252 @@
+//! Directory operations of ov6fs, called through the VFS.
+//!
+//! Lookups and reads run in read-only transactions, while the operations
+//! that modify directories each run in a transaction of their own.
+
+use dataview::PodMethods as _;
+use ov6_types::os_str::OsStr;
+
+use super::{
+    DeviceNo, Inode, Tx, TxInode, TxToken, begin_readonly_tx,
+    name_cache::{self, Lookup},
+    orphan,
+    repr::{self, T_DEVICE, T_FILE},
+    with_write_tx,
+};
+use crate::error::KernelError;
+
+/// Looks up `name` in the directory `dir`.
+///
+/// The name is looked up in the name cache first, and the directory is
+/// searched only on a miss.
+pub(super) fn lookup(dir: &Inode, name: &OsStr) -> Result<Inode, KernelError> {
+    let tx = begin_readonly_tx();
+    let mut ip = dir.clone().into_tx(&tx);
+    let mut lip = ip.wait_lock()?;
+    let Some(mut dip) = lip.as_dir() else {
+        return Err(KernelError::NonDirectoryPathComponent);
+    };
+
+    let (dev, dir_ino) = (dip.dev(), dip.ino());
+    let next = match name_cache::lookup(dev, dir_ino, name) {
+        Lookup::Found(ino) => TxInode::get(&tx, dev, ino)?,
+        Lookup::NotFound => return Err(KernelError::FsEntryNotFound),
+        Lookup::Miss => {
+            let found = dip.lookup(name)?;
+            name_cache::insert(dev, dir_ino, name, found.as_ref().map(|(ip, _)| ip.ino()));
+            let Some((next, _off)) = found else {
+                return Err(KernelError::FsEntryNotFound);
+            };
+            next
+        }
+    };
+    Ok(Inode::from_tx(&next))
+}
+
+/// Returns the first entry of the directory `dir` at or after `off`, and the
+/// offset of the entry following it.
+pub(super) fn readdir(
+    dir: &Inode,
+    off: usize,
+) -> Result<Option<(repr::DirEntry, usize)>, KernelError> {
+    let tx = begin_readonly_tx();
+    let mut ip = dir.clone().into_tx(&tx);
+    let mut lip = ip.wait_lock()?;
+    let Some(mut dip) = lip.as_dir() else {
+        return Err(KernelError::NonDirectoryPathComponent);
+    };
+    let next = ov6_fs_path::next_entry(&mut dip, off)?;
+    Ok(next.map(|(off, de)| (de, off + ov6_fs_path::DIR_ENTRY_SIZE)))
+}
+
+/// Creates an inode of type `ty` named `name` in the directory `dir`.
+pub(super) fn create(
+    token: &mut TxToken,
+    dir: &Inode,
+    name: &OsStr,
+    ty: u16,
+    major: DeviceNo,
+    minor: u16,
+) -> Result<Inode, KernelError> {
+    with_write_tx(token, |tx| {
+        let mut dir_ip = dir.clone().into_tx(tx);
+
+        // The directory stays locked from the lookup until the new entry is
+        // written, so that creates of the same name are ordered.
+        let mut dir_lip = dir_ip.force_wait_lock();
+        let mut dir_dp = dir_lip
+            .as_dir()
+            .ok_or(KernelError::NonDirectoryPathComponent)?;
+
+        if let Some((mut file_ip, _off)) = dir_dp.lookup(name)? {
+            let file_lip = file_ip.force_wait_lock();
+            if ty == T_FILE && (file_lip.data().ty == T_FILE || file_lip.data().ty == T_DEVICE) {
+                return Ok(Inode::from_locked(&file_lip));
+            }
+            return Err(KernelError::CreateAlreadyExists);
+        }
+
+        let mut file_ip = TxInode::alloc(tx, dir_dp.dev(), ty)?;
+        let mut file_lip = file_ip.force_wait_lock();
+        file_lip.data_mut().major = major;
+        file_lip.data_mut().minor = minor;
+        file_lip.data_mut().nlink = 0; // update after
+        file_lip.update();
+
+        if let Some(mut child_dp) = file_lip.as_dir() {
+            // Create "." and ".." entries
+            child_dp.link(OsStr::new("."), child_dp.ino())?;
+            child_dp.link(OsStr::new(".."), dir_dp.ino())?;
+        }
+
+        dir_dp.link(name, file_lip.ino())?;
+
+        if file_lip.is_dir() {
+            // now that success is guaranteed:
+            dir_lip.data_mut().nlink += 1; // for ".."
+            dir_lip.update();
+        }
+
+        file_lip.data_mut().nlink = 1;
+        file_lip.update();
+
+        Ok(Inode::from_locked(&file_lip))
+    })
+}
+
+/// Creates a file with no links on the device of the directory `dir`.
+///
+/// The file is recorded in the orphan list until it is linked.
+pub(super) fn create_unnamed(token: &mut TxToken, dir: &Inode) -> Result<Inode, KernelError> {
+    with_write_tx(token, |tx| {
+        let mut dir_ip = dir.clone().into_tx(tx);
+        let dir_lip = dir_ip.force_wait_lock();
+        if !dir_lip.is_dir() {
+            return Err(KernelError::NonDirectoryPathComponent);
+        }
+        let dev = dir_lip.dev();
+        dir_lip.unlock();
+
+        let mut file_ip = TxInode::alloc(tx, dev, T_FILE)?;
+        let mut file_lip = file_ip.force_wait_lock();
+        file_lip.data_mut().major = DeviceNo::ROOT;
+        file_lip.data_mut().minor = 0;
+        file_lip.data_mut().nlink = 0;
+        file_lip.update();
+        orphan::add(tx, dev, file_lip.ino());
+        Ok(Inode::from_locked(&file_lip))
+    })
+}
+
+/// Removes the entry `name` from the directory `dir`.
+pub(super) fn unlink(
+    token: &mut TxToken,
+    dir: &Inode,
+    name: &OsStr,
+    is_rmdir: bool,
+) -> Result<(), KernelError> {
+    with_write_tx(token, |tx| {
+        let mut dir_ip = dir.clone().into_tx(tx);
+        let mut dir_lip = dir_ip.force_wait_lock();
+        let mut dir_dp = dir_lip
+            .as_dir()
+            .ok_or(KernelError::NonDirectoryPathComponent)?;
+
+        let (mut file_ip, off) = dir_dp.lookup(name)?.ok_or(KernelError::FsEntryNotFound)?;
+        let mut file_lip = file_ip.force_wait_lock();
+
+        assert!(file_lip.data().nlink > 0);
+        if let Some(mut file_dp) = file_lip.as_dir() {
+            if !is_rmdir {
+                return Err(KernelError::UnlinkDirectory);
+            }
+            if !file_dp.is_empty() {
+                return Err(KernelError::DirectoryNotEmpty);
+            }
+        } else if is_rmdir {
+            return Err(KernelError::RmdirNonDirectory);
+        }
+
+        let de = repr::DirEntry::zeroed();
+        dir_dp.get_inner().write_data(off, &de).unwrap();
+        name_cache::invalidate(dir_dp.dev(), dir_dp.ino(), name);
+
+        if file_lip.is_dir() {
+            // decrement reference to parent directory.
+            dir_dp.get_inner().data_mut().nlink -= 1;
+            dir_dp.get_inner().update();
+        }
+        dir_lip.unlock();
+        dir_ip.put();
+
+        file_lip.data_mut().nlink -= 1;
+        file_lip.update();
+        if file_lip.nlink() == 0 {
+            // freed when the last reference is dropped, which may be long
+            // after this transaction if the file is open.
+            orphan::add(tx, file_lip.dev(), file_lip.ino());
+        }
+
+        Ok(())
+    })
+}
+
+/// Adds an entry `name` referring to `inode` to the directory `dir`.
+pub(super) fn link(
+    token: &mut TxToken,
+    dir: &Inode,
+    name: &OsStr,
+    inode: &Inode,
+    allow_unlinked: bool,
+) -> Result<(), KernelError> {
+    if dir.dev() != inode.dev() {
+        return Err(KernelError::LinkCrossDevices);
+    }
+    with_write_tx(token, |tx| {
+        let mut old_ip = inode.clone().into_tx(tx);
+        let mut old_lip = old_ip.force_wait_lock();
+        if old_lip.is_dir() {
+            // hard links to directories would make the directory tree a graph.
+            return Err(KernelError::LinkDirectory);
+        }
+        if old_lip.nlink() == 0 && !allow_unlinked {
+            // unlinked by another process after the lookup.
+            return Err(KernelError::FsEntryNotFound);
+        }
+        if old_lip.nlink() == u16::MAX {
+            return Err(KernelError::TooManyLinks);
+        }
+        let was_unlinked = old_lip.nlink() == 0;
+        // Increment the link count before adding the entry so that the inode
+        // is never referenced by more entries than its link count.
+        old_lip.data_mut().nlink += 1;
+        old_lip.update();
+        old_lip.unlock();
+
+        let res = add_entry(tx, dir, name, &old_ip);
+        if res.is_err() {
+            let mut old_lip = old_ip.force_wait_lock();
+            old_lip.data_mut().nlink -= 1;
+            old_lip.update();
+        } else if was_unlinked {
+            orphan::remove(tx, old_ip.dev(), old_ip.ino());
+        }
+
+        res
+    })
+}
+
+/// Adds an entry named `name` referring to `ip` in the directory `dir`.
+fn add_entry<'tx>(
+    tx: &'tx Tx<false>,
+    dir: &Inode,
+    name: &OsStr,
+    ip: &TxInode<'tx, false>,
+) -> Result<(), KernelError> {
+    let mut dir_ip = dir.clone().into_tx(tx);
+    let mut dir_lip = dir_ip.force_wait_lock();
+    let Some(mut dir_dp) = dir_lip.as_dir() else {
+        return Err(KernelError::LinkToNonDirectory);
+    };
+    dir_dp.link(name, ip.ino())
+}
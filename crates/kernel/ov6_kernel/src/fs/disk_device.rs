@@ -0,0 +1,82 @@
+//! Raw access to the disk through device files.
+//!
+//! The whole root disk is exposed as a block device with the major number
+//! [`DeviceNo::DISK`]. As with `O_DIRECT`, the offset and the length of each
+//! transfer must be block-aligned.
+//!
+//! Blocks held in the block cache are read from and written through the
+//! cache, so that the device file and the file system see the same contents.
+//! Writing to the disk of a mounted file system may still corrupt it.
+
+use super::{DeviceNo, FS_BLOCK_SIZE, block_io, inode, page_cache, text_cache, virtio_disk};
+use crate::{
+    error::KernelError,
+    file::{self, Device},
+    memory::{
+        addr::{GenericMutSlice, GenericSlice},
+        vm_user::UserPageTable,
+    },
+};
+
+pub(super) fn init() {
+    file::register_device(
+        DeviceNo::DISK,
+        Device {
+            read,
+            write,
+            ioctl: None,
+            block: true,
+        },
+    );
+}
+
+/// Returns the number of bytes to transfer for a request of `len` bytes at
+/// `off`.
+fn transfer_len(off: usize, len: usize) -> Result<usize, KernelError> {
+    if off % FS_BLOCK_SIZE != 0 || len % FS_BLOCK_SIZE != 0 {
+        return Err(KernelError::UnalignedBlockIo);
+    }
+    Ok(usize::min(len, virtio_disk::capacity().saturating_sub(off)))
+}
+
+fn read(dst: &mut GenericMutSlice<u8>, off: usize) -> Result<usize, KernelError> {
+    let len = transfer_len(off, dst.len())?;
+    let mut buf = [0; FS_BLOCK_SIZE];
+    for i in (0..len).step_by(FS_BLOCK_SIZE) {
+        let mut br = block_io::get(DeviceNo::ROOT, (off + i) / FS_BLOCK_SIZE);
+        let bg = br.lock();
+        if bg.is_valid() {
+            // the cached block may be newer than the disk.
+            let Ok(bg) = bg.read();
+            buf.copy_from_slice(bg.bytes());
+        } else {
+            let Ok(()) = bg.read_uncached(&mut buf);
+        }
+        UserPageTable::copy_k2x_bytes(&mut dst.skip_mut(i).take_mut(FS_BLOCK_SIZE), &buf);
+    }
+    Ok(len)
+}
+
+fn write(src: &GenericSlice<u8>, off: usize) -> Result<usize, KernelError> {
+    let len = transfer_len(off, src.len())?;
+    let mut buf = [0; FS_BLOCK_SIZE];
+    for i in (0..len).step_by(FS_BLOCK_SIZE) {
+        UserPageTable::copy_x2k_bytes(&mut buf, &src.skip(i).take(FS_BLOCK_SIZE));
+        let mut br = block_io::get(DeviceNo::ROOT, (off + i) / FS_BLOCK_SIZE);
+        let bg = br.lock();
+        if bg.is_valid() {
+            let Ok(mut bg) = bg.read();
+            bg.bytes_mut().copy_from_slice(&buf);
+            let Ok(()) = bg.write();
+        } else {
+            let Ok(()) = bg.write_uncached(&buf);
+        }
+    }
+    if len > 0 {
+        // the cached inodes and pages may hold the old contents.
+        inode::invalidate_device(DeviceNo::ROOT);
+        page_cache::invalidate_device(DeviceNo::ROOT);
+        text_cache::invalidate_device(DeviceNo::ROOT);
+    }
+    Ok(len)
+}
@@ -0,0 +1,233 @@
+//! Inode and file operations of ov6fs, called through the VFS.
+
+use core::sync::atomic::Ordering;
+
+use ov6_syscall::{AccessHint, Stat};
+use ov6_types::os_str::OsStr;
+
+use super::{
+    DeviceNo, FS_BLOCK_SIZE, Inode, Ov6Fs, T_FILE, TxInode, TxToken, begin_readonly_tx,
+    begin_write_tx_sized, dir, force_begin_tx, repr,
+    vfs::{FileOps, InodeOps, OpenFile},
+    with_write_tx,
+};
+use crate::{
+    error::KernelError,
+    memory::addr::{GenericMutSlice, GenericSlice},
+    param::MAX_OP_BLOCKS,
+};
+
+impl InodeOps for Ov6Fs {
+    fn stat(&self, inode: &Inode) -> Result<Stat, KernelError> {
+        let tx = begin_readonly_tx();
+        let mut ip = inode.clone().into_tx(&tx);
+        let lip = ip.wait_lock()?;
+        let st = lip.stat()?;
+        drop(lip);
+        drop(ip);
+        Ok(st)
+    }
+
+    fn lookup(&self, dir: &Inode, name: &OsStr) -> Result<Inode, KernelError> {
+        dir::lookup(dir, name)
+    }
+
+    fn readdir(
+        &self,
+        dir: &Inode,
+        off: usize,
+    ) -> Result<Option<(repr::DirEntry, usize)>, KernelError> {
+        dir::readdir(dir, off)
+    }
+
+    fn create(
+        &self,
+        token: &mut TxToken,
+        dir: &Inode,
+        name: &OsStr,
+        ty: u16,
+        major: DeviceNo,
+        minor: u16,
+    ) -> Result<Inode, KernelError> {
+        dir::create(token, dir, name, ty, major, minor)
+    }
+
+    fn create_unnamed(&self, token: &mut TxToken, dir: &Inode) -> Result<Inode, KernelError> {
+        dir::create_unnamed(token, dir)
+    }
+
+    fn unlink(
+        &self,
+        token: &mut TxToken,
+        dir: &Inode,
+        name: &OsStr,
+        is_rmdir: bool,
+    ) -> Result<(), KernelError> {
+        dir::unlink(token, dir, name, is_rmdir)
+    }
+
+    fn link(
+        &self,
+        token: &mut TxToken,
+        dir: &Inode,
+        name: &OsStr,
+        inode: &Inode,
+        allow_unlinked: bool,
+    ) -> Result<(), KernelError> {
+        dir::link(token, dir, name, inode, allow_unlinked)
+    }
+
+    fn truncate(&self, token: &mut TxToken, inode: &Inode) -> Result<(), KernelError> {
+        with_write_tx(token, |tx| {
+            let mut ip = inode.clone().into_tx(tx);
+            let mut lip = ip.force_wait_lock();
+            if lip.ty() == T_FILE {
+                lip.truncate();
+            }
+            Ok(())
+        })
+    }
+
+    fn put(&self, inode: Inode) {
+        // most references are dropped while others are left, or while the
+        // inode still has links, which needs no transaction.
+        let Err(inode) = inode.put_without_tx() else {
+            return;
+        };
+        let tx = force_begin_tx();
+        inode.into_tx(&tx).put();
+    }
+}
+
+impl FileOps for Ov6Fs {
+    fn read(
+        &self,
+        file: &OpenFile,
+        mut dst: GenericMutSlice<u8>,
+        off: Option<usize>,
+        hint: AccessHint,
+    ) -> Result<usize, KernelError> {
+        let tx = begin_readonly_tx();
+        let mut ip = file.inode.clone().into_tx(&tx);
+        // the lock taken by `try_lock()` is released at once, as its borrow of
+        // `ip` can't be kept across the fallback below.
+        if ip.try_lock().is_err() {
+            // the inode is busy, possibly with a long write.
+            // read the written part of the file without waiting.
+            if let Some(res) = read_unlocked(file, &ip, &mut dst, off) {
+                return res;
+            }
+        }
+        let mut lip = ip.wait_lock()?;
+        let pos = off.unwrap_or_else(|| file.off.load(Ordering::Relaxed));
+        let res = match dst {
+            GenericMutSlice::User(pt, mut dst) if file.direct => lip.read_direct(pt, &mut dst, pos),
+            dst => lip.read_with_hint(dst, pos, hint),
+        };
+        if let Ok(sz) = res
+            && off.is_none()
+        {
+            file.off.fetch_add(sz, Ordering::Relaxed);
+        }
+        res
+    }
+
+    fn write(
+        &self,
+        file: &OpenFile,
+        src: GenericSlice<u8>,
+        off: Option<usize>,
+    ) -> Result<usize, KernelError> {
+        // write a few blocks at a time to avoid exceeding
+        // the maximum log transaction size, including
+        // i-node, indirect block, allocation blocks,
+        // and 2 blocks of slop for non-aligned writes.
+        // this really belongs lower down, since write_inode()
+        // might be writing a device like the console.
+        let max = ((MAX_OP_BLOCKS - 1 - 1 - 2) / 2) * FS_BLOCK_SIZE;
+        debug_assert_eq!(write_op_blocks(max), MAX_OP_BLOCKS);
+        let mut i = 0;
+        while i < src.len() {
+            let src = src.skip(i);
+            let len = usize::min(src.len(), max);
+            let src = src.take(len);
+
+            // reserve only the log blocks this chunk may write, so that
+            // small writers do not wait for each other's worst case.
+            let tx = begin_write_tx_sized(write_op_blocks(len))?;
+            let mut ip = file.inode.clone().into_tx(&tx);
+            let mut lip = ip.force_wait_lock();
+            let pos = off.map_or_else(|| file.off.load(Ordering::Relaxed), |off| off + i);
+            let res = match src {
+                GenericSlice::User(pt, src) if file.direct => lip.write_direct(pt, &src, pos),
+                src => lip.write(src, pos),
+            };
+            if let Ok(sz) = res
+                && off.is_none()
+            {
+                file.off.fetch_add(sz, Ordering::Relaxed);
+            }
+            lip.unlock();
+            ip.put();
+            tx.end();
+
+            match res {
+                Err(e) => return Err(e),
+                Ok(n) if n != src.len() => break,
+                Ok(_) => {}
+            }
+
+            i += src.len();
+        }
+        Ok(src.len())
+    }
+
+    fn access_hint(&self, inode: &Inode) -> Result<AccessHint, KernelError> {
+        let tx = begin_readonly_tx();
+        let mut ip = inode.clone().into_tx(&tx);
+        let lip = ip.wait_lock()?;
+        Ok(lip.access_hint())
+    }
+
+    fn set_access_hint(&self, inode: &Inode, hint: AccessHint) -> Result<(), KernelError> {
+        let tx = begin_readonly_tx();
+        let mut ip = inode.clone().into_tx(&tx);
+        let mut lip = ip.wait_lock()?;
+        lip.set_access_hint(hint);
+        Ok(())
+    }
+}
+
+/// Reads like [`Ov6Fs::read()`] without locking the inode.
+///
+/// Returns `None` if the inode must be locked to read.
+fn read_unlocked(
+    file: &OpenFile,
+    ip: &TxInode<'_, true>,
+    dst: &mut GenericMutSlice<u8>,
+    off: Option<usize>,
+) -> Option<Result<usize, KernelError>> {
+    if file.direct {
+        return None;
+    }
+    let pos = off.unwrap_or_else(|| file.off.load(Ordering::Relaxed));
+    let res = ip.read_unlocked(dst, pos)?;
+    if let Ok(sz) = res
+        && off.is_none()
+    {
+        // another reader of this file may have moved the offset.
+        file.off
+            .compare_exchange(pos, pos + sz, Ordering::Relaxed, Ordering::Relaxed)
+            .ok()?;
+    }
+    Some(res)
+}
+
+/// Returns the number of blocks a write of `len` bytes may modify.
+///
+/// Each data block touched, plus one for a non-aligned write, may be
+/// allocated along with its bitmap block. The i-node and the indirect block
+/// may also be modified.
+fn write_op_blocks(len: usize) -> usize {
+    (len.div_ceil(FS_BLOCK_SIZE) + 1) * 2 + 1 + 1
+}
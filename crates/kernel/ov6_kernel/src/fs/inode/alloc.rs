@@ -1,85 +1,51 @@
-use alloc::sync::{Arc, Weak};
-use core::{
-    alloc::{AllocError, Allocator, Layout},
-    mem::MaybeUninit,
-    ops::Deref,
-    ptr::NonNull,
-};
-
-use once_init::OnceInit;
-use ov6_kernel_params::NINODE;
-use slab_allocator::{ArcInnerLayout, SlabAllocator};
+use alloc::sync::Arc;
+use core::ops::Deref;
 
-use super::InodeData;
+use super::{InodeCell, InodeData, content::Published};
 use crate::{
     error::KernelError,
+    fs::vfs::InodePrivate,
+    memory::{fallible, heap::HeapAllocator},
     sync::{SleepLock, SpinLock},
 };
 
-type InodeDataLayout = ArcInnerLayout<SleepLock<Option<InodeData>>>;
-
-static ALLOCATOR: OnceInit<SpinLock<SlabAllocator<InodeDataLayout>>> = OnceInit::new();
-
-pub(super) fn init() {
-    static mut INODE_DATA_MEMORY: [MaybeUninit<InodeDataLayout>; NINODE] =
-        [const { MaybeUninit::uninit() }; NINODE];
-
-    unsafe {
-        let start = (&raw mut INODE_DATA_MEMORY[0]).cast::<InodeDataLayout>();
-        let end = start.add(NINODE);
-        let alloc = SlabAllocator::new(start..end);
-        ALLOCATOR.init(SpinLock::new(alloc));
-    }
-}
-
 #[derive(Clone)]
-struct InodeDataAllocator;
-
-unsafe impl Allocator for InodeDataAllocator {
-    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
-        assert_eq!(layout, Layout::new::<InodeDataLayout>());
-        let Some(ptr) = ALLOCATOR.get().lock().allocate() else {
-            return Err(AllocError);
-        };
-        Ok(NonNull::slice_from_raw_parts(ptr.cast(), layout.size()))
-    }
-
-    unsafe fn deallocate(&self, ptr: NonNull<u8>, _layout: Layout) {
-        unsafe { ALLOCATOR.get().lock().deallocate(ptr.cast()) }
-    }
-}
-
-#[derive(Clone)]
-pub(super) struct InodeDataArc(Arc<SleepLock<Option<InodeData>>, InodeDataAllocator>);
-
-pub(super) struct InodeDataWeak(Weak<SleepLock<Option<InodeData>>, InodeDataAllocator>);
+pub(super) struct InodeDataArc(Arc<InodeCell, HeapAllocator>);
 
 impl Deref for InodeDataArc {
     type Target = SleepLock<Option<InodeData>>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.0.data
     }
 }
 
 impl InodeDataArc {
-    pub(super) fn try_new(data: SleepLock<Option<InodeData>>) -> Result<Self, KernelError> {
-        let data = Arc::try_new_in(data, InodeDataAllocator)
-            .map_err(|AllocError| KernelError::NoFreeInodeDataInMemoryTableEntry)?;
+    pub(super) fn try_new() -> Result<Self, KernelError> {
+        let cell = InodeCell {
+            data: SleepLock::new(None),
+            published: SpinLock::new(Published::new()),
+        };
+        let data = fallible::try_new_arc_in(cell, HeapAllocator)?;
         Ok(Self(data))
     }
 
-    pub(super) fn strong_count(this: &Self) -> usize {
-        Arc::strong_count(&this.0)
+    pub(super) fn published(this: &Self) -> &SpinLock<Published> {
+        &this.0.published
     }
 
-    pub(super) fn downgrade(this: &Self) -> InodeDataWeak {
-        InodeDataWeak(Arc::downgrade(&this.0))
+    pub(super) fn into_private(self) -> InodePrivate {
+        self.0
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `data` is not the data of an ov6fs inode.
+    pub(super) fn from_private(data: InodePrivate) -> Self {
+        Self(data.downcast::<InodeCell>().ok().unwrap())
     }
-}
 
-impl InodeDataWeak {
-    pub(super) fn upgrade(this: &Self) -> Option<InodeDataArc> {
-        this.0.upgrade().map(InodeDataArc)
+    pub(super) fn strong_count(this: &Self) -> usize {
+        Arc::strong_count(&this.0)
     }
 }
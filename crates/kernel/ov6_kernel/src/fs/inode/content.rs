@@ -6,21 +6,85 @@
 //! listed in block `[NUM_DIRECT_REFS]`.
 
 use dataview::{Pod, PodMethods as _};
+use ov6_syscall::{AccessHint, UserMutSlice, UserSlice};
 
-use super::LockedTxInode;
+use super::{InodeDataArc, LockedTxInode, TxInode};
 use crate::{
     error::KernelError,
     fs::{
-        BlockNo, SUPER_BLOCK, data_block,
+        BlockNo, DeviceNo, SUPER_BLOCK, T_FILE, Tx, data_block, inode_map, name_cache, orphan,
+        page_cache,
         repr::{self, FS_BLOCK_SIZE, MAX_FILE, NUM_DIRECT_REFS, NUM_INDIRECT_REFS},
+        text_cache,
     },
     memory::{
-        addr::{GenericMutSlice, GenericSlice},
+        PAGE_SIZE, VirtAddr,
+        addr::{GenericMutSlice, GenericSlice, Validated},
+        page_table::PtEntryFlags,
         vm_user::UserPageTable,
     },
+    param::READAHEAD_PAGES,
 };
 
+/// Layout of the content of an inode, published for readers that do not
+/// lock the inode.
+#[derive(Clone, Copy)]
+pub(super) struct Published {
+    /// Incremented each time blocks of the content may be freed.
+    generation: u64,
+    /// Size and block addresses, or `None` if the inode is not read from
+    /// disk.
+    layout: Option<(usize, [Option<BlockNo>; NUM_DIRECT_REFS + 1])>,
+}
+
+impl Published {
+    pub(super) const fn new() -> Self {
+        Self {
+            generation: 0,
+            layout: None,
+        }
+    }
+}
+
+/// Returns the disk block address of the `i`th block of the content whose
+/// block addresses are `addrs`.
+///
+/// Returns `None` if there is no such block.
+fn data_block<const READ_ONLY: bool>(
+    tx: &Tx<READ_ONLY>,
+    dev: DeviceNo,
+    addrs: &[Option<BlockNo>; NUM_DIRECT_REFS + 1],
+    i: usize,
+) -> Option<BlockNo> {
+    if i < NUM_DIRECT_REFS {
+        return addrs[i];
+    }
+
+    let ind_bn = addrs[NUM_DIRECT_REFS]?;
+    let mut ind_br = tx.get_block(dev, ind_bn);
+    let Ok(ind_bg) = ind_br.lock().read();
+    ind_bg
+        .data::<repr::IndirectBlock>()
+        .get(i - NUM_DIRECT_REFS)
+}
+
 impl<const READ_ONLY: bool> LockedTxInode<'_, '_, READ_ONLY> {
+    /// Publishes the size and the block addresses of the content for
+    /// [`TxInode::read_unlocked()`].
+    pub(super) fn publish(&self) {
+        let data = self.data();
+        let mut published = InodeDataArc::published(&self.data).lock();
+        published.layout = Some((data.size as usize, data.addrs));
+    }
+
+    /// Withdraws the published layout before blocks of the content are
+    /// freed, so that the unlocked readers in progress retry with the lock.
+    fn unpublish(&self) {
+        let mut published = InodeDataArc::published(&self.data).lock();
+        published.generation += 1;
+        published.layout = None;
+    }
+
     /// Returns the disk block address of the `i`th **direct** block in inode.
     ///
     /// If there is no such block, `get_data_block()` allocates one.
@@ -95,11 +159,38 @@ impl<const READ_ONLY: bool> LockedTxInode<'_, '_, READ_ONLY> {
 
         panic!("out of range: ibn={i}");
     }
+
+    /// Returns the disk block address of the `i`th block in inode.
+    ///
+    /// Returns `None` if there is no such block.
+    fn data_block(&self, i: usize) -> Option<BlockNo> {
+        data_block(self.tx, self.dev, &self.data().addrs, i)
+    }
+
+    /// Returns the number of disk blocks allocated to the inode.
+    ///
+    /// The indirect block is counted as well as the data blocks. Holes in
+    /// the content are not counted.
+    pub fn allocated_blocks(&self) -> u32 {
+        let addrs = &self.data().addrs;
+        let mut count = addrs[..NUM_DIRECT_REFS].iter().flatten().count();
+        if let Some(ind_bn) = addrs[NUM_DIRECT_REFS] {
+            let mut ind_br = self.tx.get_block(self.dev, ind_bn);
+            let Ok(ind_bg) = ind_br.lock().read();
+            let ind = ind_bg.data::<repr::IndirectBlock>();
+            count += 1
+                + (0..NUM_INDIRECT_REFS)
+                    .filter(|&i| ind.get(i).is_some())
+                    .count();
+        }
+        count.try_into().unwrap()
+    }
 }
 
 impl LockedTxInode<'_, '_, false> {
     /// Truncates inode (discard contents).
     pub fn truncate(&mut self) {
+        self.unpublish();
         for bn in &mut self.locked.as_mut().unwrap().addrs[..NUM_DIRECT_REFS] {
             if let Some(bn) = bn.take() {
                 data_block::free(self.tx, self.dev, bn);
@@ -118,6 +209,8 @@ impl LockedTxInode<'_, '_, false> {
 
         self.data_mut().size = 0;
         self.update();
+        page_cache::invalidate(self.dev, self.ino);
+        text_cache::invalidate(self.dev, self.ino);
     }
 
     /// Copies a modified in-memory inode to disk.
@@ -130,15 +223,63 @@ impl LockedTxInode<'_, '_, false> {
         let Ok(mut bg) = br.lock().read();
         let dip = bg.data_mut::<repr::InodeBlock>().inode_mut(self.ino);
         self.data().write_repr(dip);
+        self.publish();
     }
 
     pub fn free(mut self) {
         self.data_mut().ty = 0;
         self.update();
+        self.unpublish();
+        inode_map::free(self.tx, self.dev, self.ino);
+        orphan::remove(self.tx, self.dev, self.ino);
+        name_cache::invalidate_dir(self.dev, self.ino);
         *self.locked = None;
     }
 }
 
+impl TxInode<'_, true> {
+    /// Reads the inode's data like [`LockedTxInode::read()`] without locking
+    /// the inode.
+    ///
+    /// Only the content up to the size published by the last update of the
+    /// inode is read, through the block cache, so a reader of the written
+    /// part of a file does not wait for a write in progress to the rest.
+    ///
+    /// Returns `None` if the read must be done with the inode locked: if the
+    /// inode is not read from disk yet, if `off` is at or past the published
+    /// size, or if blocks of the content may have been freed while reading.
+    pub fn read_unlocked(
+        &self,
+        dst: &mut GenericMutSlice<u8>,
+        off: usize,
+    ) -> Option<Result<usize, KernelError>> {
+        let Published { generation, layout } = *InodeDataArc::published(&self.data).lock();
+        let (size, addrs) = layout?;
+        if off >= size {
+            return None;
+        }
+        let len = usize::min(dst.len(), size - off);
+
+        let mut tot = 0;
+        while tot < len {
+            let off = off + tot;
+            // blocks below the size are always allocated.
+            let bn = data_block(self.tx, self.dev, &addrs, off / FS_BLOCK_SIZE)?;
+            let mut br = self.tx.get_block(self.dev, bn);
+            let Ok(bg) = br.lock().read();
+            let m = usize::min(len - tot, FS_BLOCK_SIZE - off % FS_BLOCK_SIZE);
+            let mut dst = dst.skip_mut(tot);
+            let mut dst = dst.take_mut(m);
+            UserPageTable::copy_k2x_bytes(&mut dst, &bg.bytes()[off % FS_BLOCK_SIZE..][..m]);
+            tot += m;
+        }
+
+        // the blocks read may have been freed and reused meanwhile.
+        let published = InodeDataArc::published(&self.data).lock();
+        (published.generation == generation).then_some(Ok(tot))
+    }
+}
+
 impl<const READ_ONLY: bool> LockedTxInode<'_, '_, READ_ONLY> {
     /// Reads the inode's data.
     ///
@@ -147,8 +288,25 @@ impl<const READ_ONLY: bool> LockedTxInode<'_, '_, READ_ONLY> {
     /// Returns the number of bytes successfully read.
     /// If the return value is less than the requested `n`,
     /// there was an error of some kind.
-    pub fn read(&mut self, mut dst: GenericMutSlice<u8>, off: usize) -> Result<usize, KernelError> {
+    pub fn read(&mut self, dst: GenericMutSlice<u8>, off: usize) -> Result<usize, KernelError> {
+        self.read_with_hint(dst, off, AccessHint::Normal)
+    }
+
+    /// Reads the inode's data like [`Self::read()`], using the page cache as
+    /// `hint` tells.
+    ///
+    /// If `hint` is [`AccessHint::Normal`], the hint of the inode is used.
+    pub fn read_with_hint(
+        &mut self,
+        mut dst: GenericMutSlice<u8>,
+        off: usize,
+        hint: AccessHint,
+    ) -> Result<usize, KernelError> {
         let data = self.data();
+        let hint = match hint {
+            AccessHint::Normal => data.access_hint,
+            hint => hint,
+        };
         let size = data.size as usize;
         if off > size || off.checked_add(dst.len()).is_none() {
             return Ok(0);
@@ -156,12 +314,18 @@ impl<const READ_ONLY: bool> LockedTxInode<'_, '_, READ_ONLY> {
         let len = usize::min(dst.len(), size - off);
         let mut dst = dst.take_mut(len);
 
+        let is_file = data.ty == T_FILE;
         let mut tot = 0;
         while tot < dst.len() {
             let off = off + tot;
             let mut dst = dst.skip_mut(tot);
-            let bn = match self.get_or_alloc_data_block(off / FS_BLOCK_SIZE) {
-                Ok(Some(bn)) => bn,
+            let res = if is_file {
+                self.read_page(&mut dst, off, hint)
+            } else {
+                None
+            };
+            match res.unwrap_or_else(|| self.read_block(&mut dst, off)) {
+                Ok(Some(m)) => tot += m,
                 Ok(None) => break,
                 Err(e) => {
                     if tot > 0 {
@@ -169,13 +333,154 @@ impl<const READ_ONLY: bool> LockedTxInode<'_, '_, READ_ONLY> {
                     }
                     return Err(e);
                 }
+            }
+        }
+        Ok(tot)
+    }
+
+    /// Copies the content at `off` to `dst` through the page cache.
+    ///
+    /// Returns the number of bytes copied, or `None` if the page cannot be
+    /// cached, or should not be cached because of `hint`.
+    fn read_page(
+        &self,
+        dst: &mut GenericMutSlice<u8>,
+        off: usize,
+        hint: AccessHint,
+    ) -> Option<Result<Option<usize>, KernelError>> {
+        let index = off / PAGE_SIZE;
+        let mut page = match hint {
+            AccessHint::Random => page_cache::get_filled(self.dev, self.ino, index)?,
+            _ => page_cache::get(self.dev, self.ino, index)?,
+        };
+        let contents = match page.get_or_fill(|buf| self.fill_page(buf, index)) {
+            Ok(contents) => contents,
+            Err(KernelError::NoFreePage) => return None,
+            Err(e) => return Some(Err(e)),
+        };
+        let m = usize::min(dst.len(), PAGE_SIZE - off % PAGE_SIZE);
+        let mut dst = dst.take_mut(m);
+        UserPageTable::copy_k2x_bytes(&mut dst, &contents[off % PAGE_SIZE..][..m]);
+
+        match hint {
+            AccessHint::Sequential => {
+                drop(page);
+                self.read_ahead(index);
+            }
+            AccessHint::NoReuse => {
+                // drop the page once it has been read to the end
+                let size = self.data().size as usize;
+                if off % PAGE_SIZE + m == PAGE_SIZE || off + m == size {
+                    page.drop_contents();
+                }
+            }
+            AccessHint::Normal | AccessHint::Random => {}
+        }
+        Some(Ok(Some(m)))
+    }
+
+    /// Reads the pages following the `index`th page into the page cache.
+    ///
+    /// Stops at the first page already cached, which was read ahead by an
+    /// earlier read.
+    fn read_ahead(&self, index: usize) {
+        let size = self.data().size as usize;
+        for i in (index + 1..=index + READAHEAD_PAGES).take_while(|i| i * PAGE_SIZE < size) {
+            let Some(mut page) = page_cache::get(self.dev, self.ino, i) else {
+                break;
+            };
+            if !matches!(page.read_ahead(|buf| self.fill_page(buf, i)), Ok(true)) {
+                break;
+            }
+        }
+    }
+
+    /// Fills `buf` with the content of the `index`th page.
+    ///
+    /// Holes and blocks past the end of the file are filled with zeros.
+    fn fill_page(&self, buf: &mut [u8; PAGE_SIZE], index: usize) {
+        const BLOCKS_PER_PAGE: usize = PAGE_SIZE / FS_BLOCK_SIZE;
+
+        for (j, chunk) in buf.chunks_exact_mut(FS_BLOCK_SIZE).enumerate() {
+            let i = index * BLOCKS_PER_PAGE + j;
+            let bn = if i < MAX_FILE {
+                self.data_block(i)
+            } else {
+                None
+            };
+            let Some(bn) = bn else {
+                chunk.fill(0);
+                continue;
             };
             let mut br = self.tx.get_block(self.dev, bn);
             let Ok(bg) = br.lock().read();
-            let m = usize::min(dst.len(), FS_BLOCK_SIZE - off % FS_BLOCK_SIZE);
-            let mut dst = dst.take_mut(m);
-            UserPageTable::copy_k2x_bytes(&mut dst, &bg.bytes()[off % FS_BLOCK_SIZE..][..m]);
-            tot += m;
+            chunk.copy_from_slice(bg.bytes());
+        }
+    }
+
+    /// Copies the content at `off` to `dst` through the block cache.
+    ///
+    /// Returns the number of bytes copied, or `None` if there is no block
+    /// at `off`.
+    fn read_block(
+        &mut self,
+        dst: &mut GenericMutSlice<u8>,
+        off: usize,
+    ) -> Result<Option<usize>, KernelError> {
+        let Some(bn) = self.get_or_alloc_data_block(off / FS_BLOCK_SIZE)? else {
+            return Ok(None);
+        };
+        let mut br = self.tx.get_block(self.dev, bn);
+        let Ok(bg) = br.lock().read();
+        let m = usize::min(dst.len(), FS_BLOCK_SIZE - off % FS_BLOCK_SIZE);
+        let mut dst = dst.take_mut(m);
+        UserPageTable::copy_k2x_bytes(&mut dst, &bg.bytes()[off % FS_BLOCK_SIZE..][..m]);
+        Ok(Some(m))
+    }
+
+    /// Reads the inode's data directly from the disk into user memory.
+    ///
+    /// Whole blocks are transferred without going through the block cache
+    /// and the page cache, unless the block is already in the block cache.
+    /// Falls back to [`Self::read()`] if `off` or `dst` is not block-aligned,
+    /// and for the last partial block of the file.
+    pub fn read_direct(
+        &mut self,
+        pt: &mut UserPageTable,
+        dst: &mut Validated<UserMutSlice<u8>>,
+        off: usize,
+    ) -> Result<usize, KernelError> {
+        let size = self.data().size as usize;
+        if off % FS_BLOCK_SIZE != 0 || dst.addr() % FS_BLOCK_SIZE != 0 || off >= size {
+            return self.read((pt, dst).into(), off);
+        }
+
+        let len = usize::min(dst.len(), size - off);
+        let mut tot = 0;
+        while len - tot >= FS_BLOCK_SIZE {
+            let Some(bn) = self.data_block((off + tot) / FS_BLOCK_SIZE) else {
+                break;
+            };
+            let mut dst = dst.skip_mut(tot).take_mut(FS_BLOCK_SIZE);
+            let mut br = self.tx.get_block(self.dev, bn);
+            let bg = br.lock();
+            if bg.is_valid() {
+                // the cached block may be newer than the disk.
+                let Ok(bg) = bg.read();
+                pt.copy_k2u_bytes(&mut dst, bg.bytes());
+            } else {
+                let chunk = pt.fetch_chunk_mut(VirtAddr::new(dst.addr())?, PtEntryFlags::UW)?;
+                let Ok(()) = bg.read_uncached(chunk.first_chunk_mut().unwrap());
+            }
+            tot += FS_BLOCK_SIZE;
+        }
+
+        if tot < len {
+            match self.read((pt, &mut dst.skip_mut(tot)).into(), off + tot) {
+                Ok(n) => tot += n,
+                Err(_) if tot > 0 => {}
+                Err(e) => return Err(e),
+            }
         }
         Ok(tot)
     }
@@ -233,13 +538,18 @@ impl LockedTxInode<'_, '_, false> {
             let Ok(mut bg) = br.lock().read();
             let m = usize::min(src.len(), FS_BLOCK_SIZE - off % FS_BLOCK_SIZE);
             let src = src.take(m);
-            UserPageTable::copy_x2k_bytes(&mut bg.bytes_mut()[off % FS_BLOCK_SIZE..][..m], &src);
+            let bytes = &mut bg.bytes_mut()[off % FS_BLOCK_SIZE..][..m];
+            UserPageTable::copy_x2k_bytes(bytes, &src);
+            page_cache::write(self.dev, self.ino, off, bytes);
             tot += m;
         }
 
         if off + tot > size {
             self.data_mut().size = (off + tot).try_into().unwrap();
         }
+        if tot > 0 {
+            text_cache::invalidate(self.dev, self.ino);
+        }
 
         // write the i-node back to disk even if the size didn't change
         // because the loop above might have called inode_block_map() and added a new
@@ -249,6 +559,86 @@ impl LockedTxInode<'_, '_, false> {
         Ok(tot)
     }
 
+    /// Writes data from user memory directly to the disk.
+    ///
+    /// Whole blocks inside the file are transferred without going through
+    /// the block cache, unless the block is already in the block cache.
+    /// Other blocks are written by [`Self::write()`], as is everything if
+    /// `off` or `src` is not block-aligned.
+    pub fn write_direct(
+        &mut self,
+        pt: &UserPageTable,
+        src: &Validated<UserSlice<u8>>,
+        off: usize,
+    ) -> Result<usize, KernelError> {
+        if off % FS_BLOCK_SIZE != 0 || src.addr() % FS_BLOCK_SIZE != 0 {
+            return self.write((pt, src).into(), off);
+        }
+
+        let mut tot = 0;
+        let mut direct = false;
+        while tot < src.len() {
+            let off = off + tot;
+            let m = usize::min(src.len() - tot, FS_BLOCK_SIZE);
+            let src = src.skip(tot).take(m);
+            let res = match self.write_block_uncached(pt, &src, off) {
+                Ok(true) => {
+                    direct = true;
+                    Ok(m)
+                }
+                Ok(false) => self.write((pt, &src).into(), off),
+                Err(e) => Err(e),
+            };
+            match res {
+                Ok(n) => {
+                    tot += n;
+                    if n < m {
+                        break;
+                    }
+                }
+                Err(_) if tot > 0 => break,
+                Err(e) => return Err(e),
+            }
+        }
+
+        if direct {
+            // the cached pages may hold the old contents.
+            page_cache::invalidate(self.dev, self.ino);
+            text_cache::invalidate(self.dev, self.ino);
+        }
+        Ok(tot)
+    }
+
+    /// Writes `src` to the block at `off` without going through the block
+    /// cache.
+    ///
+    /// Returns `false` if the block must be written through the block cache,
+    /// that is, if `src` is not a whole block inside the file or the block
+    /// is cached.
+    fn write_block_uncached(
+        &self,
+        pt: &UserPageTable,
+        src: &Validated<UserSlice<u8>>,
+        off: usize,
+    ) -> Result<bool, KernelError> {
+        let size = self.data().size as usize;
+        if src.len() != FS_BLOCK_SIZE || off + FS_BLOCK_SIZE > size {
+            return Ok(false);
+        }
+        let Some(bn) = self.data_block(off / FS_BLOCK_SIZE) else {
+            return Ok(false);
+        };
+
+        let mut br = self.tx.get_block(self.dev, bn);
+        let bg = br.lock();
+        if bg.is_valid() {
+            return Ok(false);
+        }
+        let chunk = pt.fetch_chunk(VirtAddr::new(src.addr())?, PtEntryFlags::UR)?;
+        let Ok(()) = bg.write_uncached(chunk.first_chunk().unwrap());
+        Ok(true)
+    }
+
     /// Writes `data` to inode.
     pub fn write_data<T>(&mut self, off: usize, data: &T) -> Result<(), KernelError>
     where
@@ -7,13 +7,11 @@ use super::{LockedTxInode, TxInode};
 use crate::{
     error::KernelError,
     fs::{
-        DeviceNo, InodeNo,
+        DeviceNo, InodeNo, is_xv6, name_cache,
         repr::{self, T_DIR},
     },
 };
 
-// TODO: refactoring. Add some utility methods to access DirEntry.
-
 impl<'tx, 'i, const READ_ONLY: bool> LockedTxInode<'tx, 'i, READ_ONLY> {
     pub fn is_dir(&self) -> bool {
         self.data().ty == T_DIR
@@ -24,7 +22,9 @@ impl<'tx, 'i, const READ_ONLY: bool> LockedTxInode<'tx, 'i, READ_ONLY> {
     }
 }
 
-pub struct DirInode<'tx, 'i, 'l, const READ_ONLY: bool>(&'l mut LockedTxInode<'tx, 'i, READ_ONLY>);
+pub(crate) struct DirInode<'tx, 'i, 'l, const READ_ONLY: bool>(
+    &'l mut LockedTxInode<'tx, 'i, READ_ONLY>,
+);
 
 impl<'tx, 'i, 'l, const READ_ONLY: bool> DirInode<'tx, 'i, 'l, READ_ONLY> {
     pub fn dev(&self) -> DeviceNo {
@@ -43,19 +43,28 @@ impl<'tx, 'i, 'l, const READ_ONLY: bool> DirInode<'tx, 'i, 'l, READ_ONLY> {
     }
 }
 
+impl<const READ_ONLY: bool> ov6_fs_path::DirContent for DirInode<'_, '_, '_, READ_ONLY> {
+    type Error = KernelError;
+
+    fn size(&self) -> usize {
+        self.0.data().size as usize
+    }
+
+    fn read_entry(&mut self, off: usize) -> Result<repr::DirEntry, Self::Error> {
+        if is_xv6() {
+            return Ok(self.0.read_as::<repr::xv6::DirEntry>(off)?.to_ov6());
+        }
+        self.0.read_as::<repr::DirEntry>(off)
+    }
+}
+
 impl<const READ_ONLY: bool> DirInode<'_, '_, '_, READ_ONLY> {
     /// Returns `true` if the directory is empty except for `"."` and `".."`.
+    ///
+    /// Every entry is checked, so the directory is not considered empty even
+    /// if `"."` and `".."` are not the first two entries.
     pub fn is_empty(&mut self) -> bool {
-        let de_size = size_of::<repr::DirEntry>();
-        let size = self.0.data().size as usize;
-        // skip first two entry ("." and "..").
-        for off in (2 * de_size..size).step_by(de_size) {
-            let de = self.0.read_as::<repr::DirEntry>(off).unwrap();
-            if de.ino().is_some() {
-                return false;
-            }
-        }
-        true
+        ov6_fs_path::is_empty(self).unwrap()
     }
 }
 
@@ -63,45 +72,35 @@ impl<'tx, const READ_ONLY: bool> DirInode<'tx, '_, '_, READ_ONLY> {
     /// Looks up for a directory entry by given `name`.
     ///
     /// Returns a inode that contains the entry and its offset from inode data
-    /// head.
-    pub fn lookup(&mut self, name: &OsStr) -> Option<(TxInode<'tx, READ_ONLY>, usize)> {
-        for off in (0..self.0.data().size as usize).step_by(size_of::<repr::DirEntry>()) {
-            let de = self.0.read_as::<repr::DirEntry>(off).unwrap();
-            let Some(ino) = de.ino() else { continue };
-            if !de.is_same_name(name) {
-                continue;
-            }
-            let ip = TxInode::get(self.0.tx, self.0.dev, ino);
-            return Some((ip, off));
-        }
-        None
+    /// head, or `None` if the entry is not found.
+    pub fn lookup(
+        &mut self,
+        name: &OsStr,
+    ) -> Result<Option<(TxInode<'tx, READ_ONLY>, usize)>, KernelError> {
+        let Some((ino, off)) = ov6_fs_path::lookup(self, name)? else {
+            return Ok(None);
+        };
+        let ip = TxInode::get(self.0.tx, self.0.dev, ino)?;
+        Ok(Some((ip, off)))
     }
 }
 
 impl DirInode<'_, '_, '_, false> {
     /// Writes a new directory entry (`name` and `ino`) into the directory.
+    ///
+    /// The directory is searched for `name` and for a free entry in a single
+    /// pass, and the entry is written while the directory stays locked, so
+    /// that concurrent links to the same directory cannot take the same
+    /// slot or add the same name twice.
     pub fn link(&mut self, name: &OsStr, ino: InodeNo) -> Result<(), KernelError> {
-        // Check that name is not present.
-        if self.lookup(name).is_some() {
-            return Err(KernelError::LinkAlreadyExists);
-        }
-
-        // Looks for an empty dirent.
-        let size = self.0.data().size as usize;
-        assert_eq!(size % size_of::<repr::DirEntry>(), 0);
-
-        let (mut de, off) = (0..size)
-            .step_by(size_of::<repr::DirEntry>())
-            .map(|off| {
-                let de = self.0.read_as::<repr::DirEntry>(off).unwrap();
-                (de, off)
-            })
-            .find(|(de, _)| de.ino().is_none())
-            .unwrap_or_else(|| (repr::DirEntry::zeroed(), size));
+        assert_eq!(self.0.data().size as usize % ov6_fs_path::DIR_ENTRY_SIZE, 0);
 
+        let off = ov6_fs_path::link_slot(self, name)?.ok_or(KernelError::LinkAlreadyExists)?;
+        let mut de = repr::DirEntry::zeroed();
         de.set_name(name);
         de.set_ino(Some(ino));
         self.0.write_data(off, &de)?;
+        name_cache::invalidate(self.dev(), self.ino(), name);
         Ok(())
     }
 }
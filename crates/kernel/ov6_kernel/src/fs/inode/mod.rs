@@ -19,24 +19,29 @@
 //! sequence of states before they can be used by the
 //! rest of the file system code.
 //!
-//! * Allocation: an inode is allocated if its type (on disk) is non-zero.
+//! * Allocation: an inode is allocated if its type (on disk) is non-zero, and
+//!   its bit in the inode map is set if the file system has one.
 //!   [`TxInode::alloc()`] allocates, and [`TxInode::drop()`] (destructor) or
 //!   [`TxInode::put()`] frees if the reference and link counts have fallen to
 //!   zero.
 //!
-//! * Referencing in table: an entry in the inode table is free if reference
-//!   count is zero. Otherwise tracks the number of in-memory pointers to the
-//!   entry (open files and current directories). [`TxInode::get()`] finds or
-//!   creates a table entry and increments its ref; [`TxInode::drop()`]
-//!   (destructor) or [`TxInode::put()`] decrements ref.
+//! * Referencing in table: the reference count of an entry in the inode table
+//!   tracks the number of in-memory pointers to the entry (open files and
+//!   current directories), besides the table itself. [`TxInode::get()`] finds
+//!   or creates a table entry and increments its ref; [`TxInode::drop()`]
+//!   (destructor) or [`TxInode::put()`] decrements ref. Unreferenced entries
+//!   stay cached until the table evicts them.
 //!
 //! * Valid: the information (type, size, &c) in an inode table entry is only
 //!   correct when `data` is `Some`. [`TxInode::wait_lock()`] reads the inode
-//!   from the disk and sets [`TxInode::data`], while [`TxInode::put()`] clears
-//!   [`TxInode::data`] if reference count has fallen to zero.
+//!   from the disk and sets [`TxInode::data`], while freeing the inode clears
+//!   [`TxInode::data`].
 //!
 //! * Locked: file system code may only examine and modify the information in an
-//!   inode and its content if it has first locked the inode.
+//!   inode and its content if it has first locked the inode. The one exception
+//!   is [`TxInode::read_unlocked()`], which reads the content of a file up to
+//!   the size published by the last update of the inode without the lock, so
+//!   that readers do not wait behind a long write.
 //!
 //! Thus a typical sequence is:
 //!
@@ -61,14 +66,19 @@
 //! have locked the inodes involved; this lets callers create
 //! multi-step atomic operations.
 
-use self::alloc::{InodeDataArc, InodeDataWeak};
+use dataview::PodMethods as _;
+use ov6_syscall::{AccessHint, STAT_VERSION, Stat, StatType};
+
+use self::{alloc::InodeDataArc, content::Published};
 use super::{
-    BlockNo, DeviceNo, InodeNo, SUPER_BLOCK, Tx,
+    BlockNo, DeviceNo, InodeNo, SUPER_BLOCK, Tx, inode_map, is_xv6,
     repr::{self, NUM_DIRECT_REFS},
+    vfs::Inode,
 };
 use crate::{
     error::KernelError,
-    sync::{SleepLockError, SleepLockGuard, TryLockError},
+    println,
+    sync::{SleepLock, SleepLockError, SleepLockGuard, SpinLock, TryLockError},
 };
 
 mod alloc;
@@ -76,17 +86,20 @@ mod content;
 mod directory;
 mod table;
 
-pub(super) fn init() {
-    alloc::init();
+/// Drops the cached inodes of the device `dev` that are not in use, so that
+/// they are read from the disk again.
+pub(super) fn invalidate_device(dev: DeviceNo) {
+    table::lock().invalidate_device(dev);
 }
 
 type InodeDataGuard<'a> = SleepLockGuard<'a, Option<InodeData>>;
 
-#[derive(Clone)]
-pub struct Inode {
-    dev: DeviceNo,
-    ino: InodeNo,
-    data: Option<InodeDataArc>,
+/// In-memory inode shared by the references to it.
+pub(super) struct InodeCell {
+    data: SleepLock<Option<InodeData>>,
+    /// Layout of the content, published for readers that do not lock
+    /// `data`.
+    published: SpinLock<Published>,
 }
 
 /// In-memory copy of an inode.
@@ -105,6 +118,10 @@ pub(super) struct InodeData {
     pub(super) nlink: u16,
     size: u32,
     addrs: [Option<BlockNo>; NUM_DIRECT_REFS + 1],
+    /// Access hint shared by all open files of the inode.
+    ///
+    /// Not stored on disk.
+    access_hint: AccessHint,
 }
 
 impl InodeData {
@@ -112,20 +129,21 @@ impl InodeData {
         let mut addrs = [None; NUM_DIRECT_REFS + 1];
         r.read_addrs(&mut addrs);
         Self {
-            ty: r.ty,
-            major: DeviceNo::new(u32::from(r.major)),
-            minor: r.minor,
-            nlink: r.nlink,
-            size: r.size,
+            ty: r.ty.get(),
+            major: DeviceNo::new(u32::from(r.major.get())),
+            minor: r.minor.get(),
+            nlink: r.nlink.get(),
+            size: r.size.get(),
             addrs,
+            access_hint: AccessHint::Normal,
         }
     }
 
     fn write_repr(&self, r: &mut repr::Inode) {
-        r.ty = self.ty;
-        r.major = self.major.value().try_into().unwrap();
-        r.nlink = self.nlink;
-        r.size = self.size;
+        r.ty.set(self.ty);
+        r.major.set(self.major.value().try_into().unwrap());
+        r.nlink.set(self.nlink);
+        r.size.set(self.size);
         r.write_addrs(&self.addrs);
     }
 }
@@ -150,40 +168,58 @@ impl<const READ_ONLY: bool> TxInode<'_, READ_ONLY> {
 
 impl Inode {
     pub fn from_tx<const READ_ONLY: bool>(tx: &TxInode<'_, READ_ONLY>) -> Self {
-        Self {
-            dev: tx.dev,
-            ino: tx.ino,
-            data: Some(InodeDataArc::clone(&tx.data)),
-        }
+        Self::new(tx.dev, tx.ino, InodeDataArc::clone(&tx.data).into_private())
     }
 
     pub fn from_locked<const READ_ONLY: bool>(locked: &LockedTxInode<'_, '_, READ_ONLY>) -> Self {
-        Self {
-            dev: locked.dev,
-            ino: locked.ino,
-            data: Some(InodeDataArc::clone(&locked.data)),
-        }
+        Self::new(
+            locked.dev,
+            locked.ino,
+            InodeDataArc::clone(&locked.data).into_private(),
+        )
     }
 
+    /// Returns the inode of ov6fs in the transaction `tx`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the inode is not in ov6fs.
     pub fn into_tx<'a, const READ_ONLY: bool>(
-        mut self,
+        self,
         tx: &'a Tx<READ_ONLY>,
     ) -> TxInode<'a, READ_ONLY> {
+        let (dev, ino) = (self.dev(), self.ino());
         TxInode {
             tx,
-            dev: self.dev,
-            ino: self.ino,
-            data: self.data.take().unwrap(),
+            dev,
+            ino,
+            data: InodeDataArc::from_private(self.into_data()),
         }
     }
-}
 
-impl Drop for Inode {
-    fn drop(&mut self) {
-        assert!(
-            self.data.is_none(),
-            "Inode::into_tx() must be called before dropped"
-        );
+    /// Drops the reference to an inode of ov6fs without a transaction,
+    /// unless dropping it may free the inode.
+    ///
+    /// Returns the reference back if it is the last one to an inode that may
+    /// have no links. It must then be dropped in a transaction, as
+    /// [`TxInode::drop()`] does.
+    pub(super) fn put_without_tx(self) -> Result<(), Self> {
+        let (dev, ino) = (self.dev(), self.ino());
+        let data = InodeDataArc::from_private(self.into_data());
+        let table = table::lock();
+        // one reference is held by the table. as in `TxInode::drop()`, no one
+        // else can have the inode locked if `self` is the last reference.
+        let in_use = InodeDataArc::strong_count(&data) > 2
+            || data
+                .try_lock()
+                .is_ok_and(|d| d.as_ref().is_some_and(|d| d.nlink > 0 || d.ty == 0));
+        if in_use {
+            drop(data);
+            drop(table);
+            return Ok(());
+        }
+        drop(table);
+        Err(Self::new(dev, ino, data.into_private()))
     }
 }
 
@@ -192,18 +228,20 @@ impl<'tx, const READ_ONLY: bool> TxInode<'tx, READ_ONLY> {
         TxInode { tx, dev, ino, data }
     }
 
-    pub fn root(tx: &'tx Tx<READ_ONLY>) -> Self {
-        Self::get(tx, DeviceNo::ROOT, InodeNo::ROOT)
-    }
-
     /// Finds the inode with number `ino` on device `dev`.
     ///
-    /// Returns the in-memory inode copy.
-    pub fn get(tx: &'tx Tx<READ_ONLY>, dev: DeviceNo, ino: InodeNo) -> Self {
-        let Ok(data) = table::get_or_insert(dev, ino) else {
-            panic!("cannot allocate in-memory inode entry any more");
-        };
-        TxInode::new(tx, dev, ino, data)
+    /// Returns the in-memory inode copy, or `Err()` if no in-memory inode
+    /// entry can be allocated.
+    pub fn get(tx: &'tx Tx<READ_ONLY>, dev: DeviceNo, ino: InodeNo) -> Result<Self, KernelError> {
+        let data = table::get_or_insert(dev, ino)?;
+        Ok(TxInode::new(tx, dev, ino, data))
+    }
+
+    /// Returns `true` if the inode is referenced by others than `self`, such
+    /// as open files.
+    pub(super) fn is_shared(&self) -> bool {
+        // one reference is held by the table
+        InodeDataArc::strong_count(&self.data) > 2
     }
 
     /// Drops a reference to an in-memory inode.
@@ -274,8 +312,8 @@ impl<'tx> TxInode<'tx, false> {
     /// Returns a n unlocked but allocated and referenced inode,
     /// or `Err()` if there is no free inode.
     pub fn alloc(tx: &'tx Tx<false>, dev: DeviceNo, ty: u16) -> Result<Self, KernelError> {
-        let ino = alloc_ino(tx, dev, ty)?;
-        Ok(Self::get(tx, dev, ino))
+        let ino = inode_map::alloc(tx, dev, ty)?;
+        Self::get(tx, dev, ino).inspect_err(|_| free_ino(tx, dev, ino))
     }
 }
 
@@ -283,21 +321,34 @@ impl<const READ_ONLY: bool> Drop for TxInode<'_, READ_ONLY> {
     #[track_caller]
     fn drop(&mut self) {
         let table = table::lock();
-        if InodeDataArc::strong_count(&self.data) > 1 {
+        // one reference is held by the table
+        if InodeDataArc::strong_count(&self.data) > 2 {
             return;
         }
 
-        // strong_count == 1 means no other process can have self locked,
+        // strong_count == 2 means no other process can have self locked,
         // so this acquires won't block (or deadlock).
         let lip = self.try_lock().unwrap();
 
-        // if the file is referenced in file system, do nothing
-        if lip.data().nlink > 0 {
+        // if the file is referenced in file system, do nothing. a free inode
+        // can be reached through a stale entry of the orphan list.
+        if lip.data().nlink > 0 || lip.data().ty == 0 {
             return;
         }
 
         drop(table);
 
+        if super::is_read_only() {
+            // the inode was unlinked before the file system was remounted
+            // read-only. it is freed from the orphan list once the file
+            // system is writable again.
+            crate::println!(
+                "fs: unlinked inode {} is left on the read-only file system",
+                lip.ino
+            );
+            return;
+        }
+
         // remove inode on disk
         let Some(tx) = lip.tx.to_writable() else {
             panic!("BUG: `TxInode<READ_ONLY=true>` with `nlink == 0` is dropped");
@@ -323,22 +374,36 @@ impl<'tx, 'i, const READ_ONLY: bool> LockedTxInode<'tx, 'i, READ_ONLY> {
         data: InodeDataArc,
         mut locked: InodeDataGuard<'i>,
     ) -> Self {
-        if locked.is_none() {
+        let loaded = locked.is_none();
+        if loaded {
             // read data from disk
             let sb = SUPER_BLOCK.get();
             let mut br = tx.get_block(dev, sb.inode_block(ino));
             let Ok(bg) = br.lock().read();
-            let dip = bg.data::<repr::InodeBlock>().inode(ino);
-            *locked = Some(InodeData::from_repr(dip));
+            let data = if is_xv6() {
+                let dip = bg.data::<repr::xv6::InodeBlock>().inode(ino);
+                let dip = dip.to_ov6().unwrap_or_else(|| {
+                    println!("fs: xv6 inode {ino} is invalid, read as free");
+                    repr::Inode::zeroed()
+                });
+                InodeData::from_repr(&dip)
+            } else {
+                InodeData::from_repr(bg.data::<repr::InodeBlock>().inode(ino))
+            };
+            *locked = Some(data);
         }
 
-        LockedTxInode {
+        let lip = LockedTxInode {
             tx,
             dev,
             ino,
             data,
             locked,
+        };
+        if loaded {
+            lip.publish();
         }
+        lip
     }
 
     pub fn dev(&self) -> DeviceNo {
@@ -365,9 +430,44 @@ impl<'tx, 'i, const READ_ONLY: bool> LockedTxInode<'tx, 'i, READ_ONLY> {
         self.data().major
     }
 
-    // pub fn minor(&self) -> i16 {
-    //     self.data().minor
-    // }
+    pub fn access_hint(&self) -> AccessHint {
+        self.data().access_hint
+    }
+
+    /// Returns the metadata of the inode, as reported by `fstat`.
+    pub fn stat(&self) -> Result<Stat, KernelError> {
+        let ty = match self.ty() {
+            repr::T_DIR => StatType::Dir,
+            repr::T_FILE => StatType::File,
+            repr::T_DEVICE => StatType::Dev,
+            repr::T_SOCK => StatType::Socket,
+            ty => return Err(KernelError::CorruptedInodeType(self.ino(), ty)),
+        };
+        let (major, minor) = if ty == StatType::Dev {
+            (self.major().value(), u32::from(self.minor()))
+        } else {
+            (0, 0)
+        };
+        Ok(Stat {
+            version: STAT_VERSION,
+            dev: self.dev().value(),
+            ino: self.ino().value(),
+            ty: ty as u16,
+            nlink: self.nlink(),
+            blocks: self.allocated_blocks(),
+            size: u64::from(self.size()),
+            major,
+            minor,
+        })
+    }
+
+    pub fn set_access_hint(&mut self, hint: AccessHint) {
+        self.data_mut().access_hint = hint;
+    }
+
+    pub fn minor(&self) -> u16 {
+        self.data().minor
+    }
 
     pub(super) fn data(&self) -> &InodeData {
         self.locked.as_ref().unwrap()
@@ -384,23 +484,13 @@ impl<'tx, 'i, const READ_ONLY: bool> LockedTxInode<'tx, 'i, READ_ONLY> {
     }
 }
 
-/// Allocates an inode on device `dev`.
-///
-/// Marks it as allocated by giving it type `ty`.
-/// Returns an allocated inode number or `Err()` if there is no free inode.
-fn alloc_ino(tx: &Tx<false>, dev: DeviceNo, ty: u16) -> Result<InodeNo, KernelError> {
+/// Frees an inode allocated by [`inode_map::alloc()`] that has never been used.
+fn free_ino(tx: &Tx<false>, dev: DeviceNo, ino: InodeNo) {
     let sb = SUPER_BLOCK.get();
-
-    for ino in 1..(sb.ninodes) {
-        let ino = InodeNo::new(ino);
-        let mut br = tx.get_block(dev, sb.inode_block(ino));
-        let Ok(mut bg) = br.lock().read();
-        let disk_ip = bg.data_mut::<repr::InodeBlock>().inode_mut(ino);
-        if disk_ip.is_free() {
-            disk_ip.allocate(ty);
-            return Ok(ino);
-        }
-    }
-    crate::println!("no free inodes");
-    Err(KernelError::StorageOutOfInodes)
+    let mut br = tx.get_block(dev, sb.inode_block(ino));
+    let Ok(mut bg) = br.lock().read();
+    *bg.data_mut::<repr::InodeBlock>().inode_mut(ino) = repr::Inode::zeroed();
+    drop(bg);
+    drop(br);
+    inode_map::free(tx, dev, ino);
 }
@@ -1,13 +1,29 @@
+//! Table of in-memory inodes.
+//!
+//! The inodes are kept in a hash table keyed by `(dev, ino)`, whose entries
+//! are allocated from the kernel heap. An entry holds a reference to the
+//! inode, so an inode stays cached after the last [`TxInode`](super::TxInode)
+//! referring to it is dropped. Once the table holds [`NINODE`] entries, the
+//! least recently used unreferenced ones are evicted to make room for new
+//! ones; the table grows beyond it only while all the inodes are in use.
+
+use alloc::vec::Vec;
+
 use ov6_fs_types::InodeNo;
 use ov6_kernel_params::NINODE;
+use safe_cast::SafeFrom as _;
 
-use super::{InodeDataArc, InodeDataWeak};
+use super::InodeDataArc;
 use crate::{
     error::KernelError,
     fs::DeviceNo,
-    sync::{SleepLock, SpinLock, SpinLockGuard},
+    memory::heap::HeapAllocator,
+    sync::{SpinLock, SpinLockGuard},
 };
 
+/// Number of hash buckets.
+const NBUCKET: usize = 64;
+
 static INODE_TABLE: SpinLock<InodeTable> = SpinLock::new(InodeTable::new());
 
 pub(super) fn get_or_insert(dev: DeviceNo, ino: InodeNo) -> Result<InodeDataArc, KernelError> {
@@ -18,42 +34,115 @@ pub(super) fn lock() -> SpinLockGuard<'static, InodeTable> {
     INODE_TABLE.lock()
 }
 
+struct Entry {
+    dev: DeviceNo,
+    ino: InodeNo,
+    data: InodeDataArc,
+    /// Used to find the least recently used entry.
+    last_used: u64,
+}
+
+impl Entry {
+    /// Returns `true` if only the table refers to the inode.
+    ///
+    /// References are only taken through the table, so an unreferenced entry
+    /// stays unreferenced while the table is locked.
+    fn is_unreferenced(&self) -> bool {
+        InodeDataArc::strong_count(&self.data) == 1
+    }
+}
+
 pub(super) struct InodeTable {
-    table: [Option<(DeviceNo, InodeNo, InodeDataWeak)>; NINODE],
+    buckets: [Vec<Entry, HeapAllocator>; NBUCKET],
+    /// Number of entries in `buckets`.
+    len: usize,
+    tick: u64,
+}
+
+fn bucket_of(dev: DeviceNo, ino: InodeNo) -> usize {
+    let hash = (dev.value().rotate_left(16) ^ ino.value()).wrapping_mul(0x9e37_79b9);
+    usize::safe_from(hash >> 16) % NBUCKET
 }
 
 impl InodeTable {
     const fn new() -> Self {
         Self {
-            table: [const { None }; NINODE],
+            buckets: [const { Vec::new_in(HeapAllocator) }; NBUCKET],
+            len: 0,
+            tick: 0,
         }
     }
 
     fn get_or_insert(&mut self, dev: DeviceNo, ino: InodeNo) -> Result<InodeDataArc, KernelError> {
-        let mut empty_idx = None;
-        for (i, entry) in self.table.iter_mut().enumerate() {
-            let Some(entry_body) = entry else {
-                empty_idx = Some(i);
-                continue;
-            };
-
-            if let Some(data) = InodeDataWeak::upgrade(&entry_body.2) {
-                if entry_body.0 == dev && entry_body.1 == ino {
-                    return Ok(data);
-                }
-                continue;
-            }
-
-            // If reference is not available, remove it
-            *entry = None;
-            empty_idx = Some(i);
+        self.tick += 1;
+        let tick = self.tick;
+        let bucket = bucket_of(dev, ino);
+        if let Some(entry) = self.buckets[bucket]
+            .iter_mut()
+            .find(|e| e.dev == dev && e.ino == ino)
+        {
+            entry.last_used = tick;
+            return Ok(InodeDataArc::clone(&entry.data));
         }
 
-        let empty_idx = empty_idx.ok_or(KernelError::NoFreeInodeInMemoryTableEntry)?;
+        while self.len >= NINODE && self.evict() {}
+
+        // make room in the bucket first, so that pushing the new entry cannot
+        // fail.
+        while self.buckets[bucket].try_reserve(1).is_err() {
+            if !self.evict() {
+                return Err(KernelError::NoFreeInodeInMemoryTableEntry);
+            }
+        }
+        let data = loop {
+            match InodeDataArc::try_new() {
+                Ok(data) => break data,
+                Err(_) if self.evict() => {}
+                Err(_) => return Err(KernelError::NoFreeInodeInMemoryTableEntry),
+            }
+        };
 
-        // insert new entry
-        let data = InodeDataArc::try_new(SleepLock::new(None))?;
-        self.table[empty_idx] = Some((dev, ino, InodeDataArc::downgrade(&data)));
+        self.buckets[bucket].push(Entry {
+            dev,
+            ino,
+            data: InodeDataArc::clone(&data),
+            last_used: tick,
+        });
+        self.len += 1;
         Ok(data)
     }
+
+    /// Drops the unreferenced entries of the device `dev`.
+    pub(super) fn invalidate_device(&mut self, dev: DeviceNo) {
+        for entries in &mut self.buckets {
+            let before = entries.len();
+            entries.retain(|e| e.dev != dev || !e.is_unreferenced());
+            self.len -= before - entries.len();
+        }
+    }
+
+    /// Drops the least recently used unreferenced entry.
+    ///
+    /// Returns `false` if all the inodes are referenced.
+    fn evict(&mut self) -> bool {
+        let Some((bucket, idx)) = self
+            .buckets
+            .iter()
+            .enumerate()
+            .flat_map(|(bucket, entries)| {
+                entries
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, e)| e.is_unreferenced())
+                    .map(move |(idx, e)| (bucket, idx, e.last_used))
+            })
+            .min_by_key(|(_, _, last_used)| *last_used)
+            .map(|(bucket, idx, _)| (bucket, idx))
+        else {
+            return false;
+        };
+        self.buckets[bucket].swap_remove(idx);
+        self.len -= 1;
+        true
+    }
 }
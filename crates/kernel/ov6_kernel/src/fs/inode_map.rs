@@ -0,0 +1,144 @@
+//! Inode allocator.
+//!
+//! On file systems with an inode map ([`SuperBlock::has_inode_bitmap()`]), an
+//! inode is allocated if its bit in the map is set. On older file systems, an
+//! inode is allocated if its type is not zero.
+//!
+//! The number of free inodes and the inode to start the next search from are
+//! kept in memory, so that an allocation usually does not scan the inodes
+//! known to be in use.
+
+use super::{
+    DeviceNo, InodeNo, SUPER_BLOCK, SuperBlock, Tx,
+    repr::{self, BITS_PER_BLOCK},
+};
+use crate::{error::KernelError, sync::SpinLock};
+
+struct Summary {
+    /// Number of free inodes.
+    free: u32,
+    /// Inode number to start the next search from.
+    ///
+    /// Inodes before it are usually in use, but the search wraps around in
+    /// case they are not.
+    hint: u32,
+}
+
+static SUMMARY: SpinLock<Summary> = SpinLock::new(Summary { free: 0, hint: 1 });
+
+/// Returns `true` if the inode `ino` is marked as allocated in the inode map.
+///
+/// The file system must have an inode map.
+pub(super) fn is_marked<const READ_ONLY: bool>(
+    tx: &Tx<READ_ONLY>,
+    dev: DeviceNo,
+    sb: &SuperBlock,
+    ino: InodeNo,
+) -> bool {
+    let mut br = tx.get_block(dev, sb.ibmap_block(ino));
+    let Ok(bg) = br.lock().read();
+    bg.data::<repr::BmapBlock>()
+        .is_allocated(ino.as_index() % BITS_PER_BLOCK)
+}
+
+/// Returns `true` if the inode `ino` is allocated.
+fn is_allocated(tx: &Tx<true>, dev: DeviceNo, sb: &SuperBlock, ino: InodeNo) -> bool {
+    if sb.has_inode_bitmap() {
+        return is_marked(tx, dev, sb, ino);
+    }
+    let mut br = tx.get_block(dev, sb.inode_block(ino));
+    let Ok(bg) = br.lock().read();
+    !bg.data::<repr::InodeBlock>().inode(ino).is_free()
+}
+
+/// Counts the free inodes on device `dev`.
+pub(super) fn init(tx: &Tx<true>, dev: DeviceNo, sb: &SuperBlock) {
+    let free = (1..sb.ninodes.get())
+        .map(InodeNo::new)
+        .filter(|&ino| !is_allocated(tx, dev, sb, ino))
+        .count();
+    *SUMMARY.lock() = Summary {
+        free: free.try_into().unwrap(),
+        hint: 1,
+    };
+}
+
+/// Returns the number of free inodes.
+pub(super) fn free_count() -> u32 {
+    SUMMARY.lock().free
+}
+
+/// Allocates the inode `ino` with type `ty` if it is free.
+///
+/// Returns `false` if it is in use.
+fn claim(tx: &Tx<false>, dev: DeviceNo, sb: &SuperBlock, ino: InodeNo, ty: u16) -> bool {
+    if sb.has_inode_bitmap() {
+        let mut br = tx.get_block(dev, sb.ibmap_block(ino));
+        let Ok(mut bg) = br.lock().read();
+        let bit = ino.as_index() % BITS_PER_BLOCK;
+        if bg.data::<repr::BmapBlock>().is_allocated(bit) {
+            return false;
+        }
+        bg.data_mut::<repr::BmapBlock>().allocate(bit);
+    }
+
+    let mut br = tx.get_block(dev, sb.inode_block(ino));
+    let Ok(mut bg) = br.lock().read();
+    let disk_ip = bg.data_mut::<repr::InodeBlock>().inode_mut(ino);
+    if !disk_ip.is_free() {
+        // The inode map said the inode was free. The bit set above fixes the
+        // map, so the inode is no longer counted as free.
+        let mut summary = SUMMARY.lock();
+        summary.free = summary.free.saturating_sub(1);
+        return false;
+    }
+    disk_ip.allocate(ty);
+    true
+}
+
+/// Allocates an inode on device `dev`.
+///
+/// Marks it as allocated by giving it type `ty`.
+/// Returns an allocated inode number or `Err()` if there is no free inode.
+pub(super) fn alloc(tx: &Tx<false>, dev: DeviceNo, ty: u16) -> Result<InodeNo, KernelError> {
+    let sb = SUPER_BLOCK.get();
+    let hint = {
+        let summary = SUMMARY.lock();
+        (summary.free > 0).then_some(summary.hint)
+    };
+
+    if let Some(hint) = hint {
+        for inum in (hint..sb.ninodes.get()).chain(1..hint) {
+            let ino = InodeNo::new(inum);
+            if claim(tx, dev, sb, ino, ty) {
+                let mut summary = SUMMARY.lock();
+                summary.free -= 1;
+                summary.hint = inum + 1;
+                return Ok(ino);
+            }
+        }
+    }
+    crate::println!("no free inodes");
+    Err(KernelError::StorageOutOfInodes)
+}
+
+/// Marks the inode `ino` on device `dev` as free.
+///
+/// The caller must have cleared the type of the inode.
+pub(super) fn free(tx: &Tx<false>, dev: DeviceNo, ino: InodeNo) {
+    let sb = SUPER_BLOCK.get();
+    if sb.has_inode_bitmap() {
+        let mut br = tx.get_block(dev, sb.ibmap_block(ino));
+        let Ok(mut bg) = br.lock().read();
+        let bit = ino.as_index() % BITS_PER_BLOCK;
+        assert!(
+            bg.data::<repr::BmapBlock>().is_allocated(bit),
+            "freeing free inode"
+        );
+        bg.data_mut::<repr::BmapBlock>().free(bit);
+    }
+
+    let mut summary = SUMMARY.lock();
+    summary.free += 1;
+    summary.hint = u32::min(summary.hint, ino.value());
+}
@@ -7,22 +7,21 @@
 //! write an uncommitted system call's data to disk.
 //!
 //! A system call should call [`begin_tx()`] to mark
-//! its start and end. Usually [`begin_tx()`] just increments
-//! the count of in-progress FS system calls and returns.
-//! But if it thinks the log is close to running out, it
+//! its start and end. Usually [`begin_tx()`] just reserves
+//! log space for the blocks the system call may write and returns.
+//! But if the log would run out of space, it
 //! sleeps until the last outstanding transaction commits.
 //!
-//! The log is a physical re-do log containiing disk blocks.
+//! Most transactions reserve [`MAX_OP_BLOCKS`] blocks. A transaction that
+//! knows it writes fewer blocks can reserve only those with
+//! [`begin_write_tx_sized()`], so that more of them can run concurrently.
 //!
-//! The on-disk log format:
+//! A system call that modifies the file system calls [`begin_write_tx()`]
+//! instead, which fails while the file system is mounted read-only.
+//! Remounting read-only waits for the outstanding transactions to commit.
 //!
-//! ```text
-//! header block, containing block #s for block A, B, C, ...
-//! block A
-//! block B
-//! block C
-//! ...
-//! ```
+//! The transactions and the on-disk log are implemented by the [`wal`]
+//! crate. This module ties it to the root disk and the process scheduler.
 
 use core::{
     convert::Infallible,
@@ -30,250 +29,110 @@ use core::{
     ops::{Deref, DerefMut},
 };
 
-use arrayvec::ArrayVec;
 use once_init::OnceInit;
 use ov6_kernel_params::LOG_SIZE;
+use wal::{BeginError, CondVar};
 
-use super::{
-    block_io::{BlockGuard, BlockRef},
-    repr,
+use super::block_io::{
+    BlockAllocator, BlockGuard, BlockMutex, BlockRef, LruMutex, VirtioDiskDevice,
 };
 use crate::{
+    error::KernelError,
     fs::{
         BlockNo, DeviceNo, SuperBlock,
         block_io::{self},
     },
     param::MAX_OP_BLOCKS,
-    sync::{SpinLock, SpinLockCondVar, WaitError},
+    sync::{SpinLock, SpinLockCondVar, SpinLockGuard, WaitError},
 };
 
-struct LogHeader {
-    sb: &'static SuperBlock,
-    dev: DeviceNo,
-    blocks: ArrayVec<BlockRef, LOG_SIZE>,
-}
-
-impl LogHeader {
-    fn new(dev: DeviceNo, sb: &'static SuperBlock) -> Self {
-        assert!(LOG_SIZE >= sb.max_log_len());
-        Self {
-            sb,
-            dev,
-            blocks: ArrayVec::new(),
-        }
-    }
-
-    fn max_len(&self) -> usize {
-        self.blocks.capacity()
-    }
-
-    fn len(&self) -> usize {
-        self.blocks.len()
-    }
+type LogState =
+    wal::WalState<'static, VirtioDiskDevice, LruMutex, BlockMutex, LOG_SIZE, BlockAllocator>;
+type Log = wal::Wal<SpinLock<LogState>, SpinLockCondVar>;
 
-    fn push(&mut self, block: &BlockRef) {
-        assert!(self.blocks.len() < self.sb.max_log_len());
-        if self.blocks.iter().all(|b| b.index() != block.index()) {
-            self.blocks.push(block.clone());
-        }
-    }
-
-    fn recover_from_log(&mut self) {
-        self.read();
-        self.install_transaction();
-        self.write_log_head();
-    }
-
-    fn commit(&mut self) {
-        if !self.blocks.is_empty() {
-            self.write_log_body(); // Write modified blocks from cache to log
-            self.write_log_head(); // Write header to disk -- the real commit
-            self.install_transaction(); // Now install writes to home locations
-            assert!(self.blocks.is_empty());
-            self.write_log_head(); // Erase the transaction from the log
-        }
-    }
+static LOG: OnceInit<Log> = OnceInit::new();
 
-    /// Reads the log header from disk into the in-memory log header.
-    fn read(&mut self) {
-        assert!(self.blocks.is_empty());
-        let mut bh = block_io::get(self.dev, self.sb.log_header_block().as_index());
-        let Ok(bg) = bh.lock().read();
-        let header = bg.data::<repr::LogHeader>();
-        for &bn in header.block_indices() {
-            let br = block_io::get(self.dev, bn as usize);
-            self.push(&br);
-        }
-    }
+impl<T> CondVar<SpinLock<T>> for SpinLockCondVar {
+    type Error = WaitError;
 
-    /// Writes in-memory block cache to log body.
-    fn write_log_body(&mut self) {
-        for (i, br) in (0..).zip(&mut self.blocks) {
-            let Ok(bg) = br.lock().read();
-            let mut log_br = block_io::get(self.dev, self.sb.log_body_block(i).as_index());
-            let mut log_bg = log_br.lock().set_data(bg.bytes());
-            let Ok(()) = log_bg.write();
-        }
+    fn wait<'a>(&self, guard: SpinLockGuard<'a, T>) -> Result<SpinLockGuard<'a, T>, WaitError>
+    where
+        SpinLock<T>: 'a,
+    {
+        Self::wait(self, guard).map_err(|(_guard, e)| e)
     }
 
-    /// Writes in-memory log header to disk.
-    ///
-    /// This is the true point at which the current transaction commits.
-    fn write_log_head(&self) {
-        let mut br = block_io::get(self.dev, self.sb.log_header_block().as_index());
-        let mut bg = br.lock().zeroed();
-        let dst = bg.data_mut::<repr::LogHeader>();
-        dst.set_len(self.blocks.len());
-        for (i, br) in self.blocks.iter().enumerate() {
-            dst.block_indices_mut()[i] = br.index().try_into().unwrap();
-        }
-        let Ok(()) = bg.write(); // infallible
+    fn force_wait<'a>(&self, guard: SpinLockGuard<'a, T>) -> SpinLockGuard<'a, T>
+    where
+        SpinLock<T>: 'a,
+    {
+        Self::force_wait(self, guard)
     }
 
-    /// Copies committed blocks from log to their home location.
-    fn install_transaction(&mut self) {
-        for mut br in self.blocks.drain(..) {
-            let Ok(mut bg) = br.lock().read();
-            let Ok(()) = bg.write();
-        }
-        assert!(self.blocks.is_empty());
+    fn notify(&self) {
+        Self::notify(self);
     }
 }
 
-struct Log {
-    data: SpinLock<LogData>,
-    cond: SpinLockCondVar,
+/// Initializes the log, replaying the committed transactions left in it.
+///
+/// If `read_only` is true, transactions that modify the file system cannot be
+/// started until [`set_read_only()`] is called.
+pub(super) fn init(dev: DeviceNo, sb: &'static SuperBlock, read_only: bool) {
+    LOG.init(Log::replay(
+        block_io::cache(dev),
+        sb,
+        read_only,
+        SpinLockCondVar::new(),
+    ));
 }
 
-struct LogData {
-    outstanding: usize,
-    header: Option<LogHeader>, // If None, data is committing.
+/// Makes the file system read-only or writable.
+///
+/// When making it read-only, waits for the outstanding transactions to
+/// commit.
+pub(super) fn set_read_only(read_only: bool) {
+    LOG.get().set_read_only(read_only);
 }
 
-static LOG: OnceInit<Log> = OnceInit::new();
-
-impl Log {
-    fn new(dev: DeviceNo, sb: &'static SuperBlock) -> Self {
-        let mut header = LogHeader::new(dev, sb);
-        header.recover_from_log();
-
-        Self {
-            data: SpinLock::new(LogData {
-                outstanding: 0,
-                header: Some(header),
-            }),
-            cond: SpinLockCondVar::new(),
-        }
-    }
-
-    /// Starts FS transaction.
-    ///
-    /// Called at the start of each FS system call.
-    fn begin_op(&self) -> Result<(), WaitError> {
-        let mut data = self.data.lock();
-        loop {
-            let Some(header) = &data.header else {
-                // header is under committing
-                match self.cond.wait(data) {
-                    Ok(guard) => {
-                        data = guard;
-                        continue;
-                    }
-                    Err((_guard, WaitError::WaitingProcessAlreadyKilled)) => {
-                        return Err(WaitError::WaitingProcessAlreadyKilled);
-                    }
-                }
-            };
-            if header.len() + (data.outstanding + 1) * MAX_OP_BLOCKS > header.max_len() {
-                // this op might exhaust log space; wait for commit.
-                match self.cond.wait(data) {
-                    Ok(guard) => {
-                        data = guard;
-                        continue;
-                    }
-                    Err((_guard, WaitError::WaitingProcessAlreadyKilled)) => {
-                        return Err(WaitError::WaitingProcessAlreadyKilled);
-                    }
-                }
-            }
-            data.outstanding += 1;
-            break;
-        }
-
-        Ok(())
-    }
-
-    /// Starts FS transaction.
-    ///
-    /// Called at the start of each FS system call.
-    fn force_begin_op(&self) {
-        let mut data = self.data.lock();
-        loop {
-            let Some(header) = &data.header else {
-                // header is under committing
-                data = self.cond.force_wait(data);
-                continue;
-            };
-            if header.len() + (data.outstanding + 1) * MAX_OP_BLOCKS > header.max_len() {
-                // this op might exhaust log space; wait for commit.
-                data = self.cond.force_wait(data);
-                continue;
-            }
-            data.outstanding += 1;
-            break;
-        }
-    }
-
-    /// Ends FS transaction.
-    ///
-    /// Called at the end of each FS system call.
-    /// Commits if this was the last outstanding operation.
-    fn end_op(&self) {
-        let mut header = None;
-
-        let mut data = self.data.lock();
-        data.outstanding -= 1;
-        assert!(data.header.is_some()); // not under committing
-        if data.outstanding == 0 {
-            header = data.header.take();
-        } else {
-            // begin_op() may be waiting for log space,
-            // and decrementing log.outstanding has decreased
-            // the amount of reserved space.
-            self.cond.notify();
-        }
-        drop(data); // unlock here
-
-        if let Some(mut header) = header {
-            // call commit w/o holding locks, since not allowed
-            // to sleep with locks.
-            header.commit();
-            let mut data = self.data.lock();
-            assert!(data.header.is_none());
-            data.header = Some(header);
-            self.cond.notify();
-        }
-    }
+/// Returns `true` if transactions that modify the file system cannot be
+/// started.
+pub(super) fn is_read_only() -> bool {
+    LOG.get().is_read_only()
+}
 
-    #[expect(clippy::needless_pass_by_ref_mut)]
-    fn write(&self, b: &mut BlockGuard<true>) {
-        let data = &mut *self.data.lock();
-        let header = data.header.as_mut().unwrap();
-        assert!(data.outstanding > 0);
+/// Waits for the outstanding FS transactions to commit, and blocks the
+/// transactions started later.
+pub(super) fn shutdown() {
+    LOG.get().shutdown();
+}
 
-        header.push(&b.block());
-    }
+/// Starts FS transaction.
+///
+/// Called at the start of each FS system call that only looks up or reads
+/// files. It can be started on a read-only file system.
+pub fn begin_tx() -> Result<Tx<'static, false>, KernelError> {
+    Tx::<false>::begin(false, MAX_OP_BLOCKS)
 }
 
-pub(super) fn init(dev: DeviceNo, sb: &'static SuperBlock) {
-    LOG.init(Log::new(dev, sb));
+/// Starts FS transaction that modifies the file system.
+///
+/// Fails with [`KernelError::ReadOnlyFs`] if the file system is read-only.
+pub fn begin_write_tx() -> Result<Tx<'static, false>, KernelError> {
+    Tx::<false>::begin(true, MAX_OP_BLOCKS)
 }
 
-/// Starts FS transaction.
+/// Starts FS transaction that modifies at most `blocks` blocks.
 ///
-/// Called at the start of each FS system call.
-pub fn begin_tx() -> Result<Tx<'static, false>, WaitError> {
-    Tx::<false>::begin()
+/// The transaction reserves only `blocks` blocks of the log instead of
+/// [`MAX_OP_BLOCKS`], so more such transactions can run concurrently.
+/// Nothing in the transaction may free an i-node or otherwise write more
+/// blocks than reserved.
+///
+/// Fails with [`KernelError::ReadOnlyFs`] if the file system is read-only.
+pub fn begin_write_tx_sized(blocks: usize) -> Result<Tx<'static, false>, KernelError> {
+    assert!(blocks <= MAX_OP_BLOCKS);
+    Tx::<false>::begin(true, blocks)
 }
 
 /// Starts FS transaction.
@@ -287,35 +146,80 @@ pub fn begin_readonly_tx() -> Tx<'static, true> {
     Tx::<true>::begin_read_only()
 }
 
+/// Permission to start a transaction with [`with_write_tx()`].
+///
+/// Each process owns one token, which is borrowed while its transaction
+/// runs. The closure run in the transaction cannot borrow the token again,
+/// so such transactions cannot be nested: an inner transaction could wait
+/// forever for log space held by the outer one.
+pub struct TxToken(());
+
+impl TxToken {
+    /// Creates the token of a new process.
+    pub const fn new() -> Self {
+        Self(())
+    }
+}
+
+/// Runs `f` in a transaction that modifies the file system.
+///
+/// Fails with [`KernelError::ReadOnlyFs`] if the file system is read-only.
+///
+/// Starting the transaction waits, and then retries, while the log lacks
+/// space for it, until the running operations commit.
+#[expect(clippy::needless_pass_by_ref_mut)]
+pub fn with_write_tx<T, F>(token: &mut TxToken, f: F) -> Result<T, KernelError>
+where
+    F: FnOnce(&Tx<'_, false>) -> Result<T, KernelError>,
+{
+    let _ = token; // only borrowed to forbid nesting
+    let tx = begin_write_tx()?;
+    f(&tx)
+}
+
 pub struct Tx<'log, const READ_ONLY: bool> {
     log: Option<&'log Log>,
+    /// Number of log blocks reserved by the transaction.
+    reserved: usize,
 }
 
 impl<const READ_ONLY: bool> Drop for Tx<'_, READ_ONLY> {
     fn drop(&mut self) {
         if !READ_ONLY {
-            self.log.unwrap().end_op();
+            self.log.unwrap().end(self.reserved);
         }
     }
 }
 
 impl Tx<'_, false> {
-    fn begin() -> Result<Self, WaitError> {
+    fn begin(write: bool, blocks: usize) -> Result<Self, KernelError> {
         let log = LOG.get();
-        log.begin_op()?;
-        Ok(Self { log: Some(log) })
+        log.begin(write, blocks).map_err(|e| match e {
+            BeginError::ReadOnly => KernelError::ReadOnlyFs,
+            BeginError::Wait(e) => e.into(),
+        })?;
+        Ok(Self {
+            log: Some(log),
+            reserved: blocks,
+        })
     }
 
     fn force_begin() -> Self {
         let log = LOG.get();
-        log.force_begin_op();
-        Self { log: Some(log) }
+        log.force_begin(MAX_OP_BLOCKS);
+        Self {
+            log: Some(log),
+            reserved: MAX_OP_BLOCKS,
+        }
     }
 }
 
 impl Tx<'_, true> {
     fn begin_read_only() -> Self {
-        Self { log: None }
+        Self {
+            log: None,
+            reserved: 0,
+        }
     }
 }
 
@@ -339,6 +243,7 @@ impl<const READ_ONLY: bool> Tx<'_, READ_ONLY> {
             Some(NestedTx {
                 tx: ManuallyDrop::new(Tx {
                     log: Some(LOG.get()),
+                    reserved: 0,
                 }),
             })
         }
@@ -380,9 +285,9 @@ impl<const VALID: bool, const READ_ONLY: bool> Drop for TxBlockGuard<'_, VALID,
     fn drop(&mut self) {
         if let Some(guard) = self.guard.take() {
             if guard.is_dirty() {
-                if let Ok(mut guard) = guard.try_validate() {
+                if let Ok(guard) = guard.try_validate() {
                     if let Some(log) = self.log {
-                        log.write(&mut guard);
+                        log.write(&guard.block());
                     }
                 }
             }
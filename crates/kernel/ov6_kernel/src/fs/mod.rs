@@ -4,32 +4,58 @@
 //!   + Blocks: allocator for raw disk blocks.
 //!   + Log: crash recovery for multi-step updates.
 //!   + Files: inode allocator, reading, writing, metadata.
+//!   + Page cache: cached pages of file contents.
 //!   + Directories: inode with special contents (list of other inodes!)
+//!   + Name cache: cached results of directory lookups.
 //!   + Names: paths like `/usr/rtm/xv6/fs.c` for convenient naming.
+//!   + tmpfs: a file system kept in memory, mounted through the VFS.
+//!   + VFS: the mount table, dispatching file system, inode and file
+//!     operations.
 //!
 //! This file contains the low-level file system manipulation
 //! routines. The (higher-level) system call implementations
 //! are in `syscall/file.rs`
 
-use core::fmt;
+use core::{
+    fmt,
+    sync::atomic::{AtomicU32, Ordering},
+};
 
-use dataview::Pod;
+use dataview::{Pod, PodMethods as _};
 use once_init::OnceInit;
-use ov6_fs_types::{self as repr, SuperBlock};
-pub use repr::{BlockNo, FS_BLOCK_SIZE, InodeNo, T_DEVICE, T_DIR, T_FILE};
-use safe_cast::SafeInto as _;
+use ov6_fs_types::{self as repr, BITS_PER_BLOCK, Compatibility, SuperBlock};
+use ov6_syscall::{DiskInfo, MountFlags, NameCacheInfo, PageCacheInfo, StatFs};
+pub use repr::{BlockNo, FS_BLOCK_SIZE, InodeNo, T_DEVICE, T_DIR, T_FILE, T_SOCK};
+use safe_cast::{SafeInto as _, to_u32};
 
+use self::vfs::{FileOps, FileSystemType, InodeOps, SuperBlockOps};
 pub use self::{
-    inode::{Inode, LockedTxInode, TxInode},
-    log::{Tx, begin_readonly_tx, begin_tx, force_begin_tx},
+    inode::TxInode,
+    log::{
+        Tx, TxToken, begin_readonly_tx, begin_tx, begin_write_tx, begin_write_tx_sized,
+        force_begin_tx, with_write_tx,
+    },
+    vfs::{Inode, InodeGuard, remount, resize, shutdown, statfs, sync},
 };
+use crate::{device::rtc, error::KernelError, param::ROOT_READ_ONLY, println, sync::SleepLock};
 
 mod block_io;
+mod check;
 mod data_block;
+mod dir;
+mod disk_device;
+mod file;
 mod inode;
+mod inode_map;
 mod log;
+mod name_cache;
 pub mod ops;
+mod orphan;
+mod page_cache;
 pub mod path;
+pub mod text_cache;
+mod tmpfs;
+pub mod vfs;
 mod virtio;
 pub mod virtio_disk;
 
@@ -45,6 +71,10 @@ impl fmt::Display for DeviceNo {
 
 impl DeviceNo {
     pub const CONSOLE: Self = Self(1);
+    /// Major device number of raw disks.
+    pub const DISK: Self = Self(2);
+    /// First device number allocated to file systems without a device.
+    pub const FIRST_NODEV: Self = Self(0x100);
     /// Device number of file system root disk.
     pub const ROOT: Self = Self(0);
 
@@ -61,15 +91,41 @@ impl DeviceNo {
     }
 }
 
+/// Returns the statistics of the page cache.
+pub fn page_cache_info() -> PageCacheInfo {
+    page_cache::info()
+}
+
+/// Returns the statistics of the name lookup cache.
+pub fn name_cache_info() -> NameCacheInfo {
+    name_cache::info()
+}
+
+/// Returns the statistics of the disk.
+pub fn disk_info() -> DiskInfo {
+    virtio_disk::info()
+}
+
 pub fn init() {
-    inode::init();
+    page_cache::init();
+    text_cache::init();
     block_io::init();
     virtio_disk::init();
+    disk_device::init();
+    vfs::register_type(&Ov6FsType).unwrap();
+    vfs::register_type(&tmpfs::TmpFsType).unwrap();
 }
 
 // there should be one superblock per disk device, but we run with
 // only one device
 static SUPER_BLOCK: OnceInit<SuperBlock> = OnceInit::new();
+/// Size of the file system in blocks.
+///
+/// Unlike the rest of [`SUPER_BLOCK`], this changes when the file system is
+/// resized.
+static FS_SIZE: AtomicU32 = AtomicU32::new(0);
+/// Held while resizing, so that concurrent resizes see each other's size.
+static RESIZE_LOCK: SleepLock<()> = SleepLock::new(());
 
 /// Reads the super block.
 fn init_superblock(tx: &Tx<true>, dev: DeviceNo) {
@@ -78,11 +134,303 @@ fn init_superblock(tx: &Tx<true>, dev: DeviceNo) {
     SUPER_BLOCK.init_by_ref(bg.data::<SuperBlock>());
 }
 
-pub fn init_in_proc(dev: DeviceNo) {
+/// Returns the current size of the file system in blocks.
+fn fs_size() -> u32 {
+    FS_SIZE.load(Ordering::Acquire)
+}
+
+/// Returns `true` if the file system is mounted read-only.
+///
+/// A file system is mounted read-only if it is requested, or if it uses
+/// features that must be known to write it but are not supported.
+fn is_read_only() -> bool {
+    log::is_read_only()
+}
+
+/// Returns `true` if the file system can only be mounted read-only.
+fn is_read_only_compatible() -> bool {
+    SUPER_BLOCK.get().compatibility() != Compatibility::ReadWrite
+}
+
+/// Returns `true` if the file system was made by the original xv6.
+///
+/// Its inodes and directory entries are read through the xv6 layout.
+fn is_xv6() -> bool {
+    SUPER_BLOCK.get().is_xv6()
+}
+
+/// Returns `Err` if the file system cannot be modified.
+fn check_writable() -> Result<(), KernelError> {
+    if is_read_only() {
+        return Err(KernelError::ReadOnlyFs);
+    }
+    Ok(())
+}
+
+/// Updates the on-disk super block with `f`.
+///
+/// The super block is written directly, bypassing the log.
+fn update_superblock<F>(dev: DeviceNo, f: F)
+where
+    F: FnOnce(&mut SuperBlock),
+{
+    let mut br = block_io::get(dev, SuperBlock::SUPER_BLOCK_NO.as_index());
+    let Ok(mut bg) = br.lock().read();
+    f(bg.data_mut::<SuperBlock>());
+    let Ok(()) = bg.write();
+}
+
+/// Mounts the file system on `dev`.
+fn mount(dev: DeviceNo) {
     let tx = log::begin_readonly_tx();
     init_superblock(&tx, dev);
 
     let sb = SUPER_BLOCK.get();
-    assert_eq!(sb.magic, SuperBlock::FS_MAGIC);
-    log::init(dev, sb);
+    assert_eq!(sb.magic.get(), SuperBlock::FS_MAGIC);
+    match sb.compatibility() {
+        Compatibility::ReadWrite => {}
+        Compatibility::ReadOnly if sb.is_xv6() => {
+            println!("fs: xv6 file system, mounting read-only");
+        }
+        Compatibility::ReadOnly => {
+            println!(
+                "fs: unsupported read-only compatible features {:#x}, mounting read-only",
+                sb.features.ro_compat.get()
+            );
+        }
+        Compatibility::Incompatible => panic!(
+            "fs: unsupported version {} or incompatible features {:#x}",
+            sb.version.get(),
+            sb.features.incompat.get()
+        ),
+    }
+
+    let clean = sb.state.get() == SuperBlock::STATE_CLEAN;
+    if !clean {
+        println!("fs: file system was not cleanly unmounted, replaying log");
+    }
+    // committed transactions left in the log are replayed here.
+    log::init(dev, sb, ROOT_READ_ONLY || is_read_only_compatible());
+
+    // the replayed log may have resized the file system.
+    let mut br = tx.get_block(dev, SuperBlock::SUPER_BLOCK_NO);
+    let Ok(bg) = br.lock().read();
+    let mut current = SuperBlock::zeroed();
+    current
+        .as_bytes_mut()
+        .copy_from_slice(bg.data::<SuperBlock>().as_bytes());
+    drop(bg);
+    drop(br);
+    FS_SIZE.store(current.size.get(), Ordering::Release);
+
+    if !clean {
+        let report = check::quick_check(&tx, dev, &current);
+        println!(
+            "fs: quick check: {} inodes, {} blocks, {} problems",
+            report.inodes, report.blocks, report.problems
+        );
+    }
+    inode_map::init(&tx, dev, &current);
+
+    if !is_read_only() {
+        update_superblock(dev, |sb| {
+            sb.state.set(SuperBlock::STATE_DIRTY);
+            sb.mount_count.set(sb.mount_count.get().wrapping_add(1));
+            sb.last_mount_time.set(rtc::now().as_secs());
+        });
+        // the orphans left by a crash are not open by anyone.
+        orphan::reclaim(dev);
+    }
+}
+
+/// Mounts the root file system.
+///
+/// This reads the disk, so it must be called from a process.
+pub fn init_in_proc(dev: DeviceNo) {
+    vfs::mount(Ov6FsType.name(), dev, None, None).unwrap();
+}
+
+struct Ov6FsType;
+
+impl FileSystemType for Ov6FsType {
+    fn name(&self) -> &'static str {
+        "ov6fs"
+    }
+
+    fn mount(
+        &'static self,
+        dev: DeviceNo,
+        _size: Option<usize>,
+    ) -> Result<&'static dyn SuperBlockOps, KernelError> {
+        // there is only one super block, so only the root disk is supported
+        if dev != DeviceNo::ROOT {
+            return Err(KernelError::DeviceNotFound(dev));
+        }
+        mount(dev);
+        Ok(&Ov6Fs)
+    }
+}
+
+struct Ov6Fs;
+
+impl SuperBlockOps for Ov6Fs {
+    fn root(&self, dev: DeviceNo) -> Result<Inode, KernelError> {
+        let tx = begin_readonly_tx();
+        let ip = TxInode::get(&tx, dev, InodeNo::ROOT)?;
+        Ok(Inode::from_tx(&ip))
+    }
+
+    fn check_writable(&self) -> Result<(), KernelError> {
+        check_writable()
+    }
+
+    /// Returns the statistics of the file system on `dev`.
+    ///
+    /// The free blocks are counted by scanning the bitmap, while the free inodes
+    /// are kept counted by the inode allocator.
+    fn statfs(&self, dev: DeviceNo) -> Result<StatFs, KernelError> {
+        let sb = SUPER_BLOCK.get();
+        let tx = begin_readonly_tx();
+
+        let size = fs_size();
+        let mut free_blocks = 0;
+        for bn0 in (0..size).step_by(BITS_PER_BLOCK) {
+            let mut br = tx.get_block(dev, sb.bmap_block(bn0));
+            let Ok(bg) = br.lock().read();
+            let bmap = bg.data::<repr::BmapBlock>();
+            free_blocks += (0..to_u32!(BITS_PER_BLOCK))
+                .take_while(|bni| bn0 + *bni < size)
+                .filter(|&bni| !bmap.is_allocated(bni.safe_into()))
+                .count();
+        }
+
+        Ok(StatFs {
+            dev: dev.value(),
+            block_size: to_u32!(FS_BLOCK_SIZE),
+            blocks: size,
+            free_blocks: free_blocks.try_into().unwrap(),
+            inodes: sb.ninodes.get(),
+            free_inodes: inode_map::free_count(),
+            uuid: *sb.uuid.as_bytes(),
+            label: sb.label,
+        })
+    }
+
+    /// Commits the completed file system operations to the disk.
+    ///
+    /// Waits for the running commit to finish. The log is committed immediately
+    /// unless other operations are in progress, in which case the last of them
+    /// commits it.
+    fn sync(&self) -> Result<(), KernelError> {
+        let tx = begin_tx()?;
+        tx.end();
+        Ok(())
+    }
+
+    /// Grows the file system on `dev` to `size` blocks, or as large as possible
+    /// if `size` is `None`.
+    ///
+    /// The data area is extended into the bits left unused in the bitmap blocks,
+    /// so the file system cannot grow beyond what its bitmap covers. The super
+    /// block is updated through the log, so after a crash the file system has
+    /// either the old or the new size.
+    ///
+    /// Returns the new size in blocks.
+    fn resize(&self, dev: DeviceNo, size: Option<u32>) -> Result<u32, KernelError> {
+        check_writable()?;
+        let _guard = RESIZE_LOCK.wait_lock()?;
+        let sb = SUPER_BLOCK.get();
+        let old_size = fs_size();
+        // the data area starts at the same block whatever the size is
+        let data_start = sb.data_start();
+        let bmap_capacity = (data_start - sb.bmapstart.get()) * to_u32!(BITS_PER_BLOCK);
+        let disk_capacity =
+            u32::try_from(virtio_disk::capacity() / FS_BLOCK_SIZE).unwrap_or(u32::MAX);
+        let max_size = u32::min(bmap_capacity, disk_capacity);
+
+        let size = size.unwrap_or(max_size);
+        if size < old_size {
+            return Err(KernelError::ShrinkFs);
+        }
+        if size > max_size {
+            return Err(KernelError::ResizeFsTooLarge(max_size));
+        }
+        if size == old_size {
+            return Ok(size);
+        }
+
+        let tx = begin_write_tx()?;
+        // the bits beyond the old size may be left over from an older file system
+        let bits_per_block = to_u32!(BITS_PER_BLOCK);
+        let mut bn = old_size;
+        while bn < size {
+            let end = u32::min(size, (bn / bits_per_block + 1) * bits_per_block);
+            let mut br = tx.get_block(dev, sb.bmap_block(bn));
+            let Ok(mut bg) = br.lock().read();
+            let bmap = bg.data_mut::<repr::BmapBlock>();
+            for b in bn..end {
+                bmap.free((b % bits_per_block).safe_into());
+            }
+            bn = end;
+        }
+        let mut br = tx.get_block(dev, SuperBlock::SUPER_BLOCK_NO);
+        let Ok(mut bg) = br.lock().read();
+        let new_sb = bg.data_mut::<SuperBlock>();
+        new_sb.size.set(size);
+        new_sb.nblocks.set(size - data_start);
+        drop(bg);
+        drop(br);
+        tx.end();
+
+        FS_SIZE.store(size, Ordering::Release);
+        Ok(size)
+    }
+
+    /// Changes the flags of the file system mounted on `dev`.
+    ///
+    /// Remounting read-only waits for the running transactions to commit, and
+    /// marks the file system as cleanly unmounted, so that it can be inspected
+    /// while it is mounted. A file system that uses unsupported read-only
+    /// compatible features cannot be remounted writable.
+    fn remount(&self, dev: DeviceNo, flags: MountFlags) -> Result<(), KernelError> {
+        let read_only = flags.contains(MountFlags::READ_ONLY);
+        if !read_only && is_read_only_compatible() {
+            return Err(KernelError::ReadOnlyFs);
+        }
+        // serializes remounts, and resizes that are writing the super block.
+        let _guard = RESIZE_LOCK.wait_lock()?;
+        if read_only == is_read_only() {
+            return Ok(());
+        }
+
+        if read_only {
+            log::set_read_only(true);
+            update_superblock(dev, |sb| sb.state.set(SuperBlock::STATE_CLEAN));
+        } else {
+            // marked dirty before anything is written.
+            update_superblock(dev, |sb| sb.state.set(SuperBlock::STATE_DIRTY));
+            log::set_read_only(false);
+            // orphans could not be freed while read-only.
+            orphan::wake();
+        }
+        Ok(())
+    }
+
+    /// Commits the log and marks the file system as cleanly unmounted.
+    ///
+    /// File system operations started after this call never complete.
+    fn shutdown(&self) {
+        log::shutdown();
+        if !is_read_only() {
+            update_superblock(DeviceNo::ROOT, |sb| sb.state.set(SuperBlock::STATE_CLEAN));
+        }
+    }
+
+    fn inode_ops(&'static self) -> &'static dyn InodeOps {
+        &Self
+    }
+
+    fn file_ops(&'static self) -> &'static dyn FileOps {
+        &Self
+    }
 }
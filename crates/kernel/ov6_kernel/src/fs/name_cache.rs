@@ -0,0 +1,172 @@
+//! Cache of directory lookups.
+//!
+//! Maps `(dev, directory inode, name)` to the inode number that the name
+//! refers to, or to `None` if the directory has no entry of the name (a
+//! negative entry). Path resolution consults the cache before searching the
+//! directory contents.
+//!
+//! The entries of a directory are looked up, added, and invalidated only
+//! while the directory is locked, so a cached entry always agrees with the
+//! directory contents.
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use ov6_syscall::NameCacheInfo;
+use ov6_types::os_str::OsStr;
+
+use super::{DeviceNo, InodeNo, repr::DIR_SIZE};
+use crate::{param::NNAME_CACHE, sync::SpinLock};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Key {
+    dev: DeviceNo,
+    dir: InodeNo,
+    name: [u8; DIR_SIZE],
+}
+
+impl Key {
+    fn new(dev: DeviceNo, dir: InodeNo, name: &OsStr) -> Self {
+        // directory entries only compare the first `DIR_SIZE` bytes of names.
+        let mut buf = [0; DIR_SIZE];
+        let len = usize::min(name.len(), DIR_SIZE);
+        buf[..len].copy_from_slice(&name.as_bytes()[..len]);
+        Self {
+            dev,
+            dir,
+            name: buf,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    key: Key,
+    target: Option<InodeNo>,
+    /// Used to find the least recently used entry.
+    last_used: u64,
+}
+
+struct Entries {
+    entries: [Option<Entry>; NNAME_CACHE],
+    tick: u64,
+}
+
+static ENTRIES: SpinLock<Entries> = SpinLock::new(Entries {
+    entries: [None; NNAME_CACHE],
+    tick: 0,
+});
+
+struct Stats {
+    hits: AtomicUsize,
+    negative_hits: AtomicUsize,
+    misses: AtomicUsize,
+    invalidations: AtomicUsize,
+}
+
+static STATS: Stats = Stats {
+    hits: AtomicUsize::new(0),
+    negative_hits: AtomicUsize::new(0),
+    misses: AtomicUsize::new(0),
+    invalidations: AtomicUsize::new(0),
+};
+
+/// Result of [`lookup()`].
+pub(super) enum Lookup {
+    /// The name is cached as referring to the inode.
+    Found(InodeNo),
+    /// The name is cached as not existing.
+    NotFound,
+    /// The name is not cached.
+    Miss,
+}
+
+/// Looks up `name` in the directory `dir` on device `dev`.
+pub(super) fn lookup(dev: DeviceNo, dir: InodeNo, name: &OsStr) -> Lookup {
+    let key = Key::new(dev, dir, name);
+    let mut entries = ENTRIES.lock();
+    entries.tick += 1;
+    let tick = entries.tick;
+    let Some(entry) = entries.entries.iter_mut().flatten().find(|e| e.key == key) else {
+        STATS.misses.fetch_add(1, Ordering::Relaxed);
+        return Lookup::Miss;
+    };
+    entry.last_used = tick;
+    let counter = if entry.target.is_some() {
+        &STATS.hits
+    } else {
+        &STATS.negative_hits
+    };
+    counter.fetch_add(1, Ordering::Relaxed);
+    entry.target.map_or(Lookup::NotFound, Lookup::Found)
+}
+
+/// Records that `name` in the directory `dir` refers to `target`, or does
+/// not exist if `target` is `None`.
+///
+/// Replaces the least recently used entry if the cache is full.
+pub(super) fn insert(dev: DeviceNo, dir: InodeNo, name: &OsStr, target: Option<InodeNo>) {
+    let key = Key::new(dev, dir, name);
+    let mut entries = ENTRIES.lock();
+    entries.tick += 1;
+    let entry = Entry {
+        key,
+        target,
+        last_used: entries.tick,
+    };
+
+    let slot = entries
+        .entries
+        .iter()
+        .position(|e| e.is_some_and(|e| e.key == key))
+        .unwrap_or_else(|| {
+            entries
+                .entries
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, e)| e.map_or(0, |e| e.last_used))
+                .map(|(idx, _)| idx)
+                .unwrap()
+        });
+    entries.entries[slot] = Some(entry);
+}
+
+/// Drops the entries matching `pred`.
+fn invalidate_by<F>(pred: F)
+where
+    F: Fn(&Key) -> bool,
+{
+    let mut entries = ENTRIES.lock();
+    for slot in &mut entries.entries {
+        if slot.is_some_and(|e| pred(&e.key)) {
+            *slot = None;
+            STATS.invalidations.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Drops the cached entry of `name` in the directory `dir`.
+///
+/// Must be called when an entry of the name is added to or removed from the
+/// directory.
+pub(super) fn invalidate(dev: DeviceNo, dir: InodeNo, name: &OsStr) {
+    let key = Key::new(dev, dir, name);
+    invalidate_by(|k| *k == key);
+}
+
+/// Drops all cached entries of the directory `dir`.
+///
+/// Must be called when the inode is freed, since the inode number may be
+/// reused for another directory.
+pub(super) fn invalidate_dir(dev: DeviceNo, dir: InodeNo) {
+    invalidate_by(|k| k.dev == dev && k.dir == dir);
+}
+
+/// Returns the statistics of the cache.
+pub(super) fn info() -> NameCacheInfo {
+    NameCacheInfo {
+        hits: STATS.hits.load(Ordering::Relaxed),
+        negative_hits: STATS.negative_hits.load(Ordering::Relaxed),
+        misses: STATS.misses.load(Ordering::Relaxed),
+        invalidations: STATS.invalidations.load(Ordering::Relaxed),
+    }
+}
@@ -1,16 +1,15 @@
-use dataview::PodMethods as _;
+//! Operations on the entries named by paths.
+//!
+//! The directory of the entry is resolved through the VFS, and the entry is
+//! updated by the inode operations of the file system the directory is in.
+
 use ov6_types::{
     os_str::OsStr,
     path::{Component, Path},
 };
 
-use super::{
-    DeviceNo, Tx,
-    inode::TxInode,
-    path,
-    repr::{T_DEVICE, T_FILE},
-};
-use crate::{error::KernelError, fs::repr};
+use super::{DeviceNo, Inode, InodeGuard, TxToken, path, repr::T_DIR, vfs};
+use crate::error::KernelError;
 
 fn split_path(path: &Path) -> Option<(&Path, &OsStr)> {
     let mut it = path.components();
@@ -22,130 +21,133 @@ fn split_path(path: &Path) -> Option<(&Path, &OsStr)> {
     Some((dir_path, file_name))
 }
 
-pub fn unlink(tx: &Tx<false>, cwd: TxInode<false>, path: &Path) -> Result<(), KernelError> {
+/// Removes a non-directory entry.
+pub fn unlink(
+    token: &mut TxToken,
+    root: &Inode,
+    cwd: &Inode,
+    path: &Path,
+) -> Result<(), KernelError> {
+    remove(token, root, cwd, path, false)
+}
+
+/// Removes an empty directory.
+pub fn rmdir(
+    token: &mut TxToken,
+    root: &Inode,
+    cwd: &Inode,
+    path: &Path,
+) -> Result<(), KernelError> {
+    remove(token, root, cwd, path, true)
+}
+
+fn remove(
+    token: &mut TxToken,
+    root: &Inode,
+    cwd: &Inode,
+    path: &Path,
+    is_rmdir: bool,
+) -> Result<(), KernelError> {
     let (dir_path, file_name) = split_path(path).ok_or(KernelError::UnlinkRootDir)?;
-    let mut dir_ip = path::resolve(tx, cwd, dir_path)?;
+    let dir = path::resolve(root, cwd, dir_path)?;
 
     // Cannot unlink "." or "..".
-    if file_name == ".." || file_name == "." {
+    if ov6_fs_path::is_dot_or_dot_dot(file_name) {
         return Err(KernelError::UnlinkDots);
     }
 
-    let mut dir_lip = dir_ip.force_wait_lock();
-    let mut dir_dp = dir_lip
-        .as_dir()
-        .ok_or(KernelError::NonDirectoryPathComponent)?;
-
-    let (mut file_ip, off) = dir_dp
-        .lookup(file_name)
-        .ok_or(KernelError::FsEntryNotFound)?;
-    let mut file_lip = file_ip.force_wait_lock();
-
-    assert!(file_lip.data().nlink > 0);
-    if let Some(mut file_dp) = file_lip.as_dir() {
-        if !file_dp.is_empty() {
-            return Err(KernelError::DirectoryNotEmpty);
-        }
+    // a directory that a file system is mounted on is kept.
+    let file = path::lookup(&dir, file_name)?;
+    if file.dev() != dir.dev() {
+        return Err(KernelError::MountPointBusy);
     }
+    drop(file);
 
-    let de = repr::DirEntry::zeroed();
-    dir_dp.get_inner().write_data(off, &de).unwrap();
-
-    if file_lip.is_dir() {
-        // decrement reference to parent directory.
-        dir_dp.get_inner().data_mut().nlink -= 1;
-        dir_dp.get_inner().update();
-    }
-    dir_lip.unlock();
-    dir_ip.put();
-
-    file_lip.data_mut().nlink -= 1;
-    file_lip.update();
-
-    Ok(())
+    vfs::inode_ops(dir.dev())?.unlink(token, &dir, file_name, is_rmdir)
 }
 
-pub fn create<'tx>(
-    tx: &'tx Tx<'tx, false>,
-    cwd: TxInode<'tx, false>,
+pub fn create(
+    token: &mut TxToken,
+    root: &Inode,
+    cwd: &Inode,
     path: &Path,
     ty: u16,
     major: DeviceNo,
     minor: u16,
-) -> Result<TxInode<'tx, false>, KernelError> {
+) -> Result<InodeGuard, KernelError> {
     let (dir_path, file_name) = split_path(path).ok_or(KernelError::CreateRootDir)?;
-    let mut dir_ip = path::resolve(tx, cwd, dir_path)?;
-
-    let mut dir_lip = dir_ip.force_wait_lock();
-    let mut dir_dp = dir_lip
-        .as_dir()
-        .ok_or(KernelError::NonDirectoryPathComponent)?;
-
-    if let Some((mut file_ip, _off)) = dir_dp.lookup(file_name) {
-        let file_lip = file_ip.force_wait_lock();
-        if ty == T_FILE && (file_lip.data().ty == T_FILE || file_lip.data().ty == T_DEVICE) {
-            drop(file_lip);
-            return Ok(file_ip);
-        }
-        return Err(KernelError::CreateAlreadyExists);
-    }
-
-    let mut file_ip = TxInode::alloc(tx, dir_dp.dev(), ty)?;
-    let mut file_lip = file_ip.force_wait_lock();
-    file_lip.data_mut().major = major;
-    file_lip.data_mut().minor = minor;
-    file_lip.data_mut().nlink = 0; // update after
-    file_lip.update();
-
-    if let Some(mut child_dp) = file_lip.as_dir() {
-        // Create "." and ".." entries
-        child_dp.link(OsStr::new("."), child_dp.ino())?;
-        child_dp.link(OsStr::new(".."), dir_dp.ino())?;
-    }
-
-    dir_dp.link(file_name, file_lip.ino())?;
-
-    if file_lip.is_dir() {
-        // now that success is guaranteed:
-        dir_lip.data_mut().nlink += 1; // for ".."
-        dir_lip.update();
-    }
-
-    file_lip.data_mut().nlink = 1;
-    file_lip.update();
+    let dir = path::resolve(root, cwd, dir_path)?;
+    let ip = vfs::inode_ops(dir.dev())?.create(token, &dir, file_name, ty, major, minor)?;
+    Ok(InodeGuard::new(ip))
+}
 
-    drop(file_lip);
-    Ok(file_ip)
+/// Creates a file with no links on the device of the directory `dir_path`.
+///
+/// The file is freed when its last reference is dropped, unless it is linked
+/// into a directory with [`flink()`] before that.
+pub fn create_unnamed(
+    token: &mut TxToken,
+    root: &Inode,
+    cwd: &Inode,
+    dir_path: &Path,
+) -> Result<InodeGuard, KernelError> {
+    let dir = path::resolve(root, cwd, dir_path)?;
+    let ip = vfs::inode_ops(dir.dev())?.create_unnamed(token, &dir)?;
+    Ok(InodeGuard::new(ip))
 }
 
 pub fn link(
-    tx: &Tx<false>,
-    cwd: TxInode<false>,
+    token: &mut TxToken,
+    root: &Inode,
+    cwd: &Inode,
     old_path: &Path,
     new_path: &Path,
 ) -> Result<(), KernelError> {
     let (new_dir_path, new_file_name) = split_path(new_path).ok_or(KernelError::LinkRootDir)?;
+    let old_ip = path::resolve(root, cwd, old_path)?;
+    link_inode(
+        token,
+        root,
+        cwd,
+        &old_ip,
+        new_dir_path,
+        new_file_name,
+        false,
+    )
+}
 
-    let mut old_ip = path::resolve(tx, cwd.clone(), old_path)?;
-    let old_lip = old_ip.force_wait_lock();
-    if old_lip.is_dir() {
-        return Err(KernelError::NonDirectoryPathComponent);
+/// Adds an entry `new_path` referring to `ip`, the inode of an open file.
+///
+/// Unlike [`link()`], `ip` may have no links, such as an inode created by
+/// [`create_unnamed()`].
+pub fn flink(
+    token: &mut TxToken,
+    root: &Inode,
+    cwd: &Inode,
+    ip: &Inode,
+    new_path: &Path,
+) -> Result<(), KernelError> {
+    let (new_dir_path, new_file_name) = split_path(new_path).ok_or(KernelError::LinkRootDir)?;
+    link_inode(token, root, cwd, ip, new_dir_path, new_file_name, true)
+}
+
+fn link_inode(
+    token: &mut TxToken,
+    root: &Inode,
+    cwd: &Inode,
+    ip: &Inode,
+    new_dir_path: &Path,
+    new_file_name: &OsStr,
+    allow_unlinked: bool,
+) -> Result<(), KernelError> {
+    if vfs::inode_ops(ip.dev())?.stat(ip)?.ty == T_DIR {
+        // hard links to directories would make the directory tree a graph.
+        return Err(KernelError::LinkDirectory);
     }
-    old_lip.unlock();
 
-    let mut new_dir_ip = path::resolve(tx, cwd, new_dir_path)?;
-    let mut new_dir_lip = new_dir_ip.force_wait_lock();
-    if new_dir_lip.dev() != old_ip.dev() {
+    let dir = path::resolve(root, cwd, new_dir_path)?;
+    if dir.dev() != ip.dev() {
         return Err(KernelError::LinkCrossDevices);
     }
-    let Some(mut new_dir_dp) = new_dir_lip.as_dir() else {
-        return Err(KernelError::LinkToNonDirectory);
-    };
-    new_dir_dp.link(new_file_name, old_ip.ino())?;
-
-    let mut old_lip = old_ip.force_wait_lock();
-    old_lip.data_mut().nlink += 1;
-    old_lip.update();
-
-    Ok(())
+    vfs::inode_ops(dir.dev())?.link(token, &dir, new_file_name, ip, allow_unlinked)
 }
@@ -0,0 +1,172 @@
+//! List of orphan inodes.
+//!
+//! An inode whose last link is removed while it is open is freed when the
+//! last reference to it is dropped. If the system crashes in between, the
+//! inode and its blocks would stay allocated with no links until fsck. To
+//! reclaim them, such inodes are recorded in the orphan list of the super
+//! block in the transaction that unlinks them, and are removed from it in the
+//! transaction that frees them or links them again.
+//!
+//! The inodes left in the list by a crash are freed when the file system is
+//! mounted. The last reference to an inode may also be dropped while the file
+//! system is read-only; such inodes are freed by a kernel task once the file
+//! system is remounted writable.
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use ov6_fs_types::{Le, MAX_ORPHANS};
+
+use super::{
+    DeviceNo, InodeNo, SUPER_BLOCK, SuperBlock, Tx, TxInode, begin_readonly_tx, begin_write_tx,
+};
+use crate::{
+    println,
+    proc::ops::{self as proc_ops, KernelTask},
+    sync::{SpinLock, SpinLockCondVar},
+};
+
+/// Set when the reclaimer task is requested to scan the list.
+static PENDING: SpinLock<bool> = SpinLock::new(false);
+static PENDING_CHANGED: SpinLockCondVar = SpinLockCondVar::new();
+static TASK_STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Adds the inode `ino` to the orphan list of `dev`.
+///
+/// If the list is full, the inode is not recorded, and leaks if the system
+/// crashes before it is freed.
+pub(super) fn add(tx: &Tx<false>, dev: DeviceNo, ino: InodeNo) {
+    if !SUPER_BLOCK.get().has_orphan_list() {
+        return;
+    }
+    let mut br = tx.get_block(dev, SuperBlock::SUPER_BLOCK_NO);
+    let Ok(mut bg) = br.lock().read();
+    let orphans = &bg.data::<SuperBlock>().orphans;
+    if orphans.iter().any(|o| o.get() == ino.value()) {
+        return;
+    }
+    let Some(i) = orphans.iter().position(|o| o.get() == 0) else {
+        println!("fs: orphan list is full, inode {ino} is not recorded");
+        return;
+    };
+    bg.data_mut::<SuperBlock>().orphans[i].set(ino.value());
+}
+
+/// Removes the inode `ino` from the orphan list of `dev`, if it is there.
+pub(super) fn remove(tx: &Tx<false>, dev: DeviceNo, ino: InodeNo) {
+    if !SUPER_BLOCK.get().has_orphan_list() {
+        return;
+    }
+    let mut br = tx.get_block(dev, SuperBlock::SUPER_BLOCK_NO);
+    let Ok(mut bg) = br.lock().read();
+    let orphans = &bg.data::<SuperBlock>().orphans;
+    if let Some(i) = orphans.iter().position(|o| o.get() == ino.value()) {
+        bg.data_mut::<SuperBlock>().orphans[i].set(0);
+    }
+}
+
+/// Returns `true` if the orphan list of `dev` has any inode.
+fn has_orphans(dev: DeviceNo) -> bool {
+    if !SUPER_BLOCK.get().has_orphan_list() {
+        return false;
+    }
+    let tx = begin_readonly_tx();
+    let mut br = tx.get_block(dev, SuperBlock::SUPER_BLOCK_NO);
+    let Ok(bg) = br.lock().read();
+    bg.data::<SuperBlock>().orphans.iter().any(|o| o.get() != 0)
+}
+
+/// Frees the inodes in the orphan list of `dev` that are no longer open.
+///
+/// Each inode is freed in its own transaction, so that the truncation of a
+/// large file fits in the log. Stops if the file system is read-only.
+pub(super) fn reclaim(dev: DeviceNo) {
+    let sb = SUPER_BLOCK.get();
+    if !sb.has_orphan_list() {
+        return;
+    }
+
+    let orphans: [u32; MAX_ORPHANS] = {
+        let tx = begin_readonly_tx();
+        let mut br = tx.get_block(dev, SuperBlock::SUPER_BLOCK_NO);
+        let Ok(bg) = br.lock().read();
+        bg.data::<SuperBlock>().orphans.map(Le::<u32>::get)
+    };
+
+    let mut freed = 0;
+    // an inode read from the list may have been freed, or even reused, since
+    // then. the checks below tell such inodes from orphans.
+    for inum in orphans.into_iter().filter(|inum| *inum != 0) {
+        let Ok(tx) = begin_write_tx() else {
+            break;
+        };
+        let ino = InodeNo::new(inum);
+        if inum >= sb.ninodes.get() {
+            println!("fs: orphan list has invalid inode {ino}, dropped");
+            remove(&tx, dev, ino);
+            continue;
+        }
+
+        let Ok(mut ip) = TxInode::get(&tx, dev, ino) else {
+            break;
+        };
+        if ip.is_shared() {
+            // freed when the last reference is dropped.
+            continue;
+        }
+        let lip = ip.force_wait_lock();
+        if lip.ty() == 0 || lip.nlink() > 0 {
+            // already freed, or linked again.
+            drop(lip);
+            remove(&tx, dev, ino);
+            continue;
+        }
+        drop(lip);
+        // dropping the last reference frees the inode and removes it from
+        // the list.
+        drop(ip);
+        freed += 1;
+    }
+
+    if freed > 0 {
+        println!("fs: freed {freed} orphan inodes");
+    }
+}
+
+/// Requests the reclaimer task to free the orphans of the root device.
+///
+/// Does nothing if the list is empty. The task is started by the first
+/// request.
+pub(super) fn wake() {
+    if !has_orphans(DeviceNo::ROOT) {
+        return;
+    }
+    if !TASK_STARTED.swap(true, Ordering::AcqRel)
+        && let Err(e) = proc_ops::spawn_kernel_task::<Reclaimer>()
+    {
+        TASK_STARTED.store(false, Ordering::Release);
+        println!("fs: cannot start the orphan reclaimer: {e}");
+        return;
+    }
+    *PENDING.lock() = true;
+    PENDING_CHANGED.notify();
+}
+
+/// Kernel task freeing the orphans on request.
+struct Reclaimer;
+
+impl KernelTask for Reclaimer {
+    const NAME: &'static str = "orphan_reclaim";
+
+    fn run() -> ! {
+        loop {
+            let mut pending = PENDING.lock();
+            while !*pending {
+                pending = PENDING_CHANGED.force_wait(pending);
+            }
+            *pending = false;
+            drop(pending);
+
+            reclaim(DeviceNo::ROOT);
+        }
+    }
+}
@@ -0,0 +1,346 @@
+//! Page cache for file contents.
+//!
+//! The contents of regular files are cached in pages keyed by
+//! `(dev, ino, page index)`, so that reading a large file does not go
+//! through the block cache for every 1 KiB block.
+//!
+//! Writes update the block (through the log) and the cached page in the same
+//! transaction, so a cached page is written back to the disk when the
+//! transaction commits. The pages of an inode are invalidated when its
+//! contents are truncated.
+//!
+//! The cached pages of an inode are accessed only while the inode is locked,
+//! so the cache itself only tracks which slots are in use.
+//!
+//! Pages are allocated from the page frame allocator on demand. The pages of
+//! the slots not in use are released when the allocator runs out of pages.
+//!
+//! How a read uses the cache depends on the access hint of the file: pages
+//! may be read ahead, bypassed, or dropped right after read.
+
+use alloc::boxed::Box;
+use core::{
+    ptr::NonNull,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use ov6_syscall::PageCacheInfo;
+
+use super::{DeviceNo, InodeNo};
+use crate::{
+    error::KernelError,
+    memory::{
+        PAGE_SIZE,
+        page::{self, PageFrameAllocator, Reclaimer},
+    },
+    param::NPAGE_CACHE,
+    sync::SpinLock,
+};
+
+type PageBox = Box<[u8; PAGE_SIZE], PageFrameAllocator>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Key {
+    dev: DeviceNo,
+    ino: InodeNo,
+    index: usize,
+}
+
+struct Slot {
+    key: Option<Key>,
+    /// Identifies the assignment of `key` to the slot.
+    tag: u64,
+    /// Tag of the assignment that `page` is filled for.
+    ///
+    /// The page holds the contents of `key` only if this equals `tag`.
+    filled: Option<u64>,
+    page: Option<PageBox>,
+    /// Number of [`CachedPage`]s referring to the slot.
+    refcnt: usize,
+    /// Used to find the least recently used slot.
+    last_used: u64,
+}
+
+impl Slot {
+    const fn new() -> Self {
+        Self {
+            key: None,
+            tag: 0,
+            filled: None,
+            page: None,
+            refcnt: 0,
+            last_used: 0,
+        }
+    }
+
+    fn is_filled(&self) -> bool {
+        self.filled == Some(self.tag)
+    }
+}
+
+struct Slots {
+    slots: [Slot; NPAGE_CACHE],
+    tick: u64,
+}
+
+static SLOTS: SpinLock<Slots> = SpinLock::new(Slots {
+    slots: [const { Slot::new() }; NPAGE_CACHE],
+    tick: 0,
+});
+
+/// Number of pages allocated for the cache.
+static ALLOCATED_PAGES: AtomicUsize = AtomicUsize::new(0);
+
+struct Stats {
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+    readahead: AtomicUsize,
+    bypassed: AtomicUsize,
+    dropped: AtomicUsize,
+}
+
+static STATS: Stats = Stats {
+    hits: AtomicUsize::new(0),
+    misses: AtomicUsize::new(0),
+    readahead: AtomicUsize::new(0),
+    bypassed: AtomicUsize::new(0),
+    dropped: AtomicUsize::new(0),
+};
+
+pub(super) fn init() {
+    page::add_reclaimer(Reclaimer {
+        reclaimable_pages: || ALLOCATED_PAGES.load(Ordering::Relaxed),
+        reclaim,
+    });
+}
+
+/// A reference to a slot of the page cache.
+///
+/// The page of the slot is not released while the reference is alive.
+pub(super) struct CachedPage {
+    idx: usize,
+    tag: u64,
+}
+
+impl Drop for CachedPage {
+    fn drop(&mut self) {
+        SLOTS.lock().slots[self.idx].refcnt -= 1;
+    }
+}
+
+impl CachedPage {
+    /// Returns the contents of the page.
+    ///
+    /// If the page does not hold the contents yet, allocates a page and
+    /// fills it by `fill`.
+    pub(super) fn get_or_fill<F>(&mut self, fill: F) -> Result<&[u8; PAGE_SIZE], KernelError>
+    where
+        F: FnOnce(&mut [u8; PAGE_SIZE]),
+    {
+        let (contents, filled) = self.fill(fill)?;
+        let counter = if filled { &STATS.misses } else { &STATS.hits };
+        counter.fetch_add(1, Ordering::Relaxed);
+        Ok(contents)
+    }
+
+    /// Fills the page by `fill` in advance, if it does not hold the contents
+    /// yet.
+    ///
+    /// Returns `false` if the page is already filled.
+    pub(super) fn read_ahead<F>(&mut self, fill: F) -> Result<bool, KernelError>
+    where
+        F: FnOnce(&mut [u8; PAGE_SIZE]),
+    {
+        let (_, filled) = self.fill(fill)?;
+        if filled {
+            STATS.readahead.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(filled)
+    }
+
+    /// Drops the contents of the page, so that the slot is reused first.
+    pub(super) fn drop_contents(self) {
+        let mut slots = SLOTS.lock();
+        let slot = &mut slots.slots[self.idx];
+        if slot.tag == self.tag {
+            slot.key = None;
+            slot.filled = None;
+            slot.last_used = 0;
+            STATS.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns the contents of the page, and whether it was filled by `fill`
+    /// in this call.
+    #[expect(clippy::needless_pass_by_ref_mut)]
+    fn fill<F>(&mut self, fill: F) -> Result<(&[u8; PAGE_SIZE], bool), KernelError>
+    where
+        F: FnOnce(&mut [u8; PAGE_SIZE]),
+    {
+        let (page, filled) = {
+            let mut slots = SLOTS.lock();
+            let slot = &mut slots.slots[self.idx];
+            let filled = slot.filled == Some(self.tag);
+            (slot.page.as_deref_mut().map(NonNull::from), filled)
+        };
+
+        let mut page = if let Some(page) = page {
+            page
+        } else {
+            // allocate without the lock, since the allocator may reclaim
+            // the pages of the cache.
+            let Ok(new_page) = Box::try_new_zeroed_in(PageFrameAllocator) else {
+                return Err(KernelError::NoFreePage);
+            };
+            let mut new_page: PageBox = unsafe { new_page.assume_init() };
+            let page = NonNull::from(&mut *new_page);
+            let mut slots = SLOTS.lock();
+            let slot = &mut slots.slots[self.idx];
+            assert!(slot.page.is_none());
+            slot.page = Some(new_page);
+            ALLOCATED_PAGES.fetch_add(1, Ordering::Relaxed);
+            page
+        };
+
+        // SAFETY: the page is not released while `self` is alive, and it is
+        // accessed only by the process holding the inode lock.
+        let contents = unsafe { page.as_mut() };
+        if !filled {
+            SLOTS.lock().slots[self.idx].filled = None;
+            fill(contents);
+            SLOTS.lock().slots[self.idx].filled = Some(self.tag);
+        }
+        Ok((contents, !filled))
+    }
+}
+
+/// Returns the slot caching the `index`-th page of the inode.
+///
+/// If the page is not cached, assigns the least recently used slot to it.
+/// Returns `None` if all slots are in use.
+pub(super) fn get(dev: DeviceNo, ino: InodeNo, index: usize) -> Option<CachedPage> {
+    let key = Key { dev, ino, index };
+    let mut slots = SLOTS.lock();
+    slots.tick += 1;
+    let tick = slots.tick;
+
+    let idx = if let Some(idx) = slots.slots.iter().position(|s| s.key == Some(key)) {
+        idx
+    } else {
+        // prefer empty slots to the least recently used ones
+        let (idx, _) = slots
+            .slots
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.refcnt == 0)
+            .min_by_key(|(_, s)| (s.key.is_some(), s.last_used))?;
+        let slot = &mut slots.slots[idx];
+        slot.key = Some(key);
+        slot.tag = tick;
+        idx
+    };
+
+    let slot = &mut slots.slots[idx];
+    slot.refcnt += 1;
+    slot.last_used = tick;
+    Some(CachedPage { idx, tag: slot.tag })
+}
+
+/// Returns the slot caching the `index`-th page of the inode, only if it
+/// holds the contents of the page.
+///
+/// Used by the reads that should not fill the cache, which are counted as
+/// bypassed if the page is not cached.
+pub(super) fn get_filled(dev: DeviceNo, ino: InodeNo, index: usize) -> Option<CachedPage> {
+    let key = Key { dev, ino, index };
+    let mut slots = SLOTS.lock();
+    slots.tick += 1;
+    let tick = slots.tick;
+
+    let Some(idx) = slots
+        .slots
+        .iter()
+        .position(|s| s.key == Some(key) && s.is_filled())
+    else {
+        STATS.bypassed.fetch_add(1, Ordering::Relaxed);
+        return None;
+    };
+    let slot = &mut slots.slots[idx];
+    slot.refcnt += 1;
+    slot.last_used = tick;
+    Some(CachedPage { idx, tag: slot.tag })
+}
+
+/// Returns the statistics of the cache.
+pub(super) fn info() -> PageCacheInfo {
+    PageCacheInfo {
+        hits: STATS.hits.load(Ordering::Relaxed),
+        misses: STATS.misses.load(Ordering::Relaxed),
+        readahead: STATS.readahead.load(Ordering::Relaxed),
+        bypassed: STATS.bypassed.load(Ordering::Relaxed),
+        dropped: STATS.dropped.load(Ordering::Relaxed),
+    }
+}
+
+/// Copies `src` to the cached page at byte offset `off` of the inode.
+///
+/// Does nothing if the page is not cached. `src` must not cross a page
+/// boundary.
+pub(super) fn write(dev: DeviceNo, ino: InodeNo, off: usize, src: &[u8]) {
+    let key = Key {
+        dev,
+        ino,
+        index: off / PAGE_SIZE,
+    };
+    let mut slots = SLOTS.lock();
+    let Some(slot) = slots.slots.iter_mut().find(|s| s.key == Some(key)) else {
+        return;
+    };
+    if !slot.is_filled() {
+        return;
+    }
+    let page = slot.page.as_mut().unwrap();
+    page[off % PAGE_SIZE..][..src.len()].copy_from_slice(src);
+}
+
+/// Drops all cached pages of the inode.
+pub(super) fn invalidate(dev: DeviceNo, ino: InodeNo) {
+    let mut slots = SLOTS.lock();
+    for slot in &mut slots.slots {
+        if slot.key.is_some_and(|key| key.dev == dev && key.ino == ino) {
+            slot.key = None;
+            slot.filled = None;
+        }
+    }
+}
+
+/// Drops all cached pages of the device.
+pub(super) fn invalidate_device(dev: DeviceNo) {
+    let mut slots = SLOTS.lock();
+    for slot in &mut slots.slots {
+        if slot.key.is_some_and(|key| key.dev == dev) {
+            slot.key = None;
+            slot.filled = None;
+        }
+    }
+}
+
+/// Releases the pages of the slots not in use.
+///
+/// Returns the number of released pages.
+fn reclaim() -> usize {
+    // called from the page allocator, which may be used while the cache is
+    // locked.
+    let Ok(mut slots) = SLOTS.try_lock() else {
+        return 0;
+    };
+    let mut released = 0;
+    for slot in slots.slots.iter_mut().filter(|s| s.refcnt == 0) {
+        slot.filled = None;
+        if slot.page.take().is_some() {
+            released += 1;
+        }
+    }
+    ALLOCATED_PAGES.fetch_sub(released, Ordering::Relaxed);
+    released
+}
@@ -1,37 +1,174 @@
-use ov6_types::path::{Component, Path};
+use arrayvec::ArrayVec;
+use ov6_types::{
+    os_str::OsStr,
+    path::{Component, Path},
+};
 
-use super::{Tx, inode::TxInode};
-use crate::error::KernelError;
+use super::{
+    Inode, InodeGuard, repr,
+    vfs::{self, InodeOps},
+};
+use crate::{error::KernelError, param::MAX_PATH};
 
-/// Looks up and returns the inode for a given path.
-pub fn resolve<'tx>(
-    tx: &'tx Tx<false>,
-    cwd: TxInode<'tx, false>,
-    path: &Path,
-) -> Result<TxInode<'tx, false>, KernelError> {
-    let mut components = path.components().peekable();
-    let mut ip = if components.next_if_eq(&Component::RootDir).is_some() {
-        TxInode::root(tx)
-    } else {
-        cwd
-    };
+/// Absolute path without `.`, `..` or redundant separators.
+pub type NormalPath = ArrayVec<u8, MAX_PATH>;
+
+/// Returns the normalized path of the root directory.
+pub fn root_path() -> NormalPath {
+    let mut path = NormalPath::new();
+    path.push(b'/');
+    path
+}
 
-    for comp in components {
-        let name = comp.as_os_str();
+/// Joins `path` to the normalized path `base` lexically.
+///
+/// As in [`resolve()`], `..` never climbs above the root directory.
+#[expect(clippy::map_err_ignore)]
+pub fn join_normalized(base: &NormalPath, path: &Path) -> Result<NormalPath, KernelError> {
+    let mut joined = base.clone();
+    for comp in path.components() {
+        match comp {
+            Component::RootDir => joined = root_path(),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                let sep = joined.iter().rposition(|&b| b == b'/').unwrap();
+                joined.truncate(usize::max(sep, 1));
+            }
+            Component::Normal(name) => {
+                if joined.len() > 1 {
+                    joined
+                        .try_push(b'/')
+                        .map_err(|_| KernelError::PathTooLong)?;
+                }
+                joined
+                    .try_extend_from_slice(name.as_bytes())
+                    .map_err(|_| KernelError::PathTooLong)?;
+            }
+        }
+    }
+    Ok(joined)
+}
 
-        let mut lip = ip.force_wait_lock();
-        let mut dip_opt = lip.as_dir();
-        let Some(dip) = &mut dip_opt else {
-            return Err(KernelError::NonDirectoryPathComponent);
+/// Returns `path` as seen from the root directory `root`.
+///
+/// Returns `None` if `path` is not below `root`.
+pub fn rebase_normalized(path: &NormalPath, root: &NormalPath) -> Option<NormalPath> {
+    if root.len() == 1 {
+        return Some(path.clone());
+    }
+    let rest = path.strip_prefix(root.as_slice())?;
+    match rest {
+        [] => Some(root_path()),
+        [b'/', ..] => Some(rest.try_into().unwrap()),
+        _ => None,
+    }
+}
+
+/// Returns the path of the directory `dir` as seen from `root`.
+///
+/// The path is built by following `..` up to `root` and looking up the name of
+/// each directory in its parent. Returns `None` if `dir` is not below `root`,
+/// or if it has been removed.
+#[expect(clippy::map_err_ignore)]
+pub fn path_of(root: &Inode, dir: InodeGuard) -> Result<Option<NormalPath>, KernelError> {
+    // components are pushed in reverse order, each one reversed itself
+    let mut reversed = NormalPath::new();
+    let mut ip = dir;
+    while !ip.is_same(root) {
+        if let Some(covered) = vfs::covered(&ip) {
+            // the root of a mounted file system is named in the directory it
+            // is mounted on.
+            ip = InodeGuard::new(covered);
+            continue;
+        }
+
+        let ops = vfs::inode_ops(ip.dev())?;
+        let parent = match ops.lookup(&ip, OsStr::new("..")) {
+            Ok(parent) => InodeGuard::new(parent),
+            Err(KernelError::FsEntryNotFound) => return Ok(None),
+            Err(e) => return Err(e),
         };
+        if parent.is_same(&ip) {
+            // reached the top of the file system without passing `root`
+            return Ok(None);
+        }
 
-        let Some((next, _off)) = dip.lookup(name) else {
-            return Err(KernelError::FsEntryNotFound);
+        let Some(de) = find_entry(ops, &parent, &ip)? else {
+            return Ok(None);
         };
+        for &b in de.name().as_bytes().iter().rev() {
+            reversed.try_push(b).map_err(|_| KernelError::PathTooLong)?;
+        }
+        reversed
+            .try_push(b'/')
+            .map_err(|_| KernelError::PathTooLong)?;
+        ip = parent;
+    }
 
-        drop(lip);
-        ip = next;
+    if reversed.is_empty() {
+        return Ok(Some(root_path()));
     }
+    reversed.reverse();
+    Ok(Some(reversed))
+}
 
-    Ok(ip)
+/// Returns the entry of the directory `dir` that refers to `ip`, skipping
+/// `.` and `..`.
+fn find_entry(
+    ops: &dyn InodeOps,
+    dir: &Inode,
+    ip: &Inode,
+) -> Result<Option<repr::DirEntry>, KernelError> {
+    let mut off = 0;
+    while let Some((de, next)) = ops.readdir(dir, off)? {
+        if de.ino() == Some(ip.ino()) && !ov6_fs_path::is_dot_or_dot_dot(de.name()) {
+            return Ok(Some(de));
+        }
+        off = next;
+    }
+    Ok(None)
+}
+
+/// Looks up `name` in the directory `dir`.
+///
+/// Mount points are crossed: `..` of the root directory of a mounted file
+/// system is looked up in the directory it is mounted on, and a directory
+/// that a file system is mounted on is replaced by the root directory of that
+/// file system.
+pub fn lookup(dir: &Inode, name: &OsStr) -> Result<InodeGuard, KernelError> {
+    let covered = if name == ".." {
+        vfs::covered(dir).map(InodeGuard::new)
+    } else {
+        None
+    };
+    let dir = covered.as_deref().unwrap_or(dir);
+    let ip = InodeGuard::new(vfs::inode_ops(dir.dev())?.lookup(dir, name)?);
+    Ok(vfs::mounted_root(&ip).map_or(ip, InodeGuard::new))
+}
+
+/// Walks paths through the directories of the mounted file systems.
+struct VfsWalk;
+
+impl ov6_fs_path::Walk for VfsWalk {
+    type Error = KernelError;
+    type Inode = InodeGuard;
+
+    fn is_same(&self, a: &Self::Inode, b: &Self::Inode) -> bool {
+        a.is_same(b)
+    }
+
+    fn lookup(&mut self, dir: &Self::Inode, name: &OsStr) -> Result<Self::Inode, Self::Error> {
+        lookup(dir, name)
+    }
+}
+
+/// Looks up and returns the inode for a given path.
+///
+/// Absolute paths are resolved starting from `root`, and `..` never
+/// climbs above `root`. Each component is looked up through the inode
+/// operations of the file system of its directory.
+pub fn resolve(root: &Inode, cwd: &Inode, path: &Path) -> Result<InodeGuard, KernelError> {
+    let root = InodeGuard::new(root.clone());
+    let cwd = InodeGuard::new(cwd.clone());
+    ov6_fs_path::resolve(&mut VfsWalk, &root, cwd, path)
 }
@@ -0,0 +1,197 @@
+//! Cache of program text loaded by `exec()`.
+//!
+//! The pages of the read-only segments of recently executed programs are
+//! kept in the cache, keyed by `(dev, ino)` and the program header of the
+//! segment. `exec()` maps the cached pages into the new process instead of
+//! reading the segment into private pages, so that the processes executing
+//! the same program share its text.
+//!
+//! The segments of an inode are invalidated when its contents are modified.
+//! Pages already mapped into processes stay valid until they are unmapped.
+//!
+//! The segments whose pages are not mapped into any process are released when
+//! the page allocator runs out of pages.
+
+use alloc::{sync::Arc, vec::Vec};
+
+use super::{DeviceNo, Inode, InodeNo};
+use crate::{
+    memory::{
+        heap::HeapAllocator,
+        page::{self, Reclaimer, SharedPage},
+    },
+    param::{NTEXT_CACHE, TEXT_CACHE_PAGES},
+    sync::SpinLock,
+};
+
+/// Pages of a segment, starting from the page containing its first byte.
+pub type TextPages = Arc<Vec<SharedPage>, HeapAllocator>;
+
+/// Identifies a segment in a program file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Segment {
+    pub offset: usize,
+    pub vaddr: usize,
+    pub file_size: usize,
+    pub mem_size: usize,
+}
+
+struct Entry {
+    dev: DeviceNo,
+    ino: InodeNo,
+    segment: Segment,
+    pages: TextPages,
+    last_used: u64,
+}
+
+struct TextCache {
+    entries: [Option<Entry>; NTEXT_CACHE],
+    /// Number of pages held by `entries`.
+    pages: usize,
+    tick: u64,
+}
+
+impl Entry {
+    /// Returns `true` if no process maps the pages.
+    fn is_unused(&self) -> bool {
+        self.pages.iter().all(|page| !page.is_shared())
+    }
+}
+
+impl TextCache {
+    fn remove(&mut self, idx: usize) {
+        if let Some(entry) = self.entries[idx].take() {
+            self.pages -= entry.pages.len();
+        }
+    }
+}
+
+static ENTRIES: SpinLock<TextCache> = SpinLock::new(TextCache {
+    entries: [const { None }; NTEXT_CACHE],
+    pages: 0,
+    tick: 0,
+});
+
+pub(super) fn init() {
+    page::add_reclaimer(Reclaimer {
+        reclaimable_pages,
+        reclaim,
+    });
+}
+
+/// Returns the cached pages of `segment` of the program file.
+pub fn get(inode: &Inode, segment: &Segment) -> Option<TextPages> {
+    let mut entries = ENTRIES.lock();
+    entries.tick += 1;
+    let tick = entries.tick;
+    let entry = entries
+        .entries
+        .iter_mut()
+        .flatten()
+        .find(|e| e.dev == inode.dev() && e.ino == inode.ino() && e.segment == *segment)?;
+    entry.last_used = tick;
+    Some(Arc::clone(&entry.pages))
+}
+
+/// Adds the pages of `segment` of the program file to the cache.
+///
+/// Evicts the least recently used segments to make room for them. Does
+/// nothing if the segment is larger than the cache.
+pub fn insert(inode: &Inode, segment: Segment, pages: TextPages) {
+    if pages.len() > TEXT_CACHE_PAGES {
+        return;
+    }
+
+    let mut entries = ENTRIES.lock();
+    entries.tick += 1;
+    let tick = entries.tick;
+    if let Some(idx) = entries.entries.iter().position(|e| {
+        e.as_ref()
+            .is_some_and(|e| e.dev == inode.dev() && e.ino == inode.ino() && e.segment == segment)
+    }) {
+        // inserted by another process executing the same program
+        entries.remove(idx);
+    }
+
+    while entries.pages + pages.len() > TEXT_CACHE_PAGES
+        || entries.entries.iter().all(Option::is_some)
+    {
+        let (idx, _) = entries
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, e)| Some((idx, e.as_ref()?.last_used)))
+            .min_by_key(|(_, last_used)| *last_used)
+            .unwrap();
+        entries.remove(idx);
+    }
+    let idx = entries.entries.iter().position(Option::is_none).unwrap();
+
+    entries.pages += pages.len();
+    entries.entries[idx] = Some(Entry {
+        dev: inode.dev(),
+        ino: inode.ino(),
+        segment,
+        pages,
+        last_used: tick,
+    });
+}
+
+/// Drops all cached segments of the inode.
+pub(super) fn invalidate(dev: DeviceNo, ino: InodeNo) {
+    let mut entries = ENTRIES.lock();
+    for idx in 0..NTEXT_CACHE {
+        if entries.entries[idx]
+            .as_ref()
+            .is_some_and(|e| e.dev == dev && e.ino == ino)
+        {
+            entries.remove(idx);
+        }
+    }
+}
+
+/// Drops all cached segments of the device.
+pub(super) fn invalidate_device(dev: DeviceNo) {
+    let mut entries = ENTRIES.lock();
+    for idx in 0..NTEXT_CACHE {
+        if entries.entries[idx].as_ref().is_some_and(|e| e.dev == dev) {
+            entries.remove(idx);
+        }
+    }
+}
+
+/// Returns the number of pages held only by the cache.
+fn reclaimable_pages() -> usize {
+    let Ok(entries) = ENTRIES.try_lock() else {
+        return 0;
+    };
+    entries
+        .entries
+        .iter()
+        .flatten()
+        .filter(|e| Arc::strong_count(&e.pages) == 1 && e.is_unused())
+        .map(|e| e.pages.len())
+        .sum()
+}
+
+/// Releases the segments not in use.
+///
+/// Returns the number of released pages.
+fn reclaim() -> usize {
+    // called from the page allocator, which may be used while the cache is
+    // locked.
+    let Ok(mut entries) = ENTRIES.try_lock() else {
+        return 0;
+    };
+    let mut released = 0;
+    for idx in 0..NTEXT_CACHE {
+        if let Some(e) = &entries.entries[idx]
+            && Arc::strong_count(&e.pages) == 1
+            && e.is_unused()
+        {
+            released += e.pages.len();
+            entries.remove(idx);
+        }
+    }
+    released
+}
@@ -0,0 +1,719 @@
+//! tmpfs, a file system kept in memory.
+//!
+//! The inodes and their contents live in kernel memory only, so nothing is
+//! logged and everything is lost when the system shuts down. The contents are
+//! kept in pages allocated from the page frame allocator.
+//!
+//! The size of a tmpfs is limited to a number of pages, given when it is
+//! mounted, and it has as many inodes as pages. Writes that would exceed the
+//! limit fail with [`KernelError::StorageOutOfBlocks`], and creating more
+//! inodes fails with [`KernelError::StorageOutOfInodes`], instead of taking
+//! the pages from the rest of the kernel.
+//!
+//! Directories are stored as sequences of [`repr::DirEntry`] like in ov6fs,
+//! so that the entries are looked up by the same code.
+
+use alloc::{boxed::Box, sync::Arc, vec::Vec};
+use core::sync::atomic::{AtomicBool, AtomicU16, AtomicUsize, Ordering};
+
+use dataview::PodMethods as _;
+use ov6_syscall::{AccessHint, MountFlags, STAT_VERSION, Stat, StatFs, StatType};
+use ov6_types::os_str::OsStr;
+use safe_cast::to_u32;
+
+use super::{
+    DeviceNo, FS_BLOCK_SIZE, Inode, InodeNo, TxToken,
+    repr::{self, T_DEVICE, T_DIR, T_FILE, T_SOCK},
+    text_cache,
+    vfs::{FileOps, FileSystemType, InodeOps, OpenFile, SuperBlockOps},
+};
+use crate::{
+    error::KernelError,
+    memory::{
+        PAGE_SIZE,
+        addr::{GenericMutSlice, GenericSlice},
+        fallible,
+        heap::HeapAllocator,
+        page::PageFrameAllocator,
+        vm_user::UserPageTable,
+    },
+    param::TMPFS_DEFAULT_PAGES,
+    sync::{SleepLock, SpinLock},
+};
+
+type PageBox = Box<[u8; PAGE_SIZE], PageFrameAllocator>;
+type NodeArc = Arc<Node, HeapAllocator>;
+
+/// Label reported by `statfs`.
+const LABEL: &[u8] = b"tmpfs";
+
+pub(super) struct TmpFsType;
+
+impl FileSystemType for TmpFsType {
+    fn name(&self) -> &'static str {
+        "tmpfs"
+    }
+
+    /// Creates an empty tmpfs of `size` bytes, rounded up to pages.
+    fn mount(
+        &'static self,
+        dev: DeviceNo,
+        size: Option<usize>,
+    ) -> Result<&'static dyn SuperBlockOps, KernelError> {
+        let max_pages = size.map_or(TMPFS_DEFAULT_PAGES, |size| size.div_ceil(PAGE_SIZE));
+        let mut nodes = Vec::new_in(HeapAllocator);
+        #[expect(clippy::map_err_ignore)]
+        nodes.try_reserve(1).map_err(|_| KernelError::NoMemory)?;
+        // inode number 0 is not used.
+        nodes.push(None);
+
+        let fs = Box::try_new_in(
+            TmpFs {
+                dev,
+                max_pages: AtomicUsize::new(max_pages),
+                used_pages: AtomicUsize::new(0),
+                max_inodes: max_pages,
+                read_only: AtomicBool::new(false),
+                nodes: SpinLock::new(NodeTable { nodes, used: 0 }),
+            },
+            HeapAllocator,
+        )?;
+        fs.init_root()?;
+        // file systems are never unmounted.
+        Ok(Box::leak(fs))
+    }
+}
+
+struct TmpFs {
+    dev: DeviceNo,
+    /// Size limit in pages.
+    max_pages: AtomicUsize,
+    /// Number of pages holding the contents of the inodes.
+    used_pages: AtomicUsize,
+    max_inodes: usize,
+    read_only: AtomicBool,
+    nodes: SpinLock<NodeTable>,
+}
+
+/// Inodes of a tmpfs, indexed by inode number.
+///
+/// The table holds a reference to each inode until it has no links and no
+/// other references.
+struct NodeTable {
+    nodes: Vec<Option<NodeArc>, HeapAllocator>,
+    /// Number of inodes in `nodes`.
+    used: usize,
+}
+
+struct Node {
+    ty: u16,
+    major: DeviceNo,
+    minor: u16,
+    /// Number of directory entries referring to the inode.
+    ///
+    /// Modified while `data` is locked.
+    nlink: AtomicU16,
+    data: SleepLock<NodeData>,
+}
+
+struct NodeData {
+    size: usize,
+    pages: Vec<PageBox, HeapAllocator>,
+    /// Access hint shared by all open files of the inode.
+    access_hint: AccessHint,
+}
+
+/// Returns the tmpfs inode that `inode` refers to.
+fn node(inode: &Inode) -> &Node {
+    inode.data().downcast_ref::<Node>().unwrap()
+}
+
+impl TmpFs {
+    /// Creates the root directory, whose `..` refers to itself.
+    fn init_root(&self) -> Result<(), KernelError> {
+        let (ino, node) = self.alloc(T_DIR, DeviceNo::ROOT, 0)?;
+        assert_eq!(ino, InodeNo::ROOT);
+        let mut data = node.data.force_wait_lock();
+        data.add_entry(self, OsStr::new("."), ino)?;
+        data.add_entry(self, OsStr::new(".."), ino)?;
+        node.nlink.store(1, Ordering::Release);
+        Ok(())
+    }
+
+    /// Allocates an inode of type `ty` with no links.
+    fn alloc(
+        &self,
+        ty: u16,
+        major: DeviceNo,
+        minor: u16,
+    ) -> Result<(InodeNo, NodeArc), KernelError> {
+        let node = fallible::try_new_arc_in(
+            Node {
+                ty,
+                major,
+                minor,
+                nlink: AtomicU16::new(0),
+                data: SleepLock::new(NodeData {
+                    size: 0,
+                    pages: Vec::new_in(HeapAllocator),
+                    access_hint: AccessHint::Normal,
+                }),
+            },
+            HeapAllocator,
+        )?;
+
+        let mut table = self.nodes.lock();
+        if table.used >= self.max_inodes {
+            return Err(KernelError::StorageOutOfInodes);
+        }
+        let free = table.nodes.iter().skip(1).position(Option::is_none);
+        let idx = if let Some(idx) = free {
+            idx + 1
+        } else {
+            #[expect(clippy::map_err_ignore)]
+            table
+                .nodes
+                .try_reserve(1)
+                .map_err(|_| KernelError::NoMemory)?;
+            table.nodes.push(None);
+            table.nodes.len() - 1
+        };
+        table.nodes[idx] = Some(Arc::clone(&node));
+        table.used += 1;
+        Ok((InodeNo::new(idx.try_into().unwrap()), node))
+    }
+
+    /// Returns the inode `ino`, which must be referred to by a directory
+    /// entry or an [`Inode`].
+    fn get(&self, ino: InodeNo) -> NodeArc {
+        let table = self.nodes.lock();
+        Arc::clone(table.nodes[ino.as_index()].as_ref().unwrap())
+    }
+
+    /// Drops the reference `node` to the inode `ino`.
+    ///
+    /// If that was the last reference other than the table's, and the inode
+    /// has no links, the inode and its contents are freed.
+    fn release(&self, ino: InodeNo, node: NodeArc) {
+        let mut table = self.nodes.lock();
+        drop(node);
+        let slot = &mut table.nodes[ino.as_index()];
+        if !slot.as_ref().is_some_and(|node| {
+            Arc::strong_count(node) == 1 && node.nlink.load(Ordering::Acquire) == 0
+        }) {
+            return;
+        }
+        // invalidated before the inode number can be reused.
+        text_cache::invalidate(self.dev, ino);
+        let node = slot.take().unwrap();
+        table.used -= 1;
+        drop(table);
+
+        // no one else refers to the inode, so this does not wait.
+        node.data.force_wait_lock().clear(self);
+    }
+
+    fn reserve_page(&self) -> Result<(), KernelError> {
+        let max = self.max_pages.load(Ordering::Relaxed);
+        #[expect(clippy::map_err_ignore)]
+        self.used_pages
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                (used < max).then_some(used + 1)
+            })
+            .map_err(|_| KernelError::StorageOutOfBlocks)?;
+        Ok(())
+    }
+
+    fn unreserve_pages(&self, n: usize) {
+        self.used_pages.fetch_sub(n, Ordering::Relaxed);
+    }
+
+    /// Adds the entry `name` referring to `ino` to the locked directory, and
+    /// the `.` and `..` entries to the new directory `node`.
+    fn link_new(
+        &self,
+        dir: &mut NodeData,
+        dir_ino: InodeNo,
+        name: &OsStr,
+        ino: InodeNo,
+        node: &Node,
+    ) -> Result<(), KernelError> {
+        if node.ty == T_DIR {
+            let mut data = node.data.force_wait_lock();
+            data.add_entry(self, OsStr::new("."), ino)?;
+            data.add_entry(self, OsStr::new(".."), dir_ino)?;
+        }
+        dir.add_entry(self, name, ino)
+    }
+
+    /// Removes the entry at `off` referring to `node` from the locked
+    /// directory `dir_node`.
+    fn remove_entry(
+        dir_node: &Node,
+        dir: &mut NodeData,
+        off: usize,
+        node: &Node,
+        is_rmdir: bool,
+    ) -> Result<(), KernelError> {
+        let mut data = node.data.force_wait_lock();
+        assert!(node.nlink.load(Ordering::Acquire) > 0);
+        if node.ty == T_DIR {
+            if !is_rmdir {
+                return Err(KernelError::UnlinkDirectory);
+            }
+            if !ov6_fs_path::is_empty(&mut *data)? {
+                return Err(KernelError::DirectoryNotEmpty);
+            }
+        } else if is_rmdir {
+            return Err(KernelError::RmdirNonDirectory);
+        }
+
+        // the entry is already there, so this does not allocate.
+        let de = repr::DirEntry::zeroed();
+        dir.write(None, &GenericSlice::Kernel(de.as_bytes()), off)?;
+        if node.ty == T_DIR {
+            // decrement reference to parent directory.
+            dir_node.nlink.fetch_sub(1, Ordering::Release);
+        }
+        node.nlink.fetch_sub(1, Ordering::Release);
+        Ok(())
+    }
+}
+
+impl NodeData {
+    /// Copies the contents at `off` into `dst`, and returns the number of
+    /// bytes copied.
+    fn read(&self, dst: &mut GenericMutSlice<u8>, off: usize) -> usize {
+        if off >= self.size {
+            return 0;
+        }
+        let len = usize::min(dst.len(), self.size - off);
+        let mut tot = 0;
+        while tot < len {
+            let pos = off + tot;
+            let m = usize::min(len - tot, PAGE_SIZE - pos % PAGE_SIZE);
+            let mut dst = dst.skip_mut(tot);
+            let mut dst = dst.take_mut(m);
+            let page = &self.pages[pos / PAGE_SIZE];
+            UserPageTable::copy_k2x_bytes(&mut dst, &page[pos % PAGE_SIZE..][..m]);
+            tot += m;
+        }
+        tot
+    }
+
+    /// Copies `src` into the contents at `off`, and returns the number of
+    /// bytes copied.
+    ///
+    /// The contents are extended with pages reserved in `fs`. If it is
+    /// `None`, only the pages already there are written. Fewer bytes than
+    /// `src` are copied if the pages run out after some are copied.
+    fn write(
+        &mut self,
+        fs: Option<&TmpFs>,
+        src: &GenericSlice<u8>,
+        off: usize,
+    ) -> Result<usize, KernelError> {
+        if off.checked_add(src.len()).is_none() {
+            return Err(KernelError::FileTooLarge);
+        }
+        if off > self.size {
+            return Err(KernelError::WriteOffsetTooLarge);
+        }
+
+        let mut tot = 0;
+        while tot < src.len() {
+            let pos = off + tot;
+            if pos / PAGE_SIZE == self.pages.len() {
+                let res = fs.ok_or(KernelError::StorageOutOfBlocks);
+                if let Err(e) = res.and_then(|fs| self.grow(fs)) {
+                    if tot > 0 {
+                        break;
+                    }
+                    return Err(e);
+                }
+            }
+            let m = usize::min(src.len() - tot, PAGE_SIZE - pos % PAGE_SIZE);
+            let src = src.skip(tot);
+            let src = src.take(m);
+            let page = &mut self.pages[pos / PAGE_SIZE];
+            UserPageTable::copy_x2k_bytes(&mut page[pos % PAGE_SIZE..][..m], &src);
+            tot += m;
+        }
+        self.size = usize::max(self.size, off + tot);
+        Ok(tot)
+    }
+
+    /// Appends a zero-filled page to the contents.
+    fn grow(&mut self, fs: &TmpFs) -> Result<(), KernelError> {
+        #[expect(clippy::map_err_ignore)]
+        self.pages
+            .try_reserve(1)
+            .map_err(|_| KernelError::NoMemory)?;
+        fs.reserve_page()?;
+        match fallible::try_new_zeroed_box_in(PageFrameAllocator) {
+            Ok(page) => {
+                self.pages.push(unsafe { page.assume_init() });
+                Ok(())
+            }
+            Err(e) => {
+                fs.unreserve_pages(1);
+                Err(e)
+            }
+        }
+    }
+
+    /// Frees the contents.
+    fn clear(&mut self, fs: &TmpFs) {
+        fs.unreserve_pages(self.pages.len());
+        self.pages.clear();
+        self.size = 0;
+    }
+
+    /// Adds the entry `name` referring to `ino` to the directory.
+    fn add_entry(&mut self, fs: &TmpFs, name: &OsStr, ino: InodeNo) -> Result<(), KernelError> {
+        let off = ov6_fs_path::link_slot(self, name)?.ok_or(KernelError::LinkAlreadyExists)?;
+        let mut de = repr::DirEntry::zeroed();
+        de.set_name(name);
+        de.set_ino(Some(ino));
+        // the pages are a multiple of entries, so an entry is never written
+        // partially.
+        self.write(Some(fs), &GenericSlice::Kernel(de.as_bytes()), off)?;
+        Ok(())
+    }
+}
+
+impl ov6_fs_path::DirContent for NodeData {
+    type Error = KernelError;
+
+    fn size(&self) -> usize {
+        self.size
+    }
+
+    fn read_entry(&mut self, off: usize) -> Result<repr::DirEntry, Self::Error> {
+        let mut de = repr::DirEntry::zeroed();
+        let n = self.read(&mut de.as_bytes_mut().into(), off);
+        assert_eq!(n, ov6_fs_path::DIR_ENTRY_SIZE);
+        Ok(de)
+    }
+}
+
+impl SuperBlockOps for TmpFs {
+    fn root(&self, dev: DeviceNo) -> Result<Inode, KernelError> {
+        Ok(Inode::new(dev, InodeNo::ROOT, self.get(InodeNo::ROOT)))
+    }
+
+    fn check_writable(&self) -> Result<(), KernelError> {
+        if self.read_only.load(Ordering::Relaxed) {
+            return Err(KernelError::ReadOnlyFs);
+        }
+        Ok(())
+    }
+
+    /// Returns the statistics of the file system on `dev`.
+    ///
+    /// The blocks are pages, and the size is the limit of the file system
+    /// rather than the pages it uses.
+    fn statfs(&self, dev: DeviceNo) -> Result<StatFs, KernelError> {
+        let used_inodes = self.nodes.lock().used;
+        let max_pages = self.max_pages.load(Ordering::Relaxed);
+        let used_pages = self.used_pages.load(Ordering::Relaxed);
+        let mut label = [0; 16];
+        label[..LABEL.len()].copy_from_slice(LABEL);
+        Ok(StatFs {
+            dev: dev.value(),
+            block_size: to_u32!(PAGE_SIZE),
+            blocks: max_pages.try_into().unwrap_or(u32::MAX),
+            free_blocks: max_pages
+                .saturating_sub(used_pages)
+                .try_into()
+                .unwrap_or(u32::MAX),
+            inodes: self.max_inodes.try_into().unwrap_or(u32::MAX),
+            free_inodes: (self.max_inodes - used_inodes)
+                .try_into()
+                .unwrap_or(u32::MAX),
+            uuid: [0; 16],
+            label,
+        })
+    }
+
+    /// Does nothing, as there is no disk to write to.
+    fn sync(&self) -> Result<(), KernelError> {
+        Ok(())
+    }
+
+    /// Changes the size limit of the file system to `size` pages.
+    ///
+    /// The limit cannot be lowered below the pages in use. `None` keeps the
+    /// current limit, as memory has no fixed size to grow to. Returns the new
+    /// limit in pages.
+    fn resize(&self, _dev: DeviceNo, size: Option<u32>) -> Result<u32, KernelError> {
+        self.check_writable()?;
+        let old_size = self.max_pages.load(Ordering::Relaxed);
+        let Some(size) = size else {
+            return Ok(old_size.try_into().unwrap_or(u32::MAX));
+        };
+        let new_size = usize::try_from(size).unwrap();
+        if new_size < self.used_pages.load(Ordering::Relaxed) {
+            return Err(KernelError::ShrinkFs);
+        }
+        self.max_pages.store(new_size, Ordering::Relaxed);
+        Ok(size)
+    }
+
+    fn remount(&self, _dev: DeviceNo, flags: MountFlags) -> Result<(), KernelError> {
+        self.read_only
+            .store(flags.contains(MountFlags::READ_ONLY), Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Does nothing, as the contents are lost anyway.
+    fn shutdown(&self) {}
+
+    fn inode_ops(&'static self) -> &'static dyn InodeOps {
+        self
+    }
+
+    fn file_ops(&'static self) -> &'static dyn FileOps {
+        self
+    }
+}
+
+impl InodeOps for TmpFs {
+    fn stat(&self, inode: &Inode) -> Result<Stat, KernelError> {
+        let node = node(inode);
+        let data = node.data.wait_lock()?;
+        let ty = match node.ty {
+            T_DIR => StatType::Dir,
+            T_FILE => StatType::File,
+            T_DEVICE => StatType::Dev,
+            T_SOCK => StatType::Socket,
+            ty => return Err(KernelError::CorruptedInodeType(inode.ino(), ty)),
+        };
+        let (major, minor) = if ty == StatType::Dev {
+            (node.major.value(), u32::from(node.minor))
+        } else {
+            (0, 0)
+        };
+        Ok(Stat {
+            version: STAT_VERSION,
+            dev: inode.dev().value(),
+            ino: inode.ino().value(),
+            ty: ty as u16,
+            nlink: node.nlink.load(Ordering::Acquire),
+            blocks: (data.pages.len() * (PAGE_SIZE / FS_BLOCK_SIZE))
+                .try_into()
+                .unwrap_or(u32::MAX),
+            size: data.size.try_into().unwrap(),
+            major,
+            minor,
+        })
+    }
+
+    fn lookup(&self, dir: &Inode, name: &OsStr) -> Result<Inode, KernelError> {
+        let dir_node = node(dir);
+        if dir_node.ty != T_DIR {
+            return Err(KernelError::NonDirectoryPathComponent);
+        }
+        let mut dir_data = dir_node.data.wait_lock()?;
+        let (ino, _off) =
+            ov6_fs_path::lookup(&mut *dir_data, name)?.ok_or(KernelError::FsEntryNotFound)?;
+        // the entry keeps the inode while the directory is locked.
+        Ok(Inode::new(self.dev, ino, self.get(ino)))
+    }
+
+    fn readdir(
+        &self,
+        dir: &Inode,
+        off: usize,
+    ) -> Result<Option<(repr::DirEntry, usize)>, KernelError> {
+        let dir_node = node(dir);
+        if dir_node.ty != T_DIR {
+            return Err(KernelError::NonDirectoryPathComponent);
+        }
+        let mut dir_data = dir_node.data.wait_lock()?;
+        let next = ov6_fs_path::next_entry(&mut *dir_data, off)?;
+        Ok(next.map(|(off, de)| (de, off + ov6_fs_path::DIR_ENTRY_SIZE)))
+    }
+
+    fn create(
+        &self,
+        _token: &mut TxToken,
+        dir: &Inode,
+        name: &OsStr,
+        ty: u16,
+        major: DeviceNo,
+        minor: u16,
+    ) -> Result<Inode, KernelError> {
+        self.check_writable()?;
+        let dir_node = node(dir);
+        if dir_node.ty != T_DIR {
+            return Err(KernelError::NonDirectoryPathComponent);
+        }
+
+        // The directory stays locked from the lookup until the new entry is
+        // written, so that creates of the same name are ordered.
+        let mut dir_data = dir_node.data.force_wait_lock();
+        if let Some((ino, _off)) = ov6_fs_path::lookup(&mut *dir_data, name)? {
+            let node = self.get(ino);
+            if ty == T_FILE && (node.ty == T_FILE || node.ty == T_DEVICE) {
+                return Ok(Inode::new(self.dev, ino, node));
+            }
+            self.release(ino, node);
+            return Err(KernelError::CreateAlreadyExists);
+        }
+
+        let (ino, node) = self.alloc(ty, major, minor)?;
+        if let Err(e) = self.link_new(&mut dir_data, dir.ino(), name, ino, &node) {
+            // freed as it has no links.
+            self.release(ino, node);
+            return Err(e);
+        }
+        if ty == T_DIR {
+            // now that success is guaranteed:
+            dir_node.nlink.fetch_add(1, Ordering::Release); // for ".."
+        }
+        node.nlink.store(1, Ordering::Release);
+        Ok(Inode::new(self.dev, ino, node))
+    }
+
+    fn create_unnamed(&self, _token: &mut TxToken, dir: &Inode) -> Result<Inode, KernelError> {
+        self.check_writable()?;
+        if node(dir).ty != T_DIR {
+            return Err(KernelError::NonDirectoryPathComponent);
+        }
+        let (ino, node) = self.alloc(T_FILE, DeviceNo::ROOT, 0)?;
+        Ok(Inode::new(self.dev, ino, node))
+    }
+
+    fn unlink(
+        &self,
+        _token: &mut TxToken,
+        dir: &Inode,
+        name: &OsStr,
+        is_rmdir: bool,
+    ) -> Result<(), KernelError> {
+        self.check_writable()?;
+        let dir_node = node(dir);
+        if dir_node.ty != T_DIR {
+            return Err(KernelError::NonDirectoryPathComponent);
+        }
+        let mut dir_data = dir_node.data.force_wait_lock();
+        let (ino, off) =
+            ov6_fs_path::lookup(&mut *dir_data, name)?.ok_or(KernelError::FsEntryNotFound)?;
+        let node = self.get(ino);
+        let res = Self::remove_entry(dir_node, &mut dir_data, off, &node, is_rmdir);
+        drop(dir_data);
+        // freed here if the inode has no links and is not open.
+        self.release(ino, node);
+        res
+    }
+
+    fn link(
+        &self,
+        _token: &mut TxToken,
+        dir: &Inode,
+        name: &OsStr,
+        inode: &Inode,
+        allow_unlinked: bool,
+    ) -> Result<(), KernelError> {
+        if dir.dev() != inode.dev() {
+            return Err(KernelError::LinkCrossDevices);
+        }
+        self.check_writable()?;
+        let dir_node = node(dir);
+        let node = node(inode);
+        if node.ty == T_DIR {
+            // hard links to directories would make the directory tree a graph.
+            return Err(KernelError::LinkDirectory);
+        }
+        if dir_node.ty != T_DIR {
+            return Err(KernelError::LinkToNonDirectory);
+        }
+
+        let data = node.data.force_wait_lock();
+        let nlink = node.nlink.load(Ordering::Acquire);
+        if nlink == 0 && !allow_unlinked {
+            // unlinked by another process after the lookup.
+            return Err(KernelError::FsEntryNotFound);
+        }
+        if nlink == u16::MAX {
+            return Err(KernelError::TooManyLinks);
+        }
+        // Increment the link count before adding the entry so that the inode
+        // is never referenced by more entries than its link count.
+        node.nlink.store(nlink + 1, Ordering::Release);
+        drop(data);
+
+        let res = dir_node
+            .data
+            .force_wait_lock()
+            .add_entry(self, name, inode.ino());
+        if res.is_err() {
+            let _data = node.data.force_wait_lock();
+            node.nlink.fetch_sub(1, Ordering::Release);
+        }
+        res
+    }
+
+    fn truncate(&self, _token: &mut TxToken, inode: &Inode) -> Result<(), KernelError> {
+        self.check_writable()?;
+        let node = node(inode);
+        if node.ty == T_FILE {
+            node.data.force_wait_lock().clear(self);
+            text_cache::invalidate(self.dev, inode.ino());
+        }
+        Ok(())
+    }
+
+    fn put(&self, inode: Inode) {
+        let ino = inode.ino();
+        let node = inode.into_data().downcast::<Node>().ok().unwrap();
+        self.release(ino, node);
+    }
+}
+
+impl FileOps for TmpFs {
+    fn read(
+        &self,
+        file: &OpenFile,
+        mut dst: GenericMutSlice<u8>,
+        off: Option<usize>,
+        _hint: AccessHint,
+    ) -> Result<usize, KernelError> {
+        let data = node(&file.inode).data.wait_lock()?;
+        let pos = off.unwrap_or_else(|| file.off.load(Ordering::Relaxed));
+        let sz = data.read(&mut dst, pos);
+        if off.is_none() {
+            file.off.fetch_add(sz, Ordering::Relaxed);
+        }
+        Ok(sz)
+    }
+
+    fn write(
+        &self,
+        file: &OpenFile,
+        src: GenericSlice<u8>,
+        off: Option<usize>,
+    ) -> Result<usize, KernelError> {
+        self.check_writable()?;
+        let mut data = node(&file.inode).data.force_wait_lock();
+        let pos = off.unwrap_or_else(|| file.off.load(Ordering::Relaxed));
+        let sz = data.write(Some(self), &src, pos)?;
+        if off.is_none() {
+            file.off.fetch_add(sz, Ordering::Relaxed);
+        }
+        if sz > 0 {
+            text_cache::invalidate(self.dev, file.inode.ino());
+        }
+        Ok(sz)
+    }
+
+    fn access_hint(&self, inode: &Inode) -> Result<AccessHint, KernelError> {
+        Ok(node(inode).data.wait_lock()?.access_hint)
+    }
+
+    fn set_access_hint(&self, inode: &Inode, hint: AccessHint) -> Result<(), KernelError> {
+        node(inode).data.wait_lock()?.access_hint = hint;
+        Ok(())
+    }
+}
@@ -0,0 +1,547 @@
+//! Virtual file system layer.
+//!
+//! A file system type implements [`FileSystemType`] and is registered by
+//! name with [`register_type()`]. Mounting a device creates the
+//! [`SuperBlockOps`] of the file system on it, which is kept in the mount
+//! table and serves the operations on the file system as a whole.
+//!
+//! The super block also gives the [`InodeOps`] that look up, create and
+//! remove the entries of directories and release inodes, and the
+//! [`FileOps`] that files are read and written with. Paths are resolved
+//! through the [`InodeOps`] of each directory on the way, and a directory
+//! that a file system is mounted on is replaced by the root directory of
+//! that file system.
+//!
+//! Each file system keeps its own data for an [`Inode`], and operations that
+//! may start a log transaction borrow the [`TxToken`] of the process, so
+//! that such operations cannot be nested.
+
+use alloc::sync::Arc;
+use core::{
+    any::Any,
+    ops::Deref,
+    sync::atomic::{AtomicU32, AtomicUsize, Ordering},
+};
+
+use arrayvec::ArrayVec;
+use ov6_syscall::{AccessHint, MountFlags, Stat, StatFs};
+use ov6_types::os_str::OsStr;
+
+use super::{DeviceNo, InodeNo, TxToken, repr};
+use crate::{
+    error::KernelError,
+    memory::{
+        addr::{GenericMutSlice, GenericSlice},
+        heap::HeapAllocator,
+    },
+    param::{NFSTYPE, NMOUNT},
+    sync::SpinLock,
+};
+
+/// A kind of file system that can be mounted.
+pub trait FileSystemType: Sync {
+    /// Returns the name of the type, used to mount it.
+    fn name(&self) -> &'static str;
+
+    /// Mounts the file system on `dev`.
+    ///
+    /// `size` limits the size in bytes of a file system kept in memory, and
+    /// is ignored by the others. This may sleep, so it must be called from a
+    /// process.
+    fn mount(
+        &'static self,
+        dev: DeviceNo,
+        size: Option<usize>,
+    ) -> Result<&'static dyn SuperBlockOps, KernelError>;
+}
+
+/// Operations on a mounted file system.
+pub trait SuperBlockOps: Sync {
+    /// Returns the root directory of the file system on `dev`.
+    fn root(&self, dev: DeviceNo) -> Result<Inode, KernelError>;
+
+    /// Returns `Err` if the file system cannot be modified.
+    fn check_writable(&self) -> Result<(), KernelError>;
+
+    /// Returns the statistics of the file system on `dev`.
+    fn statfs(&self, dev: DeviceNo) -> Result<StatFs, KernelError>;
+
+    /// Commits the completed operations to the disk.
+    fn sync(&self) -> Result<(), KernelError>;
+
+    /// Grows the file system on `dev` to `size` blocks, or as large as
+    /// possible if `size` is `None`, and returns the new size.
+    fn resize(&self, dev: DeviceNo, size: Option<u32>) -> Result<u32, KernelError>;
+
+    /// Changes the flags of the file system on `dev`.
+    fn remount(&self, dev: DeviceNo, flags: MountFlags) -> Result<(), KernelError>;
+
+    /// Commits the file system and marks it cleanly unmounted.
+    fn shutdown(&self);
+
+    /// Returns the operations on the inodes of the file system.
+    fn inode_ops(&'static self) -> &'static dyn InodeOps;
+
+    /// Returns the operations on the files of the file system.
+    fn file_ops(&'static self) -> &'static dyn FileOps;
+}
+
+/// Operations on the inodes of a mounted file system.
+pub trait InodeOps: Sync {
+    /// Returns the metadata of `inode`.
+    fn stat(&self, inode: &Inode) -> Result<Stat, KernelError>;
+
+    /// Returns the inode named `name` in the directory `dir`.
+    ///
+    /// `.` and `..` are looked up as well.
+    fn lookup(&self, dir: &Inode, name: &OsStr) -> Result<Inode, KernelError>;
+
+    /// Returns the first entry of the directory `dir` at or after `off`, and
+    /// the offset of the entry following it.
+    ///
+    /// Returns `None` at the end of the directory.
+    fn readdir(
+        &self,
+        dir: &Inode,
+        off: usize,
+    ) -> Result<Option<(repr::DirEntry, usize)>, KernelError>;
+
+    /// Creates an inode of type `ty` named `name` in the directory `dir`.
+    ///
+    /// If a file is created and `name` is an existing file or device, that
+    /// one is returned instead.
+    fn create(
+        &self,
+        token: &mut TxToken,
+        dir: &Inode,
+        name: &OsStr,
+        ty: u16,
+        major: DeviceNo,
+        minor: u16,
+    ) -> Result<Inode, KernelError>;
+
+    /// Creates a file with no links in the file system of the directory
+    /// `dir`.
+    ///
+    /// The file is freed when its last reference is dropped, unless it is
+    /// linked into a directory with [`InodeOps::link()`] before that.
+    fn create_unnamed(&self, token: &mut TxToken, dir: &Inode) -> Result<Inode, KernelError>;
+
+    /// Removes the entry `name` from the directory `dir`.
+    ///
+    /// The entry must be an empty directory if `is_rmdir` is `true`, and
+    /// must not be a directory otherwise.
+    fn unlink(
+        &self,
+        token: &mut TxToken,
+        dir: &Inode,
+        name: &OsStr,
+        is_rmdir: bool,
+    ) -> Result<(), KernelError>;
+
+    /// Adds an entry `name` referring to `inode` to the directory `dir`.
+    ///
+    /// `inode` may have no links only if `allow_unlinked` is `true`.
+    fn link(
+        &self,
+        token: &mut TxToken,
+        dir: &Inode,
+        name: &OsStr,
+        inode: &Inode,
+        allow_unlinked: bool,
+    ) -> Result<(), KernelError>;
+
+    /// Truncates `inode` to zero length if it is a regular file.
+    fn truncate(&self, token: &mut TxToken, inode: &Inode) -> Result<(), KernelError>;
+
+    /// Drops the reference to `inode`.
+    ///
+    /// If that was the last reference and the inode has no links, the inode
+    /// and its content are freed.
+    fn put(&self, inode: Inode);
+}
+
+/// Operations on the files of a mounted file system.
+pub trait FileOps: Sync {
+    /// Reads from `file` at `off` into `dst`, or from the file offset and
+    /// advances it if `off` is `None`.
+    ///
+    /// `hint` is the access pattern expected by the file descriptor.
+    fn read(
+        &self,
+        file: &OpenFile,
+        dst: GenericMutSlice<u8>,
+        off: Option<usize>,
+        hint: AccessHint,
+    ) -> Result<usize, KernelError>;
+
+    /// Writes `src` to `file` at `off`, or at the file offset and advances it
+    /// if `off` is `None`.
+    fn write(
+        &self,
+        file: &OpenFile,
+        src: GenericSlice<u8>,
+        off: Option<usize>,
+    ) -> Result<usize, KernelError>;
+
+    /// Returns the access hint shared by all open files of `inode`.
+    fn access_hint(&self, inode: &Inode) -> Result<AccessHint, KernelError>;
+
+    /// Sets the access hint shared by all open files of `inode`.
+    fn set_access_hint(&self, inode: &Inode, hint: AccessHint) -> Result<(), KernelError>;
+}
+
+/// Data that a file system keeps for an in-memory inode.
+pub type InodePrivate = Arc<dyn Any + Send + Sync, HeapAllocator>;
+
+/// A reference to an inode of a mounted file system.
+///
+/// The reference must be dropped with [`put()`], since releasing the last
+/// reference may free the inode.
+#[derive(Clone)]
+pub struct Inode {
+    dev: DeviceNo,
+    ino: InodeNo,
+    data: Option<InodePrivate>,
+}
+
+impl Inode {
+    pub fn new(dev: DeviceNo, ino: InodeNo, data: InodePrivate) -> Self {
+        Self {
+            dev,
+            ino,
+            data: Some(data),
+        }
+    }
+
+    pub fn dev(&self) -> DeviceNo {
+        self.dev
+    }
+
+    pub fn ino(&self) -> InodeNo {
+        self.ino
+    }
+
+    /// Returns `true` if `self` and `other` refer to the same inode.
+    pub fn is_same(&self, other: &Self) -> bool {
+        self.dev == other.dev && self.ino == other.ino
+    }
+
+    /// Returns the data kept by the file system.
+    pub fn data(&self) -> &InodePrivate {
+        self.data.as_ref().unwrap()
+    }
+
+    /// Consumes the reference and returns the data kept by the file system.
+    pub fn into_data(mut self) -> InodePrivate {
+        self.data.take().unwrap()
+    }
+}
+
+impl Drop for Inode {
+    fn drop(&mut self) {
+        assert!(
+            self.data.is_none(),
+            "vfs::put() must be called before dropped"
+        );
+    }
+}
+
+/// An [`Inode`] that is dropped with [`put()`] when it goes out of scope.
+///
+/// It must not be dropped in a log transaction, as [`put()`] may start one.
+#[derive(Clone)]
+pub struct InodeGuard(Option<Inode>);
+
+impl InodeGuard {
+    pub fn new(inode: Inode) -> Self {
+        Self(Some(inode))
+    }
+
+    /// Returns the inode, which must then be dropped with [`put()`].
+    pub fn into_inner(mut self) -> Inode {
+        self.0.take().unwrap()
+    }
+}
+
+impl Deref for InodeGuard {
+    type Target = Inode;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref().unwrap()
+    }
+}
+
+impl Drop for InodeGuard {
+    fn drop(&mut self) {
+        if let Some(inode) = self.0.take() {
+            put(inode);
+        }
+    }
+}
+
+/// A file opened in a mounted file system.
+pub struct OpenFile {
+    /// Inode of the file
+    pub inode: Inode,
+    /// File offset
+    pub off: AtomicUsize,
+    /// Transfers block-aligned data without going through the caches.
+    pub direct: bool,
+}
+
+enum MountState {
+    /// The file system is being mounted, and cannot be used yet.
+    Mounting,
+    Mounted {
+        sb: &'static dyn SuperBlockOps,
+        /// Root directory of the file system
+        root: Inode,
+    },
+}
+
+struct Mount {
+    dev: DeviceNo,
+    state: MountState,
+    /// Directory the file system is mounted on, or `None` for the root file
+    /// system.
+    covered: Option<Inode>,
+}
+
+impl Mount {
+    fn sb(&self) -> Option<&'static dyn SuperBlockOps> {
+        match self.state {
+            MountState::Mounting => None,
+            MountState::Mounted { sb, .. } => Some(sb),
+        }
+    }
+
+    fn root(&self) -> Option<&Inode> {
+        match &self.state {
+            MountState::Mounting => None,
+            MountState::Mounted { root, .. } => Some(root),
+        }
+    }
+}
+
+static TYPES: SpinLock<ArrayVec<&'static dyn FileSystemType, NFSTYPE>> =
+    SpinLock::new(ArrayVec::new_const());
+static MOUNTS: SpinLock<ArrayVec<Mount, NMOUNT>> = SpinLock::new(ArrayVec::new_const());
+
+/// Registers a file system type.
+pub fn register_type(ty: &'static dyn FileSystemType) -> Result<(), KernelError> {
+    let mut types = TYPES.lock();
+    if types.iter().any(|t| t.name() == ty.name()) {
+        return Err(KernelError::FsTypeAlreadyRegistered);
+    }
+    #[expect(clippy::map_err_ignore)]
+    types.try_push(ty).map_err(|_| KernelError::NoFreeFsType)?;
+    Ok(())
+}
+
+/// Mounts the file system of type `name` on `dev`.
+///
+/// The file system is mounted on the directory `at`, or is the root file
+/// system if `at` is `None`. See [`FileSystemType::mount()`] for `size`.
+pub fn mount(
+    name: &str,
+    dev: DeviceNo,
+    at: Option<&Inode>,
+    size: Option<usize>,
+) -> Result<(), KernelError> {
+    let ty = TYPES
+        .lock()
+        .iter()
+        .find(|ty| ty.name() == name)
+        .copied()
+        .ok_or(KernelError::FsTypeNotFound)?;
+
+    // mounting reads the disk, so the table is not locked meanwhile.
+    // the slot reserved here keeps others from mounting `dev` or mounting on
+    // `at` until then.
+    {
+        let mut mounts = MOUNTS.lock();
+        if mounts.iter().any(|m| m.dev == dev) {
+            return Err(KernelError::DeviceAlreadyMounted(dev));
+        }
+        if mounts.iter().any(|m| is_covered(m, at)) {
+            return Err(KernelError::MountPointBusy);
+        }
+        if mounts.is_full() {
+            return Err(KernelError::NoFreeMount);
+        }
+        mounts.push(Mount {
+            dev,
+            state: MountState::Mounting,
+            covered: at.cloned(),
+        });
+    }
+
+    let res = ty.mount(dev, size).and_then(|sb| Ok((sb, sb.root(dev)?)));
+    let mut mounts = MOUNTS.lock();
+    let idx = mounts.iter().position(|m| m.dev == dev).unwrap();
+    match res {
+        Ok((sb, root)) => {
+            mounts[idx].state = MountState::Mounted { sb, root };
+            Ok(())
+        }
+        Err(e) => {
+            let m = mounts.remove(idx);
+            drop(mounts);
+            if let Some(covered) = m.covered {
+                put(covered);
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Mounts the file system of type `name`, which has no device, on the
+/// directory `at`.
+///
+/// A device number from [`DeviceNo::FIRST_NODEV`] is allocated to identify
+/// the file system, and is returned.
+pub fn mount_nodev(name: &str, at: &Inode, size: Option<usize>) -> Result<DeviceNo, KernelError> {
+    static NEXT_DEV: AtomicU32 = AtomicU32::new(DeviceNo::FIRST_NODEV.value());
+
+    let dev = DeviceNo::new(NEXT_DEV.fetch_add(1, Ordering::Relaxed));
+    mount(name, dev, Some(at), size)?;
+    Ok(dev)
+}
+
+/// Returns `true` if `m` is mounted on `at`, or is the root file system if
+/// `at` is `None`.
+fn is_covered(m: &Mount, at: Option<&Inode>) -> bool {
+    match (&m.covered, at) {
+        (Some(covered), Some(at)) => covered.is_same(at),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+/// Returns the file system mounted on `dev`.
+fn lookup(dev: DeviceNo) -> Result<&'static dyn SuperBlockOps, KernelError> {
+    MOUNTS
+        .lock()
+        .iter()
+        .find(|m| m.dev == dev)
+        .and_then(Mount::sb)
+        .ok_or(KernelError::DeviceNotFound(dev))
+}
+
+/// Returns the root directory of the root file system.
+pub fn root_dir() -> Result<Inode, KernelError> {
+    MOUNTS
+        .lock()
+        .iter()
+        .find(|m| m.covered.is_none())
+        .and_then(Mount::root)
+        .cloned()
+        .ok_or(KernelError::DeviceNotFound(DeviceNo::ROOT))
+}
+
+/// Returns the root directory of the file system mounted on `dir`.
+///
+/// If another file system is mounted on that root directory, its root
+/// directory is returned instead. Returns `None` if nothing is mounted on
+/// `dir`.
+pub fn mounted_root(dir: &Inode) -> Option<Inode> {
+    let mounts = MOUNTS.lock();
+    let mut root = None;
+    let mut ip = dir;
+    while let Some(next) = mounts
+        .iter()
+        .find(|m| m.covered.as_ref().is_some_and(|c| c.is_same(ip)))
+        .and_then(Mount::root)
+    {
+        root = Some(next);
+        ip = next;
+    }
+    root.cloned()
+}
+
+/// Returns the directory that the file system whose root directory is `dir`
+/// is mounted on.
+///
+/// If that directory is the root of another mounted file system, the
+/// directory that one is mounted on is returned instead. Returns `None` if
+/// `dir` is not the root directory of a file system mounted on a directory.
+pub fn covered(dir: &Inode) -> Option<Inode> {
+    let mounts = MOUNTS.lock();
+    let mut covered = None;
+    let mut ip = dir;
+    while let Some(next) = mounts
+        .iter()
+        .find(|m| m.root().is_some_and(|r| r.is_same(ip)))
+        .and_then(|m| m.covered.as_ref())
+    {
+        covered = Some(next);
+        ip = next;
+    }
+    covered.cloned()
+}
+
+/// Returns the inode operations of the file system mounted on `dev`.
+pub fn inode_ops(dev: DeviceNo) -> Result<&'static dyn InodeOps, KernelError> {
+    Ok(lookup(dev)?.inode_ops())
+}
+
+/// Returns the file operations of the file system mounted on `dev`.
+pub fn file_ops(dev: DeviceNo) -> Result<&'static dyn FileOps, KernelError> {
+    Ok(lookup(dev)?.file_ops())
+}
+
+/// Drops the reference to `inode`.
+///
+/// See [`InodeOps::put()`].
+pub fn put(inode: Inode) {
+    // file systems are never unmounted, so the inode's one is still there.
+    inode_ops(inode.dev()).unwrap().put(inode);
+}
+
+/// Returns the status of `inode`.
+pub fn stat(inode: &Inode) -> Result<Stat, KernelError> {
+    inode_ops(inode.dev())?.stat(inode)
+}
+
+/// Returns `Err` if the file system mounted on `dev` cannot be modified.
+pub fn check_writable(dev: DeviceNo) -> Result<(), KernelError> {
+    lookup(dev)?.check_writable()
+}
+
+/// Returns the statistics of the file system mounted on `dev`.
+pub fn statfs(dev: DeviceNo) -> Result<StatFs, KernelError> {
+    lookup(dev)?.statfs(dev)
+}
+
+/// Returns the super blocks of the mounted file systems.
+fn super_blocks() -> ArrayVec<&'static dyn SuperBlockOps, NMOUNT> {
+    MOUNTS.lock().iter().filter_map(Mount::sb).collect()
+}
+
+/// Commits the completed operations of all mounted file systems.
+pub fn sync() -> Result<(), KernelError> {
+    for sb in super_blocks() {
+        sb.sync()?;
+    }
+    Ok(())
+}
+
+/// Grows the file system mounted on `dev`.
+///
+/// See [`SuperBlockOps::resize()`].
+pub fn resize(dev: DeviceNo, size: Option<u32>) -> Result<u32, KernelError> {
+    lookup(dev)?.resize(dev, size)
+}
+
+/// Changes the flags of the file system mounted on `dev`.
+pub fn remount(dev: DeviceNo, flags: MountFlags) -> Result<(), KernelError> {
+    lookup(dev)?.remount(dev, flags)
+}
+
+/// Shuts down all mounted file systems.
+pub fn shutdown() {
+    for sb in super_blocks() {
+        sb.shutdown();
+    }
+}
@@ -34,6 +34,8 @@ pub enum MmioRegister {
     DriverDescHigh = 0x094,
     DeviceDescLow = 0x0a0, // physical address for used ring, write-only
     DeviceDescHigh = 0x0a4,
+    ConfigCapacityLow = 0x100, // disk size in sectors, read-only
+    ConfigCapacityHigh = 0x104,
 }
 
 // Status register bits, from qemu virtio_config.h
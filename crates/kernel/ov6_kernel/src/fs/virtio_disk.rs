@@ -1,7 +1,13 @@
 use alloc::boxed::Box;
-use core::{array, mem, pin::Pin, ptr, sync::atomic::Ordering};
+use core::{
+    array, mem,
+    pin::Pin,
+    ptr,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
 use once_init::OnceInit;
+use ov6_syscall::DiskInfo;
 use safe_cast::{SafeInto as _, to_u16, to_u32};
 use vcell::VolatileCell;
 
@@ -13,7 +19,15 @@ use crate::{
             VirtioBlkReqType, VirtqAvail, VirtqDesc, VirtqDescFlags, VirtqUsed,
         },
     },
-    memory::{layout::VIRTIO0, page::PageFrameAllocator},
+    interrupt::{
+        deferred::{self, Work},
+        plic,
+    },
+    memory::{
+        PhysAddr,
+        layout::{VIRTIO0, VIRTIO0_IRQ},
+        page::PageFrameAllocator,
+    },
     sync::{SpinLock, SpinLockCondVar},
 };
 
@@ -75,13 +89,16 @@ struct TrackInfo {
 
 static DISK: OnceInit<SpinLock<Disk<NUM>>> = OnceInit::new();
 
+static READS: AtomicUsize = AtomicUsize::new(0);
+static WRITES: AtomicUsize = AtomicUsize::new(0);
+
 fn addr_low<T>(p: &T) -> u32 {
-    let addr = ptr::from_ref(p).addr();
+    let addr = PhysAddr::from(p).addr();
     (addr & 0xffff_ffff).try_into().unwrap()
 }
 
 fn addr_high<T>(p: &T) -> u32 {
-    let addr = ptr::from_ref(p).addr();
+    let addr = PhysAddr::from(p).addr();
     ((addr >> 32) & 0xffff_ffff).try_into().unwrap()
 }
 
@@ -298,7 +315,7 @@ impl<const N: usize> Disk<N> {
             reserved: 0,
             sector,
         };
-        let buf0_addr = ptr::from_mut(buf0).addr();
+        let buf0_addr = PhysAddr::from(buf0).addr();
 
         self.desc[usize::from(desc_idx[0])] = VirtqDesc {
             addr: buf0_addr as u64,
@@ -308,7 +325,7 @@ impl<const N: usize> Disk<N> {
         };
 
         self.desc[usize::from(desc_idx[1])] = VirtqDesc {
-            addr: req.as_ptr().addr() as u64,
+            addr: PhysAddr::from(req.as_ptr()).addr() as u64,
             len: to_u32!(FS_BLOCK_SIZE),
             flags: req.flag() | VirtqDescFlags::NEXT,
             next: desc_idx[2],
@@ -317,13 +334,13 @@ impl<const N: usize> Disk<N> {
         let info = &mut self.info[usize::from(desc_idx[0])];
         info.status.set(0xff); // device writes 0 on success
         self.desc[usize::from(desc_idx[2])] = VirtqDesc {
-            addr: info.status.as_ptr().addr().safe_into(),
+            addr: PhysAddr::from(info.status.as_ptr()).addr().safe_into(),
             len: 1,
             flags: VirtqDescFlags::WRITE,
             next: 0,
         };
 
-        // record struct buf for `handle_interrupt()`.
+        // record struct buf for `complete_requests()`.
         info.in_progress = true;
 
         // tell the device the first index in our chain of descriptors.
@@ -344,6 +361,25 @@ pub(super) fn init() {
     let disk = Disk::<NUM>::new(VIRTIO0, &DESC_FREED, &REQ_COMPLETED);
     disk.init();
     DISK.init(SpinLock::new(disk));
+    deferred::register(Work::DiskCompletion, complete_requests);
+    plic::register(VIRTIO0_IRQ, handle_interrupt);
+}
+
+/// Returns the size of the disk in bytes.
+pub(super) fn capacity() -> usize {
+    let disk = DISK.get().lock();
+    let low = u64::from(disk.read_reg(MmioRegister::ConfigCapacityLow));
+    let high = u64::from(disk.read_reg(MmioRegister::ConfigCapacityHigh));
+    let sectors: usize = ((high << 32) | low).try_into().unwrap();
+    sectors * BLK_SECTOR_SIZE
+}
+
+/// Resets the device.
+///
+/// Requests in flight are never completed.
+pub fn reset() {
+    let disk = DISK.get().lock();
+    disk.write_reg(MmioRegister::Status, ConfigStatus::empty().bits());
 }
 
 fn read_or_write(offset: usize, req: &Request) {
@@ -374,15 +410,25 @@ fn read_or_write(offset: usize, req: &Request) {
 }
 
 pub(super) fn read(offset: usize, data: &mut [u8]) {
+    READS.fetch_add(1, Ordering::Relaxed);
     read_or_write(offset, &Request::DeviceToBuf(data));
 }
 
 pub(super) fn write(offset: usize, data: &[u8]) {
+    WRITES.fetch_add(1, Ordering::Relaxed);
     read_or_write(offset, &Request::BufToDevice(data));
 }
 
+/// Returns the number of requests sent to the disk.
+pub(super) fn info() -> DiskInfo {
+    DiskInfo {
+        reads: READS.load(Ordering::Relaxed),
+        writes: WRITES.load(Ordering::Relaxed),
+    }
+}
+
 pub fn handle_interrupt() {
-    let mut disk = DISK.get().lock();
+    let disk = DISK.get().lock();
 
     // the device won't raise another interrupt until we tell it
     // we've seen this interrupt, which the following line does.
@@ -394,6 +440,14 @@ pub fn handle_interrupt() {
         MmioRegister::InterruptAck,
         disk.read_reg(MmioRegister::InterruptStatus) & 0x3,
     );
+    drop(disk);
+
+    deferred::schedule(Work::DiskCompletion);
+}
+
+/// Wakes up the waiters of the requests completed by the device.
+fn complete_requests() {
+    let mut disk = DISK.get().lock();
 
     // the device increments disk.used.idx when it
     // adds an entry to the used ring.
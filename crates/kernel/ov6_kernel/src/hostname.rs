@@ -0,0 +1,48 @@
+//! Host name of the system.
+//!
+//! The name is held by the kernel and read and changed with the
+//! `GetHostname` and `SetHostname` system calls. It is seeded at boot from
+//! the `OV6_HOSTNAME` build parameter, and init later sets it from
+//! `/etc/hostname`.
+
+use arrayvec::ArrayVec;
+use ov6_syscall::HOST_NAME_MAX;
+
+use crate::{error::KernelError, sync::SpinLock};
+
+/// Host name used if none is given at build time.
+const DEFAULT_HOSTNAME: &str = "ov6";
+
+static HOSTNAME: SpinLock<ArrayVec<u8, HOST_NAME_MAX>> = SpinLock::new(ArrayVec::new_const());
+
+pub fn init() {
+    let name = option_env!("OV6_HOSTNAME")
+        .filter(|name| validate(name.as_bytes()).is_ok())
+        .unwrap_or(DEFAULT_HOSTNAME);
+    set(name.as_bytes()).unwrap();
+}
+
+/// Checks that `name` is a valid host name.
+///
+/// A host name is 1 to [`HOST_NAME_MAX`] bytes of printable, non-whitespace
+/// ASCII characters.
+fn validate(name: &[u8]) -> Result<(), KernelError> {
+    if name.is_empty() || name.len() > HOST_NAME_MAX || !name.iter().all(u8::is_ascii_graphic) {
+        return Err(KernelError::InvalidHostname);
+    }
+    Ok(())
+}
+
+/// Returns the current host name.
+pub fn get() -> ArrayVec<u8, HOST_NAME_MAX> {
+    HOSTNAME.lock().clone()
+}
+
+/// Changes the host name.
+pub fn set(name: &[u8]) -> Result<(), KernelError> {
+    validate(name)?;
+    let mut hostname = HOSTNAME.lock();
+    hostname.clear();
+    hostname.try_extend_from_slice(name).unwrap();
+    Ok(())
+}
@@ -9,7 +9,7 @@ use riscv::register::{
     sie,
 };
 
-use crate::{cpu, interrupt::timer, param::NCPU};
+use crate::{boot_args, cpu, interrupt::timer, param::NCPU};
 
 // entry.s needs one stack per CPU.
 const KERNEL_STACK_SIZE: usize = 4096;
@@ -38,12 +38,13 @@ extern "C" fn boot() {
 
         // set up a stack for kernel.
         // sp = kernel_stack + ((hartid + 1) * stack_size)
-        "la sp, {kernel_stack}",
-        "li a0, {stack_size}",
-        "csrr a1, mhartid",
-        "addi a1, a1, 1",
-        "mul a0, a0, a1",
-        "add sp, sp, a0",
+        // a0 (hartid) and a1 (device tree address) are passed through to init.
+        "lla sp, {kernel_stack}",
+        "li t0, {stack_size}",
+        "csrr t1, mhartid",
+        "addi t1, t1, 1",
+        "mul t0, t0, t1",
+        "add sp, sp, t0",
 
         // jump to init
         "call {init}",
@@ -53,7 +54,9 @@ extern "C" fn boot() {
     );
 }
 
-extern "C" fn init() -> ! {
+extern "C" fn init(_hartid: usize, dtb_addr: usize) -> ! {
+    boot_args::set_dtb_addr(dtb_addr);
+
     // set M Previous Privilege mode to Supervisor, for mret.
     unsafe {
         mstatus::set_mpp(mstatus::MPP::Supervisor);
@@ -111,7 +114,7 @@ extern "C" fn entry() -> ! {
         // set up stack for kernel
         // sp = kernel_stack + ((tp + 1) * stack_size)
         // where tp = mhartid
-        "la sp, {kernel_stack}",
+        "lla sp, {kernel_stack}",
         "li a0, {stack_size}",
         "addi a1, tp, 1",
         "mul a0, a0, a1",
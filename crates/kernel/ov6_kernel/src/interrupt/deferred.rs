@@ -0,0 +1,82 @@
+//! Work deferred from device interrupt handlers (bottom halves).
+//!
+//! A device interrupt handler only acknowledges the device and calls
+//! [`schedule()`]. The work itself runs from the supervisor software interrupt
+//! (SSIP) of the same CPU, which is taken as soon as the hard handler has
+//! returned and interrupts are enabled again. External interrupts have a higher
+//! priority than software interrupts, so device interrupts that arrive in the
+//! meantime are served first.
+
+use core::{
+    arch::asm,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use once_init::OnceInit;
+
+use crate::{cpu, param::NCPU};
+
+/// Kinds of deferred work.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum Work {
+    /// Processing of completed disk requests.
+    DiskCompletion,
+    /// Processing of received network packets.
+    NetReceive,
+    /// Firing of expired kernel timers.
+    Timers,
+}
+
+const NWORK: usize = 3;
+
+/// Supervisor software interrupt pending bit of `sip`.
+const SIP_SSIP: usize = 1 << 1;
+
+static HANDLERS: [OnceInit<fn()>; NWORK] = [const { OnceInit::new() }; NWORK];
+
+/// Bitmap of the scheduled [`Work`] for each CPU.
+static PENDING: [AtomicU32; NCPU] = [const { AtomicU32::new(0) }; NCPU];
+
+/// Registers `handler` to run `work`.
+///
+/// # Panics
+///
+/// Panics if `work` already has a handler.
+pub fn register(work: Work, handler: fn()) {
+    assert!(
+        HANDLERS[work as usize].try_init(handler).is_ok(),
+        "{work:?} is already registered"
+    );
+}
+
+/// Schedules `work` to run on this CPU after the current interrupt handler
+/// returns.
+///
+/// Scheduling work that is already pending has no effect.
+pub fn schedule(work: Work) {
+    assert!(!super::is_enabled());
+    PENDING[cpu::id()].fetch_or(1 << work as u32, Ordering::Relaxed);
+    unsafe {
+        asm!("csrs sip, {}", in(reg) SIP_SSIP);
+    }
+}
+
+/// Runs the work scheduled on this CPU.
+///
+/// Called from the supervisor software interrupt, with interrupts disabled.
+/// Work scheduled again by a handler runs on the next software interrupt,
+/// after the interrupts that became pending in the meantime.
+pub(super) fn handle() {
+    unsafe {
+        asm!("csrc sip, {}", in(reg) SIP_SSIP);
+    }
+
+    let works = PENDING[cpu::id()].swap(0, Ordering::Relaxed);
+    for (i, handler) in HANDLERS.iter().enumerate() {
+        if works & (1 << i) != 0 {
+            let handler = handler.get();
+            handler();
+        }
+    }
+}
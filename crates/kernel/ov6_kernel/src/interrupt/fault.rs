@@ -0,0 +1,131 @@
+//! Reports of user memory faults.
+//!
+//! When a user process is killed by a memory access exception, the kernel
+//! prints what kind of access faulted, why it was not allowed, and where the
+//! address lies relative to the regions of the address space.
+
+use core::{fmt, ops::Range};
+
+use riscv::interrupt::supervisor::Exception;
+
+use crate::memory::{VirtAddr, page_table::PtEntryFlags, vm_user::UserPageTable};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Access {
+    Read,
+    Write,
+    Execute,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cause {
+    Misaligned,
+    NotMapped,
+    /// The page is mapped, but not with the permission for the access.
+    Permission,
+    /// A write to a copy-on-write page whose copy could not be made.
+    CopyOnWrite,
+}
+
+pub(super) struct FaultReport {
+    access: Access,
+    cause: Cause,
+    addr: usize,
+    /// The region closest to `addr`.
+    nearest: Option<(&'static str, Range<VirtAddr>)>,
+    /// Whether `addr` is below the stack, and closer to it than to any other
+    /// region.
+    below_stack: bool,
+}
+
+impl FaultReport {
+    /// Classifies exception `e` at address `addr` by looking at the page
+    /// table.
+    ///
+    /// Returns `None` if `e` is not a memory access exception.
+    pub(super) fn new(e: Exception, addr: usize, pt: &UserPageTable) -> Option<Self> {
+        let (access, misaligned) = match e {
+            Exception::InstructionMisaligned => (Access::Execute, true),
+            Exception::InstructionFault | Exception::InstructionPageFault => {
+                (Access::Execute, false)
+            }
+            Exception::LoadMisaligned => (Access::Read, true),
+            Exception::LoadFault | Exception::LoadPageFault => (Access::Read, false),
+            Exception::StoreMisaligned => (Access::Write, true),
+            Exception::StoreFault | Exception::StorePageFault => (Access::Write, false),
+            _ => return None,
+        };
+
+        let flags = VirtAddr::new(addr).ok().and_then(|va| pt.page_flags(va));
+        let cause = match flags {
+            _ if misaligned => Cause::Misaligned,
+            None => Cause::NotMapped,
+            Some(flags) if access == Access::Write && flags.contains(PtEntryFlags::C) => {
+                Cause::CopyOnWrite
+            }
+            Some(_) => Cause::Permission,
+        };
+
+        let nearest = pt
+            .regions()
+            .into_iter()
+            .filter(|(_name, range)| !range.is_empty())
+            .min_by_key(|(_name, range)| distance(addr, range));
+        let below_stack = nearest
+            .as_ref()
+            .is_some_and(|(name, range)| *name == "stack" && addr < range.start.addr());
+
+        Some(Self {
+            access,
+            cause,
+            addr,
+            nearest,
+            below_stack,
+        })
+    }
+}
+
+/// Returns the distance in bytes from `addr` to `range`.
+fn distance(addr: usize, range: &Range<VirtAddr>) -> usize {
+    if addr < range.start.addr() {
+        range.start.addr() - addr
+    } else if addr >= range.end.addr() {
+        addr - range.end.addr() + 1
+    } else {
+        0
+    }
+}
+
+impl fmt::Display for FaultReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let access = match self.access {
+            Access::Read => "read",
+            Access::Write => "write",
+            Access::Execute => "execute",
+        };
+        let cause = match self.cause {
+            Cause::Misaligned => "misaligned address",
+            Cause::NotMapped => "page not mapped",
+            Cause::Permission => "page not accessible",
+            Cause::CopyOnWrite => "copy-on-write page could not be copied",
+        };
+        write!(f, "          {access} at {:#x}: {cause}", self.addr)?;
+
+        if let Some((name, range)) = &self.nearest {
+            let (start, end) = (range.start.addr(), range.end.addr());
+            write!(f, "\n          nearest region: {name} {start:#x}..{end:#x}")?;
+            if self.addr < start {
+                write!(f, " ({:#x} bytes below)", start - self.addr)?;
+            } else if self.addr >= end {
+                write!(f, " ({:#x} bytes above)", self.addr - end)?;
+            }
+        }
+        if self.below_stack {
+            write!(
+                f,
+                "\n          below the user stack: likely a stack overflow"
+            )?;
+        }
+        Ok(())
+    }
+}
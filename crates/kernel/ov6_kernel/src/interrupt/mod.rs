@@ -10,9 +10,12 @@ use riscv::register::sstatus;
 use crate::{cpu, param::NCPU};
 
 pub mod clic;
+pub mod deferred;
+mod fault;
 mod kernel_vec;
 pub mod plic;
 pub mod timer;
+pub mod timer_wheel;
 pub mod trampoline;
 pub mod trap;
 
@@ -1,38 +1,53 @@
 //! the RISC-V Platform Level Interrupt Controller (PLIC).
+//!
+//! Device drivers register a handler for their IRQ with [`register()`].
+//! [`handle()`] claims a pending interrupt and calls the handler, so adding a
+//! device does not need any change in the trap dispatch.
 
-use core::ptr;
+use core::{
+    ptr,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use once_init::OnceInit;
+use ov6_syscall::{InterruptInfo, NIRQ};
 
 use crate::{
     cpu,
-    memory::layout::{PLIC, UART0_IRQ, VIRTIO0_IRQ, plic_sclaim, plic_senable, plic_spriority},
+    memory::layout::{PLIC, plic_sclaim, plic_senable, plic_spriority},
 };
 
-pub fn init() {
-    // set desired IRQ priorities non-zero (otherwise disabled).
-    unsafe {
-        ptr::with_exposed_provenance_mut::<u32>(PLIC + UART0_IRQ * 4).write_volatile(1);
-        ptr::with_exposed_provenance_mut::<u32>(PLIC + VIRTIO0_IRQ * 4).write_volatile(1);
+static HANDLERS: [OnceInit<fn()>; NIRQ] = [const { OnceInit::new() }; NIRQ];
+static COUNTS: [AtomicUsize; NIRQ] = [const { AtomicUsize::new(0) }; NIRQ];
+static SPURIOUS: AtomicUsize = AtomicUsize::new(0);
 
-        // PCIe IRQs are 32 to 35
-        for irq in 1..0x35 {
-            ptr::with_exposed_provenance_mut::<u32>(PLIC + irq * 4).write_volatile(1);
-        }
+/// Registers `handler` to be called on interrupts from `irq`, and sets the
+/// priority of `irq` non-zero (otherwise disabled).
+///
+/// # Panics
+///
+/// Panics if `irq` is out of range or already has a handler.
+pub fn register(irq: usize, handler: fn()) {
+    assert!(0 < irq && irq < NIRQ, "invalid irq={irq}");
+    assert!(
+        HANDLERS[irq].try_init(handler).is_ok(),
+        "irq={irq} is already registered"
+    );
+    unsafe {
+        ptr::with_exposed_provenance_mut::<u32>(PLIC + irq * 4).write_volatile(1);
     }
 }
 
 pub fn init_hart() {
     let hart = cpu::id();
 
-    // set enable bits for this hart's S-mode
-    // for the uart and virtio disk.
-    unsafe {
-        ptr::with_exposed_provenance_mut::<u32>(plic_senable(hart))
-            .write_volatile((1 << UART0_IRQ) | (1 << VIRTIO0_IRQ));
-    }
-
-    // hack to get at next 32 IRQs for E1000
-    unsafe {
-        ptr::with_exposed_provenance_mut::<u32>(plic_senable(hart) + 4).write_volatile(0xffff_ffff);
+    // set enable bits for this hart's S-mode for all IRQs.
+    // IRQs without a handler keep priority 0, so they are never delivered.
+    for word in 0..NIRQ / 32 {
+        unsafe {
+            ptr::with_exposed_provenance_mut::<u32>(plic_senable(hart) + word * 4)
+                .write_volatile(0xffff_ffff);
+        }
     }
 
     // set this hart's S-mode priority threshold to 0
@@ -41,14 +56,50 @@ pub fn init_hart() {
     }
 }
 
+/// Serves a supervisor external interrupt.
+///
+/// Claims the pending IRQ, calls its handler, and tells the PLIC that it has
+/// been served. An interrupt with no IRQ pending (another hart already claimed
+/// it) or with no handler registered is counted as spurious.
+pub fn handle() {
+    // irq indicates which device interrupted.
+    let irq = claim();
+    if irq == 0 {
+        SPURIOUS.fetch_add(1, Ordering::Relaxed);
+        return;
+    }
+
+    let irq = usize::try_from(irq).unwrap();
+    if let Some(handler) = HANDLERS.get(irq).and_then(|h| h.try_get().ok()) {
+        COUNTS[irq].fetch_add(1, Ordering::Relaxed);
+        handler();
+    } else {
+        SPURIOUS.fetch_add(1, Ordering::Relaxed);
+        crate::println!("unexpected interrupt irq={irq}");
+    }
+
+    // the PLIC allows each device to raise at most one
+    // interrupt at a time; tell the PLIC the device is
+    // now allowed to interrupt again.
+    complete(u32::try_from(irq).unwrap());
+}
+
+/// Returns the number of interrupts served for each IRQ.
+pub fn info() -> InterruptInfo {
+    InterruptInfo {
+        counts: COUNTS.each_ref().map(|c| c.load(Ordering::Relaxed)),
+        spurious: SPURIOUS.load(Ordering::Relaxed),
+    }
+}
+
 /// Asks the PLIC what interrupt we should serve.
-pub fn claim() -> u32 {
+fn claim() -> u32 {
     let hart = cpu::id();
     unsafe { ptr::with_exposed_provenance_mut::<u32>(plic_sclaim(hart)).read_volatile() }
 }
 
 /// Tells the PLIC we've served this IRQ.
-pub fn complete(irq: u32) {
+fn complete(irq: u32) {
     let hart = cpu::id();
     unsafe {
         ptr::with_exposed_provenance_mut::<u32>(plic_sclaim(hart)).write_volatile(irq);
@@ -1,21 +1,43 @@
-use core::{arch::asm, time::Duration};
+use core::{
+    arch::asm,
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    time::Duration,
+};
 
 use riscv::register::{mcounteren, mie, scounteren};
+use safe_cast::SafeFrom as _;
 
+use super::{
+    deferred::{self, Work},
+    trap::TrapFrame,
+};
 use crate::{
-    cpu,
-    sync::{SpinLock, SpinLockCondVar},
+    boot_args,
+    cpu::{self, Cpu},
+    println, proc,
 };
 
 const NANOS_PER_CLOCK: u64 = 100;
 const NANOS_PER_SEC: u64 = 1_000_000_000;
-const TICKS_PER_SEC: u64 = 10;
+pub const TICKS_PER_SEC: u64 = 10;
 const NANOS_PER_TICK: u64 = NANOS_PER_SEC / TICKS_PER_SEC;
 const CLOCKS_PER_TICK: u64 = NANOS_PER_TICK / NANOS_PER_CLOCK;
 pub const NANOS_PER_TICKS: u64 = NANOS_PER_SEC / TICKS_PER_SEC;
 
-pub static TICKS: SpinLock<u64> = SpinLock::new(0);
-pub static TICKS_UPDATED: SpinLockCondVar = SpinLockCondVar::new();
+static TICKS: AtomicU64 = AtomicU64::new(0);
+
+/// Whether the clock is virtual (the `virtual_time` boot option).
+///
+/// In this mode, the time seen by the kernel and by user processes is derived
+/// from the number of timer ticks rather than from the `time` CSR, so it
+/// advances by exactly one tick per scheduling quantum. Combined with QEMU's
+/// `-icount`, which drives the timer interrupts by the instruction count, the
+/// timing observed by tests becomes reproducible.
+static VIRTUAL: AtomicBool = AtomicBool::new(false);
+
+/// Encoding of `rdtime x0` (`csrrs x0, time, x0`).
+const RDTIME: u32 = 0xc010_2073;
+const RDTIME_RD_MASK: u32 = 0x1f << 7;
 
 /// Ask each hart to generate timer interrupts.
 pub fn init() {
@@ -46,13 +68,59 @@ pub fn init() {
     }
 }
 
+/// Selects the clock source according to the boot options.
+///
+/// Called on CPU 0 before the other harts start.
+pub fn init_clock() {
+    if boot_args::flag("virtual_time") {
+        VIRTUAL.store(true, Ordering::Relaxed);
+        println!("virtual time enabled");
+    }
+}
+
+/// Sets up this hart's clock in supervisor mode.
+pub fn init_hart() {
+    if is_virtual() {
+        // make user `rdtime` trap so that it can be emulated.
+        unsafe {
+            scounteren::clear_tm();
+        }
+    }
+}
+
+fn is_virtual() -> bool {
+    VIRTUAL.load(Ordering::Relaxed)
+}
+
+/// Emulates the user instruction `insn` if it is `rdtime` in virtual time
+/// mode.
+///
+/// Returns `true` if the instruction was emulated, in which case the
+/// destination register and the program counter in `tf` are updated.
+pub(super) fn emulate_rdtime(tf: &mut TrapFrame, insn: usize) -> bool {
+    if !is_virtual() {
+        return false;
+    }
+    let Ok(insn) = u32::try_from(insn) else {
+        return false;
+    };
+    if (insn & !RDTIME_RD_MASK) != RDTIME {
+        return false;
+    }
+    let rd = usize::safe_from((insn & RDTIME_RD_MASK) >> 7);
+    tf.user_registers
+        .set(rd, usize::safe_from(Uptime::now().time));
+    tf.epc += 4;
+    true
+}
+
 pub(super) fn handle_interrupt() {
     if cpu::id() == 0 {
-        let mut ticks = TICKS.lock();
-        *ticks += 1;
-        TICKS_UPDATED.notify();
-        drop(ticks);
+        TICKS.fetch_add(1, Ordering::Relaxed);
+        deferred::schedule(Work::Timers);
+        proc::load::sample();
     }
+    Cpu::current().account_tick();
 
     // ask for the next timer interrupt. this also clears
     // the interrupt request. 1_000_000 is about a tenth
@@ -64,30 +132,32 @@ pub(super) fn handle_interrupt() {
     }
 }
 
+/// Returns the number of timer ticks since boot.
+pub(super) fn ticks() -> u64 {
+    TICKS.load(Ordering::Relaxed)
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub(crate) struct Uptime {
     time: u64,
 }
 
 impl Uptime {
-    pub(crate) const MAX: Self = Self { time: u64::MAX };
+    pub(crate) const ZERO: Self = Self { time: 0 };
 
     pub(crate) fn now() -> Self {
+        if is_virtual() {
+            return Self {
+                time: ticks() * CLOCKS_PER_TICK,
+            };
+        }
         let time: u64;
         unsafe { asm!("csrr {}, time", out(reg) time) }
         Self { time }
     }
 
-    pub(crate) fn checked_add(self, dur: Duration) -> Option<Self> {
-        let nanos = self.time.checked_add(
-            (dur.as_nanos() / u128::from(NANOS_PER_CLOCK))
-                .try_into()
-                .ok()?,
-        )?;
-        Some(Self { time: nanos })
-    }
-
-    pub(crate) fn saturating_add(self, dur: Duration) -> Self {
-        self.checked_add(dur).unwrap_or(Self::MAX)
+    pub(crate) fn saturating_duration_since(self, earlier: Self) -> Duration {
+        let clocks = self.time.saturating_sub(earlier.time);
+        Duration::from_nanos(clocks.saturating_mul(NANOS_PER_CLOCK))
     }
 }
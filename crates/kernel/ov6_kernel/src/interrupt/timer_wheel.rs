@@ -0,0 +1,245 @@
+//! Kernel timers.
+//!
+//! Timers are kept in a hashed timing wheel of [`WHEEL_SIZE`] slots, indexed
+//! by the tick on which they expire. On each tick, the timer interrupt of CPU 0
+//! schedules [`Work::Timers`], which fires the expired timers of the slots
+//! for the ticks elapsed since the last run. Timers are allocated from a fixed
+//! table of [`NTIMER`] entries.
+//!
+//! A timer either calls a function ([`add()`]), which can be done from
+//! interrupt and task context, or wakes up a process sleeping in [`sleep()`].
+//! Timer functions run from the deferred work queue, with interrupts disabled
+//! and no lock held.
+
+use core::time::Duration;
+
+use safe_cast::{SafeFrom as _, to_u64};
+
+use super::{
+    deferred::{self, Work},
+    timer,
+};
+use crate::{
+    error::KernelError,
+    sync::{SpinLock, SpinLockCondVar, WaitError},
+};
+
+/// Maximum number of pending timers.
+const NTIMER: usize = 128;
+
+/// Number of slots of the wheel.
+const WHEEL_SIZE: usize = 64;
+
+/// A handle to cancel a timer added by [`add()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimerHandle {
+    index: usize,
+    generation: u64,
+}
+
+#[derive(Clone, Copy)]
+enum Action {
+    /// Calls the function with the argument.
+    Call(fn(usize), usize),
+    /// Wakes up the process sleeping on the entry.
+    Wake,
+}
+
+#[derive(Clone, Copy)]
+enum State {
+    Free,
+    Pending {
+        expires: u64,
+        action: Action,
+    },
+    /// A `Wake` timer that has expired, but is not yet freed by its sleeper.
+    Fired,
+}
+
+#[derive(Clone, Copy)]
+struct Entry {
+    state: State,
+    /// Incremented each time the entry is freed, to detect stale handles.
+    generation: u64,
+    /// Next entry in the same slot.
+    next: Option<usize>,
+}
+
+struct Wheel {
+    entries: [Entry; NTIMER],
+    /// First entry of each slot.
+    slots: [Option<usize>; WHEEL_SIZE],
+    /// The tick being processed.
+    now: u64,
+}
+
+static WHEEL: SpinLock<Wheel> = SpinLock::new(Wheel::new());
+static WAKE: [SpinLockCondVar; NTIMER] = [const { SpinLockCondVar::new() }; NTIMER];
+
+impl Wheel {
+    const fn new() -> Self {
+        Self {
+            entries: [Entry {
+                state: State::Free,
+                generation: 0,
+                next: None,
+            }; NTIMER],
+            slots: [None; WHEEL_SIZE],
+            now: 0,
+        }
+    }
+
+    fn slot(tick: u64) -> usize {
+        usize::safe_from(tick % to_u64!(WHEEL_SIZE))
+    }
+
+    fn insert(&mut self, expires: u64, action: Action) -> Result<TimerHandle, KernelError> {
+        let index = self
+            .entries
+            .iter()
+            .position(|e| matches!(e.state, State::Free))
+            .ok_or(KernelError::NoFreeTimer)?;
+
+        // a timer that has already expired is fired by the next run.
+        let slot = Self::slot(u64::max(expires, self.now));
+        let entry = &mut self.entries[index];
+        entry.state = State::Pending { expires, action };
+        entry.next = self.slots[slot];
+        self.slots[slot] = Some(index);
+
+        Ok(TimerHandle {
+            index,
+            generation: entry.generation,
+        })
+    }
+
+    /// Removes the pending entry `index` from its slot.
+    fn unlink(&mut self, index: usize) {
+        let next = self.entries[index].next.take();
+        if let Some(slot) = self.slots.iter().position(|head| *head == Some(index)) {
+            self.slots[slot] = next;
+            return;
+        }
+        let prev = self
+            .entries
+            .iter()
+            .position(|e| e.next == Some(index))
+            .unwrap();
+        self.entries[prev].next = next;
+    }
+
+    fn free(&mut self, index: usize) {
+        let entry = &mut self.entries[index];
+        entry.state = State::Free;
+        entry.generation += 1;
+    }
+
+    /// Removes one timer that has expired by `target` from the wheel.
+    ///
+    /// Returns the index and the action of the timer, or `None` if all
+    /// expired timers have been removed.
+    fn expire_one(&mut self, target: u64) -> Option<(usize, Action)> {
+        loop {
+            let mut cursor = self.slots[Self::slot(self.now)];
+            while let Some(index) = cursor {
+                let entry = &self.entries[index];
+                cursor = entry.next;
+                let State::Pending { expires, action } = entry.state else {
+                    unreachable!();
+                };
+                if expires <= self.now {
+                    self.unlink(index);
+                    match action {
+                        Action::Call(..) => self.free(index),
+                        Action::Wake => self.entries[index].state = State::Fired,
+                    }
+                    return Some((index, action));
+                }
+            }
+            if self.now >= target {
+                return None;
+            }
+            self.now += 1;
+        }
+    }
+}
+
+/// Converts `dur` to a number of ticks, rounding up.
+fn to_ticks(dur: Duration) -> u64 {
+    dur.as_nanos()
+        .div_ceil(u128::from(timer::NANOS_PER_TICKS))
+        .try_into()
+        .unwrap_or(u64::MAX)
+}
+
+pub fn init() {
+    deferred::register(Work::Timers, run);
+}
+
+/// Adds a timer that calls `f(arg)` after `dur`.
+///
+/// # Errors
+///
+/// Returns an error if there are too many pending timers.
+pub fn add(dur: Duration, f: fn(usize), arg: usize) -> Result<TimerHandle, KernelError> {
+    let expires = timer::ticks().saturating_add(to_ticks(dur));
+    WHEEL.lock().insert(expires, Action::Call(f, arg))
+}
+
+/// Cancels the timer.
+///
+/// Returns `false` if the timer has already fired.
+pub fn cancel(handle: TimerHandle) -> bool {
+    let mut wheel = WHEEL.lock();
+    let entry = &wheel.entries[handle.index];
+    if entry.generation != handle.generation || !matches!(entry.state, State::Pending { .. }) {
+        return false;
+    }
+    wheel.unlink(handle.index);
+    wheel.free(handle.index);
+    true
+}
+
+/// Sleeps for `dur`.
+///
+/// # Errors
+///
+/// Returns an error if there are too many pending timers, or if the process
+/// is killed while sleeping.
+pub fn sleep(dur: Duration) -> Result<(), KernelError> {
+    if dur.is_zero() {
+        return Ok(());
+    }
+
+    let expires = timer::ticks().saturating_add(to_ticks(dur));
+    let mut wheel = WHEEL.lock();
+    let TimerHandle { index, .. } = wheel.insert(expires, Action::Wake)?;
+    while !matches!(wheel.entries[index].state, State::Fired) {
+        wheel = match WAKE[index].wait(wheel) {
+            Ok(wheel) => wheel,
+            Err((mut wheel, WaitError::WaitingProcessAlreadyKilled)) => {
+                if matches!(wheel.entries[index].state, State::Pending { .. }) {
+                    wheel.unlink(index);
+                }
+                wheel.free(index);
+                return Err(KernelError::CallerProcessAlreadyKilled);
+            }
+        };
+    }
+    wheel.free(index);
+    Ok(())
+}
+
+/// Fires the timers that have expired.
+fn run() {
+    let target = timer::ticks();
+    loop {
+        let Some((index, action)) = WHEEL.lock().expire_one(target) else {
+            break;
+        };
+        match action {
+            Action::Call(f, arg) => f(arg),
+            Action::Wake => WAKE[index].notify(),
+        }
+    }
+}
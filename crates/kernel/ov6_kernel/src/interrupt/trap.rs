@@ -1,6 +1,7 @@
 use core::{mem, ptr};
 
-use dataview::Pod;
+use dataview::{DataView, Pod};
+use ov6_syscall::PtraceStop;
 use riscv::{
     interrupt::{
         Trap,
@@ -13,23 +14,18 @@ use riscv::{
         stvec::{self, Stvec, TrapMode},
     },
 };
-use safe_cast::SafeInto as _;
 
-use super::{clic, kernel_vec, plic, timer, trampoline};
+use super::{clic, deferred, fault::FaultReport, kernel_vec, plic, timer, trampoline};
 use crate::{
-    console::uart,
-    cpu, device,
+    cpu,
     error::KernelError,
-    fs,
-    interrupt::{self, timer::Uptime},
+    interrupt,
+    kallsyms::Symbolized,
     memory::{
-        PAGE_SIZE, VirtAddr,
-        layout::{E1000_IRQ, KSTACK_PAGES, UART0_IRQ, VIRTIO0_IRQ},
-        page_table::PtEntryFlags,
-        vm_user::UserPageTable,
+        PAGE_SIZE, VirtAddr, layout::KSTACK_PAGES, page_table::PtEntryFlags, vm_user::UserPageTable,
     },
     println,
-    proc::{self, Proc, ProcPrivateData, ProcPrivateDataGuard, scheduler},
+    proc::{self, Proc, ProcPrivateData, ProcPrivateDataGuard, oom, ptrace, scheduler},
     syscall,
 };
 
@@ -69,6 +65,29 @@ pub struct UserRegisters {
     pub t6: usize,
 }
 
+impl UserRegisters {
+    /// Sets the general purpose register `x<index>`.
+    ///
+    /// Writes to `x0` are ignored.
+    pub fn set(&mut self, index: usize, value: usize) {
+        // the fields are laid out in the order of x1..=x31.
+        if let Some(index) = index.checked_sub(1) {
+            let regs: &mut [usize; 31] = DataView::from_mut(self).get_mut(0);
+            regs[index] = value;
+        }
+    }
+
+    /// Returns the general purpose register `x<index>`.
+    ///
+    /// `x0` is always 0.
+    pub fn get(&self, index: usize) -> usize {
+        index.checked_sub(1).map_or(0, |index| {
+            let regs: &[usize; 31] = DataView::from(self).get(0);
+            regs[index]
+        })
+    }
+}
+
 #[repr(C)]
 #[derive(Debug, Clone, Copy, Pod)]
 pub struct TrapFrame {
@@ -133,12 +152,22 @@ extern "C" fn trap_user() {
             // so enable only now that we're done with those registers.
             interrupt::enable();
 
+            ptrace::handle_syscall(p, &mut private, PtraceStop::SyscallEntry);
+            if p.shared().lock().killed() {
+                proc::ops::exit(p, private, -1);
+            }
+
             let mut private_opt = Some(private);
             syscall::syscall(p, &mut private_opt);
             private = private_opt.unwrap();
+
+            ptrace::handle_syscall(p, &mut private, PtraceStop::SyscallExit);
         }
+        Trap::Exception(Exception::Breakpoint) if ptrace::handle_breakpoint(p, &mut private) => {}
+        Trap::Exception(Exception::IllegalInstruction)
+            if timer::emulate_rdtime(private.trapframe_mut(), stval::read()) => {}
         Trap::Exception(Exception::StorePageFault)
-            if request_user_write(&mut private, stval::read()).is_ok() => {}
+            if request_user_write(p, &mut private, stval::read()).is_ok() => {}
         Trap::Exception(e) => {
             let mut shared = p.shared().lock();
             let pid = shared.pid();
@@ -148,6 +177,9 @@ extern "C" fn trap_user() {
 
             println!("usertrap: exception {e:?} pid={pid} name={name}");
             println!("          sepc={sepc:#x} stval={stval:#x}");
+            if let Some(report) = FaultReport::new(e, stval, private.pagetable()) {
+                println!("{report}");
+            }
             print_user_backtrace(sepc, private.trapframe(), private.pagetable());
             shared.kill();
         }
@@ -167,6 +199,8 @@ extern "C" fn trap_user() {
         }
     }
 
+    ptrace::handle_pending_stop(p, &mut private);
+
     {
         let mut shared = p.shared().lock();
         if shared.killed() {
@@ -174,12 +208,13 @@ extern "C" fn trap_user() {
             proc::ops::exit(p, private, -1);
         }
         if let Some(alarm) = shared.alarm_mut() {
-            let now = Uptime::now();
-            if alarm.is_expired(now) {
-                alarm.update(now);
+            if alarm.take_expired() {
                 private.enter_signal_handler(alarm.handler());
             }
         }
+        if let Some(handler) = shared.take_interrupt() {
+            private.enter_signal_handler(handler);
+        }
     }
 
     // gibe up the CPU if this is a timer interrupt.
@@ -190,9 +225,16 @@ extern "C" fn trap_user() {
     trap_user_ret(private);
 }
 
-fn request_user_write(private: &mut ProcPrivateData, addr: usize) -> Result<(), KernelError> {
+/// Makes the page at `addr` writable, copying it if it is copy-on-write.
+///
+/// The copy cannot fail for lack of memory while other processes hold some.
+fn request_user_write(
+    p: &Proc,
+    private: &mut ProcPrivateData,
+    addr: usize,
+) -> Result<(), KernelError> {
     let va = VirtAddr::new(addr)?;
-    private.pagetable_mut().request_user_write(va)?;
+    oom::retry(p, || private.pagetable_mut().request_user_write(va))?;
     Ok(())
 }
 
@@ -244,6 +286,8 @@ pub fn trap_user_ret(mut private: ProcPrivateDataGuard) {
     // we're back in user space, where usertrap() is correct.
     interrupt::disable();
 
+    ptrace::sync_instruction_cache(&private);
+
     // send syscalls, interrupts, and exceptions to uservec in trampoline.S
     let trampoline_uservec = trampoline::user_vec_addr();
     let mut stvec = Stvec::from_bits(0);
@@ -306,6 +350,7 @@ pub extern "C" fn trap_kernel() {
             let stval = stval::read();
             println!("kernel trap: exception {e:#?}");
             println!("             sepc={sepc:#x} stval={stval:#x}");
+            println!("             at {}", Symbolized(sepc));
             panic!("unexpected trap (exception)");
         }
         Trap::Interrupt(int) => (int, handle_dev_interrupt(int)),
@@ -314,6 +359,10 @@ pub extern "C" fn trap_kernel() {
     match which_dev {
         IntrKind::Timer => {
             // give up the CPU if this is a timer interrupt.
+            //
+            // This preempts a process running kernel code at any point where
+            // interrupts are enabled (i.e. no spin lock is held), so long
+            // file system operations need no explicit preemption points.
             if let Some(p) = Proc::try_current() {
                 scheduler::yield_(p);
             }
@@ -353,7 +402,11 @@ enum IntrKind {
 /// 0 if not recognized
 fn handle_dev_interrupt(int: Interrupt) -> IntrKind {
     match int {
-        Interrupt::SupervisorSoft => IntrKind::NotRecognized,
+        Interrupt::SupervisorSoft => {
+            // work deferred by device interrupt handlers.
+            deferred::handle();
+            IntrKind::Other
+        }
         Interrupt::SupervisorTimer => {
             timer::handle_interrupt();
             IntrKind::Timer
@@ -366,26 +419,7 @@ fn handle_dev_interrupt(int: Interrupt) -> IntrKind {
                 return IntrKind::InterProcessor;
             }
 
-            // irq indicates which device interrupted.
-            let irq = plic::claim();
-
-            match irq.safe_into() {
-                UART0_IRQ => uart::handle_interrupt(),
-                VIRTIO0_IRQ => fs::virtio_disk::handle_interrupt(),
-                E1000_IRQ => device::e1000::handle_interrupt(),
-                irq => {
-                    if irq > 0 {
-                        println!("unexpected interrupt irq={irq}");
-                    }
-                }
-            }
-
-            // the PLIC allows each device to raise at most one
-            // interrupt at a time; tell the PLIC the device is
-            // now allowed to interrupt again.
-            if irq > 0 {
-                plic::complete(irq);
-            }
+            plic::handle();
             IntrKind::Other
         }
     }
@@ -0,0 +1,3 @@
+//! Inter-process communication facilities not backed by files.
+
+pub mod msg_queue;
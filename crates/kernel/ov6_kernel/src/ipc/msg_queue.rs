@@ -0,0 +1,181 @@
+//! Message queues.
+//!
+//! A message queue is identified by a key chosen by the processes sharing it,
+//! and is accessed through the identifier returned by [`get()`]. Messages are
+//! received in the order of their priorities, highest first, and in the order
+//! they were sent among the same priority.
+//!
+//! The identifier encodes the slot of the queue table and the generation of
+//! the slot, which is advanced when the queue is removed. So an identifier of
+//! a removed queue never refers to a queue created later in the same slot.
+
+use arrayvec::ArrayVec;
+use ov6_syscall::{MSG_SIZE_MAX, MsgQueueFlags};
+
+use crate::{
+    error::KernelError,
+    param::{MSG_QUEUE_DEPTH, NMSG_QUEUE},
+    sync::{SpinLock, SpinLockCondVar},
+};
+
+/// A message in a message queue.
+#[derive(Clone, Copy)]
+pub struct Message {
+    pub priority: usize,
+    len: usize,
+    data: [u8; MSG_SIZE_MAX],
+}
+
+impl Message {
+    pub fn data(&self) -> &[u8] {
+        &self.data[..self.len]
+    }
+}
+
+struct Queue {
+    /// Key of the queue, or `None` if the slot is free.
+    key: Option<u64>,
+    generation: usize,
+    /// Sending to the full queue fails instead of waiting.
+    nonblocking_send: bool,
+    /// Messages sorted by priority in descending order.
+    messages: ArrayVec<Message, MSG_QUEUE_DEPTH>,
+}
+
+static QUEUES: SpinLock<[Queue; NMSG_QUEUE]> = SpinLock::new(
+    [const {
+        Queue {
+            key: None,
+            generation: 0,
+            nonblocking_send: false,
+            messages: ArrayVec::new_const(),
+        }
+    }; NMSG_QUEUE],
+);
+
+/// Notified when a message is sent to the queue, or the queue is removed.
+static NOT_EMPTY: [SpinLockCondVar; NMSG_QUEUE] = [const { SpinLockCondVar::new() }; NMSG_QUEUE];
+/// Notified when a message is received from the queue, or the queue is
+/// removed.
+static NOT_FULL: [SpinLockCondVar; NMSG_QUEUE] = [const { SpinLockCondVar::new() }; NMSG_QUEUE];
+
+/// Largest generation that keeps identifiers from overflowing.
+const MAX_GENERATION: usize = usize::MAX / NMSG_QUEUE;
+
+fn id_of(slot: usize, generation: usize) -> usize {
+    generation * NMSG_QUEUE + slot
+}
+
+/// Returns the slot of the queue identified by `id`.
+fn lookup(queues: &[Queue; NMSG_QUEUE], id: usize) -> Result<usize, KernelError> {
+    let slot = id % NMSG_QUEUE;
+    let queue = &queues[slot];
+    if queue.key.is_none() || id_of(slot, queue.generation) != id {
+        return Err(KernelError::MsgQueueNotFound(id));
+    }
+    Ok(slot)
+}
+
+/// Returns the identifier of the queue of `key`.
+///
+/// With [`MsgQueueFlags::CREATE`], a queue is created if no queue has the
+/// key. [`MsgQueueFlags::NONBLOCKING_SEND`] takes effect only when the queue
+/// is created.
+pub fn get(key: u64, flags: MsgQueueFlags) -> Result<usize, KernelError> {
+    let mut queues = QUEUES.lock();
+    if let Some(slot) = queues.iter().position(|q| q.key == Some(key)) {
+        if flags.contains(MsgQueueFlags::CREATE | MsgQueueFlags::EXCLUSIVE) {
+            return Err(KernelError::MsgQueueAlreadyExists);
+        }
+        return Ok(id_of(slot, queues[slot].generation));
+    }
+
+    if !flags.contains(MsgQueueFlags::CREATE) {
+        return Err(KernelError::MsgQueueKeyNotFound);
+    }
+    let slot = queues
+        .iter()
+        .position(|q| q.key.is_none())
+        .ok_or(KernelError::NoFreeMsgQueue)?;
+    let queue = &mut queues[slot];
+    queue.key = Some(key);
+    queue.nonblocking_send = flags.contains(MsgQueueFlags::NONBLOCKING_SEND);
+    Ok(id_of(slot, queue.generation))
+}
+
+/// Sends `data` to the queue identified by `id` with `priority`.
+///
+/// Waits until the queue has room for the message, unless the queue was
+/// created with [`MsgQueueFlags::NONBLOCKING_SEND`].
+pub fn send(id: usize, data: &[u8], priority: usize) -> Result<(), KernelError> {
+    if data.len() > MSG_SIZE_MAX {
+        return Err(KernelError::TooLargeMessage(data.len()));
+    }
+    let mut msg = Message {
+        priority,
+        len: data.len(),
+        data: [0; MSG_SIZE_MAX],
+    };
+    msg.data[..data.len()].copy_from_slice(data);
+
+    let mut queues = QUEUES.lock();
+    let slot = lookup(&queues, id)?;
+    while queues[slot].messages.is_full() {
+        if queues[slot].nonblocking_send {
+            return Err(KernelError::MsgQueueFull);
+        }
+        queues = NOT_FULL[slot].wait(queues).map_err(|(_guard, e)| e)?;
+        if lookup(&queues, id).is_err() {
+            return Err(KernelError::MsgQueueRemoved);
+        }
+    }
+
+    let messages = &mut queues[slot].messages;
+    let pos = messages
+        .iter()
+        .position(|m| m.priority < priority)
+        .unwrap_or(messages.len());
+    messages.insert(pos, msg);
+    NOT_EMPTY[slot].notify();
+    Ok(())
+}
+
+/// Receives the message of the highest priority from the queue identified by
+/// `id`.
+///
+/// Waits until a message is sent if the queue is empty. If the message is
+/// longer than `capacity` bytes, it is left in the queue.
+pub fn recv(id: usize, capacity: usize) -> Result<Message, KernelError> {
+    let mut queues = QUEUES.lock();
+    let slot = lookup(&queues, id)?;
+    while queues[slot].messages.is_empty() {
+        queues = NOT_EMPTY[slot].wait(queues).map_err(|(_guard, e)| e)?;
+        if lookup(&queues, id).is_err() {
+            return Err(KernelError::MsgQueueRemoved);
+        }
+    }
+
+    let messages = &mut queues[slot].messages;
+    if messages[0].len > capacity {
+        return Err(KernelError::MessageBufferTooSmall(messages[0].len));
+    }
+    let msg = messages.remove(0);
+    NOT_FULL[slot].notify();
+    Ok(msg)
+}
+
+/// Removes the queue identified by `id`, discarding its messages.
+///
+/// The processes waiting on the queue fail with
+/// [`KernelError::MsgQueueRemoved`].
+pub fn remove(id: usize) -> Result<(), KernelError> {
+    let mut queues = QUEUES.lock();
+    let slot = lookup(&queues, id)?;
+    let queue = &mut queues[slot];
+    queue.key = None;
+    queue.generation = (queue.generation + 1) % MAX_GENERATION;
+    queue.messages.clear();
+    NOT_EMPTY[slot].notify();
+    NOT_FULL[slot].notify();
+    Ok(())
+}
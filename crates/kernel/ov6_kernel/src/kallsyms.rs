@@ -0,0 +1,122 @@
+//! Symbol table of the kernel.
+//!
+//! The function symbols of the linked kernel are embedded in the `.kallsyms`
+//! section by the Makefile, which fills the section reserved here after
+//! linking, so that adding the table does not move the code it describes.
+//!
+//! The table is text: one `<hex address> <name>` line per symbol, sorted by
+//! address and padded with NUL bytes. It is empty in a kernel that is not
+//! built by the Makefile.
+//!
+//! The table is used to name the addresses in panic backtraces, and is read
+//! by user space with the `GetKernelSymbols` system call.
+//!
+//! The addresses are written with a fixed width by the Makefile, so that they
+//! are rewritten in place with the runtime addresses when the kernel is moved
+//! by KASLR (see [`memory::kaslr`]).
+//!
+//! [`memory::kaslr`]: crate::memory::kaslr
+
+use core::{arch::global_asm, fmt, ptr, slice};
+
+/// Size of the `.kallsyms` section.
+const KALLSYMS_SIZE: usize = 512 * 1024;
+
+#[used]
+#[unsafe(link_section = ".kallsyms")]
+static KALLSYMS: [u8; KALLSYMS_SIZE] = [0; KALLSYMS_SIZE];
+
+// get the bounds of the section from the linker, so that the contents
+// written after linking are not assumed to be zero.
+global_asm!(
+    "
+        .global _ov6_kallsyms_start_addr
+        _ov6_kallsyms_start_addr: .dword _ov6_kallsyms_start
+        .global _ov6_kallsyms_end_addr
+        _ov6_kallsyms_end_addr: .dword _ov6_kallsyms_end
+    "
+);
+
+unsafe extern "C" {
+    #[link_name = "_ov6_kallsyms_start_addr"]
+    static KALLSYMS_START: usize;
+    #[link_name = "_ov6_kallsyms_end_addr"]
+    static KALLSYMS_END: usize;
+}
+
+/// Returns the text of the symbol table.
+pub fn table() -> &'static [u8] {
+    let (start, end) = unsafe { (KALLSYMS_START, KALLSYMS_END) };
+    let section =
+        unsafe { slice::from_raw_parts(ptr::with_exposed_provenance(start), end - start) };
+    let len = section
+        .iter()
+        .position(|b| *b == 0)
+        .unwrap_or(section.len());
+    &section[..len]
+}
+
+/// Adds `slide` to the addresses in the table.
+///
+/// # Safety
+///
+/// Must be called once, with paging disabled, before the table is read.
+pub unsafe fn relocate(slide: usize) {
+    const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+    let (start, end) = unsafe { (KALLSYMS_START, KALLSYMS_END) };
+    let section =
+        unsafe { slice::from_raw_parts_mut(ptr::with_exposed_provenance_mut(start), end - start) };
+    let len = section
+        .iter()
+        .position(|b| *b == 0)
+        .unwrap_or(section.len());
+    for line in section[..len].split_mut(|b| *b == b'\n') {
+        let Some((addr, _)) = parse_line(line) else {
+            continue;
+        };
+        let width = line.iter().position(|b| *b == b' ').unwrap();
+        let mut addr = addr.wrapping_add(slide);
+        for digit in line[..width].iter_mut().rev() {
+            *digit = HEX_DIGITS[addr & 0xf];
+            addr >>= 4;
+        }
+    }
+}
+
+fn parse_line(line: &[u8]) -> Option<(usize, &str)> {
+    let sep = line.iter().position(|b| *b == b' ')?;
+    let addr = str::from_utf8(&line[..sep]).ok()?;
+    let addr = usize::from_str_radix(addr, 16).ok()?;
+    let name = str::from_utf8(&line[sep + 1..]).ok()?;
+    Some((addr, name))
+}
+
+/// Returns the name of the function containing `addr` and the offset of
+/// `addr` from its start.
+///
+/// A symbol is assumed to extend to the next one, so addresses after the
+/// last symbol, which marks the end of the text, are not resolved.
+pub fn lookup(addr: usize) -> Option<(&'static str, usize)> {
+    let mut found = None;
+    for (sym_addr, name) in table().split(|b| *b == b'\n').filter_map(parse_line) {
+        if sym_addr > addr {
+            return found;
+        }
+        found = Some((name, addr - sym_addr));
+    }
+    None
+}
+
+/// Formats a kernel address followed by the function containing it, if known.
+pub struct Symbolized(pub usize);
+
+impl fmt::Display for Symbolized {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#p}", ptr::without_provenance::<u8>(self.0))?;
+        if let Some((name, offset)) = lookup(self.0) {
+            write!(f, " <{name}+{offset:#x}>")?;
+        }
+        Ok(())
+    }
+}
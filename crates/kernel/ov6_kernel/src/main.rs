@@ -14,24 +14,32 @@ use ov6_kernel_params as param;
 
 extern crate alloc;
 
+mod audit;
+mod boot_args;
 mod console;
 mod cpu;
 mod device;
 mod error;
 mod file;
 mod fs;
+mod hostname;
 mod init;
 mod interrupt;
+mod ipc;
+mod kallsyms;
 mod memory;
 mod net;
 mod proc;
+#[cfg(feature = "selftest")]
+mod selftest;
+mod shutdown;
 mod sync;
 mod syscall;
 
+static STARTED: AtomicBool = AtomicBool::new(false);
+
 // start() jumps here in supervisor mode on all CPUs.
 extern "C" fn main() -> ! {
-    static STARTED: AtomicBool = AtomicBool::new(false);
-
     interrupt::disable();
 
     if cpu::id() == 0 {
@@ -39,28 +47,43 @@ extern "C" fn main() -> ! {
         println!();
         println!("ov6 kernel is booting");
         println!();
+        boot_args::init(); // boot options, before the device tree is overwritten
+        interrupt::timer::init_clock(); // clock source
         device::test::init(); // test device
         memory::page::init(); // physical page allocator
+        memory::kaslr::init(); // choose the kernel's virtual address
         memory::vm_kernel::init(); // create kernel page table
-        memory::vm_kernel::init_hart(); // turn on paging
+        memory::kaslr::relocate(); // move the kernel to its virtual address
+    } else {
+        while !STARTED.load(Ordering::Acquire) {
+            hint::spin_loop();
+        }
+    }
+
+    memory::vm_kernel::init_hart(main_virt); // turn on paging
+}
+
+// init_hart() jumps here in the kernel's virtual address on all CPUs.
+extern "C" fn main_virt() -> ! {
+    if cpu::id() == 0 {
         interrupt::trap::init_hart(); // install kernel trap vectort
-        interrupt::plic::init(); // set up interrupt controller
         interrupt::plic::init_hart(); // ask PLIC for device interrupts
+        interrupt::timer::init_hart(); // clock
+        interrupt::timer_wheel::init(); // kernel timers
+        console::init_device(); // console device
         fs::init(); // file system (buffer cache and hard disk)
-        file::init(); // file table
+        hostname::init(); // host name
+        #[cfg(feature = "selftest")]
+        selftest::run(); // in-kernel self tests
         proc::ops::spawn_init(); // first user process
         device::pci::init(); // PCI device driver
-        net::init();
 
         STARTED.store(true, Ordering::Release);
     } else {
-        while !STARTED.load(Ordering::Acquire) {
-            hint::spin_loop();
-        }
         println!("hart {} starting", cpu::id());
-        memory::vm_kernel::init_hart(); // turn on paging
         interrupt::trap::init_hart(); // install kernel trap vector
         interrupt::plic::init_hart(); // ask PLIC for device interrupts
+        interrupt::timer::init_hart(); // clock
     }
 
     proc::scheduler::schedule();
@@ -7,7 +7,7 @@ use core::{
 use dataview::Pod;
 use ov6_syscall::{UserMutRef, UserMutSlice, UserRef, UserSlice};
 
-use super::{PAGE_SHIFT, PAGE_SIZE, vm_user::UserPageTable};
+use super::{PAGE_SHIFT, PAGE_SIZE, kaslr, vm_user::UserPageTable};
 use crate::error::KernelError;
 
 const fn page_roundup(addr: usize) -> usize {
@@ -295,7 +295,7 @@ where
     T: ?Sized,
 {
     fn from(r: &T) -> Self {
-        Self(ptr::from_ref(r).addr())
+        Self(kaslr::to_phys(ptr::from_ref(r).addr()))
     }
 }
 
@@ -304,7 +304,7 @@ where
     T: ?Sized,
 {
     fn from(r: &mut T) -> Self {
-        Self(ptr::from_mut(r).addr())
+        Self(kaslr::to_phys(ptr::from_mut(r).addr()))
     }
 }
 
@@ -313,7 +313,7 @@ where
     T: ?Sized,
 {
     fn from(ptr: *const T) -> Self {
-        Self(ptr.addr())
+        Self(kaslr::to_phys(ptr.addr()))
     }
 }
 
@@ -322,7 +322,7 @@ where
     T: ?Sized,
 {
     fn from(ptr: *mut T) -> Self {
-        Self(ptr.addr())
+        Self(kaslr::to_phys(ptr.addr()))
     }
 }
 
@@ -331,7 +331,7 @@ where
     T: ?Sized,
 {
     fn from(ptr: NonNull<T>) -> Self {
-        Self(ptr.addr().get())
+        Self::from(ptr.as_ptr())
     }
 }
 
@@ -345,41 +345,52 @@ impl TryAsVirtAddrRange for Range<VirtAddr> {
     }
 }
 
+/// Returns the range of addresses covered by a user pointer argument.
+///
+/// Every user pointer goes through this check before it is validated against
+/// the page table, so that a malformed pointer is rejected with an error
+/// instead of being found by a page fault while copying:
+///
+/// - the size of the buffer must fit in `usize`,
+/// - the address must be aligned for the pointee type,
+/// - the buffer must not wrap around the address space, and
+/// - the buffer must lie below [`VirtAddr::MAX`].
+fn user_va_range(
+    addr: usize,
+    size: Option<usize>,
+    align: usize,
+) -> Result<Range<VirtAddr>, KernelError> {
+    let size = size.ok_or(KernelError::UserBufferTooLarge)?;
+    if !addr.is_multiple_of(align) {
+        return Err(KernelError::MisalignedUserPointer(addr, align));
+    }
+    let end = addr
+        .checked_add(size)
+        .ok_or(KernelError::UserBufferWrapsAround(addr, size))?;
+    Ok(VirtAddr::new(addr)?..VirtAddr::new(end)?)
+}
+
 impl<T> TryAsVirtAddrRange for UserRef<T> {
     fn try_as_va_range(&self) -> Result<Range<VirtAddr>, KernelError> {
-        let start = VirtAddr::new(self.addr())?;
-        let end = start.byte_add(self.size())?;
-        Ok(start..end)
+        user_va_range(self.addr(), Some(self.size()), align_of::<T>())
     }
 }
 
 impl<T> TryAsVirtAddrRange for UserMutRef<T> {
     fn try_as_va_range(&self) -> Result<Range<VirtAddr>, KernelError> {
-        let start = VirtAddr::new(self.addr())?;
-        let end = start.byte_add(self.size())?;
-        Ok(start..end)
+        user_va_range(self.addr(), Some(self.size()), align_of::<T>())
     }
 }
 
 impl<T> TryAsVirtAddrRange for UserSlice<T> {
     fn try_as_va_range(&self) -> Result<Range<VirtAddr>, KernelError> {
-        let size = self
-            .size()
-            .ok_or(KernelError::TooLargeVirtualAddress(usize::MAX))?;
-        let start = VirtAddr::new(self.addr())?;
-        let end = start.byte_add(size)?;
-        Ok(start..end)
+        user_va_range(self.addr(), self.size(), align_of::<T>())
     }
 }
 
 impl<T> TryAsVirtAddrRange for UserMutSlice<T> {
     fn try_as_va_range(&self) -> Result<Range<VirtAddr>, KernelError> {
-        let size = self
-            .size()
-            .ok_or(KernelError::TooLargeVirtualAddress(usize::MAX))?;
-        let start = VirtAddr::new(self.addr())?;
-        let end = start.byte_add(size)?;
-        Ok(start..end)
+        user_va_range(self.addr(), self.size(), align_of::<T>())
     }
 }
 
@@ -0,0 +1,67 @@
+//! Fallible allocation of kernel objects.
+//!
+//! Kernel objects whose allocation is triggered by user processes must not
+//! abort the kernel when memory runs out. They are allocated through the
+//! functions in this module, which report failures as
+//! [`KernelError::NoMemory`].
+//!
+//! A process can make its own allocations through this module fail on purpose
+//! with [`inject_fault()`], so that the error paths can be tested.
+
+use alloc::{boxed::Box, sync::Arc};
+use core::{alloc::Allocator, mem::MaybeUninit};
+
+use ov6_types::process::ProcId;
+
+use crate::{error::KernelError, proc::Proc, sync::SpinLock};
+
+/// Process that an allocation failure is injected into, and the number of
+/// allocations that succeed before the failure.
+static FAULT: SpinLock<Option<(ProcId, usize)>> = SpinLock::new(None);
+
+/// Makes the allocation after `count` successful ones by the process `pid`
+/// fail.
+///
+/// Replaces the fault previously injected, if any. The fault is removed once
+/// it is triggered.
+pub fn inject_fault(pid: ProcId, count: usize) {
+    *FAULT.lock() = Some((pid, count));
+}
+
+/// Returns `Err` if an injected fault is triggered by this allocation.
+fn check_fault() -> Result<(), KernelError> {
+    let mut fault = FAULT.lock();
+    let Some((pid, count)) = &mut *fault else {
+        return Ok(());
+    };
+    let Some(p) = Proc::try_current() else {
+        return Ok(());
+    };
+    if p.shared().lock().pid() != *pid {
+        return Ok(());
+    }
+    if *count > 0 {
+        *count -= 1;
+        return Ok(());
+    }
+    *fault = None;
+    Err(KernelError::NoMemory)
+}
+
+/// Allocates `value` in an [`Arc`] with `alloc`.
+pub fn try_new_arc_in<T, A>(value: T, alloc: A) -> Result<Arc<T, A>, KernelError>
+where
+    A: Allocator,
+{
+    check_fault()?;
+    Ok(Arc::try_new_in(value, alloc)?)
+}
+
+/// Allocates a zero-filled [`Box`] with `alloc`.
+pub fn try_new_zeroed_box_in<T, A>(alloc: A) -> Result<Box<MaybeUninit<T>, A>, KernelError>
+where
+    A: Allocator,
+{
+    check_fault()?;
+    Ok(Box::try_new_zeroed_in(alloc)?)
+}
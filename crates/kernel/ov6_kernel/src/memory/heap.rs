@@ -1,16 +1,242 @@
-use core::alloc::GlobalAlloc;
+//! Kernel heap.
+//!
+//! Small objects are allocated from slabs of fixed size classes. A slab is a
+//! page taken from the page allocator, with a header at its start followed
+//! by the objects. A slab is returned to the page allocator as soon as all
+//! of its objects are freed.
+//!
+//! Objects larger than the largest size class take a whole page.
 
-struct PanicAllocator;
+use core::{
+    alloc::{AllocError, Allocator, GlobalAlloc, Layout},
+    num::NonZero,
+    ptr::{self, NonNull},
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
-unsafe impl GlobalAlloc for PanicAllocator {
-    unsafe fn alloc(&self, _layout: core::alloc::Layout) -> *mut u8 {
-        panic!("global alloc is not supported")
+use ov6_syscall::{HEAP_SIZE_CLASSES, HeapClassInfo, HeapInfo};
+
+use super::{PAGE_SIZE, page::PageFrameAllocator};
+use crate::sync::SpinLock;
+
+/// Sizes of the objects of each size class.
+///
+/// Objects are aligned to their size, so an object of a class can hold any
+/// layout whose size and alignment are both at most the class size.
+const CLASS_SIZES: [usize; HEAP_SIZE_CLASSES] = [16, 32, 64, 128, 256, 512, 1024];
+
+const PAGE_LAYOUT: Layout = Layout::new::<[u8; PAGE_SIZE]>();
+
+/// Header at the start of a slab page.
+struct Slab {
+    /// Next slab of the same class that has free objects.
+    next: Option<NonNull<Slab>>,
+    free_list: Option<NonNull<FreeObject>>,
+    /// Number of objects in use.
+    in_use: usize,
+}
+
+struct FreeObject {
+    next: Option<NonNull<FreeObject>>,
+}
+
+struct SizeClass {
+    size: usize,
+    /// Slabs that have free objects.
+    partial: Option<NonNull<Slab>>,
+    pages: usize,
+    allocated: usize,
+}
+
+unsafe impl Send for SizeClass {}
+
+impl SizeClass {
+    const fn new(size: usize) -> Self {
+        Self {
+            size,
+            partial: None,
+            pages: 0,
+            allocated: 0,
+        }
+    }
+
+    /// Takes a page from the page allocator and makes it a slab of free
+    /// objects.
+    fn grow(&mut self) -> Result<NonNull<Slab>, AllocError> {
+        let page = PageFrameAllocator.allocate(PAGE_LAYOUT)?.cast::<u8>();
+        let mut free_list = None;
+        let first = size_of::<Slab>().next_multiple_of(self.size);
+        let count = (PAGE_SIZE - first) / self.size;
+        for i in (0..count).rev() {
+            let obj = unsafe { page.byte_add(first + i * self.size) }.cast::<FreeObject>();
+            unsafe {
+                obj.write(FreeObject { next: free_list });
+            }
+            free_list = Some(obj);
+        }
+
+        let slab = page.cast::<Slab>();
+        unsafe {
+            slab.write(Slab {
+                next: self.partial,
+                free_list,
+                in_use: 0,
+            });
+        }
+        self.partial = Some(slab);
+        self.pages += 1;
+        Ok(slab)
+    }
+
+    fn allocate(&mut self) -> Result<NonNull<u8>, AllocError> {
+        let mut slab = match self.partial {
+            Some(slab) => slab,
+            None => self.grow()?,
+        };
+        let slab_ref = unsafe { slab.as_mut() };
+        let obj = slab_ref.free_list.unwrap();
+        slab_ref.free_list = unsafe { obj.as_ref().next };
+        slab_ref.in_use += 1;
+        if slab_ref.free_list.is_none() {
+            // the slab is full
+            self.partial = slab_ref.next.take();
+        }
+        self.allocated += 1;
+        Ok(obj.cast())
+    }
+
+    /// Frees the object at `ptr`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been allocated from this size class.
+    unsafe fn deallocate(&mut self, ptr: NonNull<u8>) {
+        let mut slab = ptr
+            .map_addr(|addr| NonZero::new(addr.get() & !(PAGE_SIZE - 1)).unwrap())
+            .cast::<Slab>();
+        let slab_ref = unsafe { slab.as_mut() };
+        let was_full = slab_ref.free_list.is_none();
+        let obj = ptr.cast::<FreeObject>();
+        unsafe {
+            obj.write(FreeObject {
+                next: slab_ref.free_list,
+            });
+        }
+        slab_ref.free_list = Some(obj);
+        slab_ref.in_use -= 1;
+        self.allocated -= 1;
+
+        if slab_ref.in_use == 0 {
+            if !was_full {
+                self.unlink(slab);
+            }
+            self.pages -= 1;
+            unsafe {
+                PageFrameAllocator.deallocate(slab.cast(), PAGE_LAYOUT);
+            }
+        } else if was_full {
+            slab_ref.next = self.partial;
+            self.partial = Some(slab);
+        }
+    }
+
+    /// Removes `slab` from the list of slabs with free objects.
+    fn unlink(&mut self, slab: NonNull<Slab>) {
+        let mut link = &mut self.partial;
+        while let Some(mut s) = *link {
+            if s == slab {
+                *link = unsafe { s.as_ref().next };
+                return;
+            }
+            link = unsafe { &mut s.as_mut().next };
+        }
+        unreachable!("slab not found in the partial list");
     }
 
-    unsafe fn dealloc(&self, _ptr: *mut u8, _layout: core::alloc::Layout) {
-        panic!("global alloc is not supported")
+    fn info(&self) -> HeapClassInfo {
+        HeapClassInfo {
+            object_size: self.size,
+            pages: self.pages,
+            allocated: self.allocated,
+        }
+    }
+}
+
+static SIZE_CLASSES: [SpinLock<SizeClass>; HEAP_SIZE_CLASSES] = {
+    let mut classes = [const { SpinLock::new(SizeClass::new(0)) }; HEAP_SIZE_CLASSES];
+    let mut i = 0;
+    while i < HEAP_SIZE_CLASSES {
+        classes[i] = SpinLock::new(SizeClass::new(CLASS_SIZES[i]));
+        i += 1;
+    }
+    classes
+};
+/// Number of pages allocated for the objects larger than any size class.
+static LARGE_PAGES: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns the size class for `layout`, or `None` if it needs a whole page.
+fn size_class(layout: Layout) -> Option<&'static SpinLock<SizeClass>> {
+    let size = usize::max(layout.size(), layout.align());
+    let idx = CLASS_SIZES.iter().position(|&s| size <= s)?;
+    Some(&SIZE_CLASSES[idx])
+}
+
+/// Allocator of the kernel heap.
+///
+/// Used as the global allocator, and also as the allocator of the
+/// collections that must handle allocation failures.
+#[derive(Debug, Clone, Copy)]
+pub struct HeapAllocator;
+
+unsafe impl Allocator for HeapAllocator {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        if let Some(class) = size_class(layout) {
+            let mut class = class.lock();
+            let ptr = class.allocate()?;
+            return Ok(NonNull::slice_from_raw_parts(ptr, class.size));
+        }
+
+        if layout.size() > PAGE_SIZE || layout.align() > PAGE_SIZE {
+            return Err(AllocError);
+        }
+        let page = PageFrameAllocator.allocate(PAGE_LAYOUT)?;
+        LARGE_PAGES.fetch_add(1, Ordering::Relaxed);
+        Ok(page)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        if let Some(class) = size_class(layout) {
+            unsafe {
+                class.lock().deallocate(ptr);
+            }
+            return;
+        }
+
+        unsafe {
+            PageFrameAllocator.deallocate(ptr, PAGE_LAYOUT);
+        }
+        LARGE_PAGES.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+unsafe impl GlobalAlloc for HeapAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        self.allocate(layout)
+            .map_or(ptr::null_mut(), |p| p.cast().as_ptr())
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { self.deallocate(NonNull::new(ptr).unwrap(), layout) }
     }
 }
 
 #[global_allocator]
-static GLOBAL_ALLOCATOR: PanicAllocator = PanicAllocator;
+static GLOBAL_ALLOCATOR: HeapAllocator = HeapAllocator;
+
+/// Returns the usage of the kernel heap.
+pub(crate) fn info() -> HeapInfo {
+    HeapInfo {
+        classes: SIZE_CLASSES.each_ref().map(|class| class.lock().info()),
+        large_pages: LARGE_PAGES.load(Ordering::Relaxed),
+    }
+}
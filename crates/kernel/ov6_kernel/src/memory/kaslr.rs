@@ -0,0 +1,193 @@
+//! Kernel address space layout randomization.
+//!
+//! The kernel is linked at its physical load address as a position-independent
+//! executable, and runs there with paging disabled until the kernel page table
+//! is ready. Before paging is enabled, hart 0 chooses a random virtual address
+//! for the kernel image and patches the absolute addresses in the image (the
+//! `R_RISCV_RELATIVE` relocations the linker leaves in `.rela.dyn`) to point
+//! into it. The kernel page table maps the image at that address, and each
+//! hart jumps there as it enables paging (see [`vm_kernel::init_hart`]).
+//!
+//! The distance between the virtual and the physical address of the image is
+//! called the *slide*. The window of the virtual addresses is far larger than
+//! the image, which is placed at a megapage boundary in it, so that the slide
+//! has 16 bits of entropy.
+//!
+//! RAM, including the image, stays mapped at its physical address, so the
+//! pointers taken before paging is enabled remain valid, but the image is not
+//! executable there. Addresses given to devices and put in page tables must
+//! be physical ones; the conversions to [`PhysAddr`] translate the addresses
+//! in the image with [`to_phys`].
+//!
+//! The random seed is taken from the device tree (see [`boot_args::rng_seed`])
+//! and mixed with the clock.
+//!
+//! KASLR is disabled by the `nokaslr` boot option, e.g. to debug the kernel
+//! with GDB, which only knows the link-time addresses.
+//!
+//! [`vm_kernel::init_hart`]: super::vm_kernel::init_hart
+//! [`PhysAddr`]: super::PhysAddr
+
+use core::{
+    arch::global_asm,
+    ptr, slice,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use riscv::register::time;
+
+use super::{
+    layout::{KERNEL_BASE, KERNEL_END},
+    level_page_size,
+};
+use crate::{boot_args, kallsyms, println};
+
+/// Lowest virtual address of the kernel image.
+///
+/// Above the direct map of RAM.
+const VIRT_BASE: usize = 0x10_0000_0000;
+
+/// End of the virtual addresses the kernel image is placed in.
+///
+/// Below the kernel stacks, which are mapped under `TRAPFRAME`.
+const VIRT_END: usize = 0x30_0000_0000;
+
+/// Alignment of the kernel image, that of a megapage.
+const VIRT_ALIGN: usize = level_page_size(1);
+
+/// `r_info` of an `R_RISCV_RELATIVE` relocation, which has no symbol.
+const R_RISCV_RELATIVE: u64 = 3;
+
+/// `Elf64_Rela`.
+#[repr(C)]
+struct Rela {
+    offset: usize,
+    info: u64,
+    addend: usize,
+}
+
+// get the bounds of the relocations from the linker.
+global_asm!(
+    "
+        .global _ov6_rela_start_addr
+        _ov6_rela_start_addr: .dword _ov6_rela_start
+        .global _ov6_rela_end_addr
+        _ov6_rela_end_addr: .dword _ov6_rela_end
+    "
+);
+
+unsafe extern "C" {
+    #[link_name = "_ov6_rela_start_addr"]
+    static RELA_START: usize;
+    #[link_name = "_ov6_rela_end_addr"]
+    static RELA_END: usize;
+}
+
+/// Distance of the virtual address of the kernel image from its physical one.
+static SLIDE: AtomicUsize = AtomicUsize::new(0);
+
+/// Physical address of the start of the kernel image.
+static IMAGE_START: AtomicUsize = AtomicUsize::new(0);
+
+/// Physical address of the end of the kernel image.
+static IMAGE_END: AtomicUsize = AtomicUsize::new(0);
+
+/// Chooses the virtual address of the kernel image.
+///
+/// Called on CPU 0 before the kernel page table is made.
+pub fn init() {
+    let (start, end) = unsafe { (KERNEL_BASE, KERNEL_END) };
+    IMAGE_START.store(start, Ordering::Relaxed);
+    IMAGE_END.store(end, Ordering::Relaxed);
+
+    if boot_args::flag("nokaslr") {
+        println!("KASLR disabled");
+        return;
+    }
+
+    let size = (end - start).next_multiple_of(VIRT_ALIGN);
+    let slots = u64::try_from((VIRT_END - VIRT_BASE - size) / VIRT_ALIGN + 1).unwrap();
+    let slot = usize::try_from(entropy() % slots).unwrap();
+    SLIDE.store(VIRT_BASE + slot * VIRT_ALIGN - start, Ordering::Relaxed);
+}
+
+/// Returns the distance of the virtual address of the kernel image from its
+/// physical one.
+///
+/// Zero if KASLR is disabled.
+pub fn slide() -> usize {
+    SLIDE.load(Ordering::Relaxed)
+}
+
+/// Returns the physical address of the kernel address `addr`.
+///
+/// Addresses outside of the virtual mapping of the kernel image are already
+/// physical ones.
+pub fn to_phys(addr: usize) -> usize {
+    let slide = slide();
+    let start = IMAGE_START.load(Ordering::Relaxed) + slide;
+    let end = IMAGE_END.load(Ordering::Relaxed) + slide;
+    if (start..end).contains(&addr) {
+        addr - slide
+    } else {
+        addr
+    }
+}
+
+/// Moves the addresses in the kernel image to its virtual address.
+///
+/// Called on CPU 0 with paging disabled, just before it is enabled. Code
+/// pointers taken after this point are only valid once paging is enabled,
+/// so this must not be followed by anything but [`vm_kernel::init_hart`].
+///
+/// [`vm_kernel::init_hart`]: super::vm_kernel::init_hart
+pub fn relocate() {
+    let slide = slide();
+    if slide == 0 {
+        return;
+    }
+
+    // the bounds are themselves relocated, so read them first.
+    let relocs = unsafe {
+        let (start, end) = (RELA_START, RELA_END);
+        slice::from_raw_parts(
+            ptr::with_exposed_provenance::<Rela>(start),
+            (end - start) / size_of::<Rela>(),
+        )
+    };
+    if let Some(rela) = relocs.iter().find(|r| r.info != R_RISCV_RELATIVE) {
+        panic!("unsupported relocation type {} in the kernel", rela.info);
+    }
+
+    unsafe {
+        kallsyms::relocate(slide);
+    }
+    for rela in relocs {
+        unsafe {
+            ptr::with_exposed_provenance_mut::<usize>(rela.offset).write(rela.addend + slide);
+        }
+    }
+}
+
+/// Returns random bits for the slide.
+fn entropy() -> u64 {
+    let seed = boot_args::rng_seed();
+    if seed.is_empty() {
+        println!("no random seed given, KASLR depends on the clock only");
+    }
+    let mut x = time::read64();
+    for chunk in seed.chunks(8) {
+        let mut bytes = [0; 8];
+        bytes[..chunk.len()].copy_from_slice(chunk);
+        x = mix(x ^ u64::from_le_bytes(bytes));
+    }
+    mix(x)
+}
+
+/// Scrambles the bits of `x` (the finalizer of `SplitMix64`).
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
@@ -26,6 +26,11 @@
 //! PHYS_TOP    -- end RAM used by the kernel
 //! ```
 //!
+//! These are physical addresses. The kernel image is also mapped at a random
+//! virtual address chosen at boot, where the kernel runs once paging is
+//! enabled (see [`kaslr`](super::kaslr)). The linker symbols for them are
+//! only read before the kernel is moved there.
+//!
 //! [hw/riscv/virt.c]: https://github.com/qemu/qemu/blob/v9.2.2/hw/riscv/virt.c
 
 use core::arch::global_asm;
@@ -38,6 +43,9 @@ use crate::memory::{PAGE_SIZE, VirtAddr};
 /// Test MMIO Device
 pub const VIRT_TEST: usize = 0x10_0000;
 
+/// Goldfish real-time clock
+pub const GOLDFISH_RTC: usize = 0x10_1000;
+
 // qemu puts UART registers here in physical memory.
 pub const UART0: usize = 0x1000_0000;
 pub const UART0_IRQ: usize = 10;
@@ -117,14 +125,17 @@ unsafe extern "C" {
 // ...                 data, bss
 // ...                 expandable heap
 // ...
-// 0x000f_ffff_e000 -- user stack bottom
-// 0x0010_0000_0000 -- user stack top
+// ...                 user stack bottom
+// 0x0020_0000_0000 -- user stack top
 // ...
-// 0x0020_0000_0000 -- usyscall
+// 0x002f_ffff_f000 -- usyscall
 // ...
 // 0x003f_ffff_e000 -- TRAPFRAME
 // 0x003f_ffff_f000 -- TRAMPOLINE
 // 0x0040_0000_0000 -- VirtAddr::MAX
+// ```
+//
+// User programs find these addresses in `UserLayout` on the usyscall page.
 
 pub const USER_STACK_BOTTOM_ADDR: usize = USER_STACK_TOP_ADDR - USER_STACK_SIZE;
 pub const USER_STACK_TOP_ADDR: usize = 0x0020_0000_0000;
@@ -14,7 +14,9 @@ pub const fn level_page_size(level: usize) -> usize {
 pub const PAGE_SHIFT: usize = 12;
 
 pub mod addr;
+pub mod fallible;
 pub mod heap;
+pub mod kaslr;
 pub mod layout;
 pub mod page;
 mod page_allocator;
@@ -4,11 +4,86 @@
 //!
 //! Allocates whole 4096-byte pages.
 
+use once_init::OnceInit;
 use ov6_syscall::MemoryInfo;
 
 pub use self::page_manager::PageFrameAllocator;
-use super::{PhysAddr, layout::KERNEL_END, page_manager};
-use crate::memory::layout::PHYS_TOP;
+use super::{
+    PAGE_SIZE, PhysAddr,
+    layout::KERNEL_END,
+    page_manager::{self, Page},
+};
+use crate::{error::KernelError, memory::layout::PHYS_TOP, proc::oom};
+
+/// Functions to release the pages held by a cache under memory pressure.
+pub struct Reclaimer {
+    /// Returns the number of pages that can be released by `reclaim`.
+    pub reclaimable_pages: fn() -> usize,
+    /// Releases the pages not in use and returns the number of released
+    /// pages.
+    ///
+    /// Called when the allocator runs out of pages, so this must not block
+    /// nor allocate pages.
+    pub reclaim: fn() -> usize,
+}
+
+/// Maximum number of caches registered by [`add_reclaimer()`].
+const MAX_RECLAIMERS: usize = 2;
+
+static RECLAIMERS: [OnceInit<Reclaimer>; MAX_RECLAIMERS] =
+    [const { OnceInit::new() }; MAX_RECLAIMERS];
+
+/// A reference to a page of user memory.
+///
+/// The page can be mapped into user page tables, which hold their own
+/// references to it. The page is freed when all the references are dropped.
+#[derive(Debug)]
+pub struct SharedPage {
+    pa: PhysAddr,
+}
+
+impl Clone for SharedPage {
+    fn clone(&self) -> Self {
+        Self { pa: self.share() }
+    }
+}
+
+impl Drop for SharedPage {
+    fn drop(&mut self) {
+        drop(Page::from_raw(self.pa));
+    }
+}
+
+impl SharedPage {
+    /// Allocates a zeroed page.
+    pub fn alloc_zeroed() -> Result<Self, KernelError> {
+        let pa = Page::alloc_zeroed()?.into_raw();
+        Ok(Self { pa })
+    }
+
+    /// Returns `true` if the page is also referred by others.
+    pub fn is_shared(&self) -> bool {
+        let page = Page::from_raw(self.pa);
+        let ref_count = page.ref_count();
+        let _ = page.into_raw();
+        ref_count > 1
+    }
+
+    /// Returns the contents of the page, or `None` if the page is referred
+    /// by others.
+    pub fn bytes_mut(&mut self) -> Option<&mut [u8; PAGE_SIZE]> {
+        (!self.is_shared()).then(|| unsafe { &mut *self.pa.as_mut_ptr() })
+    }
+
+    /// Adds a reference to the page, and returns its physical address.
+    ///
+    /// The caller owns the added reference.
+    pub(super) fn share(&self) -> PhysAddr {
+        let page = Page::from_raw(self.pa);
+        page.increment_ref();
+        page.into_raw()
+    }
+}
 
 /// Initializes the physical memory allocator.
 ///
@@ -21,6 +96,47 @@ pub fn init() {
     unsafe { page_manager::init(pa_start..pa_end) }
 }
 
+/// Registers a cache whose pages are released when the allocator runs out
+/// of pages.
+pub fn add_reclaimer(mut reclaimer: Reclaimer) {
+    for slot in &RECLAIMERS {
+        match slot.try_init(reclaimer) {
+            Ok(()) => return,
+            Err(r) => reclaimer = r,
+        }
+    }
+    panic!("too many reclaimers");
+}
+
+/// Zeroes some free pages in advance, so that allocations of zeroed pages
+/// (user memory, page tables) do not have to.
+///
+/// Called by idle CPUs. Returns the number of pages zeroed, which is `0` if
+/// enough zeroed pages are already available.
+pub fn scrub() -> usize {
+    page_manager::get().scrub()
+}
+
+/// Releases the pages held by the caches.
+///
+/// Returns the number of released pages.
+pub(super) fn reclaim() -> usize {
+    RECLAIMERS
+        .iter()
+        .filter_map(|r| r.try_get().ok())
+        .map(|r| (r.reclaim)())
+        .sum()
+}
+
+/// Returns the number of pages held by the caches that can be released.
+pub(super) fn reclaimable_pages() -> usize {
+    RECLAIMERS
+        .iter()
+        .filter_map(|r| r.try_get().ok())
+        .map(|r| (r.reclaimable_pages)())
+        .sum()
+}
+
 /// Checks if the given address is within the allocated address range.
 ///
 /// Returns `true` if the pointer is within the range, otherwise `false`.
@@ -29,6 +145,12 @@ pub(super) fn is_heap_addr(pa: PhysAddr) -> bool {
 }
 
 /// Retrieves memory information, including the number of free and total pages.
+///
+/// Pages held by the caches are counted as free because they are released
+/// on demand.
 pub(crate) fn info() -> MemoryInfo {
-    page_manager::get().info()
+    let mut info = page_manager::get().info();
+    info.free_pages += reclaimable_pages();
+    info.oom_kills = oom::kills();
+    info
 }
@@ -1,13 +1,68 @@
 use core::{ops::Range, ptr::NonNull};
 
 use ov6_syscall::MemoryInfo;
-use page_alloc::PageFrameAllocator;
+use page_alloc::{PageFrameAllocator, page_ops};
 
-use super::{PAGE_SIZE, PhysAddr};
+use super::{PAGE_SIZE, PhysAddr, page};
 use crate::{error::KernelError, sync::SpinLock};
 
+/// Number of zeroed pages the scrubber keeps ready for allocation.
+const ZEROED_POOL_TARGET: usize = 256;
+
+/// Maximum number of pages zeroed by one call of
+/// [`PageAllocator::scrub()`].
+const SCRUB_BATCH: usize = 8;
+
 pub(super) struct PageAllocator {
     allocator: SpinLock<PageFrameAllocator<PAGE_SIZE>>,
+    /// Free pages that are already zeroed.
+    zeroed: SpinLock<ZeroedPool>,
+}
+
+/// Header written at the start of a page in [`ZeroedPool`].
+///
+/// Cleared before the page is handed out, so that the whole page is zero.
+struct ZeroedPage {
+    next: Option<NonNull<ZeroedPage>>,
+}
+
+struct ZeroedPool {
+    head: Option<NonNull<ZeroedPage>>,
+    len: usize,
+}
+
+unsafe impl Send for ZeroedPool {}
+
+impl ZeroedPool {
+    const fn new() -> Self {
+        Self { head: None, len: 0 }
+    }
+
+    /// Adds a zero-filled page to the pool.
+    ///
+    /// # Safety
+    ///
+    /// `page` must be a zero-filled page that is not used by anyone else.
+    unsafe fn push(&mut self, page: NonNull<u8>) {
+        let zp = page.cast::<ZeroedPage>();
+        unsafe {
+            zp.write(ZeroedPage { next: self.head });
+        }
+        self.head = Some(zp);
+        self.len += 1;
+    }
+
+    /// Takes a zero-filled page from the pool.
+    fn pop(&mut self) -> Option<NonNull<u8>> {
+        let zp = self.head?;
+        self.head = unsafe { zp.as_ref().next };
+        self.len -= 1;
+        let page = zp.cast::<u8>();
+        unsafe {
+            page.write_bytes(0, size_of::<ZeroedPage>());
+        }
+        Some(page)
+    }
 }
 
 impl PageAllocator {
@@ -22,6 +77,7 @@ impl PageAllocator {
 
         Self {
             allocator: SpinLock::new(allocator),
+            zeroed: SpinLock::new(ZeroedPool::new()),
         }
     }
 
@@ -62,11 +118,7 @@ impl PageAllocator {
     /// The allocated page is filled with a specific pattern to help detect
     /// uninitialized usage.
     pub(super) fn alloc_page(&self) -> Result<NonNull<u8>, KernelError> {
-        let p = self
-            .allocator
-            .lock()
-            .alloc()
-            .ok_or(KernelError::NoFreePage)?;
+        let p = self.alloc_or_reclaim(PageFrameAllocator::alloc)?;
         unsafe {
             p.write_bytes(5, PAGE_SIZE);
         }
@@ -79,21 +131,69 @@ impl PageAllocator {
     /// available.
     ///
     /// The allocated page is initialized to zero.
+    ///
+    /// Pages zeroed in advance by [`Self::scrub()`] are used first, and a page
+    /// is zeroed on demand only if there are none.
     pub(super) fn alloc_zeroed_page(&self) -> Result<NonNull<u8>, KernelError> {
-        self.allocator
-            .lock()
-            .alloc_zeroed()
-            .ok_or(KernelError::NoFreePage)
+        if let Some(p) = self.zeroed.lock().pop() {
+            return Ok(p);
+        }
+        self.alloc_or_reclaim(PageFrameAllocator::alloc_zeroed)
+    }
+
+    /// Allocates a page by `alloc`.
+    ///
+    /// If no page is available, takes a page from the zeroed pages, or
+    /// releases the pages held by the cache and tries again.
+    fn alloc_or_reclaim(
+        &self,
+        alloc: fn(&mut PageFrameAllocator<PAGE_SIZE>) -> Option<NonNull<u8>>,
+    ) -> Result<NonNull<u8>, KernelError> {
+        // the allocator must be unlocked while reclaiming, since the released
+        // pages are returned to the allocator.
+        let p = alloc(&mut self.allocator.lock());
+        if let Some(p) = p {
+            return Ok(p);
+        }
+        if let Some(p) = self.zeroed.lock().pop() {
+            return Ok(p);
+        }
+        if page::reclaim() == 0 {
+            return Err(KernelError::NoFreePage);
+        }
+        alloc(&mut self.allocator.lock()).ok_or(KernelError::NoFreePage)
+    }
+
+    /// Zeroes some free pages in advance, so that later allocations of zeroed
+    /// pages do not have to.
+    ///
+    /// Returns the number of pages zeroed.
+    pub(super) fn scrub(&self) -> usize {
+        let mut scrubbed = 0;
+        while scrubbed < SCRUB_BATCH && self.zeroed.lock().len < ZEROED_POOL_TARGET {
+            let Some(p) = self.allocator.lock().alloc() else {
+                break;
+            };
+            unsafe {
+                page_ops::zero(p, PAGE_SIZE);
+                self.zeroed.lock().push(p);
+            }
+            scrubbed += 1;
+        }
+        scrubbed
     }
 
     /// Retrieves memory information, including the number of free and total
     /// pages.
     pub(super) fn info(&self) -> MemoryInfo {
         let allocator = self.allocator.lock();
+        let zeroed = self.zeroed.lock();
         MemoryInfo {
-            free_pages: allocator.free_pages(),
+            free_pages: allocator.free_pages() + zeroed.len,
+            zeroed_pages: zeroed.len,
             total_pages: allocator.total_pages(),
             page_size: PAGE_SIZE,
+            oom_kills: 0,
         }
     }
 }
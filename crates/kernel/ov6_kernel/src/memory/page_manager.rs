@@ -87,6 +87,42 @@ impl Page<'static> {
         PAGE_MANAGER.get().get_page(pa)
     }
 
+    /// Adds an owner to the page at `pa`.
+    ///
+    /// A non-shareable page (a page allocated by [`PageFrameAllocator`])
+    /// becomes shareable with two owners.
+    pub(super) fn share_raw(pa: PhysAddr) {
+        let page = Self::from_raw(pa);
+        if page.state.try_unmark_non_shareable().is_ok() {
+            page.state.increment_ref();
+        }
+        page.state.increment_ref();
+        let _ = page.into_raw();
+    }
+
+    /// Returns `true` if the page at `pa` has more than one owner.
+    pub(super) fn is_shared_raw(pa: PhysAddr) -> bool {
+        let page = Self::from_raw(pa);
+        let count = page.ref_count();
+        let _ = page.into_raw();
+        count != u32::MAX && count > 1
+    }
+
+    /// Removes an owner from the page at `pa` shared by [`Self::share_raw()`].
+    ///
+    /// Returns `true` if it was the last owner. In that case the page is not
+    /// freed, but becomes non-shareable again so that the caller can free it
+    /// with [`PageFrameAllocator`].
+    pub(super) fn unshare_raw(pa: PhysAddr) -> bool {
+        let page = Self::from_raw(pa);
+        let last = page.ref_count() == u32::MAX || page.state.decrement_ref() == 1;
+        if last && page.ref_count() == 0 {
+            page.state.mark_non_shareable();
+        }
+        let _ = page.into_raw();
+        last
+    }
+
     /// Allocates one 4096-byte page of physical memory.
     ///
     /// Returns a `Page` instance on success, or an error if no memory is
@@ -340,6 +376,13 @@ impl PageManager {
         self.allocator.is_heap_addr(pa.as_non_null())
     }
 
+    /// Zeroes some free pages in advance.
+    ///
+    /// Returns the number of pages zeroed.
+    pub(super) fn scrub(&self) -> usize {
+        self.allocator.scrub()
+    }
+
     /// Retrieves memory information, including the number of free and total
     /// pages.
     pub(super) fn info(&self) -> MemoryInfo {
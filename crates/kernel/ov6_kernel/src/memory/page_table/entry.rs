@@ -3,6 +3,7 @@ use core::slice;
 
 use bitflags::bitflags;
 use dataview::Pod;
+use page_alloc::page_ops;
 
 use super::PageTableEntries;
 use crate::{
@@ -162,7 +163,7 @@ impl PtEntry {
     /// Frees the memory associated with this entry.
     ///
     /// If the entry is a non-leaf entry, it recursively frees all child
-    /// entries.
+    /// entries, unless the child page table is shared with other page tables.
     pub(super) fn free(&mut self, level: usize) {
         if !self.is_valid() {
             return;
@@ -174,10 +175,7 @@ impl PtEntry {
 
         if is_non_leaf {
             assert!(level > 0);
-            let ptr = pa.as_mut_ptr::<PageTableEntries>();
-            let mut pt = unsafe { Box::from_raw_in(ptr, PageFrameAllocator) };
-            pt.free(level - 1);
-            drop(pt);
+            Self::release_page_table(pa, level - 1);
         } else {
             assert_eq!(level, 0, "super page is not supported yet");
             if page::is_heap_addr(pa) {
@@ -186,6 +184,68 @@ impl PtEntry {
         }
     }
 
+    /// Drops a reference to the level-`level` page table at `pa`.
+    ///
+    /// If it was the last reference, frees the page table and its entries.
+    fn release_page_table(pa: PhysAddr, level: usize) {
+        if !Page::unshare_raw(pa) {
+            return;
+        }
+        let ptr = pa.as_mut_ptr::<PageTableEntries>();
+        let mut pt = unsafe { Box::from_raw_in(ptr, PageFrameAllocator) };
+        pt.free(level);
+        drop(pt);
+    }
+
+    /// Returns `true` if this entry points to a page table shared with other
+    /// page tables.
+    pub(super) fn is_shared_page_table(&self) -> bool {
+        self.is_non_leaf() && Page::is_shared_raw(self.phys_addr())
+    }
+
+    /// Shares the page table pointed by this non-leaf entry with another page
+    /// table.
+    ///
+    /// Returns an entry for the other page table that points to the same
+    /// page table.
+    ///
+    /// The shared page table must not be modified until it is unshared by
+    /// [`Self::unshare_page_table()`].
+    pub(super) fn share_page_table(&self) -> Self {
+        assert!(self.is_non_leaf());
+        Page::share_raw(self.phys_addr());
+        Self(self.0)
+    }
+
+    /// Makes the page table pointed by this entry owned only by this entry.
+    ///
+    /// If the page table is shared with other page tables, this entry is
+    /// replaced with a copy of it.
+    pub(super) fn unshare_page_table(&mut self) -> Result<(), KernelError> {
+        if !self.is_shared_page_table() {
+            return Ok(());
+        }
+
+        let old_pa = self.phys_addr();
+        let old_pt = self.get_page_table().unwrap();
+        let mut new_pt = PageTableEntries::try_allocate()?;
+        for (dst, src) in new_pt.0.iter_mut().zip(&old_pt.0) {
+            // only level-0 page tables are shared
+            assert!(!src.is_non_leaf());
+            if src.is_valid() && page::is_heap_addr(src.phys_addr()) {
+                let page = Page::from_raw(src.phys_addr());
+                page.increment_ref();
+                let _ = page.into_raw();
+            }
+            *dst = Self(src.0);
+        }
+
+        self.0 = 0;
+        self.set_page_table(new_pt);
+        Self::release_page_table(old_pa, 0);
+        Ok(())
+    }
+
     /// Returns the physical page number (PPN) associated with this entry.
     fn phys_page_num(&self) -> PhysPageNum {
         PhysPageNum::new(self.0 >> 10)
@@ -273,9 +333,44 @@ impl PtEntry {
         let new_pa = new_page.into_raw();
 
         unsafe {
-            new_pa
-                .as_mut_ptr::<u8>()
-                .copy_from(old_pa.as_ptr::<u8>(), level_page_size(level));
+            page_ops::copy(
+                new_pa.as_non_null(),
+                old_pa.as_non_null(),
+                level_page_size(level),
+            );
+        }
+        *self = unsafe { Self::new(new_pa.phys_page_num(), flags) };
+
+        drop(page);
+
+        Ok(())
+    }
+
+    /// Replaces the page by a private copy if it is shared, keeping the
+    /// flags.
+    ///
+    /// Used to modify pages that the user cannot write, such as shared text
+    /// pages.
+    pub(crate) fn make_private(&mut self, level: usize) -> Result<(), KernelError> {
+        assert_eq!(level, 0, "super page is not supported yet");
+
+        let page = Page::from_raw(self.phys_addr());
+        if page.ref_count() == 1 {
+            let _ = page.into_raw();
+            return Ok(());
+        }
+
+        let flags = self.flags();
+        let old_pa = self.phys_addr();
+        let new_page = Page::alloc()?;
+        let new_pa = new_page.into_raw();
+
+        unsafe {
+            page_ops::copy(
+                new_pa.as_non_null(),
+                old_pa.as_non_null(),
+                level_page_size(level),
+            );
         }
         *self = unsafe { Self::new(new_pa.phys_page_num(), flags) };
 
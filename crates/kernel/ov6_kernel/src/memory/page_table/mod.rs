@@ -4,7 +4,11 @@
 //! table entries.
 
 use alloc::boxed::Box;
-use core::{alloc::AllocError, fmt, ops::RangeBounds};
+use core::{
+    alloc::AllocError,
+    fmt,
+    ops::{RangeBounds, RangeInclusive},
+};
 
 use dataview::Pod;
 use riscv::register::satp::{self, Satp};
@@ -14,7 +18,11 @@ use self::{
     entry::PtEntry,
     iter::{Entries, LeavesMut},
 };
-use super::{PhysAddr, VirtAddr, addr::PhysPageNum, page::PageFrameAllocator};
+use super::{
+    PhysAddr, VirtAddr,
+    addr::PhysPageNum,
+    page::{PageFrameAllocator, SharedPage},
+};
 use crate::{
     error::KernelError,
     memory::{self, PAGE_SIZE, PageRound as _, level_page_size, page_manager::Page},
@@ -96,6 +104,10 @@ impl PageTable {
     /// Clones pages from another page table within the specified virtual
     /// address range.
     ///
+    /// The pages are shared as copy-on-write pages. A level-0 page table
+    /// whose pages all lie in the range is shared as a whole instead of
+    /// copying its entries, and is copied when either page table modifies it.
+    ///
     /// Returns an error if the source pages are inaccessible or if the
     /// operation fails.
     pub(super) fn clone_pages_from<R>(
@@ -107,6 +119,51 @@ impl PageTable {
     where
         R: RangeBounds<VirtAddr>,
     {
+        let Some(va_range) = VirtAddr::range_inclusive(va_range) else {
+            return Ok(());
+        };
+        let (start, end) = (*va_range.start(), *va_range.end());
+
+        for table_va in level1_pages(start, end) {
+            if let Some(src_pte) = other.0.find_entry_mut(1, table_va)
+                && src_pte.is_non_leaf()
+                && leaves_in(src_pte.get_page_table().unwrap(), table_va, start, end) == Leaves::All
+            {
+                if !src_pte.is_shared_page_table() {
+                    let src_pt = src_pte.get_page_table_mut().unwrap();
+                    for (i, pte) in src_pt.0.iter_mut().enumerate() {
+                        if !pte.is_valid() {
+                            continue;
+                        }
+                        if !pte.flags().contains(flags) {
+                            let va = table_va.with_level_idx(0, i);
+                            return Err(KernelError::InaccessiblePage(va));
+                        }
+                        pte.make_copy_on_write();
+                    }
+                }
+                let dst_pte = self.0.find_or_create_leaf(1, table_va)?;
+                assert!(!dst_pte.is_valid());
+                *dst_pte = src_pte.share_page_table();
+                continue;
+            }
+
+            let table_end = table_va.byte_add(level_page_size(1) - 1).unwrap();
+            let range = VirtAddr::max(start, table_va)..=VirtAddr::min(end, table_end);
+            self.clone_leaves_from(other, range, flags)?;
+        }
+
+        Ok(())
+    }
+
+    /// Clones pages from another page table within the specified virtual
+    /// address range, copying the entries one by one.
+    fn clone_leaves_from(
+        &mut self,
+        other: &mut Self,
+        va_range: RangeInclusive<VirtAddr>,
+        flags: PtEntryFlags,
+    ) -> Result<(), KernelError> {
         for (level, va, src_pte) in other.leaves_mut(va_range) {
             if !src_pte.is_leaf() {
                 continue;
@@ -134,6 +191,31 @@ impl PageTable {
         Ok(())
     }
 
+    /// Maps `page` at virtual address `va`.
+    ///
+    /// Returns an error if `va` is already mapped.
+    pub(super) fn map_shared_page(
+        &mut self,
+        va: VirtAddr,
+        page: &SharedPage,
+        perm: PtEntryFlags,
+    ) -> Result<(), KernelError> {
+        assert!(va.is_page_aligned());
+        assert!(perm.intersects(PtEntryFlags::RWX), "perm={perm:?}");
+        let pte = self.0.find_or_create_leaf(0, va)?;
+        if pte.is_valid() {
+            let pte_perm = pte.flags() & PtEntryFlags::URWX;
+            return Err(KernelError::VirtualAddressWithUnexpectedPerm(
+                va, perm, pte_perm,
+            ));
+        }
+        let pa = page.share();
+        unsafe {
+            pte.set_phys_page_num(pa.phys_page_num(), perm | PtEntryFlags::V);
+        }
+        Ok(())
+    }
+
     /// Unmaps the pages of memory starting at virtual address `va` and covering
     /// `size` bytes.
     ///
@@ -141,17 +223,37 @@ impl PageTable {
     pub(super) fn unmap_addrs(&mut self, va: VirtAddr, size: usize) -> Result<(), KernelError> {
         let start = va;
         let end = va.byte_add(size)?;
-        self.unmap_range(start..end);
-        Ok(())
+        self.unmap_range(start..end)
     }
 
-    fn unmap_range<R>(&mut self, va_range: R)
+    fn unmap_range<R>(&mut self, va_range: R) -> Result<(), KernelError>
     where
         R: RangeBounds<VirtAddr>,
     {
+        let Some(va_range) = VirtAddr::range_inclusive(va_range) else {
+            return Ok(());
+        };
+        let (start, end) = (*va_range.start(), *va_range.end());
+
+        // shared page tables must not be modified.
+        for table_va in level1_pages(start, end) {
+            let Some(pte) = self.0.find_entry_mut(1, table_va) else {
+                continue;
+            };
+            if !pte.is_shared_page_table() {
+                continue;
+            }
+            match leaves_in(pte.get_page_table().unwrap(), table_va, start, end) {
+                Leaves::None => {}
+                Leaves::All => pte.free(1),
+                Leaves::Some => pte.unshare_page_table()?,
+            }
+        }
+
         for (level, _va, pte) in self.leaves_mut(va_range) {
             pte.free(level);
         }
+        Ok(())
     }
 
     fn entries<R>(&self, va_range: R) -> Entries<'_>
@@ -205,11 +307,19 @@ impl PageTable {
                 return Ok((level, pte));
             }
             assert!(pte.is_non_leaf());
+            pte.unshare_page_table()?;
             pt = pte.get_page_table_mut().unwrap();
         }
         panic!("invalid page table");
     }
 
+    /// Returns the flags of the leaf entry mapping `va`, or `None` if `va` is
+    /// not mapped.
+    pub(super) fn leaf_flags(&self, va: VirtAddr) -> Option<PtEntryFlags> {
+        let (_level, pte) = self.find_leaf_entry(va).ok()?;
+        Some(pte.flags())
+    }
+
     /// Fetches a chunk of memory corresponding to the virtual address `va`.
     ///
     /// Returns a slice of bytes if the operation is successful, or an error
@@ -256,6 +366,33 @@ impl PageTable {
         Ok(&mut page[offset..])
     }
 
+    /// Fetches a mutable chunk of the user page at `va`, even if the user
+    /// cannot write it.
+    ///
+    /// A page shared with other page tables is copied first, so the
+    /// modification is visible only through this page table.
+    pub(super) fn fetch_chunk_private_mut(
+        &mut self,
+        va: VirtAddr,
+    ) -> Result<&mut [u8], KernelError> {
+        let (level, pte) = self.find_leaf_entry_mut(va)?;
+        assert!(pte.is_valid() && pte.is_leaf());
+        let flags = pte.flags();
+        if !flags.contains(PtEntryFlags::U) {
+            return Err(KernelError::InaccessiblePage(va));
+        }
+        if flags.contains(PtEntryFlags::C) {
+            pte.request_user_write(level, va)?;
+        } else if !flags.contains(PtEntryFlags::W) {
+            pte.make_private(level)?;
+        }
+
+        let page = pte.get_page_bytes_mut(level).unwrap();
+        let page_size = page.len();
+        let offset = va.addr() % page_size;
+        Ok(&mut page[offset..])
+    }
+
     pub(super) fn request_user_write(&mut self, va: VirtAddr) -> Result<(), KernelError> {
         let (level, pte) = self.find_leaf_entry_mut(va)?;
         pte.request_user_write(level, va)?;
@@ -397,6 +534,7 @@ impl PageTableEntries {
                 let new_pt = Self::try_allocate()?;
                 pte.set_page_table(new_pt);
             }
+            pte.unshare_page_table()?;
             pt = pte.get_page_table_mut().unwrap();
         }
 
@@ -405,6 +543,24 @@ impl PageTableEntries {
         Ok(pte)
     }
 
+    /// Returns the level-`level` entry for `va`, or `None` if the page table
+    /// containing it does not exist.
+    ///
+    /// Page tables shared with other page tables are returned as is, so the
+    /// caller must not modify the entries in them.
+    fn find_entry_mut(&mut self, level: usize, va: VirtAddr) -> Option<&mut PtEntry> {
+        assert!(level <= 2);
+        let mut pt = self;
+        for level in (level + 1..=2).rev() {
+            let pte = &mut pt.0[va.level_idx(level)];
+            if !pte.is_non_leaf() {
+                return None;
+            }
+            pt = pte.get_page_table_mut().unwrap();
+        }
+        Some(&mut pt.0[va.level_idx(level)])
+    }
+
     /// Creates PTE for virtual page `vpn` that refer to
     /// physical page `ppn`.
     fn map_page(
@@ -521,6 +677,44 @@ impl PageTableEntries {
     }
 }
 
+/// Returns the start addresses of the level-1 pages overlapping
+/// `start..=end`.
+fn level1_pages(start: VirtAddr, end: VirtAddr) -> impl Iterator<Item = VirtAddr> {
+    let first = start.level_page_rounddown(1);
+    core::iter::successors(Some(first), move |va| {
+        va.byte_add(level_page_size(1)).ok().filter(|va| *va <= end)
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Leaves {
+    None,
+    Some,
+    All,
+}
+
+/// Returns how many of the valid entries in the level-0 page table `pt`
+/// mapping from `table_va` lie in `start..=end`.
+fn leaves_in(pt: &PageTableEntries, table_va: VirtAddr, start: VirtAddr, end: VirtAddr) -> Leaves {
+    let (mut inside, mut outside) = (false, false);
+    for (i, pte) in pt.0.iter().enumerate() {
+        if !pte.is_valid() {
+            continue;
+        }
+        let va = table_va.with_level_idx(0, i);
+        if start <= va && va <= end {
+            inside = true;
+        } else {
+            outside = true;
+        }
+    }
+    match (inside, outside) {
+        (false, _) => Leaves::None,
+        (true, true) => Leaves::Some,
+        (true, false) => Leaves::All,
+    }
+}
+
 /// Dumps the contents of the given page table for debugging purposes.
 pub(crate) fn dump_pagetable(pt: &PageTable) {
     println!("page table {:p}", pt);
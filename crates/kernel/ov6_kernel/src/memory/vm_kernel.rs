@@ -1,8 +1,11 @@
+use core::arch::naked_asm;
+
 use once_init::OnceInit;
 use ov6_kernel_params::NPROC;
-use riscv::{asm, register::satp};
+use riscv::asm;
 
 use super::{
+    kaslr,
     layout::{self, KSTACK_PAGES},
     page_table::{self, MapTarget, PageTable},
 };
@@ -12,8 +15,9 @@ use crate::{
     memory::{
         PAGE_SIZE, PhysAddr, VirtAddr,
         layout::{
-            CLINT, CLINT_SIZE, KERNEL_BASE, PCIE_ECAM, PCIE_ECAM_SIZE, PCIE_MMIO, PCIE_MMIO_SIZE,
-            PHYS_TOP, PLIC, PLIC_SIZE, TEXT_END, TRAMPOLINE, UART0, VIRT_TEST, VIRTIO0,
+            CLINT, CLINT_SIZE, GOLDFISH_RTC, KERNEL_BASE, KERNEL_END, PCIE_ECAM, PCIE_ECAM_SIZE,
+            PCIE_MMIO, PCIE_MMIO_SIZE, PHYS_TOP, PLIC, PLIC_SIZE, TEXT_END, TRAMPOLINE, UART0,
+            VIRT_TEST, VIRTIO0,
         },
         page_table::PtEntryFlags,
     },
@@ -28,18 +32,43 @@ pub fn init() {
 }
 
 /// Switch h/w page table register to the kernel's page table,
-/// and enable paging.
-pub fn init_hart() {
+/// enable paging, and continue at `cont` in the virtual mapping of the
+/// kernel image.
+///
+/// The stack is kept, but `cont` starts a new chain of stack frames, as the
+/// return addresses of the frames so far are physical ones.
+pub fn init_hart(cont: extern "C" fn() -> !) -> ! {
     // wait for any previous writes to the page table memory to finish.
     asm::sfence_vma_all();
 
     let satp = KERNEL_PAGE_TABLE.get().0.satp();
-    unsafe {
-        satp::write(satp);
-    }
+    let slide = kaslr::slide();
+    unsafe { enable_paging(satp.bits(), cont as usize + slide, slide) }
+}
 
-    // flush state entries from the TLB.
-    asm::sfence_vma_all();
+/// Writes `satp` and jumps to `cont`.
+///
+/// The hart runs in the physical mapping of the kernel image until it jumps
+/// to the virtual one. With KASLR, the physical mapping is not executable,
+/// so fetching the instruction after the write to `satp` traps. `stvec` is
+/// set to the virtual address of the rest of the sequence beforehand, so the
+/// trap lands there as well.
+#[unsafe(naked)]
+unsafe extern "C" fn enable_paging(satp: usize, cont: usize, slide: usize) -> ! {
+    naked_asm!(
+        "lla t0, 2f",
+        "add t0, t0, a2",
+        "csrw stvec, t0",
+        "csrw satp, a0",
+        "jr t0",
+        ".align 2",
+        "2:",
+        // flush stale entries from the TLB.
+        "sfence.vma zero, zero",
+        // start a new chain of stack frames.
+        "mv fp, zero",
+        "jr a1",
+    );
 }
 
 unsafe fn ident_map(
@@ -58,17 +87,38 @@ unsafe fn ident_map(
     }
 }
 
+unsafe fn slide_map(
+    kpgtbl: &mut PageTable,
+    addr: usize,
+    size: usize,
+    slide: usize,
+    perm: PtEntryFlags,
+) -> Result<(), KernelError> {
+    unsafe {
+        kpgtbl.map_addrs(
+            VirtAddr::new(addr + slide)?,
+            MapTarget::fixed_addr(PhysAddr::new(addr)),
+            size,
+            perm,
+        )
+    }
+}
+
 pub struct KernelPageTable(PageTable);
 
 impl KernelPageTable {
-    /// Makes a direct-map page table for the kernel.
+    /// Makes a direct-map page table for the kernel, with the kernel image
+    /// also mapped at its randomized virtual address.
     pub fn new() -> Self {
         use PtEntryFlags as F;
 
-        let phys_trampoline = PhysAddr::new(trampoline::trampoline as usize);
+        let phys_trampoline = PhysAddr::new(kaslr::to_phys(trampoline::trampoline as usize));
+        let slide = kaslr::slide();
 
         let rw = F::RW;
         let rx = F::RX;
+        // the kernel text is only executed in the virtual mapping with KASLR.
+        let text = if slide == 0 { rx } else { F::R };
 
         let mut kpgtbl = PageTable::try_allocate().unwrap();
 
@@ -76,6 +126,9 @@ impl KernelPageTable {
             // SiFive test MMIO device
             ident_map(&mut kpgtbl, VIRT_TEST, PAGE_SIZE, rw).unwrap();
 
+            // Goldfish RTC
+            ident_map(&mut kpgtbl, GOLDFISH_RTC, PAGE_SIZE, rw).unwrap();
+
             // uart registers
             ident_map(&mut kpgtbl, UART0, PAGE_SIZE, rw).unwrap();
 
@@ -94,12 +147,20 @@ impl KernelPageTable {
             // PLIC
             ident_map(&mut kpgtbl, PLIC, PLIC_SIZE, rw).unwrap();
 
-            // map kernel text executable and red-only.
-            ident_map(&mut kpgtbl, KERNEL_BASE, TEXT_END - KERNEL_BASE, rx).unwrap();
+            // map kernel text read-only.
+            ident_map(&mut kpgtbl, KERNEL_BASE, TEXT_END - KERNEL_BASE, text).unwrap();
 
             // map kernel data and the physical RAM we'll make use of.
             ident_map(&mut kpgtbl, TEXT_END, PHYS_TOP - TEXT_END, rw).unwrap();
 
+            if slide != 0 {
+                // map the kernel image at its randomized address,
+                // with the text executable and read-only.
+                slide_map(&mut kpgtbl, KERNEL_BASE, TEXT_END - KERNEL_BASE, slide, rx).unwrap();
+                let data_size = (KERNEL_END - TEXT_END).next_multiple_of(PAGE_SIZE);
+                slide_map(&mut kpgtbl, TEXT_END, data_size, slide, rw).unwrap();
+            }
+
             // map the trampoline for trap entry/exit to
             // the highest virtual address in the kernel.
             kpgtbl
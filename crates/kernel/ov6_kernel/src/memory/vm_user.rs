@@ -1,17 +1,20 @@
-use core::ops::Range;
+use core::{ops::Range, ptr::NonNull};
 
 use dataview::{DataView, Pod, PodMethods as _};
-use ov6_syscall::{USyscallData, UserMutRef, UserMutSlice, UserRef, UserSlice};
+use ov6_syscall::{USyscallData, UserLayout, UserMutRef, UserMutSlice, UserRef, UserSlice};
 use ov6_types::process::ProcId;
+use page_alloc::page_ops;
 use riscv::register::satp::Satp;
 
 use super::{
-    PageRound as _, PhysAddr, VirtAddr,
+    PAGE_SIZE, PageRound as _, PhysAddr, VirtAddr,
     addr::{GenericMutSlice, GenericSlice, Validated},
+    kaslr,
     layout::{
         TRAMPOLINE, TRAMPOLINE_SIZE, TRAPFRAME, TRAPFRAME_SIZE, USER_STACK_BOTTOM, USER_STACK_SIZE,
         USYSCALL, USYSCALL_SIZE,
     },
+    page::SharedPage,
     page_table::{self, MapTarget, PageTable, PtEntryFlags},
 };
 use crate::{
@@ -46,7 +49,9 @@ impl UserPageTable {
         unsafe {
             this.pt.map_addrs(
                 TRAMPOLINE,
-                MapTarget::fixed_addr(PhysAddr::new(trampoline::trampoline as usize)),
+                MapTarget::fixed_addr(PhysAddr::new(kaslr::to_phys(
+                    trampoline::trampoline as usize,
+                ))),
                 TRAMPOLINE_SIZE,
                 PtEntryFlags::RX,
             )?;
@@ -86,6 +91,7 @@ impl UserPageTable {
 
     pub fn set_heap_start(&mut self, heap_start: VirtAddr) {
         self.heap_start = heap_start;
+        self.usyscall_data_mut().layout.heap_start = heap_start.addr();
     }
 
     pub fn program_break(&self) -> VirtAddr {
@@ -96,6 +102,14 @@ impl UserPageTable {
         self.stack_start.byte_add(self.stack_size).unwrap()
     }
 
+    /// Returns the number of pages of user memory: the program, the heap,
+    /// and the stack.
+    pub fn user_pages(&self) -> usize {
+        let program_and_heap =
+            self.program_break().page_roundup().addr() - VirtAddr::MIN_AVA.addr();
+        (program_and_heap + self.stack_size) / PAGE_SIZE
+    }
+
     pub fn alloc_stack(&mut self) -> Result<(), KernelError> {
         unsafe {
             self.pt.map_addrs(
@@ -119,13 +133,26 @@ impl UserPageTable {
             )?;
         }
 
-        let bytes = self.fetch_chunk_mut(start_va, PtEntryFlags::U)?;
-        assert!(bytes.len() >= size_of::<USyscallData>());
-        *DataView::from_mut(bytes).get_mut::<USyscallData>(0) = USyscallData { pid };
+        let layout = UserLayout {
+            heap_start: self.heap_start.addr(),
+            stack_bottom: self.stack_start.addr(),
+            stack_top: self.stack_top().addr(),
+            usyscall: USYSCALL.addr(),
+            trapframe: TRAPFRAME.addr(),
+            trampoline: TRAMPOLINE.addr(),
+            max_va: VirtAddr::MAX.addr(),
+        };
+        *self.usyscall_data_mut() = USyscallData::new(pid, layout);
 
         Ok(())
     }
 
+    fn usyscall_data_mut(&mut self) -> &mut USyscallData {
+        let bytes = self.fetch_chunk_mut(USYSCALL, PtEntryFlags::U).unwrap();
+        assert!(bytes.len() >= size_of::<USyscallData>());
+        DataView::from_mut(bytes).get_mut::<USyscallData>(0)
+    }
+
     pub unsafe fn map_addrs(
         &mut self,
         va: VirtAddr,
@@ -136,6 +163,18 @@ impl UserPageTable {
         unsafe { self.pt.map_addrs(va, pa, size, flags) }
     }
 
+    /// Maps `page` at virtual address `va`.
+    ///
+    /// Returns an error if `va` is already mapped.
+    pub fn map_shared_page(
+        &mut self,
+        va: VirtAddr,
+        page: &SharedPage,
+        flags: PtEntryFlags,
+    ) -> Result<(), KernelError> {
+        self.pt.map_shared_page(va, page, flags)
+    }
+
     pub fn grow_heap_by(
         &mut self,
         increment: usize,
@@ -170,7 +209,9 @@ impl UserPageTable {
                 self.pt
                     .map_addrs(map_start, MapTarget::allocate_new_zeroed(), map_size, xperm)
             } {
-                self.shrink_heap_to_size(old_size);
+                // the pages mapped above are not in shared page tables, so
+                // this does not allocate.
+                self.shrink_heap_to_size(old_size).unwrap();
                 return Err(e);
             }
         }
@@ -183,35 +224,36 @@ impl UserPageTable {
             .heap_size
             .checked_sub(decrement)
             .ok_or(KernelError::HeapSizeUnderflow)?;
-        self.shrink_heap_to_size(new_size);
-
-        Ok(())
+        self.shrink_heap_to_size(new_size)
     }
 
     /// Deallocates user pages to bring the process size to `new_size`.
     ///
     /// `new_size` need not be page-aligned.
     /// `new_size` need not to be less than current size.
-    fn shrink_heap_to_size(&mut self, new_size: usize) {
+    fn shrink_heap_to_size(&mut self, new_size: usize) -> Result<(), KernelError> {
         if new_size >= self.heap_size {
-            return;
+            return Ok(());
         }
 
         if new_size.page_roundup() < self.heap_size.page_roundup() {
             let size = self.heap_size.page_roundup() - new_size.page_roundup();
             let start_va = self.heap_start.byte_add(new_size).unwrap().page_roundup();
-            self.pt.unmap_addrs(start_va, size).unwrap();
+            self.pt.unmap_addrs(start_va, size)?;
         }
 
         self.heap_size = new_size;
+        Ok(())
     }
 
     pub fn clone_from(&mut self, other: &mut Self) -> Result<(), KernelError> {
-        self.pt.clone_pages_from(
-            &mut other.pt,
-            VirtAddr::MIN_AVA..other.heap_start,
-            PtEntryFlags::U,
-        )?;
+        // program and heap are cloned at once, so that the page table at
+        // their boundary can be shared.
+        let heap_end = other.heap_start.byte_add(other.heap_size)?;
+        self.pt
+            .clone_pages_from(&mut other.pt, VirtAddr::MIN_AVA..heap_end, PtEntryFlags::U)?;
+        self.set_heap_start(other.heap_start);
+        self.heap_size = other.heap_size;
 
         let stack_range = other.stack_start..other.stack_top();
         self.pt
@@ -219,16 +261,33 @@ impl UserPageTable {
         self.stack_start = other.stack_start;
         self.stack_size = other.stack_size;
 
-        let heap_start = other.heap_start;
-        let heap_end = heap_start.byte_add(other.heap_size)?;
-        self.pt
-            .clone_pages_from(&mut other.pt, heap_start..heap_end, PtEntryFlags::U)?;
-        self.heap_start = other.heap_start;
-        self.heap_size = other.heap_size;
-
         Ok(())
     }
 
+    /// Returns the flags of the page mapping `va`, or `None` if `va` is not
+    /// mapped.
+    pub fn page_flags(&self, va: VirtAddr) -> Option<PtEntryFlags> {
+        self.pt.leaf_flags(va)
+    }
+
+    /// Returns the regions of the address space, with their names.
+    ///
+    /// The program region extends up to the start of the heap, and may
+    /// include unmapped pages at its end.
+    pub fn regions(&self) -> [(&'static str, Range<VirtAddr>); 6] {
+        [
+            ("program", VirtAddr::MIN_AVA..self.heap_start),
+            ("heap", self.heap_start..self.program_break()),
+            ("stack", self.stack_start..self.stack_top()),
+            (
+                "usyscall",
+                USYSCALL..USYSCALL.byte_add(USYSCALL_SIZE).unwrap(),
+            ),
+            ("trapframe", TRAPFRAME..TRAMPOLINE),
+            ("trampoline", TRAMPOLINE..VirtAddr::MAX),
+        ]
+    }
+
     pub fn fetch_chunk(&self, va: VirtAddr, flags: PtEntryFlags) -> Result<&[u8], KernelError> {
         self.pt.fetch_chunk(va, flags)
     }
@@ -241,7 +300,27 @@ impl UserPageTable {
         self.pt.fetch_chunk_mut(va, flags)
     }
 
+    /// Fetches a mutable chunk of user memory at `va`, even if the page is
+    /// read-only, copying it if it is shared.
+    ///
+    /// Used by ptrace to modify the program text of the traced process.
+    pub fn fetch_chunk_private_mut(&mut self, va: VirtAddr) -> Result<&mut [u8], KernelError> {
+        if va >= TRAPFRAME {
+            return Err(KernelError::ReservedVirtualAddress(va));
+        }
+        self.pt.fetch_chunk_private_mut(va)
+    }
+
+    /// Checks that all pages in `va` are mapped with `perm`.
+    ///
+    /// A range reaching the pages reserved by the kernel (trapframe and
+    /// trampoline) is rejected without looking at the page table.
     pub fn validate(&self, va: Range<VirtAddr>, perm: PtEntryFlags) -> Result<(), KernelError> {
+        if va.end > TRAPFRAME {
+            return Err(KernelError::ReservedVirtualAddress(VirtAddr::max(
+                va.start, TRAPFRAME,
+            )));
+        }
         self.pt.validate(va, perm)
     }
 
@@ -253,6 +332,12 @@ impl UserPageTable {
         self.validate(va, PtEntryFlags::UW)
     }
 
+    // The copy functions below never dereference user virtual addresses.
+    // They look up each page in the page table and access it through the
+    // kernel's direct mapping, so a bad user address is found by the lookup
+    // instead of faulting, and no fixup of faulting instructions is needed.
+    // The lookups cannot fail, since the buffers have been validated.
+
     /// Copies from user to kernel.
     pub fn copy_k2u<T>(&mut self, dst: &mut Validated<UserMutRef<T>>, src: &T)
     where
@@ -274,7 +359,7 @@ impl UserPageTable {
                 .fetch_chunk_mut(dst_start, PtEntryFlags::UW)
                 .unwrap();
             let n = usize::min(src.len(), dst_chunk.len());
-            dst_chunk[..n].copy_from_slice(&src[..n]);
+            copy_chunk(&mut dst_chunk[..n], &src[..n]);
             dst_start = dst_start.byte_add(n).unwrap();
             src = &src[n..];
         }
@@ -310,7 +395,7 @@ impl UserPageTable {
         while src_start < src_end {
             let src_chunk = self.pt.fetch_chunk(src_start, PtEntryFlags::UR).unwrap();
             let n = usize::min(dst.len(), src_chunk.len());
-            dst[..n].copy_from_slice(&src_chunk[..n]);
+            copy_chunk(&mut dst[..n], &src_chunk[..n]);
             dst = &mut dst[n..];
             src_start = src_start.byte_add(n).unwrap();
         }
@@ -373,7 +458,7 @@ impl UserPageTable {
             }
 
             let n = usize::min(dst_bytes.len(), src_bytes.len());
-            dst_bytes[..n].copy_from_slice(&src_bytes[..n]);
+            copy_chunk(&mut dst_bytes[..n], &src_bytes[..n]);
             dst_bytes = &mut dst_bytes[n..];
             src_bytes = &src_bytes[n..];
             total_copied += n;
@@ -403,3 +488,23 @@ impl UserPageTable {
         self.pt.request_user_write(va)
     }
 }
+
+/// Copies a chunk of a user copy.
+///
+/// A whole page between page-aligned buffers, which is the common case of
+/// large reads and writes, is copied by [`page_ops::copy()`].
+fn copy_chunk(dst: &mut [u8], src: &[u8]) {
+    assert_eq!(dst.len(), src.len());
+    let is_page = |p: *const u8| p.addr().is_multiple_of(PAGE_SIZE);
+    if dst.len() == PAGE_SIZE && is_page(dst.as_ptr()) && is_page(src.as_ptr()) {
+        unsafe {
+            page_ops::copy(
+                NonNull::from(dst).cast(),
+                NonNull::from(src).cast(),
+                PAGE_SIZE,
+            );
+        }
+        return;
+    }
+    dst.copy_from_slice(src);
+}
@@ -1,9 +1,12 @@
 use core::net::Ipv4Addr;
 
+use ov6_syscall::NetInfo;
+
 use self::{
     ethernet::{Eth, EthType},
     ipv4::Ipv4,
 };
+use crate::device::e1000;
 
 mod arp;
 mod ethernet;
@@ -19,6 +22,10 @@ pub fn handle_receive(bytes: &[u8]) {
     ethernet::handle_receive(bytes);
 }
 
-pub fn init() {
-    udp::init();
+/// Returns the receive statistics of the network device and stack.
+pub fn info() -> NetInfo {
+    NetInfo {
+        rx_dropped: udp::dropped(),
+        ..e1000::info()
+    }
 }
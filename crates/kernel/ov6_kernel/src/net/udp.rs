@@ -1,15 +1,11 @@
 use alloc::{boxed::Box, sync::Arc};
 use core::{
-    alloc::{AllocError, Allocator, Layout},
-    mem::MaybeUninit,
     net::SocketAddrV4,
-    ptr::NonNull,
+    sync::atomic::{AtomicUsize, Ordering},
 };
 
 use arraydeque::{ArrayDeque, Saturating};
 use dataview::{DataView, Pod};
-use once_init::OnceInit;
-use slab_allocator::{ArcInnerLayout, SlabAllocator};
 
 use super::{Eth, EthType, HOST_MAC, Ipv4, LOCAL_IP, LOCAL_MAC, ipv4::IpProtocol};
 use crate::{
@@ -18,6 +14,8 @@ use crate::{
     memory::{
         PAGE_SIZE,
         addr::{GenericMutSlice, GenericSlice},
+        fallible,
+        heap::HeapAllocator,
         page::PageFrameAllocator,
         vm_user::UserPageTable,
     },
@@ -38,26 +36,37 @@ pub(super) fn handle_receive(_eth: &Eth, ipv4: &Ipv4, ipv4_body: &[u8]) {
 
     let ports = PORTS.lock();
     let Some(port) = ports.iter().flatten().find(|p| p.0 == dst_port) else {
+        DROPPED.fetch_add(1, Ordering::Relaxed);
         return;
     };
     let port = Arc::clone(&port.1);
     drop(ports);
 
-    let Ok(data) = Box::try_new_zeroed_in(PageFrameAllocator) else {
+    let Some(mut data) = alloc_buf() else {
+        DROPPED.fetch_add(1, Ordering::Relaxed);
         return;
     };
-    let mut data: Box<[u8; PAGE_SIZE], PageFrameAllocator> = unsafe { data.assume_init() };
     data[..len].copy_from_slice(payload);
 
     let src = SocketAddrV4::new(ipv4.src(), src_port);
-    let _ = port
+    let res = port
         .queue
         .lock()
         .datagrams
         .push_back(Datagram { src, data, len });
+    if let Err(e) = res {
+        DROPPED.fetch_add(1, Ordering::Relaxed);
+        free_buf(e.element.data);
+        return;
+    }
     port.receive.notify();
 }
 
+/// Returns the number of received datagrams that were dropped.
+pub(super) fn dropped() -> usize {
+    DROPPED.load(Ordering::Relaxed)
+}
+
 pub fn bind(port: u16) -> Result<(), KernelError> {
     let mut ports = PORTS.lock();
     if ports.iter().flatten().any(|p| p.0 == port) {
@@ -67,7 +76,8 @@ pub fn bind(port: u16) -> Result<(), KernelError> {
         return Err(KernelError::NoFreePort);
     };
 
-    *p = Some((port, PortRef::new_in(Port::new(true), PortAllocator)));
+    let port_ref = fallible::try_new_arc_in(Port::new(true), HeapAllocator)?;
+    *p = Some((port, port_ref));
 
     Ok(())
 }
@@ -140,10 +150,29 @@ pub fn recv_from(
     let data = port.wait_receive()?;
     let copy_size = usize::min(data.len, bytes.len());
     UserPageTable::copy_k2x_bytes(&mut bytes.take_mut(copy_size), &data.data[..copy_size]);
+    free_buf(data.data);
 
     Ok((copy_size, data.src))
 }
 
+/// Takes a datagram buffer from the pool, or allocates a new one if the pool
+/// is empty.
+fn alloc_buf() -> Option<Buf> {
+    if let Some(buf) = BUF_POOL.lock().iter_mut().find_map(Option::take) {
+        return Some(buf);
+    }
+    let buf = fallible::try_new_zeroed_box_in(PageFrameAllocator).ok()?;
+    Some(unsafe { buf.assume_init() })
+}
+
+/// Returns a datagram buffer to the pool, or frees it if the pool is full.
+fn free_buf(buf: Buf) {
+    let mut pool = BUF_POOL.lock();
+    if let Some(slot) = pool.iter_mut().find(|slot| slot.is_none()) {
+        *slot = Some(buf);
+    }
+}
+
 #[repr(C)]
 #[derive(Debug, Pod)]
 struct Udp {
@@ -227,43 +256,25 @@ impl PortQueue {
     }
 }
 
+type Buf = Box<[u8; PAGE_SIZE], PageFrameAllocator>;
+
 struct Datagram {
     src: SocketAddrV4,
-    data: Box<[u8; PAGE_SIZE], PageFrameAllocator>,
+    data: Buf,
     len: usize,
 }
 
-type PortRef = Arc<Port, PortAllocator>;
-type PortRefLayout = ArcInnerLayout<Port>;
-static PORT_ALLOCATOR: OnceInit<SpinLock<SlabAllocator<PortRefLayout>>> = OnceInit::new();
-
-pub(super) fn init() {
-    static mut PORT_REF_MEMORY: [MaybeUninit<PortRefLayout>; MAX_BIND_PORT] =
-        [const { MaybeUninit::uninit() }; MAX_BIND_PORT];
+/// Number of datagram buffers kept for reuse.
+const BUF_POOL_SIZE: usize = MAX_PORT_MSGS;
 
-    unsafe {
-        let start = (&raw mut PORT_REF_MEMORY[0]).cast::<PortRefLayout>();
-        let end = start.add(MAX_BIND_PORT);
-        let alloc = SlabAllocator::new(start..end);
-        PORT_ALLOCATOR.init(SpinLock::new(alloc));
-    }
-}
+/// Buffers of consumed datagrams, reused for the next received ones instead
+/// of allocating a page per datagram.
+static BUF_POOL: SpinLock<[Option<Buf>; BUF_POOL_SIZE]> =
+    SpinLock::new([const { None }; BUF_POOL_SIZE]);
 
-#[derive(Clone)]
-struct PortAllocator;
+static DROPPED: AtomicUsize = AtomicUsize::new(0);
 
-unsafe impl Allocator for PortAllocator {
-    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
-        assert_eq!(layout, Layout::new::<PortRefLayout>());
-        let Some(ptr) = PORT_ALLOCATOR.get().lock().allocate() else {
-            return Err(AllocError);
-        };
-        Ok(NonNull::slice_from_raw_parts(ptr.cast(), layout.size()))
-    }
-
-    unsafe fn deallocate(&self, ptr: NonNull<u8>, _layout: Layout) {
-        unsafe { PORT_ALLOCATOR.get().lock().deallocate(ptr.cast()) }
-    }
-}
+type PortRef = Arc<Port, HeapAllocator>;
 
-static PORTS: SpinLock<[Option<(u16, PortRef)>; 16]> = SpinLock::new([const { None }; 16]);
+static PORTS: SpinLock<[Option<(u16, PortRef)>; MAX_BIND_PORT]> =
+    SpinLock::new([const { None }; MAX_BIND_PORT]);
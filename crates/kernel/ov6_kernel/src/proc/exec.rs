@@ -1,15 +1,25 @@
+use alloc::{sync::Arc, vec::Vec};
+use core::ops::Range;
+
 use dataview::PodMethods as _;
-use ov6_syscall::UserMutSlice;
+use ov6_syscall::{AccessHint, UserMutSlice};
 use ov6_types::path::Path;
 use safe_cast::{SafeFrom as _, SafeInto as _};
 
-use super::ProcPrivateData;
+use super::{OnInterrupt, ProcPrivateData};
 use crate::{
     error::KernelError,
-    fs::{self, LockedTxInode},
+    fs::{
+        self, InodeGuard, T_FILE,
+        text_cache::{self, Segment, TextPages},
+        vfs::{self, FileOps, OpenFile},
+    },
     memory::{
         PAGE_SIZE, PageRound as _, VirtAddr,
-        addr::{AsGenericSliceOfSlice, GenericSliceOfSlice, Validate as _},
+        addr::{AsGenericSliceOfSlice, GenericMutSlice, GenericSliceOfSlice, Validate as _},
+        fallible,
+        heap::HeapAllocator,
+        page::SharedPage,
         page_table::{MapTarget, PtEntryFlags},
         vm_user::UserPageTable,
     },
@@ -38,6 +48,47 @@ fn flags2perm(flags: u32) -> PtEntryFlags {
     perm
 }
 
+/// A program file read through the file operations of its file system.
+struct Program {
+    ops: &'static dyn FileOps,
+    file: Option<OpenFile>,
+}
+
+impl Program {
+    fn open(ip: InodeGuard) -> Result<Self, KernelError> {
+        if vfs::stat(&ip)?.ty != T_FILE {
+            return Err(KernelError::InvalidExecutable);
+        }
+        let ops = vfs::file_ops(ip.dev())?;
+        let file = OpenFile {
+            inode: ip.into_inner(),
+            off: 0.into(),
+            direct: false,
+        };
+        Ok(Self {
+            ops,
+            file: Some(file),
+        })
+    }
+
+    fn inode(&self) -> &fs::Inode {
+        &self.file.as_ref().unwrap().inode
+    }
+
+    fn read(&self, dst: GenericMutSlice<u8>, off: usize) -> Result<usize, KernelError> {
+        let file = self.file.as_ref().unwrap();
+        self.ops.read(file, dst, Some(off), AccessHint::Normal)
+    }
+}
+
+impl Drop for Program {
+    fn drop(&mut self) {
+        if let Some(file) = self.file.take() {
+            vfs::put(file.inode);
+        }
+    }
+}
+
 fn arg_stack_size(arg_data_size: usize, arg_len: usize) -> Option<usize> {
     let argv_size = arg_len.checked_add(1)?.checked_mul(size_of::<usize>())?;
     let stack_size = argv_size.checked_add(arg_data_size)?;
@@ -59,15 +110,13 @@ where
         .filter(|size| *size <= user_stack_size)
         .ok_or(KernelError::ArgumentListTooLarge)?;
 
-    let tx = fs::begin_tx()?;
-    let cwd = private.cwd().clone().into_tx(&tx);
-    let mut ip = fs::path::resolve(&tx, cwd, path)?;
-    let mut lip = ip.force_wait_lock();
+    let ip = fs::path::resolve(private.root(), private.cwd(), path)?;
+    let program = Program::open(ip)?;
 
     // Check ELF header
     let mut elf = ElfHeader::zero();
 
-    let nread = lip.read(elf.as_bytes_mut().into(), 0)?;
+    let nread = program.read(elf.as_bytes_mut().into(), 0)?;
     if nread != size_of::<ElfHeader>() {
         return Err(KernelError::InvalidExecutable);
     }
@@ -78,15 +127,13 @@ where
     let mut pt = UserPageTable::new(private.pid)?;
 
     // Load program into memory.
-    let segment_end = load_segments(&mut lip, &mut pt, &elf)?;
+    let segment_end = load_segments(&program, &mut pt, &elf)?;
     assert!(segment_end.is_page_aligned());
     let heap_start = segment_end.byte_add(PAGE_SIZE)?.level_page_roundup(1);
 
     pt.set_heap_start(heap_start);
 
-    lip.unlock();
-    ip.put();
-    tx.end();
+    drop(program);
 
     pt.alloc_stack()?;
 
@@ -100,10 +147,20 @@ where
 
     // Save program name for debugging.
     let name = path.file_name().unwrap();
-    p.shared().lock().set_name(name);
+    let mut shared = p.shared().lock();
+    shared.set_name(name);
+    shared.set_mem_pages(pt.user_pages());
+    // The interrupt handler does not exist in the new image.
+    if let OnInterrupt::Catch(_) = shared.on_interrupt() {
+        shared.set_on_interrupt(OnInterrupt::Terminate);
+    }
+    shared.ptrace.stop_on_exec();
+    drop(shared);
 
     // Commit to the user image.
     private.update_pagetable(pt);
+    private.tracee.forget_step_breakpoints();
+    private.close_on_exec();
     let tf = private.trapframe_mut();
     tf.epc = elf.entry.safe_into(); // initial pogram counter = main
     tf.user_registers.sp = sp.addr(); // initial stack pointer
@@ -111,8 +168,8 @@ where
     Ok((argc, argv))
 }
 
-fn load_segments<const READ_ONLY: bool>(
-    lip: &mut LockedTxInode<READ_ONLY>,
+fn load_segments(
+    program: &Program,
     new_pt: &mut UserPageTable,
     elf: &ElfHeader,
 ) -> Result<VirtAddr, KernelError> {
@@ -121,7 +178,7 @@ fn load_segments<const READ_ONLY: bool>(
     for i in 0..elf.phnum {
         let off = usize::safe_from(elf.phoff) + usize::from(i) * size_of::<ProgramHeader>();
         let mut ph = ProgramHeader::zero();
-        lip.read(ph.as_bytes_mut().into(), off)?;
+        program.read(ph.as_bytes_mut().into(), off)?;
         if ph.ty != ELF_PROG_LOAD {
             continue;
         }
@@ -139,6 +196,31 @@ fn load_segments<const READ_ONLY: bool>(
         let map_start = va_start.page_rounddown();
         let map_end = va_end.page_roundup();
         let map_size = map_end.checked_sub(map_start).unwrap();
+
+        // read-only segments are shared between processes, unless they share
+        // pages with other segments.
+        if !perm.contains(PtEntryFlags::W) && !shares_page(program, elf, i, map_start..map_end)? {
+            let segment = Segment {
+                offset: ph.off.safe_into(),
+                vaddr: ph.vaddr.safe_into(),
+                file_size: ph.filesz.safe_into(),
+                mem_size: ph.memsz.safe_into(),
+            };
+            let pages = if let Some(pages) = text_cache::get(program.inode(), &segment) {
+                pages
+            } else {
+                let pages = read_segment_pages(program, &segment, map_start, map_size)?;
+                text_cache::insert(program.inode(), segment, Arc::clone(&pages));
+                pages
+            };
+            for (page, va) in pages.iter().zip((map_start.addr()..).step_by(PAGE_SIZE)) {
+                new_pt.map_shared_page(VirtAddr::new(va)?, page, perm)?;
+            }
+            new_pt.validate(va_start..va_end, perm)?;
+            segment_end = VirtAddr::max(segment_end, map_end);
+            continue;
+        }
+
         unsafe {
             new_pt.map_addrs(
                 map_start,
@@ -153,7 +235,7 @@ fn load_segments<const READ_ONLY: bool>(
         load_segment(
             new_pt,
             va_start,
-            lip,
+            program,
             ph.off.safe_into(),
             ph.filesz.safe_into(),
         )?;
@@ -164,13 +246,79 @@ fn load_segments<const READ_ONLY: bool>(
     Ok(segment_end)
 }
 
+/// Returns `true` if the pages in `map_range` are also used by another
+/// loadable segment than the `idx`-th one.
+fn shares_page(
+    program: &Program,
+    elf: &ElfHeader,
+    idx: u16,
+    map_range: Range<VirtAddr>,
+) -> Result<bool, KernelError> {
+    for i in (0..elf.phnum).filter(|i| *i != idx) {
+        let off = usize::safe_from(elf.phoff) + usize::from(i) * size_of::<ProgramHeader>();
+        let mut ph = ProgramHeader::zero();
+        program.read(ph.as_bytes_mut().into(), off)?;
+        if ph.ty != ELF_PROG_LOAD || ph.memsz == 0 {
+            continue;
+        }
+        let Some(end) = ph.vaddr.checked_add(ph.memsz) else {
+            return Err(KernelError::InvalidExecutable);
+        };
+        let start = VirtAddr::new(ph.vaddr.safe_into())?.page_rounddown();
+        let end = VirtAddr::new(end.safe_into())?.page_roundup();
+        if start < map_range.end && map_range.start < end {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Reads a program segment into new pages.
+///
+/// The pages are mapped from `map_start`, which is the start of the segment
+/// rounded down to the page boundary.
+fn read_segment_pages(
+    program: &Program,
+    segment: &Segment,
+    map_start: VirtAddr,
+    map_size: usize,
+) -> Result<TextPages, KernelError> {
+    let mut pages = Vec::new();
+    if pages.try_reserve_exact(map_size / PAGE_SIZE).is_err() {
+        return Err(KernelError::NoMemory);
+    }
+
+    let file_start = segment.vaddr;
+    let file_end = segment.vaddr + segment.file_size;
+    for page_start in (map_start.addr()..)
+        .step_by(PAGE_SIZE)
+        .take(map_size / PAGE_SIZE)
+    {
+        let mut page = SharedPage::alloc_zeroed()?;
+        let bytes = page.bytes_mut().unwrap();
+        let start = usize::clamp(file_start, page_start, page_start + PAGE_SIZE);
+        let end = usize::clamp(file_end, page_start, page_start + PAGE_SIZE);
+        if start < end {
+            let dst = &mut bytes[start - page_start..end - page_start];
+            let len = dst.len();
+            let nread = program.read(dst.into(), segment.offset + (start - file_start))?;
+            if nread != len {
+                return Err(KernelError::InvalidExecutable);
+            }
+        }
+        pages.push(page);
+    }
+
+    fallible::try_new_arc_in(pages, HeapAllocator)
+}
+
 /// Loads a program segment into pagetable at virtual address `va`.
 ///
 /// `va` must be page-aligned.
-fn load_segment<const READ_ONLY: bool>(
+fn load_segment(
     new_pt: &mut UserPageTable,
     va: VirtAddr,
-    lip: &mut LockedTxInode<READ_ONLY>,
+    program: &Program,
     file_offset: usize,
     file_size: usize,
 ) -> Result<(), KernelError> {
@@ -183,7 +331,7 @@ fn load_segment<const READ_ONLY: bool>(
         if dst_chunk.len() > rest_len {
             dst_chunk = &mut dst_chunk[..rest_len];
         }
-        let nread = lip.read(dst_chunk.into(), file_offset + copied)?;
+        let nread = program.read(dst_chunk.into(), file_offset + copied)?;
         if nread != dst_chunk.len() {
             return Err(KernelError::InvalidExecutable);
         }
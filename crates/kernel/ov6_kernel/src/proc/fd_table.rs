@@ -0,0 +1,140 @@
+//! Per-process file descriptor tables.
+//!
+//! A table starts empty and grows on demand, on the kernel heap, up to
+//! [`NOFILE`] descriptors. Its capacity is doubled on each growth so that
+//! opening many files does not reallocate it each time.
+//!
+//! Each descriptor has its own [`FdFlags`], which are not shared with the
+//! other descriptors of the same file.
+
+use alloc::vec::Vec;
+
+use ov6_syscall::FdFlags;
+use ov6_types::fs::RawFd;
+
+use crate::{error::KernelError, file::File, param::NOFILE};
+
+/// Capacity of a table when it first grows.
+const MIN_CAPACITY: usize = 8;
+
+pub(super) struct FdTable {
+    files: Vec<Option<(File, FdFlags)>>,
+}
+
+impl FdTable {
+    pub(super) const fn new() -> Self {
+        Self { files: Vec::new() }
+    }
+
+    pub(super) fn get(&self, fd: RawFd) -> Option<&File> {
+        self.entry(fd).map(|(file, _flags)| file)
+    }
+
+    fn entry(&self, fd: RawFd) -> Option<&(File, FdFlags)> {
+        self.files.get(fd.get()).and_then(Option::as_ref)
+    }
+
+    pub(super) fn flags(&self, fd: RawFd) -> Option<FdFlags> {
+        self.entry(fd).map(|(_file, flags)| *flags)
+    }
+
+    /// Sets the flags of `fd`.
+    ///
+    /// Returns `None` if `fd` is not open.
+    pub(super) fn set_flags(&mut self, fd: RawFd, flags: FdFlags) -> Option<()> {
+        let (_file, entry_flags) = self.files.get_mut(fd.get())?.as_mut()?;
+        *entry_flags = flags;
+        Some(())
+    }
+
+    /// Makes the table at least `len` descriptors long.
+    fn grow_to(&mut self, len: usize) -> Result<(), KernelError> {
+        if len <= self.files.len() {
+            return Ok(());
+        }
+        if len > NOFILE {
+            return Err(KernelError::NoFreeFileDescriptorTableEntry);
+        }
+        if len > self.files.capacity() {
+            let capacity = usize::max(len, self.files.capacity() * 2).clamp(MIN_CAPACITY, NOFILE);
+            #[expect(clippy::map_err_ignore)]
+            self.files
+                .try_reserve_exact(capacity - self.files.len())
+                .map_err(|_| KernelError::NoMemory)?;
+        }
+        self.files.resize_with(len, || None);
+        Ok(())
+    }
+
+    /// Stores `file` at the lowest free descriptor.
+    pub(super) fn add(&mut self, file: File, flags: FdFlags) -> Result<RawFd, KernelError> {
+        let fd = if let Some(fd) = self.files.iter().position(Option::is_none) {
+            fd
+        } else {
+            let fd = self.files.len();
+            self.grow_to(fd + 1)?;
+            fd
+        };
+        assert!(self.files[fd].replace((file, flags)).is_none());
+        Ok(RawFd::new(fd))
+    }
+
+    /// Stores `file` at `fd`, returning the file previously stored there.
+    pub(super) fn set(
+        &mut self,
+        fd: RawFd,
+        file: File,
+        flags: FdFlags,
+    ) -> Result<Option<File>, KernelError> {
+        self.grow_to(fd.get() + 1)?;
+        let old = self.files[fd.get()].replace((file, flags));
+        Ok(old.map(|(file, _flags)| file))
+    }
+
+    pub(super) fn take(&mut self, fd: RawFd) -> Option<File> {
+        let (file, _flags) = self.files.get_mut(fd.get())?.take()?;
+        Some(file)
+    }
+
+    /// Returns a copy of the table, incrementing the reference counts of the
+    /// open files.
+    ///
+    /// The flags of the descriptors are copied as well.
+    pub(super) fn try_clone(&self) -> Result<Self, KernelError> {
+        let mut files = Vec::new();
+        #[expect(clippy::map_err_ignore)]
+        files
+            .try_reserve_exact(self.files.capacity())
+            .map_err(|_| KernelError::NoMemory)?;
+        files.extend(
+            self.files
+                .iter()
+                .map(|entry| entry.as_ref().map(|(file, flags)| (file.dup(), *flags))),
+        );
+        Ok(Self { files })
+    }
+
+    /// Closes all open files.
+    pub(super) fn close_all(&mut self) {
+        for (file, _flags) in self.files.drain(..).flatten() {
+            file.close();
+        }
+    }
+
+    /// Closes the files whose descriptors have [`FdFlags::CLOEXEC`] set.
+    pub(super) fn close_on_exec(&mut self) {
+        for entry in &mut self.files {
+            if entry
+                .as_ref()
+                .is_some_and(|(_file, flags)| flags.contains(FdFlags::CLOEXEC))
+            {
+                let (file, _flags) = entry.take().unwrap();
+                file.close();
+            }
+        }
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.files.iter().all(Option::is_none)
+    }
+}
@@ -0,0 +1,162 @@
+//! Per-process accounting and throttling of disk I/O.
+//!
+//! Each block transferred between the block cache and the disk is counted
+//! for the process that caused the transfer. A process with an I/O limit has
+//! a token bucket holding up to one second of its limit, and reading or
+//! writing a file takes a token for each block requested. A process that runs
+//! out of tokens sleeps until the bucket refills, which leaves the disk to the
+//! other processes.
+//!
+//! Throttling happens when a read or write is requested, before any file
+//! system lock is taken, so a throttled process never holds up the others
+//! or the log commit. Transfers made outside of any process (e.g. during
+//! boot) are not counted.
+
+use core::time::Duration;
+
+use ov6_syscall::IoStats;
+
+use super::Proc;
+use crate::{
+    fs::FS_BLOCK_SIZE,
+    interrupt::{timer::Uptime, timer_wheel},
+};
+
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+/// Direction of a block transfer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IoDirection {
+    Read,
+    Write,
+}
+
+#[derive(Debug)]
+pub struct IoAccount {
+    blocks_read: u64,
+    blocks_written: u64,
+    throttled: u64,
+    /// Maximum number of blocks per second, or 0 if unlimited.
+    limit: u64,
+    /// Tokens in the bucket, in units of 1/10^9 block.
+    tokens: u128,
+    /// When the bucket was last refilled.
+    refilled_at: Uptime,
+}
+
+impl IoAccount {
+    pub(super) const fn new() -> Self {
+        Self {
+            blocks_read: 0,
+            blocks_written: 0,
+            throttled: 0,
+            limit: 0,
+            tokens: 0,
+            refilled_at: Uptime::ZERO,
+        }
+    }
+
+    /// Returns a new account for a child process, which inherits the limit.
+    pub(super) fn inherit(&self) -> Self {
+        let mut account = Self::new();
+        account.set_limit(self.limit);
+        account
+    }
+
+    /// Sets the maximum number of blocks transferred per second, or removes
+    /// the limit if `limit` is 0.
+    ///
+    /// The bucket starts full.
+    pub fn set_limit(&mut self, limit: u64) {
+        self.limit = limit;
+        self.tokens = self.capacity();
+        self.refilled_at = Uptime::now();
+    }
+
+    pub fn stats(&self) -> IoStats {
+        IoStats {
+            blocks_read: self.blocks_read,
+            blocks_written: self.blocks_written,
+            throttled: self.throttled,
+            limit: self.limit,
+        }
+    }
+
+    fn capacity(&self) -> u128 {
+        u128::from(self.limit) * NANOS_PER_SEC
+    }
+
+    /// Takes the tokens for transferring `blocks` blocks.
+    ///
+    /// A request larger than the bucket takes the whole bucket, so that it
+    /// does not wait forever.
+    ///
+    /// Returns `None` if the transfer may start, or the time to wait for the
+    /// tokens otherwise.
+    fn take(&mut self, blocks: u64, now: Uptime) -> Option<Duration> {
+        if self.limit == 0 {
+            return None;
+        }
+
+        let elapsed = now.saturating_duration_since(self.refilled_at);
+        self.tokens = u128::min(
+            self.capacity(),
+            self.tokens + elapsed.as_nanos() * u128::from(self.limit),
+        );
+        self.refilled_at = now;
+
+        let needed = u128::min(self.capacity(), u128::from(blocks) * NANOS_PER_SEC);
+        if self.tokens < needed {
+            let missing = needed - self.tokens;
+            let nanos = missing.div_ceil(u128::from(self.limit));
+            return Some(Duration::from_nanos(nanos.try_into().unwrap_or(u64::MAX)));
+        }
+        self.tokens -= needed;
+        None
+    }
+}
+
+/// Counts a block transferred between the block cache and the disk for the
+/// current process.
+///
+/// This is called by the disk driver, possibly with file system locks held,
+/// so it never waits.
+pub fn account(dir: IoDirection) {
+    let Some(p) = Proc::try_current() else {
+        return;
+    };
+
+    let mut shared = p.shared().lock();
+    match dir {
+        IoDirection::Read => shared.io.blocks_read += 1,
+        IoDirection::Write => shared.io.blocks_written += 1,
+    }
+}
+
+/// Waits until the current process may read or write `bytes` bytes of a
+/// file, and takes the tokens for them from its I/O limit.
+///
+/// This must be called before taking any file system lock. A killed
+/// process is not kept waiting, so that it can exit.
+pub fn throttle(bytes: usize) {
+    let Some(p) = Proc::try_current() else {
+        return;
+    };
+    let blocks = bytes.div_ceil(FS_BLOCK_SIZE) as u64;
+    if blocks == 0 {
+        return;
+    }
+
+    loop {
+        let mut shared = p.shared().lock();
+        let Some(wait) = shared.io.take(blocks, Uptime::now()) else {
+            return;
+        };
+        shared.io.throttled += 1;
+        drop(shared);
+
+        if timer_wheel::sleep(wait).is_err() {
+            return;
+        }
+    }
+}
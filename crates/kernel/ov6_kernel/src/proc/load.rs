@@ -0,0 +1,80 @@
+//! Load averages and CPU utilization.
+//!
+//! On each timer tick, CPU 0 counts the processes that are runnable or
+//! running, and folds the count into exponentially-decaying averages over the
+//! last 1, 5 and 15 minutes, in the same way as other Unix kernels. Each CPU
+//! counts its ticks as busy or idle by itself.
+
+use ov6_syscall::{LOAD_FSHIFT, LoadInfo, MAX_CPUS};
+use safe_cast::SafeInto as _;
+
+use super::{PROC, ProcState};
+use crate::{
+    cpu,
+    interrupt::timer::{TICKS_PER_SEC, Uptime},
+    param::NCPU,
+    sync::SpinLock,
+};
+
+/// Number of fractional bits of the averages kept by the kernel.
+const FSHIFT: u32 = 32;
+
+/// `1.0` in fixed point.
+const FIXED_1: u64 = 1 << FSHIFT;
+
+/// Decay factor applied on each tick: `exp(-1 / (minutes * 60 * TICKS_PER_SEC))`
+/// in fixed point.
+const EXP: [u64; 3] = [4_287_814_979, 4_293_535_879, 4_294_490_104];
+
+const _: () = assert!(TICKS_PER_SEC == 10, "EXP assumes 10 ticks per second");
+const _: () = assert!(NCPU <= MAX_CPUS);
+
+static AVERAGES: SpinLock<[u64; 3]> = SpinLock::new([0; 3]);
+
+/// Samples the number of runnable and running processes.
+///
+/// Called on each tick by CPU 0.
+pub fn sample() {
+    let n = PROC
+        .iter()
+        .filter(|p| {
+            matches!(
+                p.shared.lock().state,
+                ProcState::Runnable | ProcState::Running
+            )
+        })
+        .count();
+    let n: u64 = n.safe_into();
+    let n = u128::from(n << FSHIFT);
+
+    let mut averages = AVERAGES.lock();
+    for (avg, exp) in averages.iter_mut().zip(EXP) {
+        let exp = u128::from(exp);
+        let new = (u128::from(*avg) * exp + n * (u128::from(FIXED_1) - exp)) >> FSHIFT;
+        *avg = u64::try_from(new).unwrap();
+    }
+}
+
+/// Returns the uptime, the load averages and the CPU utilization.
+pub fn info() -> LoadInfo {
+    let uptime = Uptime::now().saturating_duration_since(Uptime::ZERO);
+    let averages = AVERAGES.lock().map(|avg| avg >> (FSHIFT - LOAD_FSHIFT));
+    let mut busy_ticks = [0; MAX_CPUS];
+    let mut idle_ticks = [0; MAX_CPUS];
+    for (id, (busy, idle)) in busy_ticks
+        .iter_mut()
+        .zip(&mut idle_ticks)
+        .enumerate()
+        .take(cpu::num_cpus())
+    {
+        (*busy, *idle) = cpu::ticks(id);
+    }
+    LoadInfo {
+        uptime_nanos: u64::try_from(uptime.as_nanos()).unwrap(),
+        averages,
+        ticks_per_sec: TICKS_PER_SEC,
+        num_cpus: cpu::num_cpus(),
+        busy_ticks,
+        idle_ticks,
+    }
+}
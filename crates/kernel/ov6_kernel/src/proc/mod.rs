@@ -1,30 +1,39 @@
 use core::{
+    array,
     cell::UnsafeCell,
     mem,
     num::NonZero,
     ops::{Deref, DerefMut},
     panic::Location,
     ptr,
-    sync::atomic::{AtomicBool, AtomicPtr, AtomicU32, Ordering},
+    sync::atomic::{AtomicBool, AtomicPtr, AtomicU32, AtomicUsize, Ordering},
     time::Duration,
 };
 
 use arrayvec::ArrayVec;
 use once_init::OnceInit;
-use ov6_types::{fs::RawFd, os_str::OsStr, process::ProcId};
+use ov6_syscall::{
+    Capabilities, CpuSet, FdFlags, SyscallCode, SyscallFilterAction, SyscallFilterMask, UMASK_BITS,
+    UMASK_DEFAULT, error::SyscallError,
+};
+use ov6_types::{fs::RawFd, os_str::OsStr, path::Path, process::ProcId};
 
 use self::{
+    fd_table::FdTable,
+    io::IoAccount,
+    ptrace::{PtraceState, TraceeData},
     scheduler::Context,
     wait_lock::{Parent, WaitLock},
 };
 use crate::{
+    audit,
     cpu::Cpu,
     error::KernelError,
     file::File,
-    fs::Inode,
+    fs::{self, Inode, TxToken, path::NormalPath},
     interrupt::{
         self,
-        timer::Uptime,
+        timer_wheel::{self, TimerHandle},
         trap::{self, TrapFrame, UserRegisters},
     },
     memory::{
@@ -38,7 +47,12 @@ use crate::{
 
 mod elf;
 pub mod exec;
+mod fd_table;
+pub mod io;
+pub mod load;
+pub mod oom;
 pub mod ops;
+pub mod ptrace;
 pub mod scheduler;
 mod wait_lock;
 
@@ -55,20 +69,62 @@ enum ProcState {
     Zombie { exit_status: i32 },
 }
 
+/// A periodic alarm of a process, driven by a kernel timer.
 #[derive(Debug)]
 pub struct AlarmInfo {
-    time: Uptime,
     dur: Duration,
     handler: VirtAddr,
+    /// Argument of the timer function: the index of the process in the
+    /// process table and the ID of the alarm.
+    timer_arg: usize,
+    timer: TimerHandle,
+    /// The timer has fired and the handler is not called yet.
+    expired: bool,
 }
 
 impl AlarmInfo {
-    pub(crate) fn is_expired(&self, now: Uptime) -> bool {
-        now >= self.time
+    /// Creates an alarm that calls `handler` of `p` every `dur`.
+    pub(crate) fn new(p: &Proc, dur: Duration, handler: VirtAddr) -> Result<Self, KernelError> {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+        let index = PROC.iter().position(|q| ptr::eq(q, p)).unwrap();
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed) % (usize::MAX / NPROC);
+        let timer_arg = id * NPROC + index;
+        let timer = timer_wheel::add(dur, Self::expire, timer_arg)?;
+        Ok(Self {
+            dur,
+            handler,
+            timer_arg,
+            timer,
+            expired: false,
+        })
     }
 
-    pub(crate) fn update(&mut self, now: Uptime) {
-        self.time = now.saturating_add(self.dur);
+    /// Timer function of the alarm.
+    ///
+    /// The alarm may have been replaced since the timer was added, so the
+    /// alarm ID is checked.
+    fn expire(timer_arg: usize) {
+        let p = &PROC[timer_arg % NPROC];
+        let mut shared = p.shared.lock();
+        if let Some(alarm) = &mut shared.alarm {
+            if alarm.timer_arg == timer_arg {
+                alarm.expired = true;
+            }
+        }
+    }
+
+    /// Returns `true` if the alarm has expired since the last call, and
+    /// starts the next period.
+    pub(crate) fn take_expired(&mut self) -> bool {
+        if !mem::take(&mut self.expired) {
+            return false;
+        }
+        // if no timer is available, the alarm stops.
+        if let Ok(timer) = timer_wheel::add(self.dur, Self::expire, self.timer_arg) {
+            self.timer = timer;
+        }
+        true
     }
 
     pub(crate) fn handler(&self) -> VirtAddr {
@@ -76,6 +132,49 @@ impl AlarmInfo {
     }
 }
 
+impl Drop for AlarmInfo {
+    fn drop(&mut self) {
+        timer_wheel::cancel(self.timer);
+    }
+}
+
+/// Action taken when the process receives an interrupt from the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnInterrupt {
+    /// The process is killed.
+    Terminate,
+    /// The interrupt is discarded.
+    Ignore,
+    /// The handler at the address is called, in the same way as an alarm
+    /// handler.
+    Catch(VirtAddr),
+}
+
+/// Per-process system call allowlist.
+#[derive(Debug, Clone, Copy)]
+pub struct SyscallFilter {
+    allowed: SyscallFilterMask,
+    action: SyscallFilterAction,
+}
+
+impl SyscallFilter {
+    /// Returns `true` if `code` passes the filter.
+    ///
+    /// `exit` is always allowed so that a filtered process can terminate.
+    pub fn allows(&self, code: SyscallCode) -> bool {
+        let n = code as usize;
+        code == SyscallCode::Exit
+            || self
+                .allowed
+                .get(n / 64)
+                .is_some_and(|word| word & (1 << (n % 64)) != 0)
+    }
+
+    pub fn action(&self) -> SyscallFilterAction {
+        self.action
+    }
+}
+
 /// Per-process state that can be accessed from other processes.
 pub struct ProcSharedData {
     /// Process ID
@@ -88,6 +187,24 @@ pub struct ProcSharedData {
     killed: bool,
     /// Alarm information
     alarm: Option<AlarmInfo>,
+    /// Process group ID
+    pgid: Option<ProcId>,
+    /// Action taken on an interrupt from the terminal
+    on_interrupt: OnInterrupt,
+    /// An interrupt is caught and its handler is not called yet
+    interrupt_pending: bool,
+    /// Disk I/O accounting
+    io: IoAccount,
+    /// Nice value, from `NICE_MIN` (highest priority) to `NICE_MAX`
+    nice: isize,
+    /// Number of pages of user memory
+    mem_pages: usize,
+    /// CPUs the process is allowed to run on
+    affinity: CpuSet,
+    /// Process runs only in the kernel, and cannot be killed
+    kernel_task: bool,
+    /// Tracing state
+    ptrace: PtraceState,
     /// Process context.
     ///
     /// Call `switch()` here to enter process.
@@ -123,14 +240,73 @@ impl ProcSharedData {
         self.alarm.as_mut()
     }
 
-    pub fn set_alarm(&mut self, dur: Duration, handler: VirtAddr) {
-        let time = Uptime::now().saturating_add(dur);
-        self.alarm = Some(AlarmInfo { time, dur, handler });
+    pub fn set_alarm(&mut self, alarm: AlarmInfo) {
+        self.alarm = Some(alarm);
     }
 
     pub fn clear_alarm(&mut self) {
         self.alarm = None;
     }
+
+    pub fn io_mut(&mut self) -> &mut IoAccount {
+        &mut self.io
+    }
+
+    pub fn pgid(&self) -> ProcId {
+        self.pgid.unwrap()
+    }
+
+    pub fn nice(&self) -> isize {
+        self.nice
+    }
+
+    pub fn set_nice(&mut self, nice: isize) {
+        self.nice = nice;
+    }
+
+    pub fn set_mem_pages(&mut self, pages: usize) {
+        self.mem_pages = pages;
+    }
+
+    pub fn affinity(&self) -> CpuSet {
+        self.affinity
+    }
+
+    pub fn set_affinity(&mut self, affinity: CpuSet) {
+        self.affinity = affinity;
+    }
+
+    pub fn set_pgid(&mut self, pgid: ProcId) {
+        self.pgid = Some(pgid);
+    }
+
+    pub fn on_interrupt(&self) -> OnInterrupt {
+        self.on_interrupt
+    }
+
+    pub fn set_on_interrupt(&mut self, action: OnInterrupt) {
+        self.on_interrupt = action;
+        if action == OnInterrupt::Ignore {
+            self.interrupt_pending = false;
+        }
+    }
+
+    /// Returns `true` if an interrupt is caught and its handler is not
+    /// called yet.
+    pub fn interrupt_pending(&self) -> bool {
+        self.interrupt_pending
+    }
+
+    /// Takes the pending interrupt, returning the handler to be called.
+    pub fn take_interrupt(&mut self) -> Option<VirtAddr> {
+        if !mem::take(&mut self.interrupt_pending) {
+            return None;
+        }
+        match self.on_interrupt {
+            OnInterrupt::Catch(handler) => Some(handler),
+            OnInterrupt::Terminate | OnInterrupt::Ignore => None,
+        }
+    }
 }
 
 pub struct ProcShared(SpinLock<ProcSharedData>);
@@ -143,6 +319,15 @@ impl ProcShared {
             state: ProcState::Unused,
             killed: false,
             alarm: None,
+            pgid: None,
+            on_interrupt: OnInterrupt::Terminate,
+            interrupt_pending: false,
+            io: IoAccount::new(),
+            nice: 0,
+            mem_pages: 0,
+            affinity: CpuSet::ALL,
+            kernel_task: false,
+            ptrace: PtraceState::new(),
             context: Context::zeroed(),
         }))
     }
@@ -180,6 +365,16 @@ pub struct SignalHandlerState {
     context: InterruptedContext,
 }
 
+/// The file system state of a process, borrowed while it runs file system
+/// operations.
+///
+/// `tx_token` is passed to the operations that modify the file system.
+pub struct FsContext<'a> {
+    pub tx_token: &'a mut TxToken,
+    pub root: &'a mut Inode,
+    pub cwd: &'a mut Inode,
+}
+
 pub struct ProcPrivateData {
     pid: ProcId,
     /// Virtual address of kernel stack.
@@ -187,15 +382,34 @@ pub struct ProcPrivateData {
     /// User page table,
     pagetable: UserPageTable,
     /// Open files
-    ofile: [Option<File>; NOFILE],
+    ofile: FdTable,
     /// Current directory
     cwd: Option<Inode>,
+    /// Path of the current directory from the root directory, or `None` if
+    /// the current directory is outside the root directory
+    cwd_path: Option<NormalPath>,
+    /// Root directory used for absolute path resolution
+    root: Option<Inode>,
+    /// Permission to start file system transactions
+    tx_token: TxToken,
+    /// Capabilities held by the process
+    caps: Capabilities,
+    /// System call allowlist
+    syscall_filter: Option<SyscallFilter>,
     /// System call trace mask
     trace_mask: u64,
+    /// File mode creation mask
+    umask: u16,
+    /// Tracing state only the process itself accesses
+    tracee: TraceeData,
     signal_handler_state: Option<SignalHandlerState>,
 }
 
 impl ProcPrivateData {
+    pub fn pid(&self) -> ProcId {
+        self.pid
+    }
+
     pub fn kstack(&self) -> VirtAddr {
         self.kstack
     }
@@ -226,26 +440,57 @@ impl ProcPrivateData {
 
     pub fn ofile(&self, fd: RawFd) -> Result<&File, KernelError> {
         self.ofile
-            .get(fd.get())
-            .and_then(|x| x.as_ref())
+            .get(fd)
             .ok_or(KernelError::FileDescriptorNotFound(fd, self.pid))
     }
 
     pub fn add_ofile(&mut self, file: File) -> Result<RawFd, KernelError> {
-        let (fd, slot) = self
-            .ofile
-            .iter_mut()
-            .enumerate()
-            .find(|(_, slot)| slot.is_none())
-            .ok_or(KernelError::NoFreeFileDescriptorTableEntry)?;
-        assert!(slot.replace(file).is_none());
-        Ok(RawFd::new(fd))
+        self.ofile.add(file, FdFlags::empty())
+    }
+
+    /// Stores `file` at the lowest free descriptor, with `flags`.
+    pub fn add_ofile_with_flags(
+        &mut self,
+        file: File,
+        flags: FdFlags,
+    ) -> Result<RawFd, KernelError> {
+        self.ofile.add(file, flags)
+    }
+
+    /// Stores `file` at `fd` with `flags`, returning the file previously open
+    /// at `fd`.
+    pub fn set_ofile(
+        &mut self,
+        fd: RawFd,
+        file: File,
+        flags: FdFlags,
+    ) -> Result<Option<File>, KernelError> {
+        if fd.get() >= NOFILE {
+            return Err(KernelError::FileDescriptorNotFound(fd, self.pid));
+        }
+        self.ofile.set(fd, file, flags)
+    }
+
+    pub fn ofile_flags(&self, fd: RawFd) -> Result<FdFlags, KernelError> {
+        self.ofile
+            .flags(fd)
+            .ok_or(KernelError::FileDescriptorNotFound(fd, self.pid))
+    }
+
+    pub fn set_ofile_flags(&mut self, fd: RawFd, flags: FdFlags) -> Result<(), KernelError> {
+        self.ofile
+            .set_flags(fd, flags)
+            .ok_or(KernelError::FileDescriptorNotFound(fd, self.pid))
+    }
+
+    /// Closes the files opened with [`FdFlags::CLOEXEC`], on `exec`.
+    pub fn close_on_exec(&mut self) {
+        self.ofile.close_on_exec();
     }
 
     pub fn unset_ofile(&mut self, fd: RawFd) -> Result<File, KernelError> {
         self.ofile
-            .get_mut(fd.get())
-            .and_then(Option::take)
+            .take(fd)
             .ok_or(KernelError::FileDescriptorNotFound(fd, self.pid))
     }
 
@@ -254,8 +499,84 @@ impl ProcPrivateData {
         self.cwd.as_ref().unwrap()
     }
 
-    pub fn update_cwd(&mut self, cwd: Inode) -> Inode {
-        self.cwd.replace(cwd).unwrap()
+    pub fn cwd_path(&self) -> Option<&NormalPath> {
+        self.cwd_path.as_ref()
+    }
+
+    pub fn set_cwd_path(&mut self, path: Option<NormalPath>) {
+        self.cwd_path = path;
+    }
+
+    /// Returns the normalized path of `path` relative to the current
+    /// directory.
+    ///
+    /// Returns `None` if `path` is relative and the current directory is
+    /// outside the root directory.
+    pub fn normalize_path(&self, path: &Path) -> Result<Option<NormalPath>, KernelError> {
+        let root = fs::path::root_path();
+        let base = match &self.cwd_path {
+            Some(cwd) => cwd,
+            None if path.is_absolute() => &root,
+            None => return Ok(None),
+        };
+        fs::path::join_normalized(base, path).map(Some)
+    }
+
+    #[track_caller]
+    pub fn root(&self) -> &Inode {
+        self.root.as_ref().unwrap()
+    }
+
+    /// Borrows the file system state of the process to run a transaction.
+    pub fn fs_context(&mut self) -> FsContext<'_> {
+        FsContext {
+            tx_token: &mut self.tx_token,
+            root: self.root.as_mut().unwrap(),
+            cwd: self.cwd.as_mut().unwrap(),
+        }
+    }
+
+    pub fn caps(&self) -> Capabilities {
+        self.caps
+    }
+
+    /// Drops `caps` from the process permanently.
+    pub fn drop_caps(&mut self, caps: Capabilities) {
+        self.caps.remove(caps);
+    }
+
+    /// Returns `Err` if the process does not hold all of `caps`.
+    ///
+    /// Denied requests are recorded to the audit log.
+    pub fn require_caps(
+        &self,
+        syscall: SyscallCode,
+        caps: Capabilities,
+        path: Option<&Path>,
+    ) -> Result<(), KernelError> {
+        if !self.caps.contains(caps) {
+            audit::record(self.pid, syscall, SyscallError::NotPermitted, path);
+            return Err(KernelError::MissingCapability(caps.difference(self.caps)));
+        }
+        Ok(())
+    }
+
+    pub fn syscall_filter(&self) -> Option<&SyscallFilter> {
+        self.syscall_filter.as_ref()
+    }
+
+    /// Installs a system call filter.
+    ///
+    /// If a filter is already installed, the new filter can only narrow it:
+    /// the allowed set becomes the intersection and the stricter action wins.
+    pub fn add_syscall_filter(&mut self, allowed: SyscallFilterMask, action: SyscallFilterAction) {
+        let filter = self
+            .syscall_filter
+            .map_or(SyscallFilter { allowed, action }, |old| SyscallFilter {
+                allowed: array::from_fn(|i| old.allowed[i] & allowed[i]),
+                action: old.action.max(action),
+            });
+        self.syscall_filter = Some(filter);
     }
 
     pub fn trace_mask(&self) -> u64 {
@@ -266,6 +587,15 @@ impl ProcPrivateData {
         self.trace_mask = mask;
     }
 
+    /// Sets the file mode creation mask and returns the previous one.
+    ///
+    /// Bits outside [`UMASK_BITS`] are ignored. The mask is kept across
+    /// `exec`; files carry no permission bits yet, so it is not applied when
+    /// they are created.
+    pub fn replace_umask(&mut self, mask: u16) -> u16 {
+        mem::replace(&mut self.umask, mask & UMASK_BITS)
+    }
+
     pub fn enter_signal_handler(&mut self, handler: VirtAddr) {
         if self.signal_handler_state.is_some() {
             // already entered
@@ -333,8 +663,9 @@ impl ProcPrivateDataGuard<'_> {
         let private = unsafe { proc.private.get().as_mut().unwrap() }
             .take()
             .unwrap();
-        assert!(private.ofile.iter().all(Option::is_none));
+        assert!(private.ofile.is_empty());
         assert!(private.cwd.is_none());
+        assert!(private.root.is_none());
 
         proc.private_borrowed.store(false, Ordering::Release);
     }
@@ -348,6 +679,9 @@ pub struct Proc {
     parent: Parent,
     /// Condition variable that is notified when a child process ends.
     child_ended: SpinLockCondVar,
+    /// Condition variable that is notified when the tracer resumes this
+    /// process or posts a request to it.
+    ptrace_event: SpinLockCondVar,
     /// `true` if `private` is borrowed.
     private_borrowed: AtomicBool,
     /// Location where `private` is borrowed.
@@ -364,6 +698,7 @@ impl Proc {
             shared: ProcShared::new(),
             parent: Parent::new(),
             child_ended: SpinLockCondVar::new(),
+            ptrace_event: SpinLockCondVar::new(),
             private_borrowed: AtomicBool::new(false),
             borrowed_location: AtomicPtr::new(ptr::from_ref(Location::caller()).cast_mut()),
             private: UnsafeCell::new(None),
@@ -479,6 +814,7 @@ impl Proc {
 
         let pid = Self::allocate_pid();
         shared.pid = Some(pid);
+        shared.pgid = Some(pid);
         shared.state = ProcState::Used;
 
         let res: Result<ProcPrivateData, KernelError> = (|| {
@@ -486,9 +822,16 @@ impl Proc {
                 pid,
                 kstack: layout::kstack(i),
                 pagetable: UserPageTable::new(pid)?,
-                ofile: [const { None }; NOFILE],
+                ofile: FdTable::new(),
                 cwd: None,
+                cwd_path: None,
+                root: None,
+                tx_token: TxToken::new(),
+                caps: Capabilities::empty(),
+                syscall_filter: None,
                 trace_mask: 0,
+                umask: UMASK_DEFAULT,
+                tracee: TraceeData::new(),
                 signal_handler_state: None,
             };
 
@@ -528,6 +871,16 @@ impl Proc {
         shared.pid = None;
         shared.name.clear();
         shared.killed = false;
+        shared.alarm = None;
+        shared.pgid = None;
+        shared.on_interrupt = OnInterrupt::Terminate;
+        shared.interrupt_pending = false;
+        shared.io = IoAccount::new();
+        shared.nice = 0;
+        shared.mem_pages = 0;
+        shared.affinity = CpuSet::ALL;
+        shared.kernel_task = false;
+        shared.ptrace = PtraceState::new();
 
         shared.state = ProcState::Unused;
     }
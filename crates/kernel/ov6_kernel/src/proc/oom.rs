@@ -0,0 +1,147 @@
+//! Out-of-memory killer.
+//!
+//! A user page that must be allocated without a way to report the failure,
+//! such as the copy of a copy-on-write page, is not given up when the
+//! allocator and the caches run out of pages. Instead, a victim process is
+//! killed and its memory is used to retry the allocation.
+//!
+//! The victim is the process with the highest badness score: its pages of
+//! user memory, weighted by its nice value so that processes with a lower
+//! priority are chosen first. The init process is never chosen.
+
+use core::{
+    ptr,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use ov6_syscall::NICE_MIN;
+use ov6_types::process::ProcId;
+
+use super::{INIT_PROC, PROC, Proc, ProcState, scheduler};
+use crate::{error::KernelError, println};
+
+/// Number of processes killed by the OOM killer.
+static KILLS: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns the number of processes killed by the OOM killer.
+pub fn kills() -> usize {
+    KILLS.load(Ordering::Relaxed)
+}
+
+/// Runs `f`, killing a victim and retrying while it fails for lack of pages.
+///
+/// Returns the error of `f` if no victim other than `p` is found. In that
+/// case `p` may have been killed.
+pub fn retry<T, F>(p: &Proc, mut f: F) -> Result<T, KernelError>
+where
+    F: FnMut() -> Result<T, KernelError>,
+{
+    loop {
+        match f() {
+            Err(KernelError::NoFreePage) if kill_victim(p) => {}
+            res => return res,
+        }
+    }
+}
+
+/// Returns the badness score of a process.
+///
+/// The pages of the process are weighted from 1 for the highest priority to
+/// 40 for the lowest.
+fn badness(mem_pages: usize, nice: isize) -> usize {
+    let weight = (nice - NICE_MIN + 1).cast_unsigned();
+    mem_pages.saturating_mul(weight)
+}
+
+struct Victim {
+    proc: &'static Proc,
+    pid: ProcId,
+    /// The process is already killed, and is releasing its memory.
+    dying: bool,
+}
+
+fn select_victim() -> Option<Victim> {
+    let init_proc = *INIT_PROC.get();
+    let mut victim = None;
+    let mut max_badness = 0;
+    for p in &PROC {
+        if ptr::eq(p, init_proc) {
+            continue;
+        }
+        let shared = p.shared.lock();
+        if matches!(shared.state, ProcState::Unused | ProcState::Zombie { .. })
+            || shared.kernel_task
+        {
+            continue;
+        }
+        let pid = shared.pid.unwrap();
+        if shared.killed {
+            // waiting for it is enough, rather than killing another one.
+            return Some(Victim {
+                proc: p,
+                pid,
+                dying: true,
+            });
+        }
+        let badness = badness(shared.mem_pages, shared.nice);
+        if badness > max_badness {
+            max_badness = badness;
+            victim = Some(Victim {
+                proc: p,
+                pid,
+                dying: false,
+            });
+        }
+    }
+    victim
+}
+
+/// Kills a victim process to free memory for `p`.
+///
+/// Returns `true` if the victim is another process and has released its
+/// memory, so that the allocation can be retried.
+fn kill_victim(p: &Proc) -> bool {
+    if p.shared.lock().killed {
+        return false;
+    }
+    let Some(victim) = select_victim() else {
+        return false;
+    };
+
+    if !victim.dying {
+        let mut shared = victim.proc.shared.lock();
+        if shared.pid != Some(victim.pid) {
+            // exited in the meantime.
+            return true;
+        }
+        let pid = victim.pid;
+        let name = shared.name().display();
+        let pages = shared.mem_pages;
+        let nice = shared.nice;
+        let badness = badness(pages, nice);
+        println!("oom: killed pid={pid} name={name} pages={pages} nice={nice} badness={badness}");
+        shared.killed = true;
+        if let ProcState::Sleeping { .. } = shared.state {
+            // Wake process from sleep().
+            shared.state = ProcState::Runnable;
+        }
+        KILLS.fetch_add(1, Ordering::Relaxed);
+    }
+
+    if ptr::eq(victim.proc, p) {
+        return false;
+    }
+
+    // the memory of the victim is released before it becomes a zombie.
+    loop {
+        let shared = victim.proc.shared.lock();
+        if shared.pid != Some(victim.pid) || matches!(shared.state, ProcState::Zombie { .. }) {
+            return true;
+        }
+        drop(shared);
+        if p.shared.lock().killed {
+            return false;
+        }
+        scheduler::yield_(p);
+    }
+}
@@ -1,17 +1,20 @@
 use core::{cmp, ptr};
 
-use ov6_syscall::{RegisterValue as _, ReturnType, WaitTarget, syscall as sys};
+use ov6_syscall::{
+    Capabilities, CpuSet, IoStats, RegisterValue as _, ReturnType, WaitTarget, syscall as sys,
+};
 use ov6_types::{os_str::OsStr, path::Path, process::ProcId};
 
 use super::{PROC, ProcPrivateData, ProcPrivateDataGuard, ProcShared, WaitLock};
 use crate::{
     cpu,
     error::KernelError,
-    fs::{self, DeviceNo, Inode, TxInode},
-    interrupt::{clic, trap},
+    fs::{self, DeviceNo, vfs},
+    interrupt::{self, clic, trap},
     memory::page_table::PtEntryFlags,
     println,
-    proc::{INIT_PROC, Proc, ProcState, scheduler, wait_lock},
+    proc::{INIT_PROC, OnInterrupt, Proc, ProcState, scheduler, wait_lock},
+    shutdown,
     sync::{SpinLockCondVar, SpinLockGuard, WaitError},
     syscall::ReturnValue,
 };
@@ -24,9 +27,8 @@ pub fn spawn_init() {
 
     shared.context.ra = forkret_init as usize;
 
-    let tx = fs::begin_readonly_tx();
-    private.cwd = Some(Inode::from_tx(&TxInode::root(&tx)));
-    tx.end();
+    private.cwd_path = Some(fs::path::root_path());
+    private.caps = Capabilities::all();
     shared.set_name(OsStr::new("spawn_init"));
     shared.state = ProcState::Runnable;
 
@@ -48,6 +50,10 @@ extern "C" fn forkret_init() {
     // regular process (e.g., because it calls sleep), and thus cannot
     // be run from main().
     fs::init_in_proc(DeviceNo::ROOT);
+    // the root directory can be looked up once the root file system is
+    // mounted.
+    private.cwd = Some(vfs::root_dir().unwrap());
+    private.root = Some(vfs::root_dir().unwrap());
 
     let argv: &[&[u8]] = &[b"/init"];
     let arg_data_size = argv.iter().map(|arg| arg.len() + 1).sum();
@@ -61,8 +67,47 @@ extern "C" fn forkret_init() {
     trap::trap_user_ret(private);
 }
 
+/// A task run by a process that stays in the kernel.
+pub trait KernelTask {
+    /// Name of the process.
+    const NAME: &'static str;
+
+    /// Runs the task in the context of the process.
+    fn run() -> !;
+}
+
+/// Creates a process running the kernel task `T`.
+///
+/// The process has no parent and cannot be killed.
+pub fn spawn_kernel_task<T>() -> Result<ProcId, KernelError>
+where
+    T: KernelTask,
+{
+    let (_p, mut shared, _private) = Proc::allocate()?;
+    shared.context.ra = kernel_task_ret::<T> as usize;
+    shared.kernel_task = true;
+    shared.set_name(OsStr::new(T::NAME));
+    shared.state = ProcState::Runnable;
+    Ok(shared.pid())
+}
+
+extern "C" fn kernel_task_ret<T>()
+where
+    T: KernelTask,
+{
+    // Still holding `p->shared` from `scheduler()`.
+    let p = Proc::current();
+    let _ = unsafe { p.shared.remember_locked() }; // unlock here
+
+    T::run();
+}
+
 /// Grows user memory by `n` Bytes.
-pub fn resize_by(private: &mut ProcPrivateData, increment: isize) -> Result<(), KernelError> {
+pub fn resize_by(
+    p: &Proc,
+    private: &mut ProcPrivateData,
+    increment: isize,
+) -> Result<(), KernelError> {
     let pagetable = private.pagetable_mut();
     let amt = increment.saturating_abs().cast_unsigned();
     match increment.cmp(&0) {
@@ -70,6 +115,7 @@ pub fn resize_by(private: &mut ProcPrivateData, increment: isize) -> Result<(),
         cmp::Ordering::Equal => {}
         cmp::Ordering::Greater => pagetable.grow_heap_by(amt, PtEntryFlags::URW)?,
     }
+    p.shared().lock().set_mem_pages(pagetable.user_pages());
     Ok(())
 }
 
@@ -77,7 +123,21 @@ pub fn resize_by(private: &mut ProcPrivateData, increment: isize) -> Result<(),
 ///
 /// Sets up child kernel stack to return as if from `fork()` system call.
 pub fn fork(p: &'static Proc, p_private: &mut ProcPrivateData) -> Result<ProcId, KernelError> {
-    let parent_name = p.shared().lock().name.clone();
+    if shutdown::is_shutting_down() {
+        return Err(KernelError::ShuttingDown);
+    }
+
+    let (parent_name, parent_pgid, parent_on_interrupt, child_io, parent_nice, parent_affinity) = {
+        let shared = p.shared().lock();
+        (
+            shared.name.clone(),
+            shared.pgid,
+            shared.on_interrupt,
+            shared.io.inherit(),
+            shared.nice,
+            shared.affinity,
+        )
+    };
 
     // Allocate process.
     let (np, mut np_shared, mut np_private) = Proc::allocate()?;
@@ -92,6 +152,16 @@ pub fn fork(p: &'static Proc, p_private: &mut ProcPrivateData) -> Result<ProcId,
         return Err(e);
     }
 
+    // increment refereence counts on open file descriptors.
+    match p_private.ofile.try_clone() {
+        Ok(ofile) => np_private.ofile = ofile,
+        Err(e) => {
+            np.free(&mut np_shared);
+            drop(np_shared);
+            return Err(e);
+        }
+    }
+
     // Copy saved user registers.
     let np_tf = np_private.trapframe_mut();
     *np_tf = *p_private.trapframe();
@@ -100,15 +170,20 @@ pub fn fork(p: &'static Proc, p_private: &mut ProcPrivateData) -> Result<ProcId,
     let child_ret: ReturnType<sys::Fork> = Ok(None);
     ReturnValue::from(child_ret.encode()).store(np_tf);
 
-    // increment refereence counts on open file descriptors.
-    for (of, nof) in p_private.ofile.iter().zip(&mut np_private.ofile) {
-        if let Some(of) = of {
-            *nof = Some(of.dup());
-        }
-    }
     np_private.cwd.clone_from(&p_private.cwd);
+    np_private.cwd_path.clone_from(&p_private.cwd_path);
+    np_private.root.clone_from(&p_private.root);
+    np_private.caps = p_private.caps;
+    np_private.syscall_filter = p_private.syscall_filter;
     np_private.trace_mask = p_private.trace_mask;
+    np_private.umask = p_private.umask;
     np_shared.name = parent_name;
+    np_shared.pgid = parent_pgid;
+    np_shared.on_interrupt = parent_on_interrupt;
+    np_shared.io = child_io;
+    np_shared.nice = parent_nice;
+    np_shared.affinity = parent_affinity;
+    np_shared.mem_pages = np_private.pagetable().user_pages();
 
     let pid = np_shared.pid.unwrap();
     drop(np_shared);
@@ -133,6 +208,10 @@ fn reparent(old_parent: &Proc, new_parent: &'static Proc, wait_lock: &mut SpinLo
     for pp in &PROC {
         if pp.is_child_of(old_parent, wait_lock) {
             pp.set_parent(new_parent, wait_lock);
+            // init does not trace the orphans.
+            if pp.shared.lock().ptrace.detach() {
+                pp.ptrace_event.notify();
+            }
             new_parent.child_ended.notify();
         }
     }
@@ -154,15 +233,10 @@ pub fn exit(p: &Proc, mut p_private: ProcPrivateDataGuard, status: i32) -> ! {
         assert!(!ptr::eq(p, init_proc), "init exiting");
 
         // Close all open files.
-        for of in &mut p_private.ofile {
-            if let Some(of) = of.take() {
-                of.close();
-            }
-        }
+        p_private.ofile.close_all();
 
-        let tx = fs::force_begin_tx();
-        p_private.cwd.take().unwrap().into_tx(&tx).put();
-        tx.end();
+        vfs::put(p_private.cwd.take().unwrap());
+        vfs::put(p_private.root.take().unwrap());
 
         let mut wait_lock = wait_lock::lock();
 
@@ -363,6 +437,9 @@ pub fn kill(pid: ProcId) -> Result<(), KernelError> {
     for p in &PROC {
         let mut shared = p.shared.lock();
         if shared.pid == Some(pid) {
+            if shared.kernel_task {
+                return Err(KernelError::KillKernelTask(pid));
+            }
             shared.killed = true;
             if let ProcState::Sleeping { .. } = shared.state {
                 // Wake process from sleep().
@@ -376,6 +453,117 @@ pub fn kill(pid: ProcId) -> Result<(), KernelError> {
     Err(KernelError::ProcessNotFound(pid))
 }
 
+/// Sends an interrupt from the terminal to the processes in the group `pgid`.
+///
+/// Each process takes the action it registered: it is killed, ignores the
+/// interrupt, or calls its handler when it returns to user space.
+pub fn interrupt_group(pgid: ProcId) {
+    for p in &PROC {
+        let mut shared = p.shared.lock();
+        if shared.pgid != Some(pgid) {
+            continue;
+        }
+        match shared.on_interrupt {
+            OnInterrupt::Terminate => {
+                shared.killed = true;
+                if let ProcState::Sleeping { .. } = shared.state {
+                    // Wake process from sleep().
+                    shared.state = ProcState::Runnable;
+                }
+            }
+            OnInterrupt::Ignore => {}
+            OnInterrupt::Catch(_) => shared.interrupt_pending = true,
+        }
+    }
+}
+
+/// Moves the process `pid` to the process group `pgid`.
+///
+/// `pid` must be the caller or one of its children. `pgid` must be `pid`,
+/// which makes the process the leader of a new group, or an existing group.
+pub fn set_process_group(p: &'static Proc, pid: ProcId, pgid: ProcId) -> Result<(), KernelError> {
+    let target = if p.shared().lock().pid == Some(pid) {
+        p
+    } else {
+        let mut wait_lock = wait_lock::lock();
+        let target = PROC
+            .iter()
+            .find(|pp| pp.shared.lock().pid == Some(pid) && pp.is_child_of(p, &mut wait_lock))
+            .ok_or(KernelError::ProcessNotFound(pid))?;
+        drop(wait_lock);
+        target
+    };
+
+    if pgid != pid && !PROC.iter().any(|pp| pp.shared.lock().pgid == Some(pgid)) {
+        return Err(KernelError::ProcessGroupNotFound(pgid));
+    }
+
+    let mut shared = target.shared.lock();
+    if shared.pid != Some(pid) {
+        // exited and freed in the meantime.
+        return Err(KernelError::ProcessNotFound(pid));
+    }
+    shared.set_pgid(pgid);
+    Ok(())
+}
+
+/// Returns the process group of the process `pid`.
+pub fn process_group(pid: ProcId) -> Result<ProcId, KernelError> {
+    PROC.iter()
+        .find_map(|p| {
+            let shared = p.shared.lock();
+            (shared.pid == Some(pid)).then(|| shared.pgid())
+        })
+        .ok_or(KernelError::ProcessNotFound(pid))
+}
+
+/// Restricts the process `pid` to the CPUs in `affinity`.
+///
+/// CPUs that are not online are dropped from the set. If `p` itself is
+/// moved off the current CPU, it gives up the CPU so that an allowed one
+/// picks it up.
+pub fn set_affinity(p: &Proc, pid: ProcId, affinity: CpuSet) -> Result<(), KernelError> {
+    let online = affinity.intersection(CpuSet::first(cpu::num_cpus()));
+    if online.is_empty() {
+        return Err(KernelError::InvalidCpuSet(affinity.bits()));
+    }
+    let affinity = online;
+
+    for pp in &PROC {
+        let mut shared = pp.shared.lock();
+        if shared.pid == Some(pid) {
+            shared.set_affinity(affinity);
+            drop(shared);
+            if ptr::eq(pp, p) && !affinity.contains(interrupt::with_push_disabled(cpu::id)) {
+                scheduler::yield_(p);
+            }
+            return Ok(());
+        }
+        drop(shared);
+    }
+    Err(KernelError::ProcessNotFound(pid))
+}
+
+/// Returns the CPUs the process `pid` is allowed to run on.
+pub fn affinity(pid: ProcId) -> Result<CpuSet, KernelError> {
+    PROC.iter()
+        .find_map(|p| {
+            let shared = p.shared.lock();
+            (shared.pid == Some(pid)).then(|| shared.affinity())
+        })
+        .ok_or(KernelError::ProcessNotFound(pid))
+}
+
+/// Returns the disk I/O statistics of the process `pid`.
+pub fn io_stats(pid: ProcId) -> Result<IoStats, KernelError> {
+    PROC.iter()
+        .find_map(|p| {
+            let shared = p.shared.lock();
+            (shared.pid == Some(pid)).then(|| shared.io.stats())
+        })
+        .ok_or(KernelError::ProcessNotFound(pid))
+}
+
 /// Prints a process listing to console.
 ///
 /// For debugging.
@@ -0,0 +1,617 @@
+//! Process tracing.
+//!
+//! A traced process stops when it is about to return to user space after an
+//! event its tracer (the parent) asked for, and sleeps until the tracer
+//! resumes it. While the process is stopped, the tracer reads and writes its
+//! registers and memory. The private data of the stopped process stays
+//! borrowed by the process itself, so these requests are posted to it and run
+//! in its context.
+//!
+//! Single-stepping replaces the instructions that may run next by `c.ebreak`,
+//! and restores them when the process traps again.
+
+use core::arch::asm;
+
+use arrayvec::ArrayVec;
+use ov6_syscall::{PtraceRegs, PtraceRequest, PtraceStop};
+use ov6_types::process::ProcId;
+
+use super::{PROC, Proc, ProcPrivateData, ProcState, WaitLock, wait_lock};
+use crate::{
+    error::KernelError,
+    memory::{VirtAddr, page_table::PtEntryFlags, vm_user::UserPageTable},
+    sync::{SpinLockGuard, WaitError},
+};
+
+/// Encoding of the `c.ebreak` instruction.
+const C_EBREAK: u16 = 0x9002;
+
+const WORD_SIZE: usize = size_of::<usize>();
+
+/// How a stopped process is resumed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Resume {
+    /// Runs until the next breakpoint or `exec`.
+    Continue,
+    /// Also stops at the entry and exit of each system call.
+    Syscall,
+    /// Stops after a single instruction.
+    Step,
+}
+
+/// A request of the tracer, run by the stopped process.
+///
+/// The registers are exchanged through [`PtraceState::regs`].
+#[derive(Debug, Clone, Copy)]
+enum Command {
+    GetRegs,
+    SetRegs,
+    PeekData(usize),
+    PokeData(usize, usize),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Reply {
+    Word(usize),
+    Done,
+}
+
+/// Tracing state of a process that the tracer accesses.
+pub(super) struct PtraceState {
+    /// Process is traced by its parent
+    traced: bool,
+    /// Stop taken when the process next returns to user space
+    pending_stop: Option<PtraceStop>,
+    /// Reason of the current stop, or `None` if the process is running
+    stop: Option<PtraceStop>,
+    /// How the process runs after the current stop
+    resume: Resume,
+    /// Request of the tracer to be run by the stopped process
+    command: Option<Command>,
+    /// Result of the last request
+    reply: Option<Result<Reply, KernelError>>,
+    /// Registers read by `GetRegs` or to be written by `SetRegs`
+    regs: PtraceRegs,
+}
+
+impl PtraceState {
+    pub(super) const fn new() -> Self {
+        Self {
+            traced: false,
+            pending_stop: None,
+            stop: None,
+            resume: Resume::Continue,
+            command: None,
+            reply: None,
+            regs: PtraceRegs::zeroed(),
+        }
+    }
+
+    /// Stops tracing, resuming the process if it is stopped.
+    ///
+    /// Returns `true` if the process was traced.
+    pub(super) fn detach(&mut self) -> bool {
+        let traced = self.traced;
+        *self = Self::new();
+        traced
+    }
+
+    /// Requests a stop after the image of the process is replaced by `exec`.
+    pub(super) fn stop_on_exec(&mut self) {
+        if self.traced {
+            self.pending_stop = Some(PtraceStop::Exec);
+        }
+    }
+}
+
+/// Tracing state of a process that only the process itself accesses.
+pub(super) struct TraceeData {
+    /// Instructions replaced by `c.ebreak` to stop after a single step
+    step_breakpoints: ArrayVec<(VirtAddr, u16), 2>,
+    /// Program text was modified, and the instruction cache must be
+    /// synchronized before returning to user space
+    text_modified: bool,
+}
+
+impl TraceeData {
+    pub(super) const fn new() -> Self {
+        Self {
+            step_breakpoints: ArrayVec::new_const(),
+            text_modified: false,
+        }
+    }
+
+    /// Forgets the step breakpoints, which went away with the old image.
+    pub(super) fn forget_step_breakpoints(&mut self) {
+        self.step_breakpoints.clear();
+    }
+
+    fn insert_step_breakpoints(&mut self, pt: &mut UserPageTable) {
+        for pc in next_pcs(pt) {
+            if self.step_breakpoints.iter().any(|(va, _)| va.addr() == pc) {
+                continue;
+            }
+            // if the next instruction is not mapped, the step ends with a
+            // page fault instead.
+            let Ok(va) = VirtAddr::new(pc) else {
+                continue;
+            };
+            let Ok(chunk) = pt.fetch_chunk_private_mut(va) else {
+                continue;
+            };
+            let insn = &mut chunk[..size_of::<u16>()];
+            let orig = u16::from_le_bytes(insn.try_into().unwrap());
+            insn.copy_from_slice(&C_EBREAK.to_le_bytes());
+            self.step_breakpoints.push((va, orig));
+            self.text_modified = true;
+        }
+    }
+
+    /// Restores the instructions replaced by the step breakpoints, and returns
+    /// their addresses.
+    fn remove_step_breakpoints(&mut self, pt: &mut UserPageTable) -> ArrayVec<VirtAddr, 2> {
+        let mut removed = ArrayVec::new();
+        while let Some((va, orig)) = self.step_breakpoints.pop() {
+            if let Ok(chunk) = pt.fetch_chunk_private_mut(va) {
+                chunk[..size_of::<u16>()].copy_from_slice(&orig.to_le_bytes());
+            }
+            removed.push(va);
+        }
+        removed
+    }
+}
+
+impl Command {
+    fn run(
+        self,
+        private: &mut ProcPrivateData,
+        regs: &mut PtraceRegs,
+    ) -> Result<Reply, KernelError> {
+        match self {
+            Self::GetRegs => {
+                let tf = private.trapframe();
+                regs.pc = tf.epc;
+                for (i, x) in regs.x.iter_mut().enumerate() {
+                    *x = tf.user_registers.get(i);
+                }
+                Ok(Reply::Done)
+            }
+            Self::SetRegs => {
+                let tf = private.trapframe_mut();
+                tf.epc = regs.pc;
+                for (i, x) in regs.x.into_iter().enumerate() {
+                    tf.user_registers.set(i, x);
+                }
+                Ok(Reply::Done)
+            }
+            Self::PeekData(addr) => {
+                let va = word_addr(addr)?;
+                let chunk = private.pagetable().fetch_chunk(va, PtEntryFlags::U)?;
+                let word = usize::from_ne_bytes(chunk[..WORD_SIZE].try_into().unwrap());
+                Ok(Reply::Word(word))
+            }
+            Self::PokeData(addr, data) => {
+                let va = word_addr(addr)?;
+                let chunk = private.pagetable.fetch_chunk_private_mut(va)?;
+                chunk[..WORD_SIZE].copy_from_slice(&data.to_ne_bytes());
+                private.tracee.text_modified = true;
+                Ok(Reply::Done)
+            }
+        }
+    }
+}
+
+fn word_addr(addr: usize) -> Result<VirtAddr, KernelError> {
+    if !addr.is_multiple_of(WORD_SIZE) {
+        return Err(KernelError::MisalignedUserPointer(addr, WORD_SIZE));
+    }
+    VirtAddr::new(addr)
+}
+
+fn read_u16(pt: &UserPageTable, addr: usize) -> Option<u16> {
+    let va = VirtAddr::new(addr).ok()?;
+    let chunk = pt.fetch_chunk(va, PtEntryFlags::U).ok()?;
+    let bytes = chunk.get(..size_of::<u16>())?;
+    Some(u16::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+fn sign_extend(value: usize, bits: u32) -> isize {
+    let shift = usize::BITS - bits;
+    (value << shift).cast_signed() >> shift
+}
+
+/// Returns the addresses of the instructions that may run after the one at
+/// the program counter.
+///
+/// Both targets of a conditional branch are returned, so the condition need
+/// not be evaluated.
+fn next_pcs(pt: &UserPageTable) -> ArrayVec<usize, 2> {
+    let tf = pt.trapframe();
+    let pc = tf.epc;
+    let reg = |i: usize| tf.user_registers.get(i);
+    let mut pcs = ArrayVec::new();
+
+    let Some(lo) = read_u16(pt, pc) else {
+        return pcs;
+    };
+
+    if lo & 0b11 != 0b11 {
+        // compressed instruction
+        let insn = usize::from(lo);
+        let rs1 = (insn >> 7) & 0x1f;
+        let rs2 = (insn >> 2) & 0x1f;
+        match (insn & 0b11, insn >> 13) {
+            // C.J
+            (0b01, 0b101) => {
+                let imm = (((insn >> 12) & 1) << 11)
+                    | (((insn >> 11) & 1) << 4)
+                    | (((insn >> 9) & 0b11) << 8)
+                    | (((insn >> 8) & 1) << 10)
+                    | (((insn >> 7) & 1) << 6)
+                    | (((insn >> 6) & 1) << 7)
+                    | (((insn >> 3) & 0b111) << 1)
+                    | (((insn >> 2) & 1) << 5);
+                pcs.push(pc.wrapping_add_signed(sign_extend(imm, 12)));
+                return pcs;
+            }
+            // C.BEQZ, C.BNEZ
+            (0b01, 0b110 | 0b111) => {
+                let imm = (((insn >> 12) & 1) << 8)
+                    | (((insn >> 10) & 0b11) << 3)
+                    | (((insn >> 5) & 0b11) << 6)
+                    | (((insn >> 3) & 0b11) << 1)
+                    | (((insn >> 2) & 1) << 5);
+                pcs.push(pc.wrapping_add_signed(sign_extend(imm, 9)));
+            }
+            // C.JR, C.JALR (C.EBREAK has rs1 = 0)
+            (0b10, 0b100) if rs1 != 0 && rs2 == 0 => {
+                pcs.push(reg(rs1) & !1);
+                return pcs;
+            }
+            _ => {}
+        }
+        pcs.push(pc.wrapping_add(2));
+        return pcs;
+    }
+
+    let Some(hi) = read_u16(pt, pc.wrapping_add(2)) else {
+        return pcs;
+    };
+    let insn = usize::from(lo) | (usize::from(hi) << 16);
+    let rs1 = (insn >> 15) & 0x1f;
+    match insn & 0x7f {
+        // JAL
+        0x6f => {
+            let imm = (((insn >> 31) & 1) << 20)
+                | (((insn >> 21) & 0x3ff) << 1)
+                | (((insn >> 20) & 1) << 11)
+                | (((insn >> 12) & 0xff) << 12);
+            pcs.push(pc.wrapping_add_signed(sign_extend(imm, 21)));
+            return pcs;
+        }
+        // JALR
+        0x67 => {
+            let imm = sign_extend(insn >> 20, 12);
+            pcs.push(reg(rs1).wrapping_add_signed(imm) & !1);
+            return pcs;
+        }
+        // BEQ, BNE, BLT, BGE, BLTU, BGEU
+        0x63 => {
+            let imm = (((insn >> 31) & 1) << 12)
+                | (((insn >> 25) & 0x3f) << 5)
+                | (((insn >> 8) & 0xf) << 1)
+                | (((insn >> 7) & 1) << 11);
+            pcs.push(pc.wrapping_add_signed(sign_extend(imm, 13)));
+        }
+        _ => {}
+    }
+    pcs.push(pc.wrapping_add(4));
+    pcs
+}
+
+fn notify_tracer(p: &Proc, wait_lock: &mut SpinLockGuard<WaitLock>) {
+    if let Some(parent) = p.parent.get(wait_lock) {
+        parent.child_ended.notify();
+    }
+}
+
+/// Stops the current process, and sleeps until the tracer resumes it.
+///
+/// Requests of the tracer are run while the process is stopped.
+/// The stop ends early if the process is killed.
+fn stop(p: &Proc, private: &mut ProcPrivateData, reason: PtraceStop) {
+    private
+        .tracee
+        .remove_step_breakpoints(&mut private.pagetable);
+
+    let mut wait_lock = wait_lock::lock();
+    {
+        let mut shared = p.shared.lock();
+        if !shared.ptrace.traced || shared.killed() {
+            return;
+        }
+        shared.ptrace.stop = Some(reason);
+    }
+    notify_tracer(p, &mut wait_lock);
+
+    loop {
+        let mut shared = p.shared.lock();
+        if shared.ptrace.stop.is_none() {
+            break;
+        }
+        if let Some(command) = shared.ptrace.command.take() {
+            let mut regs = shared.ptrace.regs;
+            drop(shared);
+            drop(wait_lock);
+            let reply = command.run(private, &mut regs);
+            wait_lock = wait_lock::lock();
+            let mut shared = p.shared.lock();
+            shared.ptrace.regs = regs;
+            shared.ptrace.reply = Some(reply);
+            drop(shared);
+            notify_tracer(p, &mut wait_lock);
+            continue;
+        }
+        drop(shared);
+
+        wait_lock = match p.ptrace_event.wait(wait_lock) {
+            Ok(wait_lock) => wait_lock,
+            Err((mut wait_lock, WaitError::WaitingProcessAlreadyKilled)) => {
+                let mut shared = p.shared.lock();
+                shared.ptrace.stop = None;
+                shared.ptrace.command = None;
+                drop(shared);
+                notify_tracer(p, &mut wait_lock);
+                return;
+            }
+        };
+    }
+
+    let step = p.shared.lock().ptrace.resume == Resume::Step;
+    drop(wait_lock);
+
+    if step {
+        private
+            .tracee
+            .insert_step_breakpoints(&mut private.pagetable);
+    }
+}
+
+/// Takes the stop requested while the process was running, such as by an
+/// attach or an `exec`.
+pub fn handle_pending_stop(p: &Proc, private: &mut ProcPrivateData) {
+    let reason = p.shared.lock().ptrace.pending_stop.take();
+    if let Some(reason) = reason {
+        stop(p, private, reason);
+    }
+}
+
+/// Stops at the entry or exit of a system call if the tracer asked for it.
+pub fn handle_syscall(p: &Proc, private: &mut ProcPrivateData, reason: PtraceStop) {
+    let stops = {
+        let shared = p.shared.lock();
+        shared.ptrace.traced && shared.ptrace.resume == Resume::Syscall
+    };
+    if stops {
+        stop(p, private, reason);
+    }
+}
+
+/// Handles `ebreak` executed by the process.
+///
+/// Returns `false` if the breakpoint is not for the tracer, and should be
+/// treated as a fatal exception.
+pub fn handle_breakpoint(p: &Proc, private: &mut ProcPrivateData) -> bool {
+    let epc = private.trapframe().epc;
+    let stepped = private
+        .tracee
+        .remove_step_breakpoints(&mut private.pagetable)
+        .iter()
+        .any(|va| va.addr() == epc);
+
+    let traced = p.shared.lock().ptrace.traced;
+    if traced {
+        let reason = if stepped {
+            PtraceStop::Step
+        } else {
+            PtraceStop::Breakpoint
+        };
+        stop(p, private, reason);
+    }
+
+    // a step breakpoint left after the tracer has gone is just skipped.
+    traced || stepped
+}
+
+/// Makes the program text modified for the tracer visible to the
+/// instruction fetch of this hart.
+pub fn sync_instruction_cache(private: &ProcPrivateData) {
+    if private.tracee.text_modified {
+        unsafe {
+            asm!("fence.i");
+        }
+    }
+}
+
+/// Returns the child `pid` of `p`.
+fn find_child(
+    p: &Proc,
+    pid: ProcId,
+    wait_lock: &mut SpinLockGuard<WaitLock>,
+) -> Result<&'static Proc, KernelError> {
+    PROC.iter()
+        .find(|pp| pp.shared.lock().pid == Some(pid) && pp.is_child_of(p, wait_lock))
+        .ok_or(KernelError::ProcessNotFound(pid))
+}
+
+fn trace_me(p: &Proc, pid: ProcId) -> Result<(), KernelError> {
+    let mut shared = p.shared.lock();
+    if shared.pid != Some(pid) {
+        return Err(KernelError::ProcessNotFound(pid));
+    }
+    if shared.ptrace.traced {
+        return Err(KernelError::AlreadyTraced(pid));
+    }
+    shared.ptrace.traced = true;
+    Ok(())
+}
+
+fn attach(p: &Proc, pid: ProcId) -> Result<(), KernelError> {
+    let mut wait_lock = wait_lock::lock();
+    let tracee = find_child(p, pid, &mut wait_lock)?;
+    let mut shared = tracee.shared.lock();
+    if shared.ptrace.traced {
+        return Err(KernelError::AlreadyTraced(pid));
+    }
+    shared.ptrace.traced = true;
+    shared.ptrace.pending_stop = Some(PtraceStop::Attach);
+    Ok(())
+}
+
+/// Resumes the stopped child `pid`, or detaches from it if `resume` is
+/// `None`.
+fn resume(p: &Proc, pid: ProcId, resume: Option<Resume>) -> Result<(), KernelError> {
+    let mut wait_lock = wait_lock::lock();
+    let tracee = find_child(p, pid, &mut wait_lock)?;
+    {
+        let mut shared = tracee.shared.lock();
+        let ptrace = &mut shared.ptrace;
+        if !ptrace.traced {
+            return Err(KernelError::NotTraced(pid));
+        }
+        if ptrace.stop.is_none() {
+            return Err(KernelError::TraceeNotStopped(pid));
+        }
+        match resume {
+            Some(resume) => {
+                ptrace.stop = None;
+                ptrace.resume = resume;
+            }
+            None => {
+                ptrace.detach();
+            }
+        }
+    }
+    tracee.ptrace_event.notify();
+    Ok(())
+}
+
+/// Waits for the traced child `pid` to stop or exit.
+///
+/// An exited child is not reaped.
+fn wait(p: &Proc, pid: ProcId) -> Result<PtraceStop, KernelError> {
+    let mut wait_lock = wait_lock::lock();
+    loop {
+        let tracee = find_child(p, pid, &mut wait_lock)?;
+        {
+            let shared = tracee.shared.lock();
+            if let ProcState::Zombie { .. } = shared.state {
+                return Ok(PtraceStop::Exited);
+            }
+            if !shared.ptrace.traced {
+                return Err(KernelError::NotTraced(pid));
+            }
+            if let Some(stop) = shared.ptrace.stop {
+                return Ok(stop);
+            }
+        }
+        wait_lock = p
+            .child_ended
+            .wait(wait_lock)
+            .map_err(|(_wait_lock, e)| KernelError::from(e))?;
+    }
+}
+
+/// Posts `command` to the stopped child `pid`, and waits for its reply.
+///
+/// `regs` is passed to the child, and updated with the registers it returns.
+fn command(
+    p: &Proc,
+    pid: ProcId,
+    command: Command,
+    regs: &mut PtraceRegs,
+) -> Result<Reply, KernelError> {
+    let mut wait_lock = wait_lock::lock();
+    let tracee = find_child(p, pid, &mut wait_lock)?;
+    {
+        let mut shared = tracee.shared.lock();
+        if !shared.ptrace.traced {
+            return Err(KernelError::NotTraced(pid));
+        }
+        if shared.ptrace.stop.is_none() {
+            return Err(KernelError::TraceeNotStopped(pid));
+        }
+        shared.ptrace.command = Some(command);
+        shared.ptrace.reply = None;
+        shared.ptrace.regs = *regs;
+    }
+    tracee.ptrace_event.notify();
+
+    loop {
+        {
+            let mut shared = tracee.shared.lock();
+            if let Some(reply) = shared.ptrace.reply.take() {
+                *regs = shared.ptrace.regs;
+                return reply;
+            }
+            if shared.pid != Some(pid) || shared.ptrace.stop.is_none() {
+                // killed while stopped
+                return Err(KernelError::TraceeNotStopped(pid));
+            }
+        }
+        wait_lock = p
+            .child_ended
+            .wait(wait_lock)
+            .map_err(|(_wait_lock, e)| KernelError::from(e))?;
+    }
+}
+
+/// Handles a `Ptrace` request from `p`.
+pub fn request(
+    p: &Proc,
+    request: PtraceRequest,
+    pid: ProcId,
+    addr: usize,
+    data: usize,
+) -> Result<usize, KernelError> {
+    match request {
+        PtraceRequest::TraceMe => trace_me(p, pid)?,
+        PtraceRequest::Attach => attach(p, pid)?,
+        PtraceRequest::Detach => resume(p, pid, None)?,
+        PtraceRequest::Continue => resume(p, pid, Some(Resume::Continue))?,
+        PtraceRequest::Syscall => resume(p, pid, Some(Resume::Syscall))?,
+        PtraceRequest::Step => resume(p, pid, Some(Resume::Step))?,
+        PtraceRequest::Wait => return Ok(wait(p, pid)? as usize),
+        PtraceRequest::PeekData => {
+            let Reply::Word(word) =
+                command(p, pid, Command::PeekData(addr), &mut PtraceRegs::zeroed())?
+            else {
+                unreachable!()
+            };
+            return Ok(word);
+        }
+        PtraceRequest::PokeData => {
+            command(
+                p,
+                pid,
+                Command::PokeData(addr, data),
+                &mut PtraceRegs::zeroed(),
+            )?;
+        }
+    }
+    Ok(0)
+}
+
+/// Returns the registers of the stopped child `pid`.
+pub fn get_regs(p: &Proc, pid: ProcId) -> Result<PtraceRegs, KernelError> {
+    let mut regs = PtraceRegs::zeroed();
+    command(p, pid, Command::GetRegs, &mut regs)?;
+    Ok(regs)
+}
+
+/// Sets the registers of the stopped child `pid`.
+pub fn set_regs(p: &Proc, pid: ProcId, regs: &PtraceRegs) -> Result<(), KernelError> {
+    let mut regs = *regs;
+    command(p, pid, Command::SetRegs, &mut regs)?;
+    Ok(())
+}
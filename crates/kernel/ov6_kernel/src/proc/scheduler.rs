@@ -7,6 +7,7 @@ use super::{PROC, Proc, ProcSharedData, ProcState};
 use crate::{
     cpu::{self, Cpu},
     interrupt,
+    memory::page,
     sync::SpinLockGuard,
 };
 
@@ -88,7 +89,7 @@ pub fn schedule() -> ! {
                 // The process is running on another CPU.
                 continue;
             };
-            if shared.state != ProcState::Runnable {
+            if shared.state != ProcState::Runnable || !shared.affinity.contains(cpuid) {
                 drop(shared);
                 continue;
             }
@@ -110,7 +111,11 @@ pub fn schedule() -> ! {
         }
 
         if !found {
-            // nothing to run, stop running on this core until an interrupt.
+            // nothing to run, zero free pages in advance while idle.
+            if page::scrub() > 0 {
+                continue;
+            }
+            // nothing to do, stop running on this core until an interrupt.
             interrupt::enable();
             cpu.set_idle(true);
             asm::wfi();
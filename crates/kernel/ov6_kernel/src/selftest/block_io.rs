@@ -0,0 +1,153 @@
+//! Tests of the block I/O cache, on a RAM disk.
+
+use alloc::{vec, vec::Vec};
+use core::{
+    convert::Infallible,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use block_io::{BlockData, BlockDevice, BlockIoCache, LruMap};
+
+use super::{Test, TestResult};
+use crate::sync::SpinLock;
+
+pub(super) const TESTS: &[Test] = &[
+    Test {
+        name: "block_io: blocks are read once",
+        run: read_cached,
+    },
+    Test {
+        name: "block_io: written blocks reach the disk",
+        run: write_through,
+    },
+    Test {
+        name: "block_io: least recently used block is evicted",
+        run: evict,
+    },
+    Test {
+        name: "block_io: busy cache is exhausted",
+        run: exhaust,
+    },
+];
+
+const BLOCK_SIZE: usize = 512;
+const NUM_BLOCKS: usize = 8;
+
+/// A block device in memory, counting the transfers.
+struct RamDisk {
+    blocks: SpinLock<Vec<[u8; BLOCK_SIZE]>>,
+    reads: AtomicUsize,
+    writes: AtomicUsize,
+}
+
+impl RamDisk {
+    /// Creates a RAM disk whose block `i` is filled with `i`.
+    fn new() -> Self {
+        let mut blocks = vec![[0; BLOCK_SIZE]; NUM_BLOCKS];
+        for (i, block) in blocks.iter_mut().enumerate() {
+            block.fill(u8::try_from(i).unwrap());
+        }
+        Self {
+            blocks: SpinLock::new(blocks),
+            reads: AtomicUsize::new(0),
+            writes: AtomicUsize::new(0),
+        }
+    }
+
+    fn reads(&self) -> usize {
+        self.reads.load(Ordering::Relaxed)
+    }
+
+    fn writes(&self) -> usize {
+        self.writes.load(Ordering::Relaxed)
+    }
+}
+
+impl BlockDevice<BLOCK_SIZE> for &RamDisk {
+    type Error = Infallible;
+
+    fn read(&self, block_index: usize, data: &mut [u8; BLOCK_SIZE]) -> Result<(), Self::Error> {
+        self.reads.fetch_add(1, Ordering::Relaxed);
+        data.copy_from_slice(&self.blocks.lock()[block_index]);
+        Ok(())
+    }
+
+    fn write(&self, block_index: usize, data: &[u8; BLOCK_SIZE]) -> Result<(), Self::Error> {
+        self.writes.fetch_add(1, Ordering::Relaxed);
+        self.blocks.lock()[block_index].copy_from_slice(data);
+        Ok(())
+    }
+}
+
+type BlockMutex = SpinLock<BlockData<BLOCK_SIZE>>;
+type Cache<'a> = BlockIoCache<&'a RamDisk, SpinLock<LruMap<BlockMutex>>>;
+
+/// Reads the block `index` through the cache and returns its first byte.
+fn read_first_byte(cache: &Cache<'_>, index: usize) -> Option<u8> {
+    let mut block = cache.try_get(index)?;
+    let Ok(block) = block.lock().read();
+    Some(block.bytes()[0])
+}
+
+fn read_cached() -> TestResult {
+    let disk = RamDisk::new();
+    let cache = Cache::new(&disk, 4);
+
+    check!(read_first_byte(&cache, 3) == Some(3));
+    check!(read_first_byte(&cache, 3) == Some(3));
+    check!(disk.reads() == 1);
+    check!(read_first_byte(&cache, 5) == Some(5));
+    check!(disk.reads() == 2);
+    check!(disk.writes() == 0);
+    Ok(())
+}
+
+fn write_through() -> TestResult {
+    let disk = RamDisk::new();
+    let cache = Cache::new(&disk, 4);
+
+    {
+        let mut block = check_ok!(cache.try_get(2));
+        let Ok(mut block) = block.lock().read();
+        block.bytes_mut().fill(0x5a);
+        check!(block.is_dirty());
+        let Ok(()) = block.write();
+        check!(!block.is_dirty());
+    }
+    check!(disk.writes() == 1);
+    check!(disk.blocks.lock()[2].iter().all(|&b| b == 0x5a));
+    // the cached copy is used afterwards.
+    check!(read_first_byte(&cache, 2) == Some(0x5a));
+    check!(disk.reads() == 1);
+    Ok(())
+}
+
+fn evict() -> TestResult {
+    let disk = RamDisk::new();
+    let cache = Cache::new(&disk, 2);
+
+    check!(read_first_byte(&cache, 0) == Some(0));
+    check!(read_first_byte(&cache, 1) == Some(1));
+    check!(read_first_byte(&cache, 0) == Some(0)); // 1 is now the LRU
+    check!(disk.reads() == 2);
+
+    check!(read_first_byte(&cache, 2) == Some(2)); // evicts 1
+    check!(read_first_byte(&cache, 0) == Some(0));
+    check!(disk.reads() == 3);
+    check!(read_first_byte(&cache, 1) == Some(1)); // evicts 2
+    check!(disk.reads() == 4);
+    Ok(())
+}
+
+fn exhaust() -> TestResult {
+    let disk = RamDisk::new();
+    let cache = Cache::new(&disk, 2);
+
+    let first = check_ok!(cache.try_get(0));
+    let second = check_ok!(cache.try_get(1));
+    check!(cache.try_get(2).is_none());
+    drop(first);
+    check!(cache.try_get(2).is_some());
+    drop(second);
+    Ok(())
+}
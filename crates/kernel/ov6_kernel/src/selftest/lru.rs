@@ -0,0 +1,72 @@
+//! Tests of the LRU cache.
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use lru::{Lru, LruMap};
+
+use super::{Test, TestResult};
+use crate::sync::SpinLock;
+
+pub(super) const TESTS: &[Test] = &[
+    Test {
+        name: "lru: cached entries are found",
+        run: hit,
+    },
+    Test {
+        name: "lru: least recently used entry is recycled",
+        run: recycle,
+    },
+    Test {
+        name: "lru: referenced entries are not recycled",
+        run: referenced,
+    },
+];
+
+type TestLru = Lru<SpinLock<LruMap<usize, AtomicUsize>>>;
+
+fn set(lru: &TestLru, key: usize, value: usize) -> Option<()> {
+    lru.get(key)?.value().store(value, Ordering::Relaxed);
+    Some(())
+}
+
+fn get(lru: &TestLru, key: usize) -> Option<usize> {
+    Some(lru.get(key)?.value().load(Ordering::Relaxed))
+}
+
+fn hit() -> TestResult {
+    let lru = TestLru::new(3);
+    for key in 0..3 {
+        check_ok!(set(&lru, key, key * 10));
+    }
+    for key in 0..3 {
+        check!(get(&lru, key) == Some(key * 10));
+    }
+    Ok(())
+}
+
+fn recycle() -> TestResult {
+    let lru = TestLru::new(2);
+    check_ok!(set(&lru, 1, 10));
+    check_ok!(set(&lru, 2, 20));
+
+    // values are not reset on recycling, so a stale value shows which entry
+    // was reused.
+    check!(get(&lru, 3) == Some(10)); // recycles 1
+    check!(get(&lru, 2) == Some(20)); // 2 is still cached
+    check!(get(&lru, 1) == Some(10)); // recycles 3, which was used before 2
+    check!(get(&lru, 2) == Some(20));
+    Ok(())
+}
+
+fn referenced() -> TestResult {
+    let lru = TestLru::new(2);
+    let first = check_ok!(lru.get(1));
+    let second = check_ok!(lru.get(2));
+    check!(lru.get(3).is_none());
+    check!(lru.get(1).is_some());
+
+    drop(second);
+    check!(lru.get(3).is_some());
+    drop(first);
+    Ok(())
+}
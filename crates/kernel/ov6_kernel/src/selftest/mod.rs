@@ -0,0 +1,93 @@
+//! Boot-time self tests.
+//!
+//! With the `selftest` feature, the kernel runs these tests on CPU 0 after the
+//! core subsystems are initialized and before the first user process starts.
+//! They exercise kernel data structures in the running kernel, with the real
+//! allocators and locks, without a round trip through user space.
+//!
+//! The results are printed in the TAP (Test Anything Protocol) format. If any
+//! test fails, the machine is stopped with a failure exit code.
+
+use crate::{
+    device::test::{self, Finisher},
+    println,
+};
+
+/// Returns a [`TestFailure`] from the test if the condition does not hold.
+macro_rules! check {
+    ($cond:expr) => {
+        if !$cond {
+            return Err($crate::selftest::TestFailure {
+                expr: stringify!($cond),
+                file: file!(),
+                line: line!(),
+            });
+        }
+    };
+}
+
+/// Unwraps a `Result` or `Option`, or returns a [`TestFailure`] from the test.
+macro_rules! check_ok {
+    ($expr:expr) => {
+        // both `Result` and `Option` iterate over their success value.
+        match IntoIterator::into_iter($expr).next() {
+            Some(value) => value,
+            None => {
+                return Err($crate::selftest::TestFailure {
+                    expr: stringify!($expr),
+                    file: file!(),
+                    line: line!(),
+                });
+            }
+        }
+    };
+}
+
+mod block_io;
+mod lru;
+mod page;
+mod path;
+
+/// Exit code of the machine when a self test fails.
+const FAILURE_CODE: u16 = 3;
+
+const TESTS: &[&[Test]] = &[page::TESTS, lru::TESTS, block_io::TESTS, path::TESTS];
+
+struct Test {
+    name: &'static str,
+    run: fn() -> TestResult,
+}
+
+struct TestFailure {
+    expr: &'static str,
+    file: &'static str,
+    line: u32,
+}
+
+type TestResult = Result<(), TestFailure>;
+
+/// Runs all the self tests.
+pub fn run() {
+    let total = TESTS.iter().map(|tests| tests.len()).sum::<usize>();
+    println!("TAP version 13");
+    println!("1..{total}");
+
+    let mut failed = 0;
+    for (i, test) in TESTS.iter().copied().flatten().enumerate() {
+        let num = i + 1;
+        match (test.run)() {
+            Ok(()) => println!("ok {num} - {}", test.name),
+            Err(e) => {
+                println!("not ok {num} - {}", test.name);
+                println!("# {}:{}: check failed: {}", e.file, e.line, e.expr);
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        println!("# {failed} of {total} self tests failed");
+        test::finish(Finisher::Fail(FAILURE_CODE));
+    }
+    println!("# all {total} self tests passed");
+}
@@ -0,0 +1,62 @@
+//! Tests of the physical page allocator.
+
+use arrayvec::ArrayVec;
+
+use super::{Test, TestResult};
+use crate::memory::{self, PAGE_SIZE, page::SharedPage};
+
+pub(super) const TESTS: &[Test] = &[
+    Test {
+        name: "page: allocated pages are zeroed",
+        run: alloc_zeroed,
+    },
+    Test {
+        name: "page: shared pages are not writable",
+        run: shared,
+    },
+    Test {
+        name: "page: freed pages are returned",
+        run: free_count,
+    },
+];
+
+fn alloc_zeroed() -> TestResult {
+    for _ in 0..4 {
+        let mut page = check_ok!(SharedPage::alloc_zeroed());
+        let bytes = check_ok!(page.bytes_mut());
+        check!(bytes.iter().all(|&b| b == 0));
+        // dirty the page, so that a reused page must be zeroed again.
+        bytes.fill(0xa5);
+    }
+    Ok(())
+}
+
+fn shared() -> TestResult {
+    let mut page = check_ok!(SharedPage::alloc_zeroed());
+    check!(!page.is_shared());
+
+    let mut other = page.clone();
+    check!(page.is_shared() && other.is_shared());
+    check!(page.bytes_mut().is_none());
+    check!(other.bytes_mut().is_none());
+
+    drop(other);
+    check!(!page.is_shared());
+    check!(page.bytes_mut().is_some_and(|b| b.len() == PAGE_SIZE));
+    Ok(())
+}
+
+fn free_count() -> TestResult {
+    const COUNT: usize = 8;
+
+    let before = memory::info().free_pages;
+    let mut pages = ArrayVec::<SharedPage, COUNT>::new();
+    for _ in 0..COUNT {
+        pages.push(check_ok!(SharedPage::alloc_zeroed()));
+    }
+    check!(memory::info().free_pages + COUNT == before);
+
+    drop(pages);
+    check!(memory::info().free_pages == before);
+    Ok(())
+}
@@ -0,0 +1,77 @@
+//! Tests of the lexical path operations.
+
+use alloc::string::String;
+
+use ov6_types::path::Path;
+
+use super::{Test, TestResult};
+use crate::{
+    error::KernelError,
+    fs::path::{self, NormalPath},
+    param::MAX_PATH,
+};
+
+pub(super) const TESTS: &[Test] = &[
+    Test {
+        name: "path: join normalizes components",
+        run: join,
+    },
+    Test {
+        name: "path: join stops at the root",
+        run: join_parent_of_root,
+    },
+    Test {
+        name: "path: join rejects long paths",
+        run: join_too_long,
+    },
+    Test {
+        name: "path: rebase to a root directory",
+        run: rebase,
+    },
+];
+
+fn normal(s: &str) -> NormalPath {
+    s.as_bytes().try_into().unwrap()
+}
+
+fn joined(base: &str, path: &str) -> Option<NormalPath> {
+    path::join_normalized(&normal(base), Path::new(path)).ok()
+}
+
+fn join() -> TestResult {
+    check!(joined("/", "a/b") == Some(normal("/a/b")));
+    check!(joined("/a", "./b//c/../d/") == Some(normal("/a/b/d")));
+    check!(joined("/a/b", "..") == Some(normal("/a")));
+    check!(joined("/a/b", "/c") == Some(normal("/c")));
+    check!(joined("/a", "") == Some(normal("/a")));
+    Ok(())
+}
+
+fn join_parent_of_root() -> TestResult {
+    check!(joined("/", "..") == Some(normal("/")));
+    check!(joined("/a", "../../..") == Some(normal("/")));
+    check!(joined("/a", "../../b") == Some(normal("/b")));
+    Ok(())
+}
+
+fn join_too_long() -> TestResult {
+    let long = "x".repeat(MAX_PATH);
+    let res = path::join_normalized(&path::root_path(), Path::new(long.as_str()));
+    check!(matches!(res, Err(KernelError::PathTooLong)));
+
+    let mut fits = String::from("/");
+    fits.push_str(&long[..MAX_PATH - 1]);
+    check!(joined("/", &fits[1..]) == Some(normal(&fits)));
+    Ok(())
+}
+
+fn rebase() -> TestResult {
+    let root = normal("/jail");
+    let rebased = |p: &str| path::rebase_normalized(&normal(p), &root);
+    check!(rebased("/jail/bin") == Some(normal("/bin")));
+    check!(rebased("/jail") == Some(normal("/")));
+    check!(rebased("/jailbreak").is_none());
+    check!(rebased("/etc").is_none());
+    check!(path::rebase_normalized(&normal("/etc"), &path::root_path()) == Some(normal("/etc")));
+    Ok(())
+}
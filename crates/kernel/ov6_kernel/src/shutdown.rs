@@ -0,0 +1,79 @@
+//! System shutdown.
+//!
+//! `halt` and `reboot` do not stop the machine directly. They record the
+//! request here, and init, which waits for it, stops the other processes and
+//! commits the file system before calling the `Halt` or `Reboot` system call.
+//!
+//! The system calls then run [`shutdown()`], which quiesces the kernel before
+//! powering off or resetting the machine.
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use ov6_syscall::ShutdownRequest;
+
+use crate::{
+    device::{
+        e1000,
+        test::{self, Finisher},
+    },
+    error::KernelError,
+    fs::{self, virtio_disk},
+    println,
+    sync::{SleepLock, SpinLock, SpinLockCondVar},
+};
+
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+/// Held while shutting down, so that concurrent callers of [`shutdown()`]
+/// wait for the first one to finish the machine.
+static SHUTDOWN_LOCK: SleepLock<()> = SleepLock::new(());
+static REQUEST: SpinLock<Option<ShutdownRequest>> = SpinLock::new(None);
+static REQUESTED: SpinLockCondVar = SpinLockCondVar::new();
+
+/// Records a shutdown request.
+///
+/// The first request wins; later requests are ignored until the system is
+/// shut down.
+pub fn request(req: ShutdownRequest) {
+    let mut request = REQUEST.lock();
+    if request.is_none() {
+        *request = Some(req);
+        REQUESTED.notify();
+    }
+}
+
+/// Waits until a shutdown is requested and returns the request.
+///
+/// The request is not consumed, so every waiter receives it.
+pub fn wait_request() -> Result<ShutdownRequest, KernelError> {
+    let mut request = REQUEST.lock();
+    loop {
+        if let Some(req) = *request {
+            return Ok(req);
+        }
+        request = REQUESTED.wait(request).map_err(|(_guard, e)| e)?;
+    }
+}
+
+/// Returns `true` if the system is shutting down.
+///
+/// New processes are not created while shutting down.
+pub fn is_shutting_down() -> bool {
+    SHUTTING_DOWN.load(Ordering::Acquire)
+}
+
+/// Shuts down the system.
+///
+/// Stops creating processes, commits the file system and marks it clean,
+/// and resets the devices before finishing the machine with `finisher`.
+pub fn shutdown(finisher: Finisher) -> ! {
+    let _guard = SHUTDOWN_LOCK.force_wait_lock();
+    SHUTTING_DOWN.store(true, Ordering::Release);
+
+    println!("ov6 - syncing file system");
+    fs::shutdown();
+    println!("ov6 - resetting devices");
+    virtio_disk::reset();
+    e1000::reset();
+
+    test::finish(finisher);
+}
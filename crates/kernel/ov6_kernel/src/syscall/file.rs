@@ -1,18 +1,21 @@
+use alloc::boxed::Box;
 use core::{convert::Infallible, mem};
 
 use ov6_syscall::{
-    OpenFlags, Register, RegisterValue, Syscall, UserSlice, error::SyscallError, syscall,
+    AT_FDCWD, AccessMode, AtFlags, Capabilities, FcntlRequest, FdFlags, OpenFlags, Register,
+    RegisterValue, Syscall, SyscallCode, UserSlice, error::SyscallError, syscall,
 };
-use ov6_types::{os_str::OsStr, path::Path};
+use ov6_types::{fs::RawFd, os_str::OsStr, path::Path};
 
 use super::SyscallExt;
 use crate::{
     error::KernelError,
-    file::File,
-    fs::{self, DeviceNo, Inode, T_DEVICE, T_DIR, T_FILE},
+    file::{self, File},
+    fs::{self, DeviceNo, Inode, InodeGuard, T_DEVICE, T_DIR, T_FILE, T_SOCK, vfs},
     memory::{
-        VirtAddr,
+        PAGE_SIZE, VirtAddr,
         addr::{Validate as _, Validated},
+        page::PageFrameAllocator,
     },
     param::MAX_PATH,
     proc::{Proc, ProcPrivateData, exec},
@@ -49,6 +52,60 @@ impl SyscallExt for syscall::Dup {
     }
 }
 
+impl SyscallExt for syscall::Dup2 {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (fd, new_fd): Self::Arg,
+    ) -> Self::Return {
+        let file = private.ofile(fd)?.clone();
+        if fd != new_fd
+            && let Some(old) = private.set_ofile(new_fd, file, FdFlags::empty())?
+        {
+            old.close();
+        }
+        Ok(new_fd)
+    }
+}
+
+impl SyscallExt for syscall::Dup3 {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (fd, new_fd, flags): Self::Arg,
+    ) -> Self::Return {
+        let fd_flags = fd_flags(flags)?;
+        if fd == new_fd {
+            return Err(KernelError::DupSameFd.into());
+        }
+        let file = private.ofile(fd)?.clone();
+        if let Some(old) = private.set_ofile(new_fd, file, fd_flags)? {
+            old.close();
+        }
+        Ok(new_fd)
+    }
+}
+
+/// Converts the flags given to `Pipe2` and `Dup3` into file descriptor flags.
+fn fd_flags(flags: OpenFlags) -> Result<FdFlags, KernelError> {
+    if flags.difference(OpenFlags::CLOEXEC) != OpenFlags::empty() {
+        return Err(KernelError::InvalidFdFlags(flags.bits()));
+    }
+    if flags.contains(OpenFlags::CLOEXEC) {
+        Ok(FdFlags::CLOEXEC)
+    } else {
+        Ok(FdFlags::empty())
+    }
+}
+
 impl SyscallExt for syscall::Read {
     type KernelArg = Self::Arg;
     type KernelReturn = Self::Return;
@@ -83,6 +140,180 @@ impl SyscallExt for syscall::Write {
     }
 }
 
+impl SyscallExt for syscall::Pread {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (fd, data, off): Self::Arg,
+    ) -> Self::Return {
+        let mut data = data.validate(private.pagetable())?;
+        let file = private.ofile(fd)?;
+        let n = file
+            .clone()
+            .read_at(private.pagetable_mut(), &mut data, off)?;
+        Ok(n)
+    }
+}
+
+impl SyscallExt for syscall::Pwrite {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (fd, data, off): Self::Arg,
+    ) -> Self::Return {
+        let data = data.validate(private.pagetable())?;
+        let file = private.ofile(fd)?;
+        let n = file.clone().write_at(private.pagetable(), &data, off)?;
+        Ok(n)
+    }
+}
+
+impl SyscallExt for syscall::Readv {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (fd, iov): Self::Arg,
+    ) -> Self::Return {
+        let iov = iov.validate(private.pagetable())?;
+        let file = private.ofile(fd)?.clone();
+
+        // Scatter into each buffer in turn, stopping at the first short read.
+        // An error after some bytes are read ends the call with the bytes
+        // read so far.
+        let mut total = 0;
+        for i in 0..iov.len() {
+            let buf = private.pagetable().copy_u2k(&iov.nth(i));
+            let len = buf.len();
+            let n = match buf
+                .validate(private.pagetable())
+                .and_then(|mut buf| file.read(private.pagetable_mut(), &mut buf))
+            {
+                Ok(n) => n,
+                Err(_) if total > 0 => break,
+                Err(e) => return Err(e.into()),
+            };
+            total += n;
+            if n < len {
+                break;
+            }
+        }
+        Ok(total)
+    }
+}
+
+impl SyscallExt for syscall::Writev {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (fd, iov): Self::Arg,
+    ) -> Self::Return {
+        let iov = iov.validate(private.pagetable())?;
+        let file = private.ofile(fd)?.clone();
+
+        // Gather from each buffer in turn, stopping at the first short write.
+        // An error after some bytes are written ends the call with the bytes
+        // written so far.
+        let mut total = 0;
+        for i in 0..iov.len() {
+            let buf = private.pagetable().copy_u2k(&iov.nth(i));
+            let len = buf.len();
+            let n = match buf
+                .validate(private.pagetable())
+                .and_then(|buf| file.write(private.pagetable(), &buf))
+            {
+                Ok(n) => n,
+                Err(_) if total > 0 => break,
+                Err(e) => return Err(e.into()),
+            };
+            total += n;
+            if n < len {
+                break;
+            }
+        }
+        Ok(total)
+    }
+}
+
+impl SyscallExt for syscall::SendFile {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (out_fd, in_fd, count): Self::Arg,
+    ) -> Self::Return {
+        let out_file = private.ofile(out_fd)?.clone();
+        let in_file = private.ofile(in_fd)?.clone();
+
+        let Ok(buf) = Box::try_new_zeroed_in(PageFrameAllocator) else {
+            return Err(KernelError::NoFreePage.into());
+        };
+        let mut buf: Box<[u8; PAGE_SIZE], PageFrameAllocator> = unsafe { buf.assume_init() };
+
+        // Copy through a kernel page until `count` bytes are copied or the
+        // end of the input is reached. An error after some bytes are copied
+        // ends the call with the bytes copied so far.
+        let mut total = 0;
+        while total < count {
+            let len = usize::min(count - total, buf.len());
+            let nread = match in_file.read_kernel(&mut buf[..len]) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(_) if total > 0 => break,
+                Err(e) => return Err(e.into()),
+            };
+            let mut nwritten = 0;
+            while nwritten < nread {
+                match out_file.write_kernel(&buf[nwritten..nread]) {
+                    Ok(0) => break,
+                    Ok(n) => nwritten += n,
+                    Err(_) if total + nwritten > 0 => break,
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            total += nwritten;
+            if nwritten < nread {
+                break;
+            }
+        }
+        Ok(total)
+    }
+}
+
+impl SyscallExt for syscall::Splice {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (in_fd, out_fd, count): Self::Arg,
+    ) -> Self::Return {
+        let in_file = private.ofile(in_fd)?.clone();
+        let out_file = private.ofile(out_fd)?.clone();
+        Ok(File::splice(&in_file, &out_file, count)?)
+    }
+}
+
 impl SyscallExt for syscall::Close {
     type KernelArg = Self::Arg;
     type KernelReturn = Self::Return;
@@ -112,6 +343,67 @@ impl SyscallExt for syscall::Fstat {
     }
 }
 
+impl SyscallExt for syscall::Fstatfs {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (fd, user_statfs): Self::Arg,
+    ) -> Self::Return {
+        let mut user_statfs = user_statfs.validate(private.pagetable_mut())?;
+        let file = private.ofile(fd)?;
+        let stat = file.clone().stat()?;
+        let statfs = fs::statfs(DeviceNo::new(stat.dev))?;
+        private.pagetable_mut().copy_k2u(&mut user_statfs, &statfs);
+        Ok(())
+    }
+}
+
+impl SyscallExt for syscall::Ioctl {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (fd, req, arg): Self::Arg,
+    ) -> Self::Return {
+        let file = private.ofile(fd)?;
+        let ret = file.clone().ioctl(req, arg)?;
+        Ok(ret)
+    }
+}
+
+impl SyscallExt for syscall::Fcntl {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (fd, req, arg): Self::Arg,
+    ) -> Self::Return {
+        match req {
+            FcntlRequest::GetFdFlags => Ok(private.ofile_flags(fd)?.bits()),
+            FcntlRequest::SetFdFlags => {
+                let flags = FdFlags::from_bits(arg).ok_or(KernelError::InvalidFdFlags(arg))?;
+                private.set_ofile_flags(fd, flags)?;
+                Ok(0)
+            }
+            _ => {
+                let file = private.ofile(fd)?;
+                let ret = file.fcntl(req, arg)?;
+                Ok(ret)
+            }
+        }
+    }
+}
+
 impl SyscallExt for syscall::Link {
     type KernelArg = Self::Arg;
     type KernelReturn = Self::Return;
@@ -127,9 +419,29 @@ impl SyscallExt for syscall::Link {
         let old = fetch_path(private, user_old, &mut old)?;
         let new = fetch_path(private, user_new, &mut new)?;
 
-        let tx = fs::begin_tx().map_err(KernelError::from)?;
-        let cwd = private.cwd().clone().into_tx(&tx);
-        fs::ops::link(&tx, cwd, old, new)?;
+        let ctx = private.fs_context();
+        fs::ops::link(ctx.tx_token, ctx.root, ctx.cwd, old, new)?;
+        Ok(())
+    }
+}
+
+impl SyscallExt for syscall::Flink {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (fd, user_new): Self::Arg,
+    ) -> Self::Return {
+        let mut new = [0; MAX_PATH];
+        let new = fetch_path(private, user_new, &mut new)?;
+        let file = private.ofile(fd)?.clone();
+        let inode = file.inode().ok_or(KernelError::FlinkNonInodeFile)?;
+
+        let ctx = private.fs_context();
+        fs::ops::flink(ctx.tx_token, ctx.root, ctx.cwd, inode, new)?;
         Ok(())
     }
 }
@@ -147,13 +459,127 @@ impl SyscallExt for syscall::Unlink {
         let mut path = [0; MAX_PATH];
         let path = fetch_path(private, user_path, &mut path)?;
 
-        let tx = fs::begin_tx().map_err(KernelError::from)?;
-        let cwd = private.cwd().clone().into_tx(&tx);
-        fs::ops::unlink(&tx, cwd, path)?;
+        let ctx = private.fs_context();
+        fs::ops::unlink(ctx.tx_token, ctx.root, ctx.cwd, path)?;
+        Ok(())
+    }
+}
+
+impl SyscallExt for syscall::Rmdir {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (user_path,): Self::Arg,
+    ) -> Self::Return {
+        let mut path = [0; MAX_PATH];
+        let path = fetch_path(private, user_path, &mut path)?;
+
+        let ctx = private.fs_context();
+        fs::ops::rmdir(ctx.tx_token, ctx.root, ctx.cwd, path)?;
         Ok(())
     }
 }
 
+/// Returns the file referred to by `dir_fd`, or `None` for [`AT_FDCWD`].
+///
+/// Whether the file is a directory is checked when a path is resolved from it.
+fn dir_file(private: &ProcPrivateData, dir_fd: RawFd) -> Result<Option<File>, KernelError> {
+    if dir_fd == AT_FDCWD {
+        return Ok(None);
+    }
+    let file = private.ofile(dir_fd)?.clone();
+    if file.inode().is_none() {
+        return Err(KernelError::DirFdNotInode(dir_fd));
+    }
+    Ok(Some(file))
+}
+
+/// Returns the directory that relative paths are resolved from.
+fn base_dir<'a>(cwd: &'a Inode, dir: Option<&'a File>) -> &'a Inode {
+    dir.and_then(File::inode).unwrap_or(cwd)
+}
+
+fn sys_open(
+    private: &mut ProcPrivateData,
+    code: SyscallCode,
+    dir_fd: RawFd,
+    user_path: UserSlice<u8>,
+    mode: OpenFlags,
+) -> Result<RawFd, KernelError> {
+    let mut path = [0; MAX_PATH];
+    let path = fetch_path(private, user_path, &mut path)?;
+
+    let readable = !mode.contains(OpenFlags::WRITE_ONLY);
+    let writable = mode.contains(OpenFlags::WRITE_ONLY) || mode.contains(OpenFlags::READ_WRITE);
+    if mode.contains(OpenFlags::TMPFILE) && (!writable || mode.contains(OpenFlags::CREATE)) {
+        return Err(KernelError::InvalidTmpfileOpen);
+    }
+
+    let dir = dir_file(private, dir_fd)?;
+    let ctx = private.fs_context();
+    let base = base_dir(ctx.cwd, dir.as_ref());
+    let ip = if mode.contains(OpenFlags::TMPFILE) {
+        fs::ops::create_unnamed(ctx.tx_token, ctx.root, base, path)?
+    } else if mode.contains(OpenFlags::CREATE) {
+        fs::ops::create(
+            ctx.tx_token,
+            ctx.root,
+            base,
+            path,
+            T_FILE,
+            DeviceNo::ROOT,
+            0,
+        )?
+    } else {
+        let ip = fs::path::resolve(ctx.root, base, path)?;
+        if vfs::stat(&ip)?.ty == T_DIR
+            && mode.difference(OpenFlags::DIRECT | OpenFlags::CLOEXEC) != OpenFlags::READ_ONLY
+        {
+            return Err(KernelError::OpenDirAsWritable);
+        }
+        ip
+    };
+
+    let st = vfs::stat(&ip)?;
+    if st.ty == T_SOCK {
+        return Err(KernelError::OpenSocket);
+    }
+
+    if st.ty != T_DEVICE && (writable || mode.contains(OpenFlags::TRUNC)) {
+        vfs::check_writable(ip.dev())?;
+    }
+    let (f, raw_disk) = if st.ty == T_DEVICE {
+        // writing to a raw disk bypasses the file system
+        let major = DeviceNo::new(st.major);
+        let raw_disk = writable && file::is_block_device(major);
+        let f = File::new_device(major, ip.into_inner(), readable, writable)?;
+        (f, raw_disk)
+    } else {
+        if mode.contains(OpenFlags::TRUNC) && st.ty == T_FILE {
+            vfs::inode_ops(ip.dev())?.truncate(ctx.tx_token, &ip)?;
+        }
+        let direct = mode.contains(OpenFlags::DIRECT);
+        let f = File::new_inode(ip.into_inner(), readable, writable, direct)?;
+        (f, false)
+    };
+    if raw_disk {
+        private.require_caps(code, Capabilities::RAW_IO, Some(path))?;
+    }
+
+    let fd_flags = if mode.contains(OpenFlags::CLOEXEC) {
+        FdFlags::CLOEXEC
+    } else {
+        FdFlags::empty()
+    };
+    let fd = private.add_ofile_with_flags(f, fd_flags)?;
+
+    Ok(fd)
+}
+
 impl SyscallExt for syscall::Open {
     type KernelArg = Self::Arg;
     type KernelReturn = Self::Return;
@@ -163,41 +589,163 @@ impl SyscallExt for syscall::Open {
         _p: &'static Proc,
         private: &mut Self::Private<'_>,
         (user_path, mode): Self::Arg,
+    ) -> Self::Return {
+        let fd = sys_open(private, Self::CODE, AT_FDCWD, user_path, mode)?;
+        Ok(fd)
+    }
+}
+
+impl SyscallExt for syscall::Openat {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (dir_fd, user_path, mode): Self::Arg,
+    ) -> Self::Return {
+        let fd = sys_open(private, Self::CODE, dir_fd, user_path, mode)?;
+        Ok(fd)
+    }
+}
+
+impl SyscallExt for syscall::Fstatat {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (dir_fd, user_path, user_stat): Self::Arg,
     ) -> Self::Return {
         let mut path = [0; MAX_PATH];
         let path = fetch_path(private, user_path, &mut path)?;
+        let mut user_stat = user_stat.validate(private.pagetable_mut())?;
 
-        let tx = fs::begin_tx().map_err(KernelError::from)?;
-        let cwd = private.cwd().clone().into_tx(&tx);
-        let mut ip = if mode.contains(OpenFlags::CREATE) {
-            fs::ops::create(&tx, cwd, path, T_FILE, DeviceNo::ROOT, 0)?
-        } else {
-            let mut ip = fs::path::resolve(&tx, cwd, path)?;
-            let lip = ip.force_wait_lock();
-            if lip.is_dir() && mode != OpenFlags::READ_ONLY {
-                return Err(KernelError::OpenDirAsWritable.into());
-            }
-            lip.unlock();
-            ip
-        };
+        let dir = dir_file(private, dir_fd)?;
+        let ctx = private.fs_context();
+        let ip = fs::path::resolve(ctx.root, base_dir(ctx.cwd, dir.as_ref()), path)?;
+        let stat = vfs::stat(&ip)?;
+        drop(ip);
+        private.pagetable_mut().copy_k2u(&mut user_stat, &stat);
+        Ok(())
+    }
+}
 
-        let mut lip = ip.force_wait_lock();
+/// Checks that the calling process may access `path` with `mode`.
+///
+/// Files have no permission bits, so the type of the file decides what is
+/// permitted: regular files and directories allow everything, devices can
+/// be read and written, and local sockets cannot be accessed by a path.
+/// Writing anything but a device also requires the file system to be
+/// writable.
+fn sys_access(
+    private: &mut ProcPrivateData,
+    dir_fd: RawFd,
+    user_path: UserSlice<u8>,
+    mode: AccessMode,
+    _flags: AtFlags,
+) -> Result<(), KernelError> {
+    let mut path = [0; MAX_PATH];
+    let path = fetch_path(private, user_path, &mut path)?;
 
-        let readable = !mode.contains(OpenFlags::WRITE_ONLY);
-        let writable = mode.contains(OpenFlags::WRITE_ONLY) || mode.contains(OpenFlags::READ_WRITE);
-        let f = if lip.ty() == T_DEVICE {
-            File::new_device(lip.major(), Inode::from_locked(&lip), readable, writable)?
-        } else {
-            File::new_inode(Inode::from_locked(&lip), readable, writable)?
-        };
+    let dir = dir_file(private, dir_fd)?;
+    let ctx = private.fs_context();
+    // there are no symbolic links, so `AtFlags::SYMLINK_NOFOLLOW` needs no
+    // handling
+    let ip = fs::path::resolve(ctx.root, base_dir(ctx.cwd, dir.as_ref()), path)?;
+    let ty = vfs::stat(&ip)?.ty;
+
+    let permitted = match ty {
+        T_FILE | T_DIR => AccessMode::all(),
+        T_DEVICE => AccessMode::READ | AccessMode::WRITE,
+        _ => AccessMode::empty(),
+    };
+    if !permitted.contains(mode) {
+        return Err(KernelError::AccessDenied);
+    }
+    if mode.contains(AccessMode::WRITE) && ty != T_DEVICE {
+        vfs::check_writable(ip.dev())?;
+    }
+    Ok(())
+}
+
+impl SyscallExt for syscall::Access {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (user_path, mode): Self::Arg,
+    ) -> Self::Return {
+        sys_access(private, AT_FDCWD, user_path, mode, AtFlags::empty())?;
+        Ok(())
+    }
+}
+
+impl SyscallExt for syscall::Faccessat {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (dir_fd, user_path, mode, flags): Self::Arg,
+    ) -> Self::Return {
+        sys_access(private, dir_fd, user_path, mode, flags)?;
+        Ok(())
+    }
+}
+
+impl SyscallExt for syscall::Unlinkat {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (dir_fd, user_path, flags): Self::Arg,
+    ) -> Self::Return {
+        let mut path = [0; MAX_PATH];
+        let path = fetch_path(private, user_path, &mut path)?;
 
-        if mode.contains(OpenFlags::TRUNC) && lip.ty() == T_FILE {
-            lip.truncate();
+        let dir = dir_file(private, dir_fd)?;
+        let ctx = private.fs_context();
+        let base = base_dir(ctx.cwd, dir.as_ref());
+        if flags.contains(AtFlags::REMOVE_DIR) {
+            fs::ops::rmdir(ctx.tx_token, ctx.root, base, path)?;
+        } else {
+            fs::ops::unlink(ctx.tx_token, ctx.root, base, path)?;
         }
+        Ok(())
+    }
+}
 
-        let fd = private.add_ofile(f)?;
+impl SyscallExt for syscall::Mkdirat {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
 
-        Ok(fd)
+    fn call(
+        _p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (dir_fd, user_path): Self::Arg,
+    ) -> Self::Return {
+        let mut path = [0; MAX_PATH];
+        let path = fetch_path(private, user_path, &mut path)?;
+
+        let dir = dir_file(private, dir_fd)?;
+        let ctx = private.fs_context();
+        let base = base_dir(ctx.cwd, dir.as_ref());
+        let _ip = fs::ops::create(ctx.tx_token, ctx.root, base, path, T_DIR, DeviceNo::ROOT, 0)?;
+
+        Ok(())
     }
 }
 
@@ -214,9 +762,16 @@ impl SyscallExt for syscall::Mkdir {
         let mut path = [0; MAX_PATH];
         let path = fetch_path(private, user_path, &mut path)?;
 
-        let tx = fs::begin_tx().map_err(KernelError::from)?;
-        let cwd = private.cwd().clone().into_tx(&tx);
-        let _ip = fs::ops::create(&tx, cwd, path, T_DIR, DeviceNo::ROOT, 0)?;
+        let ctx = private.fs_context();
+        let _ip = fs::ops::create(
+            ctx.tx_token,
+            ctx.root,
+            ctx.cwd,
+            path,
+            T_DIR,
+            DeviceNo::ROOT,
+            0,
+        )?;
 
         Ok(())
     }
@@ -234,10 +789,18 @@ impl SyscallExt for syscall::Mknod {
     ) -> Self::Return {
         let mut path = [0; MAX_PATH];
         let path = fetch_path(private, user_path, &mut path)?;
+        private.require_caps(Self::CODE, Capabilities::MKNOD, Some(path))?;
 
-        let tx = fs::begin_tx().map_err(KernelError::from)?;
-        let cwd = private.cwd().clone().into_tx(&tx);
-        let _ip = fs::ops::create(&tx, cwd, path, T_DEVICE, DeviceNo::new(major), minor)?;
+        let ctx = private.fs_context();
+        let _ip = fs::ops::create(
+            ctx.tx_token,
+            ctx.root,
+            ctx.cwd,
+            path,
+            T_DEVICE,
+            DeviceNo::new(major),
+            minor,
+        )?;
 
         Ok(())
     }
@@ -255,20 +818,138 @@ impl SyscallExt for syscall::Chdir {
     ) -> Self::Return {
         let mut path = [0; MAX_PATH];
         let path = fetch_path(private, user_path, &mut path)?;
+        let cwd_path = private.normalize_path(path)?;
 
-        let tx = fs::begin_tx().map_err(KernelError::from)?;
-        let cwd = private.cwd().clone().into_tx(&tx);
-        let mut ip = fs::path::resolve(&tx, cwd, path)?;
-        if !ip.force_wait_lock().is_dir() {
+        let ctx = private.fs_context();
+        let ip = fs::path::resolve(ctx.root, ctx.cwd, path)?;
+        if vfs::stat(&ip)?.ty != T_DIR {
             return Err(KernelError::ChdirNotDir.into());
         }
-        let old = private.update_cwd(Inode::from_tx(&ip));
-        old.into_tx(&tx).put();
+        vfs::put(mem::replace(ctx.cwd, ip.into_inner()));
 
+        private.set_cwd_path(cwd_path);
         Ok(())
     }
 }
 
+impl SyscallExt for syscall::Fchdir {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(_p: &'static Proc, private: &mut Self::Private<'_>, (fd,): Self::Arg) -> Self::Return {
+        let file = private.ofile(fd)?.clone();
+        let inode = file.inode().ok_or(KernelError::DirFdNotInode(fd))?;
+
+        if vfs::stat(inode)?.ty != T_DIR {
+            return Err(KernelError::ChdirNotDir.into());
+        }
+        // the directory may have been moved or removed since it was opened,
+        // so its path is looked up again from the file system
+        let ctx = private.fs_context();
+        let cwd_path = fs::path::path_of(ctx.root, InodeGuard::new(inode.clone()))?;
+        vfs::put(mem::replace(ctx.cwd, inode.clone()));
+
+        private.set_cwd_path(cwd_path);
+        Ok(())
+    }
+}
+
+impl SyscallExt for syscall::Chroot {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (user_path,): Self::Arg,
+    ) -> Self::Return {
+        let mut path = [0; MAX_PATH];
+        let path = fetch_path(private, user_path, &mut path)?;
+        private.require_caps(Self::CODE, Capabilities::CHROOT, Some(path))?;
+        let root_path = private.normalize_path(path)?;
+
+        let ctx = private.fs_context();
+        let ip = fs::path::resolve(ctx.root, ctx.cwd, path)?;
+        if vfs::stat(&ip)?.ty != T_DIR {
+            return Err(KernelError::ChrootNotDir.into());
+        }
+        vfs::put(mem::replace(ctx.root, ip.into_inner()));
+
+        // the current directory is kept, and is seen from the new root.
+        let cwd_path = private
+            .cwd_path()
+            .zip(root_path.as_ref())
+            .and_then(|(cwd, root)| fs::path::rebase_normalized(cwd, root));
+        private.set_cwd_path(cwd_path);
+        Ok(())
+    }
+}
+
+impl SyscallExt for syscall::Mount {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (user_fstype, user_path, size): Self::Arg,
+    ) -> Self::Return {
+        // longer than any registered type name.
+        const MAX_FSTYPE: usize = 16;
+
+        let mut path = [0; MAX_PATH];
+        let path = fetch_path(private, user_path, &mut path)?;
+        private.require_caps(Self::CODE, Capabilities::RAW_IO, Some(path))?;
+
+        if user_fstype.len() > MAX_FSTYPE {
+            return Err(KernelError::FsTypeNotFound.into());
+        }
+        let user_fstype = user_fstype.validate(private.pagetable())?;
+        let mut fstype = [0; MAX_FSTYPE];
+        let fstype = &mut fstype[..user_fstype.len()];
+        private.pagetable().copy_u2k_bytes(fstype, &user_fstype);
+        #[expect(clippy::map_err_ignore)]
+        let fstype = str::from_utf8(fstype).map_err(|_| KernelError::FsTypeNotFound)?;
+
+        let ctx = private.fs_context();
+        let ip = fs::path::resolve(ctx.root, ctx.cwd, path)?;
+        if vfs::stat(&ip)?.ty != T_DIR {
+            return Err(KernelError::MountNotDir.into());
+        }
+        vfs::mount_nodev(fstype, &ip, (size != 0).then_some(size))?;
+        Ok(())
+    }
+}
+
+impl SyscallExt for syscall::Getcwd {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (user_buf,): Self::Arg,
+    ) -> Self::Return {
+        let mut user_buf = user_buf.validate(private.pagetable_mut())?;
+        let path = private
+            .cwd_path()
+            .cloned()
+            .ok_or(KernelError::CwdUnreachable)?;
+        // nothing is copied unless the whole path fits, and the length is
+        // returned so that the caller can retry with a larger buffer
+        if path.len() <= user_buf.len() {
+            private
+                .pagetable_mut()
+                .copy_k2u_bytes(&mut user_buf.take_mut(path.len()), &path);
+        }
+        Ok(path.len())
+    }
+}
+
 fn sys_exec(
     p: &'static Proc,
     private: &mut ProcPrivateData,
@@ -343,21 +1024,147 @@ impl SyscallExt for syscall::Pipe {
         (fd_array,): Self::Arg,
     ) -> Self::Return {
         let mut fd_array = fd_array.validate(private.pagetable())?;
+        let files = File::new_pipe()?;
+        let fds = add_ofile_pair(private, files, FdFlags::empty())?;
+        private.pagetable_mut().copy_k2u(&mut fd_array, &fds);
+        Ok(())
+    }
+}
 
-        let (rf, wf) = File::new_pipe()?;
+impl SyscallExt for syscall::Pipe2 {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
 
-        let rfd = private.add_ofile(rf)?;
-        let wfd = match private.add_ofile(wf) {
-            Ok(wfd) => wfd,
-            Err(e) => {
-                private.unset_ofile(rfd).unwrap();
-                return Err(e.into());
-            }
-        };
+    fn call(
+        _p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (fd_array, flags): Self::Arg,
+    ) -> Self::Return {
+        let fd_flags = fd_flags(flags)?;
+        let mut fd_array = fd_array.validate(private.pagetable())?;
+        let files = File::new_pipe()?;
+        let fds = add_ofile_pair(private, files, fd_flags)?;
+        private.pagetable_mut().copy_k2u(&mut fd_array, &fds);
+        Ok(())
+    }
+}
+
+impl SyscallExt for syscall::SocketPair {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
 
-        let fds = [rfd, wfd];
+    fn call(
+        _p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (fd_array,): Self::Arg,
+    ) -> Self::Return {
+        let mut fd_array = fd_array.validate(private.pagetable())?;
+        let files = File::new_socket_pair()?;
+        let fds = add_ofile_pair(private, files, FdFlags::empty())?;
         private.pagetable_mut().copy_k2u(&mut fd_array, &fds);
+        Ok(())
+    }
+}
 
+impl SyscallExt for syscall::LocalBind {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (user_path,): Self::Arg,
+    ) -> Self::Return {
+        let mut path = [0; MAX_PATH];
+        let path = fetch_path(private, user_path, &mut path)?;
+
+        let ctx = private.fs_context();
+        let ip = fs::ops::create(
+            ctx.tx_token,
+            ctx.root,
+            ctx.cwd,
+            path,
+            T_SOCK,
+            DeviceNo::ROOT,
+            0,
+        )?;
+        let f = File::new_bound_socket(ip.dev(), ip.ino(), ip.into_inner())?;
+        let fd = private.add_ofile(f)?;
+
+        Ok(fd)
+    }
+}
+
+impl SyscallExt for syscall::LocalListen {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (fd, backlog): Self::Arg,
+    ) -> Self::Return {
+        private.ofile(fd)?.listen(backlog)?;
         Ok(())
     }
 }
+
+impl SyscallExt for syscall::LocalAccept {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(_p: &'static Proc, private: &mut Self::Private<'_>, (fd,): Self::Arg) -> Self::Return {
+        let f = private.ofile(fd)?.clone();
+        let conn = f.accept()?;
+        let fd = private.add_ofile(conn)?;
+        Ok(fd)
+    }
+}
+
+impl SyscallExt for syscall::LocalConnect {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (user_path,): Self::Arg,
+    ) -> Self::Return {
+        let mut path = [0; MAX_PATH];
+        let path = fetch_path(private, user_path, &mut path)?;
+
+        let ctx = private.fs_context();
+        let ip = fs::path::resolve(ctx.root, ctx.cwd, path)?;
+        if vfs::stat(&ip)?.ty != T_SOCK {
+            return Err(KernelError::ConnectionRefused.into());
+        }
+        let f = File::connect_socket(ip.dev(), ip.ino())?;
+        drop(ip);
+        let fd = private.add_ofile(f)?;
+
+        Ok(fd)
+    }
+}
+
+/// Allocates file descriptors for both of `files`, or for neither of them.
+fn add_ofile_pair(
+    private: &mut ProcPrivateData,
+    (f0, f1): (File, File),
+    flags: FdFlags,
+) -> Result<[RawFd; 2], KernelError> {
+    let fd0 = private.add_ofile_with_flags(f0, flags)?;
+    let fd1 = match private.add_ofile_with_flags(f1, flags) {
+        Ok(fd1) => fd1,
+        Err(e) => {
+            private.unset_ofile(fd0).unwrap();
+            return Err(e);
+        }
+    };
+    Ok([fd0, fd1])
+}
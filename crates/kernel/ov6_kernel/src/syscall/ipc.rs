@@ -0,0 +1,163 @@
+use ov6_syscall::{MSG_SIZE_MAX, syscall};
+
+use super::SyscallExt;
+use crate::{
+    error::KernelError,
+    file::File,
+    ipc::msg_queue,
+    memory::addr::Validate as _,
+    proc::{Proc, ProcPrivateData},
+};
+
+impl SyscallExt for syscall::MsgGet {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        _private: &mut Self::Private<'_>,
+        (key, flags): Self::KernelArg,
+    ) -> Self::KernelReturn {
+        let id = msg_queue::get(key, flags)?;
+        Ok(id)
+    }
+}
+
+impl SyscallExt for syscall::MsgSend {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (id, data, priority): Self::KernelArg,
+    ) -> Self::KernelReturn {
+        if data.len() > MSG_SIZE_MAX {
+            return Err(KernelError::TooLargeMessage(data.len()).into());
+        }
+        let data = data.validate(private.pagetable())?;
+        let mut buf = [0; MSG_SIZE_MAX];
+        let buf = &mut buf[..data.len()];
+        private.pagetable().copy_u2k_bytes(buf, &data);
+        msg_queue::send(id, buf, priority)?;
+        Ok(())
+    }
+}
+
+impl SyscallExt for syscall::MsgRecv {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (id, data, priority): Self::KernelArg,
+    ) -> Self::KernelReturn {
+        let pt = private.pagetable_mut();
+        let mut data = data.validate(pt)?;
+        let mut priority = priority.validate(pt)?;
+        let msg = msg_queue::recv(id, data.len())?;
+        pt.copy_k2u_bytes(&mut data.take_mut(msg.data().len()), msg.data());
+        pt.copy_k2u(&mut priority, &msg.priority);
+        Ok(msg.data().len())
+    }
+}
+
+impl SyscallExt for syscall::MsgRemove {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        _private: &mut Self::Private<'_>,
+        (id,): Self::KernelArg,
+    ) -> Self::KernelReturn {
+        msg_queue::remove(id)?;
+        Ok(())
+    }
+}
+
+impl SyscallExt for syscall::SemOpen {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (key, value): Self::KernelArg,
+    ) -> Self::KernelReturn {
+        let file = File::open_semaphore(key, value)?;
+        let fd = private.add_ofile(file)?;
+        Ok(fd)
+    }
+}
+
+impl SyscallExt for syscall::SemWait {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (fd,): Self::KernelArg,
+    ) -> Self::KernelReturn {
+        let file = private.ofile(fd)?.clone();
+        file.sem_wait()?;
+        Ok(())
+    }
+}
+
+impl SyscallExt for syscall::SemPost {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (fd,): Self::KernelArg,
+    ) -> Self::KernelReturn {
+        private.ofile(fd)?.sem_post()?;
+        Ok(())
+    }
+}
+
+impl SyscallExt for syscall::SemClose {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (fd,): Self::KernelArg,
+    ) -> Self::KernelReturn {
+        if !private.ofile(fd)?.is_semaphore() {
+            return Err(KernelError::NotSemaphore.into());
+        }
+        let _file = private.unset_ofile(fd)?;
+        Ok(())
+    }
+}
+
+impl SyscallExt for syscall::EventFd {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (initial, flags): Self::KernelArg,
+    ) -> Self::KernelReturn {
+        let file = File::new_event(initial, flags)?;
+        let fd = private.add_ofile(file)?;
+        Ok(fd)
+    }
+}
@@ -1,18 +1,20 @@
 use core::{convert::Infallible, fmt};
 
 use ov6_syscall::{
-    Register, RegisterDecodeError, RegisterValue, Syscall, SyscallCode, error::SyscallError,
-    syscall,
+    Register, RegisterDecodeError, RegisterValue, Syscall, SyscallCode, SyscallFilterAction,
+    error::SyscallError, syscall,
 };
 
 use crate::{
+    audit,
     error::KernelError,
     interrupt::trap::TrapFrame,
     println,
-    proc::{Proc, ProcPrivateData, ProcPrivateDataGuard},
+    proc::{Proc, ProcPrivateData, ProcPrivateDataGuard, SyscallFilter},
 };
 
 mod file;
+mod ipc;
 mod net;
 mod proc;
 mod system;
@@ -225,8 +227,12 @@ trait SyscallExt: Syscall {
             Err(e) => e.into_return(),
         };
 
+        // codes that do not fit in the mask are never traced.
         let trace_mask = private.get_trace_mask();
-        if trace_mask & (1 << Self::CODE as usize) != 0 {
+        if 1_u64
+            .checked_shl(Self::CODE as u32)
+            .is_some_and(|bit| trace_mask & bit != 0)
+        {
             let arg = <Self::KernelArg as RegisterValue>::Repr::decode_arg(private.get_trapframe());
             trace(p, Self::CODE, arg.as_ref().ok(), Some(&ret));
         }
@@ -241,6 +247,7 @@ trait SyscallExt: Syscall {
     ) -> Self::KernelReturn;
 }
 
+#[expect(clippy::too_many_lines)]
 pub fn syscall(p: &'static Proc, private_opt: &mut Option<ProcPrivateDataGuard>) {
     let private = private_opt.as_mut().unwrap();
     let tf = private.trapframe_mut();
@@ -254,6 +261,28 @@ pub fn syscall(p: &'static Proc, private_opt: &mut Option<ProcPrivateDataGuard>)
         return;
     };
 
+    if let Some(action) = private
+        .syscall_filter()
+        .filter(|filter| !filter.allows(ty))
+        .map(SyscallFilter::action)
+    {
+        audit::record(private.pid(), ty, SyscallError::NotPermitted, None);
+        match action {
+            SyscallFilterAction::Deny => {
+                let ret: Result<(), SyscallError> = Err(KernelError::SyscallFiltered(ty).into());
+                ReturnValue::from(ret.encode()).store(private.trapframe_mut());
+            }
+            SyscallFilterAction::Kill => {
+                let mut shared = p.shared().lock();
+                let pid = shared.pid();
+                let name = shared.name().display();
+                println!("{pid} {name}: killed by syscall filter: {ty}");
+                shared.kill();
+            }
+        }
+        return;
+    }
+
     let ret = match ty {
         SyscallCode::Fork => syscall::Fork::handle(p, private),
         SyscallCode::Exit => syscall::Exit::handle(p, private_opt),
@@ -288,6 +317,70 @@ pub fn syscall(p: &'static Proc, private_opt: &mut Option<ProcPrivateDataGuard>)
         SyscallCode::Trace => syscall::Trace::handle(p, private),
         SyscallCode::DumpKernelPageTable => syscall::DumpKernelPageTable::handle(p, private),
         SyscallCode::DumpUserPageTable => syscall::DumpUserPageTable::handle(p, private),
+        SyscallCode::Chroot => syscall::Chroot::handle(p, private),
+        SyscallCode::DropCaps => syscall::DropCaps::handle(p, private),
+        SyscallCode::SetSyscallFilter => syscall::SetSyscallFilter::handle(p, private),
+        SyscallCode::ReadAuditLog => syscall::ReadAuditLog::handle(p, private),
+        SyscallCode::Ioctl => syscall::Ioctl::handle(p, private),
+        SyscallCode::RequestShutdown => syscall::RequestShutdown::handle(p, private),
+        SyscallCode::WaitShutdownRequest => syscall::WaitShutdownRequest::handle(p, private),
+        SyscallCode::Sync => syscall::Sync::handle(p, private),
+        SyscallCode::Fstatfs => syscall::Fstatfs::handle(p, private),
+        SyscallCode::Rmdir => syscall::Rmdir::handle(p, private),
+        SyscallCode::Readv => syscall::Readv::handle(p, private),
+        SyscallCode::Writev => syscall::Writev::handle(p, private),
+        SyscallCode::SendFile => syscall::SendFile::handle(p, private),
+        SyscallCode::Pread => syscall::Pread::handle(p, private),
+        SyscallCode::Pwrite => syscall::Pwrite::handle(p, private),
+        SyscallCode::ResizeFs => syscall::ResizeFs::handle(p, private),
+        SyscallCode::InjectAllocFault => syscall::InjectAllocFault::handle(p, private),
+        SyscallCode::Fcntl => syscall::Fcntl::handle(p, private),
+        SyscallCode::Remount => syscall::Remount::handle(p, private),
+        SyscallCode::Splice => syscall::Splice::handle(p, private),
+        SyscallCode::SocketPair => syscall::SocketPair::handle(p, private),
+        SyscallCode::LocalBind => syscall::LocalBind::handle(p, private),
+        SyscallCode::LocalListen => syscall::LocalListen::handle(p, private),
+        SyscallCode::LocalAccept => syscall::LocalAccept::handle(p, private),
+        SyscallCode::LocalConnect => syscall::LocalConnect::handle(p, private),
+        SyscallCode::MsgGet => syscall::MsgGet::handle(p, private),
+        SyscallCode::MsgSend => syscall::MsgSend::handle(p, private),
+        SyscallCode::MsgRecv => syscall::MsgRecv::handle(p, private),
+        SyscallCode::MsgRemove => syscall::MsgRemove::handle(p, private),
+        SyscallCode::SemOpen => syscall::SemOpen::handle(p, private),
+        SyscallCode::SemWait => syscall::SemWait::handle(p, private),
+        SyscallCode::SemPost => syscall::SemPost::handle(p, private),
+        SyscallCode::SemClose => syscall::SemClose::handle(p, private),
+        SyscallCode::EventFd => syscall::EventFd::handle(p, private),
+        SyscallCode::SetProcessGroup => syscall::SetProcessGroup::handle(p, private),
+        SyscallCode::GetProcessGroup => syscall::GetProcessGroup::handle(p, private),
+        SyscallCode::SetInterruptAction => syscall::SetInterruptAction::handle(p, private),
+        SyscallCode::SetInterruptHandler => syscall::SetInterruptHandler::handle(p, private),
+        SyscallCode::SetIoLimit => syscall::SetIoLimit::handle(p, private),
+        SyscallCode::GetIoStats => syscall::GetIoStats::handle(p, private),
+        SyscallCode::GetHostname => syscall::GetHostname::handle(p, private),
+        SyscallCode::SetHostname => syscall::SetHostname::handle(p, private),
+        SyscallCode::Flink => syscall::Flink::handle(p, private),
+        SyscallCode::Dup2 => syscall::Dup2::handle(p, private),
+        SyscallCode::Pipe2 => syscall::Pipe2::handle(p, private),
+        SyscallCode::Dup3 => syscall::Dup3::handle(p, private),
+        SyscallCode::SetNice => syscall::SetNice::handle(p, private),
+        SyscallCode::SetAffinity => syscall::SetAffinity::handle(p, private),
+        SyscallCode::GetAffinity => syscall::GetAffinity::handle(p, private),
+        SyscallCode::Getcwd => syscall::Getcwd::handle(p, private),
+        SyscallCode::Openat => syscall::Openat::handle(p, private),
+        SyscallCode::Fstatat => syscall::Fstatat::handle(p, private),
+        SyscallCode::Unlinkat => syscall::Unlinkat::handle(p, private),
+        SyscallCode::Mkdirat => syscall::Mkdirat::handle(p, private),
+        SyscallCode::Fchdir => syscall::Fchdir::handle(p, private),
+        SyscallCode::Umask => syscall::Umask::handle(p, private),
+        SyscallCode::Access => syscall::Access::handle(p, private),
+        SyscallCode::Faccessat => syscall::Faccessat::handle(p, private),
+        SyscallCode::Ptrace => syscall::Ptrace::handle(p, private),
+        SyscallCode::PtraceGetRegs => syscall::PtraceGetRegs::handle(p, private),
+        SyscallCode::PtraceSetRegs => syscall::PtraceSetRegs::handle(p, private),
+        SyscallCode::GetKernelSymbols => syscall::GetKernelSymbols::handle(p, private),
+        SyscallCode::Getcpu => syscall::Getcpu::handle(p, private),
+        SyscallCode::Mount => syscall::Mount::handle(p, private),
     };
 
     let private = private_opt.as_mut().unwrap();
@@ -1,14 +1,17 @@
-use core::convert::Infallible;
+use core::{array, convert::Infallible};
 
-use ov6_syscall::{Register, RegisterValue, Syscall, error::SyscallError, syscall};
+use ov6_syscall::{
+    Capabilities, InterruptAction, NICE_MAX, NICE_MIN, Register, RegisterValue,
+    SYSCALL_FILTER_WORDS, Syscall, SyscallCode, SyscallFilterMask, error::SyscallError, syscall,
+};
 
 use super::SyscallExt;
 use crate::{
+    cpu,
     error::KernelError,
-    interrupt::timer::{NANOS_PER_TICKS, TICKS, TICKS_UPDATED},
+    interrupt::{self, timer_wheel},
     memory::{VirtAddr, addr::Validate as _},
-    proc::{self, Proc, ProcPrivateData, ProcPrivateDataGuard},
-    sync::WaitError,
+    proc::{self, AlarmInfo, OnInterrupt, Proc, ProcPrivateData, ProcPrivateDataGuard},
 };
 
 impl SyscallExt for syscall::Fork {
@@ -92,12 +95,12 @@ impl SyscallExt for syscall::Sbrk {
     type Private<'a> = ProcPrivateData;
 
     fn call(
-        _p: &'static Proc,
+        p: &'static Proc,
         private: &mut Self::Private<'_>,
         (increment,): Self::Arg,
     ) -> Self::Return {
         let pb = private.program_break();
-        proc::ops::resize_by(private, increment)?;
+        proc::ops::resize_by(p, private, increment)?;
         Ok(pb.addr())
     }
 }
@@ -112,21 +115,7 @@ impl SyscallExt for syscall::Sleep {
         _private: &mut Self::Private<'_>,
         (dur,): Self::Arg,
     ) -> Self::Return {
-        let sleep_ticks = (dur.as_nanos().div_ceil(u128::from(NANOS_PER_TICKS)))
-            .try_into()
-            .unwrap_or(u64::MAX);
-
-        let mut ticks = TICKS.lock();
-        let ticks0 = *ticks;
-        let end_ticks = ticks0.saturating_add(sleep_ticks);
-        while *ticks < end_ticks {
-            ticks = TICKS_UPDATED.wait(ticks).map_err(
-                |(_ticks, WaitError::WaitingProcessAlreadyKilled)| {
-                    KernelError::CallerProcessAlreadyKilled
-                },
-            )?;
-        }
-
+        timer_wheel::sleep(dur)?;
         Ok(())
     }
 }
@@ -142,8 +131,9 @@ impl SyscallExt for syscall::AlarmSet {
         (dur, handler): Self::KernelArg,
     ) -> Self::KernelReturn {
         let handler = VirtAddr::new(handler.addr())?;
+        let alarm = AlarmInfo::new(p, dur, handler)?;
         let mut shared = p.shared().lock();
-        shared.set_alarm(dur, handler);
+        shared.set_alarm(alarm);
         Ok(())
     }
 }
@@ -164,6 +154,73 @@ impl SyscallExt for syscall::AlarmClear {
     }
 }
 
+impl SyscallExt for syscall::SetProcessGroup {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        p: &'static Proc,
+        _private: &mut Self::Private<'_>,
+        (pid, pgid): Self::KernelArg,
+    ) -> Self::KernelReturn {
+        proc::ops::set_process_group(p, pid, pgid)?;
+        Ok(())
+    }
+}
+
+impl SyscallExt for syscall::GetProcessGroup {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        _private: &mut Self::Private<'_>,
+        (pid,): Self::KernelArg,
+    ) -> Self::KernelReturn {
+        let pgid = proc::ops::process_group(pid)?;
+        Ok(pgid)
+    }
+}
+
+impl SyscallExt for syscall::SetInterruptAction {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        p: &'static Proc,
+        _private: &mut Self::Private<'_>,
+        (action,): Self::KernelArg,
+    ) -> Self::KernelReturn {
+        let action = match action {
+            InterruptAction::Terminate => OnInterrupt::Terminate,
+            InterruptAction::Ignore => OnInterrupt::Ignore,
+        };
+        p.shared().lock().set_on_interrupt(action);
+        Ok(())
+    }
+}
+
+impl SyscallExt for syscall::SetInterruptHandler {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        p: &'static Proc,
+        _private: &mut Self::Private<'_>,
+        (handler,): Self::KernelArg,
+    ) -> Self::KernelReturn {
+        let handler = VirtAddr::new(handler.addr())?;
+        p.shared()
+            .lock()
+            .set_on_interrupt(OnInterrupt::Catch(handler));
+        Ok(())
+    }
+}
+
 #[derive(Debug)]
 pub(super) enum SignalReturn {
     Ok(usize, usize),
@@ -189,6 +246,167 @@ impl RegisterValue for SignalReturn {
     }
 }
 
+impl SyscallExt for syscall::SetIoLimit {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        p: &'static Proc,
+        _private: &mut Self::Private<'_>,
+        (limit,): Self::KernelArg,
+    ) -> Self::KernelReturn {
+        p.shared().lock().io_mut().set_limit(limit);
+        Ok(())
+    }
+}
+
+impl SyscallExt for syscall::SetNice {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (nice,): Self::KernelArg,
+    ) -> Self::KernelReturn {
+        if !(NICE_MIN..=NICE_MAX).contains(&nice) {
+            return Err(KernelError::InvalidNice(nice).into());
+        }
+        // raising the priority is privileged
+        if nice < p.shared().lock().nice() {
+            private.require_caps(Self::CODE, Capabilities::SET_PRIORITY, None)?;
+        }
+        p.shared().lock().set_nice(nice);
+        Ok(())
+    }
+}
+
+impl SyscallExt for syscall::Umask {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (mask,): Self::KernelArg,
+    ) -> Self::KernelReturn {
+        Ok(private.replace_umask(mask))
+    }
+}
+
+impl SyscallExt for syscall::SetAffinity {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        p: &'static Proc,
+        _private: &mut Self::Private<'_>,
+        (pid, affinity): Self::KernelArg,
+    ) -> Self::KernelReturn {
+        proc::ops::set_affinity(p, pid, affinity)?;
+        Ok(())
+    }
+}
+
+impl SyscallExt for syscall::GetAffinity {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        _private: &mut Self::Private<'_>,
+        (pid,): Self::KernelArg,
+    ) -> Self::KernelReturn {
+        let affinity = proc::ops::affinity(pid)?;
+        Ok(affinity)
+    }
+}
+
+impl SyscallExt for syscall::Getcpu {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        _private: &mut Self::Private<'_>,
+        (): Self::KernelArg,
+    ) -> Self::KernelReturn {
+        interrupt::with_push_disabled(cpu::id)
+    }
+}
+
+impl SyscallExt for syscall::GetIoStats {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (pid, user_stats): Self::KernelArg,
+    ) -> Self::KernelReturn {
+        let mut user_stats = user_stats.validate(private.pagetable_mut())?;
+        let stats = proc::ops::io_stats(pid)?;
+        private.pagetable_mut().copy_k2u(&mut user_stats, &stats);
+        Ok(())
+    }
+}
+
+impl SyscallExt for syscall::Ptrace {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        p: &'static Proc,
+        _private: &mut Self::Private<'_>,
+        (request, pid, addr, data): Self::KernelArg,
+    ) -> Self::KernelReturn {
+        let ret = proc::ptrace::request(p, request, pid, addr, data)?;
+        Ok(ret)
+    }
+}
+
+impl SyscallExt for syscall::PtraceGetRegs {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (pid, user_regs): Self::KernelArg,
+    ) -> Self::KernelReturn {
+        let mut user_regs = user_regs.validate(private.pagetable())?;
+        let regs = proc::ptrace::get_regs(p, pid)?;
+        private.pagetable_mut().copy_k2u(&mut user_regs, &regs);
+        Ok(())
+    }
+}
+
+impl SyscallExt for syscall::PtraceSetRegs {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (pid, user_regs): Self::KernelArg,
+    ) -> Self::KernelReturn {
+        let user_regs = user_regs.validate(private.pagetable())?;
+        let regs = private.pagetable().copy_u2k(&user_regs);
+        proc::ptrace::set_regs(p, pid, &regs)?;
+        Ok(())
+    }
+}
+
 impl SyscallExt for syscall::SignalReturn {
     type KernelArg = Self::Arg;
     type KernelReturn = SignalReturn;
@@ -223,6 +441,55 @@ impl SyscallExt for syscall::Trace {
     }
 }
 
+impl SyscallExt for syscall::DropCaps {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (caps,): Self::KernelArg,
+    ) -> Self::KernelReturn {
+        private.drop_caps(caps);
+        Ok(private.caps())
+    }
+}
+
+impl SyscallExt for syscall::SetSyscallFilter {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static Proc,
+        private: &mut Self::Private<'_>,
+        (allowed, action): Self::KernelArg,
+    ) -> Self::KernelReturn {
+        if allowed.len() != SYSCALL_FILTER_WORDS {
+            return Err(KernelError::InvalidSyscallFilterSize(allowed.len()).into());
+        }
+        let allowed = allowed.validate(private.pagetable())?;
+        let mask: SyscallFilterMask =
+            array::from_fn(|i| private.pagetable().copy_u2k(&allowed.nth(i)));
+
+        // reject bits that do not name a system call
+        for (i, word) in mask.iter().enumerate() {
+            let mut bits = *word;
+            while bits != 0 {
+                let n = i * 64 + bits.trailing_zeros() as usize;
+                if SyscallCode::from_repr(n).is_none() {
+                    return Err(KernelError::InvalidSyscallFilterCode(n).into());
+                }
+                bits &= bits - 1;
+            }
+        }
+
+        private.add_syscall_filter(mask, action);
+        Ok(())
+    }
+}
+
 impl SyscallExt for syscall::DumpUserPageTable {
     type KernelArg = Self::Arg;
     type KernelReturn = Self::Return;
@@ -1,10 +1,21 @@
-use ov6_syscall::{SystemInfo, syscall};
+use ov6_syscall::{
+    Capabilities, HOST_NAME_MAX, SYSTEM_INFO_VERSION, ShutdownRequest, Syscall as _, SystemInfo,
+    syscall,
+};
+use safe_cast::SafeInto as _;
 
 use super::SyscallExt;
 use crate::{
+    audit,
     device::test::{self, Finisher},
-    memory::{self, addr::Validate as _, vm_kernel},
-    proc::ProcPrivateData,
+    error::KernelError,
+    file,
+    fs::{self, DeviceNo},
+    hostname, interrupt, kallsyms,
+    memory::{self, addr::Validate as _, fallible, vm_kernel},
+    net,
+    proc::{self, ProcPrivateData},
+    shutdown,
 };
 
 impl SyscallExt for syscall::GetSystemInfo {
@@ -19,7 +30,16 @@ impl SyscallExt for syscall::GetSystemInfo {
     ) -> Self::KernelReturn {
         let mut user_sysinfo = user_sysinfo.validate(private.pagetable_mut())?;
         let sysinfo = SystemInfo {
+            version: SYSTEM_INFO_VERSION,
             memory: memory::info(),
+            heap: memory::heap::info(),
+            page_cache: fs::page_cache_info(),
+            name_cache: fs::name_cache_info(),
+            disk: fs::disk_info(),
+            interrupts: interrupt::plic::info(),
+            net: net::info(),
+            files: file::info(),
+            load: proc::load::info(),
         };
         private
             .pagetable_mut()
@@ -35,11 +55,12 @@ impl SyscallExt for syscall::Reboot {
 
     fn call(
         _p: &'static crate::proc::Proc,
-        _private: &mut Self::Private<'_>,
+        private: &mut Self::Private<'_>,
         (): Self::Arg,
     ) -> Self::Return {
+        private.require_caps(Self::CODE, Capabilities::REBOOT, None)?;
         crate::println!("ov6 - reboot requested");
-        test::finish(Finisher::Reset);
+        shutdown::shutdown(Finisher::Reset);
     }
 }
 
@@ -50,15 +71,51 @@ impl SyscallExt for syscall::Halt {
 
     fn call(
         _p: &'static crate::proc::Proc,
-        _private: &mut Self::Private<'_>,
+        private: &mut Self::Private<'_>,
         (code,): Self::Arg,
     ) -> Self::Return {
+        private.require_caps(Self::CODE, Capabilities::HALT, None)?;
         crate::println!("ov6 - halt requested");
-        test::finish(Finisher::Pass(code));
+        shutdown::shutdown(Finisher::Pass(code));
     }
 }
 
-impl SyscallExt for syscall::Abort {
+impl SyscallExt for syscall::RequestShutdown {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static crate::proc::Proc,
+        private: &mut Self::Private<'_>,
+        (req,): Self::Arg,
+    ) -> Self::Return {
+        let caps = match req {
+            ShutdownRequest::Halt(_) => Capabilities::HALT,
+            ShutdownRequest::Reboot => Capabilities::REBOOT,
+        };
+        private.require_caps(Self::CODE, caps, None)?;
+        shutdown::request(req);
+        Ok(())
+    }
+}
+
+impl SyscallExt for syscall::WaitShutdownRequest {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static crate::proc::Proc,
+        _private: &mut Self::Private<'_>,
+        (): Self::Arg,
+    ) -> Self::Return {
+        let req = shutdown::wait_request()?;
+        Ok(req)
+    }
+}
+
+impl SyscallExt for syscall::Sync {
     type KernelArg = Self::Arg;
     type KernelReturn = Self::Return;
     type Private<'a> = ProcPrivateData;
@@ -66,8 +123,74 @@ impl SyscallExt for syscall::Abort {
     fn call(
         _p: &'static crate::proc::Proc,
         _private: &mut Self::Private<'_>,
+        (): Self::Arg,
+    ) -> Self::Return {
+        fs::sync()?;
+        Ok(())
+    }
+}
+
+impl SyscallExt for syscall::ResizeFs {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static crate::proc::Proc,
+        private: &mut Self::Private<'_>,
+        (blocks,): Self::Arg,
+    ) -> Self::Return {
+        private.require_caps(Self::CODE, Capabilities::RAW_IO, None)?;
+        // 0 grows the file system as large as possible
+        let size = (blocks != 0).then(|| u32::try_from(blocks).unwrap_or(u32::MAX));
+        let size = fs::resize(DeviceNo::ROOT, size)?;
+        Ok(size.safe_into())
+    }
+}
+
+impl SyscallExt for syscall::Remount {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static crate::proc::Proc,
+        private: &mut Self::Private<'_>,
+        (flags,): Self::Arg,
+    ) -> Self::Return {
+        private.require_caps(Self::CODE, Capabilities::RAW_IO, None)?;
+        fs::remount(DeviceNo::ROOT, flags)?;
+        Ok(())
+    }
+}
+
+impl SyscallExt for syscall::InjectAllocFault {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static crate::proc::Proc,
+        private: &mut Self::Private<'_>,
+        (count,): Self::Arg,
+    ) -> Self::Return {
+        let count = usize::try_from(count).unwrap_or(usize::MAX);
+        fallible::inject_fault(private.pid(), count);
+        Ok(())
+    }
+}
+
+impl SyscallExt for syscall::Abort {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static crate::proc::Proc,
+        private: &mut Self::Private<'_>,
         (code,): Self::Arg,
     ) -> Self::Return {
+        private.require_caps(Self::CODE, Capabilities::ABORT, None)?;
         crate::println!("ov6 - abort requested");
         test::finish(Finisher::Fail(code));
     }
@@ -86,3 +209,99 @@ impl SyscallExt for syscall::DumpKernelPageTable {
         vm_kernel::dump();
     }
 }
+
+impl SyscallExt for syscall::ReadAuditLog {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static crate::proc::Proc,
+        private: &mut Self::Private<'_>,
+        (user_records,): Self::Arg,
+    ) -> Self::Return {
+        private.require_caps(Self::CODE, Capabilities::AUDIT, None)?;
+
+        let mut user_records = user_records.validate(private.pagetable_mut())?;
+        let mut seq = 0;
+        let mut count = 0;
+        while count < user_records.len() {
+            let Some(record) = audit::find(seq) else {
+                break;
+            };
+            private
+                .pagetable_mut()
+                .copy_k2u(&mut user_records.nth_mut(count), &record);
+            seq = record.seq + 1;
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+impl SyscallExt for syscall::GetHostname {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static crate::proc::Proc,
+        private: &mut Self::Private<'_>,
+        (user_name,): Self::Arg,
+    ) -> Self::Return {
+        let mut user_name = user_name.validate(private.pagetable_mut())?;
+        // the name is truncated to the buffer, and the full length returned
+        let name = hostname::get();
+        let len = usize::min(name.len(), user_name.len());
+        private
+            .pagetable_mut()
+            .copy_k2u_bytes(&mut user_name.take_mut(len), &name[..len]);
+        Ok(name.len())
+    }
+}
+
+impl SyscallExt for syscall::SetHostname {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static crate::proc::Proc,
+        private: &mut Self::Private<'_>,
+        (user_name,): Self::Arg,
+    ) -> Self::Return {
+        private.require_caps(Self::CODE, Capabilities::SET_HOSTNAME, None)?;
+        if user_name.len() > HOST_NAME_MAX {
+            return Err(KernelError::InvalidHostname.into());
+        }
+        let user_name = user_name.validate(private.pagetable())?;
+        let mut name = [0; HOST_NAME_MAX];
+        let name = &mut name[..user_name.len()];
+        private.pagetable().copy_u2k_bytes(name, &user_name);
+        hostname::set(name)?;
+        Ok(())
+    }
+}
+
+impl SyscallExt for syscall::GetKernelSymbols {
+    type KernelArg = Self::Arg;
+    type KernelReturn = Self::Return;
+    type Private<'a> = ProcPrivateData;
+
+    fn call(
+        _p: &'static crate::proc::Proc,
+        private: &mut Self::Private<'_>,
+        (user_buf, offset): Self::Arg,
+    ) -> Self::Return {
+        // the addresses reveal the layout of the kernel
+        private.require_caps(Self::CODE, Capabilities::KERNEL_SYMBOLS, None)?;
+        let mut user_buf = user_buf.validate(private.pagetable_mut())?;
+        let table = kallsyms::table();
+        let src = table.get(offset..).unwrap_or_default();
+        let len = usize::min(src.len(), user_buf.len());
+        private
+            .pagetable_mut()
+            .copy_k2u_bytes(&mut user_buf.take_mut(len), &src[..len]);
+        Ok(len)
+    }
+}
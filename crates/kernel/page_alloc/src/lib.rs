@@ -1,5 +1,6 @@
 #![cfg_attr(not(test), no_std)]
 
 pub mod page_frame_allocator;
+pub mod page_ops;
 
 pub use self::page_frame_allocator::PageFrameAllocator;
@@ -1,5 +1,11 @@
 use core::{ops::Range, ptr::NonNull};
 
+use crate::page_ops;
+
+/// Byte pattern that free pages are filled with.
+#[cfg(feature = "poison")]
+pub const POISON: u8 = 0x6b;
+
 /// Represents a single run in the free list of the page allocator.
 struct Run {
     /// Pointer to the next run in the free list.
@@ -11,6 +17,11 @@ struct Run {
 /// This allocator manages a range of physical memory and provides methods
 /// for allocating and freeing pages. It uses a free list to track available
 /// pages.
+///
+/// With the `poison` feature, free pages are filled with [`POISON`] and
+/// tracked in a bitmap stored at the start of the managed range. Freeing a
+/// page twice, or writing to a page after it is freed, panics with the
+/// address of the page.
 #[derive(Debug)]
 pub struct PageFrameAllocator<const PAGE_SIZE: usize> {
     /// The range of physical memory managed by the allocator.
@@ -21,6 +32,9 @@ pub struct PageFrameAllocator<const PAGE_SIZE: usize> {
     total_pages: usize,
     /// The number of free pages currently available for allocation.
     free_pages: usize,
+    /// Bitmap of free pages, one bit per page in `heap`.
+    #[cfg(feature = "poison")]
+    free_bitmap: NonNull<[usize]>,
 }
 
 impl<const PAGE_SIZE: usize> PageFrameAllocator<PAGE_SIZE> {
@@ -49,12 +63,19 @@ impl<const PAGE_SIZE: usize> PageFrameAllocator<PAGE_SIZE> {
         assert_eq!(heap.start.addr().get() % PAGE_SIZE, 0);
         assert_eq!(heap.end.addr().get() % PAGE_SIZE, 0);
 
+        #[cfg(feature = "poison")]
+        let (heap, free_bitmap) = unsafe { Self::take_bitmap(heap) };
+
         let mut total_pages = 0;
         let mut free_list = None;
         let mut p = heap.end;
 
         while p > heap.start {
             p = unsafe { p.byte_sub(PAGE_SIZE) };
+            #[cfg(feature = "poison")]
+            unsafe {
+                p.write_bytes(POISON, PAGE_SIZE);
+            }
             let mut run = p.cast::<Run>();
             unsafe {
                 run.as_mut().next = free_list;
@@ -63,11 +84,86 @@ impl<const PAGE_SIZE: usize> PageFrameAllocator<PAGE_SIZE> {
             total_pages += 1;
         }
 
-        Self {
+        #[cfg_attr(not(feature = "poison"), expect(unused_mut))]
+        let mut this = Self {
             heap,
             free_list,
             total_pages,
             free_pages: total_pages,
+            #[cfg(feature = "poison")]
+            free_bitmap,
+        };
+        #[cfg(feature = "poison")]
+        for n in 0..total_pages {
+            let (word, bit) = bitmap_index(n);
+            unsafe {
+                this.free_bitmap.as_mut()[word] |= bit;
+            }
+        }
+        this
+    }
+
+    /// Splits the pages that hold the bitmap of free pages off the start of
+    /// `heap`.
+    ///
+    /// Returns the remaining range and the zero-filled bitmap.
+    #[cfg(feature = "poison")]
+    unsafe fn take_bitmap(heap: Range<NonNull<u8>>) -> (Range<NonNull<u8>>, NonNull<[usize]>) {
+        let pages = (heap.end.addr().get() - heap.start.addr().get()) / PAGE_SIZE;
+        let words = pages.div_ceil(usize::BITS as usize);
+        let bitmap_pages = (words * size_of::<usize>()).div_ceil(PAGE_SIZE);
+        assert!(bitmap_pages < pages, "heap is too small for the bitmap");
+
+        let bitmap = NonNull::slice_from_raw_parts(heap.start.cast::<usize>(), words);
+        unsafe {
+            bitmap.cast::<usize>().write_bytes(0, words);
+        }
+        let start = unsafe { heap.start.byte_add(bitmap_pages * PAGE_SIZE) };
+        (start..heap.end, bitmap)
+    }
+
+    /// Returns the index of the word and the mask of the bit for `page` in
+    /// the bitmap of free pages.
+    #[cfg(feature = "poison")]
+    fn page_bit(&self, page: NonNull<u8>) -> (usize, usize) {
+        let n = (page.addr().get() - self.heap.start.addr().get()) / PAGE_SIZE;
+        bitmap_index(n)
+    }
+
+    /// Checks that `page` taken from the free list is free and has not been
+    /// written since it was freed, and marks it as allocated.
+    #[cfg(feature = "poison")]
+    fn check_alloc(&mut self, page: NonNull<u8>) {
+        let (word, bit) = self.page_bit(page);
+        let bitmap = unsafe { self.free_bitmap.as_mut() };
+        assert!(
+            bitmap[word] & bit != 0,
+            "allocating page {page:p} that is already allocated"
+        );
+        bitmap[word] &= !bit;
+
+        let bytes = unsafe { core::slice::from_raw_parts(page.as_ptr(), PAGE_SIZE) };
+        if let Some(off) = bytes[size_of::<Run>()..].iter().position(|&b| b != POISON) {
+            let off = size_of::<Run>() + off;
+            panic!(
+                "page {page:p} was written after freed: {:#x} at {:p}",
+                bytes[off],
+                unsafe { page.byte_add(off) }
+            );
+        }
+    }
+
+    /// Checks that `page` is allocated, marks it as free, and fills it with
+    /// [`POISON`].
+    #[cfg(feature = "poison")]
+    fn check_free(&mut self, page: NonNull<u8>) {
+        let (word, bit) = self.page_bit(page);
+        let bitmap = unsafe { self.free_bitmap.as_mut() };
+        assert!(bitmap[word] & bit == 0, "double free of page {page:p}");
+        bitmap[word] |= bit;
+
+        unsafe {
+            page.write_bytes(POISON, PAGE_SIZE);
         }
     }
 
@@ -105,6 +201,8 @@ impl<const PAGE_SIZE: usize> PageFrameAllocator<PAGE_SIZE> {
         let page = self.free_list.take()?;
         self.free_list = unsafe { page.as_ref().next };
         self.free_pages -= 1;
+        #[cfg(feature = "poison")]
+        self.check_alloc(page.cast());
         Some(page.cast())
     }
 
@@ -115,7 +213,7 @@ impl<const PAGE_SIZE: usize> PageFrameAllocator<PAGE_SIZE> {
     pub fn alloc_zeroed(&mut self) -> Option<NonNull<u8>> {
         let page = self.alloc()?;
         unsafe {
-            page.cast::<u8>().write_bytes(0, PAGE_SIZE);
+            page_ops::zero(page, PAGE_SIZE);
         }
         Some(page)
     }
@@ -134,10 +232,14 @@ impl<const PAGE_SIZE: usize> PageFrameAllocator<PAGE_SIZE> {
     ///
     /// - The given page is not within the managed heap range.
     /// - The given page is not page-aligned.
+    /// - The given page is already free (`poison` feature only).
     pub unsafe fn free(&mut self, page: NonNull<u8>) {
         assert!(self.heap.contains(&page));
         assert_eq!(page.addr().get() % PAGE_SIZE, 0);
 
+        #[cfg(feature = "poison")]
+        self.check_free(page);
+
         unsafe {
             let mut run = page.cast::<Run>();
             run.as_mut().next = self.free_list;
@@ -149,6 +251,14 @@ impl<const PAGE_SIZE: usize> PageFrameAllocator<PAGE_SIZE> {
 
 unsafe impl<const PAGE_SIZE: usize> Send for PageFrameAllocator<PAGE_SIZE> {}
 
+/// Returns the index of the word and the mask of the bit for the `n`-th page
+/// in the bitmap of free pages.
+#[cfg(feature = "poison")]
+fn bitmap_index(n: usize) -> (usize, usize) {
+    let bits = usize::BITS as usize;
+    (n / bits, 1 << (n % bits))
+}
+
 #[cfg(test)]
 mod tests {
     use core::cell::UnsafeCell;
@@ -157,6 +267,9 @@ mod tests {
     use super::*;
 
     const PAGE_SIZE: usize = 64;
+    /// Number of pages available in `Heap` (one page holds the bitmap with
+    /// the `poison` feature).
+    const TOTAL_PAGES: usize = if cfg!(feature = "poison") { 99 } else { 100 };
 
     #[repr(align(64))]
     struct Heap(UnsafeCell<[u8; PAGE_SIZE * 100]>);
@@ -193,11 +306,11 @@ mod tests {
         let mut pages = vec![];
         let mut addrs = HashSet::new();
 
-        assert_eq!(allocator.total_pages(), 100);
-        assert_eq!(allocator.free_pages(), 100);
+        assert_eq!(allocator.total_pages(), TOTAL_PAGES);
+        assert_eq!(allocator.free_pages(), TOTAL_PAGES);
 
         // allocate all pages
-        for _ in 0..100 {
+        for _ in 0..TOTAL_PAGES {
             let page = allocator.alloc().unwrap();
             assert_eq!(page.addr().get() % PAGE_SIZE, 0, "page is not aligned");
             assert!(addrs.insert(page.addr()), "page is duplicated");
@@ -227,6 +340,50 @@ mod tests {
                 allocator.free(page);
             }
         }
-        assert_eq!(allocator.free_pages(), 100);
+        assert_eq!(allocator.free_pages(), TOTAL_PAGES);
+    }
+
+    #[cfg(feature = "poison")]
+    #[test]
+    fn test_poison() {
+        let heap = Heap(UnsafeCell::new([0; PAGE_SIZE * 100]));
+        let mut allocator = unsafe { PageFrameAllocator::<PAGE_SIZE>::new(heap.range()) };
+
+        let page = allocator.alloc().unwrap();
+        unsafe {
+            page.write_bytes(0, PAGE_SIZE);
+            allocator.free(page);
+        }
+        let bytes = unsafe { core::slice::from_raw_parts(page.as_ptr(), PAGE_SIZE) };
+        assert!(bytes[size_of::<Run>()..].iter().all(|&b| b == POISON));
+    }
+
+    #[cfg(feature = "poison")]
+    #[test]
+    #[should_panic = "double free of page"]
+    fn test_double_free() {
+        let heap = Heap(UnsafeCell::new([0; PAGE_SIZE * 100]));
+        let mut allocator = unsafe { PageFrameAllocator::<PAGE_SIZE>::new(heap.range()) };
+
+        let page = allocator.alloc().unwrap();
+        unsafe {
+            allocator.free(page);
+            allocator.free(page);
+        }
+    }
+
+    #[cfg(feature = "poison")]
+    #[test]
+    #[should_panic = "was written after freed"]
+    fn test_use_after_free() {
+        let heap = Heap(UnsafeCell::new([0; PAGE_SIZE * 100]));
+        let mut allocator = unsafe { PageFrameAllocator::<PAGE_SIZE>::new(heap.range()) };
+
+        let page = allocator.alloc().unwrap();
+        unsafe {
+            allocator.free(page);
+            page.byte_add(PAGE_SIZE - 1).write(0);
+        }
+        let _ = allocator.alloc();
     }
 }
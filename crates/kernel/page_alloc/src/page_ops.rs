@@ -0,0 +1,141 @@
+//! Zeroing and copying of whole pages.
+//!
+//! `write_bytes` and `copy_from` on a page end up in the generic `memset` and
+//! `memcpy` of `compiler_builtins`, which have to handle any length and
+//! alignment. Pages are always aligned and a multiple of [`CHUNK_SIZE`] long,
+//! so on RISC-V these functions move eight doublewords per loop iteration
+//! instead.
+//!
+//! The vector extension is not used: the kernel does not save the vector
+//! registers on a trap, and the kernel target (`riscv64imac`) does not
+//! include it.
+
+use core::ptr::NonNull;
+
+/// Number of bytes moved by one loop iteration.
+pub const CHUNK_SIZE: usize = 64;
+
+/// Fills `len` bytes from `dst` with zeroes.
+///
+/// # Safety
+///
+/// `dst` must be valid for writes of `len` bytes and aligned to 8 bytes.
+/// `len` must be a non-zero multiple of [`CHUNK_SIZE`].
+pub unsafe fn zero(dst: NonNull<u8>, len: usize) {
+    debug_assert!(len > 0 && len.is_multiple_of(CHUNK_SIZE));
+    debug_assert!(dst.cast::<u64>().is_aligned());
+
+    #[cfg(target_arch = "riscv64")]
+    unsafe {
+        let end = dst.as_ptr().add(len);
+        core::arch::asm!(
+            "2:",
+            "sd zero, 0({dst})",
+            "sd zero, 8({dst})",
+            "sd zero, 16({dst})",
+            "sd zero, 24({dst})",
+            "sd zero, 32({dst})",
+            "sd zero, 40({dst})",
+            "sd zero, 48({dst})",
+            "sd zero, 56({dst})",
+            "addi {dst}, {dst}, 64",
+            "bltu {dst}, {end}, 2b",
+            dst = inout(reg) dst.as_ptr() => _,
+            end = in(reg) end,
+            options(nostack),
+        );
+    }
+
+    #[cfg(not(target_arch = "riscv64"))]
+    unsafe {
+        dst.write_bytes(0, len);
+    }
+}
+
+/// Copies `len` bytes from `src` to `dst`.
+///
+/// # Safety
+///
+/// `src` must be valid for reads and `dst` must be valid for writes of `len`
+/// bytes, and both must be aligned to 8 bytes. The two regions must not
+/// overlap. `len` must be a non-zero multiple of [`CHUNK_SIZE`].
+pub unsafe fn copy(dst: NonNull<u8>, src: NonNull<u8>, len: usize) {
+    debug_assert!(len > 0 && len.is_multiple_of(CHUNK_SIZE));
+    debug_assert!(dst.cast::<u64>().is_aligned());
+    debug_assert!(src.cast::<u64>().is_aligned());
+
+    #[cfg(target_arch = "riscv64")]
+    unsafe {
+        let end = src.as_ptr().add(len);
+        core::arch::asm!(
+            "2:",
+            "ld {t0}, 0({src})",
+            "ld {t1}, 8({src})",
+            "ld {t2}, 16({src})",
+            "ld {t3}, 24({src})",
+            "sd {t0}, 0({dst})",
+            "sd {t1}, 8({dst})",
+            "sd {t2}, 16({dst})",
+            "sd {t3}, 24({dst})",
+            "ld {t0}, 32({src})",
+            "ld {t1}, 40({src})",
+            "ld {t2}, 48({src})",
+            "ld {t3}, 56({src})",
+            "sd {t0}, 32({dst})",
+            "sd {t1}, 40({dst})",
+            "sd {t2}, 48({dst})",
+            "sd {t3}, 56({dst})",
+            "addi {src}, {src}, 64",
+            "addi {dst}, {dst}, 64",
+            "bltu {src}, {end}, 2b",
+            src = inout(reg) src.as_ptr() => _,
+            dst = inout(reg) dst.as_ptr() => _,
+            end = in(reg) end,
+            t0 = out(reg) _,
+            t1 = out(reg) _,
+            t2 = out(reg) _,
+            t3 = out(reg) _,
+            options(nostack),
+        );
+    }
+
+    #[cfg(not(target_arch = "riscv64"))]
+    unsafe {
+        dst.copy_from_nonoverlapping(src, len);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[repr(align(4096))]
+    struct Page([u8; 4096]);
+
+    #[test]
+    fn test_zero() {
+        let mut page = Page([0xaa; 4096]);
+        unsafe {
+            zero(NonNull::from(&mut page.0).cast(), 4096 - CHUNK_SIZE);
+        }
+        assert!(page.0[..4096 - CHUNK_SIZE].iter().all(|&b| b == 0));
+        assert!(page.0[4096 - CHUNK_SIZE..].iter().all(|&b| b == 0xaa));
+    }
+
+    #[test]
+    fn test_copy() {
+        let mut src = Page([0; 4096]);
+        for (i, b) in src.0.iter_mut().enumerate() {
+            *b = u8::try_from(i % 251).unwrap();
+        }
+        let mut dst = Page([0; 4096]);
+        unsafe {
+            copy(
+                NonNull::from(&mut dst.0).cast(),
+                NonNull::from(&mut src.0).cast(),
+                4096,
+            );
+        }
+        assert_eq!(src.0, dst.0);
+    }
+}
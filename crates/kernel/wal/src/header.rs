@@ -0,0 +1,345 @@
+//! In-memory log header and the commit protocol.
+
+use alloc::alloc::Global;
+use core::{alloc::Allocator, convert::Infallible};
+
+use arrayvec::ArrayVec;
+use block_io::{BlockData, BlockDevice, BlockIoCache, BlockRef, LruMap};
+use mutex_api::Mutex;
+use ov6_fs_types::{self as repr, FS_BLOCK_SIZE, SuperBlock};
+
+/// In-memory log header, tracking the blocks modified by the running
+/// transaction.
+///
+/// The blocks are kept referenced, so that their modified data stays in the
+/// cache until the transaction commits.
+pub struct LogHeader<'list, Device, LruMutex, BlockMutex, const LOG_SIZE: usize, A = Global>
+where
+    LruMutex: Mutex<Data = LruMap<BlockMutex, A>>,
+    A: Allocator,
+{
+    cache: &'list BlockIoCache<Device, LruMutex>,
+    sb: &'list SuperBlock,
+    blocks: ArrayVec<BlockRef<'list, Device, LruMutex, BlockMutex, A>, LOG_SIZE>,
+}
+
+impl<'list, Device, LruMutex, BlockMutex, const LOG_SIZE: usize, A>
+    LogHeader<'list, Device, LruMutex, BlockMutex, LOG_SIZE, A>
+where
+    Device: BlockDevice<FS_BLOCK_SIZE, Error = Infallible>,
+    LruMutex: Mutex<Data = LruMap<BlockMutex, A>>,
+    BlockMutex: Mutex<Data = BlockData<FS_BLOCK_SIZE>> + Default + 'list,
+    A: Allocator + Clone,
+{
+    /// Creates an empty log header for the log described by `sb`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the log on disk is longer than `LOG_SIZE` blocks.
+    pub fn new(cache: &'list BlockIoCache<Device, LruMutex>, sb: &'list SuperBlock) -> Self {
+        assert!(LOG_SIZE >= sb.max_log_len());
+        Self {
+            cache,
+            sb,
+            blocks: ArrayVec::new(),
+        }
+    }
+
+    /// Returns the maximum number of blocks a transaction can modify.
+    pub fn max_len(&self) -> usize {
+        self.sb.max_log_len()
+    }
+
+    /// Returns the number of blocks modified by the running transaction.
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Returns `true` if the running transaction modified no blocks.
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// Adds `block` to the running transaction.
+    ///
+    /// Adding the same block twice records it once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the log is full.
+    pub fn push(&mut self, block: &BlockRef<'list, Device, LruMutex, BlockMutex, A>) {
+        if self.blocks.iter().all(|b| b.index() != block.index()) {
+            assert!(self.blocks.len() < self.max_len());
+            self.blocks.push(block.clone());
+        }
+    }
+
+    /// Installs the transaction left committed in the log, if any.
+    ///
+    /// Called before the file system is used. Running it again after a
+    /// crash during the recovery is harmless.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a transaction is running.
+    pub fn recover_from_log(&mut self) {
+        self.read();
+        self.install_from_log();
+        self.flush();
+        self.blocks.clear();
+        self.write_log_head();
+        self.flush();
+    }
+
+    /// Commits the running transaction.
+    ///
+    /// The modified blocks must be held in the cache.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a block of the transaction is left uninstalled.
+    pub fn commit(&mut self) {
+        if !self.blocks.is_empty() {
+            self.write_log_body(); // Write modified blocks from cache to log
+            self.flush();
+            self.write_log_head(); // Write header to disk -- the real commit
+            self.flush();
+            self.install_transaction(); // Now install writes to home locations
+            self.flush();
+            assert!(self.blocks.is_empty());
+            self.write_log_head(); // Erase the transaction from the log
+            self.flush(); // The next transaction may reuse the log body
+        }
+    }
+
+    /// Reads the log header from disk into the in-memory log header.
+    fn read(&mut self) {
+        assert!(self.blocks.is_empty());
+        let mut br = self.cache.get(self.sb.log_header_block().as_index());
+        let Ok(bg) = br.lock().read();
+        let header = bg.data::<repr::LogHeader>();
+        assert!(header.len() <= self.max_len());
+        for bn in header.block_indices() {
+            let br = self.cache.get(bn.get() as usize);
+            self.push(&br);
+        }
+    }
+
+    /// Writes in-memory block cache to log body.
+    fn write_log_body(&mut self) {
+        for (i, br) in (0..).zip(&mut self.blocks) {
+            let Ok(bg) = br.lock().read();
+            let mut log_br = self.cache.get(self.sb.log_body_block(i).as_index());
+            let mut log_bg = log_br.lock().set_data(bg.bytes());
+            let Ok(()) = log_bg.write();
+        }
+    }
+
+    /// Writes in-memory log header to disk.
+    ///
+    /// This is the true point at which the current transaction commits.
+    fn write_log_head(&self) {
+        let mut br = self.cache.get(self.sb.log_header_block().as_index());
+        let mut bg = br.lock().zeroed();
+        let dst = bg.data_mut::<repr::LogHeader>();
+        dst.set_len(self.blocks.len());
+        for (i, br) in self.blocks.iter().enumerate() {
+            dst.block_indices_mut()[i].set(br.index().try_into().unwrap());
+        }
+        let Ok(()) = bg.write(); // infallible
+    }
+
+    /// Copies committed blocks from cache to their home location.
+    fn install_transaction(&mut self) {
+        for mut br in self.blocks.drain(..) {
+            let Ok(mut bg) = br.lock().read();
+            let Ok(()) = bg.write();
+        }
+        assert!(self.blocks.is_empty());
+    }
+
+    /// Copies committed blocks from log to their home location.
+    fn install_from_log(&mut self) {
+        for (i, br) in (0..).zip(&mut self.blocks) {
+            let mut log_br = self.cache.get(self.sb.log_body_block(i).as_index());
+            let Ok(log_bg) = log_br.lock().read();
+            let mut bg = br.lock().set_data(log_bg.bytes());
+            let Ok(()) = bg.write();
+        }
+    }
+
+    fn flush(&self) {
+        let Ok(()) = self.cache.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::sim_disk::{
+        BlockMutex, Cache, DISK_SIZE, HOME, LOG_BLOCKS, LOG_SIZE, LOG_START, LruList, NBUF, Op,
+        SimDisk, contents, empty_image, log_len, recover, super_block,
+    };
+
+    type LogHeader<'a> = super::LogHeader<'a, &'a SimDisk, Mutex<LruList>, BlockMutex, LOG_SIZE>;
+
+    /// Runs a transaction writing `value` to the blocks `indices`.
+    fn run_tx<'a>(cache: &'a Cache<'a>, log: &mut LogHeader<'a>, indices: &[usize], value: u8) {
+        for &index in indices {
+            let mut br = cache.get(index);
+            let Ok(mut bg) = br.lock().read();
+            bg.bytes_mut().fill(value);
+            drop(bg);
+            log.push(&br);
+        }
+        log.commit();
+    }
+
+    /// Runs two transactions and returns the disk and the valid states of
+    /// the home blocks.
+    fn two_transactions(sb: &SuperBlock) -> (SimDisk, [Vec<u8>; 3]) {
+        let disk = SimDisk::new(empty_image());
+        {
+            let cache = Cache::new(&disk, NBUF);
+            let mut log = LogHeader::new(&cache, sb);
+            run_tx(&cache, &mut log, &[16, 17, 18], 1);
+            run_tx(&cache, &mut log, &[17, 19], 2);
+        }
+        let states = [vec![0, 0, 0, 0], vec![1, 1, 1, 0], vec![1, 2, 1, 2]];
+        (disk, states)
+    }
+
+    #[test]
+    fn test_commit() {
+        let sb = super_block();
+        let (disk, states) = two_transactions(&sb);
+        let image = disk.current();
+        assert_eq!(contents(&image, &HOME), states[2]);
+        assert_eq!(log_len(&image), 0);
+    }
+
+    #[test]
+    fn test_commit_full() {
+        let sb = super_block();
+        let disk = SimDisk::new(empty_image());
+        let blocks = (DISK_SIZE - sb.max_log_len()..DISK_SIZE).collect::<Vec<_>>();
+        {
+            let cache = Cache::new(&disk, NBUF);
+            let mut log = LogHeader::new(&cache, &sb);
+            run_tx(&cache, &mut log, &blocks, 1);
+        }
+        let image = disk.current();
+        assert!(contents(&image, &blocks).iter().all(|&b| b == 1));
+        // the log body does not run over the blocks following it.
+        assert!(
+            image[LOG_START + LOG_BLOCKS..DISK_SIZE - sb.max_log_len()]
+                .iter()
+                .all(|b| b == &[0; FS_BLOCK_SIZE])
+        );
+    }
+
+    #[test]
+    fn test_push_dedup() {
+        let sb = super_block();
+        let disk = SimDisk::new(empty_image());
+        let cache = Cache::new(&disk, NBUF);
+        let mut log = LogHeader::new(&cache, &sb);
+        assert_eq!(log.max_len(), LOG_BLOCKS - 1);
+        let br = cache.get(16);
+        log.push(&br);
+        log.push(&br.clone());
+        assert_eq!(log.len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "assertion failed")]
+    fn test_push_overflow() {
+        let sb = super_block();
+        let disk = SimDisk::new(empty_image());
+        let cache = Cache::new(&disk, NBUF);
+        let mut log = LogHeader::new(&cache, &sb);
+        for br in (10..DISK_SIZE).map(|i| cache.get(i)) {
+            log.push(&br);
+        }
+    }
+
+    #[test]
+    fn test_recover_committed() {
+        let sb = super_block();
+        let disk = SimDisk::new(empty_image());
+        {
+            let cache = Cache::new(&disk, NBUF);
+            let mut log = LogHeader::new(&cache, &sb);
+            run_tx(&cache, &mut log, &[16, 17, 18], 1);
+        }
+
+        // crash just after the commit point: the header is written, but no
+        // block is installed.
+        let mut image = disk.initial();
+        for op in disk.ops() {
+            if let Op::Write(index, data) = op {
+                image[index] = *data;
+                if index == LOG_START {
+                    break;
+                }
+            }
+        }
+        assert_eq!(log_len(&image), 3);
+        assert_eq!(contents(&image, &HOME), [0, 0, 0, 0]);
+
+        let image = recover(image, &sb).current();
+        assert_eq!(contents(&image, &HOME), [1, 1, 1, 0]);
+        assert_eq!(log_len(&image), 0);
+    }
+
+    #[test]
+    fn test_crash_with_reordering() {
+        let sb = super_block();
+        let (disk, states) = two_transactions(&sb);
+        for image in disk.crash_images(true) {
+            let recovered = recover(image, &sb).current();
+            let home = contents(&recovered, &HOME);
+            assert!(states.contains(&home), "non-atomic state {home:?}");
+            assert_eq!(log_len(&recovered), 0);
+        }
+    }
+
+    #[test]
+    fn test_crash_during_recovery() {
+        let sb = super_block();
+        let (disk, states) = two_transactions(&sb);
+        for image in disk.crash_images(true) {
+            if log_len(&image) == 0 {
+                continue;
+            }
+            let expected = contents(&recover(image.clone(), &sb).current(), &HOME);
+            let disk = recover(image, &sb);
+            for image in disk.crash_images(true) {
+                let home = contents(&recover(image, &sb).current(), &HOME);
+                assert!(states.contains(&home));
+                assert_eq!(home, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_crash_without_barriers() {
+        let sb = super_block();
+        let disk = SimDisk::new(empty_image());
+        {
+            let cache = Cache::new(&disk, NBUF);
+            let mut log = LogHeader::new(&cache, &sb);
+            run_tx(&cache, &mut log, &[16, 17, 18], 1);
+        }
+
+        // without the flushes, the header may reach the disk before the log
+        // body, and the recovery installs stale data.
+        let states = [vec![0, 0, 0], vec![1, 1, 1]];
+        assert!(disk.crash_images(false).into_iter().any(|image| {
+            let home = contents(&recover(image, &sb).current(), &HOME[..3]);
+            !states.contains(&home)
+        }));
+    }
+}
@@ -0,0 +1,467 @@
+//! Write-ahead log of the ov6 file system.
+//!
+//! A log transaction contains the updates of multiple FS system
+//! calls. The logging system only commits when there are
+//! no FS system calls active. Thus there is never
+//! any reasoning required about whether a commit might
+//! write an uncommitted system call's data to disk.
+//!
+//! A system call calls [`Wal::begin()`] and [`Wal::end()`] to mark its start
+//! and end. Usually [`Wal::begin()`] just reserves log space for the blocks
+//! the system call may write and returns. But if the log would run out of
+//! space, it waits until the last outstanding transaction commits.
+//!
+//! The log is a physical re-do log containing disk blocks.
+//! The on-disk log format:
+//!
+//! ```text
+//! header block, containing block #s for block A, B, C, ...
+//! block A
+//! block B
+//! block C
+//! ...
+//! ```
+//!
+//! A transaction is committed in four steps, each followed by a flush of the
+//! device:
+//!
+//! 1. the modified blocks are copied to the log body,
+//! 2. the header listing them is written (the commit point),
+//! 3. the blocks are written to their home locations,
+//! 4. the header is cleared.
+//!
+//! The writes of a step may reach the disk in any order, but the flush
+//! keeps them from being reordered with the writes of the other steps. A
+//! crash at any point leaves either no header, so the transaction is lost as
+//! a whole, or a header whose blocks are all in the log body, so that
+//! [`Wal::replay()`] can install them again.
+//!
+//! The log is parameterized over the block device and the mutex, like
+//! [`block_io`], and over a [`CondVar`] to wait for log space.
+#![feature(allocator_api)]
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+
+use alloc::alloc::Global;
+use core::{alloc::Allocator, convert::Infallible};
+
+use block_io::{BlockData, BlockDevice, BlockIoCache, BlockRef, LruMap};
+use mutex_api::Mutex;
+use ov6_fs_types::{FS_BLOCK_SIZE, SuperBlock};
+
+pub use self::header::LogHeader;
+
+mod header;
+#[cfg(test)]
+mod sim_disk;
+
+/// A condition variable used with the mutex `M`.
+pub trait CondVar<M>
+where
+    M: Mutex,
+{
+    /// The error type returned when waiting is interrupted.
+    type Error;
+
+    /// Releases `guard` and waits until notified, then locks the mutex again.
+    fn wait<'a>(&self, guard: M::Guard<'a>) -> Result<M::Guard<'a>, Self::Error>
+    where
+        M: 'a;
+
+    /// Same as [`Self::wait()`], but cannot be interrupted.
+    fn force_wait<'a>(&self, guard: M::Guard<'a>) -> M::Guard<'a>
+    where
+        M: 'a;
+
+    /// Wakes up all the waiters.
+    fn notify(&self);
+}
+
+/// An error returned by [`Wal::begin()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BeginError<E> {
+    /// The transaction modifies the file system, but it is read-only.
+    ReadOnly,
+    /// Waiting for log space was interrupted.
+    Wait(E),
+}
+
+/// State of the log, protected by a mutex.
+pub struct WalState<'list, Device, LruMutex, BlockMutex, const LOG_SIZE: usize, A = Global>
+where
+    LruMutex: Mutex<Data = LruMap<BlockMutex, A>>,
+    A: Allocator,
+{
+    outstanding: usize,
+    /// Sum of the blocks reserved by the outstanding operations.
+    reserved: usize,
+    /// If `None`, data is committing.
+    header: Option<LogHeader<'list, Device, LruMutex, BlockMutex, LOG_SIZE, A>>,
+    /// If true, transactions that modify the file system cannot be started.
+    read_only: bool,
+}
+
+/// A write-ahead log.
+pub struct Wal<StateMutex, Cond> {
+    state: StateMutex,
+    cond: Cond,
+}
+
+impl<'list, Device, LruMutex, BlockMutex, StateMutex, Cond, const LOG_SIZE: usize, A>
+    Wal<StateMutex, Cond>
+where
+    Device: BlockDevice<FS_BLOCK_SIZE, Error = Infallible> + 'list,
+    LruMutex: Mutex<Data = LruMap<BlockMutex, A>> + 'list,
+    BlockMutex: Mutex<Data = BlockData<FS_BLOCK_SIZE>> + Default + 'list,
+    StateMutex: Mutex<Data = WalState<'list, Device, LruMutex, BlockMutex, LOG_SIZE, A>>,
+    Cond: CondVar<StateMutex>,
+    A: Allocator + Clone,
+{
+    /// Opens the log described by `sb`, replaying the committed transaction
+    /// left in it.
+    ///
+    /// If `read_only` is true, transactions that modify the file system
+    /// cannot be started until [`Self::set_read_only()`] is called.
+    pub fn replay(
+        cache: &'list BlockIoCache<Device, LruMutex>,
+        sb: &'list SuperBlock,
+        read_only: bool,
+        cond: Cond,
+    ) -> Self {
+        let mut header = LogHeader::new(cache, sb);
+        header.recover_from_log();
+
+        Self {
+            state: StateMutex::new(WalState {
+                outstanding: 0,
+                reserved: 0,
+                header: Some(header),
+                read_only,
+            }),
+            cond,
+        }
+    }
+
+    /// Starts FS transaction that writes at most `blocks` blocks.
+    ///
+    /// Called at the start of each FS system call. Fails if `write` is true
+    /// and the file system is read-only.
+    pub fn begin(&self, write: bool, blocks: usize) -> Result<(), BeginError<Cond::Error>> {
+        let mut state = self.state.lock();
+        loop {
+            if write && state.read_only {
+                return Err(BeginError::ReadOnly);
+            }
+            if state.has_space(blocks) {
+                break;
+            }
+            // the header is under committing, or this op might exhaust log
+            // space; wait for commit.
+            state = self.cond.wait(state).map_err(BeginError::Wait)?;
+        }
+        state.outstanding += 1;
+        state.reserved += blocks;
+        Ok(())
+    }
+
+    /// Starts FS transaction that writes at most `blocks` blocks.
+    ///
+    /// Same as [`Self::begin()`], but the waiting cannot be interrupted and
+    /// the read-only flag is ignored.
+    pub fn force_begin(&self, blocks: usize) {
+        let mut state = self.state.lock();
+        while !state.has_space(blocks) {
+            state = self.cond.force_wait(state);
+        }
+        state.outstanding += 1;
+        state.reserved += blocks;
+    }
+
+    /// Ends FS transaction started with a reservation of `blocks` blocks.
+    ///
+    /// Called at the end of each FS system call.
+    /// Commits if this was the last outstanding operation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the log is being committed by another operation.
+    pub fn end(&self, blocks: usize) {
+        let mut state = self.state.lock();
+        state.outstanding -= 1;
+        state.reserved -= blocks;
+        assert!(state.header.is_some()); // not under committing
+        let header = if state.outstanding == 0 {
+            state.header.take()
+        } else {
+            // begin() may be waiting for log space,
+            // and releasing this op's reservation has decreased
+            // the amount of reserved space.
+            self.cond.notify();
+            None
+        };
+        drop(state); // unlock here
+
+        if let Some(mut header) = header {
+            // call commit w/o holding locks, since not allowed
+            // to sleep with locks.
+            header.commit();
+            let mut state = self.state.lock();
+            assert!(state.header.is_none());
+            state.header = Some(header);
+            self.cond.notify();
+        }
+    }
+
+    /// Records that the running transaction modified `block`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no transaction is running.
+    pub fn write(&self, block: &BlockRef<'list, Device, LruMutex, BlockMutex, A>) {
+        let state = &mut *self.state.lock();
+        assert!(state.outstanding > 0);
+        state.header.as_mut().unwrap().push(block);
+    }
+
+    /// Waits for the outstanding operations to commit, then stops starting
+    /// new ones.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the log has already been shut down.
+    pub fn shutdown(&self) {
+        let mut state = self.wait_idle(self.state.lock());
+        // Leaving the header taken makes `begin()` wait forever, as if
+        // the log were always committing.
+        let header = state.header.take().unwrap();
+        assert_eq!(header.len(), 0);
+    }
+
+    /// Makes the file system read-only or writable.
+    ///
+    /// When making it read-only, waits for the outstanding operations to
+    /// commit, so that nothing is written after this returns.
+    pub fn set_read_only(&self, read_only: bool) {
+        let mut state = self.state.lock();
+        state.read_only = read_only;
+        if read_only {
+            let _state = self.wait_idle(state);
+        }
+    }
+
+    /// Returns `true` if transactions that modify the file system cannot be
+    /// started.
+    pub fn is_read_only(&self) -> bool {
+        self.state.lock().read_only
+    }
+
+    /// Waits until no operation is outstanding and nothing is committing.
+    fn wait_idle<'a>(&'a self, mut state: StateMutex::Guard<'a>) -> StateMutex::Guard<'a> {
+        while state.outstanding > 0 || state.header.is_none() {
+            state = self.cond.force_wait(state);
+        }
+        state
+    }
+}
+
+impl<'list, Device, LruMutex, BlockMutex, const LOG_SIZE: usize, A>
+    WalState<'list, Device, LruMutex, BlockMutex, LOG_SIZE, A>
+where
+    Device: BlockDevice<FS_BLOCK_SIZE, Error = Infallible>,
+    LruMutex: Mutex<Data = LruMap<BlockMutex, A>>,
+    BlockMutex: Mutex<Data = BlockData<FS_BLOCK_SIZE>> + Default + 'list,
+    A: Allocator + Clone,
+{
+    /// Returns `true` if an operation writing `blocks` blocks can start.
+    fn has_space(&self, blocks: usize) -> bool {
+        self.header
+            .as_ref()
+            .is_some_and(|header| header.len() + self.reserved + blocks <= header.max_len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Condvar, Mutex, MutexGuard},
+        thread,
+    };
+
+    use rand::{Rng as _, SeedableRng as _, rngs::StdRng};
+
+    use super::*;
+    use crate::sim_disk::{
+        BlockMutex, Cache, HOME, LOG_SIZE, LruList, NBUF, SimDisk, contents, empty_image, log_len,
+        recover, super_block,
+    };
+
+    type State<'a> = WalState<'a, &'a SimDisk, Mutex<LruList>, BlockMutex, LOG_SIZE>;
+    type Wal<'a> = super::Wal<Mutex<State<'a>>, Condvar>;
+
+    impl<T> CondVar<Mutex<T>> for Condvar {
+        type Error = Infallible;
+
+        fn wait<'a>(&self, guard: MutexGuard<'a, T>) -> Result<MutexGuard<'a, T>, Self::Error>
+        where
+            Mutex<T>: 'a,
+        {
+            Ok(self.force_wait(guard))
+        }
+
+        fn force_wait<'a>(&self, guard: MutexGuard<'a, T>) -> MutexGuard<'a, T>
+        where
+            Mutex<T>: 'a,
+        {
+            Self::wait(self, guard).unwrap()
+        }
+
+        fn notify(&self) {
+            self.notify_all();
+        }
+    }
+
+    /// Writes `value` to the block `index` in the running transaction.
+    fn write_block<'a>(cache: &'a Cache<'a>, wal: &Wal<'a>, index: usize, value: u8) {
+        let mut br = cache.get(index);
+        let Ok(mut bg) = br.lock().read();
+        bg.bytes_mut().fill(value);
+        drop(bg);
+        wal.write(&br);
+    }
+
+    #[test]
+    fn test_begin_end() {
+        let sb = super_block();
+        let disk = SimDisk::new(empty_image());
+        let cache = Cache::new(&disk, NBUF);
+        let wal = Wal::replay(&cache, &sb, false, Condvar::new());
+
+        wal.begin(true, 2).unwrap();
+        wal.begin(true, 2).unwrap();
+        write_block(&cache, &wal, HOME[0], 1);
+        wal.end(2);
+        // not committed until the last operation ends
+        assert_eq!(contents(&disk.current(), &HOME), [0, 0, 0, 0]);
+        write_block(&cache, &wal, HOME[1], 1);
+        wal.end(2);
+        assert_eq!(contents(&disk.current(), &HOME), [1, 1, 0, 0]);
+        assert_eq!(log_len(&disk.current()), 0);
+    }
+
+    #[test]
+    fn test_read_only() {
+        let sb = super_block();
+        let disk = SimDisk::new(empty_image());
+        let cache = Cache::new(&disk, NBUF);
+        let wal = Wal::replay(&cache, &sb, true, Condvar::new());
+
+        assert!(wal.is_read_only());
+        assert_eq!(wal.begin(true, 1), Err(BeginError::ReadOnly));
+        wal.begin(false, 1).unwrap();
+        wal.end(1);
+        wal.force_begin(1);
+        wal.end(1);
+
+        wal.set_read_only(false);
+        wal.begin(true, 1).unwrap();
+        wal.end(1);
+    }
+
+    #[test]
+    fn test_replay() {
+        let sb = super_block();
+        let disk = SimDisk::new(empty_image());
+        {
+            let cache = Cache::new(&disk, NBUF);
+            let wal = Wal::replay(&cache, &sb, false, Condvar::new());
+            wal.begin(true, 3).unwrap();
+            for index in &HOME[..3] {
+                write_block(&cache, &wal, *index, 1);
+            }
+            wal.end(3);
+        }
+
+        // a crash image with the transaction committed but not installed
+        let image = disk
+            .crash_images(true)
+            .into_iter()
+            .find(|image| log_len(image) == 3 && contents(image, &HOME) == [0, 0, 0, 0])
+            .unwrap();
+        let disk = SimDisk::new(image);
+        let cache = Cache::new(&disk, NBUF);
+        let _wal = Wal::replay(&cache, &sb, false, Condvar::new());
+        assert_eq!(contents(&disk.current(), &HOME), [1, 1, 1, 0]);
+        assert_eq!(log_len(&disk.current()), 0);
+    }
+
+    #[test]
+    fn test_concurrent() {
+        const ROUNDS: usize = 20;
+        const BLOCKS: usize = 3;
+
+        let sb = super_block();
+        let disk = SimDisk::new(empty_image());
+        let cache = Cache::new(&disk, NBUF);
+        let wal = Wal::replay(&cache, &sb, false, Condvar::new());
+
+        thread::scope(|s| {
+            // one thread per home block
+            for home in HOME {
+                let cache = &cache;
+                let wal = &wal;
+                s.spawn(move || {
+                    for round in 0..ROUNDS {
+                        wal.begin(true, BLOCKS).unwrap();
+                        {
+                            let state = wal.state.lock().unwrap();
+                            let header = state.header.as_ref().unwrap();
+                            assert!(header.len() + state.reserved <= header.max_len());
+                        }
+                        write_block(cache, wal, home, u8::try_from(round + 1).unwrap());
+                        wal.end(BLOCKS);
+                    }
+                });
+            }
+        });
+
+        assert_eq!(contents(&disk.current(), &HOME), [20, 20, 20, 20]);
+        assert_eq!(log_len(&disk.current()), 0);
+        wal.shutdown();
+    }
+
+    /// Runs random transactions and checks that a crash at any point
+    /// recovers the state after one of the transactions.
+    #[test]
+    fn test_random_transactions() {
+        let sb = super_block();
+        let mut rng = StdRng::seed_from_u64(0x006f_7636);
+        for _ in 0..20 {
+            let disk = SimDisk::new(empty_image());
+            let mut states = vec![contents(&disk.initial(), &HOME)];
+            {
+                let cache = Cache::new(&disk, NBUF);
+                let wal = Wal::replay(&cache, &sb, false, Condvar::new());
+                let mut state = states[0].clone();
+                for value in 1..=rng.random_range(1..6) {
+                    wal.begin(true, HOME.len()).unwrap();
+                    for (i, index) in HOME.iter().enumerate() {
+                        if rng.random_bool(0.5) {
+                            write_block(&cache, &wal, *index, value);
+                            state[i] = value;
+                        }
+                    }
+                    wal.end(HOME.len());
+                    states.push(state.clone());
+                }
+            }
+
+            for image in disk.crash_images(true) {
+                let recovered = recover(image, &sb).current();
+                let home = contents(&recovered, &HOME);
+                assert!(states.contains(&home), "non-atomic state {home:?}");
+                assert_eq!(log_len(&recovered), 0);
+            }
+        }
+    }
+}
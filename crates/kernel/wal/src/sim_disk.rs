@@ -0,0 +1,176 @@
+//! Simulated disk reordering the writes between flushes.
+
+use core::convert::Infallible;
+use std::sync::Mutex;
+
+use block_io::{BlockData, BlockDevice, BlockIoCache};
+use dataview::PodMethods as _;
+use ov6_fs_types::{self as repr, FS_BLOCK_SIZE, Le, SuperBlock};
+
+use crate::LogHeader;
+
+pub(crate) type Block = [u8; FS_BLOCK_SIZE];
+
+pub(crate) const DISK_SIZE: usize = 24;
+pub(crate) const LOG_START: usize = 2;
+pub(crate) const LOG_BLOCKS: usize = 8;
+pub(crate) const LOG_SIZE: usize = LOG_BLOCKS;
+pub(crate) const NBUF: usize = LOG_BLOCKS + 2;
+
+/// Blocks written by the tests.
+pub(crate) const HOME: [usize; 4] = [16, 17, 18, 19];
+
+pub(crate) type BlockMutex = Mutex<BlockData<FS_BLOCK_SIZE>>;
+pub(crate) type LruList = block_io::LruMap<BlockMutex>;
+pub(crate) type Cache<'a> = BlockIoCache<&'a SimDisk, Mutex<LruList>>;
+
+#[derive(Clone)]
+pub(crate) enum Op {
+    Write(usize, Box<Block>),
+    Flush,
+}
+
+/// A disk with a volatile write cache.
+///
+/// Reads return the latest data written, but the writes issued since the
+/// last flush reach the medium in an unknown order. The writes are recorded,
+/// so that every state the medium can be left in by a crash can be
+/// enumerated.
+pub(crate) struct SimDisk {
+    initial: Vec<Block>,
+    state: Mutex<SimState>,
+}
+
+struct SimState {
+    current: Vec<Block>,
+    ops: Vec<Op>,
+}
+
+impl SimDisk {
+    pub(crate) fn new(image: Vec<Block>) -> Self {
+        Self {
+            state: Mutex::new(SimState {
+                current: image.clone(),
+                ops: vec![],
+            }),
+            initial: image,
+        }
+    }
+
+    /// Returns the contents of the disk when it was created.
+    pub(crate) fn initial(&self) -> Vec<Block> {
+        self.initial.clone()
+    }
+
+    /// Returns the latest contents of the disk, as if all writes landed.
+    pub(crate) fn current(&self) -> Vec<Block> {
+        self.state.lock().unwrap().current.clone()
+    }
+
+    /// Returns the operations issued to the disk.
+    pub(crate) fn ops(&self) -> Vec<Op> {
+        self.state.lock().unwrap().ops.clone()
+    }
+
+    /// Returns the states of the medium after a crash at any point.
+    ///
+    /// The writes issued between two flushes are delayed and reordered: any
+    /// subset of them may have reached the medium, in the issued order or in
+    /// the reverse order.
+    ///
+    /// If `barriers` is false, the flushes are ignored, as if the device did
+    /// not implement them.
+    pub(crate) fn crash_images(&self, barriers: bool) -> Vec<Vec<Block>> {
+        let mut epochs = vec![vec![]];
+        for op in self.ops() {
+            match op {
+                Op::Write(index, data) => epochs.last_mut().unwrap().push((index, data)),
+                Op::Flush if barriers => epochs.push(vec![]),
+                Op::Flush => {}
+            }
+        }
+
+        let mut images = vec![];
+        let mut base = self.initial();
+        for epoch in &epochs {
+            assert!(epoch.len() < 16);
+            for mask in 0..1_u32 << epoch.len() {
+                let landed = epoch
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| mask & (1 << i) != 0)
+                    .map(|(_, w)| w)
+                    .collect::<Vec<_>>();
+                let mut image = base.clone();
+                for (index, data) in &landed {
+                    image[*index] = **data;
+                }
+                images.push(image);
+                let mut image = base.clone();
+                for (index, data) in landed.iter().rev() {
+                    image[*index] = **data;
+                }
+                images.push(image);
+            }
+            for (index, data) in epoch {
+                base[*index] = **data;
+            }
+        }
+        images
+    }
+}
+
+impl BlockDevice<FS_BLOCK_SIZE> for &SimDisk {
+    type Error = Infallible;
+
+    fn read(&self, block_index: usize, data: &mut Block) -> Result<(), Self::Error> {
+        *data = self.state.lock().unwrap().current[block_index];
+        Ok(())
+    }
+
+    fn write(&self, block_index: usize, data: &Block) -> Result<(), Self::Error> {
+        let mut state = self.state.lock().unwrap();
+        state.current[block_index] = *data;
+        state.ops.push(Op::Write(block_index, Box::new(*data)));
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<(), Self::Error> {
+        self.state.lock().unwrap().ops.push(Op::Flush);
+        Ok(())
+    }
+}
+
+pub(crate) fn super_block() -> SuperBlock {
+    let mut sb = SuperBlock::zeroed();
+    sb.logstart = Le::<u32>::new(LOG_START.try_into().unwrap());
+    sb.nlog = Le::<u32>::new(LOG_BLOCKS.try_into().unwrap());
+    sb
+}
+
+pub(crate) fn empty_image() -> Vec<Block> {
+    vec![[0; FS_BLOCK_SIZE]; DISK_SIZE]
+}
+
+/// Returns the first byte of each of the blocks `indices`.
+pub(crate) fn contents(image: &[Block], indices: &[usize]) -> Vec<u8> {
+    indices.iter().map(|&i| image[i][0]).collect()
+}
+
+/// Returns the number of blocks in the log header of `image`.
+pub(crate) fn log_len(image: &[Block]) -> usize {
+    image[LOG_START]
+        .as_data_view()
+        .get::<repr::LogHeader>(0)
+        .len()
+}
+
+/// Replays the log left in `image`, returning the disk written by the
+/// recovery.
+pub(crate) fn recover(image: Vec<Block>, sb: &SuperBlock) -> SimDisk {
+    let disk = SimDisk::new(image);
+    let cache = Cache::new(&disk, NBUF);
+    LogHeader::<_, _, _, LOG_SIZE>::new(&cache, sb).recover_from_log();
+    drop(cache);
+    disk
+}
@@ -0,0 +1,216 @@
+use alloc::string::String;
+use core::ops::Range;
+
+/// Text of the line being edited and the cursor position.
+///
+/// The cursor is a byte offset into the text and is always on a character
+/// boundary.
+#[derive(Debug, Default)]
+pub(crate) struct LineBuffer {
+    text: String,
+    pos: usize,
+}
+
+impl LineBuffer {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    pub(crate) fn pos(&self) -> usize {
+        self.pos
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Returns the number of characters after the cursor.
+    pub(crate) fn chars_after_cursor(&self) -> usize {
+        self.text[self.pos..].chars().count()
+    }
+
+    /// Replaces the whole text and moves the cursor to the end.
+    pub(crate) fn set(&mut self, text: &str) {
+        self.text.clear();
+        self.text.push_str(text);
+        self.pos = self.text.len();
+    }
+
+    pub(crate) fn insert_char(&mut self, c: char) {
+        self.text.insert(self.pos, c);
+        self.pos += c.len_utf8();
+    }
+
+    /// Replaces `range` with `s` and moves the cursor to the end of the
+    /// inserted text.
+    pub(crate) fn replace_range(&mut self, range: Range<usize>, s: &str) {
+        let start = range.start;
+        self.text.replace_range(range, s);
+        self.pos = start + s.len();
+    }
+
+    fn prev_boundary(&self) -> Option<usize> {
+        self.text[..self.pos]
+            .char_indices()
+            .next_back()
+            .map(|(i, _)| i)
+    }
+
+    fn next_boundary(&self) -> Option<usize> {
+        self.text[self.pos..]
+            .chars()
+            .next()
+            .map(|c| self.pos + c.len_utf8())
+    }
+
+    /// Deletes the character before the cursor.
+    pub(crate) fn delete_prev(&mut self) -> bool {
+        let Some(prev) = self.prev_boundary() else {
+            return false;
+        };
+        self.text.replace_range(prev..self.pos, "");
+        self.pos = prev;
+        true
+    }
+
+    /// Deletes the character at the cursor.
+    pub(crate) fn delete_next(&mut self) -> bool {
+        let Some(next) = self.next_boundary() else {
+            return false;
+        };
+        self.text.replace_range(self.pos..next, "");
+        true
+    }
+
+    pub(crate) fn move_left(&mut self) -> bool {
+        let Some(prev) = self.prev_boundary() else {
+            return false;
+        };
+        self.pos = prev;
+        true
+    }
+
+    pub(crate) fn move_right(&mut self) -> bool {
+        let Some(next) = self.next_boundary() else {
+            return false;
+        };
+        self.pos = next;
+        true
+    }
+
+    pub(crate) fn move_home(&mut self) {
+        self.pos = 0;
+    }
+
+    pub(crate) fn move_end(&mut self) {
+        self.pos = self.text.len();
+    }
+
+    /// Deletes the text before the cursor.
+    pub(crate) fn kill_to_start(&mut self) {
+        self.text.replace_range(..self.pos, "");
+        self.pos = 0;
+    }
+
+    /// Deletes the text after the cursor.
+    pub(crate) fn kill_to_end(&mut self) {
+        self.text.truncate(self.pos);
+    }
+
+    /// Deletes the word before the cursor, along with any whitespace between
+    /// the word and the cursor.
+    pub(crate) fn delete_prev_word(&mut self) -> bool {
+        let before = &self.text[..self.pos];
+        let trimmed = before.trim_end();
+        let start = trimmed
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_whitespace())
+            .map_or(0, |(i, c)| i + c.len_utf8());
+        if start == self.pos {
+            return false;
+        }
+        self.text.replace_range(start..self.pos, "");
+        self.pos = start;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buffer(text: &str, pos: usize) -> LineBuffer {
+        let mut buf = LineBuffer::new();
+        buf.set(text);
+        buf.pos = pos;
+        buf
+    }
+
+    #[test]
+    fn insert_and_delete() {
+        let mut buf = LineBuffer::new();
+        for c in "ac".chars() {
+            buf.insert_char(c);
+        }
+        assert!(buf.move_left());
+        buf.insert_char('b');
+        assert_eq!((buf.as_str(), buf.pos()), ("abc", 2));
+        assert!(buf.delete_prev());
+        assert_eq!((buf.as_str(), buf.pos()), ("ac", 1));
+        assert!(buf.delete_next());
+        assert_eq!((buf.as_str(), buf.pos()), ("a", 1));
+        assert!(!buf.delete_next());
+    }
+
+    #[test]
+    fn multibyte_chars() {
+        let mut buf = buffer("aあb", 4);
+        assert_eq!(buf.chars_after_cursor(), 1);
+        assert!(buf.move_left());
+        assert_eq!(buf.pos(), 1);
+        assert!(buf.delete_next());
+        assert_eq!(buf.as_str(), "ab");
+    }
+
+    #[test]
+    fn movement_at_edges() {
+        let mut buf = buffer("ab", 0);
+        assert!(!buf.move_left());
+        assert!(!buf.delete_prev());
+        buf.move_end();
+        assert!(!buf.move_right());
+        assert_eq!(buf.pos(), 2);
+    }
+
+    #[test]
+    fn kill() {
+        let mut buf = buffer("hello world", 5);
+        buf.kill_to_end();
+        assert_eq!(buf.as_str(), "hello");
+        let mut buf = buffer("hello world", 6);
+        buf.kill_to_start();
+        assert_eq!((buf.as_str(), buf.pos()), ("world", 0));
+    }
+
+    #[test]
+    fn delete_prev_word() {
+        let mut buf = buffer("ls foo/bar  ", 12);
+        assert!(buf.delete_prev_word());
+        assert_eq!((buf.as_str(), buf.pos()), ("ls ", 3));
+        assert!(buf.delete_prev_word());
+        assert_eq!(buf.as_str(), "");
+        assert!(!buf.delete_prev_word());
+    }
+
+    #[test]
+    fn replace_range() {
+        let mut buf = buffer("cat fo", 6);
+        buf.replace_range(4..6, "foo.txt");
+        assert_eq!((buf.as_str(), buf.pos()), ("cat foo.txt", 11));
+    }
+}
@@ -0,0 +1,136 @@
+use alloc::{format, string::String, vec::Vec};
+
+use ov6_user_lib::fs;
+
+/// Result of a completion request.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Completion {
+    /// Byte offset in the line where the word being completed starts.
+    ///
+    /// The text between `start` and the cursor is replaced by the chosen
+    /// candidate.
+    pub start: usize,
+    /// Candidates for the word being completed.
+    pub candidates: Vec<String>,
+}
+
+/// Completion hook called when the user presses `Tab`.
+pub trait Completer {
+    /// Returns the candidates for the word before the cursor `pos` in `line`.
+    ///
+    /// [`Completion::start`] must be a character boundary of `line` not
+    /// greater than `pos`.
+    fn complete(&self, line: &str, pos: usize) -> Completion;
+}
+
+/// Completes the word before the cursor as a file name.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FileNameCompleter;
+
+impl Completer for FileNameCompleter {
+    fn complete(&self, line: &str, pos: usize) -> Completion {
+        let start = word_start(line, pos);
+        Completion {
+            start,
+            candidates: complete_file_name(&line[start..pos]),
+        }
+    }
+}
+
+/// Returns the byte offset where the whitespace-delimited word before `pos`
+/// starts.
+#[must_use]
+pub fn word_start(line: &str, pos: usize) -> usize {
+    line[..pos]
+        .char_indices()
+        .rev()
+        .find(|(_, c)| c.is_whitespace())
+        .map_or(0, |(i, c)| i + c.len_utf8())
+}
+
+/// Returns the paths that start with `word`.
+///
+/// Directories are suffixed with `/`. Entries starting with `.` are only
+/// returned if the last component of `word` also starts with `.`.
+#[must_use]
+pub fn complete_file_name(word: &str) -> Vec<String> {
+    let (dir, prefix) = word.rfind('/').map_or(("", word), |i| word.split_at(i + 1));
+    let Ok(entries) = fs::read_dir(if dir.is_empty() { "." } else { dir }) else {
+        return Vec::new();
+    };
+
+    let mut candidates = entries
+        .flatten()
+        .filter_map(|ent| {
+            let name = ent.name().to_str()?;
+            if !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+                return None;
+            }
+            let path = format!("{dir}{name}");
+            if fs::metadata(&path).is_ok_and(|meta| meta.is_dir()) {
+                return Some(format!("{path}/"));
+            }
+            Some(path)
+        })
+        .collect::<Vec<_>>();
+    candidates.sort();
+    candidates
+}
+
+/// Returns the longest common prefix of `candidates`.
+pub(crate) fn common_prefix(candidates: &[String]) -> &str {
+    let Some((first, rest)) = candidates.split_first() else {
+        return "";
+    };
+    let mut len = first.len();
+    for cand in rest {
+        len = first
+            .char_indices()
+            .zip(cand.chars())
+            .take_while(|((i, a), b)| *i < len && a == b)
+            .last()
+            .map_or(0, |((i, a), _)| i + a.len_utf8());
+    }
+    &first[..len]
+}
+
+/// Returns the label shown for `candidate` in the candidate list.
+///
+/// Only the last path component is shown.
+pub(crate) fn display_name(candidate: &str) -> &str {
+    let trimmed = candidate.strip_suffix('/').unwrap_or(candidate);
+    trimmed
+        .rfind('/')
+        .map_or(candidate, |i| &candidate[i + 1..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn word_start_finds_last_word() {
+        assert_eq!(word_start("", 0), 0);
+        assert_eq!(word_start("ls", 2), 0);
+        assert_eq!(word_start("cat foo/b", 9), 4);
+        assert_eq!(word_start("cat foo ", 8), 8);
+        assert_eq!(word_start("cat foo bar", 7), 4);
+    }
+
+    #[test]
+    fn common_prefix_of_candidates() {
+        let cands = |s: &[&str]| s.iter().copied().map(String::from).collect::<Vec<_>>();
+        assert_eq!(common_prefix(&[]), "");
+        assert_eq!(common_prefix(&cands(&["foo"])), "foo");
+        assert_eq!(common_prefix(&cands(&["foobar", "foobaz", "foo"])), "foo");
+        assert_eq!(common_prefix(&cands(&["abc", "xyz"])), "");
+        assert_eq!(common_prefix(&cands(&["あい", "あう"])), "あ");
+    }
+
+    #[test]
+    fn display_name_is_last_component() {
+        assert_eq!(display_name("foo"), "foo");
+        assert_eq!(display_name("dir/foo"), "foo");
+        assert_eq!(display_name("dir/sub/"), "sub/");
+    }
+}
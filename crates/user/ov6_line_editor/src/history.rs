@@ -0,0 +1,71 @@
+use alloc::{collections::VecDeque, string::String};
+
+/// Previously entered lines, oldest first.
+#[derive(Debug)]
+pub(crate) struct History {
+    entries: VecDeque<String>,
+    capacity: usize,
+}
+
+impl History {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub(crate) fn get(&self, index: usize) -> Option<&str> {
+        self.entries.get(index).map(String::as_str)
+    }
+
+    /// Appends `line` to the history.
+    ///
+    /// Blank lines and lines equal to the last entry are not recorded. The
+    /// oldest entry is discarded if the history is full.
+    pub(crate) fn push(&mut self, line: &str) {
+        if line.trim().is_empty() || self.entries.back().is_some_and(|last| last == line) {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        if self.capacity > 0 {
+            self.entries.push_back(line.into());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_skips_blank_and_duplicate_lines() {
+        let mut history = History::new(10);
+        history.push("ls");
+        history.push("  ");
+        history.push("ls");
+        history.push("cat README");
+        history.push("ls");
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.get(0), Some("ls"));
+        assert_eq!(history.get(1), Some("cat README"));
+        assert_eq!(history.get(2), Some("ls"));
+    }
+
+    #[test]
+    fn push_discards_oldest_entry() {
+        let mut history = History::new(2);
+        history.push("a");
+        history.push("b");
+        history.push("c");
+        assert_eq!(history.len(), 2);
+        assert_eq!(history.get(0), Some("b"));
+        assert_eq!(history.get(1), Some("c"));
+    }
+}
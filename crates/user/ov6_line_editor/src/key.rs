@@ -0,0 +1,189 @@
+use ov6_user_lib::{error::Ov6Error, io::Read};
+
+/// A key press decoded from the terminal input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Key {
+    Char(char),
+    /// A control character, represented by its uppercase letter (e.g.
+    /// `Ctrl(b'A')` for `control-a`).
+    Ctrl(u8),
+    Enter,
+    Tab,
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    /// An input sequence that is not recognized.
+    Unknown,
+}
+
+const ESC: u8 = 0x1b;
+const DEL: u8 = 0x7f;
+
+/// Decodes key presses from a byte stream.
+pub(crate) struct KeyReader<R> {
+    input: R,
+}
+
+impl<R> KeyReader<R>
+where
+    R: Read,
+{
+    pub(crate) fn new(input: R) -> Self {
+        Self { input }
+    }
+
+    fn read_byte(&mut self) -> Result<Option<u8>, Ov6Error> {
+        let mut byte = [0];
+        loop {
+            match self.input.read(&mut byte) {
+                Ok(0) => return Ok(None),
+                Ok(_) => return Ok(Some(byte[0])),
+                Err(e) if e.is_interrupted() => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Reads the next key press.
+    ///
+    /// Returns `None` at the end of the input.
+    pub(crate) fn read_key(&mut self) -> Result<Option<Key>, Ov6Error> {
+        let Some(b) = self.read_byte()? else {
+            return Ok(None);
+        };
+        let key = match b {
+            b'\r' | b'\n' => Key::Enter,
+            b'\t' => Key::Tab,
+            DEL | 0x08 => Key::Backspace,
+            ESC => self.read_escape()?,
+            0x01..=0x1a => Key::Ctrl(b + b'@'),
+            0x20..=0x7e => Key::Char(char::from(b)),
+            0x80.. => self.read_utf8(b)?,
+            _ => Key::Unknown,
+        };
+        Ok(Some(key))
+    }
+
+    /// Decodes a `CSI` (`ESC [`) or `SS3` (`ESC O`) sequence.
+    fn read_escape(&mut self) -> Result<Key, Ov6Error> {
+        let Some(b'[' | b'O') = self.read_byte()? else {
+            return Ok(Key::Unknown);
+        };
+
+        let mut param = 0_u32;
+        let last = loop {
+            let Some(b) = self.read_byte()? else {
+                return Ok(Key::Unknown);
+            };
+            match b {
+                b'0'..=b'9' => param = param.saturating_mul(10) + u32::from(b - b'0'),
+                b';' => param = 0,
+                0x40..=0x7e => break b,
+                _ => return Ok(Key::Unknown),
+            }
+        };
+
+        let key = match (last, param) {
+            (b'A', _) => Key::Up,
+            (b'B', _) => Key::Down,
+            (b'C', _) => Key::Right,
+            (b'D', _) => Key::Left,
+            (b'H', _) | (b'~', 1 | 7) => Key::Home,
+            (b'F', _) | (b'~', 4 | 8) => Key::End,
+            (b'~', 3) => Key::Delete,
+            _ => Key::Unknown,
+        };
+        Ok(key)
+    }
+
+    fn read_utf8(&mut self, first: u8) -> Result<Key, Ov6Error> {
+        let len = match first {
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf7 => 4,
+            _ => return Ok(Key::Unknown),
+        };
+        let mut bytes = [first, 0, 0, 0];
+        for byte in &mut bytes[1..len] {
+            let Some(b) = self.read_byte()? else {
+                return Ok(Key::Unknown);
+            };
+            *byte = b;
+        }
+        let key = core::str::from_utf8(&bytes[..len])
+            .ok()
+            .and_then(|s| s.chars().next())
+            .map_or(Key::Unknown, Key::Char);
+        Ok(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    fn keys(input: &[u8]) -> Vec<Key> {
+        let mut reader = KeyReader::new(input);
+        let mut keys = Vec::new();
+        while let Some(key) = reader.read_key().unwrap() {
+            keys.push(key);
+        }
+        keys
+    }
+
+    #[test]
+    fn plain_chars() {
+        assert_eq!(
+            keys(b"a \r\t\x7f"),
+            [
+                Key::Char('a'),
+                Key::Char(' '),
+                Key::Enter,
+                Key::Tab,
+                Key::Backspace
+            ]
+        );
+    }
+
+    #[test]
+    fn control_chars() {
+        assert_eq!(
+            keys(b"\x01\x05\x08"),
+            [Key::Ctrl(b'A'), Key::Ctrl(b'E'), Key::Backspace]
+        );
+    }
+
+    #[test]
+    fn escape_sequences() {
+        assert_eq!(
+            keys(b"\x1b[A\x1b[B\x1b[C\x1b[D\x1bOH\x1b[F\x1b[3~\x1b[1~\x1b[4~\x1b[1;5C"),
+            [
+                Key::Up,
+                Key::Down,
+                Key::Right,
+                Key::Left,
+                Key::Home,
+                Key::End,
+                Key::Delete,
+                Key::Home,
+                Key::End,
+                Key::Right,
+            ]
+        );
+        assert_eq!(keys(b"\x1b[Z"), [Key::Unknown]);
+        assert_eq!(keys(b"\x1b"), [Key::Unknown]);
+    }
+
+    #[test]
+    fn utf8() {
+        assert_eq!(keys("あé".as_bytes()), [Key::Char('あ'), Key::Char('é')]);
+        assert_eq!(keys(b"\xe3\x81"), [Key::Unknown]);
+    }
+}
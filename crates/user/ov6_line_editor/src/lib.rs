@@ -0,0 +1,322 @@
+//! Line editor for interactive programs.
+//!
+//! [`LineEditor`] switches the console to raw mode while reading a line and
+//! supports the following keys:
+//!
+//! * `Left` / `control-b`, `Right` / `control-f` -- move the cursor
+//! * `Home` / `control-a`, `End` / `control-e` -- move to the start/end of line
+//! * `Up` / `control-p`, `Down` / `control-n` -- browse the history
+//! * `Backspace`, `Delete` -- delete a character
+//! * `control-u`, `control-k` -- delete to the start/end of line
+//! * `control-w` -- delete the previous word
+//! * `control-l` -- clear the screen
+//! * `control-c` -- discard the line
+//! * `control-d` -- end of file (on an empty line)
+//! * `Tab` -- complete the word before the cursor with the [`Completer`]
+//!
+//! If the standard input is not a terminal, lines are read without editing.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::{boxed::Box, format, string::String};
+use core::fmt::Write as _;
+
+use ov6_user_lib::{
+    error::Ov6Error,
+    io::{self, IsTerminal as _, Write as _, terminal},
+};
+
+pub use self::completion::{
+    Completer, Completion, FileNameCompleter, complete_file_name, word_start,
+};
+use self::{
+    buffer::LineBuffer,
+    completion::{common_prefix, display_name},
+    history::History,
+    key::{Key, KeyReader},
+};
+
+mod buffer;
+mod completion;
+mod history;
+mod key;
+
+/// Default number of lines kept in the history.
+pub const DEFAULT_HISTORY_SIZE: usize = 100;
+
+/// Width assumed when the console window size is not available.
+const DEFAULT_WIDTH: usize = 80;
+
+/// Line editor with history and completion.
+pub struct LineEditor {
+    history: History,
+    completer: Option<Box<dyn Completer>>,
+}
+
+impl Default for LineEditor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Result of handling a key press.
+enum Outcome {
+    Continue,
+    Accept,
+    Cancel,
+    Eof,
+}
+
+/// State of a single [`LineEditor::read_line`] call.
+struct Session<'a> {
+    prompt: &'a str,
+    buf: LineBuffer,
+    /// Index of the history entry being edited.
+    ///
+    /// Equal to the history length while editing the new line.
+    history_pos: usize,
+    /// The new line, saved while browsing the history.
+    saved_line: String,
+    /// Output not yet written to the console.
+    output: String,
+}
+
+impl Session<'_> {
+    fn bell(&mut self) {
+        self.output.push('\x07');
+    }
+
+    /// Redraws the prompt and the line, and moves the cursor to its position.
+    fn refresh(&mut self) {
+        let _ = write!(self.output, "\r{}{}\x1b[K", self.prompt, self.buf.as_str());
+        let back = self.buf.chars_after_cursor();
+        if back > 0 {
+            let _ = write!(self.output, "\x1b[{back}D");
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), Ov6Error> {
+        io::stderr().write_all(self.output.as_bytes())?;
+        self.output.clear();
+        Ok(())
+    }
+
+    /// Prints the completion candidates below the line.
+    fn show_candidates(&mut self, candidates: &[String]) {
+        let width = candidates
+            .iter()
+            .map(|cand| display_name(cand).chars().count())
+            .max()
+            .unwrap_or(0)
+            + 2;
+        let line_width = terminal::window_size(&io::stdin())
+            .map_or(DEFAULT_WIDTH, |size| usize::from(size.cols));
+        let per_line = usize::max(line_width / width, 1);
+
+        self.output.push('\n');
+        for (i, cand) in candidates.iter().enumerate() {
+            let name = display_name(cand);
+            if (i + 1) % per_line == 0 || i + 1 == candidates.len() {
+                let _ = writeln!(self.output, "{name}");
+            } else {
+                let _ = write!(self.output, "{name:width$}");
+            }
+        }
+    }
+}
+
+impl LineEditor {
+    /// Creates a line editor that keeps [`DEFAULT_HISTORY_SIZE`] lines of
+    /// history.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_history_size(DEFAULT_HISTORY_SIZE)
+    }
+
+    /// Creates a line editor that keeps `size` lines of history.
+    #[must_use]
+    pub fn with_history_size(size: usize) -> Self {
+        Self {
+            history: History::new(size),
+            completer: None,
+        }
+    }
+
+    /// Sets the hook called to complete the word before the cursor.
+    pub fn set_completer<C>(&mut self, completer: C)
+    where
+        C: Completer + 'static,
+    {
+        self.completer = Some(Box::new(completer));
+    }
+
+    /// Appends `line` to the history.
+    ///
+    /// Lines read by [`read_line`](Self::read_line) are added automatically.
+    pub fn add_history(&mut self, line: &str) {
+        self.history.push(line);
+    }
+
+    /// Reads a line from the standard input, showing `prompt`.
+    ///
+    /// The returned line does not include the trailing newline. Returns `None`
+    /// at the end of the input.
+    pub fn read_line(&mut self, prompt: &str) -> Result<Option<String>, Ov6Error> {
+        let mut stdin = io::stdin();
+        if !stdin.is_terminal() {
+            let mut line = String::new();
+            if stdin.read_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+            if line.ends_with('\n') {
+                line.pop();
+            }
+            return Ok(Some(line));
+        }
+
+        let _raw_mode = terminal::enable_raw_mode(&stdin)?;
+        let mut keys = KeyReader::new(stdin.lock());
+        let mut session = Session {
+            prompt,
+            buf: LineBuffer::new(),
+            history_pos: self.history.len(),
+            saved_line: String::new(),
+            output: String::new(),
+        };
+        session.output.push_str(prompt);
+        session.flush()?;
+
+        loop {
+            let outcome = keys
+                .read_key()?
+                .map_or(Outcome::Eof, |key| self.handle_key(&mut session, key));
+            let line = match outcome {
+                Outcome::Continue => {
+                    session.flush()?;
+                    continue;
+                }
+                Outcome::Accept => {
+                    session.output.push('\n');
+                    let line = String::from(session.buf.as_str());
+                    self.history.push(&line);
+                    Some(line)
+                }
+                Outcome::Cancel => {
+                    session.output.push_str("^C\n");
+                    Some(String::new())
+                }
+                Outcome::Eof => {
+                    session.output.push('\n');
+                    None
+                }
+            };
+            session.flush()?;
+            return Ok(line);
+        }
+    }
+
+    fn handle_key(&self, s: &mut Session<'_>, key: Key) -> Outcome {
+        let ok = match key {
+            Key::Char(c) => {
+                let at_end = s.buf.chars_after_cursor() == 0;
+                s.buf.insert_char(c);
+                if at_end {
+                    // no need to redraw the whole line when appending
+                    s.output.push(c);
+                    return Outcome::Continue;
+                }
+                true
+            }
+            Key::Enter => return Outcome::Accept,
+            Key::Ctrl(b'C') => return Outcome::Cancel,
+            Key::Ctrl(b'D') if s.buf.is_empty() => return Outcome::Eof,
+            Key::Tab => self.complete(s),
+            Key::Backspace => s.buf.delete_prev(),
+            Key::Delete | Key::Ctrl(b'D') => s.buf.delete_next(),
+            Key::Left | Key::Ctrl(b'B') => s.buf.move_left(),
+            Key::Right | Key::Ctrl(b'F') => s.buf.move_right(),
+            Key::Home | Key::Ctrl(b'A') => {
+                s.buf.move_home();
+                true
+            }
+            Key::End | Key::Ctrl(b'E') => {
+                s.buf.move_end();
+                true
+            }
+            Key::Up | Key::Ctrl(b'P') => self.history_prev(s),
+            Key::Down | Key::Ctrl(b'N') => self.history_next(s),
+            Key::Ctrl(b'U') => {
+                s.buf.kill_to_start();
+                true
+            }
+            Key::Ctrl(b'K') => {
+                s.buf.kill_to_end();
+                true
+            }
+            Key::Ctrl(b'W') => s.buf.delete_prev_word(),
+            Key::Ctrl(b'L') => {
+                s.output.push_str("\x1b[H\x1b[2J");
+                true
+            }
+            Key::Ctrl(_) | Key::Unknown => false,
+        };
+        if !ok {
+            s.bell();
+        }
+        s.refresh();
+        Outcome::Continue
+    }
+
+    fn history_prev(&self, s: &mut Session<'_>) -> bool {
+        if s.history_pos == 0 {
+            return false;
+        }
+        if s.history_pos == self.history.len() {
+            s.saved_line = s.buf.as_str().into();
+        }
+        s.history_pos -= 1;
+        s.buf.set(self.history.get(s.history_pos).unwrap());
+        true
+    }
+
+    fn history_next(&self, s: &mut Session<'_>) -> bool {
+        if s.history_pos >= self.history.len() {
+            return false;
+        }
+        s.history_pos += 1;
+        match self.history.get(s.history_pos) {
+            Some(line) => s.buf.set(line),
+            None => s.buf.set(&s.saved_line),
+        }
+        true
+    }
+
+    fn complete(&self, s: &mut Session<'_>) -> bool {
+        let Some(completer) = &self.completer else {
+            return false;
+        };
+        let pos = s.buf.pos();
+        let Completion { start, candidates } = completer.complete(s.buf.as_str(), pos);
+        let start = start.min(pos);
+        match candidates.as_slice() {
+            [] => return false,
+            [cand] => {
+                // Directories are completed without a trailing space so that
+                // the completion can continue into them.
+                let suffix = if cand.ends_with('/') { "" } else { " " };
+                s.buf.replace_range(start..pos, &format!("{cand}{suffix}"));
+            }
+            _ => {
+                let prefix = common_prefix(&candidates);
+                if prefix.len() > pos - start {
+                    s.buf.replace_range(start..pos, prefix);
+                } else {
+                    s.show_candidates(&candidates);
+                }
+            }
+        }
+        true
+    }
+}
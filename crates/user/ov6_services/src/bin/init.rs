@@ -4,10 +4,17 @@ use ov6_user_lib::{
     env, eprintln,
     error::Ov6Error,
     fs::{self, File},
-    process::{self, ProcessBuilder},
+    os::ov6::syscall::{self, ShutdownRequest},
+    process::{self, ChildWithIo, ProcessBuilder},
 };
 
 const CONSOLE: u32 = 1;
+const DISK: u32 = 2;
+/// Device file of the raw root disk.
+const DISK_PATH: &str = "disk0";
+const SHELL: &str = "/bin/sh";
+/// Startup script run before the console shell is started.
+const RC_PATH: &str = "/etc/rc";
 
 fn open_console() -> Result<File, Ov6Error> {
     File::options().read(true).write(true).open("console")
@@ -17,6 +24,89 @@ fn create_console() -> Result<(), Ov6Error> {
     fs::mknod("console", CONSOLE, 0)
 }
 
+fn create_disk() -> Result<(), Ov6Error> {
+    if fs::metadata(DISK_PATH).is_ok() {
+        return Ok(());
+    }
+    fs::mknod(DISK_PATH, DISK, 0)
+}
+
+fn spawn_shell(argv: &[&str]) -> ChildWithIo {
+    let arg0 = env::arg0();
+    let Ok(sh) = ProcessBuilder::new()
+        .spawn_fn(|| {
+            let Ok(_) = process::exec(SHELL, argv).map_err(|e| {
+                panic!("{} exec sh failed: {e}", arg0.display());
+            });
+            unreachable!()
+        })
+        .map_err(|e| {
+            panic!("{}: fork failed: {e}", arg0.display());
+        });
+    sh
+}
+
+/// Spawns a process that exits when a shutdown is requested.
+///
+/// `wait()` cannot wait for a shutdown request, so init watches for the exit
+/// of this process instead.
+fn spawn_shutdown_waiter() -> ChildWithIo {
+    let arg0 = env::arg0();
+    let Ok(waiter) = ProcessBuilder::new()
+        .spawn_fn(|| {
+            let Ok(_) = syscall::wait_shutdown_request().map_err(|e| {
+                panic!("{}: waiting shutdown request failed: {e}", arg0.display());
+            });
+            process::exit(0);
+        })
+        .map_err(|e| {
+            panic!("{}: fork failed: {e}", arg0.display());
+        });
+    waiter
+}
+
+/// Waits for `child` to exit.
+///
+/// Returns `false` if a shutdown is requested before `child` exits.
+fn wait_child(child: &ChildWithIo, waiter: &ChildWithIo) -> bool {
+    let arg0 = env::arg0();
+    loop {
+        // this call to wait() returns if the child or the waiter exits,
+        // or if a parentless process exits.
+        let Ok((wpid, _status)) = process::wait_any()
+            .map_err(|e| panic!("{}: wait returned an error: {e}", arg0.display()));
+        if wpid == child.id() {
+            return true;
+        }
+        if wpid == waiter.id() {
+            return false;
+        }
+        // it was a parentless process; do nothing
+    }
+}
+
+/// Stops `child`, commits the file system, and halts or reboots the system.
+fn shutdown(mut child: ChildWithIo) -> ! {
+    let arg0 = env::arg0();
+    let Ok(req) = syscall::wait_shutdown_request()
+        .map_err(|e| panic!("{}: waiting shutdown request failed: {e}", arg0.display()));
+
+    eprintln!("{}: stopping sh", arg0.display());
+    let _ = child.kill();
+    let _ = child.wait();
+
+    eprintln!("{}: syncing file system", arg0.display());
+    if let Err(e) = syscall::sync() {
+        eprintln!("{}: sync failed: {e}", arg0.display());
+    }
+
+    let Err(e) = match req {
+        ShutdownRequest::Halt(code) => syscall::halt(code),
+        ShutdownRequest::Reboot => syscall::reboot(),
+    };
+    panic!("{}: shutdown failed: {e}", arg0.display());
+}
+
 fn main() {
     let arg0 = env::arg0();
     let console = open_console()
@@ -29,31 +119,28 @@ fn main() {
     let _stdout = console.try_clone().unwrap();
     let _stderr = console.try_clone().unwrap();
 
-    loop {
-        eprintln!("{}: starting sh", arg0.display());
+    if let Err(e) = create_disk() {
+        eprintln!("{}: cannot create {DISK_PATH}: {e}", arg0.display());
+    }
 
-        let Ok(sh) = ProcessBuilder::new()
-            .spawn_fn(|| {
-                let Ok(_) = process::exec("sh", &["sh"]).map_err(|e| {
-                    panic!("{} exec sh failed: {e}", arg0.display());
-                });
-                unreachable!()
-            })
-            .map_err(|e| {
-                panic!("{}: fork failed: {e}", arg0.display());
-            });
+    // The root file system is mounted by the kernel before init starts.
 
-        loop {
-            // this call to wait() returns if the shell exits,
-            // or if a parentless process exits.
-            let Ok((wpid, _status)) = process::wait_any()
-                .map_err(|e| panic!("{}: wait returned an error: {e}", arg0.display()));
-            if wpid != sh.id() {
-                // it was a parentless process; do nothing
-                continue;
-            }
-            // the shell exited; restart it.
-            break;
+    let waiter = spawn_shutdown_waiter();
+
+    if fs::metadata(RC_PATH).is_ok() {
+        eprintln!("{}: running {RC_PATH}", arg0.display());
+        let rc = spawn_shell(&["sh", RC_PATH]);
+        if !wait_child(&rc, &waiter) {
+            shutdown(rc);
+        }
+    }
+
+    loop {
+        eprintln!("{}: starting sh", arg0.display());
+        let sh = spawn_shell(&["sh"]);
+        if !wait_child(&sh, &waiter) {
+            shutdown(sh);
         }
+        // the shell exited; restart it.
     }
 }
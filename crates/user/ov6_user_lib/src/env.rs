@@ -4,7 +4,11 @@ use core::{
     sync::atomic::{AtomicPtr, AtomicUsize, Ordering},
 };
 
-use ov6_types::{os_str::OsStr, path::Path};
+use alloc_crate::vec::Vec;
+use ov6_types::{
+    os_str::{OsStr, OsString},
+    path::{Path, PathBuf},
+};
 
 use crate::{error::Ov6Error, os::ov6::syscall};
 
@@ -118,3 +122,19 @@ where
 {
     syscall::chdir(path.as_ref())
 }
+
+/// Returns the absolute path of the current working directory.
+///
+/// The path is the one the kernel tracks on each directory change, as seen
+/// from the root directory of the process.
+pub fn current_directory() -> Result<PathBuf, Ov6Error> {
+    let mut buf = Vec::from([0; 64]);
+    loop {
+        let len = syscall::getcwd(&mut buf)?;
+        if len <= buf.len() {
+            buf.truncate(len);
+            return Ok(PathBuf::from(OsString::from_vec(buf)));
+        }
+        buf.resize(len, 0);
+    }
+}
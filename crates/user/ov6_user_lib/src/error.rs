@@ -11,8 +11,8 @@ pub enum Ov6Error {
     // ESRCH
     #[error("no such process")]
     ProcessNotFound = 3,
-    // #[error("interrupted system call")]
-    // Interrupted,
+    #[error("interrupted system call")]
+    Interrupted,
     #[error("input/output error")]
     Io,
     #[error("no such device or address")]
@@ -53,8 +53,8 @@ pub enum Ov6Error {
     TooManyOpenFilesSystem,
     #[error("too many open files")]
     TooManyOpenFiles,
-    // #[error("inappropriate I/O control operation")]
-    // NoTty,
+    #[error("inappropriate I/O control operation")]
+    NoTty,
     #[error("text file busy")]
     ExecutableFileBusy,
     #[error("file too large")]
@@ -83,13 +83,21 @@ pub enum Ov6Error {
     // FunctionNotImplemented,
     #[error("directory not empty")]
     DirectoryNotEmpty,
+    #[error("identifier removed")]
+    IdentifierRemoved,
+    #[error("socket operation on non-socket")]
+    NotASocket,
     #[error("message too long")]
     MessageTooLong,
     #[error("address already in use")]
     AddrInUse,
+    #[error("connection refused")]
+    ConnectionRefused,
 
     #[error("stream did not contain valid UTF-8")]
     InvalidUtf8,
+    #[error("invalid data")]
+    InvalidData,
     #[error("failed to fill whole buffer")]
     ReadExactEof,
     #[error("failed to write whole buffer")]
@@ -103,7 +111,7 @@ pub enum Ov6Error {
 impl Ov6Error {
     #[must_use]
     pub fn is_interrupted(&self) -> bool {
-        false // TODO
+        matches!(self, Self::Interrupted)
     }
 }
 
@@ -113,6 +121,7 @@ impl From<SyscallError> for Ov6Error {
             SyscallError::NotPermitted => Self::NotPermitted,
             SyscallError::FsEntryNotFound => Self::FsEntryNotFound,
             SyscallError::ProcessNotFound => Self::ProcessNotFound,
+            SyscallError::Interrupted => Self::Interrupted,
             SyscallError::Io => Self::Io,
             SyscallError::DeviceNotFound => Self::DeviceNotFound,
             SyscallError::ArgumentListTooLong => Self::ArgumentListTooLong,
@@ -132,6 +141,7 @@ impl From<SyscallError> for Ov6Error {
             SyscallError::InvalidInput => Self::InvalidInput,
             SyscallError::TooManyOpenFilesSystem => Self::TooManyOpenFilesSystem,
             SyscallError::TooManyOpenFiles => Self::TooManyOpenFiles,
+            SyscallError::NoTty => Self::NoTty,
             SyscallError::ExecutableFileBusy => Self::ExecutableFileBusy,
             SyscallError::FileTooLarge => Self::FileTooLarge,
             SyscallError::StorageFull => Self::StorageFull,
@@ -141,8 +151,11 @@ impl From<SyscallError> for Ov6Error {
             SyscallError::BrokenPipe => Self::BrokenPipe,
             SyscallError::InvalidFilename => Self::InvalidFilename,
             SyscallError::DirectoryNotEmpty => Self::DirectoryNotEmpty,
+            SyscallError::IdentifierRemoved => Self::IdentifierRemoved,
+            SyscallError::NotASocket => Self::NotASocket,
             SyscallError::MessageTooLong => Self::MessageTooLong,
             SyscallError::AddrInUse => Self::AddrInUse,
+            SyscallError::ConnectionRefused => Self::ConnectionRefused,
             SyscallError::Unknown => Self::Unknown,
         }
     }
@@ -1,13 +1,20 @@
 use dataview::PodMethods as _;
-use ov6_types::{fs::RawFd, os_str::OsStr, path::Path};
+pub use ov6_fs_types::{FS_BLOCK_SIZE, FsUuid};
+use ov6_fs_types::{FS_LABEL_MAX, decode_label};
+use ov6_types::{
+    fs::RawFd,
+    os_str::OsStr,
+    path::{Path, PathBuf},
+};
 pub use syscall::StatType;
 
 use crate::{
+    env,
     error::Ov6Error,
-    io::{Read, Write},
+    io::{IoSlice, IoSliceMut, Read, Write},
     os::{
         fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, IntoRawFd, OwnedFd},
-        ov6::syscall::{self, OpenFlags},
+        ov6::syscall::{self, AT_FDCWD, AccessHint, AtFlags, OpenFlags},
     },
 };
 
@@ -17,9 +24,25 @@ pub struct Metadata {
     ty: StatType,
     nlink: u16,
     size: u64,
+    blocks: u32,
+    major: u32,
+    minor: u32,
 }
 
 impl Metadata {
+    fn from_raw(stat: &syscall::Stat) -> Result<Self, Ov6Error> {
+        Ok(Self {
+            dev: stat.dev,
+            ino: stat.ino,
+            ty: StatType::from_repr(stat.ty).ok_or(Ov6Error::Unknown)?,
+            nlink: stat.nlink,
+            size: stat.size,
+            blocks: stat.blocks,
+            major: stat.major,
+            minor: stat.minor,
+        })
+    }
+
     #[must_use]
     pub fn ty(&self) -> StatType {
         self.ty
@@ -40,6 +63,11 @@ impl Metadata {
         self.ty == StatType::Dir
     }
 
+    #[must_use]
+    pub fn is_socket(&self) -> bool {
+        self.ty == StatType::Socket
+    }
+
     #[must_use]
     pub fn dev(&self) -> u32 {
         self.dev
@@ -59,6 +87,94 @@ impl Metadata {
     pub fn size(&self) -> u64 {
         self.size
     }
+
+    /// Returns the number of disk blocks allocated to the file.
+    ///
+    /// Unlike [`size()`](Self::size), this includes the indirect block and
+    /// excludes holes in the content.
+    #[must_use]
+    pub fn blocks(&self) -> u32 {
+        self.blocks
+    }
+
+    /// Returns the major device number of a device file, or zero.
+    #[must_use]
+    pub fn major(&self) -> u32 {
+        self.major
+    }
+
+    /// Returns the minor device number of a device file, or zero.
+    #[must_use]
+    pub fn minor(&self) -> u32 {
+        self.minor
+    }
+}
+
+/// Statistics of a file system.
+pub struct FsStats {
+    dev: u32,
+    block_size: u32,
+    blocks: u32,
+    free_blocks: u32,
+    inodes: u32,
+    free_inodes: u32,
+    uuid: FsUuid,
+    label: [u8; FS_LABEL_MAX],
+}
+
+impl FsStats {
+    fn from_raw(statfs: &syscall::StatFs) -> Self {
+        Self {
+            dev: statfs.dev,
+            block_size: statfs.block_size,
+            blocks: statfs.blocks,
+            free_blocks: statfs.free_blocks,
+            inodes: statfs.inodes,
+            free_inodes: statfs.free_inodes,
+            uuid: FsUuid::from_bytes(statfs.uuid),
+            label: statfs.label,
+        }
+    }
+
+    #[must_use]
+    pub fn dev(&self) -> u32 {
+        self.dev
+    }
+
+    #[must_use]
+    pub fn block_size(&self) -> u32 {
+        self.block_size
+    }
+
+    #[must_use]
+    pub fn blocks(&self) -> u32 {
+        self.blocks
+    }
+
+    #[must_use]
+    pub fn free_blocks(&self) -> u32 {
+        self.free_blocks
+    }
+
+    #[must_use]
+    pub fn inodes(&self) -> u32 {
+        self.inodes
+    }
+
+    #[must_use]
+    pub fn free_inodes(&self) -> u32 {
+        self.free_inodes
+    }
+
+    #[must_use]
+    pub fn uuid(&self) -> FsUuid {
+        self.uuid
+    }
+
+    #[must_use]
+    pub fn label(&self) -> &OsStr {
+        decode_label(&self.label)
+    }
 }
 
 #[expect(clippy::struct_excessive_bools)]
@@ -68,6 +184,9 @@ pub struct OpenOptions {
     write: bool,
     create: bool,
     truncate: bool,
+    direct: bool,
+    tmpfile: bool,
+    close_on_exec: bool,
 }
 
 impl OpenOptions {
@@ -96,15 +215,59 @@ impl OpenOptions {
         self
     }
 
+    /// Sets the option to transfer block-aligned data directly between the
+    /// buffer and the disk, bypassing the kernel caches.
+    ///
+    /// Reads and writes whose file offset or buffer address is not aligned to
+    /// [`FS_BLOCK_SIZE`] go through the caches as usual.
+    pub fn direct(&mut self, direct: bool) -> &mut Self {
+        self.direct = direct;
+        self
+    }
+
+    /// Sets the option to create an unnamed file in the directory `path`
+    /// passed to [`open`](Self::open), instead of opening `path` itself.
+    ///
+    /// The file is removed when it is closed, unless it is given a name with
+    /// [`File::link`] before that. It must be opened for writing.
+    pub fn tmpfile(&mut self, tmpfile: bool) -> &mut Self {
+        self.tmpfile = tmpfile;
+        self
+    }
+
+    /// Sets the option to close the file descriptor of the file when the
+    /// process calls `exec`.
+    pub fn close_on_exec(&mut self, close_on_exec: bool) -> &mut Self {
+        self.close_on_exec = close_on_exec;
+        self
+    }
+
     pub fn open<P>(&self, path: P) -> Result<File, Ov6Error>
     where
         P: AsRef<Path>,
     {
+        let fd = syscall::open(path.as_ref(), self.flags()?)?;
+        Ok(File { fd })
+    }
+
+    /// Opens a file at `path` relative to the directory `dir`.
+    pub fn open_at<P>(&self, dir: &Dir, path: P) -> Result<File, Ov6Error>
+    where
+        P: AsRef<Path>,
+    {
+        let fd = syscall::openat(dir.as_raw_fd(), path.as_ref(), self.flags()?)?;
+        Ok(File { fd })
+    }
+
+    fn flags(&self) -> Result<OpenFlags, Ov6Error> {
         let Self {
             read,
             write,
             create,
             truncate,
+            direct,
+            tmpfile,
+            close_on_exec,
         } = self;
         let mut flags = OpenFlags::empty();
         match (read, write) {
@@ -115,8 +278,10 @@ impl OpenOptions {
         }
         flags.set(OpenFlags::CREATE, *create);
         flags.set(OpenFlags::TRUNC, *truncate);
-        let fd = syscall::open(path.as_ref(), flags)?;
-        Ok(File { fd })
+        flags.set(OpenFlags::DIRECT, *direct);
+        flags.set(OpenFlags::TMPFILE, *tmpfile);
+        flags.set(OpenFlags::CLOEXEC, *close_on_exec);
+        Ok(flags)
     }
 }
 
@@ -156,13 +321,73 @@ impl File {
 
     pub fn metadata(&self) -> Result<Metadata, Ov6Error> {
         let stat = syscall::fstat(self.fd.as_raw_fd())?;
-        Ok(Metadata {
-            dev: stat.dev,
-            ino: stat.ino,
-            ty: StatType::from_repr(stat.ty).ok_or(Ov6Error::Unknown)?,
-            nlink: stat.nlink,
-            size: stat.size,
-        })
+        Metadata::from_raw(&stat)
+    }
+
+    /// Adds an entry `path` referring to the file.
+    ///
+    /// This gives a name to a file opened with [`OpenOptions::tmpfile`].
+    pub fn link<P>(&self, path: P) -> Result<(), Ov6Error>
+    where
+        P: AsRef<Path>,
+    {
+        syscall::flink(self.fd.as_raw_fd(), path.as_ref())
+    }
+
+    /// Returns the statistics of the file system containing the file.
+    pub fn fs_stats(&self) -> Result<FsStats, Ov6Error> {
+        let statfs = syscall::fstatfs(self.fd.as_raw_fd())?;
+        Ok(FsStats::from_raw(&statfs))
+    }
+
+    /// Reads from the file at `offset` without changing the file offset.
+    ///
+    /// Returns the number of bytes read.
+    pub fn read_at(&self, buf: &mut [u8], offset: u64) -> Result<usize, Ov6Error> {
+        let Ok(offset) = usize::try_from(offset) else {
+            return Err(Ov6Error::InvalidInput);
+        };
+        syscall::pread(self.fd.as_raw_fd(), buf, offset)
+    }
+
+    /// Writes to the file at `offset` without changing the file offset.
+    ///
+    /// Returns the number of bytes written.
+    pub fn write_at(&self, buf: &[u8], offset: u64) -> Result<usize, Ov6Error> {
+        let Ok(offset) = usize::try_from(offset) else {
+            return Err(Ov6Error::InvalidInput);
+        };
+        syscall::pwrite(self.fd.as_raw_fd(), buf, offset)
+    }
+
+    /// Reads exactly `buf.len()` bytes from the file at `offset`.
+    pub fn read_exact_at(&self, mut buf: &mut [u8], mut offset: u64) -> Result<(), Ov6Error> {
+        while !buf.is_empty() {
+            let n = match self.read_at(buf, offset) {
+                Ok(0) => return Err(Ov6Error::ReadExactEof),
+                Ok(n) => n,
+                Err(e) if e.is_interrupted() => continue,
+                Err(e) => return Err(e),
+            };
+            buf = &mut buf[n..];
+            offset += n as u64;
+        }
+        Ok(())
+    }
+
+    /// Writes all of `buf` to the file at `offset`.
+    pub fn write_all_at(&self, mut buf: &[u8], mut offset: u64) -> Result<(), Ov6Error> {
+        while !buf.is_empty() {
+            let n = match self.write_at(buf, offset) {
+                Ok(0) => return Err(Ov6Error::WriteAllEof),
+                Ok(n) => n,
+                Err(e) if e.is_interrupted() => continue,
+                Err(e) => return Err(e),
+            };
+            buf = &buf[n..];
+            offset += n as u64;
+        }
+        Ok(())
     }
 }
 
@@ -209,6 +434,10 @@ impl Write for File {
         syscall::write(self.fd.as_raw_fd(), buf)
     }
 
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> Result<usize, Ov6Error> {
+        syscall::writev(self.fd.as_raw_fd(), bufs)
+    }
+
     fn flush(&mut self) -> Result<(), Ov6Error> {
         Ok(())
     }
@@ -219,6 +448,10 @@ impl Write for &'_ File {
         syscall::write(self.fd.as_raw_fd(), buf)
     }
 
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> Result<usize, Ov6Error> {
+        syscall::writev(self.fd.as_raw_fd(), bufs)
+    }
+
     fn flush(&mut self) -> Result<(), Ov6Error> {
         Ok(())
     }
@@ -228,12 +461,20 @@ impl Read for File {
     fn read(&mut self, buf: &mut [u8]) -> Result<usize, Ov6Error> {
         syscall::read(self.fd.as_raw_fd(), buf)
     }
+
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>]) -> Result<usize, Ov6Error> {
+        syscall::readv(self.fd.as_raw_fd(), bufs)
+    }
 }
 
 impl Read for &'_ File {
     fn read(&mut self, buf: &mut [u8]) -> Result<usize, Ov6Error> {
         syscall::read(self.fd.as_raw_fd(), buf)
     }
+
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>]) -> Result<usize, Ov6Error> {
+        syscall::readv(self.fd.as_raw_fd(), bufs)
+    }
 }
 
 pub fn mknod<P>(path: P, major: u32, minor: u16) -> Result<(), Ov6Error>
@@ -252,18 +493,65 @@ where
 }
 
 pub fn metadata<P>(path: P) -> Result<Metadata, Ov6Error>
+where
+    P: AsRef<Path>,
+{
+    let stat = syscall::fstatat(AT_FDCWD, path.as_ref())?;
+    Metadata::from_raw(&stat)
+}
+
+/// Copies the contents of `from` to `to`.
+///
+/// `to` is created if it does not exist, and truncated otherwise. The data is
+/// copied by the kernel without going through a user buffer.
+///
+/// Returns the number of bytes copied.
+pub fn copy<P, Q>(from: P, to: Q) -> Result<u64, Ov6Error>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let src = File::open(from)?;
+    // the hint is only for performance, so errors are ignored.
+    let _ = syscall::set_access_hint(src.as_raw_fd(), AccessHint::Sequential);
+    let dst = File::create(to)?;
+    let mut total = 0;
+    loop {
+        let n = syscall::send_file(dst.as_raw_fd(), src.as_raw_fd(), usize::MAX)?;
+        if n == 0 {
+            break;
+        }
+        total += n as u64;
+    }
+    Ok(total)
+}
+
+/// Returns the statistics of the file system containing `path`.
+pub fn fs_stats<P>(path: P) -> Result<FsStats, Ov6Error>
 where
     P: AsRef<Path>,
 {
     let fd = syscall::open(path.as_ref(), OpenFlags::READ_ONLY)?;
-    let stat = syscall::fstat(fd.as_raw_fd())?;
-    Ok(Metadata {
-        dev: stat.dev,
-        ino: stat.ino,
-        ty: StatType::from_repr(stat.ty).ok_or(Ov6Error::Unknown)?,
-        nlink: stat.nlink,
-        size: stat.size,
-    })
+    let statfs = syscall::fstatfs(fd.as_raw_fd())?;
+    Ok(FsStats::from_raw(&statfs))
+}
+
+/// Returns the canonical, absolute form of `path`.
+///
+/// `.` and `..` components are resolved with the same rules as the kernel
+/// path walker, and the resulting path is checked to exist.
+pub fn canonicalize<P>(path: P) -> Result<PathBuf, Ov6Error>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let path = if path.is_absolute() {
+        path.normalize()
+    } else {
+        env::current_directory()?.join(path).normalize()
+    };
+    let _ = metadata(&path)?;
+    Ok(path)
 }
 
 pub fn remove_file<P>(path: P) -> Result<(), Ov6Error>
@@ -273,6 +561,14 @@ where
     syscall::unlink(path.as_ref())
 }
 
+/// Removes an empty directory.
+pub fn remove_dir<P>(path: P) -> Result<(), Ov6Error>
+where
+    P: AsRef<Path>,
+{
+    syscall::rmdir(path.as_ref())
+}
+
 pub fn create_dir<P>(path: P) -> Result<(), Ov6Error>
 where
     P: AsRef<Path>,
@@ -285,11 +581,144 @@ where
     P: AsRef<Path>,
 {
     let fd = syscall::open(path.as_ref(), OpenFlags::READ_ONLY)?;
+    let fd = check_dir(fd)?;
+    Ok(ReadDir { fd })
+}
+
+fn check_dir(fd: OwnedFd) -> Result<OwnedFd, Ov6Error> {
     let st = syscall::fstat(fd.as_raw_fd())?;
     if st.ty != StatType::Dir as u16 {
         return Err(Ov6Error::NotADirectory);
     }
-    Ok(ReadDir { fd })
+    Ok(fd)
+}
+
+/// An open directory that paths can be resolved from.
+///
+/// Paths given to the methods are relative to the directory itself, which
+/// stays the same even if it is moved, so a directory tree can be walked
+/// without racing with renames of its ancestors. Absolute paths are resolved
+/// from the root directory as usual.
+#[derive(Debug)]
+pub struct Dir {
+    fd: OwnedFd,
+}
+
+impl Dir {
+    /// Opens the directory at `path`.
+    pub fn open<P>(path: P) -> Result<Self, Ov6Error>
+    where
+        P: AsRef<Path>,
+    {
+        let fd = syscall::open(path.as_ref(), OpenFlags::READ_ONLY)?;
+        let fd = check_dir(fd)?;
+        Ok(Self { fd })
+    }
+
+    /// Opens the directory at `path` relative to this directory.
+    pub fn open_dir<P>(&self, path: P) -> Result<Self, Ov6Error>
+    where
+        P: AsRef<Path>,
+    {
+        let fd = syscall::openat(self.fd.as_raw_fd(), path.as_ref(), OpenFlags::READ_ONLY)?;
+        let fd = check_dir(fd)?;
+        Ok(Self { fd })
+    }
+
+    /// Opens the file at `path` relative to this directory in read-only mode.
+    pub fn open_file<P>(&self, path: P) -> Result<File, Ov6Error>
+    where
+        P: AsRef<Path>,
+    {
+        OpenOptions::new().read(true).open_at(self, path)
+    }
+
+    /// Creates or truncates the file at `path` relative to this directory, and
+    /// opens it in write-only mode.
+    pub fn create_file<P>(&self, path: P) -> Result<File, Ov6Error>
+    where
+        P: AsRef<Path>,
+    {
+        OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open_at(self, path)
+    }
+
+    pub fn try_clone(&self) -> Result<Self, Ov6Error> {
+        let fd = syscall::dup(self.fd.as_raw_fd())?;
+        Ok(Self { fd })
+    }
+
+    /// Returns the metadata of the directory itself.
+    pub fn dir_metadata(&self) -> Result<Metadata, Ov6Error> {
+        let stat = syscall::fstat(self.fd.as_raw_fd())?;
+        Metadata::from_raw(&stat)
+    }
+
+    /// Returns the metadata of the entry at `path` relative to this directory.
+    pub fn metadata<P>(&self, path: P) -> Result<Metadata, Ov6Error>
+    where
+        P: AsRef<Path>,
+    {
+        let stat = syscall::fstatat(self.fd.as_raw_fd(), path.as_ref())?;
+        Metadata::from_raw(&stat)
+    }
+
+    pub fn create_dir<P>(&self, path: P) -> Result<(), Ov6Error>
+    where
+        P: AsRef<Path>,
+    {
+        syscall::mkdirat(self.fd.as_raw_fd(), path.as_ref())
+    }
+
+    pub fn remove_file<P>(&self, path: P) -> Result<(), Ov6Error>
+    where
+        P: AsRef<Path>,
+    {
+        syscall::unlinkat(self.fd.as_raw_fd(), path.as_ref(), AtFlags::empty())
+    }
+
+    /// Removes the empty directory at `path` relative to this directory.
+    pub fn remove_dir<P>(&self, path: P) -> Result<(), Ov6Error>
+    where
+        P: AsRef<Path>,
+    {
+        syscall::unlinkat(self.fd.as_raw_fd(), path.as_ref(), AtFlags::REMOVE_DIR)
+    }
+
+    /// Returns an iterator over the entries of this directory.
+    ///
+    /// The directory is opened again, so the iterator starts from the first
+    /// entry and does not share the file offset with `self`.
+    pub fn read_dir(&self) -> Result<ReadDir, Ov6Error> {
+        let fd = syscall::openat(self.fd.as_raw_fd(), Path::new("."), OpenFlags::READ_ONLY)?;
+        Ok(ReadDir { fd })
+    }
+
+    /// Makes this directory the current directory of the process.
+    pub fn set_current(&self) -> Result<(), Ov6Error> {
+        syscall::fchdir(self.fd.as_raw_fd())
+    }
+}
+
+impl AsFd for Dir {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
+impl AsRawFd for Dir {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+impl From<Dir> for OwnedFd {
+    fn from(dir: Dir) -> Self {
+        dir.fd
+    }
 }
 
 pub struct ReadDir {
@@ -308,17 +737,24 @@ impl Iterator for ReadDir {
             if size == 0 {
                 return None;
             }
-            if ent.ino().is_none() || ent.name() == "." || ent.name() == ".." {
+            let Some(ino) = ent.ino() else {
+                continue;
+            };
+            if ent.name() == "." || ent.name() == ".." {
                 continue;
             }
             assert_eq!(size, size_of::<ov6_fs_types::DirEntry>());
-            return Some(Ok(DirEntry { ent }));
+            return Some(Ok(DirEntry {
+                ent,
+                ino: ino.value(),
+            }));
         }
     }
 }
 
 pub struct DirEntry {
     ent: ov6_fs_types::DirEntry,
+    ino: u32,
 }
 
 impl DirEntry {
@@ -326,4 +762,9 @@ impl DirEntry {
     pub fn name(&self) -> &OsStr {
         self.ent.name()
     }
+
+    #[must_use]
+    pub fn ino(&self) -> u32 {
+        self.ino
+    }
 }
@@ -1,6 +1,12 @@
 // some codes are borrowed from Rust standard library
 
-use core::{cmp, io::BorrowedCursor};
+use core::{
+    cmp, fmt,
+    io::BorrowedCursor,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    slice,
+};
 
 use alloc_crate::{string::String, vec::Vec};
 use ov6_syscall::StatType;
@@ -20,14 +26,109 @@ const DEFAULT_BUF_SIZE: usize = 1024;
 
 mod buffered;
 mod stdio;
+pub mod terminal;
 
 pub(crate) fn cleanup() {
     stdio::cleanup();
 }
 
+/// A buffer used by [`Write::write_vectored()`].
+///
+/// Has the same layout as the I/O vector entries passed to the kernel.
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct IoSlice<'a> {
+    ptr: *const u8,
+    len: usize,
+    _phantom: PhantomData<&'a [u8]>,
+}
+
+unsafe impl Send for IoSlice<'_> {}
+unsafe impl Sync for IoSlice<'_> {}
+
+impl<'a> IoSlice<'a> {
+    #[must_use]
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self {
+            ptr: buf.as_ptr(),
+            len: buf.len(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl Deref for IoSlice<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl fmt::Debug for IoSlice<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+/// A buffer used by [`Read::read_vectored()`].
+///
+/// Has the same layout as the I/O vector entries passed to the kernel.
+#[repr(C)]
+pub struct IoSliceMut<'a> {
+    ptr: *mut u8,
+    len: usize,
+    _phantom: PhantomData<&'a mut [u8]>,
+}
+
+unsafe impl Send for IoSliceMut<'_> {}
+unsafe impl Sync for IoSliceMut<'_> {}
+
+impl<'a> IoSliceMut<'a> {
+    #[must_use]
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        Self {
+            ptr: buf.as_mut_ptr(),
+            len: buf.len(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl Deref for IoSliceMut<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl DerefMut for IoSliceMut<'_> {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        unsafe { slice::from_raw_parts_mut(self.ptr, self.len) }
+    }
+}
+
+impl fmt::Debug for IoSliceMut<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
 pub trait Read {
     fn read(&mut self, buf: &mut [u8]) -> Result<usize, Ov6Error>;
 
+    /// Reads into `bufs` in order.
+    ///
+    /// The default implementation reads into the first non-empty buffer.
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>]) -> Result<usize, Ov6Error> {
+        let buf = bufs
+            .iter_mut()
+            .find(|b| !b.is_empty())
+            .map_or(&mut [][..], |b| &mut **b);
+        self.read(buf)
+    }
+
     fn read_to_end(&mut self, buf: &mut Vec<u8>) -> Result<usize, Ov6Error> {
         let start_len = buf.len();
 
@@ -111,6 +212,17 @@ pub trait Write {
     fn write(&mut self, buf: &[u8]) -> Result<usize, Ov6Error>;
     fn flush(&mut self) -> Result<(), Ov6Error>;
 
+    /// Writes `bufs` in order.
+    ///
+    /// The default implementation writes the first non-empty buffer.
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> Result<usize, Ov6Error> {
+        let buf = bufs
+            .iter()
+            .find(|b| !b.is_empty())
+            .map_or(&[][..], |b| &**b);
+        self.write(buf)
+    }
+
     fn write_all(&mut self, mut buf: &[u8]) -> Result<(), Ov6Error> {
         while !buf.is_empty() {
             let n = match self.write(buf) {
@@ -0,0 +1,61 @@
+//! Terminal control.
+
+pub use ov6_syscall::{TerminalMode, WindowSize};
+
+use crate::{
+    error::Ov6Error,
+    os::{
+        fd::{AsFd, AsRawFd as _, BorrowedFd},
+        ov6::syscall,
+    },
+};
+
+/// Returns the window size of the terminal.
+pub fn window_size<F>(fd: &F) -> Result<WindowSize, Ov6Error>
+where
+    F: AsFd,
+{
+    syscall::window_size(fd.as_fd().as_raw_fd())
+}
+
+/// Returns the input mode of the terminal.
+pub fn mode<F>(fd: &F) -> Result<TerminalMode, Ov6Error>
+where
+    F: AsFd,
+{
+    syscall::terminal_mode(fd.as_fd().as_raw_fd())
+}
+
+/// Sets the input mode of the terminal.
+pub fn set_mode<F>(fd: &F, mode: TerminalMode) -> Result<(), Ov6Error>
+where
+    F: AsFd,
+{
+    syscall::set_terminal_mode(fd.as_fd().as_raw_fd(), mode)
+}
+
+/// Switches the terminal to raw mode until the returned guard is dropped.
+pub fn enable_raw_mode<F>(fd: &F) -> Result<RawModeGuard<'_>, Ov6Error>
+where
+    F: AsFd,
+{
+    let fd = fd.as_fd();
+    let prev = mode(&fd)?;
+    set_mode(&fd, TerminalMode::Raw)?;
+    Ok(RawModeGuard { fd, prev })
+}
+
+/// Restores the previous terminal mode when dropped.
+///
+/// Created by [`enable_raw_mode`].
+#[derive(Debug)]
+pub struct RawModeGuard<'fd> {
+    fd: BorrowedFd<'fd>,
+    prev: TerminalMode,
+}
+
+impl Drop for RawModeGuard<'_> {
+    fn drop(&mut self) {
+        let _ = set_mode(&self.fd, self.prev);
+    }
+}
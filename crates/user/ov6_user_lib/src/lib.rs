@@ -16,6 +16,7 @@ pub mod backtrace;
 pub mod env;
 pub mod error;
 pub mod fs;
+pub mod hash;
 pub mod io;
 pub mod net;
 pub mod os;
@@ -1,2 +1,4 @@
 pub mod fd;
 pub mod ov6;
+
+pub use self::ov6::net::socketpair;
@@ -0,0 +1,140 @@
+//! Message queues, semaphores, and event counters.
+
+pub use ov6_syscall::{EventFdFlags, MSG_SIZE_MAX, MsgQueueFlags};
+use ov6_types::fs::RawFd;
+
+use crate::{
+    error::Ov6Error,
+    os::{
+        fd::{AsFd, AsRawFd, BorrowedFd, IntoRawFd as _, OwnedFd},
+        ov6::syscall,
+    },
+};
+
+/// A kernel message queue shared by the processes using the same key.
+///
+/// Messages are received in the order of their priorities, highest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MsgQueue {
+    id: usize,
+}
+
+impl MsgQueue {
+    /// Opens the message queue of `key`.
+    ///
+    /// See [`MsgQueueFlags`] for the effects of `flags`.
+    pub fn get(key: u64, flags: MsgQueueFlags) -> Result<Self, Ov6Error> {
+        let id = syscall::msg_get(key, flags)?;
+        Ok(Self { id })
+    }
+
+    /// Returns the identifier of the queue.
+    #[must_use]
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Sends `data` with `priority`, waiting while the queue is full.
+    pub fn send(&self, data: &[u8], priority: usize) -> Result<(), Ov6Error> {
+        syscall::msg_send(self.id, data, priority)
+    }
+
+    /// Receives the message of the highest priority into `buf`, waiting while
+    /// the queue is empty.
+    ///
+    /// Returns the length and the priority of the message.
+    pub fn recv(&self, buf: &mut [u8]) -> Result<(usize, usize), Ov6Error> {
+        syscall::msg_recv(self.id, buf)
+    }
+
+    /// Removes the queue, waking the processes waiting on it.
+    pub fn remove(self) -> Result<(), Ov6Error> {
+        syscall::msg_remove(self.id)
+    }
+}
+
+/// A counting semaphore shared by the processes using the same key.
+///
+/// The semaphore is a file descriptor, so it is inherited by child
+/// processes.
+#[derive(Debug)]
+pub struct Semaphore(OwnedFd);
+
+impl Semaphore {
+    /// Opens the semaphore of `key`, creating it with `value` if it does not
+    /// exist.
+    pub fn open(key: u64, value: usize) -> Result<Self, Ov6Error> {
+        let fd = syscall::sem_open(key, value)?;
+        Ok(Self(fd))
+    }
+
+    /// Waits until the value is positive, and decrements it.
+    pub fn wait(&self) -> Result<(), Ov6Error> {
+        syscall::sem_wait(self.0.as_raw_fd())
+    }
+
+    /// Increments the value, waking a process waiting on the semaphore.
+    pub fn post(&self) -> Result<(), Ov6Error> {
+        syscall::sem_post(self.0.as_raw_fd())
+    }
+
+    /// Closes the semaphore, reporting the error that dropping it ignores.
+    pub fn close(self) -> Result<(), Ov6Error> {
+        let fd = self.0.into_raw_fd();
+        unsafe { syscall::sem_close(fd) }
+    }
+}
+
+impl AsFd for Semaphore {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.0.as_fd()
+    }
+}
+
+impl AsRawFd for Semaphore {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
+
+/// An event counter, used to wake a process waiting on it.
+///
+/// The counter is a file descriptor, so it is inherited by child processes.
+#[derive(Debug)]
+pub struct EventFd(OwnedFd);
+
+impl EventFd {
+    /// Creates an event counter of `initial`.
+    pub fn new(initial: u64, flags: EventFdFlags) -> Result<Self, Ov6Error> {
+        let fd = syscall::event_fd(initial, flags)?;
+        Ok(Self(fd))
+    }
+
+    /// Adds `value` to the counter, waking the processes waiting on it.
+    pub fn notify(&self, value: u64) -> Result<(), Ov6Error> {
+        syscall::write(self.0.as_raw_fd(), &value.to_ne_bytes())?;
+        Ok(())
+    }
+
+    /// Waits until the counter is non-zero, and returns it.
+    ///
+    /// With [`EventFdFlags::SEMAPHORE`], returns 1 and decrements the
+    /// counter. Otherwise, resets the counter to 0.
+    pub fn wait(&self) -> Result<u64, Ov6Error> {
+        let mut buf = [0; size_of::<u64>()];
+        syscall::read(self.0.as_raw_fd(), &mut buf)?;
+        Ok(u64::from_ne_bytes(buf))
+    }
+}
+
+impl AsFd for EventFd {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.0.as_fd()
+    }
+}
+
+impl AsRawFd for EventFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
@@ -1 +1,3 @@
+pub mod ipc;
+pub mod net;
 pub mod syscall;
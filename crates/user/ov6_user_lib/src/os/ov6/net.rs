@@ -0,0 +1,140 @@
+//! Local stream sockets.
+
+use ov6_types::{fs::RawFd, path::Path};
+
+use crate::{
+    error::Ov6Error,
+    io::{IoSlice, IoSliceMut, Read, Write},
+    os::{
+        fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, IntoRawFd, OwnedFd},
+        ov6::syscall,
+    },
+};
+
+/// Creates a pair of local stream sockets connected to each other.
+///
+/// Unlike a pipe, both sockets can be read from and written to. Bytes
+/// written to one of them are read from the other.
+pub fn socketpair() -> Result<(LocalStream, LocalStream), Ov6Error> {
+    let (a, b) = syscall::socket_pair()?;
+    Ok((LocalStream(a), LocalStream(b)))
+}
+
+/// Number of connections a [`LocalListener`] keeps pending.
+const LISTEN_BACKLOG: usize = 4;
+
+/// A local stream socket connected to a peer.
+#[derive(Debug)]
+pub struct LocalStream(OwnedFd);
+
+/// A local socket bound to a path, accepting connections.
+#[derive(Debug)]
+pub struct LocalListener(OwnedFd);
+
+impl LocalListener {
+    /// Creates a socket bound to `path` and starts accepting connections.
+    ///
+    /// `path` must not exist. It is left in the file system after the
+    /// listener is dropped, and must be removed before it is bound again.
+    pub fn bind<P>(path: P) -> Result<Self, Ov6Error>
+    where
+        P: AsRef<Path>,
+    {
+        let fd = syscall::local_bind(path.as_ref())?;
+        syscall::local_listen(fd.as_raw_fd(), LISTEN_BACKLOG)?;
+        Ok(Self(fd))
+    }
+
+    /// Waits for a connection and returns the socket connected to the peer.
+    pub fn accept(&self) -> Result<LocalStream, Ov6Error> {
+        let fd = syscall::local_accept(self.0.as_raw_fd())?;
+        Ok(LocalStream(fd))
+    }
+}
+
+impl AsFd for LocalListener {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.0.as_fd()
+    }
+}
+
+impl AsRawFd for LocalListener {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
+
+impl LocalStream {
+    /// Connects to the listener bound to `path`.
+    pub fn connect<P>(path: P) -> Result<Self, Ov6Error>
+    where
+        P: AsRef<Path>,
+    {
+        let fd = syscall::local_connect(path.as_ref())?;
+        Ok(Self(fd))
+    }
+
+    pub fn try_clone(&self) -> Result<Self, Ov6Error> {
+        Ok(Self(self.0.try_clone()?))
+    }
+}
+
+impl AsFd for LocalStream {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.0.as_fd()
+    }
+}
+
+impl AsRawFd for LocalStream {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
+
+impl FromRawFd for LocalStream {
+    unsafe fn from_raw_fd(fd: RawFd) -> Self {
+        unsafe { Self(OwnedFd::from_raw_fd(fd)) }
+    }
+}
+
+impl IntoRawFd for LocalStream {
+    fn into_raw_fd(self) -> RawFd {
+        self.0.into_raw_fd()
+    }
+}
+
+impl From<OwnedFd> for LocalStream {
+    fn from(fd: OwnedFd) -> Self {
+        Self(fd)
+    }
+}
+
+impl From<LocalStream> for OwnedFd {
+    fn from(stream: LocalStream) -> Self {
+        stream.0
+    }
+}
+
+impl Read for LocalStream {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Ov6Error> {
+        syscall::read(self.0.as_raw_fd(), buf)
+    }
+
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>]) -> Result<usize, Ov6Error> {
+        syscall::readv(self.0.as_raw_fd(), bufs)
+    }
+}
+
+impl Write for LocalStream {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Ov6Error> {
+        syscall::write(self.0.as_raw_fd(), buf)
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> Result<usize, Ov6Error> {
+        syscall::writev(self.0.as_raw_fd(), bufs)
+    }
+
+    fn flush(&mut self) -> Result<(), Ov6Error> {
+        Ok(())
+    }
+}
@@ -232,3 +232,67 @@ syscall!(Abort);
 syscall!(Trace);
 syscall!(DumpKernelPageTable);
 syscall!(DumpUserPageTable);
+syscall!(Chroot);
+syscall!(DropCaps);
+syscall!(SetSyscallFilter);
+syscall!(ReadAuditLog);
+syscall!(Ioctl);
+syscall!(RequestShutdown);
+syscall!(WaitShutdownRequest);
+syscall!(Sync);
+syscall!(Fstatfs);
+syscall!(Rmdir);
+syscall!(Readv);
+syscall!(Writev);
+syscall!(SendFile);
+syscall!(Pread);
+syscall!(Pwrite);
+syscall!(ResizeFs);
+syscall!(InjectAllocFault);
+syscall!(Fcntl);
+syscall!(Remount);
+syscall!(Splice);
+syscall!(SocketPair);
+syscall!(LocalBind);
+syscall!(LocalListen);
+syscall!(LocalAccept);
+syscall!(LocalConnect);
+syscall!(MsgGet);
+syscall!(MsgSend);
+syscall!(MsgRecv);
+syscall!(MsgRemove);
+syscall!(SemOpen);
+syscall!(SemWait);
+syscall!(SemPost);
+syscall!(SemClose);
+syscall!(EventFd);
+syscall!(SetProcessGroup);
+syscall!(GetProcessGroup);
+syscall!(SetInterruptAction);
+syscall!(SetInterruptHandler);
+syscall!(SetIoLimit);
+syscall!(GetIoStats);
+syscall!(GetHostname);
+syscall!(SetHostname);
+syscall!(Flink);
+syscall!(Dup2);
+syscall!(Pipe2);
+syscall!(Dup3);
+syscall!(SetNice);
+syscall!(SetAffinity);
+syscall!(GetAffinity);
+syscall!(Getcwd);
+syscall!(Openat);
+syscall!(Fstatat);
+syscall!(Unlinkat);
+syscall!(Mkdirat);
+syscall!(Fchdir);
+syscall!(Umask);
+syscall!(Access);
+syscall!(Faccessat);
+syscall!(Ptrace);
+syscall!(PtraceGetRegs);
+syscall!(PtraceSetRegs);
+syscall!(GetKernelSymbols);
+syscall!(Getcpu);
+syscall!(Mount);
@@ -1,12 +1,22 @@
 use core::{
     convert::Infallible,
     net::{Ipv4Addr, SocketAddrV4},
+    num::NonZero,
     ptr,
     time::Duration,
 };
 
 use dataview::PodMethods as _;
-pub use ov6_syscall::{MemoryInfo, OpenFlags, Stat, StatType, SyscallCode, SystemInfo};
+pub use ov6_syscall::{
+    AT_FDCWD, AccessHint, AccessMode, AtFlags, AuditRecord, Capabilities, CpuSet, DiskInfo,
+    EventFdFlags, FcntlRequest, FdFlags, FileInfo, HOST_NAME_MAX, HeapClassInfo, HeapInfo,
+    InterruptAction, InterruptInfo, IoStats, IoctlRequest, LOAD_FSHIFT, LoadInfo, MAX_CPUS,
+    MSG_SIZE_MAX, MemoryInfo, MountFlags, MsgQueueFlags, NICE_MAX, NICE_MIN, NIRQ, NameCacheInfo,
+    NetInfo, OpenFlags, PageCacheInfo, PtraceRegs, PtraceRequest, PtraceStop, STAT_VERSION,
+    SYSTEM_INFO_VERSION, ShutdownRequest, Stat, StatFs, StatType, SyscallCode, SyscallFilterAction,
+    SyscallFilterMask, SystemInfo, TerminalMode, UMASK_BITS, UMASK_DEFAULT, UserLayout, WindowSize,
+    error::SyscallError,
+};
 use ov6_syscall::{
     USYSCALL_ADDR, USyscallData, UserMutRef, UserMutSlice, UserRef, UserSlice, WaitTarget, syscall,
 };
@@ -15,8 +25,9 @@ use ov6_types::{fs::RawFd, path::Path, process::ProcId};
 use self::ffi::SyscallExt as _;
 use crate::{
     error::Ov6Error,
+    io::{IoSlice, IoSliceMut},
     os::fd::{FromRawFd as _, OwnedFd},
-    process::ExitStatus,
+    process::{self, ExitStatus},
 };
 
 pub mod ffi;
@@ -45,6 +56,134 @@ pub fn pipe() -> Result<(OwnedFd, OwnedFd), Ov6Error> {
     }))
 }
 
+/// Creates a pipe with `flags`.
+///
+/// Only [`OpenFlags::CLOEXEC`] is accepted, which is set on both file
+/// descriptors before any other thread can `exec`.
+pub fn pipe2(flags: OpenFlags) -> Result<(OwnedFd, OwnedFd), Ov6Error> {
+    let mut pipefd = [const { RawFd::new(0) }; 2];
+    syscall::Pipe2::call((UserMutRef::new(&mut pipefd), flags))?;
+    Ok((unsafe { OwnedFd::from_raw_fd(pipefd[0]) }, unsafe {
+        OwnedFd::from_raw_fd(pipefd[1])
+    }))
+}
+
+/// Creates a pair of connected bidirectional sockets.
+pub fn socket_pair() -> Result<(OwnedFd, OwnedFd), Ov6Error> {
+    let mut fds = [const { RawFd::new(0) }; 2];
+    syscall::SocketPair::call((UserMutRef::new(&mut fds),))?;
+    Ok((unsafe { OwnedFd::from_raw_fd(fds[0]) }, unsafe {
+        OwnedFd::from_raw_fd(fds[1])
+    }))
+}
+
+/// Creates a local socket bound to `path`.
+///
+/// An inode of the socket is created at `path`, which must not exist.
+pub fn local_bind(path: &Path) -> Result<OwnedFd, Ov6Error> {
+    let fd = syscall::LocalBind::call((UserSlice::new(path.as_os_str().as_bytes()),))?;
+    unsafe { Ok(OwnedFd::from_raw_fd(fd)) }
+}
+
+/// Starts accepting connections on the bound socket `fd`, keeping up to
+/// `backlog` of them pending.
+pub fn local_listen(fd: RawFd, backlog: usize) -> Result<(), Ov6Error> {
+    syscall::LocalListen::call((fd, backlog))?;
+    Ok(())
+}
+
+/// Waits for a connection to the bound socket `fd`.
+pub fn local_accept(fd: RawFd) -> Result<OwnedFd, Ov6Error> {
+    let fd = syscall::LocalAccept::call((fd,))?;
+    unsafe { Ok(OwnedFd::from_raw_fd(fd)) }
+}
+
+/// Connects to the local socket bound to `path`.
+pub fn local_connect(path: &Path) -> Result<OwnedFd, Ov6Error> {
+    let fd = syscall::LocalConnect::call((UserSlice::new(path.as_os_str().as_bytes()),))?;
+    unsafe { Ok(OwnedFd::from_raw_fd(fd)) }
+}
+
+/// Returns the identifier of the message queue of `key`.
+///
+/// With [`MsgQueueFlags::CREATE`], a queue is created if no queue has the
+/// key.
+pub fn msg_get(key: u64, flags: MsgQueueFlags) -> Result<usize, Ov6Error> {
+    let id = syscall::MsgGet::call((key, flags))?;
+    Ok(id)
+}
+
+/// Sends `data` to the message queue `id` with `priority`.
+///
+/// `data` must be at most [`MSG_SIZE_MAX`] bytes.
+pub fn msg_send(id: usize, data: &[u8], priority: usize) -> Result<(), Ov6Error> {
+    syscall::MsgSend::call((id, UserSlice::new(data), priority))?;
+    Ok(())
+}
+
+/// Receives the message of the highest priority from the message queue `id`.
+///
+/// Returns the length and the priority of the message.
+pub fn msg_recv(id: usize, buf: &mut [u8]) -> Result<(usize, usize), Ov6Error> {
+    let mut priority = 0;
+    let len = syscall::MsgRecv::call((id, UserMutSlice::new(buf), UserMutRef::new(&mut priority)))?;
+    Ok((len, priority))
+}
+
+/// Removes the message queue `id`.
+///
+/// The processes waiting on the queue fail with
+/// [`Ov6Error::IdentifierRemoved`].
+pub fn msg_remove(id: usize) -> Result<(), Ov6Error> {
+    syscall::MsgRemove::call((id,))?;
+    Ok(())
+}
+
+/// Opens the semaphore of `key`, creating it with `value` if it does not
+/// exist.
+///
+/// The semaphore is freed when all of the file descriptors referring to it
+/// are closed.
+pub fn sem_open(key: u64, value: usize) -> Result<OwnedFd, Ov6Error> {
+    let fd = syscall::SemOpen::call((key, value))?;
+    unsafe { Ok(OwnedFd::from_raw_fd(fd)) }
+}
+
+/// Waits until the semaphore `fd` is positive, and decrements it.
+pub fn sem_wait(fd: RawFd) -> Result<(), Ov6Error> {
+    syscall::SemWait::call((fd,))?;
+    Ok(())
+}
+
+/// Increments the semaphore `fd`, waking a process waiting on it.
+pub fn sem_post(fd: RawFd) -> Result<(), Ov6Error> {
+    syscall::SemPost::call((fd,))?;
+    Ok(())
+}
+
+/// Closes the semaphore `fd`.
+///
+/// Unlike [`close()`], fails if `fd` is not a semaphore.
+///
+/// # Safety
+///
+/// This invalidates `OwnedFd` and `BorrowedFd` instances that refer to the
+/// closed file descriptor.
+pub unsafe fn sem_close(fd: RawFd) -> Result<(), Ov6Error> {
+    syscall::SemClose::call((fd,))?;
+    Ok(())
+}
+
+/// Creates an event counter of `initial`.
+///
+/// Writing an 8-byte value to the returned file descriptor adds it to the
+/// counter. Reading 8 bytes from it waits until the counter is non-zero, and
+/// returns the counter.
+pub fn event_fd(initial: u64, flags: EventFdFlags) -> Result<OwnedFd, Ov6Error> {
+    let fd = syscall::EventFd::call((initial, flags))?;
+    unsafe { Ok(OwnedFd::from_raw_fd(fd)) }
+}
+
 pub fn write(fd: RawFd, buf: &[u8]) -> Result<usize, Ov6Error> {
     let nwritten = syscall::Write::call((fd, UserSlice::new(buf)))?;
     Ok(nwritten)
@@ -55,6 +194,48 @@ pub fn read(fd: RawFd, buf: &mut [u8]) -> Result<usize, Ov6Error> {
     Ok(nread)
 }
 
+pub fn pwrite(fd: RawFd, buf: &[u8], offset: usize) -> Result<usize, Ov6Error> {
+    let nwritten = syscall::Pwrite::call((fd, UserSlice::new(buf), offset))?;
+    Ok(nwritten)
+}
+
+pub fn pread(fd: RawFd, buf: &mut [u8], offset: usize) -> Result<usize, Ov6Error> {
+    let nread = syscall::Pread::call((fd, UserMutSlice::new(buf), offset))?;
+    Ok(nread)
+}
+
+pub fn writev(fd: RawFd, bufs: &[IoSlice<'_>]) -> Result<usize, Ov6Error> {
+    // `IoSlice` has the same layout as `UserSlice<u8>`.
+    let iov = unsafe { UserSlice::from_raw_parts(bufs.as_ptr().addr(), bufs.len()) };
+    let nwritten = syscall::Writev::call((fd, iov))?;
+    Ok(nwritten)
+}
+
+pub fn readv(fd: RawFd, bufs: &mut [IoSliceMut<'_>]) -> Result<usize, Ov6Error> {
+    // `IoSliceMut` has the same layout as `UserMutSlice<u8>`.
+    let iov = unsafe { UserSlice::from_raw_parts(bufs.as_ptr().addr(), bufs.len()) };
+    let nread = syscall::Readv::call((fd, iov))?;
+    Ok(nread)
+}
+
+/// Copies up to `count` bytes from `in_fd` to `out_fd` inside the kernel.
+///
+/// Returns the number of bytes copied, which is 0 at the end of `in_fd`.
+pub fn send_file(out_fd: RawFd, in_fd: RawFd, count: usize) -> Result<usize, Ov6Error> {
+    let ncopied = syscall::SendFile::call((out_fd, in_fd, count))?;
+    Ok(ncopied)
+}
+
+/// Moves up to `count` bytes from `in_fd` to `out_fd` without copying them
+/// through user space.
+///
+/// Either `in_fd` or `out_fd` must be a pipe. Returns the number of bytes
+/// moved, which is 0 at the end of `in_fd`.
+pub fn splice(in_fd: RawFd, out_fd: RawFd, count: usize) -> Result<usize, Ov6Error> {
+    let nmoved = syscall::Splice::call((in_fd, out_fd, count))?;
+    Ok(nmoved)
+}
+
 /// # Safety
 ///
 /// This invalidates `OwnedFd` and `BorrowedFd` instances that refer to the
@@ -82,6 +263,14 @@ pub fn open(path: &Path, flags: OpenFlags) -> Result<OwnedFd, Ov6Error> {
     unsafe { Ok(OwnedFd::from_raw_fd(fd)) }
 }
 
+/// Opens `path` relative to the directory `dir_fd`.
+///
+/// If `dir_fd` is [`AT_FDCWD`], `path` is relative to the current directory.
+pub fn openat(dir_fd: RawFd, path: &Path, flags: OpenFlags) -> Result<OwnedFd, Ov6Error> {
+    let fd = syscall::Openat::call((dir_fd, UserSlice::new(path.as_os_str().as_bytes()), flags))?;
+    unsafe { Ok(OwnedFd::from_raw_fd(fd)) }
+}
+
 pub fn mknod(path: &Path, major: u32, minor: u16) -> Result<(), Ov6Error> {
     syscall::Mknod::call((UserSlice::new(path.as_os_str().as_bytes()), major, minor))?;
     Ok(())
@@ -92,12 +281,168 @@ pub fn unlink(path: &Path) -> Result<(), Ov6Error> {
     Ok(())
 }
 
+pub fn rmdir(path: &Path) -> Result<(), Ov6Error> {
+    syscall::Rmdir::call((UserSlice::new(path.as_os_str().as_bytes()),))?;
+    Ok(())
+}
+
+/// Removes `path` relative to the directory `dir_fd`.
+///
+/// Removes a directory if `flags` contains [`AtFlags::REMOVE_DIR`], and a
+/// file otherwise.
+pub fn unlinkat(dir_fd: RawFd, path: &Path, flags: AtFlags) -> Result<(), Ov6Error> {
+    syscall::Unlinkat::call((dir_fd, UserSlice::new(path.as_os_str().as_bytes()), flags))?;
+    Ok(())
+}
+
 pub fn fstat(fd: RawFd) -> Result<Stat, Ov6Error> {
     let mut stat = Stat::zeroed();
     syscall::Fstat::call((fd, UserMutRef::new(&mut stat)))?;
+    check_stat_version(&stat)?;
     Ok(stat)
 }
 
+/// Checks that the calling process may access `path` with `mode`, without
+/// opening it.
+///
+/// An empty `mode` only checks that `path` exists. Fails with
+/// [`Ov6Error::PermissionDenied`] if the access is not permitted.
+pub fn access(path: &Path, mode: AccessMode) -> Result<(), Ov6Error> {
+    syscall::Access::call((UserSlice::new(path.as_os_str().as_bytes()), mode))?;
+    Ok(())
+}
+
+/// Checks that the calling process may access `path` relative to the
+/// directory `dir_fd` with `mode`, without opening it.
+///
+/// If `dir_fd` is [`AT_FDCWD`], `path` is relative to the current directory.
+pub fn faccessat(
+    dir_fd: RawFd,
+    path: &Path,
+    mode: AccessMode,
+    flags: AtFlags,
+) -> Result<(), Ov6Error> {
+    syscall::Faccessat::call((
+        dir_fd,
+        UserSlice::new(path.as_os_str().as_bytes()),
+        mode,
+        flags,
+    ))?;
+    Ok(())
+}
+
+/// Returns the metadata of `path` relative to the directory `dir_fd`.
+pub fn fstatat(dir_fd: RawFd, path: &Path) -> Result<Stat, Ov6Error> {
+    let mut stat = Stat::zeroed();
+    syscall::Fstatat::call((
+        dir_fd,
+        UserSlice::new(path.as_os_str().as_bytes()),
+        UserMutRef::new(&mut stat),
+    ))?;
+    check_stat_version(&stat)?;
+    Ok(stat)
+}
+
+fn check_stat_version(stat: &Stat) -> Result<(), Ov6Error> {
+    if stat.version != STAT_VERSION {
+        return Err(Ov6Error::Unknown);
+    }
+    Ok(())
+}
+
+pub fn fstatfs(fd: RawFd) -> Result<StatFs, Ov6Error> {
+    let mut statfs = StatFs::zeroed();
+    syscall::Fstatfs::call((fd, UserMutRef::new(&mut statfs)))?;
+    Ok(statfs)
+}
+
+pub fn ioctl(fd: RawFd, req: IoctlRequest, arg: usize) -> Result<usize, Ov6Error> {
+    let ret = syscall::Ioctl::call((fd, req, arg))?;
+    Ok(ret)
+}
+
+/// Returns the window size of the terminal referred to by `fd`.
+pub fn window_size(fd: RawFd) -> Result<WindowSize, Ov6Error> {
+    let raw = ioctl(fd, IoctlRequest::GetWindowSize, 0)?;
+    WindowSize::from_raw(raw).ok_or(Ov6Error::Unknown)
+}
+
+/// Sets the window size of the terminal referred to by `fd`.
+pub fn set_window_size(fd: RawFd, size: WindowSize) -> Result<(), Ov6Error> {
+    ioctl(fd, IoctlRequest::SetWindowSize, size.to_raw())?;
+    Ok(())
+}
+
+/// Returns the input mode of the terminal referred to by `fd`.
+pub fn terminal_mode(fd: RawFd) -> Result<TerminalMode, Ov6Error> {
+    let raw = ioctl(fd, IoctlRequest::GetMode, 0)?;
+    TerminalMode::from_repr(raw).ok_or(Ov6Error::Unknown)
+}
+
+/// Sets the input mode of the terminal referred to by `fd`.
+pub fn set_terminal_mode(fd: RawFd, mode: TerminalMode) -> Result<(), Ov6Error> {
+    ioctl(fd, IoctlRequest::SetMode, mode as usize)?;
+    Ok(())
+}
+
+/// Returns the foreground process group of the terminal referred to by `fd`.
+pub fn foreground_group(fd: RawFd) -> Result<Option<ProcId>, Ov6Error> {
+    let raw = ioctl(fd, IoctlRequest::GetForeground, 0)?;
+    let Ok(raw) = u32::try_from(raw) else {
+        return Err(Ov6Error::Unknown);
+    };
+    Ok(NonZero::new(raw).map(ProcId::new))
+}
+
+/// Sets the foreground process group of the terminal referred to by `fd`.
+pub fn set_foreground_group(fd: RawFd, pgid: ProcId) -> Result<(), Ov6Error> {
+    ioctl(fd, IoctlRequest::SetForeground, u32::from(pgid) as usize)?;
+    Ok(())
+}
+
+pub fn fcntl(fd: RawFd, req: FcntlRequest, arg: usize) -> Result<usize, Ov6Error> {
+    let ret = syscall::Fcntl::call((fd, req, arg))?;
+    Ok(ret)
+}
+
+/// Returns the flags of the file descriptor `fd`.
+pub fn fd_flags(fd: RawFd) -> Result<FdFlags, Ov6Error> {
+    let raw = fcntl(fd, FcntlRequest::GetFdFlags, 0)?;
+    FdFlags::from_bits(raw).ok_or(Ov6Error::Unknown)
+}
+
+/// Sets the flags of the file descriptor `fd`.
+pub fn set_fd_flags(fd: RawFd, flags: FdFlags) -> Result<(), Ov6Error> {
+    fcntl(fd, FcntlRequest::SetFdFlags, flags.bits())?;
+    Ok(())
+}
+
+/// Returns the access hint of the file descriptor `fd`.
+pub fn access_hint(fd: RawFd) -> Result<AccessHint, Ov6Error> {
+    let raw = fcntl(fd, FcntlRequest::GetFdAccessHint, 0)?;
+    AccessHint::from_repr(raw).ok_or(Ov6Error::Unknown)
+}
+
+/// Sets the access hint of the file descriptor `fd`.
+pub fn set_access_hint(fd: RawFd, hint: AccessHint) -> Result<(), Ov6Error> {
+    fcntl(fd, FcntlRequest::SetFdAccessHint, hint as usize)?;
+    Ok(())
+}
+
+/// Returns the access hint of the file referred to by `fd`.
+pub fn file_access_hint(fd: RawFd) -> Result<AccessHint, Ov6Error> {
+    let raw = fcntl(fd, FcntlRequest::GetFileAccessHint, 0)?;
+    AccessHint::from_repr(raw).ok_or(Ov6Error::Unknown)
+}
+
+/// Sets the access hint of the file referred to by `fd`.
+///
+/// The hint is shared by all file descriptors of the file, while it is open.
+pub fn set_file_access_hint(fd: RawFd, hint: AccessHint) -> Result<(), Ov6Error> {
+    fcntl(fd, FcntlRequest::SetFileAccessHint, hint as usize)?;
+    Ok(())
+}
+
 pub fn link(old: &Path, new: &Path) -> Result<(), Ov6Error> {
     syscall::Link::call((
         UserSlice::new(old.as_os_str().as_bytes()),
@@ -106,27 +451,86 @@ pub fn link(old: &Path, new: &Path) -> Result<(), Ov6Error> {
     Ok(())
 }
 
+/// Adds an entry `new` referring to the file of `fd`.
+///
+/// Unlike [`link`], the file may have no links, such as one opened with
+/// [`OpenFlags::TMPFILE`].
+pub fn flink(fd: RawFd, new: &Path) -> Result<(), Ov6Error> {
+    syscall::Flink::call((fd, UserSlice::new(new.as_os_str().as_bytes())))?;
+    Ok(())
+}
+
 pub fn mkdir(path: &Path) -> Result<(), Ov6Error> {
     syscall::Mkdir::call((UserSlice::new(path.as_os_str().as_bytes()),))?;
     Ok(())
 }
 
+/// Creates a directory `path` relative to the directory `dir_fd`.
+pub fn mkdirat(dir_fd: RawFd, path: &Path) -> Result<(), Ov6Error> {
+    syscall::Mkdirat::call((dir_fd, UserSlice::new(path.as_os_str().as_bytes())))?;
+    Ok(())
+}
+
 pub fn chdir(path: &Path) -> Result<(), Ov6Error> {
     syscall::Chdir::call((UserSlice::new(path.as_os_str().as_bytes()),))?;
     Ok(())
 }
 
+/// Changes the current directory to the directory `fd`.
+pub fn fchdir(fd: RawFd) -> Result<(), Ov6Error> {
+    syscall::Fchdir::call((fd,))?;
+    Ok(())
+}
+
+/// Copies the path of the current directory to `buf` and returns its length.
+///
+/// Nothing is copied if `buf` is too short; the returned length is that of
+/// the whole path.
+pub fn getcwd(buf: &mut [u8]) -> Result<usize, Ov6Error> {
+    let len = syscall::Getcwd::call((UserMutSlice::new(buf),))?;
+    Ok(len)
+}
+
+pub fn chroot(path: &Path) -> Result<(), Ov6Error> {
+    syscall::Chroot::call((UserSlice::new(path.as_os_str().as_bytes()),))?;
+    Ok(())
+}
+
 pub fn dup(fd: RawFd) -> Result<OwnedFd, Ov6Error> {
     let fd = syscall::Dup::call((fd,))?;
     Ok(unsafe { OwnedFd::from_raw_fd(fd) })
 }
 
+/// Duplicates `fd` to `new_fd`, closing the file previously open at `new_fd`.
+///
+/// If `fd` and `new_fd` are equal, only checks that `fd` is open.
+pub fn dup2(fd: RawFd, new_fd: RawFd) -> Result<OwnedFd, Ov6Error> {
+    let fd = syscall::Dup2::call((fd, new_fd))?;
+    Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+}
+
+/// Duplicates `fd` to `new_fd` like [`dup2`], setting `flags` on `new_fd`.
+///
+/// Only [`OpenFlags::CLOEXEC`] is accepted. Unlike [`dup2`], `fd` and
+/// `new_fd` must differ.
+pub fn dup3(fd: RawFd, new_fd: RawFd, flags: OpenFlags) -> Result<OwnedFd, Ov6Error> {
+    let fd = syscall::Dup3::call((fd, new_fd, flags))?;
+    Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+}
+
 #[must_use]
 pub fn ugetpid() -> ProcId {
     let usyscall_data = ptr::with_exposed_provenance::<USyscallData>(USYSCALL_ADDR);
     unsafe { (*usyscall_data).pid }
 }
 
+/// Returns the layout of the address space of the current process.
+#[must_use]
+pub fn user_layout() -> UserLayout {
+    let usyscall_data = ptr::with_exposed_provenance::<USyscallData>(USYSCALL_ADDR);
+    unsafe { (*usyscall_data).layout }
+}
+
 /// # Safety
 ///
 /// This function is unsafe because it may invalidate the region of memory that
@@ -151,6 +555,201 @@ pub fn alarm_clear() -> Result<(), Ov6Error> {
     Ok(())
 }
 
+/// Moves the process `pid` to the process group `pgid`.
+///
+/// `pid` must be the calling process or one of its children.
+pub fn set_process_group(pid: ProcId, pgid: ProcId) -> Result<(), Ov6Error> {
+    syscall::SetProcessGroup::call((pid, pgid))?;
+    Ok(())
+}
+
+/// Returns the process group of the process `pid`.
+pub fn process_group(pid: ProcId) -> Result<ProcId, Ov6Error> {
+    let pgid = syscall::GetProcessGroup::call((pid,))?;
+    Ok(pgid)
+}
+
+/// Sets the action taken when an interrupt arrives from the terminal.
+pub fn set_interrupt_action(action: InterruptAction) -> Result<(), Ov6Error> {
+    syscall::SetInterruptAction::call((action,))?;
+    Ok(())
+}
+
+/// Calls `handler` when an interrupt arrives from the terminal.
+///
+/// The handler must return by calling [`signal_return`].
+pub fn set_interrupt_handler(handler: extern "C" fn()) -> Result<(), Ov6Error> {
+    syscall::SetInterruptHandler::call((UserRef::from_fn(handler),))?;
+    Ok(())
+}
+
+/// Limits the disk I/O of the calling process to `limit` blocks per second.
+///
+/// File reads and writes wait until the limit allows the blocks they
+/// request. The limit is removed if `limit` is 0. It is inherited by child processes.
+pub fn set_io_limit(limit: u64) -> Result<(), Ov6Error> {
+    syscall::SetIoLimit::call((limit,))?;
+    Ok(())
+}
+
+/// Sets the nice value of the calling process, from [`NICE_MIN`] (highest
+/// priority) to [`NICE_MAX`] (lowest priority).
+///
+/// Processes with a lower priority are killed first when the system runs
+/// out of memory. Raising the priority requires
+/// [`Capabilities::SET_PRIORITY`]. The value is inherited by child
+/// processes.
+pub fn set_nice(nice: isize) -> Result<(), Ov6Error> {
+    syscall::SetNice::call((nice,))?;
+    Ok(())
+}
+
+/// Sets the file mode creation mask of the calling process and returns the
+/// previous mask.
+///
+/// Bits outside [`UMASK_BITS`] are ignored. The mask is inherited by child
+/// processes and kept across [`exec`]. The first process starts with
+/// [`UMASK_DEFAULT`].
+pub fn umask(mask: u16) -> Result<u16, Ov6Error> {
+    let old = syscall::Umask::call((mask,))?;
+    Ok(old)
+}
+
+/// Restricts the process `pid` to the CPUs in `affinity`.
+///
+/// CPUs that are not online are ignored; fails with
+/// [`Ov6Error::InvalidInput`] if none of them is. The set is inherited by
+/// child processes.
+pub fn set_affinity(pid: ProcId, affinity: CpuSet) -> Result<(), Ov6Error> {
+    syscall::SetAffinity::call((pid, affinity))?;
+    Ok(())
+}
+
+/// Returns the CPUs the process `pid` is allowed to run on.
+pub fn affinity(pid: ProcId) -> Result<CpuSet, Ov6Error> {
+    let affinity = syscall::GetAffinity::call((pid,))?;
+    Ok(affinity)
+}
+
+/// Returns the ID of the CPU the calling process is running on.
+///
+/// The process may be moved to another CPU allowed by its affinity as soon
+/// as the call returns.
+#[must_use]
+pub fn getcpu() -> usize {
+    syscall::Getcpu::call(())
+}
+
+/// Returns the disk I/O statistics of the process `pid`.
+pub fn io_stats(pid: ProcId) -> Result<IoStats, Ov6Error> {
+    let mut stats = IoStats::zeroed();
+    syscall::GetIoStats::call((pid, UserMutRef::new(&mut stats)))?;
+    Ok(stats)
+}
+
+/// Makes the caller traced by its parent.
+///
+/// The caller runs until it stops, typically at the next `exec`.
+pub fn ptrace_traceme() -> Result<(), Ov6Error> {
+    let pid = process::id();
+    syscall::Ptrace::call((PtraceRequest::TraceMe, pid, 0, 0))?;
+    Ok(())
+}
+
+/// Starts tracing the child `pid`.
+///
+/// The child stops with [`PtraceStop::Attach`] the next time it returns to
+/// user space.
+pub fn ptrace_attach(pid: ProcId) -> Result<(), Ov6Error> {
+    syscall::Ptrace::call((PtraceRequest::Attach, pid, 0, 0))?;
+    Ok(())
+}
+
+/// Stops tracing the stopped child `pid`, and resumes it.
+pub fn ptrace_detach(pid: ProcId) -> Result<(), Ov6Error> {
+    syscall::Ptrace::call((PtraceRequest::Detach, pid, 0, 0))?;
+    Ok(())
+}
+
+/// Resumes the stopped child `pid`.
+pub fn ptrace_cont(pid: ProcId) -> Result<(), Ov6Error> {
+    syscall::Ptrace::call((PtraceRequest::Continue, pid, 0, 0))?;
+    Ok(())
+}
+
+/// Resumes the stopped child `pid` until the next system call entry or exit.
+pub fn ptrace_syscall(pid: ProcId) -> Result<(), Ov6Error> {
+    syscall::Ptrace::call((PtraceRequest::Syscall, pid, 0, 0))?;
+    Ok(())
+}
+
+/// Resumes the stopped child `pid` for a single instruction.
+pub fn ptrace_step(pid: ProcId) -> Result<(), Ov6Error> {
+    syscall::Ptrace::call((PtraceRequest::Step, pid, 0, 0))?;
+    Ok(())
+}
+
+/// Waits for the traced child `pid` to stop or exit.
+///
+/// An exited child must still be waited for with [`wait()`].
+pub fn ptrace_wait(pid: ProcId) -> Result<PtraceStop, Ov6Error> {
+    let stop = syscall::Ptrace::call((PtraceRequest::Wait, pid, 0, 0))?;
+    PtraceStop::from_repr(stop).ok_or(Ov6Error::Unknown)
+}
+
+/// Reads the word at `addr` in the stopped child `pid`.
+pub fn ptrace_peek(pid: ProcId, addr: usize) -> Result<usize, Ov6Error> {
+    let word = syscall::Ptrace::call((PtraceRequest::PeekData, pid, addr, 0))?;
+    Ok(word)
+}
+
+/// Writes `data` to the word at `addr` in the stopped child `pid`.
+///
+/// Read-only pages, such as the program text, are also written.
+pub fn ptrace_poke(pid: ProcId, addr: usize, data: usize) -> Result<(), Ov6Error> {
+    syscall::Ptrace::call((PtraceRequest::PokeData, pid, addr, data))?;
+    Ok(())
+}
+
+/// Returns the registers of the stopped child `pid`.
+pub fn ptrace_get_regs(pid: ProcId) -> Result<PtraceRegs, Ov6Error> {
+    let mut regs = PtraceRegs::zeroed();
+    syscall::PtraceGetRegs::call((pid, UserMutRef::new(&mut regs)))?;
+    Ok(regs)
+}
+
+/// Sets the registers of the stopped child `pid`.
+pub fn ptrace_set_regs(pid: ProcId, regs: &PtraceRegs) -> Result<(), Ov6Error> {
+    syscall::PtraceSetRegs::call((pid, UserRef::new(regs)))?;
+    Ok(())
+}
+
+/// Copies the host name to `buf` and returns its length.
+///
+/// The name is truncated if `buf` is too short; the returned length is that of
+/// the whole name.
+pub fn get_hostname(buf: &mut [u8]) -> Result<usize, Ov6Error> {
+    let len = syscall::GetHostname::call((UserMutSlice::new(buf),))?;
+    Ok(len)
+}
+
+/// Changes the host name.
+pub fn set_hostname(name: &str) -> Result<(), Ov6Error> {
+    syscall::SetHostname::call((UserSlice::new(name.as_bytes()),))?;
+    Ok(())
+}
+
+/// Copies the kernel symbol table from `offset` to `buf`, and returns the
+/// number of bytes copied.
+///
+/// The table is text with one `<hex address> <name>` line per function,
+/// sorted by address. It is empty if the kernel was built without it.
+/// Reading it requires the [`Capabilities::KERNEL_SYMBOLS`] capability.
+pub fn get_kernel_symbols(buf: &mut [u8], offset: usize) -> Result<usize, Ov6Error> {
+    let len = syscall::GetKernelSymbols::call((UserMutSlice::new(buf), offset))?;
+    Ok(len)
+}
+
 pub fn signal_return() -> Result<Infallible, Ov6Error> {
     let _: Infallible = syscall::SignalReturn::call(())?;
     unreachable!()
@@ -180,6 +779,9 @@ pub fn send(src_port: u16, dst: SocketAddrV4, bytes: &[u8]) -> Result<usize, Ov6
 pub fn get_system_info() -> Result<SystemInfo, Ov6Error> {
     let mut info = SystemInfo::zeroed();
     syscall::GetSystemInfo::call((UserMutRef::new(&mut info),))?;
+    if info.version != SYSTEM_INFO_VERSION {
+        return Err(Ov6Error::Unknown);
+    }
     Ok(info)
 }
 
@@ -193,6 +795,73 @@ pub fn halt(code: u16) -> Result<Infallible, Ov6Error> {
     unreachable!()
 }
 
+/// Asks init to shut down the system.
+///
+/// Init stops the other processes and commits the file system before halting
+/// or rebooting.
+pub fn request_shutdown(req: ShutdownRequest) -> Result<(), Ov6Error> {
+    syscall::RequestShutdown::call((req,))?;
+    Ok(())
+}
+
+/// Waits until a shutdown is requested.
+pub fn wait_shutdown_request() -> Result<ShutdownRequest, Ov6Error> {
+    let req = syscall::WaitShutdownRequest::call(())?;
+    Ok(req)
+}
+
+/// Commits the completed file system operations to the disk.
+pub fn sync() -> Result<(), Ov6Error> {
+    syscall::Sync::call(())?;
+    Ok(())
+}
+
+/// Grows the root file system to `blocks` blocks, or as large as the disk
+/// allows if `blocks` is `None`.
+///
+/// Returns the new size of the file system in blocks.
+pub fn resize_fs(blocks: Option<u32>) -> Result<u32, Ov6Error> {
+    let size = syscall::ResizeFs::call((blocks.map_or(0, u64::from),))?;
+    let Ok(size) = u32::try_from(size) else {
+        return Err(Ov6Error::Unknown);
+    };
+    Ok(size)
+}
+
+/// Changes the flags of the mounted root file system.
+///
+/// Remounting read-only waits for the running file system operations to
+/// complete. Later modifications fail with
+/// [`Ov6Error::ReadOnlyFilesystem`].
+pub fn remount(flags: MountFlags) -> Result<(), Ov6Error> {
+    syscall::Remount::call((flags,))?;
+    Ok(())
+}
+
+/// Mounts a file system of type `fstype` that has no device, such as
+/// `"tmpfs"`, on the directory `target`.
+///
+/// `size` limits the size in bytes of a file system kept in memory, or is
+/// the default of the file system type if `None`.
+pub fn mount(fstype: &str, target: &Path, size: Option<usize>) -> Result<(), Ov6Error> {
+    syscall::Mount::call((
+        UserSlice::new(fstype.as_bytes()),
+        UserSlice::new(target.as_os_str().as_bytes()),
+        size.unwrap_or(0),
+    ))?;
+    Ok(())
+}
+
+/// Makes the kernel allocation after `count` successful ones by the calling
+/// process fail.
+///
+/// The system call that makes the failing allocation returns
+/// [`Ov6Error::OutOfMemory`].
+pub fn inject_alloc_fault(count: usize) -> Result<(), Ov6Error> {
+    syscall::InjectAllocFault::call((count as u64,))?;
+    Ok(())
+}
+
 pub fn abort(code: u16) -> Result<Infallible, Ov6Error> {
     let _: Infallible = syscall::Abort::call((code,))?;
     unreachable!()
@@ -214,6 +883,43 @@ pub fn uptime() -> u64 {
     unimplemented!()
 }
 
+/// Drops `caps` from the current process and returns the remaining
+/// capabilities.
+///
+/// Dropped capabilities can never be regained.
+/// Passing an empty set only queries the current capabilities.
+pub fn drop_caps(caps: Capabilities) -> Result<Capabilities, Ov6Error> {
+    let caps = syscall::DropCaps::call((caps,))?;
+    Ok(caps)
+}
+
+/// Restricts the system calls the calling process may invoke.
+///
+/// Calls not listed in `allowed` are handled according to `action`.
+/// `exit` is always allowed. The filter is inherited by children and kept
+/// across `exec`, and can only be narrowed by subsequent calls.
+pub fn set_syscall_filter(
+    allowed: &[SyscallCode],
+    action: SyscallFilterAction,
+) -> Result<(), Ov6Error> {
+    let mut mask = SyscallFilterMask::default();
+    for &code in allowed {
+        let n = code as usize;
+        let word = mask.get_mut(n / 64).ok_or(Ov6Error::InvalidInput)?;
+        *word |= 1 << (n % 64);
+    }
+    syscall::SetSyscallFilter::call((UserSlice::new(&mask), action))?;
+    Ok(())
+}
+
+/// Reads records from the kernel audit log, oldest first.
+///
+/// Returns the number of records stored in `records`.
+pub fn read_audit_log(records: &mut [AuditRecord]) -> Result<usize, Ov6Error> {
+    let n = syscall::ReadAuditLog::call((UserMutSlice::new(records),))?;
+    Ok(n)
+}
+
 pub fn trace(mask: u64) {
     syscall::Trace::call((mask,));
 }
@@ -1,8 +1,9 @@
+pub use ov6_syscall::PIPE_BUF;
 use ov6_types::fs::RawFd;
 
 use crate::{
     error::Ov6Error,
-    io::{Read, Write},
+    io::{IoSlice, IoSliceMut, Read, Write},
     os::{
         fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, IntoRawFd, OwnedFd},
         ov6::syscall,
@@ -108,6 +109,10 @@ impl Read for PipeReader {
     fn read(&mut self, buf: &mut [u8]) -> Result<usize, Ov6Error> {
         syscall::read(self.0.as_raw_fd(), buf)
     }
+
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>]) -> Result<usize, Ov6Error> {
+        syscall::readv(self.0.as_raw_fd(), bufs)
+    }
 }
 
 impl Write for PipeWriter {
@@ -115,6 +120,10 @@ impl Write for PipeWriter {
         syscall::write(self.0.as_raw_fd(), buf)
     }
 
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> Result<usize, Ov6Error> {
+        syscall::writev(self.0.as_raw_fd(), bufs)
+    }
+
     fn flush(&mut self) -> Result<(), Ov6Error> {
         Ok(())
     }
@@ -1,12 +1,12 @@
 use core::convert::Infallible;
 
 use alloc_crate::vec::Vec;
-use ov6_syscall::{UserSlice, WaitTarget};
+use ov6_syscall::{AccessMode, UserSlice, WaitTarget};
 pub use ov6_types::process::ProcId;
 use ov6_types::{os_str::OsStr, path::Path};
 
 pub use self::builder::{ChildWithIo, ProcessBuilder, Stdio};
-use crate::{error::Ov6Error, os::ov6::syscall};
+use crate::{error::Ov6Error, fs::File, io::Read as _, os::ov6::syscall};
 
 mod builder;
 
@@ -147,27 +147,128 @@ pub unsafe fn shrink_break(size: usize) -> Result<*mut u8, Ov6Error> {
     unsafe { syscall::sbrk(-isize::try_from(size).unwrap()) }
 }
 
+/// Maximum length of the `#!` line of a script, including `#!`.
+const SHEBANG_MAX: usize = 128;
+
 /// Replaces the current process image with a new process image specified by the
 /// path and arguments.
+///
+/// If the file is a script starting with `#!interpreter [arg]`, the interpreter
+/// is executed instead with `argv` of `[interpreter, arg, path, argv[1..]...]`.
+/// The interpreter itself must not be a script.
 pub fn exec<P, A>(path: P, argv: &[A]) -> Result<Infallible, Ov6Error>
 where
     P: AsRef<Path>,
     A: AsRef<OsStr>,
+{
+    let path = path.as_ref();
+    let Err(e) = exec_image(path, argv);
+    if !matches!(e, Ov6Error::ExecFormat) {
+        return Err(e);
+    }
+
+    let mut buf = [0; SHEBANG_MAX];
+    let Some((interpreter, arg)) = read_shebang(path, &mut buf) else {
+        return Err(e);
+    };
+    let mut new_argv = Vec::with_capacity(argv.len() + 2);
+    new_argv.push(interpreter);
+    new_argv.extend(arg);
+    new_argv.push(path.as_os_str());
+    new_argv.extend(argv.iter().skip(1).map(AsRef::as_ref));
+    exec_image(Path::new(interpreter), &new_argv)
+}
+
+/// Reads the `#!` line of the file at `path`.
+///
+/// Returns the interpreter and its optional argument.
+fn read_shebang<'a>(path: &Path, buf: &'a mut [u8]) -> Option<(&'a OsStr, Option<&'a OsStr>)> {
+    let mut file = File::open(path).ok()?;
+    let mut len = 0;
+    while len < buf.len() {
+        let n = file.read(&mut buf[len..]).ok()?;
+        if n == 0 {
+            break;
+        }
+        len += n;
+    }
+
+    let line = buf[..len].strip_prefix(b"#!")?;
+    let line = &line[..line.iter().position(|&c| c == b'\n')?];
+    let line = line.trim_ascii();
+    let (interpreter, arg) = line
+        .iter()
+        .position(u8::is_ascii_whitespace)
+        .map_or((line, None), |i| (&line[..i], Some(line[i..].trim_ascii())));
+    if interpreter.is_empty() {
+        return None;
+    }
+    Some((OsStr::from_bytes(interpreter), arg.map(OsStr::from_bytes)))
+}
+
+fn exec_image<A>(path: &Path, argv: &[A]) -> Result<Infallible, Ov6Error>
+where
+    A: AsRef<OsStr>,
 {
     if argv.len() < 10 {
         let mut new_argv = [const { unsafe { UserSlice::from_raw_parts(0, 0) } }; 10];
         for (dst, src) in new_argv.iter_mut().zip(argv) {
             *dst = UserSlice::new(src.as_ref().as_bytes());
         }
-        syscall::exec(path.as_ref(), &new_argv[..argv.len()])
+        syscall::exec(path, &new_argv[..argv.len()])
     } else {
         let argv = argv
             .iter()
             .map(|s| UserSlice::new(s.as_ref().as_bytes()))
             .collect::<Vec<_>>();
 
-        syscall::exec(path.as_ref(), &argv)
+        syscall::exec(path, &argv)
+    }
+}
+
+/// Default command search path used by [`exec_search`].
+pub const DEFAULT_PATH: &str = "/bin:/";
+
+/// Replaces the current process image with the command `name`, searching the
+/// directories in `search_path` for it.
+///
+/// `search_path` is a list of directories separated by `:`, in the same format
+/// as the `PATH` environment variable of Unix. An empty entry means the
+/// current directory.
+///
+/// If `name` contains a `/`, it is executed as is without searching.
+///
+/// # Errors
+///
+/// Returns [`Ov6Error::FsEntryNotFound`] if the command is not found in any
+/// directory. If the command is found but cannot be executed, returns the error
+/// of the last failed attempt.
+pub fn exec_search<S, A>(name: S, argv: &[A], search_path: &OsStr) -> Result<Infallible, Ov6Error>
+where
+    S: AsRef<OsStr>,
+    A: AsRef<OsStr>,
+{
+    let name = name.as_ref();
+    if name.as_bytes().contains(&b'/') {
+        return exec(Path::new(name), argv);
+    }
+
+    let mut error = Ov6Error::FsEntryNotFound;
+    for dir in search_path.as_bytes().split(|b| *b == b':') {
+        let dir = if dir.is_empty() {
+            Path::new(".")
+        } else {
+            Path::new(OsStr::from_bytes(dir))
+        };
+        let path = dir.join(name);
+        // probe the path first, so that loading an image is only attempted
+        // for the candidates that can be executed
+        let Err(e) = syscall::access(&path, AccessMode::EXECUTE).and_then(|()| exec(&path, argv));
+        if !matches!(e, Ov6Error::FsEntryNotFound | Ov6Error::NotADirectory) {
+            error = e;
+        }
     }
+    Err(error)
 }
 
 /// Sends a kill signal to the process with the specified process ID.
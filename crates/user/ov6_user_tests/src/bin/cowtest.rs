@@ -8,8 +8,12 @@ use ov6_user_lib::{
     os::ov6::syscall,
     process::{self, ProcessBuilder, Stdio},
     thread,
+    time::Instant,
+};
+use ov6_user_tests::{
+    message,
+    test_runner::{TestEntry, TestParam},
 };
-use ov6_user_tests::test_runner::{TestEntry, TestParam};
 
 fn main() {
     TestParam::parse().run(TESTS);
@@ -46,6 +50,11 @@ const TESTS: &[TestEntry] = &[
         test: fork_fork,
         tags: &[],
     },
+    TestEntry {
+        name: "bench",
+        test: bench,
+        tags: &[],
+    },
 ];
 
 /// Allocate more than half of physical memory,
@@ -228,3 +237,42 @@ fn fork_fork() {
         }
     }
 }
+
+/// Measures the time to zero and to copy a page.
+///
+/// Growing the heap zeroes the new pages, and writing to them in a child
+/// copies them.
+fn bench() {
+    const PAGES: usize = 1024;
+    const PAGE_SIZE: usize = 4096;
+
+    let size = PAGES * PAGE_SIZE;
+    let start = Instant::now();
+    let p = process::grow_break(size).unwrap();
+    let elapsed = start.elapsed() / u32::try_from(PAGES).unwrap();
+    message!("zero: {}ns/page", elapsed.as_nanos());
+
+    for i in (0..size).step_by(PAGE_SIZE) {
+        unsafe { p.add(i).write(1); }
+    }
+
+    let status = ProcessBuilder::new()
+        .spawn_fn(|| {
+            let start = Instant::now();
+            for i in (0..size).step_by(PAGE_SIZE) {
+                unsafe { p.add(i).write(2); }
+            }
+            let elapsed = start.elapsed() / u32::try_from(PAGES).unwrap();
+            message!("copy on write: {}ns/page", elapsed.as_nanos());
+            process::exit(0);
+        })
+        .unwrap()
+        .wait()
+        .unwrap();
+    assert!(status.success());
+
+    for i in (0..size).step_by(PAGE_SIZE) {
+        assert_eq!(unsafe { p.add(i).read() }, 1);
+    }
+    unsafe { process::shrink_break(size) }.unwrap();
+}
@@ -20,6 +20,7 @@ use core::{
 
 use ov6_user_lib::{
     env,
+    error::Ov6Error,
     fs::{self, File},
     io::{Read as _, STDOUT_FD, Write as _},
     os::ov6::syscall,
@@ -58,6 +59,13 @@ fn rand() -> u64 {
     n
 }
 
+/// Removes `path`, which may be either a file or a directory.
+fn remove(path: &str) {
+    if matches!(fs::remove_file(path), Err(Ov6Error::IsADirectory)) {
+        let _ = fs::remove_dir(path);
+    }
+}
+
 #[expect(clippy::too_many_lines)]
 fn go(name: u8) {
     let mut buf = [0; 999];
@@ -92,11 +100,11 @@ fn go(name: u8) {
                     .open("grindir/../grindir/../b");
             }
             3 => {
-                let _ = fs::remove_file("grindir/../a");
+                remove("grindir/../a");
             }
             4 => {
                 env::set_current_directory("grindir").unwrap();
-                let _ = fs::remove_file("../b");
+                remove("../b");
                 env::set_current_directory("/").unwrap();
             }
             5 => {
@@ -146,11 +154,11 @@ fn go(name: u8) {
                 let _ = fs::remove_file("b/b");
             }
             11 => {
-                let _ = fs::remove_file("b");
+                remove("b");
                 let _ = fs::link("../grindir/./../a", "../b");
             }
             12 => {
-                let _ = fs::remove_file("../grindir/../a");
+                remove("../grindir/../a");
                 let _ = fs::link(".././b", "/grindir/../a");
             }
             13 => {
@@ -224,7 +232,7 @@ fn go(name: u8) {
             20 => {
                 ProcessBuilder::new()
                     .spawn_fn(|| {
-                        let _ = fs::remove_file("a");
+                        remove("a");
                         let _ = fs::create_dir("a");
                         let _ = env::set_current_directory("a");
                         let _file = File::options()
@@ -260,7 +268,7 @@ fn go(name: u8) {
                 let mut child_a = ProcessBuilder::new()
                     .stdout(Stdio::Pipe)
                     .spawn_fn(|| {
-                        process::exec("grindir/../echo", &["echo", "hi"]).unwrap();
+                        process::exec("grindir/../bin/echo", &["echo", "hi"]).unwrap();
                         unreachable!();
                     })
                     .unwrap();
@@ -270,7 +278,7 @@ fn go(name: u8) {
                     .stdin(Stdio::Fd(stdout_a.into()))
                     .stdout(Stdio::Pipe)
                     .spawn_fn(|| {
-                        process::exec("/cat", &["cat"]).unwrap();
+                        process::exec("/bin/cat", &["cat"]).unwrap();
                         unreachable!();
                     })
                     .unwrap();
@@ -290,8 +298,8 @@ fn go(name: u8) {
 }
 
 fn iter() {
-    let _ = fs::remove_file("a");
-    let _ = fs::remove_file("b");
+    remove("a");
+    remove("b");
 
     let mut child1 = ProcessBuilder::new()
         .spawn_fn(|| {
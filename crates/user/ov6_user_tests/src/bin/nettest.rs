@@ -66,6 +66,11 @@ const TESTS: &[TestEntry] = &[
         test: rxburst,
         tags: &[],
     },
+    TestEntry {
+        name: "rxflood",
+        test: rxflood,
+        tags: &[],
+    },
     TestEntry {
         name: "tx",
         test: tx,
@@ -177,6 +182,33 @@ fn rxburst() {
     rx();
 }
 
+fn rxflood() {
+    const N: usize = 256;
+
+    let sock = UdpSocket::bind(2000).unwrap();
+    let before = syscall::get_system_info().unwrap().net;
+
+    let mut last_seq = None;
+    for _ in 0..N {
+        let mut ibuf = [0; 128];
+        let (cc, src) = sock.recv_from(&mut ibuf).unwrap();
+        assert_eq!(*src.ip(), server_ip());
+        let ibuf = str::from_utf8(&ibuf[..cc]).unwrap();
+        let seq: usize = ibuf.strip_prefix("packet ").unwrap().parse().unwrap();
+        // packets may be dropped under load, but never reordered
+        assert!(last_seq.is_none_or(|last| last < seq));
+        last_seq = Some(seq);
+    }
+
+    let after = syscall::get_system_info().unwrap().net;
+    let received = after.rx_packets - before.rx_packets;
+    let dropped = after.rx_dropped - before.rx_dropped;
+    let missed = after.rx_missed - before.rx_missed;
+    let polls = after.rx_polls - before.rx_polls;
+    assert!(received >= N);
+    eprintln!("received={received} dropped={dropped} missed={missed} polls={polls}");
+}
+
 fn tx() {
     let dst = server_addr();
     for i in 0..5 {
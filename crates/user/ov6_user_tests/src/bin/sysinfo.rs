@@ -1,7 +1,10 @@
 #![cfg_attr(not(test), no_std)]
 
 use ov6_user_lib::{
-    os::ov6::syscall::{self, MemoryInfo, SystemInfo},
+    os::ov6::syscall::{
+        self, DiskInfo, FileInfo, HeapClassInfo, HeapInfo, InterruptInfo, LOAD_FSHIFT, LoadInfo,
+        MemoryInfo, NameCacheInfo, NetInfo, PageCacheInfo, SystemInfo,
+    },
     println,
 };
 use ov6_user_tests::{OrExit as _, exit_err};
@@ -11,21 +14,174 @@ fn main() {
         exit_err!(e, "cannot get system info");
     });
 
-    let SystemInfo { memory } = sysinfo;
+    let SystemInfo {
+        version: _,
+        memory,
+        heap,
+        page_cache,
+        name_cache,
+        disk,
+        interrupts,
+        net,
+        files,
+        load,
+    } = sysinfo;
 
     print_memory_info(&memory);
+    println!();
+    print_heap_info(&heap);
+    println!();
+    print_page_cache_info(&page_cache);
+    println!();
+    print_name_cache_info(&name_cache);
+    println!();
+    print_disk_info(&disk);
+    println!();
+    print_interrupt_info(&interrupts);
+    println!();
+    print_net_info(&net);
+    println!();
+    print_file_info(&files);
+    println!();
+    print_load_info(&load);
 }
 
 fn print_memory_info(info: &MemoryInfo) {
     let MemoryInfo {
         free_pages,
+        zeroed_pages,
         total_pages,
         page_size,
+        oom_kills,
     } = info;
 
     println!("# Memory Information");
     println!("{:<12} {total_pages}", "PageTotal");
     println!("{:<12} {free_pages}", "PageFree");
+    println!("{:<12} {zeroed_pages}", "PageZeroed");
     println!("{:<12} {} kB", "MemTotal", total_pages * page_size / 1024);
     println!("{:<12} {} kB", "MemFree", free_pages * page_size / 1024);
+    println!("{:<12} {oom_kills}", "OomKills");
+}
+
+fn print_heap_info(info: &HeapInfo) {
+    let HeapInfo {
+        classes,
+        large_pages,
+    } = info;
+
+    println!("# Kernel Heap");
+    println!("{:<12} {:>8} {:>8}", "Size", "Pages", "Objects");
+    for class in classes {
+        let HeapClassInfo {
+            object_size,
+            pages,
+            allocated,
+        } = class;
+        println!("{object_size:<12} {pages:>8} {allocated:>8}");
+    }
+    println!("{:<12} {large_pages:>8} {large_pages:>8}", "large");
+}
+
+fn print_page_cache_info(info: &PageCacheInfo) {
+    let PageCacheInfo {
+        hits,
+        misses,
+        readahead,
+        bypassed,
+        dropped,
+    } = info;
+
+    println!("# Page Cache");
+    println!("{:<12} {hits}", "Hits");
+    println!("{:<12} {misses}", "Misses");
+    println!("{:<12} {readahead}", "ReadAhead");
+    println!("{:<12} {bypassed}", "Bypassed");
+    println!("{:<12} {dropped}", "Dropped");
+}
+
+fn print_name_cache_info(info: &NameCacheInfo) {
+    let NameCacheInfo {
+        hits,
+        negative_hits,
+        misses,
+        invalidations,
+    } = info;
+
+    println!("# Name Cache");
+    println!("{:<12} {hits}", "Hits");
+    println!("{:<12} {negative_hits}", "NegHits");
+    println!("{:<12} {misses}", "Misses");
+    println!("{:<12} {invalidations}", "Invalidated");
+}
+
+fn print_disk_info(info: &DiskInfo) {
+    let DiskInfo { reads, writes } = info;
+
+    println!("# Disk");
+    println!("{:<12} {reads}", "Reads");
+    println!("{:<12} {writes}", "Writes");
+}
+
+fn print_interrupt_info(info: &InterruptInfo) {
+    let InterruptInfo { counts, spurious } = info;
+
+    println!("# Interrupts");
+    println!("{:<12} {:>8}", "IRQ", "Count");
+    for (irq, count) in counts.iter().enumerate() {
+        if *count > 0 {
+            println!("{irq:<12} {count:>8}");
+        }
+    }
+    println!("{:<12} {spurious:>8}", "spurious");
+}
+
+fn print_net_info(info: &NetInfo) {
+    let NetInfo {
+        rx_packets,
+        rx_polls,
+        rx_missed,
+        rx_dropped,
+    } = info;
+
+    println!("# Network");
+    println!("{:<12} {rx_packets}", "RxPackets");
+    println!("{:<12} {rx_polls}", "RxPolls");
+    println!("{:<12} {rx_missed}", "RxMissed");
+    println!("{:<12} {rx_dropped}", "RxDropped");
+}
+
+fn print_file_info(info: &FileInfo) {
+    let FileInfo { open, peak } = info;
+
+    println!("# Files");
+    println!("{:<12} {open}", "Open");
+    println!("{:<12} {peak}", "Peak");
+}
+
+fn print_load_info(info: &LoadInfo) {
+    let LoadInfo {
+        uptime_nanos,
+        averages,
+        ticks_per_sec,
+        num_cpus,
+        busy_ticks,
+        idle_ticks,
+    } = info;
+
+    println!("# Load");
+    println!("{:<12} {uptime_nanos}", "UptimeNanos");
+    println!("{:<12} {ticks_per_sec}", "TicksPerSec");
+    for (name, avg) in ["Load1", "Load5", "Load15"].into_iter().zip(averages) {
+        println!("{name:<12} {avg} (>> {LOAD_FSHIFT})");
+    }
+    println!("{:<12} {:>12} {:>12}", "CPU", "BusyTicks", "IdleTicks");
+    for (id, (busy, idle)) in busy_ticks
+        .iter()
+        .zip(idle_ticks)
+        .take(*num_cpus)
+        .enumerate()
+    {
+        println!("{id:<12} {busy:>12} {idle:>12}");
+    }
 }
@@ -0,0 +1,30 @@
+//! Fixture program debugged by the `udb` tests.
+//!
+//! The functions are kept out of line, so that breakpoints can be set on
+//! them by name.
+
+#![cfg_attr(not(test), no_std)]
+
+use core::hint;
+
+use ov6_user_lib::println;
+
+#[inline(never)]
+fn fib(n: u64) -> u64 {
+    if n < 2 { n } else { fib(n - 1) + fib(n - 2) }
+}
+
+#[inline(never)]
+fn compute(n: u64) -> u64 {
+    hint::black_box(fib(n))
+}
+
+#[inline(never)]
+fn outer() -> u64 {
+    compute(hint::black_box(10))
+}
+
+fn main() {
+    let result = outer();
+    println!("result {result}");
+}
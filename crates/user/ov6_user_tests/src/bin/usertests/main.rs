@@ -11,9 +11,8 @@ mod tests;
 
 const PAGE_SIZE: usize = 4096;
 const KERN_BASE: usize = 0x8000_0000;
-const MAX_VA: usize = 1 << (9 + 9 + 9 + 12 - 1);
 const README_PATH: &str = "README";
-const ECHO_PATH: &str = "echo";
+const ECHO_PATH: &str = "/bin/echo";
 const ROOT_DIR_PATH: &str = "/";
 
 const BUF_SIZE: usize = (MAX_OP_BLOCKS + 2) * FS_BLOCK_SIZE;
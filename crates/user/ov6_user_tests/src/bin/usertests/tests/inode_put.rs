@@ -39,7 +39,7 @@ pub fn inode_put_open() {
     .unwrap();
 
     thread::sleep(Duration::from_millis(100));
-    fs::remove_file(OIDIR_PATH).unwrap();
+    fs::remove_dir(OIDIR_PATH).unwrap();
 
     let status = child.wait().unwrap();
     assert!(status.success());
@@ -50,7 +50,7 @@ pub fn inode_put_exit() {
     let status = process::fork_fn(|| {
         fs::create_dir(IPUTDIR_PATH).unwrap();
         env::set_current_directory(IPUTDIR_PATH).unwrap();
-        fs::remove_file("../iputdir").unwrap();
+        fs::remove_dir("../iputdir").unwrap();
         process::exit(0);
     })
     .unwrap()
@@ -63,6 +63,6 @@ pub fn inode_put_exit() {
 pub fn inode_put_chdir() {
     fs::create_dir(IPUTDIR_PATH).unwrap();
     env::set_current_directory(IPUTDIR_PATH).unwrap();
-    fs::remove_file("../iputdir").unwrap();
+    fs::remove_dir("../iputdir").unwrap();
     env::set_current_directory(ROOT_DIR_PATH).unwrap();
 }
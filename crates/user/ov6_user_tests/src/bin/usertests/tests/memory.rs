@@ -1,25 +1,34 @@
+use core::{slice, time::Duration};
+
+use ov6_kernel_params::USER_STACK_PAGES;
 use ov6_syscall::{UserMutSlice, UserSlice, error::SyscallError, syscall};
 use ov6_user_lib::{
+    error::Ov6Error,
     fs::{self, File},
     io::{self, Read as _, STDOUT_FD, Write as _},
-    os::{fd::AsRawFd as _, ov6::syscall::ffi::SyscallExt as _},
+    os::{
+        fd::AsRawFd as _,
+        ov6::syscall::{self as user_syscall, MemoryInfo, ffi::SyscallExt as _},
+    },
     pipe,
     process::{self, Stdio},
+    thread,
 };
 use ov6_user_tests::expect;
 
-use crate::README_PATH;
+use crate::{PAGE_SIZE, README_PATH};
 
 const FILE_PATH: &str = "copyin1";
 
 /// what if you pass ridiculous pointers to system calls
 /// that read user memory with copyin?
 pub fn copy_u2k() {
+    let layout = user_syscall::user_layout();
     let addrs: &[usize] = &[
         0x8000_0000,
-        0x3f_ffff_e000,
-        0x3f_ffff_f000,
-        0x40_0000_0000,
+        layout.trapframe,
+        layout.trampoline,
+        layout.max_va,
         0xffff_ffff_ffff_ffff,
     ];
 
@@ -55,12 +64,13 @@ pub fn copy_u2k() {
 /// what if you pass ridiculous pointers to system calls
 /// that write user memory with copyout?
 pub fn copy_k2u() {
+    let layout = user_syscall::user_layout();
     let addrs: &[usize] = &[
         0,
         0x8000_0000,
-        0x3f_ffff_e000,
-        0x3f_ffff_f000,
-        0x40_0000_0000,
+        layout.trapframe,
+        layout.trampoline,
+        layout.max_va,
         0xffff_ffff_ffff_ffff,
     ];
 
@@ -88,6 +98,48 @@ pub fn copy_k2u() {
     }
 }
 
+/// The layout on the usyscall page matches the address space.
+pub fn user_layout() {
+    const N: usize = size_of::<usize>();
+
+    let layout = user_syscall::user_layout();
+    assert!(layout.heap_start <= process::current_break().addr());
+    assert_eq!(
+        layout.stack_top - layout.stack_bottom,
+        USER_STACK_PAGES * PAGE_SIZE
+    );
+    let sp = &raw const layout;
+    assert!((layout.stack_bottom..layout.stack_top).contains(&sp.addr()));
+    assert_eq!(layout.trapframe + PAGE_SIZE, layout.trampoline);
+    assert_eq!(layout.trampoline + PAGE_SIZE, layout.max_va);
+
+    // the usyscall page can be read, but not written.
+    let (mut rx, tx) = pipe::pipe().unwrap();
+    expect!(
+        syscall::Write::call((tx.as_raw_fd(), unsafe {
+            UserSlice::from_raw_parts(layout.usyscall, N)
+        })),
+        Ok(N),
+    );
+    expect!(
+        syscall::Read::call((rx.as_raw_fd(), unsafe {
+            UserMutSlice::from_raw_parts(layout.usyscall, N)
+        })),
+        Err(SyscallError::BadAddress),
+    );
+    let mut buf = [0; N];
+    rx.read_exact(&mut buf).unwrap();
+
+    // a buffer reaching the pages reserved by the kernel is rejected.
+    let start = layout.trapframe - PAGE_SIZE;
+    expect!(
+        syscall::Write::call((tx.as_raw_fd(), unsafe {
+            UserSlice::from_raw_parts(start, PAGE_SIZE + 1)
+        })),
+        Err(SyscallError::BadAddress),
+    );
+}
+
 /// See if the kernel refuses to read/write user memory that the
 /// application doesn't have anymore, because it returned it.
 pub fn rw_sbrk() {
@@ -172,3 +224,94 @@ pub fn count_free_pages() {
          os_free_pages={os_free_pages}"
     );
 }
+
+/// test the usage statistics of the kernel heap.
+pub fn heap_stats() {
+    const PORT: u16 = 2999;
+
+    fn allocated() -> usize {
+        let heap = user_syscall::get_system_info().unwrap().heap;
+        heap.classes.iter().map(|c| c.allocated).sum::<usize>() + heap.large_pages
+    }
+
+    let heap = user_syscall::get_system_info().unwrap().heap;
+    for pair in heap.classes.windows(2) {
+        assert!(pair[0].object_size < pair[1].object_size);
+    }
+    for class in &heap.classes {
+        assert!(class.allocated * class.object_size <= class.pages * PAGE_SIZE);
+        // empty slabs are returned to the page allocator
+        assert_eq!(class.allocated == 0, class.pages == 0);
+    }
+
+    // a bound port is allocated from the heap
+    let before = allocated();
+    user_syscall::bind(PORT).unwrap();
+    assert_eq!(allocated(), before + 1);
+    user_syscall::unbind(PORT).unwrap();
+    assert_eq!(allocated(), before);
+}
+
+/// test that zeroed pages prepared by idle CPUs are handed out as zero-filled.
+pub fn zeroed_pages() {
+    const SIZE: usize = 64 * PAGE_SIZE;
+
+    fn memory_info() -> MemoryInfo {
+        user_syscall::get_system_info().unwrap().memory
+    }
+
+    // let idle CPUs zero free pages
+    for _ in 0..10 {
+        if memory_info().zeroed_pages > 0 {
+            break;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    let info = memory_info();
+    assert!(info.zeroed_pages > 0, "no pages zeroed in advance");
+    assert!(info.zeroed_pages <= info.free_pages);
+
+    let a = process::grow_break(SIZE).unwrap();
+    let mem = unsafe { slice::from_raw_parts_mut(a, SIZE) };
+    assert!(mem.iter().all(|&b| b == 0));
+    mem.fill(0xa5);
+    let _ = unsafe { process::shrink_break(SIZE) }.unwrap();
+
+    // dirty pages are zeroed again before reused
+    let a = process::grow_break(SIZE).unwrap();
+    let mem = unsafe { slice::from_raw_parts(a, SIZE) };
+    assert!(mem.iter().all(|&b| b == 0));
+    let _ = unsafe { process::shrink_break(SIZE) }.unwrap();
+}
+
+/// test that allocation failures in the kernel are reported to the caller.
+pub fn alloc_fault() {
+    const PORT: u16 = 2998;
+    const PATH: &str = "allocfault";
+
+    user_syscall::inject_alloc_fault(0).unwrap();
+    expect!(pipe::pipe(), Err(Ov6Error::OutOfMemory));
+    // the fault is removed once triggered
+    let (rx, tx) = pipe::pipe().unwrap();
+    drop((rx, tx));
+
+    user_syscall::inject_alloc_fault(0).unwrap();
+    expect!(user_syscall::bind(PORT), Err(Ov6Error::OutOfMemory));
+    user_syscall::bind(PORT).unwrap();
+    user_syscall::unbind(PORT).unwrap();
+
+    // the inode allocated on the disk is released when the in-memory inode
+    // cannot be allocated
+    let free_inodes = fs::fs_stats(".").unwrap().free_inodes();
+    user_syscall::inject_alloc_fault(0).unwrap();
+    expect!(File::create(PATH), Err(Ov6Error::OutOfMemory));
+    expect!(fs::metadata(PATH).err(), Some(Ov6Error::FsEntryNotFound));
+    assert_eq!(fs::fs_stats(".").unwrap().free_inodes(), free_inodes);
+
+    // the in-memory inode is allocated again after the file is closed
+    drop(File::create(PATH).unwrap());
+    user_syscall::inject_alloc_fault(0).unwrap();
+    expect!(File::open(PATH), Err(Ov6Error::OutOfMemory));
+    drop(File::open(PATH).unwrap());
+    fs::remove_file(PATH).unwrap();
+}
@@ -1,17 +1,37 @@
-use core::{cell::UnsafeCell, mem::MaybeUninit, ptr, slice};
+use core::{
+    array,
+    cell::UnsafeCell,
+    mem::MaybeUninit,
+    ptr, slice,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
 
+use ov6_fs_types::FS_BLOCK_SIZE;
 use ov6_kernel_params::USER_STACK_PAGES;
-use ov6_syscall::{UserMutSlice, UserSlice, error::SyscallError, syscall};
+use ov6_syscall::{
+    SYSCALL_FILTER_WORDS, Stat, UserMutRef, UserMutSlice, UserSlice, error::SyscallError, syscall,
+};
 use ov6_user_lib::{
+    env,
     error::Ov6Error,
     fs::{self, File},
     io::{Read as _, Write as _},
-    os::{fd::AsRawFd as _, ov6::syscall::ffi::SyscallExt as _},
+    os::{
+        fd::AsRawFd as _,
+        ov6::syscall::{
+            self as user_syscall, AuditRecord, Capabilities, PtraceStop, SyscallCode,
+            SyscallFilterAction, ffi::SyscallExt as _,
+        },
+    },
     os_str::OsStr,
     path::Path,
-    process::{self, ProcessBuilder},
+    pipe,
+    process::{self, ExitStatus, ProcessBuilder},
+    thread,
+    time::Instant,
 };
-use ov6_user_tests::expect;
+use ov6_user_tests::{expect, message};
 
 use crate::{BUF, ECHO_PATH, PAGE_SIZE};
 
@@ -212,6 +232,50 @@ pub fn pg_bug() {
     );
 }
 
+/// Malformed user pointers are rejected with `BadAddress` before anything is
+/// copied.
+pub fn bad_pointers() {
+    let file = File::open(ECHO_PATH).unwrap();
+
+    // a misaligned `Stat` is not written.
+    let buf = [0xa5_u8; size_of::<Stat>() + 16];
+    let addr = buf.as_ptr().addr().next_multiple_of(8) + 1;
+    expect!(
+        syscall::Fstat::call((file.as_raw_fd(), unsafe {
+            UserMutRef::<Stat>::from_raw(addr)
+        })),
+        Err(SyscallError::BadAddress),
+    );
+    assert!(buf.iter().all(|&b| b == 0xa5));
+
+    // random buffers above the user stack, where only the usyscall page is
+    // mapped.
+    let layout = user_syscall::user_layout();
+    let (_rx, tx) = pipe::pipe().unwrap();
+    let mut state: usize = 0x2545_f491_4f6c_dd1d;
+    for _ in 0..1000 {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        let addr = layout.stack_top + state % (usize::MAX - layout.stack_top);
+        let len = if state.is_multiple_of(2) {
+            1 + (state >> 8) % (2 * PAGE_SIZE)
+        } else {
+            (state >> 8) | 1
+        };
+        if addr < layout.usyscall + PAGE_SIZE && layout.usyscall < addr.saturating_add(len) {
+            continue;
+        }
+        expect!(
+            syscall::Write::call((tx.as_raw_fd(), unsafe {
+                UserSlice::from_raw_parts(addr, len)
+            })),
+            Err(SyscallError::BadAddress),
+            "addr={addr:#x}, len={len:#x}",
+        );
+    }
+}
+
 /// regression test. does the kernel panic if a process `sbrk()`s its
 /// size to be less than a page, or zero, or reduces the break by an
 /// amount too small to cause a page to be freed?
@@ -286,3 +350,460 @@ pub fn bad_arg() {
         );
     }
 }
+
+/// exec the same program many times, and check that overwriting a program
+/// drops its text cached by earlier execs.
+pub fn exec_text_cache() {
+    const N: usize = 50;
+    const TRUE_PATH: &str = "/bin/true";
+    const FALSE_PATH: &str = "/bin/false";
+    const PATH: &str = "textcache";
+
+    fn run(path: &str) -> ExitStatus {
+        ProcessBuilder::new()
+            .spawn_fn(|| {
+                process::exec(path, &[path]).unwrap();
+                unreachable!();
+            })
+            .unwrap()
+            .wait()
+            .unwrap()
+    }
+
+    let start = Instant::now();
+    for _ in 0..N {
+        assert!(run(TRUE_PATH).success());
+    }
+    let elapsed = start.elapsed() / u32::try_from(N).unwrap();
+    message!(
+        "fork/exec: {}.{:03}ms",
+        elapsed.as_millis(),
+        elapsed.subsec_micros() % 1000
+    );
+
+    fs::copy(TRUE_PATH, PATH).unwrap();
+    assert!(run(PATH).success());
+    fs::copy(FALSE_PATH, PATH).unwrap();
+    assert_eq!(run(PATH).code(), 1);
+    fs::remove_file(PATH).unwrap();
+}
+
+/// dropped capabilities must gate privileged syscalls and never come back.
+pub fn drop_caps() {
+    const DEV_PATH: &str = "capsdev";
+    const DIR_PATH: &str = "capsdir";
+
+    let status = ProcessBuilder::new()
+        .spawn_fn(|| {
+            let caps = user_syscall::drop_caps(Capabilities::empty()).unwrap();
+            assert!(caps.contains(Capabilities::MKNOD | Capabilities::CHROOT));
+
+            let caps = user_syscall::drop_caps(Capabilities::MKNOD | Capabilities::CHROOT).unwrap();
+            assert!(!caps.intersects(Capabilities::MKNOD | Capabilities::CHROOT));
+
+            expect!(fs::mknod(DEV_PATH, 99, 0), Err(Ov6Error::NotPermitted));
+            fs::create_dir(DIR_PATH).unwrap();
+            expect!(
+                user_syscall::chroot(Path::new(DIR_PATH)),
+                Err(Ov6Error::NotPermitted)
+            );
+            fs::remove_dir(DIR_PATH).unwrap();
+
+            // dropped capabilities are inherited and cannot be regained
+            let status = ProcessBuilder::new()
+                .spawn_fn(|| {
+                    let caps = user_syscall::drop_caps(Capabilities::empty()).unwrap();
+                    assert!(!caps.contains(Capabilities::MKNOD));
+                    expect!(fs::mknod(DEV_PATH, 99, 0), Err(Ov6Error::NotPermitted));
+                    process::exit(0);
+                })
+                .unwrap()
+                .wait()
+                .unwrap();
+            assert!(status.success());
+
+            process::exit(0);
+        })
+        .unwrap()
+        .wait()
+        .unwrap();
+    assert!(status.success());
+
+    let caps = user_syscall::drop_caps(Capabilities::empty()).unwrap();
+    assert!(caps.contains(Capabilities::MKNOD));
+}
+
+pub fn syscall_filter() {
+    let status = ProcessBuilder::new()
+        .spawn_fn(|| {
+            // malformed masks are rejected
+            expect!(
+                syscall::SetSyscallFilter::call((
+                    UserSlice::new(&[u64::MAX]),
+                    SyscallFilterAction::Deny
+                )),
+                Err(SyscallError::InvalidInput)
+            );
+            expect!(
+                syscall::SetSyscallFilter::call((
+                    UserSlice::new(&[u64::MAX; SYSCALL_FILTER_WORDS]),
+                    SyscallFilterAction::Deny
+                )),
+                Err(SyscallError::InvalidInput)
+            );
+
+            user_syscall::set_syscall_filter(
+                &[
+                    SyscallCode::Write,
+                    SyscallCode::Close,
+                    SyscallCode::Fork,
+                    SyscallCode::Getcwd,
+                ],
+                SyscallFilterAction::Deny,
+            )
+            .unwrap();
+            expect!(File::open(ECHO_PATH), Err(Ov6Error::NotPermitted));
+            expect!(fs::create_dir("filterdir"), Err(Ov6Error::NotPermitted));
+            // codes above 63 can be allowed
+            env::current_directory().unwrap();
+
+            // filters can only be narrowed
+            user_syscall::set_syscall_filter(
+                &[SyscallCode::Write, SyscallCode::Open],
+                SyscallFilterAction::Deny,
+            )
+            .unwrap();
+            expect!(File::open(ECHO_PATH), Err(Ov6Error::NotPermitted));
+            expect!(env::current_directory(), Err(Ov6Error::NotPermitted));
+            expect!(
+                ProcessBuilder::new().spawn_fn(|| process::exit(0)),
+                Err(Ov6Error::NotPermitted)
+            );
+
+            process::exit(0);
+        })
+        .unwrap()
+        .wait()
+        .unwrap();
+    assert!(status.success());
+
+    let status = ProcessBuilder::new()
+        .spawn_fn(|| {
+            user_syscall::set_syscall_filter(&[SyscallCode::Write], SyscallFilterAction::Kill)
+                .unwrap();
+            let _ = File::open(ECHO_PATH);
+            process::exit(0);
+        })
+        .unwrap()
+        .wait()
+        .unwrap();
+    assert_eq!(status.code(), -1);
+}
+
+pub fn audit_log() {
+    const DEV_PATH: &str = "auditdev";
+
+    let mut child = ProcessBuilder::new()
+        .spawn_fn(|| {
+            user_syscall::drop_caps(Capabilities::MKNOD).unwrap();
+            expect!(fs::mknod(DEV_PATH, 99, 0), Err(Ov6Error::NotPermitted));
+            process::exit(0);
+        })
+        .unwrap();
+    let pid = u32::from(child.id());
+    assert!(child.wait().unwrap().success());
+
+    let mut records = [AuditRecord::zeroed(); 32];
+    let n = user_syscall::read_audit_log(&mut records).unwrap();
+    let record = records[..n]
+        .iter()
+        .rev()
+        .find(|r| r.pid == pid)
+        .expect("denied mknod should be recorded");
+    assert_eq!(record.syscall(), Some(SyscallCode::Mknod));
+    assert_eq!(record.error(), Some(SyscallError::NotPermitted));
+    assert_eq!(record.path(), Some(DEV_PATH.as_bytes()));
+
+    // reading the audit log requires the AUDIT capability
+    let status = ProcessBuilder::new()
+        .spawn_fn(|| {
+            user_syscall::drop_caps(Capabilities::AUDIT).unwrap();
+            let mut records = [AuditRecord::zeroed(); 1];
+            expect!(
+                user_syscall::read_audit_log(&mut records),
+                Err(Ov6Error::NotPermitted)
+            );
+            process::exit(0);
+        })
+        .unwrap()
+        .wait()
+        .unwrap();
+    assert!(status.success());
+}
+
+/// disk I/O is accounted to the process, and file reads and writes are
+/// throttled by its I/O limit.
+pub fn io_limit() {
+    const FILE_PATH: &str = "iolimit";
+    const LIMIT: u64 = 20;
+    const ROUNDS: usize = 3;
+
+    let buf = unsafe { (&raw const BUF).as_ref() }.unwrap();
+
+    let status = ProcessBuilder::new()
+        .spawn_fn(|| {
+            let pid = process::id();
+            user_syscall::set_io_limit(LIMIT).unwrap();
+            assert_eq!(user_syscall::io_stats(pid).unwrap().limit, LIMIT);
+
+            let start = Instant::now();
+            for _ in 0..ROUNDS {
+                let mut file = File::create(FILE_PATH).unwrap();
+                file.write_all(buf).unwrap();
+            }
+            let elapsed = start.elapsed();
+            fs::remove_file(FILE_PATH).unwrap();
+
+            let stats = user_syscall::io_stats(pid).unwrap();
+            assert!(stats.blocks_written > 0);
+            // the bucket holds a second of the limit at first, and the
+            // rest of the requested blocks are written at the limit.
+            let blocks = (ROUNDS * buf.len().div_ceil(FS_BLOCK_SIZE)) as u64;
+            let excess = blocks.saturating_sub(LIMIT);
+            if excess > 0 {
+                assert!(stats.throttled > 0);
+            }
+            let min = Duration::from_millis(excess * 1000 / LIMIT);
+            assert!(
+                elapsed + Duration::from_millis(100) >= min,
+                "{blocks} blocks took only {elapsed:?}"
+            );
+            process::exit(0);
+        })
+        .unwrap()
+        .wait()
+        .unwrap();
+    assert!(status.success());
+
+    // the limit is not inherited from the child
+    let stats = user_syscall::io_stats(process::id()).unwrap();
+    assert_eq!(stats.limit, 0);
+}
+
+/// concurrent sleepers each wake up after their own duration, and a killed
+/// sleeper exits without waiting for its timer.
+pub fn sleep_timers() {
+    const N: usize = 8;
+    const TICK: Duration = Duration::from_millis(100);
+
+    let start = Instant::now();
+    let children: [_; N] = array::from_fn(|i| {
+        let dur = TICK * u32::try_from(i + 1).unwrap();
+        ProcessBuilder::new()
+            .spawn_fn(move || {
+                let start = Instant::now();
+                thread::sleep(dur);
+                assert!(start.elapsed() + TICK >= dur);
+                process::exit(0);
+            })
+            .unwrap()
+    });
+    for mut child in children {
+        assert!(child.wait().unwrap().success());
+    }
+    assert!(start.elapsed() + TICK >= TICK * u32::try_from(N).unwrap());
+
+    let mut child = ProcessBuilder::new()
+        .spawn_fn(|| {
+            thread::sleep(Duration::from_secs(100));
+            process::exit(0);
+        })
+        .unwrap();
+    thread::sleep(TICK);
+    let start = Instant::now();
+    child.kill().unwrap();
+    assert!(!child.wait().unwrap().success());
+    assert!(start.elapsed() < Duration::from_secs(10));
+}
+
+/// the host name can be read back after it is changed, and only processes
+/// with the `SET_HOSTNAME` capability can change it.
+pub fn hostname() {
+    const NAME: &str = "usertests-host";
+
+    let mut orig = [0; user_syscall::HOST_NAME_MAX];
+    let orig_len = user_syscall::get_hostname(&mut orig).unwrap();
+    let orig = str::from_utf8(&orig[..orig_len]).unwrap();
+
+    user_syscall::set_hostname(NAME).unwrap();
+    let mut buf = [0; user_syscall::HOST_NAME_MAX];
+    let len = user_syscall::get_hostname(&mut buf).unwrap();
+    assert_eq!(&buf[..len], NAME.as_bytes());
+
+    // a short buffer receives the head of the name, and the full length
+    let mut short = [0; 4];
+    assert_eq!(user_syscall::get_hostname(&mut short).unwrap(), NAME.len());
+    assert_eq!(&short, &NAME.as_bytes()[..4]);
+
+    expect!(user_syscall::set_hostname(""), Err(Ov6Error::InvalidInput));
+    expect!(
+        user_syscall::set_hostname("with space"),
+        Err(Ov6Error::InvalidInput)
+    );
+
+    let status = ProcessBuilder::new()
+        .spawn_fn(|| {
+            user_syscall::drop_caps(Capabilities::SET_HOSTNAME).unwrap();
+            expect!(
+                user_syscall::set_hostname("denied"),
+                Err(Ov6Error::NotPermitted)
+            );
+            process::exit(0);
+        })
+        .unwrap()
+        .wait()
+        .unwrap();
+    assert!(status.success());
+
+    user_syscall::set_hostname(orig).unwrap();
+}
+
+/// the kernel symbol table is read in pieces, and only by processes with the
+/// `KERNEL_SYMBOLS` capability.
+pub fn kernel_symbols() {
+    let mut head = [0; 64];
+    let len = user_syscall::get_kernel_symbols(&mut head, 0).unwrap();
+    if len > 0 {
+        // the first line is `<hex address> <name>`
+        let line = head[..len].split(|b| *b == b'\n').next().unwrap();
+        let sep = line.iter().position(|b| *b == b' ').unwrap();
+        assert!(line[..sep].iter().all(u8::is_ascii_hexdigit));
+
+        // reading from an offset continues the same text
+        let mut rest = [0; 64];
+        let rest_len = user_syscall::get_kernel_symbols(&mut rest, 1).unwrap();
+        let n = usize::min(rest_len, len - 1);
+        assert_eq!(&rest[..n], &head[1..=n]);
+    }
+    expect!(
+        user_syscall::get_kernel_symbols(&mut head, usize::MAX),
+        Ok(0)
+    );
+
+    let status = ProcessBuilder::new()
+        .spawn_fn(|| {
+            user_syscall::drop_caps(Capabilities::KERNEL_SYMBOLS).unwrap();
+            expect!(
+                user_syscall::get_kernel_symbols(&mut [0; 64], 0),
+                Err(Ov6Error::NotPermitted)
+            );
+            process::exit(0);
+        })
+        .unwrap()
+        .wait()
+        .unwrap();
+    assert!(status.success());
+}
+
+/// the uptime and the tick counts advance, and a busy process raises the
+/// 1-minute load average.
+pub fn load_average() {
+    let load = || user_syscall::get_system_info().unwrap().load;
+    let total_ticks = |load: &user_syscall::LoadInfo| -> u64 {
+        load.busy_ticks[..load.num_cpus]
+            .iter()
+            .chain(&load.idle_ticks[..load.num_cpus])
+            .sum()
+    };
+
+    let before = load();
+    assert!(before.num_cpus >= 1);
+    assert!(before.num_cpus <= user_syscall::MAX_CPUS);
+
+    // stay runnable for a while
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_millis(500) {}
+
+    let after = load();
+    assert!(after.uptime_nanos > before.uptime_nanos);
+    assert!(total_ticks(&after) > total_ticks(&before));
+    assert!(after.averages[0] > 0);
+}
+
+/// can a parent stop its child at a breakpoint, access its registers and
+/// memory, and single-step it?
+pub fn ptrace() {
+    /// Stops at an uncompressed `ebreak` and returns `a0` after it.
+    #[cfg(target_arch = "riscv64")]
+    fn breakpoint() -> usize {
+        let marker: usize;
+        unsafe {
+            core::arch::asm!(
+                ".option push",
+                ".option norvc",
+                "li a0, 0",
+                "ebreak",
+                ".option pop",
+                out("a0") marker,
+            );
+        }
+        marker
+    }
+    #[cfg(not(target_arch = "riscv64"))]
+    fn breakpoint() -> usize {
+        panic!("not riscv64");
+    }
+
+    static VALUE: AtomicUsize = AtomicUsize::new(0);
+    let value_addr = VALUE.as_ptr().addr();
+
+    let mut child = ProcessBuilder::new()
+        .spawn_fn(|| {
+            user_syscall::ptrace_traceme().unwrap();
+            let marker = breakpoint();
+            let ok = marker == 7 && VALUE.load(Ordering::Relaxed) == 42;
+            process::exit(i32::from(!ok));
+        })
+        .unwrap();
+    let pid = child.id();
+
+    expect!(user_syscall::ptrace_wait(pid), Ok(PtraceStop::Breakpoint));
+    expect!(
+        user_syscall::ptrace_attach(pid),
+        Err(Ov6Error::NotPermitted)
+    );
+
+    let mut regs = user_syscall::ptrace_get_regs(pid).unwrap();
+    user_syscall::ptrace_peek(pid, regs.pc & !7).unwrap();
+    expect!(user_syscall::ptrace_peek(pid, value_addr), Ok(0));
+    user_syscall::ptrace_poke(pid, value_addr, 42).unwrap();
+    expect!(user_syscall::ptrace_peek(pid, value_addr), Ok(42));
+    expect!(
+        user_syscall::ptrace_peek(pid, value_addr + 1),
+        Err(Ov6Error::InvalidInput)
+    );
+
+    // skip the ebreak, and make it look like it set a0
+    regs.pc += 4;
+    regs.x[10] = 7;
+    user_syscall::ptrace_set_regs(pid, &regs).unwrap();
+
+    user_syscall::ptrace_step(pid).unwrap();
+    expect!(user_syscall::ptrace_wait(pid), Ok(PtraceStop::Step));
+    let stepped = user_syscall::ptrace_get_regs(pid).unwrap();
+    assert_ne!(stepped.pc, regs.pc);
+
+    user_syscall::ptrace_cont(pid).unwrap();
+    expect!(user_syscall::ptrace_wait(pid), Ok(PtraceStop::Exited));
+    expect!(
+        user_syscall::ptrace_cont(pid),
+        Err(Ov6Error::ProcessNotFound)
+    );
+    assert!(child.wait().unwrap().success());
+
+    // only children can be traced
+    expect!(
+        user_syscall::ptrace_attach(process::id()),
+        Err(Ov6Error::ProcessNotFound)
+    );
+}
@@ -32,11 +32,18 @@ macro_rules! slow {
 pub const TESTS: &[TestEntry] = &[
     quick!(memory::copy_u2k),
     quick!(memory::copy_k2u),
+    quick!(memory::user_layout),
     quick!(memory::rw_sbrk),
     quick!(memory::count_free_pages),
+    quick!(memory::heap_stats),
+    quick!(memory::zeroed_pages),
+    quick!(memory::alloc_fault),
     quick!(simple_fs::open_test),
     quick!(simple_fs::too_many_open_files),
-    quick!(simple_fs::too_many_open_files_in_system),
+    quick!(simple_fs::many_fds),
+    quick!(simple_fs::fd_flags),
+    quick!(simple_fs::close_on_exec),
+    quick!(simple_fs::many_open_files_in_system),
     quick!(simple_fs::write_test),
     quick!(simple_fs::write_big_test),
     quick!(simple_fs::create_test),
@@ -46,6 +53,20 @@ pub const TESTS: &[TestEntry] = &[
     quick!(simple_fork::pipe),
     quick!(simple_fork::broken_pipe),
     quick!(simple_fork::pipe_bad_fd),
+    quick!(simple_fork::pipe_large),
+    quick!(simple_fork::pipe_page_aligned),
+    quick!(simple_fork::pipe_partial_write),
+    quick!(simple_fork::pipe_atomic_write),
+    quick!(simple_fork::pipe_splice),
+    quick!(simple_fork::socket_pair),
+    quick!(simple_fork::local_socket),
+    quick!(simple_fork::msg_queue_priority),
+    quick!(simple_fork::msg_queue_blocking),
+    quick!(simple_fork::msg_queue_remove),
+    quick!(simple_fork::semaphore),
+    quick!(simple_fork::semaphore_mutex),
+    quick!(simple_fork::event_fd),
+    quick!(simple_fork::event_fd_semaphore),
     quick!(simple_fork::kill_status),
     quick!(simple_fork::kill_error),
     quick!(simple_fork::preempt),
@@ -64,10 +85,22 @@ pub const TESTS: &[TestEntry] = &[
     quick!(more_fs::inode_put_chdir),
     quick!(more_fs::shared_fd),
     quick!(more_fs::four_files),
+    quick!(more_fs::small_writers),
+    quick!(more_fs::read_while_append),
     quick!(more_fs::create_delete),
     quick!(more_fs::unlink_read),
     quick!(more_fs::link),
+    quick!(more_fs::link_errors),
+    quick!(more_fs::rmdir),
+    quick!(more_fs::direct_io),
+    quick!(more_fs::access_hint),
+    quick!(more_fs::vectored_io),
+    quick!(more_fs::positional_io),
+    quick!(more_fs::raw_disk),
     quick!(more_fs::concreate),
+    quick!(more_fs::concreate_dir),
+    quick!(more_fs::name_cache),
+    quick!(more_fs::interrupt_counts),
     quick!(more_fs::link_unlink),
     quick!(more_fs::subdir),
     quick!(more_fs::big_write),
@@ -76,6 +109,13 @@ pub const TESTS: &[TestEntry] = &[
     quick!(more_fs::rm_dot),
     quick!(more_fs::dir_file),
     quick!(more_fs::iref),
+    quick!(more_fs::chroot),
+    quick!(more_fs::canonicalize),
+    quick!(more_fs::getcwd),
+    quick!(more_fs::dir_relative),
+    quick!(more_fs::tmpfile),
+    quick!(more_fs::tmpfs),
+    quick!(more_fs::access),
     quick!(more_fork::fork),
     quick!(more_fork::sbrk_basic),
     quick!(more_fork::sbrk_much),
@@ -83,6 +123,7 @@ pub const TESTS: &[TestEntry] = &[
     quick!(more_fork::max_va_plus),
     quick!(more_fork::sbrk_fail),
     quick!(more_fork::sbrk_arg),
+    quick!(more_fork::fork_big_heap),
     quick!(misc::validate),
     quick!(misc::bss),
     quick!(misc::big_arg),
@@ -91,14 +132,28 @@ pub const TESTS: &[TestEntry] = &[
     quick!(misc::stack),
     quick!(misc::no_write),
     quick!(misc::pg_bug),
+    quick!(misc::bad_pointers),
     quick!(misc::sbrk_bugs),
     quick!(misc::sbrk_last),
     quick!(misc::sbrk8000),
     quick!(misc::bad_arg),
+    quick!(misc::exec_text_cache),
+    quick!(misc::drop_caps),
+    quick!(misc::syscall_filter),
+    quick!(misc::audit_log),
+    quick!(misc::io_limit),
+    quick!(misc::sleep_timers),
+    quick!(misc::hostname),
+    quick!(misc::kernel_symbols),
+    quick!(misc::load_average),
+    quick!(misc::ptrace),
     slow!(slow_fs::big_dir),
     slow!(slow_fs::many_writes),
     slow!(slow_fs::bad_write),
     slow!(slow_proc::execout),
+    slow!(slow_proc::oom_killer),
+    slow!(slow_proc::cpu_affinity),
     slow!(slow_fs::disk_full),
     slow!(slow_fs::out_of_inodes),
+    slow!(slow_fs::write_latency),
 ];
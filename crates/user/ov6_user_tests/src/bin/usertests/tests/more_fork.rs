@@ -4,14 +4,16 @@ use core::{ptr, time::Duration};
 use ov6_user_lib::{
     error::Ov6Error,
     fs::{self, File},
-    io::{Read as _, Write as _},
+    io::{self, Read as _, Write as _},
+    os::ov6::syscall,
     pipe,
     process::{self, ProcessBuilder, Stdio},
     thread,
+    time::Instant,
 };
 use ov6_user_tests::{expect, message};
 
-use crate::{KERN_BASE, MAX_VA, PAGE_SIZE};
+use crate::{KERN_BASE, PAGE_SIZE};
 
 /// test that fork fails gracefully
 /// the forktest binary also does this, but it runs out of proc entries first.
@@ -143,7 +145,7 @@ pub fn kern_mem() {
 
 /// user code should not be able to write to addresses above MAXVA.
 pub fn max_va_plus() {
-    let mut a = MAX_VA;
+    let mut a = syscall::user_layout().max_va;
     loop {
         let status = ProcessBuilder::new()
             .spawn_fn(|| {
@@ -231,3 +233,74 @@ pub fn sbrk_arg() {
         a.write(0);
     }
 }
+
+/// fork a process with a large heap many times, and check that the parent and
+/// the child do not see each other's writes.
+pub fn fork_big_heap() {
+    const SIZE: usize = 16 * 1024 * 1024;
+    const N: usize = 50;
+
+    let old_break = process::current_break();
+    let a = process::grow_break(SIZE).unwrap();
+    for i in (0..SIZE).step_by(PAGE_SIZE) {
+        unsafe {
+            a.add(i).write(1);
+        }
+    }
+
+    let start = Instant::now();
+    for _ in 0..N {
+        let status = ProcessBuilder::new()
+            .spawn_fn(|| process::exit(0))
+            .unwrap()
+            .wait()
+            .unwrap();
+        assert!(status.success());
+    }
+    let elapsed = start.elapsed() / u32::try_from(N).unwrap();
+    message!(
+        "fork with {}MB heap: {}.{:03}ms",
+        SIZE / 1024 / 1024,
+        elapsed.as_millis(),
+        elapsed.subsec_micros() % 1000
+    );
+
+    // writes by the child are not visible to the parent
+    let status = ProcessBuilder::new()
+        .spawn_fn(|| {
+            for i in (0..SIZE).step_by(PAGE_SIZE) {
+                unsafe {
+                    a.add(i).write(2);
+                }
+            }
+            process::exit(0);
+        })
+        .unwrap()
+        .wait()
+        .unwrap();
+    assert!(status.success());
+    for i in (0..SIZE).step_by(PAGE_SIZE) {
+        assert_eq!(unsafe { a.add(i).read() }, 1);
+    }
+
+    // writes by the parent are not visible to the child
+    let mut child = ProcessBuilder::new()
+        .stdin(Stdio::Pipe)
+        .spawn_fn(|| {
+            io::stdin().read_exact(&mut [0]).unwrap();
+            for i in (0..SIZE).step_by(PAGE_SIZE) {
+                assert_eq!(unsafe { a.add(i).read() }, 1);
+            }
+            process::exit(0);
+        })
+        .unwrap();
+    for i in (0..SIZE).step_by(PAGE_SIZE) {
+        unsafe {
+            a.add(i).write(3);
+        }
+    }
+    child.stdin.take().unwrap().write(b"x").unwrap();
+    assert!(child.wait().unwrap().success());
+
+    unsafe { process::shrink_break(process::current_break().addr() - old_break.addr()) }.unwrap();
+}
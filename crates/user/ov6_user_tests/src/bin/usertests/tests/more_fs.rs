@@ -1,13 +1,23 @@
+use alloc::vec::Vec;
 use core::time::Duration;
 
-use ov6_fs_types::FS_BLOCK_SIZE;
+use ov6_fs_types::{FS_BLOCK_SIZE, SuperBlock};
 use ov6_kernel_params::{MAX_OP_BLOCKS, NINODE};
+use ov6_syscall::{UserSlice, error::SyscallError, syscall};
 use ov6_user_lib::{
     env,
     error::Ov6Error,
-    fs::{self, File},
-    io::{Read as _, Write as _},
+    fs::{self, Dir, File},
+    io::{IoSlice, IoSliceMut, Read as _, Write as _},
+    os::{
+        fd::AsRawFd as _,
+        ov6::syscall::{
+            self as user_syscall, AccessHint, AccessMode, AtFlags, Capabilities,
+            ffi::SyscallExt as _,
+        },
+    },
     os_str::OsStr,
+    path::Path,
     process::{self, ProcessBuilder},
     thread,
 };
@@ -138,7 +148,7 @@ pub fn inode_put_open() {
         .unwrap();
 
     thread::sleep(Duration::from_millis(100));
-    fs::remove_file(OIDIR_PATH).unwrap();
+    fs::remove_dir(OIDIR_PATH).unwrap();
 
     let status = child.wait().unwrap();
     assert!(status.success());
@@ -150,7 +160,7 @@ pub fn inode_put_exit() {
         .spawn_fn(|| {
             fs::create_dir(IPUTDIR_PATH).unwrap();
             env::set_current_directory(IPUTDIR_PATH).unwrap();
-            fs::remove_file("../iputdir").unwrap();
+            fs::remove_dir("../iputdir").unwrap();
             process::exit(0);
         })
         .unwrap()
@@ -163,7 +173,7 @@ pub fn inode_put_exit() {
 pub fn inode_put_chdir() {
     fs::create_dir(IPUTDIR_PATH).unwrap();
     env::set_current_directory(IPUTDIR_PATH).unwrap();
-    fs::remove_file("../iputdir").unwrap();
+    fs::remove_dir("../iputdir").unwrap();
     env::set_current_directory(ROOT_DIR_PATH).unwrap();
 }
 
@@ -260,6 +270,88 @@ pub fn four_files() {
     }
 }
 
+/// many processes write one block at a time concurrently
+pub fn small_writers() {
+    const NCHILD: usize = 8;
+    const N: usize = 10;
+
+    let mut name = [b's', 0];
+    for pi in 0..NCHILD {
+        name[1] = b'0' + u8::try_from(pi).unwrap();
+        let path = OsStr::from_bytes(&name);
+        let _ = fs::remove_file(path);
+        ProcessBuilder::new()
+            .spawn_fn(|| {
+                let mut file = File::create(path).unwrap();
+                let buf = [name[1]; FS_BLOCK_SIZE];
+                for _ in 0..N {
+                    file.write_all(&buf).unwrap();
+                }
+                process::exit(0);
+            })
+            .unwrap();
+    }
+
+    for _ in 0..NCHILD {
+        let (_, status) = process::wait_any().unwrap();
+        assert!(status.success());
+    }
+
+    let mut buf = [0; FS_BLOCK_SIZE];
+    for pi in 0..NCHILD {
+        name[1] = b'0' + u8::try_from(pi).unwrap();
+        let path = OsStr::from_bytes(&name);
+        let mut file = File::open(path).unwrap();
+        for _ in 0..N {
+            file.read_exact(&mut buf).unwrap();
+            assert!(buf.iter().all(|&c| c == name[1]));
+        }
+        expect!(file.read(&mut buf), Ok(0));
+        drop(file);
+        fs::remove_file(path).unwrap();
+    }
+}
+
+/// a process reads the written part of a file while another appends to it
+pub fn read_while_append() {
+    const PATH: &str = "readappend";
+    const HEAD: usize = 4 * FS_BLOCK_SIZE;
+    const CHUNK: usize = 3 * FS_BLOCK_SIZE;
+    const N: usize = 20;
+
+    let buf = unsafe { (&raw mut BUF).as_mut() }.unwrap();
+
+    let _ = fs::remove_file(PATH);
+    let file = File::create(PATH).unwrap();
+    buf[..HEAD].fill(b'h');
+    file.write_all_at(&buf[..HEAD], 0).unwrap();
+
+    let mut child = ProcessBuilder::new()
+        .spawn_fn(|| {
+            buf[..CHUNK].fill(b'a');
+            for i in 0..N {
+                let off = u64::try_from(HEAD + i * CHUNK).unwrap();
+                file.write_all_at(&buf[..CHUNK], off).unwrap();
+            }
+            process::exit(0);
+        })
+        .unwrap();
+
+    for i in 0..N {
+        file.read_exact_at(&mut buf[..HEAD], 0).unwrap();
+        assert!(buf[..HEAD].iter().all(|&c| c == b'h'));
+        let off = u64::try_from(HEAD + i * CHUNK).unwrap();
+        let n = file.read_at(&mut buf[..CHUNK], off).unwrap();
+        assert!(buf[..n].iter().all(|&c| c == b'a'));
+    }
+
+    assert!(child.wait().unwrap().success());
+    let size = file.metadata().unwrap().size();
+    assert_eq!(size, u64::try_from(HEAD + N * CHUNK).unwrap());
+    drop(file);
+    fs::remove_file(PATH).unwrap();
+}
+
 /// four processes create and delete different files in same directory
 pub fn create_delete() {
     const N: usize = 20;
@@ -380,7 +472,409 @@ pub fn link() {
         Err(Ov6Error::FsEntryNotFound)
     );
 
-    expect!(fs::link(".", FILE1_PATH), Err(Ov6Error::NotADirectory));
+    expect!(fs::link(".", FILE1_PATH), Err(Ov6Error::NotPermitted));
+}
+
+/// test the error paths of link and unlink, and that the link counts are
+/// kept consistent.
+pub fn link_errors() {
+    const DIR_PATH: &str = "lerrd";
+    const FILE_PATH: &str = "lerrd/f";
+    const LINK_PATH: &str = "lerrd/l";
+
+    fs::create_dir(DIR_PATH).unwrap();
+    assert_eq!(fs::metadata(DIR_PATH).unwrap().nlink(), 1);
+    let _ = File::create(FILE_PATH).unwrap();
+
+    // hard links to directories are not permitted
+    expect!(fs::link(DIR_PATH, "lerrdl"), Err(Ov6Error::NotPermitted));
+    expect!(fs::link("lerrd/.", "lerrdl"), Err(Ov6Error::NotPermitted));
+    expect!(fs::link("lerrd/..", "lerrdl"), Err(Ov6Error::NotPermitted));
+    expect!(File::open("lerrdl"), Err(Ov6Error::FsEntryNotFound));
+    assert_eq!(fs::metadata(DIR_PATH).unwrap().nlink(), 1);
+
+    // failed links do not change the link count
+    expect!(fs::link(FILE_PATH, FILE_PATH), Err(Ov6Error::AlreadyExists));
+    expect!(
+        fs::link(FILE_PATH, "lerrx/l"),
+        Err(Ov6Error::FsEntryNotFound)
+    );
+    expect!(
+        fs::link(FILE_PATH, "lerrd/f/l"),
+        Err(Ov6Error::NotADirectory)
+    );
+    assert_eq!(fs::metadata(FILE_PATH).unwrap().nlink(), 1);
+
+    fs::link(FILE_PATH, LINK_PATH).unwrap();
+    assert_eq!(fs::metadata(FILE_PATH).unwrap().nlink(), 2);
+    assert_eq!(
+        fs::metadata(LINK_PATH).unwrap().ino(),
+        fs::metadata(FILE_PATH).unwrap().ino()
+    );
+
+    // a subdirectory increments the link count of its parent by ".."
+    fs::create_dir("lerrd/sub").unwrap();
+    assert_eq!(fs::metadata(DIR_PATH).unwrap().nlink(), 2);
+
+    // non-empty directories cannot be unlinked
+    expect!(fs::remove_dir(DIR_PATH), Err(Ov6Error::DirectoryNotEmpty));
+    let _ = File::create("lerrd/sub/f").unwrap();
+    expect!(
+        fs::remove_dir("lerrd/sub"),
+        Err(Ov6Error::DirectoryNotEmpty)
+    );
+    fs::remove_file("lerrd/sub/f").unwrap();
+    fs::remove_dir("lerrd/sub").unwrap();
+    assert_eq!(fs::metadata(DIR_PATH).unwrap().nlink(), 1);
+
+    expect!(fs::remove_file("lerrd/."), Err(Ov6Error::InvalidInput));
+    expect!(fs::remove_file("lerrd/.."), Err(Ov6Error::InvalidInput));
+    expect!(fs::remove_file("lerrd/x"), Err(Ov6Error::FsEntryNotFound));
+
+    fs::remove_file(FILE_PATH).unwrap();
+    assert_eq!(fs::metadata(LINK_PATH).unwrap().nlink(), 1);
+    fs::remove_file(LINK_PATH).unwrap();
+    expect!(fs::remove_file(LINK_PATH), Err(Ov6Error::FsEntryNotFound));
+    fs::remove_dir(DIR_PATH).unwrap();
+}
+
+/// test that directories are removed only by rmdir, and only when empty.
+pub fn rmdir() {
+    const DIR_PATH: &str = "rmdird";
+    const FILE_PATH: &str = "rmdird/f";
+
+    fs::create_dir(DIR_PATH).unwrap();
+    let _ = File::create(FILE_PATH).unwrap();
+
+    expect!(fs::remove_file(DIR_PATH), Err(Ov6Error::IsADirectory));
+    expect!(fs::remove_dir(FILE_PATH), Err(Ov6Error::NotADirectory));
+    expect!(fs::remove_dir(DIR_PATH), Err(Ov6Error::DirectoryNotEmpty));
+    expect!(fs::remove_dir("rmdird/x"), Err(Ov6Error::FsEntryNotFound));
+    expect!(fs::remove_dir("rmdird/."), Err(Ov6Error::InvalidInput));
+    expect!(fs::remove_dir("/"), Err(Ov6Error::ResourceBusy));
+
+    // a directory containing only an empty subdirectory is not empty
+    fs::remove_file(FILE_PATH).unwrap();
+    fs::create_dir("rmdird/sub").unwrap();
+    expect!(fs::remove_dir(DIR_PATH), Err(Ov6Error::DirectoryNotEmpty));
+    fs::remove_dir("rmdird/sub").unwrap();
+
+    fs::remove_dir(DIR_PATH).unwrap();
+    expect!(fs::remove_dir(DIR_PATH), Err(Ov6Error::FsEntryNotFound));
+}
+
+/// test reading and writing with the `direct` open option
+pub fn direct_io() {
+    const FILE_PATH: &str = "directio";
+    const N: usize = 4;
+    const TAIL: usize = 100;
+    const SIZE: usize = N * FS_BLOCK_SIZE + TAIL;
+
+    #[repr(align(1024))]
+    struct AlignedBuf([u8; SIZE + 1]);
+    static mut ALIGNED_BUF: AlignedBuf = AlignedBuf([0; SIZE + 1]);
+
+    let buf = &mut unsafe { (&raw mut ALIGNED_BUF).as_mut() }.unwrap().0;
+    let is_valid = |data: &[u8], overwritten: bool| {
+        data.iter().enumerate().all(|(i, &b)| {
+            let expected = match i / FS_BLOCK_SIZE {
+                1 if overwritten => 0xaa,
+                n if n < N => u8::try_from(n).unwrap(),
+                _ => 0xff,
+            };
+            b == expected
+        })
+    };
+
+    let _ = fs::remove_file(FILE_PATH);
+    let mut file = File::create(FILE_PATH).unwrap();
+    for i in 0..to_u8!(N) {
+        buf[..FS_BLOCK_SIZE].fill(i);
+        file.write_all(&buf[..FS_BLOCK_SIZE]).unwrap();
+    }
+    buf[..TAIL].fill(0xff);
+    file.write_all(&buf[..TAIL]).unwrap();
+    drop(file);
+
+    let mut file = File::open(FILE_PATH).unwrap();
+    file.read_exact(&mut buf[..SIZE]).unwrap();
+    assert!(is_valid(&buf[..SIZE], false));
+    drop(file);
+
+    // overwrite the second block directly
+    let mut file = File::options()
+        .read(true)
+        .write(true)
+        .direct(true)
+        .open(FILE_PATH)
+        .unwrap();
+    file.read_exact(&mut buf[..FS_BLOCK_SIZE]).unwrap();
+    assert!(buf[..FS_BLOCK_SIZE].iter().all(|&b| b == 0));
+    buf[..FS_BLOCK_SIZE].fill(0xaa);
+    file.write_all(&buf[..FS_BLOCK_SIZE]).unwrap();
+    drop(file);
+
+    // reads through the caches see the block written directly
+    let mut file = File::open(FILE_PATH).unwrap();
+    buf.fill(0);
+    file.read_exact(&mut buf[..SIZE]).unwrap();
+    assert!(is_valid(&buf[..SIZE], true));
+    drop(file);
+
+    // aligned and unaligned direct reads
+    for start in [0, 1] {
+        let mut file = File::options()
+            .read(true)
+            .direct(true)
+            .open(FILE_PATH)
+            .unwrap();
+        buf.fill(0);
+        file.read_exact(&mut buf[start..][..SIZE]).unwrap();
+        assert!(is_valid(&buf[start..][..SIZE], true));
+        drop(file);
+    }
+
+    fs::remove_file(FILE_PATH).unwrap();
+}
+
+/// test the access hints, which change how reads use the page cache
+pub fn access_hint() {
+    const FILE_PATH: &str = "accesshint";
+    const SIZE: usize = 8 * 4096;
+
+    let pattern = |off: usize| -> [u8; 512] {
+        core::array::from_fn(|i| u8::try_from((off + i) % 251).unwrap())
+    };
+    let page_cache = || user_syscall::get_system_info().unwrap().page_cache;
+    let read_all = |hint| {
+        let mut file = File::open(FILE_PATH).unwrap();
+        user_syscall::set_access_hint(file.as_raw_fd(), hint).unwrap();
+        assert_eq!(user_syscall::access_hint(file.as_raw_fd()).unwrap(), hint);
+        let mut buf = [0; 512];
+        for off in (0..SIZE).step_by(buf.len()) {
+            file.read_exact(&mut buf).unwrap();
+            assert_eq!(buf, pattern(off));
+        }
+        expect!(file.read(&mut buf), Ok(0));
+    };
+
+    let _ = fs::remove_file(FILE_PATH);
+    let mut file = File::create(FILE_PATH).unwrap();
+    for off in (0..SIZE).step_by(512) {
+        file.write_all(&pattern(off)).unwrap();
+    }
+    drop(file);
+
+    // random reads do not fill the cache
+    let before = page_cache();
+    read_all(AccessHint::Random);
+    assert!(page_cache().bypassed > before.bypassed);
+
+    // sequential reads fill the following pages in advance
+    let before = page_cache();
+    read_all(AccessHint::Sequential);
+    assert!(page_cache().readahead > before.readahead);
+
+    // pages read once are dropped from the cache
+    let before = page_cache();
+    read_all(AccessHint::NoReuse);
+    assert!(page_cache().dropped > before.dropped);
+
+    // the hint of the file is shared by the file descriptors of the file
+    let file1 = File::open(FILE_PATH).unwrap();
+    let file2 = File::open(FILE_PATH).unwrap();
+    expect!(
+        user_syscall::file_access_hint(file2.as_raw_fd()),
+        Ok(AccessHint::Normal)
+    );
+    user_syscall::set_file_access_hint(file1.as_raw_fd(), AccessHint::Random).unwrap();
+    expect!(
+        user_syscall::file_access_hint(file2.as_raw_fd()),
+        Ok(AccessHint::Random)
+    );
+    expect!(
+        user_syscall::access_hint(file2.as_raw_fd()),
+        Ok(AccessHint::Normal)
+    );
+    drop(file1);
+    drop(file2);
+
+    let (rx, _tx) = user_syscall::pipe().unwrap();
+    expect!(
+        user_syscall::set_access_hint(rx.as_raw_fd(), AccessHint::Sequential),
+        Err(Ov6Error::NotSeekable)
+    );
+
+    fs::remove_file(FILE_PATH).unwrap();
+}
+
+/// test `readv`/`writev`
+pub fn vectored_io() {
+    const FILE_PATH: &str = "vectoredio";
+
+    let _ = fs::remove_file(FILE_PATH);
+    let mut file = File::create(FILE_PATH).unwrap();
+    let bufs = [
+        IoSlice::new(b"hello"),
+        IoSlice::new(b""),
+        IoSlice::new(b", "),
+        IoSlice::new(b"world"),
+    ];
+    expect!(file.write_vectored(&bufs), Ok(12));
+    drop(file);
+
+    let mut file = File::open(FILE_PATH).unwrap();
+    let mut a = [0; 4];
+    let mut b = [0; 0];
+    let mut c = [0; 16];
+    let mut bufs = [
+        IoSliceMut::new(&mut a),
+        IoSliceMut::new(&mut b),
+        IoSliceMut::new(&mut c),
+    ];
+    expect!(file.read_vectored(&mut bufs), Ok(12));
+    expect!(file.read_vectored(&mut bufs), Ok(0));
+    drop(file);
+    assert_eq!(&a, b"hell");
+    assert_eq!(&c[..8], b"o, world");
+
+    // a bad buffer after a successful transfer ends the call
+    let file = File::options().write(true).open(FILE_PATH).unwrap();
+    let bad = unsafe { UserSlice::from_raw_parts(0x8000_0000, 10) };
+    let iov = [UserSlice::new(b"HELLO".as_slice()), bad];
+    expect!(
+        syscall::Writev::call((file.as_raw_fd(), UserSlice::new(iov.as_slice()))),
+        Ok(5)
+    );
+    expect!(
+        syscall::Writev::call((file.as_raw_fd(), UserSlice::new(&iov[1..]))),
+        Err(SyscallError::BadAddress)
+    );
+    drop(file);
+
+    fs::remove_file(FILE_PATH).unwrap();
+}
+
+/// test `pread`/`pwrite`, which do not use the file offset.
+pub fn positional_io() {
+    const FILE_PATH: &str = "positionalio";
+    const N: usize = 100;
+    const SIZE: usize = 10;
+
+    let _ = fs::remove_file(FILE_PATH);
+    let mut file = File::options()
+        .read(true)
+        .write(true)
+        .create(true)
+        .open(FILE_PATH)
+        .unwrap();
+    file.write_all(b"0123456789").unwrap();
+
+    let mut buf = [0; 4];
+    expect!(file.read_at(&mut buf, 3), Ok(4));
+    assert_eq!(&buf, b"3456");
+    expect!(file.read_at(&mut buf, 8), Ok(2));
+    expect!(file.read_at(&mut buf, 10), Ok(0));
+    expect!(file.write_at(b"ab", 2), Ok(2));
+    // the file offset is still at the end
+    expect!(file.write(b"!"), Ok(1));
+    expect!(file.read_at(&mut buf, 0), Ok(4));
+    assert_eq!(&buf, b"01ab");
+    expect!(file.read_at(&mut buf, 8), Ok(3));
+    assert_eq!(&buf[..3], b"89!");
+
+    // processes sharing the file descriptor write to their own regions
+    let handle = process::fork().unwrap();
+    let (fill, base) = if handle.is_child() {
+        (b'c', 0)
+    } else {
+        (b'p', N * SIZE)
+    };
+    for i in 0..N {
+        let off = u64::try_from(base + i * SIZE).unwrap();
+        expect!(file.write_at(&[fill; SIZE], off), Ok(SIZE));
+    }
+    assert!(handle.join().unwrap().success());
+
+    let mut buf = [0; SIZE];
+    for i in 0..2 * N {
+        let off = u64::try_from(i * SIZE).unwrap();
+        expect!(file.read_at(&mut buf, off), Ok(SIZE));
+        let fill = if i < N { b'c' } else { b'p' };
+        assert!(buf.iter().all(|&c| c == fill));
+    }
+    drop(file);
+
+    let (rx, _tx) = user_syscall::pipe().unwrap();
+    expect!(
+        user_syscall::pread(rx.as_raw_fd(), &mut buf, 0),
+        Err(Ov6Error::NotSeekable)
+    );
+
+    fs::remove_file(FILE_PATH).unwrap();
+}
+
+/// test raw access to the disk through a block device file.
+pub fn raw_disk() {
+    const DISK_PATH: &str = "rawdisk";
+    const DISK: u32 = 2;
+
+    let _ = fs::remove_file(DISK_PATH);
+    fs::mknod(DISK_PATH, DISK, 0).unwrap();
+
+    // the super block is in the block 1
+    let mut file = File::options()
+        .read(true)
+        .write(true)
+        .open(DISK_PATH)
+        .unwrap();
+    let mut boot = [0; FS_BLOCK_SIZE];
+    let mut sb = [0; FS_BLOCK_SIZE];
+    expect!(file.read(&mut boot), Ok(FS_BLOCK_SIZE));
+    expect!(file.read(&mut sb), Ok(FS_BLOCK_SIZE));
+    assert_eq!(sb[..4], SuperBlock::FS_MAGIC.to_le_bytes());
+    let mut buf = [0; FS_BLOCK_SIZE];
+    expect!(
+        file.read_at(&mut buf, u64::try_from(FS_BLOCK_SIZE).unwrap()),
+        Ok(FS_BLOCK_SIZE)
+    );
+    assert_eq!(buf, sb);
+
+    // transfers must be block-aligned
+    expect!(file.read_at(&mut buf, 1), Err(Ov6Error::InvalidInput));
+    expect!(
+        file.read_at(&mut buf[..100], 0),
+        Err(Ov6Error::InvalidInput)
+    );
+    expect!(file.write_at(&boot[..100], 0), Err(Ov6Error::InvalidInput));
+    // reading past the end of the disk returns 0
+    expect!(file.read_at(&mut buf, 1 << 40), Ok(0));
+
+    // the boot block is unused, so rewriting it is harmless
+    expect!(file.write_at(&boot, 0), Ok(FS_BLOCK_SIZE));
+    expect!(file.read_at(&mut buf, 0), Ok(FS_BLOCK_SIZE));
+    assert_eq!(buf, boot);
+    drop(file);
+
+    // writing to the disk requires the RAW_IO capability
+    let status = ProcessBuilder::new()
+        .spawn_fn(|| {
+            user_syscall::drop_caps(Capabilities::RAW_IO).unwrap();
+            expect!(
+                File::options().write(true).open(DISK_PATH),
+                Err(Ov6Error::NotPermitted)
+            );
+            let file = File::open(DISK_PATH).unwrap();
+            let mut buf = [0; FS_BLOCK_SIZE];
+            expect!(file.read_at(&mut buf, 0), Ok(FS_BLOCK_SIZE));
+            process::exit(0);
+        })
+        .unwrap()
+        .wait()
+        .unwrap();
+    assert!(status.success());
+
+    fs::remove_file(DISK_PATH).unwrap();
 }
 
 /// test concurrent create/link/unlink of the same file
@@ -442,6 +936,156 @@ pub fn concreate() {
     }
 }
 
+/// many processes create files in the same directory at once,
+/// some with the same names. each name must be added exactly once.
+pub fn concreate_dir() {
+    const NCHILD: usize = 6;
+    const NSHARED: usize = 10;
+    const NUNIQUE: usize = 10;
+    const DIR: &str = "cdir";
+
+    let _ = fs::create_dir(DIR);
+
+    for pi in 0..NCHILD {
+        ProcessBuilder::new()
+            .spawn_fn(|| {
+                let mut path = *b"cdir/xx";
+                for i in 0..usize::max(NSHARED, NUNIQUE) {
+                    if i < NSHARED {
+                        path[5] = b's';
+                        path[6] = b'0' + u8::try_from(i).unwrap();
+                        File::options()
+                            .write(true)
+                            .create(true)
+                            .open(OsStr::from_bytes(&path))
+                            .unwrap();
+                    }
+                    if i < NUNIQUE {
+                        path[5] = b'a' + u8::try_from(pi).unwrap();
+                        path[6] = b'0' + u8::try_from(i).unwrap();
+                        File::create(OsStr::from_bytes(&path)).unwrap();
+                    }
+                }
+                process::exit(0);
+            })
+            .unwrap();
+    }
+
+    for _ in 0..NCHILD {
+        let (_, status) = process::wait_any().unwrap();
+        assert!(status.success());
+    }
+
+    let mut shared = [0; NSHARED];
+    let mut unique = [[0; NUNIQUE]; NCHILD];
+    for entry in fs::read_dir(DIR).unwrap() {
+        let entry = entry.unwrap();
+        let name = entry.name().as_bytes();
+        if name == b"." || name == b".." {
+            continue;
+        }
+        assert_eq!(name.len(), 2, "unexpected entry {}", entry.name().display());
+        let i = usize::from(name[1] - b'0');
+        if name[0] == b's' {
+            shared[i] += 1;
+        } else {
+            unique[usize::from(name[0] - b'a')][i] += 1;
+        }
+    }
+    assert!(shared.iter().all(|&n| n == 1), "shared entries: {shared:?}");
+    assert!(
+        unique.iter().flatten().all(|&n| n == 1),
+        "unique entries: {unique:?}"
+    );
+
+    let mut path = *b"cdir/xx";
+    for i in 0..NSHARED {
+        path[5] = b's';
+        path[6] = b'0' + u8::try_from(i).unwrap();
+        fs::remove_file(OsStr::from_bytes(&path)).unwrap();
+    }
+    for pi in 0..NCHILD {
+        for i in 0..NUNIQUE {
+            path[5] = b'a' + u8::try_from(pi).unwrap();
+            path[6] = b'0' + u8::try_from(i).unwrap();
+            fs::remove_file(OsStr::from_bytes(&path)).unwrap();
+        }
+    }
+    fs::remove_dir(DIR).unwrap();
+}
+
+/// repeated lookups of the same path are served from the name cache
+/// without reading the disk, and the cache follows creates and unlinks.
+pub fn name_cache() {
+    const FILE: &str = "ncdir/a/b/c/file";
+    const MISSING: &str = "ncdir/a/b/c/missing";
+
+    let sysinfo = || user_syscall::get_system_info().unwrap();
+
+    fs::create_dir("ncdir").unwrap();
+    fs::create_dir("ncdir/a").unwrap();
+    fs::create_dir("ncdir/a/b").unwrap();
+    fs::create_dir("ncdir/a/b/c").unwrap();
+    File::create(FILE).unwrap();
+
+    // fill the cache
+    fs::metadata(FILE).unwrap();
+    let before = sysinfo();
+    for _ in 0..10 {
+        fs::metadata(FILE).unwrap();
+    }
+    let after = sysinfo();
+    assert!(after.name_cache.hits >= before.name_cache.hits + 10 * 5);
+    assert_eq!(after.name_cache.misses, before.name_cache.misses);
+    assert_eq!(after.disk.reads, before.disk.reads);
+
+    // names that do not exist are cached too
+    expect!(fs::metadata(MISSING).err(), Some(Ov6Error::FsEntryNotFound));
+    let before = sysinfo();
+    expect!(fs::metadata(MISSING).err(), Some(Ov6Error::FsEntryNotFound));
+    let after = sysinfo();
+    assert!(after.name_cache.negative_hits > before.name_cache.negative_hits);
+    assert_eq!(after.disk.reads, before.disk.reads);
+
+    // creating and removing a name invalidates its entry
+    File::create(MISSING).unwrap();
+    fs::metadata(MISSING).unwrap();
+    fs::remove_file(MISSING).unwrap();
+    expect!(fs::metadata(MISSING).err(), Some(Ov6Error::FsEntryNotFound));
+    fs::remove_file(FILE).unwrap();
+    expect!(fs::metadata(FILE).err(), Some(Ov6Error::FsEntryNotFound));
+    assert!(sysinfo().name_cache.invalidations > after.name_cache.invalidations);
+
+    fs::remove_dir("ncdir/a/b/c").unwrap();
+    fs::remove_dir("ncdir/a/b").unwrap();
+    fs::remove_dir("ncdir/a").unwrap();
+    fs::remove_dir("ncdir").unwrap();
+}
+
+/// disk requests complete by interrupt, and every interrupt is counted
+/// for its IRQ.
+pub fn interrupt_counts() {
+    const FILE: &str = "irqfile";
+
+    let sysinfo = || user_syscall::get_system_info().unwrap();
+    let total = |info: &user_syscall::SystemInfo| info.interrupts.counts.iter().sum::<usize>();
+
+    let before = sysinfo();
+    let mut file = File::create(FILE).unwrap();
+    file.write_all(&[0xa5; 4096]).unwrap();
+    drop(file);
+    user_syscall::sync().unwrap();
+    let after = sysinfo();
+
+    let requests =
+        (after.disk.reads + after.disk.writes) - (before.disk.reads + before.disk.writes);
+    assert!(requests > 0);
+    assert!(total(&after) > total(&before));
+    assert!(after.interrupts.spurious >= before.interrupts.spurious);
+
+    fs::remove_file(FILE).unwrap();
+}
+
 /// another concurrent link/unlink/create test,
 /// to look for deadlocks.
 pub fn link_unlink() {
@@ -477,7 +1121,7 @@ pub fn subdir() {
     file.write_all(b"ff").unwrap();
     drop(file);
 
-    expect!(fs::remove_file("dd"), Err(Ov6Error::DirectoryNotEmpty));
+    expect!(fs::remove_dir("dd"), Err(Ov6Error::DirectoryNotEmpty));
 
     fs::create_dir("/dd/dd").unwrap();
 
@@ -549,9 +1193,9 @@ pub fn subdir() {
 
     fs::remove_file("dd/dd/ffff").unwrap();
     fs::remove_file("dd/ff").unwrap();
-    expect!(fs::remove_file("dd"), Err(Ov6Error::DirectoryNotEmpty));
-    fs::remove_file("dd/dd").unwrap();
-    fs::remove_file("dd").unwrap();
+    expect!(fs::remove_dir("dd"), Err(Ov6Error::DirectoryNotEmpty));
+    fs::remove_dir("dd/dd").unwrap();
+    fs::remove_dir("dd").unwrap();
 }
 
 /// test writes that are larger than the log.
@@ -621,24 +1265,24 @@ pub fn fourteen() {
     expect!(fs::create_dir(N15_14), Err(Ov6Error::AlreadyExists));
 
     // clean up
-    expect!(fs::remove_file(N15_14), Err(Ov6Error::DirectoryNotEmpty));
-    expect!(fs::remove_file(N14_14), Err(Ov6Error::DirectoryNotEmpty));
+    expect!(fs::remove_dir(N15_14), Err(Ov6Error::DirectoryNotEmpty));
+    expect!(fs::remove_dir(N14_14), Err(Ov6Error::DirectoryNotEmpty));
     fs::remove_file(N14_14_14).unwrap();
     expect!(fs::remove_file(N15_15_15), Err(Ov6Error::FsEntryNotFound));
-    fs::remove_file(N14_15).unwrap();
-    fs::remove_file(N14).unwrap();
+    fs::remove_dir(N14_15).unwrap();
+    fs::remove_dir(N14).unwrap();
 }
 
 pub fn rm_dot() {
     fs::create_dir("dots").unwrap();
     env::set_current_directory("dots").unwrap();
-    expect!(fs::remove_file("."), Err(Ov6Error::InvalidInput));
-    expect!(fs::remove_file(".."), Err(Ov6Error::InvalidInput));
+    expect!(fs::remove_dir("."), Err(Ov6Error::InvalidInput));
+    expect!(fs::remove_dir(".."), Err(Ov6Error::InvalidInput));
 
     env::set_current_directory("/").unwrap();
-    expect!(fs::remove_file("dots/."), Err(Ov6Error::InvalidInput));
-    expect!(fs::remove_file("dots/.."), Err(Ov6Error::InvalidInput));
-    fs::remove_file("dots").unwrap();
+    expect!(fs::remove_dir("dots/."), Err(Ov6Error::InvalidInput));
+    expect!(fs::remove_dir("dots/.."), Err(Ov6Error::InvalidInput));
+    fs::remove_dir("dots").unwrap();
 }
 
 pub fn dir_file() {
@@ -683,8 +1327,414 @@ pub fn iref() {
     // clean up
     for _ in 0..=NINODE {
         env::set_current_directory("..").unwrap();
-        fs::remove_file(DIR_PATH).unwrap();
+        fs::remove_dir(DIR_PATH).unwrap();
     }
 
     env::set_current_directory("/").unwrap();
 }
+
+/// can a chrooted process escape its root directory via `..`?
+pub fn chroot() {
+    const DIR_PATH: &str = "chrootd";
+    const MARKER_PATH: &str = "chrootd/marker";
+
+    fs::create_dir(DIR_PATH).unwrap();
+    drop(File::create(MARKER_PATH).unwrap());
+
+    let status = ProcessBuilder::new()
+        .spawn_fn(|| {
+            user_syscall::chroot(Path::new(DIR_PATH)).unwrap();
+            env::set_current_directory("/").unwrap();
+            let _ = File::open("/marker").unwrap();
+
+            for path in ["..", "../..", "/..", "/../.."] {
+                env::set_current_directory(path).unwrap();
+                let _ = File::open("marker").unwrap();
+                expect!(File::open(README_PATH), Err(Ov6Error::FsEntryNotFound));
+            }
+            expect!(File::open("/../../README"), Err(Ov6Error::FsEntryNotFound));
+
+            // the root directory is inherited across fork
+            let status = ProcessBuilder::new()
+                .spawn_fn(|| {
+                    let _ = File::open("/../marker").unwrap();
+                    expect!(File::open("/README"), Err(Ov6Error::FsEntryNotFound));
+                    process::exit(0);
+                })
+                .unwrap()
+                .wait()
+                .unwrap();
+            assert!(status.success());
+
+            process::exit(0);
+        })
+        .unwrap()
+        .wait()
+        .unwrap();
+    assert!(status.success());
+
+    // the parent's root directory is unchanged
+    let _ = File::open(README_PATH).unwrap();
+
+    fs::remove_file(MARKER_PATH).unwrap();
+    fs::remove_dir(DIR_PATH).unwrap();
+}
+
+pub fn canonicalize() {
+    const DIR_PATH: &str = "canond";
+    const SUBDIR_PATH: &str = "canond/sub";
+
+    fs::create_dir(DIR_PATH).unwrap();
+    fs::create_dir(SUBDIR_PATH).unwrap();
+
+    let status = ProcessBuilder::new()
+        .spawn_fn(|| {
+            let base = env::current_directory().unwrap();
+            assert!(base.is_absolute());
+
+            env::set_current_directory(SUBDIR_PATH).unwrap();
+            let cwd = env::current_directory().unwrap();
+            assert_eq!(cwd, base.join(SUBDIR_PATH));
+
+            assert_eq!(fs::canonicalize(".").unwrap(), cwd);
+            assert_eq!(fs::canonicalize("../sub/./").unwrap(), cwd);
+            assert_eq!(fs::canonicalize("..").unwrap(), base.join(DIR_PATH));
+            assert_eq!(
+                fs::canonicalize("/../..").unwrap(),
+                Path::new("/").to_path_buf()
+            );
+            expect!(
+                fs::canonicalize("nonexistent"),
+                Err(Ov6Error::FsEntryNotFound)
+            );
+
+            process::exit(0);
+        })
+        .unwrap()
+        .wait()
+        .unwrap();
+    assert!(status.success());
+
+    fs::remove_dir(SUBDIR_PATH).unwrap();
+    fs::remove_dir(DIR_PATH).unwrap();
+}
+
+/// the path of the current directory follows each change of directory, and is
+/// seen from the root directory of the process.
+pub fn getcwd() {
+    const DIR_PATH: &str = "/getcwdd";
+    const SUBDIR_PATH: &str = "/getcwdd/sub";
+
+    fs::create_dir(DIR_PATH).unwrap();
+    fs::create_dir(SUBDIR_PATH).unwrap();
+
+    let status = ProcessBuilder::new()
+        .spawn_fn(|| {
+            let cwd = || env::current_directory().unwrap();
+
+            env::set_current_directory("/").unwrap();
+            assert_eq!(cwd(), Path::new("/"));
+            env::set_current_directory("getcwdd/./sub//").unwrap();
+            assert_eq!(cwd(), Path::new(SUBDIR_PATH));
+            env::set_current_directory("../sub/..").unwrap();
+            assert_eq!(cwd(), Path::new(DIR_PATH));
+            env::set_current_directory("/../..").unwrap();
+            assert_eq!(cwd(), Path::new("/"));
+
+            // a failed change keeps the path
+            expect!(
+                env::set_current_directory("getcwdd/nonexistent"),
+                Err(Ov6Error::FsEntryNotFound)
+            );
+            assert_eq!(cwd(), Path::new("/"));
+
+            // nothing is copied to a short buffer
+            env::set_current_directory(SUBDIR_PATH).unwrap();
+            let mut buf = [0; 4];
+            assert_eq!(user_syscall::getcwd(&mut buf).unwrap(), SUBDIR_PATH.len());
+            assert_eq!(buf, [0; 4]);
+
+            // the path is inherited across fork
+            let status = ProcessBuilder::new()
+                .spawn_fn(move || {
+                    assert_eq!(cwd(), Path::new(SUBDIR_PATH));
+                    process::exit(0);
+                })
+                .unwrap()
+                .wait()
+                .unwrap();
+            assert!(status.success());
+
+            user_syscall::chroot(Path::new(DIR_PATH)).unwrap();
+            assert_eq!(cwd(), Path::new("/sub"));
+
+            // the current directory is outside the new root
+            env::set_current_directory("/").unwrap();
+            user_syscall::chroot(Path::new("sub")).unwrap();
+            expect!(env::current_directory(), Err(Ov6Error::FsEntryNotFound));
+            env::set_current_directory("/").unwrap();
+            assert_eq!(cwd(), Path::new("/"));
+
+            process::exit(0);
+        })
+        .unwrap()
+        .wait()
+        .unwrap();
+    assert!(status.success());
+
+    fs::remove_dir(SUBDIR_PATH).unwrap();
+    fs::remove_dir(DIR_PATH).unwrap();
+}
+
+/// paths given to the `*at` syscalls are resolved from the directory handle,
+/// independently of the current directory.
+pub fn dir_relative() {
+    const DIR_PATH: &str = "/atdir";
+
+    fs::create_dir(DIR_PATH).unwrap();
+    let dir = Dir::open(DIR_PATH).unwrap();
+
+    dir.create_dir("sub").unwrap();
+    dir.create_file("sub/file")
+        .unwrap()
+        .write_all(b"data")
+        .unwrap();
+    assert!(dir.metadata("sub").unwrap().is_dir());
+    assert_eq!(dir.metadata("sub/file").unwrap().size(), 4);
+    assert!(fs::metadata("/atdir/sub/file").unwrap().is_file());
+
+    // absolute paths ignore the directory
+    assert_eq!(dir.metadata("/").unwrap().ino(), 1);
+
+    // AT_FDCWD resolves from the current directory
+    let stat = user_syscall::fstatat(user_syscall::AT_FDCWD, Path::new(DIR_PATH)).unwrap();
+    assert_eq!(stat.ino, dir.dir_metadata().unwrap().ino());
+
+    let names = dir
+        .read_dir()
+        .unwrap()
+        .map(|ent| ent.unwrap().name().to_os_string())
+        .collect::<Vec<_>>();
+    assert_eq!(names, [OsStr::new("sub")]);
+
+    let status = ProcessBuilder::new()
+        .spawn_fn(|| {
+            let sub = Dir::open(DIR_PATH).unwrap().open_dir("sub").unwrap();
+            env::set_current_directory("/").unwrap();
+            let mut buf = [0; 4];
+            sub.open_file("file").unwrap().read_exact(&mut buf).unwrap();
+            assert_eq!(&buf, b"data");
+
+            sub.set_current().unwrap();
+            assert_eq!(env::current_directory().unwrap(), Path::new("/atdir/sub"));
+            assert!(fs::metadata("file").unwrap().is_file());
+
+            // a file cannot be a base directory
+            let file = sub.open_file("file").unwrap();
+            expect!(
+                user_syscall::fstatat(file.as_raw_fd(), Path::new("x")),
+                Err(Ov6Error::NotADirectory)
+            );
+            expect!(
+                user_syscall::fchdir(file.as_raw_fd()),
+                Err(Ov6Error::NotADirectory)
+            );
+
+            // a removed directory has no path
+            sub.create_dir("gone").unwrap();
+            let gone = sub.open_dir("gone").unwrap();
+            sub.remove_dir("gone").unwrap();
+            gone.set_current().unwrap();
+            expect!(env::current_directory(), Err(Ov6Error::FsEntryNotFound));
+            process::exit(0);
+        })
+        .unwrap()
+        .wait()
+        .unwrap();
+    assert!(status.success());
+
+    expect!(dir.remove_dir("sub"), Err(Ov6Error::DirectoryNotEmpty));
+    expect!(dir.remove_file("sub"), Err(Ov6Error::IsADirectory));
+    dir.remove_file("sub/file").unwrap();
+    dir.remove_dir("sub").unwrap();
+    expect!(dir.metadata("sub").err(), Some(Ov6Error::FsEntryNotFound));
+    drop(dir);
+    fs::remove_dir(DIR_PATH).unwrap();
+}
+
+/// an unnamed file is freed when closed, unless it is linked into a directory.
+pub fn tmpfile() {
+    const LINKED_PATH: &str = "tmpfile-linked";
+    const DATA: &[u8] = b"tmpfile data";
+
+    let free_inodes = fs::fs_stats(".").unwrap().free_inodes();
+    let mut file = File::options()
+        .read(true)
+        .write(true)
+        .tmpfile(true)
+        .open(".")
+        .unwrap();
+    file.write_all(DATA).unwrap();
+    assert_eq!(file.metadata().unwrap().nlink(), 0);
+    assert_eq!(fs::fs_stats(".").unwrap().free_inodes(), free_inodes - 1);
+    drop(file);
+    assert_eq!(fs::fs_stats(".").unwrap().free_inodes(), free_inodes);
+
+    let mut file = File::options().write(true).tmpfile(true).open(".").unwrap();
+    file.write_all(DATA).unwrap();
+    file.link(LINKED_PATH).unwrap();
+    assert_eq!(file.metadata().unwrap().nlink(), 1);
+    expect!(file.link(LINKED_PATH), Err(Ov6Error::AlreadyExists));
+    drop(file);
+
+    let mut buf = [0; DATA.len()];
+    File::open(LINKED_PATH)
+        .unwrap()
+        .read_exact(&mut buf)
+        .unwrap();
+    assert_eq!(buf, DATA);
+    fs::remove_file(LINKED_PATH).unwrap();
+
+    // an unnamed file must be writable, and created in a directory
+    expect!(
+        File::options().read(true).tmpfile(true).open("."),
+        Err(Ov6Error::InvalidInput)
+    );
+    expect!(
+        File::options().write(true).tmpfile(true).open(README_PATH),
+        Err(Ov6Error::NotADirectory)
+    );
+}
+
+/// test the tmpfs mounted on `/tmp` by `/etc/rc`.
+pub fn tmpfs() {
+    const FILE_PATH: &str = "/tmp/tmpfs-file";
+    const DIR_PATH: &str = "/tmp/tmpfs-dir";
+    const LINK_PATH: &str = "/tmp/tmpfs-dir/link";
+    const DATA: &[u8] = b"tmpfs data";
+
+    let stats = fs::fs_stats("/tmp").unwrap();
+    assert_eq!(stats.label(), OsStr::new("tmpfs"));
+    assert_ne!(stats.dev(), fs::fs_stats(ROOT_DIR_PATH).unwrap().dev());
+    let (free_blocks, free_inodes) = (stats.free_blocks(), stats.free_inodes());
+
+    let _ = fs::remove_file(FILE_PATH);
+    File::create(FILE_PATH).unwrap().write_all(DATA).unwrap();
+    let mut buf = [0; DATA.len()];
+    File::open(FILE_PATH).unwrap().read_exact(&mut buf).unwrap();
+    assert_eq!(buf, DATA);
+    let stats = fs::fs_stats("/tmp").unwrap();
+    assert_eq!(stats.free_blocks(), free_blocks - 1);
+    assert_eq!(stats.free_inodes(), free_inodes - 1);
+
+    fs::create_dir(DIR_PATH).unwrap();
+    fs::link(FILE_PATH, LINK_PATH).unwrap();
+    assert_eq!(fs::metadata(FILE_PATH).unwrap().nlink(), 2);
+    expect!(fs::remove_dir(DIR_PATH), Err(Ov6Error::DirectoryNotEmpty));
+    expect!(
+        fs::link(FILE_PATH, "tmpfs-link"),
+        Err(Ov6Error::CrossesDevices)
+    );
+    fs::remove_file(LINK_PATH).unwrap();
+    fs::remove_dir(DIR_PATH).unwrap();
+    fs::remove_file(FILE_PATH).unwrap();
+    let stats = fs::fs_stats("/tmp").unwrap();
+    assert_eq!(stats.free_blocks(), free_blocks);
+    assert_eq!(stats.free_inodes(), free_inodes);
+
+    // writes beyond the size limit fail, and the pages are freed by unlinking.
+    let mut file = File::create(FILE_PATH).unwrap();
+    let chunk = [0xa5; 512];
+    let mut written = 0;
+    loop {
+        match file.write(&chunk) {
+            Ok(n) => written += n,
+            Err(Ov6Error::StorageFull) => break,
+            Err(e) => panic!("write failed: {e:?}"),
+        }
+    }
+    assert_eq!(written, free_blocks as usize * stats.block_size() as usize);
+    assert_eq!(fs::fs_stats("/tmp").unwrap().free_blocks(), 0);
+    drop(file);
+    fs::remove_file(FILE_PATH).unwrap();
+    assert_eq!(fs::fs_stats("/tmp").unwrap().free_blocks(), free_blocks);
+
+    expect!(
+        user_syscall::mount("tmpfs", Path::new(README_PATH), None),
+        Err(Ov6Error::NotADirectory)
+    );
+    expect!(
+        user_syscall::mount("nofs", Path::new("/tmp"), None),
+        Err(Ov6Error::DeviceNotFound)
+    );
+    // ov6fs needs a disk.
+    expect!(
+        user_syscall::mount("ov6fs", Path::new("/tmp"), None),
+        Err(Ov6Error::DeviceNotFound)
+    );
+}
+
+/// test `Access` and `Faccessat`.
+pub fn access() {
+    const DIR_PATH: &str = "/accessdir";
+    let rwx = AccessMode::READ | AccessMode::WRITE | AccessMode::EXECUTE;
+
+    user_syscall::access(Path::new(README_PATH), AccessMode::empty()).unwrap();
+    user_syscall::access(Path::new(README_PATH), rwx).unwrap();
+    user_syscall::access(Path::new(ROOT_DIR_PATH), rwx).unwrap();
+    expect!(
+        user_syscall::access(Path::new("no-such-file"), AccessMode::empty()),
+        Err(Ov6Error::FsEntryNotFound)
+    );
+    expect!(
+        user_syscall::access(Path::new("README/x"), AccessMode::READ),
+        Err(Ov6Error::NotADirectory)
+    );
+
+    // devices can be read and written, but not executed
+    user_syscall::access(Path::new("/console"), AccessMode::READ | AccessMode::WRITE).unwrap();
+    expect!(
+        user_syscall::access(Path::new("/console"), AccessMode::EXECUTE),
+        Err(Ov6Error::PermissionDenied)
+    );
+
+    fs::create_dir(DIR_PATH).unwrap();
+    let dir = Dir::open(DIR_PATH).unwrap();
+    dir.create_file("file").unwrap();
+    for flags in [AtFlags::empty(), AtFlags::SYMLINK_NOFOLLOW] {
+        user_syscall::faccessat(dir.as_raw_fd(), Path::new("file"), rwx, flags).unwrap();
+        expect!(
+            user_syscall::faccessat(
+                dir.as_raw_fd(),
+                Path::new("none"),
+                AccessMode::empty(),
+                flags
+            ),
+            Err(Ov6Error::FsEntryNotFound)
+        );
+    }
+    user_syscall::faccessat(
+        user_syscall::AT_FDCWD,
+        Path::new("accessdir/file"),
+        AccessMode::READ,
+        AtFlags::empty(),
+    )
+    .unwrap();
+
+    // a file cannot be a base directory
+    let file = dir.open_file("file").unwrap();
+    expect!(
+        user_syscall::faccessat(
+            file.as_raw_fd(),
+            Path::new("x"),
+            AccessMode::empty(),
+            AtFlags::empty()
+        ),
+        Err(Ov6Error::NotADirectory)
+    );
+    drop(file);
+
+    dir.remove_file("file").unwrap();
+    drop(dir);
+    fs::remove_dir(DIR_PATH).unwrap();
+}
@@ -1,4 +1,4 @@
-use alloc::alloc::Global;
+use alloc::{alloc::Global, vec, vec::Vec};
 use core::{
     alloc::{Allocator as _, Layout},
     hint,
@@ -7,19 +7,28 @@ use core::{
     time::Duration,
 };
 
+use ov6_kernel_params::MSG_QUEUE_DEPTH;
 use ov6_user_lib::{
     eprint,
     error::Ov6Error,
     fs::{self, File},
     io::{self, Read as _, Write as _},
-    os::{fd::AsRawFd as _, ov6::syscall},
+    os::{
+        self,
+        fd::AsRawFd as _,
+        ov6::{
+            ipc::{EventFd, EventFdFlags, MSG_SIZE_MAX, MsgQueue, MsgQueueFlags, Semaphore},
+            net::{LocalListener, LocalStream},
+            syscall,
+        },
+    },
     pipe,
     process::{self, ProcId, ProcessBuilder, Stdio},
     thread,
 };
 use ov6_user_tests::expect;
 
-use crate::BUF;
+use crate::{BUF, PAGE_SIZE};
 
 pub fn pipe() {
     const N: usize = 5;
@@ -80,6 +89,514 @@ pub fn pipe_bad_fd() {
     );
 }
 
+/// Writes much more than the pipe buffer holds in one call.
+pub fn pipe_large() {
+    const SIZE: usize = 64 * 1024;
+
+    let mut child = ProcessBuilder::new()
+        .stdout(Stdio::Pipe)
+        .spawn_fn(|| {
+            let data = (0..SIZE)
+                .map(|i| u8::try_from(i % 251).unwrap())
+                .collect::<Vec<_>>();
+            let n = syscall::write(io::STDOUT_FD, &data).unwrap();
+            assert_eq!(n, SIZE);
+            process::exit(0);
+        })
+        .unwrap();
+
+    let mut rx = child.stdout.take().unwrap();
+    let mut buf = vec![0; 5000];
+    let mut total = 0;
+    loop {
+        let n = rx.read(&mut buf).unwrap();
+        if n == 0 {
+            break;
+        }
+        for (i, b) in buf[..n].iter().enumerate() {
+            assert_eq!(usize::from(*b), (total + i) % 251);
+        }
+        total += n;
+    }
+    assert_eq!(total, SIZE);
+    assert!(child.wait().unwrap().success());
+}
+
+/// Whole pages between page-aligned buffers go through the page copy path.
+pub fn pipe_page_aligned() {
+    const PAGES: usize = 8;
+
+    let layout = Layout::from_size_align(PAGES * PAGE_SIZE, PAGE_SIZE).unwrap();
+    let mut child = ProcessBuilder::new()
+        .stdout(Stdio::Pipe)
+        .spawn_fn(|| {
+            let mut p = Global.allocate_zeroed(layout).unwrap();
+            let buf = &mut unsafe { p.as_mut() }[..layout.size()];
+            for (i, b) in buf.iter_mut().enumerate() {
+                *b = u8::try_from(i % 251).unwrap();
+            }
+            let n = syscall::write(io::STDOUT_FD, buf).unwrap();
+            assert_eq!(n, buf.len());
+            process::exit(0);
+        })
+        .unwrap();
+
+    let mut rx = child.stdout.take().unwrap();
+    let mut p = Global.allocate_zeroed(layout).unwrap();
+    let buf = &mut unsafe { p.as_mut() }[..layout.size()];
+    rx.read_exact(buf).unwrap();
+    for (i, b) in buf.iter().enumerate() {
+        assert_eq!(usize::from(*b), i % 251);
+    }
+    expect!(rx.read(buf), Ok(0));
+    unsafe {
+        Global.deallocate(p.cast(), layout);
+    }
+    assert!(child.wait().unwrap().success());
+}
+
+/// A write interrupted by closing the read end returns the bytes written.
+pub fn pipe_partial_write() {
+    const SIZE: usize = 64 * 1024;
+
+    let mut child = ProcessBuilder::new()
+        .stdin(Stdio::Pipe)
+        .spawn_fn(|| {
+            let mut buf = [0; 100];
+            io::stdin().read_exact(&mut buf).unwrap();
+            process::exit(0);
+        })
+        .unwrap();
+
+    let tx = child.stdin.take().unwrap();
+    let data = vec![0xa5; SIZE];
+    let n = syscall::write(tx.as_raw_fd(), &data).unwrap();
+    assert!(0 < n && n < SIZE, "n={n}");
+    expect!(
+        syscall::write(tx.as_raw_fd(), &data),
+        Err(Ov6Error::BrokenPipe)
+    );
+    assert!(child.wait().unwrap().success());
+}
+
+/// Writes of at most `PIPE_BUF` bytes by several writers are not interleaved.
+pub fn pipe_atomic_write() {
+    const NCHILD: usize = 4;
+    const NWRITE: usize = 20;
+
+    let (mut rx, tx) = pipe::pipe().unwrap();
+    for i in 0..NCHILD {
+        ProcessBuilder::new()
+            .stdout(Stdio::Fd(tx.try_clone().unwrap().into()))
+            .spawn_fn(|| {
+                let buf = vec![u8::try_from(i).unwrap(); pipe::PIPE_BUF];
+                for _ in 0..NWRITE {
+                    let n = syscall::write(io::STDOUT_FD, &buf).unwrap();
+                    assert_eq!(n, buf.len());
+                }
+                process::exit(0);
+            })
+            .unwrap();
+    }
+    drop(tx);
+
+    let mut buf = vec![0; pipe::PIPE_BUF];
+    let mut counts = [0; NCHILD];
+    for _ in 0..NCHILD * NWRITE {
+        rx.read_exact(&mut buf).unwrap();
+        assert!(buf.iter().all(|b| *b == buf[0]), "interleaved write");
+        counts[usize::from(buf[0])] += 1;
+    }
+    assert_eq!(counts, [NWRITE; NCHILD]);
+    expect!(rx.read(&mut buf), Ok(0));
+
+    for _ in 0..NCHILD {
+        let (_pid, status) = process::wait_any().unwrap();
+        assert!(status.success());
+    }
+}
+
+/// Moves a file through a pipe with splice.
+pub fn pipe_splice() {
+    const SIZE: usize = 40000;
+    const IN_PATH: &str = "splicein";
+    const OUT_PATH: &str = "spliceout";
+
+    let data = (0..SIZE)
+        .map(|i| u8::try_from(i % 253).unwrap())
+        .collect::<Vec<_>>();
+    File::create(IN_PATH).unwrap().write_all(&data).unwrap();
+
+    let mut child = ProcessBuilder::new()
+        .stdout(Stdio::Pipe)
+        .spawn_fn(|| {
+            let input = File::open(IN_PATH).unwrap();
+            let mut total = 0;
+            loop {
+                let n = syscall::splice(input.as_raw_fd(), io::STDOUT_FD, 3000).unwrap();
+                if n == 0 {
+                    break;
+                }
+                total += n;
+            }
+            assert_eq!(total, SIZE);
+            process::exit(0);
+        })
+        .unwrap();
+
+    let rx = child.stdout.take().unwrap();
+    let output = File::create(OUT_PATH).unwrap();
+    let mut total = 0;
+    loop {
+        let n = syscall::splice(rx.as_raw_fd(), output.as_raw_fd(), SIZE).unwrap();
+        if n == 0 {
+            break;
+        }
+        total += n;
+    }
+    assert_eq!(total, SIZE);
+    assert!(child.wait().unwrap().success());
+
+    let mut read_back = vec![0; SIZE];
+    File::open(OUT_PATH)
+        .unwrap()
+        .read_exact(&mut read_back)
+        .unwrap();
+    assert!(read_back == data);
+
+    // one end must be a pipe, and the ends must not be of the same pipe.
+    let input = File::open(IN_PATH).unwrap();
+    expect!(
+        syscall::splice(input.as_raw_fd(), output.as_raw_fd(), 1),
+        Err(Ov6Error::InvalidInput)
+    );
+    let (rx, tx) = pipe::pipe().unwrap();
+    expect!(
+        syscall::splice(rx.as_raw_fd(), tx.as_raw_fd(), 1),
+        Err(Ov6Error::InvalidInput)
+    );
+
+    fs::remove_file(IN_PATH).unwrap();
+    fs::remove_file(OUT_PATH).unwrap();
+}
+
+/// Both sockets of a pair can be read from and written to.
+pub fn socket_pair() {
+    let (mut a, mut b) = os::socketpair().unwrap();
+    let mut child = ProcessBuilder::new()
+        .spawn_fn(|| {
+            let mut buf = [0; 4];
+            b.read_exact(&mut buf).unwrap();
+            assert_eq!(&buf, b"ping");
+            b.write_all(b"pong").unwrap();
+            process::exit(0);
+        })
+        .unwrap();
+    drop(b);
+
+    a.write_all(b"ping").unwrap();
+    let mut buf = [0; 4];
+    a.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"pong");
+    assert!(child.wait().unwrap().success());
+
+    // the peer is closed by the exit of the child.
+    expect!(a.read(&mut buf), Ok(0));
+    expect!(a.write(b"x"), Err(Ov6Error::BrokenPipe));
+}
+
+/// Connects to a local socket bound to a path.
+pub fn local_socket() {
+    const PATH: &str = "lsock";
+    const NCHILD: u8 = 3;
+
+    let listener = LocalListener::bind(PATH).unwrap();
+    assert!(fs::metadata(PATH).unwrap().is_socket());
+    expect!(
+        LocalListener::bind(PATH).err(),
+        Some(Ov6Error::AlreadyExists)
+    );
+    expect!(File::open(PATH).err(), Some(Ov6Error::DeviceNotFound));
+
+    for i in 0..NCHILD {
+        ProcessBuilder::new()
+            .spawn_fn(|| {
+                let mut stream = LocalStream::connect(PATH).unwrap();
+                stream.write_all(&[i]).unwrap();
+                let mut buf = [0];
+                stream.read_exact(&mut buf).unwrap();
+                assert_eq!(buf[0], i + 100);
+                process::exit(0);
+            })
+            .unwrap();
+    }
+
+    let mut seen = [false; NCHILD as usize];
+    for _ in 0..NCHILD {
+        let mut stream = listener.accept().unwrap();
+        let mut buf = [0];
+        stream.read_exact(&mut buf).unwrap();
+        seen[usize::from(buf[0])] = true;
+        stream.write_all(&[buf[0] + 100]).unwrap();
+    }
+    assert!(seen.iter().all(|s| *s));
+    for _ in 0..NCHILD {
+        let (_pid, status) = process::wait_any().unwrap();
+        assert!(status.success());
+    }
+
+    drop(listener);
+    expect!(
+        LocalStream::connect(PATH).err(),
+        Some(Ov6Error::ConnectionRefused)
+    );
+    fs::remove_file(PATH).unwrap();
+    expect!(
+        LocalStream::connect(PATH).err(),
+        Some(Ov6Error::FsEntryNotFound)
+    );
+}
+
+pub fn msg_queue_priority() {
+    const KEY: u64 = 0x6d73_6701;
+
+    let queue = MsgQueue::get(KEY, MsgQueueFlags::CREATE | MsgQueueFlags::EXCLUSIVE).unwrap();
+    expect!(
+        MsgQueue::get(KEY, MsgQueueFlags::CREATE | MsgQueueFlags::EXCLUSIVE),
+        Err(Ov6Error::AlreadyExists)
+    );
+    assert_eq!(MsgQueue::get(KEY, MsgQueueFlags::empty()).unwrap(), queue);
+
+    for (data, priority) in [(b"a", 1), (b"b", 3), (b"c", 2), (b"d", 3), (b"e", 0)] {
+        queue.send(data, priority).unwrap();
+    }
+    expect!(
+        queue.send(&[0; MSG_SIZE_MAX + 1], 0),
+        Err(Ov6Error::MessageTooLong)
+    );
+    expect!(queue.recv(&mut []), Err(Ov6Error::MessageTooLong));
+
+    let mut buf = [0; MSG_SIZE_MAX];
+    for (data, priority) in [(b"b", 3), (b"d", 3), (b"c", 2), (b"a", 1), (b"e", 0)] {
+        let (len, prio) = queue.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], data);
+        assert_eq!(prio, priority);
+    }
+
+    queue.remove().unwrap();
+    expect!(queue.send(b"x", 0), Err(Ov6Error::InvalidInput));
+    expect!(
+        MsgQueue::get(KEY, MsgQueueFlags::empty()),
+        Err(Ov6Error::FsEntryNotFound)
+    );
+}
+
+pub fn msg_queue_blocking() {
+    const KEY: u64 = 0x6d73_6702;
+
+    let queue =
+        MsgQueue::get(KEY, MsgQueueFlags::CREATE | MsgQueueFlags::NONBLOCKING_SEND).unwrap();
+    for _ in 0..MSG_QUEUE_DEPTH {
+        queue.send(b"x", 0).unwrap();
+    }
+    expect!(
+        queue.send(b"x", 0),
+        Err(Ov6Error::ResourceTempolaryUnavailable)
+    );
+    queue.remove().unwrap();
+
+    let queue = MsgQueue::get(KEY, MsgQueueFlags::CREATE).unwrap();
+
+    // the receiver waits for the message sent later.
+    ProcessBuilder::new()
+        .spawn_fn(move || {
+            thread::sleep(Duration::from_millis(100));
+            queue.send(b"late", 0).unwrap();
+            process::exit(0);
+        })
+        .unwrap();
+    let mut buf = [0; MSG_SIZE_MAX];
+    let (len, _) = queue.recv(&mut buf).unwrap();
+    assert_eq!(&buf[..len], b"late");
+    let (_pid, status) = process::wait_any().unwrap();
+    assert!(status.success());
+
+    // the sender waits for the full queue to have room.
+    ProcessBuilder::new()
+        .spawn_fn(move || {
+            for i in 0..=MSG_QUEUE_DEPTH {
+                let data = [u8::try_from(i).unwrap()];
+                queue.send(&data, 0).unwrap();
+            }
+            process::exit(0);
+        })
+        .unwrap();
+    thread::sleep(Duration::from_millis(100));
+    for i in 0..=MSG_QUEUE_DEPTH {
+        let (len, _) = queue.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], &[u8::try_from(i).unwrap()]);
+    }
+    let (_pid, status) = process::wait_any().unwrap();
+    assert!(status.success());
+
+    queue.remove().unwrap();
+}
+
+pub fn msg_queue_remove() {
+    const KEY: u64 = 0x6d73_6703;
+    const NCHILD: usize = 3;
+
+    let queue = MsgQueue::get(KEY, MsgQueueFlags::CREATE).unwrap();
+    for _ in 0..NCHILD {
+        ProcessBuilder::new()
+            .spawn_fn(move || {
+                let mut buf = [0; MSG_SIZE_MAX];
+                expect!(queue.recv(&mut buf), Err(Ov6Error::IdentifierRemoved));
+                process::exit(0);
+            })
+            .unwrap();
+    }
+
+    thread::sleep(Duration::from_millis(100));
+    queue.remove().unwrap();
+    for _ in 0..NCHILD {
+        let (_pid, status) = process::wait_any().unwrap();
+        assert!(status.success());
+    }
+}
+
+pub fn semaphore() {
+    const PING: u64 = 0x7365_6d01;
+    const PONG: u64 = 0x7365_6d02;
+    const N: usize = 20;
+
+    let ping = Semaphore::open(PING, 0).unwrap();
+    let pong = Semaphore::open(PONG, 0).unwrap();
+
+    ProcessBuilder::new()
+        .spawn_fn(|| {
+            for _ in 0..N {
+                ping.wait().unwrap();
+                pong.post().unwrap();
+            }
+            process::exit(0);
+        })
+        .unwrap();
+    for _ in 0..N {
+        ping.post().unwrap();
+        pong.wait().unwrap();
+    }
+    let (_pid, status) = process::wait_any().unwrap();
+    assert!(status.success());
+
+    // the initial value is ignored when the semaphore exists.
+    let again = Semaphore::open(PING, 5).unwrap();
+    again.post().unwrap();
+    ping.wait().unwrap();
+
+    let (rx, _tx) = pipe::pipe().unwrap();
+    expect!(
+        syscall::sem_post(rx.as_raw_fd()),
+        Err(Ov6Error::InvalidInput)
+    );
+    expect!(
+        unsafe { syscall::sem_close(rx.as_raw_fd()) },
+        Err(Ov6Error::InvalidInput)
+    );
+
+    again.close().unwrap();
+    ping.close().unwrap();
+    pong.close().unwrap();
+}
+
+pub fn semaphore_mutex() {
+    const KEY: u64 = 0x7365_6d03;
+    const PATH: &str = "semcount";
+    const NCHILD: u32 = 4;
+    const NITER: u32 = 20;
+
+    let file = File::options()
+        .read(true)
+        .write(true)
+        .create(true)
+        .open(PATH)
+        .unwrap();
+    file.write_at(&0_u32.to_ne_bytes(), 0).unwrap();
+    let sem = Semaphore::open(KEY, 1).unwrap();
+
+    for _ in 0..NCHILD {
+        ProcessBuilder::new()
+            .spawn_fn(|| {
+                for _ in 0..NITER {
+                    sem.wait().unwrap();
+                    let mut buf = [0; 4];
+                    file.read_at(&mut buf, 0).unwrap();
+                    let n = u32::from_ne_bytes(buf) + 1;
+                    file.write_at(&n.to_ne_bytes(), 0).unwrap();
+                    sem.post().unwrap();
+                }
+                process::exit(0);
+            })
+            .unwrap();
+    }
+    for _ in 0..NCHILD {
+        let (_pid, status) = process::wait_any().unwrap();
+        assert!(status.success());
+    }
+
+    let mut buf = [0; 4];
+    file.read_at(&mut buf, 0).unwrap();
+    assert_eq!(u32::from_ne_bytes(buf), NCHILD * NITER);
+    fs::remove_file(PATH).unwrap();
+}
+
+pub fn event_fd() {
+    let event = EventFd::new(0, EventFdFlags::empty()).unwrap();
+
+    // the waiter is woken by the notification from another process.
+    ProcessBuilder::new()
+        .spawn_fn(|| {
+            thread::sleep(Duration::from_millis(100));
+            event.notify(3).unwrap();
+            event.notify(4).unwrap();
+            process::exit(0);
+        })
+        .unwrap();
+    let mut total = 0;
+    while total < 7 {
+        total += event.wait().unwrap();
+    }
+    assert_eq!(total, 7);
+    let (_pid, status) = process::wait_any().unwrap();
+    assert!(status.success());
+
+    let mut buf = [0; 4];
+    expect!(
+        syscall::read(event.as_raw_fd(), &mut buf),
+        Err(Ov6Error::InvalidInput)
+    );
+    expect!(
+        syscall::write(event.as_raw_fd(), &u64::MAX.to_ne_bytes()),
+        Err(Ov6Error::InvalidInput)
+    );
+    expect!(
+        syscall::pread(event.as_raw_fd(), &mut buf, 0),
+        Err(Ov6Error::NotSeekable)
+    );
+}
+
+pub fn event_fd_semaphore() {
+    let event = EventFd::new(2, EventFdFlags::SEMAPHORE | EventFdFlags::NONBLOCKING).unwrap();
+    assert_eq!(event.wait().unwrap(), 1);
+    assert_eq!(event.wait().unwrap(), 1);
+    expect!(event.wait(), Err(Ov6Error::ResourceTempolaryUnavailable));
+
+    event.notify(u64::MAX - 1).unwrap();
+    expect!(event.notify(1), Err(Ov6Error::ResourceTempolaryUnavailable));
+    assert_eq!(event.wait().unwrap(), 1);
+    event.notify(1).unwrap();
+}
+
 /// test if child is killed (status = -1)
 pub fn kill_status() {
     for _ in 0..100 {
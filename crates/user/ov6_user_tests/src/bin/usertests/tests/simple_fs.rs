@@ -1,16 +1,19 @@
-use alloc::vec;
+use alloc::{vec, vec::Vec};
+use core::{iter, mem};
 
 use ov6_fs_types::{FS_BLOCK_SIZE, MAX_FILE};
+use ov6_kernel_params::NOFILE;
 use ov6_user_lib::{
     env,
     error::Ov6Error,
     fs::{self, File},
-    io::{Read as _, STDOUT_FD, Write as _},
+    io::{Read as _, STDIN_FD, STDOUT_FD, Write as _},
     os::{
         fd::{AsRawFd as _, RawFd},
-        ov6::syscall,
+        ov6::syscall::{self, FdFlags, OpenFlags},
     },
     os_str::OsStr,
+    pipe,
     process::{self, ProcessBuilder},
 };
 use ov6_user_tests::expect;
@@ -41,25 +44,160 @@ pub fn too_many_open_files() {
     drop(files);
 }
 
-pub fn too_many_open_files_in_system() {
+/// the descriptor table grows to hold hundreds of files
+pub fn many_fds() {
+    const N: usize = 300;
+
     let mut files = vec![];
-    loop {
-        let Some(mut child) = process::fork().unwrap().into_parent() else {
-            files.clear();
-            loop {
-                match File::open(ECHO_PATH) {
-                    Ok(file) => files.push(file),
-                    Err(Ov6Error::TooManyOpenFiles) => break,
-                    Err(Ov6Error::TooManyOpenFilesSystem) => process::exit(0),
-                    Err(e) => panic!("unexpected error: {e:?}"),
-                }
-            }
-            continue;
+    for _ in 0..N {
+        files.push(File::open(ECHO_PATH).unwrap());
+    }
+    assert!(files.iter().any(|f| f.as_raw_fd().get() >= N));
+    let ino = files[0].metadata().unwrap().ino();
+
+    let high = RawFd::new(NOFILE - 1);
+    let file = File::from(syscall::dup2(files[0].as_raw_fd(), high).unwrap());
+    assert_eq!(file.as_raw_fd(), high);
+    assert_eq!(file.metadata().unwrap().ino(), ino);
+
+    // the file open at the target descriptor is replaced
+    let dir = File::open(".").unwrap();
+    let dir_ino = dir.metadata().unwrap().ino();
+    assert_ne!(dir_ino, ino);
+    let fd = syscall::dup2(dir.as_raw_fd(), high).unwrap();
+    mem::forget(fd); // `file` owns the descriptor
+    assert_eq!(file.metadata().unwrap().ino(), dir_ino);
+
+    expect!(
+        syscall::dup2(files[0].as_raw_fd(), RawFd::new(NOFILE)),
+        Err(Ov6Error::BadFileDescriptor)
+    );
+    drop(file);
+    drop(dir);
+    drop(files);
+}
+
+/// the close-on-exec flag belongs to each file descriptor
+pub fn fd_flags() {
+    let file = File::options()
+        .read(true)
+        .close_on_exec(true)
+        .open(ECHO_PATH)
+        .unwrap();
+    let fd = file.as_raw_fd();
+    assert_eq!(syscall::fd_flags(fd).unwrap(), FdFlags::CLOEXEC);
+
+    // dup and dup2 clear the flag, dup3 sets it atomically
+    // (`dup` owns the descriptor replaced by dup2 and dup3)
+    let dup = syscall::dup(fd).unwrap();
+    let dup_fd = dup.as_raw_fd();
+    assert_eq!(syscall::fd_flags(dup_fd).unwrap(), FdFlags::empty());
+    mem::forget(syscall::dup3(fd, dup_fd, OpenFlags::CLOEXEC).unwrap());
+    assert_eq!(syscall::fd_flags(dup_fd).unwrap(), FdFlags::CLOEXEC);
+    mem::forget(syscall::dup2(fd, dup_fd).unwrap());
+    assert_eq!(syscall::fd_flags(dup_fd).unwrap(), FdFlags::empty());
+    expect!(
+        syscall::dup3(fd, fd, OpenFlags::CLOEXEC),
+        Err(Ov6Error::InvalidInput)
+    );
+    expect!(
+        syscall::dup3(fd, dup_fd, OpenFlags::TRUNC),
+        Err(Ov6Error::InvalidInput)
+    );
+    drop(dup);
+
+    syscall::set_fd_flags(fd, FdFlags::empty()).unwrap();
+    assert_eq!(syscall::fd_flags(fd).unwrap(), FdFlags::empty());
+    syscall::set_fd_flags(fd, FdFlags::CLOEXEC).unwrap();
+
+    let (rx, tx) = syscall::pipe2(OpenFlags::CLOEXEC).unwrap();
+    assert_eq!(syscall::fd_flags(rx.as_raw_fd()).unwrap(), FdFlags::CLOEXEC);
+    assert_eq!(syscall::fd_flags(tx.as_raw_fd()).unwrap(), FdFlags::CLOEXEC);
+    expect!(
+        syscall::pipe2(OpenFlags::READ_WRITE),
+        Err(Ov6Error::InvalidInput)
+    );
+
+    // fork copies the flags
+    let status = ProcessBuilder::new()
+        .spawn_fn(|| {
+            assert_eq!(syscall::fd_flags(fd).unwrap(), FdFlags::CLOEXEC);
+            assert_eq!(syscall::fd_flags(rx.as_raw_fd()).unwrap(), FdFlags::CLOEXEC);
+            process::exit(0);
+        })
+        .unwrap()
+        .wait()
+        .unwrap();
+    assert!(status.success());
+    drop((rx, tx));
+    drop(file);
+}
+
+/// exec closes the file descriptors with the close-on-exec flag
+pub fn close_on_exec() {
+    let (rx, tx) = syscall::pipe2(OpenFlags::CLOEXEC).unwrap();
+    let (stdin_rx, stdin_tx) = syscall::pipe2(OpenFlags::CLOEXEC).unwrap();
+
+    let mut child = ProcessBuilder::new()
+        .spawn_fn(|| {
+            // dup2 clears the flag, so only the new stdin survives exec
+            let stdin = syscall::dup2(stdin_rx.as_raw_fd(), STDIN_FD).unwrap();
+            mem::forget(stdin);
+            // `tx` leaks into cat unless it is closed by exec
+            process::exec("/bin/cat", &["cat"]).unwrap();
+            unreachable!();
+        })
+        .unwrap();
+    drop(stdin_rx);
+    drop(tx);
+
+    // cat is blocked reading its stdin, so the pipe reaches the end only if
+    // exec has closed the write end
+    let mut rx = File::from(rx);
+    assert_eq!(rx.read(&mut [0]).unwrap(), 0);
+
+    drop(stdin_tx);
+    let status = child.wait().unwrap();
+    assert!(status.success());
+}
+
+/// the system has no limit of open files besides memory
+pub fn many_open_files_in_system() {
+    const NCHILD: usize = 8;
+    const PER_CHILD: usize = 20;
+
+    let before = syscall::get_system_info().unwrap().files.open;
+    let (mut ready_rx, mut ready_tx) = pipe::pipe().unwrap();
+    let (mut release_rx, release_tx) = pipe::pipe().unwrap();
+
+    let mut children = vec![];
+    for _ in 0..NCHILD {
+        let Some(child) = process::fork().unwrap().into_parent() else {
+            drop(ready_rx);
+            drop(release_tx);
+            let _files = iter::repeat_with(|| File::open(ECHO_PATH).unwrap())
+                .take(PER_CHILD)
+                .collect::<Vec<_>>();
+            ready_tx.write_all(&[0]).unwrap();
+            // keep the files open until the parent has counted them
+            let _ = release_rx.read(&mut [0]);
+            process::exit(0);
         };
+        children.push(child);
+    }
+    drop(ready_tx);
+    drop(release_rx);
+
+    let mut ready = [0; NCHILD];
+    ready_rx.read_exact(&mut ready).unwrap();
+    let opened = NCHILD * PER_CHILD;
+    let during = syscall::get_system_info().unwrap().files.open;
+    assert!(during >= before + opened, "{during} < {before} + {opened}");
+
+    drop(release_tx);
+    for mut child in children {
         assert!(child.wait().unwrap().success());
-        break;
     }
-    drop(files);
 }
 
 pub fn write_test() {
@@ -152,7 +290,7 @@ pub fn dir_test() {
     fs::create_dir(DIR_PATH).unwrap();
     env::set_current_directory(DIR_PATH).unwrap();
     env::set_current_directory("..").unwrap();
-    fs::remove_file(DIR_PATH).unwrap();
+    fs::remove_dir(DIR_PATH).unwrap();
 }
 
 pub fn exec_test() {
@@ -1,3 +1,5 @@
+use core::time::Duration;
+
 use ov6_fs_types::{FS_BLOCK_SIZE, MAX_FILE};
 use ov6_syscall::{UserSlice, error::SyscallError, syscall};
 use ov6_user_lib::{
@@ -7,6 +9,8 @@ use ov6_user_lib::{
     os::{fd::AsRawFd as _, ov6::syscall::ffi::SyscallExt as _},
     os_str::OsStr,
     process::{self, ProcessBuilder},
+    thread,
+    time::Instant,
 };
 use ov6_user_tests::expect;
 
@@ -112,7 +116,7 @@ pub fn bad_write() {
 /// can the kernel tolerate running out of disk space?
 pub fn disk_full() {
     const DIR_PATH: &str = "diskfulldir";
-    let _ = fs::remove_file(DIR_PATH);
+    let _ = fs::remove_dir(DIR_PATH);
 
     'outer: for fc in b'0'..0o177 {
         let name = [b'b', b'i', b'g', fc];
@@ -152,7 +156,7 @@ pub fn disk_full() {
 
     // this mkdir() is expected to fail.
     expect!(fs::create_dir(DIR_PATH), Err(Ov6Error::StorageFull));
-    let _ = fs::remove_file(DIR_PATH);
+    let _ = fs::remove_dir(DIR_PATH);
 
     for i in 0..nzz {
         let name = [
@@ -202,3 +206,43 @@ pub fn out_of_inodes() {
         let _ = fs::remove_file(path);
     }
 }
+
+/// a process sleeping for a short time wakes up in time while another one
+/// keeps the kernel busy with large writes.
+///
+/// Timer interrupts preempt kernel code whenever interrupts are enabled, so
+/// long file system operations need no explicit preemption points.
+pub fn write_latency() {
+    const FILE_PATH: &str = "wlat";
+    const WRITES: usize = 200;
+    const NAPS: usize = 20;
+    const NAP: Duration = Duration::from_millis(100);
+    const MAX_LATENCY: Duration = Duration::from_secs(1);
+
+    let buf = unsafe { (&raw const BUF).as_ref() }.unwrap();
+
+    let mut writer = ProcessBuilder::new()
+        .spawn_fn(|| {
+            for _ in 0..WRITES {
+                let mut file = File::create(FILE_PATH).unwrap();
+                file.write_all(buf).unwrap();
+            }
+            process::exit(0);
+        })
+        .unwrap();
+
+    let mut worst = Duration::ZERO;
+    for _ in 0..NAPS {
+        let start = Instant::now();
+        thread::sleep(NAP);
+        worst = Duration::max(worst, start.elapsed());
+    }
+
+    assert!(writer.wait().unwrap().success());
+    fs::remove_file(FILE_PATH).unwrap();
+    assert!(
+        worst < NAP + MAX_LATENCY,
+        "sleep overslept by {:?} while writing",
+        worst - NAP
+    );
+}
@@ -1,9 +1,18 @@
+use core::time::Duration;
+
 use ov6_user_lib::{
-    io::STDOUT_FD,
-    os::{fd::AsRawFd as _, ov6::syscall},
+    error::Ov6Error,
+    io::{Read as _, STDOUT_FD, Write as _},
+    os::{
+        fd::AsRawFd as _,
+        ov6::syscall::{self, CpuSet, NICE_MAX},
+    },
     pipe,
     process::{self, ProcessBuilder},
+    thread,
+    time::Instant,
 };
+use ov6_user_tests::expect;
 
 use crate::{ECHO_PATH, PAGE_SIZE};
 
@@ -47,3 +56,113 @@ pub fn execout() {
         assert!(status.success());
     }
 }
+
+/// a copy-on-write fault that finds no free page kills the process with the
+/// highest badness, a low-priority one, instead of the faulting process.
+pub fn oom_killer() {
+    // room left for the page tables of fork
+    const SLACK_PAGES: usize = 128;
+
+    let memory = || syscall::get_system_info().unwrap().memory;
+    let kills = memory().oom_kills;
+    let victim_pages = memory().free_pages / 2;
+
+    let (mut ready_rx, mut ready_tx) = pipe::pipe().unwrap();
+    let (mut hold_rx, hold_tx) = pipe::pipe().unwrap();
+    let mut victim = ProcessBuilder::new()
+        .spawn_fn(|| {
+            syscall::set_nice(NICE_MAX).unwrap();
+            process::grow_break(victim_pages * PAGE_SIZE).unwrap();
+            ready_tx.write_all(&[0]).unwrap();
+            // sleep until killed
+            let _ = hold_rx.read(&mut [0]);
+            process::exit(0);
+        })
+        .unwrap();
+    ready_rx.read_exact(&mut [0]).unwrap();
+
+    let status = ProcessBuilder::new()
+        .spawn_fn(|| {
+            // allocate the rest of memory.
+            let heap = process::current_break();
+            while process::grow_break(64 * PAGE_SIZE).is_ok() {}
+            while process::grow_break(PAGE_SIZE).is_ok() {}
+            unsafe { process::shrink_break(SLACK_PAGES * PAGE_SIZE) }.unwrap();
+
+            // the pages shared with the parent are copied on write, which
+            // needs the memory of the victim.
+            let status = ProcessBuilder::new()
+                .spawn_fn(|| {
+                    for i in 0..victim_pages / 2 {
+                        unsafe {
+                            heap.add(i * PAGE_SIZE).write_volatile(1);
+                        }
+                    }
+                    process::exit(0);
+                })
+                .unwrap()
+                .wait()
+                .unwrap();
+            process::exit(status.code());
+        })
+        .unwrap()
+        .wait()
+        .unwrap();
+    assert!(status.success());
+
+    let status = victim.wait().unwrap();
+    assert_eq!(status.code(), -1, "victim not killed");
+    assert_eq!(memory().oom_kills, kills + 1);
+    drop(hold_tx);
+}
+
+/// a process pinned to a CPU only runs on that CPU, and its children inherit
+/// the CPU set.
+pub fn cpu_affinity() {
+    let me = process::id();
+    let num_cpus = syscall::get_system_info().unwrap().load.num_cpus;
+    expect!(
+        syscall::set_affinity(me, CpuSet::EMPTY),
+        Err(Ov6Error::InvalidInput)
+    );
+    if num_cpus < syscall::MAX_CPUS {
+        expect!(
+            syscall::set_affinity(me, CpuSet::single(num_cpus)),
+            Err(Ov6Error::InvalidInput)
+        );
+    }
+
+    let target = num_cpus - 1;
+    let status = ProcessBuilder::new()
+        .spawn_fn(move || {
+            let me = process::id();
+            syscall::set_affinity(me, CpuSet::single(target)).unwrap();
+            assert_eq!(syscall::affinity(me).unwrap(), CpuSet::single(target));
+
+            let status = ProcessBuilder::new()
+                .spawn_fn(move || {
+                    let affinity = syscall::affinity(process::id()).unwrap();
+                    process::exit(i32::from(affinity != CpuSet::single(target)));
+                })
+                .unwrap()
+                .wait()
+                .unwrap();
+            assert!(status.success());
+
+            // sleep between the busy rounds, so that the scheduler picks a
+            // CPU for the process again on each wakeup.
+            for _ in 0..100 {
+                let start = Instant::now();
+                while start.elapsed() < Duration::from_millis(5) {
+                    let cpu = syscall::getcpu();
+                    assert_eq!(cpu, target, "ran on cpu{cpu}");
+                }
+                thread::sleep(Duration::from_millis(1));
+            }
+            process::exit(0);
+        })
+        .unwrap()
+        .wait()
+        .unwrap();
+    assert!(status.success());
+}
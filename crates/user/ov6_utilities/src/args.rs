@@ -0,0 +1,279 @@
+//! Command-line argument parser shared by the utilities.
+//!
+//! Options are declared as a static table of [`Opt`]s and parsed with
+//! [`Parser`], which yields options and positional arguments in order.
+//!
+//! The following syntaxes are accepted:
+//!
+//! - `-a`, `-abc` (clustered short flags)
+//! - `-n VALUE`, `-nVALUE` (short option with a value)
+//! - `--name`, `--name VALUE`, `--name=VALUE` (long options)
+//! - `--` (all following arguments are positional)
+//!
+//! `-h` and `--help` print the generated usage and exit.
+
+use core::fmt;
+
+use ov6_user_lib::{
+    env::{self, ArgsOs},
+    eprint,
+    os_str::OsStr,
+    print, process,
+};
+
+/// A command-line option.
+#[derive(Debug, Clone, Copy)]
+pub struct Opt {
+    name: &'static str,
+    short: Option<u8>,
+    value: Option<&'static str>,
+    help: &'static str,
+}
+
+impl Opt {
+    /// Creates an option that takes no value.
+    ///
+    /// `name` is used as the long option name (`--name`) and to identify the
+    /// option in [`Arg`].
+    #[must_use]
+    pub const fn flag(name: &'static str) -> Self {
+        Self {
+            name,
+            short: None,
+            value: None,
+            help: "",
+        }
+    }
+
+    /// Creates an option that takes a value.
+    ///
+    /// `value_name` is shown in the usage message.
+    #[must_use]
+    pub const fn value(name: &'static str, value_name: &'static str) -> Self {
+        Self {
+            name,
+            short: None,
+            value: Some(value_name),
+            help: "",
+        }
+    }
+
+    /// Sets the short option name.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `short` is not an ASCII character.
+    #[must_use]
+    pub const fn short(mut self, short: char) -> Self {
+        assert!(short.is_ascii());
+        self.short = Some(short as u8);
+        self
+    }
+
+    /// Sets the description shown in the usage message.
+    #[must_use]
+    pub const fn help(mut self, help: &'static str) -> Self {
+        self.help = help;
+        self
+    }
+
+    fn usage_width(&self) -> usize {
+        let value_width = self.value.map_or(0, |v| v.len() + 3);
+        "-x, --".len() + self.name.len() + value_width
+    }
+}
+
+/// An argument returned by [`Parser::next`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arg {
+    /// An option without a value, identified by its name.
+    Flag(&'static str),
+    /// An option with a value, identified by its name.
+    Value(&'static str, &'static OsStr),
+    /// A positional argument.
+    Positional(&'static OsStr),
+}
+
+/// Command-line argument parser.
+pub struct Parser {
+    opts: &'static [Opt],
+    positional: &'static str,
+    args: ArgsOs,
+    shorts: &'static [u8],
+    options_done: bool,
+}
+
+impl Parser {
+    /// Creates a parser for the arguments of the current process.
+    ///
+    /// `positional` describes the positional arguments in the usage message
+    /// (e.g. `"[file...]"`).
+    #[must_use]
+    pub fn new(opts: &'static [Opt], positional: &'static str) -> Self {
+        let mut args = env::args_os();
+        let _ = args.next(); // skip the program name
+        Self {
+            opts,
+            positional,
+            args,
+            shorts: &[],
+            options_done: false,
+        }
+    }
+
+    /// Returns the usage message.
+    #[must_use]
+    pub fn usage(&self) -> Usage {
+        Usage {
+            opts: self.opts,
+            positional: self.positional,
+        }
+    }
+
+    /// Prints `msg` and the usage message to standard error, then exits.
+    pub fn usage_error(&self, msg: fmt::Arguments<'_>) -> ! {
+        let prog = env::arg0().display();
+        eprint!("{prog}: {msg}\n{}", self.usage());
+        process::exit(2);
+    }
+
+    fn find_short(&self, c: u8) -> Option<&'static Opt> {
+        self.opts.iter().find(|opt| opt.short == Some(c))
+    }
+
+    fn find_long(&self, name: &[u8]) -> Option<&'static Opt> {
+        self.opts.iter().find(|opt| opt.name.as_bytes() == name)
+    }
+
+    fn help(&self) -> ! {
+        print!("{}", self.usage());
+        process::exit(0);
+    }
+
+    fn parse_shorts(&mut self) -> Arg {
+        let (&c, rest) = self.shorts.split_first().unwrap();
+        self.shorts = rest;
+
+        let Some(opt) = self.find_short(c) else {
+            if c == b'h' {
+                self.help();
+            }
+            self.usage_error(format_args!("unknown option '-{}'", char::from(c)));
+        };
+        if opt.value.is_none() {
+            return Arg::Flag(opt.name);
+        }
+
+        let value = if self.shorts.is_empty() {
+            let Some(value) = self.args.next() else {
+                self.usage_error(format_args!("option '-{}' requires a value", char::from(c)));
+            };
+            value
+        } else {
+            OsStr::from_bytes(self.shorts)
+        };
+        self.shorts = &[];
+        Arg::Value(opt.name, value)
+    }
+
+    fn parse_long(&mut self, arg: &'static [u8]) -> Arg {
+        let (name, inline_value) = arg
+            .iter()
+            .position(|b| *b == b'=')
+            .map_or((arg, None), |i| {
+                (&arg[..i], Some(OsStr::from_bytes(&arg[i + 1..])))
+            });
+        let name_str = OsStr::from_bytes(name).display();
+
+        let Some(opt) = self.find_long(name) else {
+            if name == b"help" {
+                self.help();
+            }
+            self.usage_error(format_args!("unknown option '--{name_str}'"));
+        };
+
+        match (opt.value, inline_value) {
+            (None, None) => Arg::Flag(opt.name),
+            (None, Some(_)) => {
+                self.usage_error(format_args!("option '--{name_str}' takes no value"))
+            }
+            (Some(_), Some(value)) => Arg::Value(opt.name, value),
+            (Some(_), None) => {
+                let Some(value) = self.args.next() else {
+                    self.usage_error(format_args!("option '--{name_str}' requires a value"));
+                };
+                Arg::Value(opt.name, value)
+            }
+        }
+    }
+}
+
+impl Iterator for Parser {
+    type Item = Arg;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.shorts.is_empty() {
+            return Some(self.parse_shorts());
+        }
+
+        let arg = self.args.next()?;
+        if self.options_done {
+            return Some(Arg::Positional(arg));
+        }
+
+        let bytes = arg.as_bytes();
+        if bytes == b"--" {
+            self.options_done = true;
+            return self.next();
+        }
+        if let Some(long) = bytes.strip_prefix(b"--") {
+            return Some(self.parse_long(long));
+        }
+        if let Some(shorts) = bytes.strip_prefix(b"-").filter(|s| !s.is_empty()) {
+            self.shorts = shorts;
+            return Some(self.parse_shorts());
+        }
+        Some(Arg::Positional(arg))
+    }
+}
+
+/// Usage message generated from the option table.
+pub struct Usage {
+    opts: &'static [Opt],
+    positional: &'static str,
+}
+
+impl fmt::Display for Usage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let prog = env::arg0().display();
+        writeln!(f, "Usage: {prog} [OPTIONS] {}", self.positional)?;
+        writeln!(f)?;
+        writeln!(f, "Options:")?;
+
+        // `-h` is left to the program if it uses it for another option.
+        let mut help = Opt::flag("help").help("Print help");
+        if !self.opts.iter().any(|opt| opt.short == Some(b'h')) {
+            help = help.short('h');
+        }
+        let width = self
+            .opts
+            .iter()
+            .chain([&help])
+            .map(Opt::usage_width)
+            .max()
+            .unwrap_or(0);
+        for opt in self.opts.iter().chain([&help]) {
+            match opt.short {
+                Some(c) => write!(f, "  -{}, ", char::from(c))?,
+                None => write!(f, "      ")?,
+            }
+            write!(f, "--{}", opt.name)?;
+            if let Some(value) = opt.value {
+                write!(f, " <{value}>")?;
+            }
+            let pad = width - opt.usage_width();
+            writeln!(f, "{:pad$}  {}", "", opt.help)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,45 @@
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+
+use alloc::vec;
+
+use ov6_user_lib::{
+    env,
+    os::ov6::syscall::{self, AuditRecord},
+    os_str::OsStr,
+    print, println,
+};
+use ov6_utilities::{OrExit as _, exit_err, usage_and_exit};
+
+const MAX_RECORDS: usize = 32;
+
+fn main() {
+    let args = env::args();
+    if args.len() > 1 {
+        usage_and_exit!("");
+    }
+
+    let mut records = vec![AuditRecord::zeroed(); MAX_RECORDS];
+    let n =
+        syscall::read_audit_log(&mut records).or_exit(|e| exit_err!(e, "cannot read audit log"));
+
+    for record in &records[..n] {
+        let seq = record.seq;
+        let pid = record.pid;
+        if let Some(code) = record.syscall() {
+            print!("{seq} pid={pid} syscall={code}");
+        } else {
+            print!("{seq} pid={pid} syscall=#{}", record.syscall);
+        }
+        if let Some(e) = record.error() {
+            print!(" error=\"{e}\"");
+        } else {
+            print!(" errno={}", record.errno);
+        }
+        if let Some(path) = record.path() {
+            print!(" path={}", OsStr::from_bytes(path).display());
+        }
+        println!();
+    }
+}
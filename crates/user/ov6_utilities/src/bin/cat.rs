@@ -1,14 +1,26 @@
 #![no_std]
 
+extern crate alloc;
+
+use alloc::vec::Vec;
 use core::fmt;
 
 use ov6_user_lib::{
-    env,
     fs::File,
-    io::{self, Read, Write as _},
+    io::{self, Read, STDIN_FD, Write as _},
+    os::{
+        fd::AsRawFd as _,
+        ov6::syscall::{self, AccessHint},
+    },
     process,
 };
-use ov6_utilities::{OrExit as _, exit_err, message_err};
+use ov6_utilities::{
+    OrExit as _,
+    args::{Arg, Opt, Parser},
+    exit_err, message_err,
+};
+
+const OPTS: &[Opt] = &[];
 
 fn cat<T, P>(mut input: T, path: P)
 where
@@ -34,21 +46,29 @@ where
 }
 
 fn main() {
-    let mut args = env::args_os();
-    let _ = args.next(); // skip the program name
+    let paths = Parser::new(OPTS, "[file...]")
+        .map(|arg| match arg {
+            Arg::Positional(path) => path,
+            Arg::Flag(_) | Arg::Value(..) => unreachable!(),
+        })
+        .collect::<Vec<_>>();
 
-    if args.len() == 0 {
+    // the hint is only for performance, so errors (e.g. for pipes) are
+    // ignored.
+    if paths.is_empty() {
+        let _ = syscall::set_access_hint(STDIN_FD, AccessHint::Sequential);
         cat(io::stdin(), "standard input");
         process::exit(0);
     }
 
-    let files = args.flat_map(|path| {
+    let files = paths.into_iter().flat_map(|path| {
         File::open(path)
             .inspect_err(|e| message_err!(e, "cannot open file '{}'", path.display()))
             .map(|file| (file, path))
     });
 
     for (file, path) in files {
+        let _ = syscall::set_access_hint(file.as_raw_fd(), AccessHint::Sequential);
         cat(&file, path.display());
     }
 }
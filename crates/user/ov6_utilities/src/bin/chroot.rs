@@ -0,0 +1,33 @@
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use ov6_user_lib::{env, os::ov6::syscall, os_str::OsStr, path::Path, process};
+use ov6_utilities::{OrExit as _, exit_err, usage_and_exit};
+
+fn main() {
+    let mut args = env::args_os();
+    let _ = args.next(); // skip the program name
+
+    if args.len() < 2 {
+        usage_and_exit!("<newroot> <command...>");
+    }
+
+    let new_root = Path::new(args.next().unwrap());
+    syscall::chroot(new_root).or_exit(|e| {
+        exit_err!(
+            e,
+            "cannot change root directory to '{}'",
+            new_root.display()
+        )
+    });
+    env::set_current_directory("/")
+        .or_exit(|e| exit_err!(e, "cannot change directory to new root"));
+
+    let args = args.collect::<Vec<_>>();
+    let arg0 = args.first().unwrap();
+    let Err(e) = process::exec_search(arg0, &args, OsStr::new(process::DEFAULT_PATH));
+    exit_err!(e, "failed to exec '{}'", arg0.display());
+}
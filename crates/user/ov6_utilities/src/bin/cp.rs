@@ -0,0 +1,61 @@
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use ov6_user_lib::{fs, path::Path, process};
+use ov6_utilities::{
+    args::{Arg, Parser},
+    message, message_err,
+};
+
+/// Copies `src` to `dst`, or into `dst` if it is a directory.
+fn copy(src: &Path, dst: &Path) -> bool {
+    let is_dir = fs::metadata(dst).is_ok_and(|meta| meta.is_dir());
+    let res = if is_dir {
+        let Some(name) = src.file_name() else {
+            message!("cannot copy '{}': invalid file name", src.display());
+            return false;
+        };
+        fs::copy(src, dst.join(name))
+    } else {
+        fs::copy(src, dst)
+    };
+    if let Err(e) = res {
+        message_err!(e, "cannot copy '{}' to '{}'", src.display(), dst.display());
+        return false;
+    }
+    true
+}
+
+fn main() {
+    let mut parser = Parser::new(&[], "<src> <dst> | <src...> <dir>");
+    let mut paths = Vec::new();
+    for arg in &mut parser {
+        match arg {
+            Arg::Positional(path) => paths.push(Path::new(path)),
+            _ => unreachable!(),
+        }
+    }
+
+    if paths.len() < 2 {
+        parser.usage_error(format_args!("missing operand"));
+    }
+    let dst = paths.pop().unwrap();
+    if paths.len() > 1 && !fs::metadata(dst).is_ok_and(|meta| meta.is_dir()) {
+        parser.usage_error(format_args!(
+            "target '{}' is not a directory",
+            dst.display()
+        ));
+    }
+
+    let mut status = 0;
+    for src in paths {
+        if !copy(src, dst) {
+            status = 1;
+        }
+    }
+
+    process::exit(status);
+}
@@ -0,0 +1,59 @@
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use ov6_user_lib::{fs, path::Path, println, process};
+use ov6_utilities::{
+    args::{Arg, Opt, Parser},
+    message_err,
+};
+
+const OPTS: &[Opt] = &[];
+
+fn main() {
+    let mut paths = Vec::new();
+    for arg in Parser::new(OPTS, "[path...]") {
+        match arg {
+            Arg::Positional(path) => paths.push(Path::new(path)),
+            Arg::Flag(_) | Arg::Value(..) => unreachable!(),
+        }
+    }
+    if paths.is_empty() {
+        paths.push(Path::new("/"));
+    }
+
+    println!(
+        "{:<16} {:<36} {:>8} {:>8} {:>8} {:>8} {:>8}",
+        "Label", "UUID", "Blocks", "Used", "Free", "Inodes", "IFree"
+    );
+
+    let mut status = 0;
+    for path in paths {
+        let stats = match fs::fs_stats(path) {
+            Ok(stats) => stats,
+            Err(e) => {
+                message_err!(
+                    e,
+                    "cannot get file system statistics of '{}'",
+                    path.display()
+                );
+                status = 1;
+                continue;
+            }
+        };
+        println!(
+            "{:<16} {:<36} {:>8} {:>8} {:>8} {:>8} {:>8}",
+            stats.label().display(),
+            stats.uuid(),
+            stats.blocks(),
+            stats.blocks() - stats.free_blocks(),
+            stats.free_blocks(),
+            stats.inodes(),
+            stats.free_inodes(),
+        );
+    }
+
+    process::exit(status);
+}
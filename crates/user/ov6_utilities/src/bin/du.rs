@@ -0,0 +1,155 @@
+#![no_std]
+
+extern crate alloc;
+
+use alloc::{collections::BTreeSet, format, string::String, vec::Vec};
+
+use ov6_user_lib::{
+    fs::{self, FS_BLOCK_SIZE},
+    path::Path,
+    println, process,
+};
+use ov6_utilities::{
+    args::{Arg, Opt, Parser},
+    message_err,
+};
+
+const OPTS: &[Opt] = &[
+    Opt::flag("summarize")
+        .short('s')
+        .help("Display only a total for each argument"),
+    Opt::flag("human-readable")
+        .short('h')
+        .help("Print sizes in human readable format (e.g., 1.5K, 20M)"),
+];
+
+/// Unit of the sizes printed without `-h`.
+const UNIT: u64 = 1024;
+
+const BLOCK_SIZE: u64 = FS_BLOCK_SIZE as u64;
+
+#[derive(Debug, Clone, Copy)]
+struct Options {
+    summarize: bool,
+    human_readable: bool,
+}
+
+struct Walker {
+    opts: Options,
+    /// Files with multiple links that are already counted, as `(dev, ino)`.
+    seen: BTreeSet<(u32, u32)>,
+    status: i32,
+}
+
+fn format_human(bytes: u64) -> String {
+    const SUFFIXES: &[char] = &['K', 'M', 'G', 'T'];
+
+    if bytes < 1024 {
+        return format!("{bytes}");
+    }
+    // size in tenths of the unit
+    let mut tenths = bytes * 10 / 1024;
+    let mut suffix = 0;
+    while tenths >= 1024 * 10 && suffix + 1 < SUFFIXES.len() {
+        tenths /= 1024;
+        suffix += 1;
+    }
+    let suffix = SUFFIXES[suffix];
+    if tenths < 100 {
+        format!("{}.{}{suffix}", tenths / 10, tenths % 10)
+    } else {
+        format!("{}{suffix}", tenths / 10)
+    }
+}
+
+impl Walker {
+    fn print(&self, bytes: u64, path: &Path) {
+        if self.opts.human_readable {
+            println!("{}\t{}", format_human(bytes), path.display());
+        } else {
+            println!("{}\t{}", bytes.div_ceil(UNIT), path.display());
+        }
+    }
+
+    /// Returns the number of bytes of the disk blocks used by `path` and its
+    /// descendants.
+    fn walk(&mut self, path: &Path, is_arg: bool) -> u64 {
+        let Ok(meta) = fs::metadata(path).inspect_err(|e| {
+            message_err!(e, "cannot stat '{}'", path.display());
+            self.status = 1;
+        }) else {
+            return 0;
+        };
+
+        // count hard-linked files only once
+        if !meta.is_dir() && meta.nlink() > 1 && !self.seen.insert((meta.dev(), meta.ino())) {
+            return 0;
+        }
+
+        let mut bytes = u64::from(meta.blocks()) * BLOCK_SIZE;
+        if !meta.is_dir() {
+            if is_arg {
+                self.print(bytes, path);
+            }
+            return bytes;
+        }
+
+        // Read all entries before descending so that the directory is not kept
+        // open during the recursion.
+        let names = match fs::read_dir(path) {
+            Ok(entries) => entries
+                .filter_map(|ent| {
+                    ent.inspect_err(|e| {
+                        message_err!(e, "cannot read directory '{}' entry", path.display());
+                        self.status = 1;
+                    })
+                    .ok()
+                })
+                .map(|ent| ent.name().to_os_string())
+                .collect::<Vec<_>>(),
+            Err(e) => {
+                message_err!(e, "cannot open '{}' as directory", path.display());
+                self.status = 1;
+                Vec::new()
+            }
+        };
+        for name in names {
+            bytes += self.walk(&path.join(name), false);
+        }
+
+        if is_arg || !self.opts.summarize {
+            self.print(bytes, path);
+        }
+        bytes
+    }
+}
+
+fn main() {
+    let mut opts = Options {
+        summarize: false,
+        human_readable: false,
+    };
+    let mut paths = Vec::new();
+    for arg in Parser::new(OPTS, "[path...]") {
+        match arg {
+            Arg::Flag("summarize") => opts.summarize = true,
+            Arg::Flag("human-readable") => opts.human_readable = true,
+            Arg::Positional(path) => paths.push(Path::new(path)),
+            Arg::Flag(_) | Arg::Value(..) => unreachable!(),
+        }
+    }
+    if paths.is_empty() {
+        paths.push(Path::new("."));
+    }
+
+    let mut walker = Walker {
+        opts,
+        seen: BTreeSet::new(),
+        status: 0,
+    };
+    for path in paths {
+        walker.walk(path, true);
+    }
+
+    process::exit(walker.status);
+}
@@ -0,0 +1,368 @@
+#![no_std]
+
+extern crate alloc;
+
+use alloc::{vec, vec::Vec};
+use core::{array, fmt, mem};
+
+use dataview::PodMethods as _;
+use ov6_fs_types::{
+    BITS_PER_BLOCK, BlockNo, BmapBlock, Compatibility, DirEntry, FS_BLOCK_SIZE, INODE_PER_BLOCK,
+    IndirectBlock, Inode, InodeNo, MAX_FILE, NUM_DIRECT_REFS, NUM_INDIRECT_REFS, SuperBlock,
+    T_DEVICE, T_DIR, T_FILE, T_SOCK,
+};
+use ov6_user_lib::{error::Ov6Error, fs::File, path::Path, println, process};
+use ov6_utilities::{
+    OrExit as _,
+    args::{Arg, Parser},
+    exit_err,
+    fs_image::Image,
+    message,
+};
+
+const DIR_ENTRIES_PER_BLOCK: usize = FS_BLOCK_SIZE / size_of::<DirEntry>();
+
+/// Contents of a directory in the image.
+///
+/// Entries in holes, including the blocks dropped as invalid, read as free.
+struct ImageDir<'a> {
+    img: &'a Image,
+    blocks: &'a [Option<BlockNo>],
+    size: usize,
+    /// The last block read, with its index in the directory.
+    cached: Option<(usize, [DirEntry; DIR_ENTRIES_PER_BLOCK])>,
+}
+
+impl ov6_fs_path::DirContent for ImageDir<'_> {
+    type Error = Ov6Error;
+
+    fn size(&self) -> usize {
+        self.size
+    }
+
+    fn read_entry(&mut self, off: usize) -> Result<DirEntry, Self::Error> {
+        let bi = off / FS_BLOCK_SIZE;
+        if self
+            .cached
+            .as_ref()
+            .is_none_or(|(cached_bi, _)| *cached_bi != bi)
+        {
+            let mut ents: [DirEntry; DIR_ENTRIES_PER_BLOCK] =
+                array::from_fn(|_| DirEntry::zeroed());
+            if let Some(bn) = self.blocks.get(bi).copied().flatten() {
+                self.img.read_block(bn, &mut ents)?;
+            }
+            self.cached = Some((bi, ents));
+        }
+        let (_, ents) = self.cached.as_ref().unwrap();
+        let ent = &ents[off % FS_BLOCK_SIZE / size_of::<DirEntry>()];
+        let mut copy = DirEntry::zeroed();
+        copy.as_bytes_mut().copy_from_slice(ent.as_bytes());
+        Ok(copy)
+    }
+}
+
+struct Checker<'a> {
+    img: &'a Image,
+    sb: SuperBlock,
+    /// Blocks referenced by the inodes.
+    referenced: Vec<bool>,
+    /// Number of directory entries referring to each inode.
+    refs: Vec<u32>,
+    /// Inodes in use, with their types.
+    types: Vec<u16>,
+    problems: usize,
+}
+
+impl Checker<'_> {
+    fn problem(&mut self, args: fmt::Arguments<'_>) {
+        println!("fsck: {args}");
+        self.problems += 1;
+    }
+
+    /// Checks that the super block describes a consistent layout.
+    fn check_super_block(&mut self) -> bool {
+        let sb = &self.sb;
+        let magic = sb.magic.get();
+        let size = sb.size.get();
+        let nblocks = sb.nblocks.get();
+        let ninodes = sb.ninodes.get();
+        let nlog = sb.nlog.get();
+        let logstart = sb.logstart.get();
+        let inodestart = sb.inodestart.get();
+        let bmapstart = sb.bmapstart.get();
+        let has_inode_bitmap = sb.has_inode_bitmap();
+        let ibmapstart = sb.ibmapstart.get();
+        let version = sb.version.get();
+        let incompat = sb.features.incompat.get();
+        let compat = sb.compatibility();
+        if magic != SuperBlock::FS_MAGIC {
+            self.problem(format_args!("bad magic number {magic:#x}"));
+            return false;
+        }
+        if compat == Compatibility::Incompatible {
+            self.problem(format_args!(
+                "unsupported version {version} or features {incompat:#x}"
+            ));
+            return false;
+        }
+        let ninode_blocks = ninodes.div_ceil(u32::try_from(INODE_PER_BLOCK).unwrap());
+        let nbmap_blocks = size.div_ceil(u32::try_from(BITS_PER_BLOCK).unwrap());
+        let (ibmapstart, nibmap_blocks) = if has_inode_bitmap {
+            let nibmap_blocks = ninodes.div_ceil(u32::try_from(BITS_PER_BLOCK).unwrap());
+            (ibmapstart, nibmap_blocks)
+        } else {
+            (inodestart + ninode_blocks, 0)
+        };
+        let ok = logstart == 2
+            && inodestart >= logstart + nlog
+            && ibmapstart >= inodestart + ninode_blocks
+            && bmapstart >= ibmapstart + nibmap_blocks
+            && nblocks <= size
+            && size - nblocks >= bmapstart + nbmap_blocks
+            && ninodes > 1;
+        if !ok {
+            self.problem(format_args!(
+                "inconsistent layout: size {size} data {nblocks} log {nlog}@{logstart} inodes \
+                 {ninodes}@{inodestart} inode map @{ibmapstart} bitmap @{bmapstart}"
+            ));
+        }
+        ok
+    }
+
+    /// Returns the blocks of the inode, in file order.
+    fn inode_blocks(
+        &mut self,
+        ino: InodeNo,
+        inode: &Inode,
+    ) -> Result<Vec<Option<BlockNo>>, Ov6Error> {
+        let mut addrs = [None; NUM_DIRECT_REFS + 1];
+        inode.read_addrs(&mut addrs);
+        let mut blocks = addrs[..NUM_DIRECT_REFS].to_vec();
+        if let Some(ind_bn) = addrs[NUM_DIRECT_REFS]
+            && self.reference_block(ino, ind_bn)
+        {
+            let mut ind = IndirectBlock::zeroed();
+            self.img.read_block(ind_bn, &mut ind)?;
+            blocks.extend((0..NUM_INDIRECT_REFS).map(|i| ind.get(i)));
+        }
+
+        for bn in &mut blocks {
+            if let Some(b) = *bn
+                && !self.reference_block(ino, b)
+            {
+                *bn = None;
+            }
+        }
+        Ok(blocks)
+    }
+
+    /// Records a reference to the block `bn` from the inode `ino`.
+    ///
+    /// Returns `false` if the block number is out of the data area.
+    fn reference_block(&mut self, ino: InodeNo, bn: BlockNo) -> bool {
+        if !(self.sb.data_start()..self.sb.size.get()).contains(&bn.value()) {
+            self.problem(format_args!("inode {ino}: block {bn} out of data area"));
+            return false;
+        }
+        if mem::replace(&mut self.referenced[bn.as_index()], true) {
+            self.problem(format_args!("inode {ino}: block {bn} referenced twice"));
+        }
+        true
+    }
+
+    fn check_directory(
+        &mut self,
+        ino: InodeNo,
+        inode: &Inode,
+        blocks: &[Option<BlockNo>],
+    ) -> Result<(), Ov6Error> {
+        let mut dir = ImageDir {
+            img: self.img,
+            blocks,
+            size: usize::try_from(inode.size.get()).unwrap(),
+            cached: None,
+        };
+        let mut has_dot = false;
+        for res in ov6_fs_path::entries(&mut dir) {
+            let (_off, ent) = res?;
+            let Some(target) = ent.ino() else {
+                continue;
+            };
+            let name = ent.name();
+            if name == "." {
+                has_dot = true;
+                if target != ino {
+                    self.problem(format_args!("directory {ino}: '.' refers to {target}"));
+                }
+                continue;
+            }
+            if ino == InodeNo::ROOT && name == ".." && target != ino {
+                self.problem(format_args!("root directory: '..' refers to {target}"));
+            }
+            match self.types.get(target.as_index()) {
+                Some(&ty) if ty != 0 => self.refs[target.as_index()] += 1,
+                _ => self.problem(format_args!(
+                    "directory {ino}: entry '{}' refers to free inode {target}",
+                    name.display()
+                )),
+            }
+        }
+        if !has_dot {
+            self.problem(format_args!("directory {ino}: no '.' entry"));
+        }
+        Ok(())
+    }
+
+    fn check_inodes(&mut self) -> Result<(), Ov6Error> {
+        let mut dirs = Vec::new();
+        for inum in 1..self.sb.ninodes.get() {
+            let ino = InodeNo::new(inum);
+            let inode = self.img.read_inode(&self.sb, ino)?;
+            if inode.is_free() {
+                continue;
+            }
+            let ty = inode.ty.get();
+            self.types[ino.as_index()] = ty;
+            if ![T_DIR, T_FILE, T_DEVICE, T_SOCK].contains(&ty) {
+                self.problem(format_args!("inode {ino}: invalid type {ty}"));
+                continue;
+            }
+            if usize::try_from(inode.size.get()).unwrap() > MAX_FILE * FS_BLOCK_SIZE {
+                self.problem(format_args!("inode {ino}: too large size {}", inode.size));
+            }
+            let blocks = self.inode_blocks(ino, &inode)?;
+            if ty == T_DIR {
+                dirs.push((ino, inode, blocks));
+            }
+        }
+
+        match self.types[InodeNo::ROOT.as_index()] {
+            T_DIR => {}
+            0 => self.problem(format_args!("root directory is free")),
+            _ => self.problem(format_args!("root is not a directory")),
+        }
+        for (ino, inode, blocks) in dirs {
+            self.check_directory(ino, &inode, &blocks)?;
+        }
+
+        for inum in 1..self.sb.ninodes.get() {
+            let ino = InodeNo::new(inum);
+            if self.types[ino.as_index()] == 0 {
+                continue;
+            }
+            let inode = self.img.read_inode(&self.sb, ino)?;
+            let refs = self.refs[ino.as_index()];
+            if refs == 0 {
+                // an orphan is freed by the kernel on the next mount.
+                if !self.sb.is_orphan(ino) {
+                    self.problem(format_args!("inode {ino}: allocated but not linked"));
+                }
+            } else if u32::from(inode.nlink.get()) != refs {
+                self.problem(format_args!(
+                    "inode {ino}: link count {} but {refs} references",
+                    inode.nlink
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn check_inode_bitmap(&mut self) -> Result<(), Ov6Error> {
+        if !self.sb.has_inode_bitmap() {
+            return Ok(());
+        }
+        let mut bmap = BmapBlock::zeroed();
+        for inum in 0..self.sb.ninodes.get() {
+            let ino = InodeNo::new(inum);
+            let bit = ino.as_index() % BITS_PER_BLOCK;
+            if bit == 0 {
+                self.img.read_block(self.sb.ibmap_block(ino), &mut bmap)?;
+            }
+            let allocated = bmap.is_allocated(bit);
+            // inode 0 is never used
+            let used = inum == 0 || self.types[ino.as_index()] != 0;
+            if used && !allocated {
+                self.problem(format_args!("inode {ino} in use but marked free"));
+            } else if !used && allocated {
+                self.problem(format_args!("inode {ino} marked in use but free"));
+            }
+        }
+        Ok(())
+    }
+
+    fn check_bitmap(&mut self) -> Result<(), Ov6Error> {
+        let data_start = self.sb.data_start();
+        let mut bmap = BmapBlock::zeroed();
+        for bn in 0..self.sb.size.get() {
+            let bit = usize::try_from(bn).unwrap() % BITS_PER_BLOCK;
+            if bit == 0 {
+                self.img.read_block(self.sb.bmap_block(bn), &mut bmap)?;
+            }
+            let allocated = bmap.is_allocated(bit);
+            let used = bn < data_start || self.referenced[usize::try_from(bn).unwrap()];
+            if used && !allocated {
+                self.problem(format_args!("block {bn} in use but marked free"));
+            } else if !used && allocated {
+                self.problem(format_args!("block {bn} marked in use but not referenced"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Checks the file system image, returning the number of problems found.
+fn check(img: &Image) -> Result<usize, Ov6Error> {
+    let sb = img.read_super_block()?;
+    let mut checker = Checker {
+        img,
+        sb,
+        referenced: Vec::new(),
+        refs: Vec::new(),
+        types: Vec::new(),
+        problems: 0,
+    };
+    // check the layout before allocating the tables sized by it
+    if !checker.check_super_block() {
+        return Ok(checker.problems);
+    }
+    checker.referenced = vec![false; usize::try_from(checker.sb.size.get()).unwrap()];
+    checker.refs = vec![0; usize::try_from(checker.sb.ninodes.get()).unwrap()];
+    checker.types = vec![0; usize::try_from(checker.sb.ninodes.get()).unwrap()];
+    if checker.sb.state.get() != SuperBlock::STATE_CLEAN {
+        message!("warning: file system was not cleanly unmounted");
+    }
+    checker.check_inodes()?;
+    checker.check_inode_bitmap()?;
+    checker.check_bitmap()?;
+
+    let inodes = checker.types.iter().filter(|&&ty| ty != 0).count();
+    let blocks = checker.referenced.iter().filter(|&&r| r).count();
+    println!(
+        "{inodes}/{} inodes, {blocks}/{} blocks, {} problems",
+        checker.sb.ninodes.get() - 1,
+        checker.sb.nblocks,
+        checker.problems,
+    );
+    Ok(checker.problems)
+}
+
+fn main() {
+    let mut paths = Vec::new();
+    let mut parser = Parser::new(&[], "<image>");
+    for arg in &mut parser {
+        match arg {
+            Arg::Positional(value) => paths.push(Path::new(value)),
+            Arg::Flag(_) | Arg::Value(..) => unreachable!(),
+        }
+    }
+    let [path] = paths[..] else {
+        parser.usage_error(format_args!("expected one image"));
+    };
+
+    let file = File::open(path).or_exit(|e| exit_err!(e, "cannot open '{}'", path.display()));
+    let img = Image::new(file);
+    let problems = check(&img).or_exit(|e| exit_err!(e, "cannot check '{}'", path.display()));
+
+    process::exit(i32::from(problems > 0));
+}
@@ -1,14 +1,25 @@
 #![no_std]
 
+extern crate alloc;
+
+use alloc::vec::Vec;
+
 use ov6_user_lib::{
-    env,
     fs::File,
     io::{self, Read},
     println,
 };
-use ov6_utilities::{OrExit as _, exit_err, message_err, usage_and_exit};
+use ov6_utilities::{
+    OrExit as _,
+    args::{Arg, Opt, Parser},
+    exit_err, message_err,
+};
+
+const OPTS: &[Opt] = &[Opt::flag("invert-match")
+    .short('v')
+    .help("Select non-matching lines")];
 
-fn grep<R>(pattern: &str, mut input: R, buf: &mut [u8])
+fn grep<R>(pattern: &str, invert: bool, mut input: R, buf: &mut [u8])
 where
     R: Read,
 {
@@ -28,7 +39,7 @@ where
         while let Some(i) = buf[consumed..filled].iter().position(|c| *c == b'\n') {
             let line = &buf[consumed..filled][..i];
             let line = str::from_utf8(line).or_exit(|e| exit_err!(e, "parse line error"));
-            if match_(pattern, line) {
+            if match_(pattern, line) != invert {
                 println!("{}", line);
             }
             consumed += i + 1;
@@ -42,26 +53,36 @@ where
 fn main() {
     let mut buf = [0; 1024];
 
-    let mut args = env::args_os();
-    let _ = args.next(); // skip the program name
+    let mut invert = false;
+    let mut args = Vec::new();
+
+    let mut parser = Parser::new(OPTS, "<pattern> [file...]");
+    for arg in &mut parser {
+        match arg {
+            Arg::Flag("invert-match") => invert = true,
+            Arg::Positional(arg) => args.push(arg),
+            _ => unreachable!(),
+        }
+    }
 
+    let mut args = args.into_iter();
     let Some(pattern) = args.next() else {
-        usage_and_exit!("pattern [file...]");
+        parser.usage_error(format_args!("missing pattern"));
     };
 
     let Some(pattern) = pattern.to_str() else {
-        usage_and_exit!("pattern must be valid UTF-8");
+        parser.usage_error(format_args!("pattern must be valid UTF-8"));
     };
 
     if args.len() == 0 {
         let stdin = io::stdin();
-        grep(pattern, stdin, &mut buf);
+        grep(pattern, invert, stdin, &mut buf);
     } else {
         let files = args.flat_map(|arg| {
             File::open(arg).inspect_err(|e| message_err!(e, "cannot open '{}'", arg.display()))
         });
         for file in files {
-            grep(pattern, file, &mut buf);
+            grep(pattern, invert, file, &mut buf);
         }
     }
 }
@@ -1,6 +1,9 @@
 #![no_std]
 
-use ov6_user_lib::{env, os::ov6::syscall};
+use ov6_user_lib::{
+    env,
+    os::ov6::syscall::{self, ShutdownRequest},
+};
 use ov6_utilities::{OrExit as _, exit_err, usage_and_exit};
 
 fn main() {
@@ -15,5 +18,6 @@ fn main() {
         s.parse().or_exit(|e| exit_err!(e, "invalid code '{s}'"))
     });
 
-    syscall::halt(code).or_exit(|e| exit_err!(e, "halt failed"));
+    // init stops the processes and commits the file system before halting.
+    syscall::request_shutdown(ShutdownRequest::Halt(code)).or_exit(|e| exit_err!(e, "halt failed"));
 }
@@ -0,0 +1,66 @@
+#![no_std]
+
+extern crate alloc;
+
+use alloc::string::String;
+
+use ov6_user_lib::{
+    fs::File,
+    io::Read as _,
+    os::ov6::syscall::{self, HOST_NAME_MAX},
+    os_str::OsStr,
+    println,
+};
+use ov6_utilities::{
+    OrExit as _,
+    args::{Arg, Opt, Parser},
+    exit, exit_err,
+};
+
+const OPTS: &[Opt] = &[Opt::value("file", "FILE")
+    .short('F')
+    .help("Set the host name to the first line of FILE")];
+
+fn set(name: &str) {
+    syscall::set_hostname(name).or_exit(|e| exit_err!(e, "cannot set host name '{name}'"));
+}
+
+fn main() {
+    let mut name = None;
+    let mut file = None;
+    let mut parser = Parser::new(OPTS, "[NAME]");
+    while let Some(arg) = parser.next() {
+        match arg {
+            Arg::Value("file", path) => file = Some(path),
+            Arg::Positional(s) if name.is_none() && file.is_none() => name = Some(s),
+            Arg::Positional(s) => {
+                parser.usage_error(format_args!("unexpected argument '{}'", s.display()));
+            }
+            Arg::Flag(_) | Arg::Value(..) => unreachable!(),
+        }
+    }
+
+    if let Some(path) = file {
+        let mut contents = String::new();
+        File::open(path)
+            .and_then(|mut file| file.read_to_string(&mut contents))
+            .or_exit(|e| exit_err!(e, "cannot read '{}'", path.display()));
+        set(contents.lines().next().unwrap_or("").trim());
+        return;
+    }
+
+    if let Some(name) = name {
+        let Some(name) = name.to_str() else {
+            exit!("invalid host name '{}'", name.display());
+        };
+        set(name);
+        return;
+    }
+
+    let mut buf = [0; HOST_NAME_MAX];
+    let len = syscall::get_hostname(&mut buf).or_exit(|e| exit_err!(e, "cannot get host name"));
+    println!(
+        "{}",
+        OsStr::from_bytes(&buf[..len.min(buf.len())]).display()
+    );
+}
@@ -0,0 +1,28 @@
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use ov6_user_lib::{env, os::ov6::syscall, os_str::OsStr, process};
+use ov6_utilities::{OrExit as _, exit, exit_err, usage_and_exit};
+
+fn main() {
+    let mut args = env::args_os();
+    let _ = args.next(); // skip the program name
+
+    if args.len() < 2 {
+        usage_and_exit!("<blocks-per-sec> <command...>");
+    }
+
+    let limit = args.next().unwrap();
+    let Some(limit) = limit.to_str().and_then(|s| s.parse().ok()) else {
+        exit!("invalid limit '{}'", limit.display());
+    };
+    syscall::set_io_limit(limit).or_exit(|e| exit_err!(e, "cannot set I/O limit"));
+
+    let args = args.collect::<Vec<_>>();
+    let arg0 = args.first().unwrap();
+    let Err(e) = process::exec_search(arg0, &args, OsStr::new(process::DEFAULT_PATH));
+    exit_err!(e, "failed to exec '{}'", arg0.display());
+}
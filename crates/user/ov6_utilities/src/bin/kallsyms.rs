@@ -0,0 +1,80 @@
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use ov6_user_lib::{
+    env,
+    io::{self, Write as _},
+    os::ov6::syscall,
+    println,
+};
+use ov6_utilities::{OrExit as _, exit, exit_err, message};
+
+/// Reads the whole kernel symbol table.
+fn read_table() -> Vec<u8> {
+    let mut table = Vec::new();
+    let mut buf = [0; 4096];
+    loop {
+        let n = syscall::get_kernel_symbols(&mut buf, table.len())
+            .or_exit(|e| exit_err!(e, "cannot read kernel symbols"));
+        if n == 0 {
+            break;
+        }
+        table.extend_from_slice(&buf[..n]);
+    }
+    table
+}
+
+fn parse_addr(s: &str) -> Option<usize> {
+    usize::from_str_radix(s.strip_prefix("0x").unwrap_or(s), 16).ok()
+}
+
+/// Returns the symbols in `table`, sorted by address.
+fn symbols(table: &str) -> Vec<(usize, &str)> {
+    table
+        .lines()
+        .filter_map(|line| {
+            let (addr, name) = line.split_once(' ')?;
+            Some((parse_addr(addr)?, name))
+        })
+        .collect()
+}
+
+fn main() {
+    let mut args = env::args();
+    let _ = args.next(); // skip the program name
+
+    let table = read_table();
+    let Ok(table) = str::from_utf8(&table) else {
+        exit!("kernel symbol table is not valid UTF-8");
+    };
+
+    let addrs = args.collect::<Vec<_>>();
+    if addrs.is_empty() {
+        io::stdout()
+            .write_all(table.as_bytes())
+            .or_exit(|e| exit_err!(e, "cannot write symbols"));
+        return;
+    }
+
+    let syms = symbols(table);
+    if syms.is_empty() {
+        exit!("no kernel symbols");
+    }
+    for arg in addrs {
+        let Some(addr) = parse_addr(arg) else {
+            message!("invalid address '{arg}'");
+            continue;
+        };
+        // the last symbol marks the end of the kernel text
+        let i = syms.partition_point(|(sym_addr, _)| *sym_addr <= addr);
+        if let (Some((sym_addr, name)), Some(_)) = (i.checked_sub(1).map(|i| syms[i]), syms.get(i))
+        {
+            println!("{addr:#x} {name}+{:#x}", addr - sym_addr);
+        } else {
+            println!("{addr:#x} ?");
+        }
+    }
+}
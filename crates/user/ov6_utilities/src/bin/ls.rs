@@ -1,84 +1,309 @@
 #![no_std]
 
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+
 use ov6_user_lib::{
-    env,
     fs::{self, Metadata, StatType},
-    os_str::OsStr,
+    io::STDOUT_FD,
+    os::ov6::syscall,
+    os_str::{OsStr, OsString},
     path::Path,
-    println, process,
+    print, println, process,
+};
+use ov6_utilities::{
+    args::{Arg, Opt, Parser},
+    message_err,
 };
-use ov6_utilities::message_err;
 
-fn print_entry(name: &OsStr, meta: &Metadata) {
-    let ty = match meta.ty() {
-        StatType::Dir => "dir",
-        StatType::File => "file",
-        StatType::Dev => "dev",
-    };
-    println!(
-        "{:16} {:4} {:6} {:12}",
-        name.display(),
-        ty,
-        meta.ino(),
-        meta.size(),
-    );
+const OPTS: &[Opt] = &[
+    Opt::flag("all")
+        .short('a')
+        .help("Do not ignore entries starting with '.'"),
+    Opt::flag("long")
+        .short('l')
+        .help("Use a long listing format"),
+    Opt::flag("inode")
+        .short('i')
+        .help("Print the inode number of each entry"),
+    Opt::flag("one-per-line")
+        .short('1')
+        .help("List one entry per line"),
+    Opt::flag("sort-size")
+        .short('S')
+        .help("Sort by file size, largest first"),
+    Opt::flag("sort-time")
+        .short('t')
+        .help("Sort by modification time, newest first"),
+];
+
+/// Separator between columns in the multi-column format.
+const COLUMN_GAP: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SortKey {
+    Name,
+    Size,
+    Time,
 }
 
-fn print_dir_entry(dir_path: &Path, ent_name: &OsStr) {
-    let file_path = dir_path.join(ent_name);
-    let Ok(meta) = fs::metadata(&file_path)
-        .inspect_err(|e| message_err!(e, "cannot stat '{}'", file_path.display()))
-    else {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Long,
+    OnePerLine,
+    Columns(usize),
+}
+
+#[expect(clippy::struct_excessive_bools)]
+struct Options {
+    all: bool,
+    long: bool,
+    inode: bool,
+    one_per_line: bool,
+    sort: SortKey,
+}
+
+struct Entry {
+    name: OsString,
+    meta: Metadata,
+}
+
+impl Options {
+    fn format(&self) -> Format {
+        if self.long {
+            return Format::Long;
+        }
+        if self.one_per_line {
+            return Format::OnePerLine;
+        }
+        // Multi-column output is only used when writing to a terminal.
+        syscall::window_size(STDOUT_FD)
+            .map_or(Format::OnePerLine, |size| Format::Columns(size.cols.into()))
+    }
+}
+
+fn type_char(ty: StatType) -> char {
+    match ty {
+        StatType::Dir => 'd',
+        StatType::File => '-',
+        StatType::Dev => 'c',
+        StatType::Socket => 's',
+    }
+}
+
+fn sort_entries(entries: &mut [Entry], key: SortKey) {
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    match key {
+        // The file system does not record modification times yet, so `-t` keeps
+        // the name order.
+        SortKey::Name | SortKey::Time => {}
+        SortKey::Size => entries.sort_by_key(|ent| Reverse(ent.meta.size())),
+    }
+}
+
+fn entry_label_width(ent: &Entry, opts: &Options, ino_width: usize) -> usize {
+    let ino = if opts.inode { ino_width + 1 } else { 0 };
+    ino + ent.name.len()
+}
+
+fn print_label(ent: &Entry, opts: &Options, ino_width: usize) {
+    if opts.inode {
+        print!("{:ino_width$} ", ent.meta.ino());
+    }
+    print!("{}", ent.name.display());
+}
+
+fn print_long(entries: &[Entry], opts: &Options, ino_width: usize) {
+    let nlink_width = max_digits(entries.iter().map(|ent| ent.meta.nlink().into()));
+    let size_width = max_digits(entries.iter().map(|ent| ent.meta.size()));
+    for ent in entries {
+        print!(
+            "{} {:nlink_width$} {:size_width$} ",
+            type_char(ent.meta.ty()),
+            ent.meta.nlink(),
+            ent.meta.size(),
+        );
+        print_label(ent, opts, ino_width);
+        println!();
+    }
+}
+
+fn print_columns(entries: &[Entry], opts: &Options, ino_width: usize, line_width: usize) {
+    if entries.is_empty() {
         return;
-    };
-    print_entry(ent_name, &meta);
+    }
+
+    let widths = entries
+        .iter()
+        .map(|ent| entry_label_width(ent, opts, ino_width))
+        .collect::<Vec<_>>();
+
+    // Find the largest number of columns that fits in the line, filling the
+    // columns from top to bottom.
+    let (nrows, col_widths) = (1..=widths.len())
+        .rev()
+        .map(|ncols| {
+            let nrows = widths.len().div_ceil(ncols);
+            let col_widths = widths
+                .chunks(nrows)
+                .map(|col| col.iter().copied().max().unwrap_or(0))
+                .collect::<Vec<_>>();
+            (nrows, col_widths)
+        })
+        .find(|(_, col_widths)| {
+            let total = col_widths.iter().sum::<usize>() + COLUMN_GAP * (col_widths.len() - 1);
+            col_widths.len() == 1 || total <= line_width
+        })
+        .unwrap();
+
+    for row in 0..nrows {
+        for (col, col_width) in col_widths.iter().enumerate() {
+            let idx = col * nrows + row;
+            let Some(ent) = entries.get(idx) else {
+                break;
+            };
+            print_label(ent, opts, ino_width);
+            if idx + nrows < entries.len() {
+                let pad = col_width - widths[idx] + COLUMN_GAP;
+                print!("{:pad$}", "");
+            }
+        }
+        println!();
+    }
 }
 
-fn ls<P>(path: P)
+fn max_digits<I>(values: I) -> usize
 where
-    P: AsRef<Path>,
+    I: IntoIterator<Item = u64>,
 {
-    let path = path.as_ref();
-    let Ok(meta) =
-        fs::metadata(path).inspect_err(|e| message_err!(e, "cannot stat '{}'", path.display()))
-    else {
-        return;
-    };
-
-    match meta.ty() {
-        StatType::File | StatType::Dev => print_entry(OsStr::new(path.to_str().unwrap()), &meta),
-        StatType::Dir => {
-            for name in [".", ".."] {
-                print_dir_entry(path, OsStr::new(name));
+    values
+        .into_iter()
+        .map(|v| {
+            let mut digits = 1;
+            let mut v = v / 10;
+            while v > 0 {
+                digits += 1;
+                v /= 10;
             }
+            digits
+        })
+        .max()
+        .unwrap_or(1)
+}
 
-            let Ok(entries) = fs::read_dir(path)
-                .inspect_err(|e| message_err!(e, "cannot open '{}' as directory", path.display()))
-            else {
-                return;
-            };
-            let entries = entries.flat_map(|ent| {
-                ent.inspect_err(|e| {
-                    message_err!(e, "cannot read directory '{}' entry", path.display());
-                })
-            });
-            for ent in entries {
-                print_dir_entry(path, ent.name());
+fn print_entries(entries: &mut [Entry], opts: &Options) {
+    sort_entries(entries, opts.sort);
+
+    let ino_width = max_digits(entries.iter().map(|ent| ent.meta.ino().into()));
+    match opts.format() {
+        Format::Long => print_long(entries, opts, ino_width),
+        Format::OnePerLine => {
+            for ent in &*entries {
+                print_label(ent, opts, ino_width);
+                println!();
             }
         }
+        Format::Columns(width) => print_columns(entries, opts, ino_width, width),
+    }
+}
+
+fn read_entry(dir_path: &Path, ent_name: &OsStr) -> Option<Entry> {
+    let file_path = dir_path.join(ent_name);
+    let meta = fs::metadata(&file_path)
+        .inspect_err(|e| message_err!(e, "cannot stat '{}'", file_path.display()))
+        .ok()?;
+    Some(Entry {
+        name: ent_name.to_os_string(),
+        meta,
+    })
+}
+
+fn read_dir_entries(path: &Path, opts: &Options) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    if opts.all {
+        for name in [".", ".."] {
+            entries.extend(read_entry(path, OsStr::new(name)));
+        }
+    }
+
+    let Ok(dir) = fs::read_dir(path)
+        .inspect_err(|e| message_err!(e, "cannot open '{}' as directory", path.display()))
+    else {
+        return entries;
+    };
+    let dir = dir.flat_map(|ent| {
+        ent.inspect_err(|e| {
+            message_err!(e, "cannot read directory '{}' entry", path.display());
+        })
+    });
+    for ent in dir {
+        if !opts.all && ent.name().as_bytes().starts_with(b".") {
+            continue;
+        }
+        entries.extend(read_entry(path, ent.name()));
     }
+    entries
 }
 
 fn main() {
-    let mut args = env::args_os();
-    let _ = args.next(); // skip the program name
+    let mut opts = Options {
+        all: false,
+        long: false,
+        inode: false,
+        one_per_line: false,
+        sort: SortKey::Name,
+    };
+    let mut paths = Vec::new();
+    for arg in Parser::new(OPTS, "[path...]") {
+        match arg {
+            Arg::Flag("all") => opts.all = true,
+            Arg::Flag("long") => opts.long = true,
+            Arg::Flag("inode") => opts.inode = true,
+            Arg::Flag("one-per-line") => opts.one_per_line = true,
+            Arg::Flag("sort-size") => opts.sort = SortKey::Size,
+            Arg::Flag("sort-time") => opts.sort = SortKey::Time,
+            Arg::Positional(path) => paths.push(Path::new(path)),
+            Arg::Flag(_) | Arg::Value(..) => unreachable!(),
+        }
+    }
+    if paths.is_empty() {
+        paths.push(Path::new("."));
+    }
 
-    if args.len() < 1 {
-        ls(".");
-        process::exit(0);
+    // List the files given on the command line first, then the contents of each
+    // directory.
+    let mut files = Vec::new();
+    let mut dirs = Vec::new();
+    for path in paths {
+        let Ok(meta) =
+            fs::metadata(path).inspect_err(|e| message_err!(e, "cannot stat '{}'", path.display()))
+        else {
+            continue;
+        };
+        match meta.ty() {
+            StatType::File | StatType::Dev | StatType::Socket => files.push(Entry {
+                name: path.as_os_str().to_os_string(),
+                meta,
+            }),
+            StatType::Dir => dirs.push(path),
+        }
     }
-    for arg in args {
-        ls(arg);
+
+    let show_headers = files.len() + dirs.len() > 1;
+    if !files.is_empty() {
+        print_entries(&mut files, &opts);
+    }
+    for (i, dir) in dirs.iter().enumerate() {
+        if show_headers {
+            if i > 0 || !files.is_empty() {
+                println!();
+            }
+            println!("{}:", dir.display());
+        }
+        let mut entries = read_dir_entries(dir, &opts);
+        print_entries(&mut entries, &opts);
     }
     process::exit(0);
 }
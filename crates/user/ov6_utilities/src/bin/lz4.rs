@@ -0,0 +1,187 @@
+#![no_std]
+
+extern crate alloc;
+
+use alloc::{vec, vec::Vec};
+
+use lz4::{BLOCK_SIZE, DecompressError, FrameDecoder};
+use ov6_user_lib::{
+    error::Ov6Error,
+    fs::{self, File},
+    io::{self, Read, Write},
+    os_str::{OsStr, OsString},
+    process,
+};
+use ov6_utilities::{
+    args::{Arg, Opt, Parser},
+    compress::Lz4Writer,
+    message, message_err,
+};
+
+const OPTS: &[Opt] = &[
+    Opt::flag("decompress").short('d').help("Decompress"),
+    Opt::flag("stdout")
+        .short('c')
+        .help("Write to standard output and keep the input files"),
+    Opt::flag("keep").short('k').help("Keep the input files"),
+];
+
+const SUFFIX: &str = ".lz4";
+
+enum Error {
+    Read(Ov6Error),
+    Write(Ov6Error),
+    Data(DecompressError),
+}
+
+fn compress<R, W>(mut input: R, output: W) -> Result<(), Error>
+where
+    R: Read,
+    W: Write,
+{
+    let mut output = Lz4Writer::new(output).map_err(Error::Write)?;
+    let mut buf = vec![0; BLOCK_SIZE];
+    loop {
+        let n = input.read(&mut buf).map_err(Error::Read)?;
+        if n == 0 {
+            break;
+        }
+        output.write_all(&buf[..n]).map_err(Error::Write)?;
+    }
+    output.finish().map_err(Error::Write)?;
+    Ok(())
+}
+
+fn decompress<R, W>(mut input: R, mut output: W) -> Result<(), Error>
+where
+    R: Read,
+    W: Write,
+{
+    let mut decoder = FrameDecoder::new();
+    let mut buf = vec![0; BLOCK_SIZE];
+    let mut out = Vec::new();
+    loop {
+        let n = input.read(&mut buf).map_err(Error::Read)?;
+        if n == 0 {
+            break;
+        }
+        decoder.decode(&buf[..n], &mut out).map_err(Error::Data)?;
+        output.write_all(&out).map_err(Error::Write)?;
+        out.clear();
+    }
+    decoder.finish().map_err(Error::Data)?;
+    output.flush().map_err(Error::Write)?;
+    Ok(())
+}
+
+fn run<R, W>(input: R, output: W, decompress_mode: bool) -> Result<(), Error>
+where
+    R: Read,
+    W: Write,
+{
+    if decompress_mode {
+        decompress(input, output)
+    } else {
+        compress(input, output)
+    }
+}
+
+/// Returns the name of the file `path` is compressed to or decompressed to.
+fn output_path(path: &OsStr, decompress_mode: bool) -> Option<OsString> {
+    let bytes = path.as_bytes();
+    if decompress_mode {
+        let stem = bytes.strip_suffix(SUFFIX.as_bytes())?;
+        if stem.is_empty() || stem.ends_with(b"/") {
+            return None;
+        }
+        Some(OsStr::from_bytes(stem).to_os_string())
+    } else {
+        let mut out = path.to_os_string();
+        out.push(SUFFIX);
+        Some(out)
+    }
+}
+
+fn report(e: &Error, input: &OsStr, output: &OsStr) {
+    match e {
+        Error::Read(e) => message_err!(e, "cannot read '{}'", input.display()),
+        Error::Write(e) => message_err!(e, "cannot write '{}'", output.display()),
+        Error::Data(e) => message_err!(e, "'{}'", input.display()),
+    }
+}
+
+fn main() {
+    let mut decompress_mode = false;
+    let mut to_stdout = false;
+    let mut keep = false;
+    let mut paths = Vec::new();
+
+    for arg in Parser::new(OPTS, "[file...]") {
+        match arg {
+            Arg::Flag("decompress") => decompress_mode = true,
+            Arg::Flag("stdout") => to_stdout = true,
+            Arg::Flag("keep") => keep = true,
+            Arg::Positional(path) => paths.push(path),
+            _ => unreachable!(),
+        }
+    }
+    if paths.is_empty() {
+        paths.push(OsStr::new("-"));
+    }
+
+    let stdin_name = OsStr::new("(stdin)");
+    let stdout_name = OsStr::new("(stdout)");
+    let mut status = 0;
+    for path in paths {
+        if path == "-" {
+            if let Err(e) = run(io::stdin().lock(), io::stdout().lock(), decompress_mode) {
+                report(&e, stdin_name, stdout_name);
+                status = 1;
+            }
+            continue;
+        }
+
+        let input = match File::open(path) {
+            Ok(file) => file,
+            Err(e) => {
+                message_err!(e, "cannot open '{}'", path.display());
+                status = 1;
+                continue;
+            }
+        };
+
+        if to_stdout {
+            if let Err(e) = run(input, io::stdout().lock(), decompress_mode) {
+                report(&e, path, stdout_name);
+                status = 1;
+            }
+            continue;
+        }
+
+        let Some(out_path) = output_path(path, decompress_mode) else {
+            message!("'{}': unknown suffix, ignored", path.display());
+            status = 1;
+            continue;
+        };
+        let output = match File::create(&out_path) {
+            Ok(file) => file,
+            Err(e) => {
+                message_err!(e, "cannot create '{}'", out_path.display());
+                status = 1;
+                continue;
+            }
+        };
+        if let Err(e) = run(input, output, decompress_mode) {
+            report(&e, path, &out_path);
+            let _ = fs::remove_file(&out_path);
+            status = 1;
+            continue;
+        }
+        if !keep && let Err(e) = fs::remove_file(path) {
+            message_err!(e, "cannot remove '{}'", path.display());
+            status = 1;
+        }
+    }
+
+    process::exit(status);
+}
@@ -1,21 +1,64 @@
 #![no_std]
 
-use ov6_user_lib::{env, fs, process};
-use ov6_utilities::{message_err, usage_and_exit};
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use ov6_user_lib::{error::Ov6Error, fs, path::Path, process};
+use ov6_utilities::{
+    args::{Arg, Opt, Parser},
+    message_err,
+};
+
+const OPTS: &[Opt] = &[Opt::flag("parents")
+    .short('p')
+    .help("Make parent directories as needed, no error if existing")];
+
+fn create_dir_all(path: &Path) -> Result<(), Ov6Error> {
+    let mut ancestors = path.ancestors().collect::<Vec<_>>();
+    ancestors.reverse();
+    for dir in ancestors {
+        if dir.parent().is_none() {
+            // empty path or root directory
+            continue;
+        }
+        match fs::create_dir(dir) {
+            Ok(()) | Err(Ov6Error::AlreadyExists) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
 
 fn main() {
-    let mut args = env::args_os();
-    let _ = args.next(); // skip the program name
+    let mut parents = false;
+    let mut dirs = Vec::new();
+
+    let mut parser = Parser::new(OPTS, "<dir...>");
+    for arg in &mut parser {
+        match arg {
+            Arg::Flag("parents") => parents = true,
+            Arg::Positional(dir) => dirs.push(Path::new(dir)),
+            _ => unreachable!(),
+        }
+    }
 
-    if args.len() < 1 {
-        usage_and_exit!("files...");
+    if dirs.is_empty() {
+        parser.usage_error(format_args!("missing operand"));
     }
 
-    for arg in args {
-        if let Err(e) = fs::create_dir(arg) {
-            message_err!(e, "cannot create directory '{}'", arg.display());
+    let mut status = 0;
+    for dir in dirs {
+        let res = if parents {
+            create_dir_all(dir)
+        } else {
+            fs::create_dir(dir)
+        };
+        if let Err(e) = res {
+            message_err!(e, "cannot create directory '{}'", dir.display());
+            status = 1;
         }
     }
 
-    process::exit(0);
+    process::exit(status);
 }
@@ -0,0 +1,49 @@
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use ov6_user_lib::{os::ov6::syscall, path::Path, println};
+use ov6_utilities::{
+    OrExit as _,
+    args::{Arg, Opt, Parser},
+    exit_err,
+};
+
+const OPTS: &[Opt] = &[Opt::value("size", "BYTES")
+    .short('s')
+    .help("Size limit of the file system [default: that of the type]")];
+
+fn main() {
+    let mut size = None;
+    let mut args = Vec::new();
+    let mut parser = Parser::new(OPTS, "<type> <dir>");
+    for arg in &mut parser {
+        match arg {
+            Arg::Value("size", value) => size = Some(value),
+            Arg::Positional(value) => args.push(value),
+            Arg::Flag(_) | Arg::Value(..) => unreachable!(),
+        }
+    }
+    let [fstype, dir] = args[..] else {
+        parser.usage_error(format_args!("expected a type and a directory"));
+    };
+    let fstype = fstype.to_str().unwrap_or_else(|| {
+        parser.usage_error(format_args!("invalid type: '{}'", fstype.display()))
+    });
+    let size = size.map(|value| {
+        value
+            .to_str()
+            .and_then(|s| s.parse().ok())
+            .filter(|&n| n > 0)
+            .unwrap_or_else(|| {
+                parser.usage_error(format_args!("invalid size: '{}'", value.display()))
+            })
+    });
+
+    let dir = Path::new(dir);
+    syscall::mount(fstype, dir, size)
+        .or_exit(|e| exit_err!(e, "cannot mount {fstype} on '{}'", dir.display()));
+    println!("mounted {fstype} on {}", dir.display());
+}
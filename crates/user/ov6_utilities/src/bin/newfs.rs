@@ -0,0 +1,212 @@
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::array;
+
+use dataview::PodMethods as _;
+use ov6_fs_types::{
+    BITS_PER_BLOCK, BlockNo, BmapBlock, DirEntry, FS_BLOCK_SIZE, FS_LABEL_MAX, FsFeatures, FsUuid,
+    INODE_PER_BLOCK, Inode, InodeNo, Le, MAX_FILE, MAX_ORPHANS, SuperBlock, T_DIR, encode_label,
+};
+use ov6_kernel_params::FS_LOG_SIZE;
+use ov6_user_lib::{
+    error::Ov6Error, fs::File, os::ov6::syscall, os_str::OsStr, path::Path, println, process,
+};
+use ov6_utilities::{
+    OrExit as _,
+    args::{Arg, Opt, Parser},
+    exit_err,
+    fs_image::Image,
+};
+
+const OPTS: &[Opt] = &[
+    Opt::value("label", "LABEL")
+        .short('L')
+        .help("Set the label of the file system"),
+    Opt::value("blocks", "N")
+        .short('s')
+        .help("Size of the file system in blocks"),
+    Opt::value("inodes", "N")
+        .short('i')
+        .help("Number of inodes"),
+];
+
+const DEFAULT_INODES: u32 = 64;
+
+/// Generates a (version 4) UUID from the uptime and the process ID.
+///
+/// ov6 has no source of randomness, so this only makes collisions unlikely.
+fn generate_uuid() -> FsUuid {
+    // splitmix64
+    let mut state = syscall::uptime() ^ (u64::from(process::id().get().get()) << 32);
+    let mut next = || {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    };
+    let mut bytes = [0; 16];
+    for chunk in bytes.chunks_mut(8) {
+        chunk.copy_from_slice(&next().to_le_bytes());
+    }
+    bytes[6] = (bytes[6] & 0x0f) | 0x40; // version 4
+    bytes[8] = (bytes[8] & 0x3f) | 0x80; // variant 1
+    FsUuid::from_bytes(bytes)
+}
+
+fn parse_count(parser: &Parser, name: &str, value: &OsStr) -> u32 {
+    value
+        .to_str()
+        .and_then(|s| s.parse().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or_else(|| {
+            parser.usage_error(format_args!("invalid {name}: '{}'", value.display()))
+        })
+}
+
+/// Computes the layout of a file system with `blocks` blocks and `inodes`
+/// inodes.
+///
+/// Returns `None` if no data block is left.
+fn layout(blocks: u32, inodes: u32, uuid: FsUuid, label: [u8; FS_LABEL_MAX]) -> Option<SuperBlock> {
+    let nlog = u32::try_from(FS_LOG_SIZE).unwrap();
+    let ninode_blocks = inodes / u32::try_from(INODE_PER_BLOCK).unwrap() + 1;
+    let nibmap_blocks = inodes / u32::try_from(BITS_PER_BLOCK).unwrap() + 1;
+    let nbmap_blocks = blocks / u32::try_from(BITS_PER_BLOCK).unwrap() + 1;
+    // boot block, super block, log, inodes, inode map and bitmap
+    let nmeta = 2 + nlog + ninode_blocks + nibmap_blocks + nbmap_blocks;
+    let nblocks = blocks.checked_sub(nmeta).filter(|&n| n > 0)?;
+    Some(SuperBlock {
+        magic: Le::<u32>::new(SuperBlock::FS_MAGIC),
+        size: Le::<u32>::new(blocks),
+        nblocks: Le::<u32>::new(nblocks),
+        ninodes: Le::<u32>::new(inodes),
+        nlog: Le::<u32>::new(nlog),
+        logstart: Le::<u32>::new(2),
+        inodestart: Le::<u32>::new(2 + nlog),
+        bmapstart: Le::<u32>::new(2 + nlog + ninode_blocks + nibmap_blocks),
+        state: Le::<u32>::new(SuperBlock::STATE_CLEAN),
+        mount_count: Le::<u32>::new(0),
+        last_mount_time: Le::<u64>::new(0),
+        uuid,
+        label,
+        version: Le::<u32>::new(SuperBlock::FS_VERSION),
+        features: FsFeatures {
+            ro_compat: Le::<u32>::new(
+                FsFeatures::RO_COMPAT_INODE_BITMAP | FsFeatures::RO_COMPAT_ORPHAN_LIST,
+            ),
+            ..FsFeatures::default()
+        },
+        ibmapstart: Le::<u32>::new(2 + nlog + ninode_blocks),
+        reserved: Le::<u32>::new(0),
+        orphans: [Le::<u32>::new(0); MAX_ORPHANS],
+    })
+}
+
+fn format(img: &Image, sb: &SuperBlock) -> Result<(), Ov6Error> {
+    let zero = [0; FS_BLOCK_SIZE];
+    for bn in 0..sb.size.get() {
+        img.write_block(BlockNo::new(bn), &zero)?;
+    }
+    img.write_super_block(sb)?;
+
+    // The root directory has `.` and `..` referring to itself, in the first
+    // data block.
+    let root_bn = BlockNo::new(sb.data_start());
+    let mut root = Inode::zeroed();
+    root.allocate(T_DIR);
+    root.nlink.set(1);
+    root.size.set(u32::try_from(FS_BLOCK_SIZE).unwrap());
+    root.addrs[0].set(root_bn.value());
+    img.write_inode(sb, InodeNo::ROOT, &root)?;
+
+    // inode 0 is never used
+    let mut ibmap = BmapBlock::zeroed();
+    ibmap.allocate(0);
+    ibmap.allocate(InodeNo::ROOT.as_index());
+    img.write_block(sb.ibmap_block(InodeNo::ROOT), &ibmap)?;
+
+    let mut entries: [_; FS_BLOCK_SIZE / size_of::<DirEntry>()] =
+        array::from_fn(|_| DirEntry::zeroed());
+    for (ent, name) in entries.iter_mut().zip([".", ".."]) {
+        ent.set_ino(Some(InodeNo::ROOT));
+        ent.set_name(OsStr::new(name));
+    }
+    img.write_block(root_bn, &entries)?;
+
+    // mark the meta blocks and the root directory block as used
+    let used = root_bn.value() + 1;
+    let bits_per_block = u32::try_from(BITS_PER_BLOCK).unwrap();
+    for start in (0..used).step_by(BITS_PER_BLOCK) {
+        let mut bmap = BmapBlock::zeroed();
+        for bn in start..u32::min(used, start + bits_per_block) {
+            bmap.allocate(usize::try_from(bn - start).unwrap());
+        }
+        img.write_block(sb.bmap_block(start), &bmap)?;
+    }
+    Ok(())
+}
+
+fn main() {
+    let mut label = None;
+    let mut blocks = None;
+    let mut inodes = None;
+    let mut paths = Vec::new();
+
+    let mut parser = Parser::new(OPTS, "<image>");
+    for arg in &mut parser {
+        match arg {
+            Arg::Value("label", value) => label = Some(value),
+            Arg::Value("blocks", value) => blocks = Some(value),
+            Arg::Value("inodes", value) => inodes = Some(value),
+            Arg::Positional(value) => paths.push(Path::new(value)),
+            Arg::Flag(_) | Arg::Value(..) => unreachable!(),
+        }
+    }
+    let [path] = paths[..] else {
+        parser.usage_error(format_args!("expected one image"));
+    };
+
+    let label = label.map_or([0; FS_LABEL_MAX], |label| {
+        encode_label(label.as_bytes()).unwrap_or_else(|| {
+            parser.usage_error(format_args!("invalid label: '{}'", label.display()))
+        })
+    });
+    // the largest image that fits in a file on ov6 by default
+    let blocks = blocks.map_or_else(
+        || u32::try_from(MAX_FILE).unwrap(),
+        |n| parse_count(&parser, "size", n),
+    );
+    let inodes = inodes.map_or(DEFAULT_INODES, |n| parse_count(&parser, "inode count", n));
+    // directory entries hold 16-bit inode numbers
+    if inodes > u32::from(u16::MAX) {
+        parser.usage_error(format_args!("too many inodes: {inodes}"));
+    }
+
+    let uuid = generate_uuid();
+    let Some(sb) = layout(blocks, inodes, uuid, label) else {
+        parser.usage_error(format_args!("too small file system: {blocks} blocks"));
+    };
+
+    let file = File::options()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)
+        .or_exit(|e| exit_err!(e, "cannot open '{}'", path.display()));
+    let img = Image::new(file);
+    format(&img, &sb).or_exit(|e| exit_err!(e, "cannot format '{}'", path.display()));
+
+    println!("uuid {uuid} label '{}'", sb.label().display());
+    println!(
+        "blocks {} (meta {}, data {}) inodes {}",
+        sb.size,
+        sb.data_start(),
+        sb.nblocks,
+        sb.ninodes,
+    );
+}
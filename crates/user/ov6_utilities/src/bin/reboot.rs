@@ -1,8 +1,9 @@
 #![no_std]
 
-use ov6_user_lib::os::ov6::syscall;
+use ov6_user_lib::os::ov6::syscall::{self, ShutdownRequest};
 use ov6_utilities::{OrExit as _, exit_err};
 
 fn main() {
-    syscall::reboot().or_exit(|e| exit_err!(e, "reboot failed"));
+    // init stops the processes and commits the file system before rebooting.
+    syscall::request_shutdown(ShutdownRequest::Reboot).or_exit(|e| exit_err!(e, "reboot failed"));
 }
@@ -0,0 +1,46 @@
+#![no_std]
+
+use ov6_user_lib::{
+    os::ov6::syscall::{self, MountFlags},
+    println,
+};
+use ov6_utilities::{
+    OrExit as _,
+    args::{Arg, Opt, Parser},
+    exit_err,
+};
+
+const OPTS: &[Opt] = &[
+    Opt::flag("read-only")
+        .short('r')
+        .help("Remount the file system read-only"),
+    Opt::flag("read-write")
+        .short('w')
+        .help("Remount the file system writable (default)"),
+];
+
+fn main() {
+    let mut read_only = false;
+    let mut parser = Parser::new(OPTS, "");
+    while let Some(arg) = parser.next() {
+        match arg {
+            Arg::Flag("read-only") => read_only = true,
+            Arg::Flag("read-write") => read_only = false,
+            Arg::Positional(s) => {
+                parser.usage_error(format_args!("unexpected argument '{}'", s.display()));
+            }
+            Arg::Flag(_) | Arg::Value(..) => unreachable!(),
+        }
+    }
+
+    let flags = if read_only {
+        MountFlags::READ_ONLY
+    } else {
+        MountFlags::empty()
+    };
+    syscall::remount(flags).or_exit(|e| exit_err!(e, "cannot remount file system"));
+    println!(
+        "remounted {}",
+        if read_only { "read-only" } else { "read-write" }
+    );
+}
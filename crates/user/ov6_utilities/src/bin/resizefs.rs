@@ -0,0 +1,40 @@
+#![no_std]
+
+use ov6_user_lib::{os::ov6::syscall, println};
+use ov6_utilities::{
+    OrExit as _,
+    args::{Arg, Opt, Parser},
+    exit_err,
+};
+
+const OPTS: &[Opt] = &[Opt::value("blocks", "N")
+    .short('s')
+    .help("New size of the file system in blocks [default: the whole disk]")];
+
+fn main() {
+    let mut blocks = None;
+    let mut extra = None;
+    let mut parser = Parser::new(OPTS, "");
+    for arg in &mut parser {
+        match arg {
+            Arg::Value("blocks", value) => blocks = Some(value),
+            Arg::Positional(value) => extra = Some(value),
+            Arg::Flag(_) | Arg::Value(..) => unreachable!(),
+        }
+    }
+    if let Some(extra) = extra {
+        parser.usage_error(format_args!("unexpected argument '{}'", extra.display()));
+    }
+    let blocks = blocks.map(|value| {
+        value
+            .to_str()
+            .and_then(|s| s.parse().ok())
+            .filter(|&n| n > 0)
+            .unwrap_or_else(|| {
+                parser.usage_error(format_args!("invalid size: '{}'", value.display()))
+            })
+    });
+
+    let size = syscall::resize_fs(blocks).or_exit(|e| exit_err!(e, "cannot resize file system"));
+    println!("resized to {size} blocks");
+}
@@ -1,21 +1,91 @@
 #![no_std]
 
-use ov6_user_lib::{env, fs, process};
-use ov6_utilities::{message_err, usage_and_exit};
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use ov6_user_lib::{
+    error::Ov6Error,
+    fs::{self, Dir},
+    os_str::OsStr,
+    process,
+};
+use ov6_utilities::{
+    OrExit as _,
+    args::{Arg, Opt, Parser},
+    exit_err, message_err,
+};
+
+const OPTS: &[Opt] = &[
+    Opt::flag("force")
+        .short('f')
+        .help("Ignore nonexistent files"),
+    Opt::flag("dir").short('d').help("Remove empty directories"),
+    Opt::flag("recursive")
+        .short('r')
+        .help("Remove directories and their contents recursively"),
+];
+
+/// Removes the directory `name` in `parent` and everything below it.
+///
+/// The tree is walked through directory handles, so it is not affected by
+/// renames of the directories above the one being emptied.
+fn remove_tree(parent: &Dir, name: &OsStr) -> Result<(), Ov6Error> {
+    let dir = parent.open_dir(name)?;
+    let names = dir
+        .read_dir()?
+        .map(|ent| ent.map(|ent| ent.name().to_os_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+    for name in names {
+        match dir.remove_file(&name) {
+            Err(Ov6Error::IsADirectory) => remove_tree(&dir, &name)?,
+            res => res?,
+        }
+    }
+    drop(dir);
+    parent.remove_dir(name)
+}
 
 fn main() {
-    let mut args = env::args_os();
-    let _ = args.next(); // skip the program name
+    let mut force = false;
+    let mut dir = false;
+    let mut recursive = false;
+    let mut files = Vec::new();
 
-    if args.len() < 1 {
-        usage_and_exit!("files...");
+    let mut parser = Parser::new(OPTS, "<file...>");
+    for arg in &mut parser {
+        match arg {
+            Arg::Flag("force") => force = true,
+            Arg::Flag("dir") => dir = true,
+            Arg::Flag("recursive") => recursive = true,
+            Arg::Positional(file) => files.push(file),
+            _ => unreachable!(),
+        }
     }
 
-    for arg in args {
-        if let Err(e) = fs::remove_file(arg) {
-            message_err!(e, "cannot delete file '{}'", arg.display());
+    if files.is_empty() && !force {
+        parser.usage_error(format_args!("missing operand"));
+    }
+
+    let cwd = recursive
+        .then(|| Dir::open(".").or_exit(|e| exit_err!(e, "cannot open current directory")));
+
+    let mut status = 0;
+    for file in files {
+        let res = match fs::remove_file(file) {
+            Err(Ov6Error::IsADirectory) if recursive => remove_tree(cwd.as_ref().unwrap(), file),
+            Err(Ov6Error::IsADirectory) if dir => fs::remove_dir(file),
+            res => res,
+        };
+        match res {
+            Ok(()) => {}
+            Err(Ov6Error::FsEntryNotFound) if force => {}
+            Err(e) => {
+                message_err!(e, "cannot delete file '{}'", file.display());
+                status = 1;
+            }
         }
     }
 
-    process::exit(0);
+    process::exit(status);
 }
@@ -0,0 +1,59 @@
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use ov6_user_lib::{error::Ov6Error, fs, path::Path, process};
+use ov6_utilities::{
+    args::{Arg, Opt, Parser},
+    message_err,
+};
+
+const OPTS: &[Opt] = &[Opt::flag("parents")
+    .short('p')
+    .help("Remove the directory and its ancestors")];
+
+fn remove_dir_all_parents(path: &Path) -> Result<(), Ov6Error> {
+    for dir in path.ancestors() {
+        if dir.parent().is_none() {
+            // empty path or root directory
+            break;
+        }
+        fs::remove_dir(dir)?;
+    }
+    Ok(())
+}
+
+fn main() {
+    let mut parents = false;
+    let mut dirs = Vec::new();
+
+    let mut parser = Parser::new(OPTS, "<dir...>");
+    for arg in &mut parser {
+        match arg {
+            Arg::Flag("parents") => parents = true,
+            Arg::Positional(dir) => dirs.push(Path::new(dir)),
+            _ => unreachable!(),
+        }
+    }
+
+    if dirs.is_empty() {
+        parser.usage_error(format_args!("missing operand"));
+    }
+
+    let mut status = 0;
+    for dir in dirs {
+        let res = if parents {
+            remove_dir_all_parents(dir)
+        } else {
+            fs::remove_dir(dir)
+        };
+        if let Err(e) = res {
+            message_err!(e, "cannot remove directory '{}'", dir.display());
+            status = 1;
+        }
+    }
+
+    process::exit(status);
+}
@@ -1,47 +1,209 @@
-use alloc::borrow::Cow;
+use alloc::vec::Vec;
 use core::str::FromStr as _;
 
 use ov6_user_lib::{
-    env,
-    os_str::OsStr,
+    env, fs,
+    os::ov6::syscall::{self, AccessMode, UMASK_BITS},
+    os_str::{OsStr, OsString},
+    path::Path,
+    println,
     process::{self, ExitStatus, ProcId, ProcessBuilder},
 };
 use ov6_utilities::{message, message_err};
 
-use crate::run::{self, RunError};
+use crate::{
+    run::{self, RunError},
+    shell::Shell,
+};
+
+/// Names of the builtin commands.
+pub(super) const BUILTINS: &[&str] = &["[", "cd", "exit", "pwd", "test", "umask", "wait"];
 
 pub(super) fn run_builtin(
-    argv: &[Cow<'_, OsStr>],
+    sh: &mut Shell,
+    argv: &[OsString],
     background: bool,
 ) -> Result<Option<ExitStatus>, RunError> {
     let f = match argv[0].as_bytes() {
         b"cd" => builtin_cd,
+        b"exit" => builtin_exit,
+        b"pwd" => builtin_pwd,
+        b"test" | b"[" => builtin_test,
+        b"umask" => builtin_umask,
         b"wait" => builtin_wait,
         _ => return Ok(None),
     };
     if background {
-        let child = run::spawn_fn(ProcessBuilder::new(), || Ok(f(argv)))?;
+        let child = run::spawn_fn(ProcessBuilder::new(), || Ok(f(sh, argv)))?;
         run::wait(child, background).map(Some)
     } else {
-        let status = f(argv);
+        let status = f(sh, argv);
         Ok(Some(status))
     }
 }
 
-fn builtin_cd(argv: &[Cow<'_, OsStr>]) -> ExitStatus {
+fn builtin_cd(_sh: &mut Shell, argv: &[OsString]) -> ExitStatus {
     if argv.len() != 2 {
         message!("Usage: cd <dir>");
         return ExitStatus::new(2);
     }
     let dir = &argv[1];
-    if let Err(e) = env::set_current_directory(dir) {
+    if let Err(e) = env::set_current_directory(Path::new(dir).normalize()) {
         message_err!(e, "cannot cd to '{}'", dir.display());
         return ExitStatus::new(1);
     }
     ExitStatus::new(0)
 }
 
-fn builtin_wait(argv: &[Cow<'_, OsStr>]) -> ExitStatus {
+fn builtin_pwd(_sh: &mut Shell, argv: &[OsString]) -> ExitStatus {
+    if argv.len() != 1 {
+        message!("Usage: pwd");
+        return ExitStatus::new(2);
+    }
+    match env::current_directory() {
+        Ok(cwd) => {
+            println!("{}", cwd.display());
+            ExitStatus::new(0)
+        }
+        Err(e) => {
+            message_err!(e, "cannot get current directory");
+            ExitStatus::new(1)
+        }
+    }
+}
+
+fn builtin_exit(sh: &mut Shell, argv: &[OsString]) -> ExitStatus {
+    let code = match argv {
+        [_] => sh.status().code(),
+        [_, code] => {
+            let Some(code) = code.to_str().and_then(|s| i32::from_str(s).ok()) else {
+                message!("invalid exit status '{}'", code.display());
+                return ExitStatus::new(2);
+            };
+            code
+        }
+        _ => {
+            message!("Usage: exit [status]");
+            return ExitStatus::new(2);
+        }
+    };
+    process::exit(code);
+}
+
+#[derive(Debug, thiserror::Error)]
+enum TestError {
+    #[error("missing ']'")]
+    MissingBracket,
+    #[error("unknown operator '{}'", .0.display())]
+    UnknownOperator(OsString),
+    #[error("integer expression expected: '{}'", .0.display())]
+    NotInteger(OsString),
+    #[error("too many arguments")]
+    TooManyArguments,
+}
+
+fn builtin_test(_sh: &mut Shell, argv: &[OsString]) -> ExitStatus {
+    let mut args = argv[1..]
+        .iter()
+        .map(OsString::as_os_str)
+        .collect::<Vec<_>>();
+    let result = if argv[0] == "[" && args.pop().is_none_or(|last| last != "]") {
+        Err(TestError::MissingBracket)
+    } else {
+        eval_test(&args)
+    };
+    match result {
+        Ok(true) => ExitStatus::new(0),
+        Ok(false) => ExitStatus::new(1),
+        Err(e) => {
+            message_err!(e);
+            ExitStatus::new(2)
+        }
+    }
+}
+
+fn is_binary_op(op: &OsStr) -> bool {
+    ["=", "!=", "-eq", "-ne", "-lt", "-le", "-gt", "-ge"]
+        .iter()
+        .any(|s| op == *s)
+}
+
+fn eval_test(args: &[&OsStr]) -> Result<bool, TestError> {
+    match args {
+        [] => Ok(false),
+        [s] => Ok(!s.is_empty()),
+        [lhs, op, rhs] if is_binary_op(op) => eval_binary(lhs, op, rhs),
+        [not, rest @ ..] if *not == "!" => eval_test(rest).map(|b| !b),
+        [op, operand] => eval_unary(op, operand),
+        _ => Err(TestError::TooManyArguments),
+    }
+}
+
+fn eval_unary(op: &OsStr, operand: &OsStr) -> Result<bool, TestError> {
+    let res = match op.as_bytes() {
+        b"-n" => !operand.is_empty(),
+        b"-z" => operand.is_empty(),
+        b"-e" => fs::metadata(Path::new(operand)).is_ok(),
+        b"-f" => fs::metadata(Path::new(operand)).is_ok_and(|meta| meta.is_file()),
+        b"-d" => fs::metadata(Path::new(operand)).is_ok_and(|meta| meta.is_dir()),
+        b"-r" => syscall::access(Path::new(operand), AccessMode::READ).is_ok(),
+        b"-w" => syscall::access(Path::new(operand), AccessMode::WRITE).is_ok(),
+        b"-x" => syscall::access(Path::new(operand), AccessMode::EXECUTE).is_ok(),
+        _ => return Err(TestError::UnknownOperator(op.to_os_string())),
+    };
+    Ok(res)
+}
+
+fn eval_binary(lhs: &OsStr, op: &OsStr, rhs: &OsStr) -> Result<bool, TestError> {
+    let int = |s: &OsStr| {
+        s.to_str()
+            .and_then(|s| i64::from_str(s).ok())
+            .ok_or_else(|| TestError::NotInteger(s.to_os_string()))
+    };
+    let res = match op.as_bytes() {
+        b"=" => lhs == rhs,
+        b"!=" => lhs != rhs,
+        b"-eq" => int(lhs)? == int(rhs)?,
+        b"-ne" => int(lhs)? != int(rhs)?,
+        b"-lt" => int(lhs)? < int(rhs)?,
+        b"-le" => int(lhs)? <= int(rhs)?,
+        b"-gt" => int(lhs)? > int(rhs)?,
+        b"-ge" => int(lhs)? >= int(rhs)?,
+        _ => return Err(TestError::UnknownOperator(op.to_os_string())),
+    };
+    Ok(res)
+}
+
+fn builtin_umask(_sh: &mut Shell, argv: &[OsString]) -> ExitStatus {
+    let res = match argv {
+        // reading the mask requires replacing it, so restore it afterwards.
+        [_] => syscall::umask(0).and_then(syscall::umask).map(|mask| {
+            println!("{mask:04o}");
+        }),
+        [_, mask] => {
+            let Some(mask) = mask
+                .to_str()
+                .and_then(|s| u16::from_str_radix(s, 8).ok())
+                .filter(|mask| mask & !UMASK_BITS == 0)
+            else {
+                message!("invalid mask '{}'", mask.display());
+                return ExitStatus::new(2);
+            };
+            syscall::umask(mask).map(|_| ())
+        }
+        _ => {
+            message!("Usage: umask [mask]");
+            return ExitStatus::new(2);
+        }
+    };
+    if let Err(e) = res {
+        message_err!(e, "cannot set file mode creation mask");
+        return ExitStatus::new(1);
+    }
+    ExitStatus::new(0)
+}
+
+fn builtin_wait(_sh: &mut Shell, argv: &[OsString]) -> ExitStatus {
     if argv.len() == 1 {
         match process::wait_any() {
             Ok((_pid, status)) => return status,
@@ -1,11 +1,11 @@
-use alloc::{borrow::Cow, boxed::Box, vec::Vec};
+use alloc::{boxed::Box, string::String, vec::Vec};
 
-use ov6_user_lib::os_str::OsStr;
+use crate::tokenizer::Word;
 
 #[derive(Debug, Default)]
 pub(super) struct Redirect<'a> {
-    pub(super) stdin: Option<Cow<'a, OsStr>>,
-    pub(super) stdout: Option<(Cow<'a, OsStr>, OutputMode)>,
+    pub(super) stdin: Option<Word<'a>>,
+    pub(super) stdout: Option<(Word<'a>, OutputMode)>,
 }
 
 impl Redirect<'_> {
@@ -21,9 +21,27 @@ pub(super) enum CommandKind<'a> {
         redirect: Redirect<'a>,
     },
     Exec {
-        argv: Vec<Cow<'a, OsStr>>,
+        argv: Vec<Word<'a>>,
         redirect: Redirect<'a>,
     },
+    /// `name=value...`
+    Assign { vars: Vec<(String, Word<'a>)> },
+    /// `if cond; then body; elif cond; then body; else body; fi`
+    If {
+        branches: Vec<Branch<'a>>,
+        else_body: Option<Vec<Command<'a>>>,
+    },
+    /// `while cond; do body; done`
+    While {
+        cond: Vec<Command<'a>>,
+        body: Vec<Command<'a>>,
+    },
+    /// `for var in items...; do body; done`
+    For {
+        var: String,
+        items: Vec<Word<'a>>,
+        body: Vec<Command<'a>>,
+    },
     Pipe {
         left: Command<'a>,
         right: Command<'a>,
@@ -38,6 +56,13 @@ pub(super) enum CommandKind<'a> {
     },
 }
 
+/// A condition and the commands run if it succeeds.
+#[derive(Debug)]
+pub(super) struct Branch<'a> {
+    pub(super) cond: Vec<Command<'a>>,
+    pub(super) body: Vec<Command<'a>>,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(super) enum OutputMode {
     Truncate,
@@ -62,10 +87,29 @@ impl<'a> Command<'a> {
         Self::new(CommandKind::Subshell { list, redirect })
     }
 
-    pub(super) fn exec(argv: Vec<Cow<'a, OsStr>>, redirect: Redirect<'a>) -> Self {
+    pub(super) fn exec(argv: Vec<Word<'a>>, redirect: Redirect<'a>) -> Self {
         Self::new(CommandKind::Exec { argv, redirect })
     }
 
+    pub(super) fn assign(vars: Vec<(String, Word<'a>)>) -> Self {
+        Self::new(CommandKind::Assign { vars })
+    }
+
+    pub(super) fn if_(branches: Vec<Branch<'a>>, else_body: Option<Vec<Self>>) -> Self {
+        Self::new(CommandKind::If {
+            branches,
+            else_body,
+        })
+    }
+
+    pub(super) fn while_(cond: Vec<Self>, body: Vec<Self>) -> Self {
+        Self::new(CommandKind::While { cond, body })
+    }
+
+    pub(super) fn for_(var: String, items: Vec<Word<'a>>, body: Vec<Self>) -> Self {
+        Self::new(CommandKind::For { var, items, body })
+    }
+
     pub(super) fn pipe(left: Self, right: Self) -> Self {
         Self::new(CommandKind::Pipe { left, right })
     }
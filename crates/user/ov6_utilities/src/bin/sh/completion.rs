@@ -0,0 +1,76 @@
+//! Tab completion of command names and paths.
+
+use alloc::{string::String, vec::Vec};
+
+use ov6_line_editor::{Completer, Completion, complete_file_name, word_start};
+use ov6_user_lib::{fs, path::Path, process};
+
+use crate::builtin::BUILTINS;
+
+pub(super) struct ShellCompleter;
+
+impl Completer for ShellCompleter {
+    fn complete(&self, line: &str, pos: usize) -> Completion {
+        let start = word_start(line, pos);
+        let word = &line[start..pos];
+        let candidates = if is_command_position(&line[..start]) && !word.contains('/') {
+            complete_command(word)
+        } else {
+            complete_file_name(word)
+        };
+        Completion { start, candidates }
+    }
+}
+
+/// Returns `true` if the word following `before` is a command name.
+fn is_command_position(before: &str) -> bool {
+    before
+        .trim_end()
+        .chars()
+        .next_back()
+        .is_none_or(|c| matches!(c, '|' | '&' | ';' | '('))
+}
+
+/// Returns the builtins and the commands in the search path that start with
+/// `prefix`.
+fn complete_command(prefix: &str) -> Vec<String> {
+    let mut candidates = BUILTINS
+        .iter()
+        .filter(|name| name.starts_with(prefix))
+        .map(|name| String::from(*name))
+        .collect::<Vec<_>>();
+    for dir in process::DEFAULT_PATH.split(':') {
+        let dir = Path::new(if dir.is_empty() { "." } else { dir });
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+        candidates.extend(entries.flatten().filter_map(|ent| {
+            let name = ent.name().to_str()?;
+            if !name.starts_with(prefix) {
+                return None;
+            }
+            let meta = fs::metadata(dir.join(name)).ok()?;
+            meta.is_file().then(|| String::from(name))
+        }));
+    }
+    candidates.sort();
+    candidates.dedup();
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_position() {
+        assert!(is_command_position(""));
+        assert!(is_command_position("  "));
+        assert!(is_command_position("ls | "));
+        assert!(is_command_position("ls;"));
+        assert!(is_command_position("ls && "));
+        assert!(is_command_position("(ls; "));
+        assert!(!is_command_position("ls "));
+        assert!(!is_command_position("cat foo > "));
+    }
+}
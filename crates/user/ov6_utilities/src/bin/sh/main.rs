@@ -2,43 +2,92 @@
 
 extern crate alloc;
 
-use alloc::string::String;
+use alloc::{format, string::String, vec::Vec};
 use core::mem;
 
-use once_init::OnceInit;
+use ov6_line_editor::LineEditor;
 use ov6_user_lib::{
-    eprint,
-    error::Ov6Error,
+    env,
     fs::File,
-    io::{self, IsTerminal as _},
-    os::fd::AsRawFd as _,
-    process::{self},
+    io::{Read as _, STDIN_FD},
+    os::{fd::AsRawFd as _, ov6::syscall},
+    os_str::{OsStr, OsString},
+    process::{self, ExitStatus},
+};
+use ov6_utilities::{
+    OrExit as _,
+    args::{self, Arg, Opt},
+    exit, exit_err, message_err,
 };
-use ov6_utilities::{OrExit as _, exit_err, message_err};
 
-use self::parser::Parser;
+use self::{completion::ShellCompleter, parser::Parser, shell::Shell};
 
 mod builtin;
 mod command;
+mod completion;
 mod parser;
 mod run;
+mod shell;
 mod tokenizer;
 
-static SHOW_PROMPT: OnceInit<bool> = OnceInit::new();
+const OPTS: &[Opt] = &[Opt::value("command", "COMMAND")
+    .short('c')
+    .help("Run COMMAND instead of reading commands from the input")];
+
+/// Exit status of a command line with a syntax error.
+const SYNTAX_ERROR_STATUS: i32 = 2;
 
-fn get_cmd(buf: &mut String) -> Result<Option<&str>, Ov6Error> {
-    if *SHOW_PROMPT.get() {
-        eprint!("$ ");
+/// Runs the commands in `input`, returning the exit status of the last one.
+fn run_script(sh: &mut Shell, input: &str) -> ExitStatus {
+    match Parser::new(input).parse() {
+        Ok(list) => run::run_list(sh, &list),
+        Err(e) => {
+            message_err!(e, "syntax error");
+            let status = ExitStatus::new(SYNTAX_ERROR_STATUS);
+            sh.set_status(status);
+            status
+        }
     }
-    buf.clear();
-    let n = io::stdin().read_line(buf)?;
-    if n == 0 {
-        return Ok(None);
+}
+
+fn run_file(sh: &mut Shell, path: &OsStr) -> ExitStatus {
+    let mut script = String::new();
+    File::open(path)
+        .and_then(|mut file| file.read_to_string(&mut script))
+        .or_exit(|e| exit_err!(e, "cannot read '{}'", path.display()));
+    run_script(sh, &script)
+}
+
+/// Called when Ctrl-C is typed while the shell is in the foreground.
+///
+/// The commands run from the shell are terminated, and the line being edited
+/// is discarded by the line editor, so there is nothing to do here.
+extern "C" fn on_interrupt() {
+    let _ = syscall::signal_return();
+}
+
+/// Makes the shell the leader of the foreground process group of the
+/// console, so that Ctrl-C terminates the running commands instead of the
+/// shell.
+fn take_terminal() {
+    let pid = process::id();
+    let res = syscall::set_process_group(pid, pid)
+        .and_then(|()| syscall::set_foreground_group(STDIN_FD, pid))
+        .and_then(|()| syscall::set_interrupt_handler(on_interrupt));
+    if let Err(e) = res {
+        message_err!(e, "cannot set up interrupts");
     }
-    Ok(Some(&buf[..n]))
 }
 
-fn main() {
+/// Returns the prompt, which shows the host name.
+fn prompt() -> String {
+    let mut name = [0; syscall::HOST_NAME_MAX];
+    let len = syscall::get_hostname(&mut name).unwrap_or(0);
+    let name = str::from_utf8(&name[..len.min(name.len())]).unwrap_or("");
+    format!("{name}$ ")
+}
+
+fn run_interactive(sh: &mut Shell) -> ! {
     // Ensure that three file descriptors are open.
     while let Ok(file) = File::options().read(true).write(true).open("console") {
         if file.as_raw_fd().get() < 3 {
@@ -48,20 +97,52 @@ fn main() {
         break;
     }
 
-    let isatty = io::stdin().is_terminal();
-    SHOW_PROMPT.init(isatty);
+    take_terminal();
+
+    let mut editor = LineEditor::new();
+    editor.set_completer(ShellCompleter);
 
     // Read and run input commands.
-    let mut buf = String::new();
     loop {
-        let Some(cmd) = get_cmd(&mut buf).or_exit(|e| exit_err!(e, "cannot read console")) else {
-            process::exit(0);
-        };
-        let Ok(list) = Parser::new(cmd).parse().inspect_err(|e| {
-            message_err!(e, "syntax error");
-        }) else {
-            continue;
+        let cmd = match editor.read_line(&prompt()) {
+            Ok(Some(cmd)) => cmd,
+            Ok(None) => process::exit(0),
+            // the line is cancelled by Ctrl-C.
+            Err(e) if e.is_interrupted() => continue,
+            Err(e) => exit_err!(e, "cannot read console"),
         };
-        run::run_list(list);
+        run_script(sh, &cmd);
     }
 }
+
+fn main() {
+    let mut command = None;
+    let mut args = Vec::new();
+    for arg in args::Parser::new(OPTS, "[script [args...]]") {
+        match arg {
+            Arg::Value("command", value) => command = Some(value),
+            Arg::Positional(arg) => args.push(arg),
+            Arg::Flag(_) | Arg::Value(..) => unreachable!(),
+        }
+    }
+
+    // `$0` is the script name, or the first argument following the command
+    // with `-c`.
+    let mut params = args.iter().copied().map(OsString::from).collect::<Vec<_>>();
+    if params.is_empty() {
+        params.push(env::arg0().into());
+    }
+    let mut sh = Shell::new(params);
+
+    let status = match (command, args.first()) {
+        (Some(command), _) => {
+            let Some(command) = command.to_str() else {
+                exit!("command is not valid UTF-8");
+            };
+            run_script(&mut sh, command)
+        }
+        (None, Some(path)) => run_file(&mut sh, path),
+        (None, None) => run_interactive(&mut sh),
+    };
+    process::exit(status.code());
+}
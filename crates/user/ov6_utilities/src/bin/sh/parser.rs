@@ -1,11 +1,11 @@
-use alloc::{borrow::Cow, vec, vec::Vec};
+use alloc::{borrow::Cow, string::String, vec, vec::Vec};
 use core::iter::Peekable;
 
-use ov6_user_lib::os_str::OsStr;
+use ov6_user_lib::os_str::{OsStr, OsString};
 
 use crate::{
-    command::{Command, OutputMode, Redirect},
-    tokenizer::{Punct, Token, TokenizeError, Tokenizer},
+    command::{Branch, Command, OutputMode, Redirect},
+    tokenizer::{self, Punct, Token, TokenizeError, Tokenizer, Word, WordPart},
 };
 
 static EXEC_TERMINATOR: &[Punct] = &[
@@ -15,8 +15,12 @@ static EXEC_TERMINATOR: &[Punct] = &[
     Punct::RParen,
     Punct::And,
     Punct::Semicolon,
+    Punct::Newline,
 ];
 
+/// Reserved words that end a command list.
+static LIST_TERMINATOR: &[&str] = &["then", "elif", "else", "fi", "do", "done"];
+
 macro_rules! try_opt {
     ($e:expr) => {
         match $e {
@@ -39,6 +43,10 @@ pub(super) enum ParseError {
     MissingPunct(Punct),
     #[error("unexpected charactesr '{0}'")]
     UnexpectedPunct(Punct),
+    #[error("missing '{0}'")]
+    MissingKeyword(&'static str),
+    #[error("invalid variable name in for loop")]
+    InvalidForVariable,
 }
 
 struct PeekTokenizer<'a> {
@@ -56,6 +64,13 @@ impl<'a> PeekTokenizer<'a> {
         self.tokens.next().transpose()
     }
 
+    fn peek_is<F>(&mut self, f: F) -> bool
+    where
+        F: FnOnce(&Token<'a>) -> bool,
+    {
+        matches!(self.tokens.peek(), Some(Ok(t)) if f(t))
+    }
+
     fn next_if<F>(&mut self, f: F) -> Result<Option<Token<'a>>, TokenizeError>
     where
         F: FnOnce(&Token<'a>) -> bool,
@@ -98,27 +113,63 @@ impl<'a> Parser<'a> {
 
     fn parse_line(&mut self) -> Result<Vec<Command<'a>>, ParseError> {
         let mut list = vec![];
-        while let Some(mut cmd) = self.parse_pipe()? {
+        loop {
+            self.skip_newlines()?;
+            if self
+                .tokens
+                .peek_is(|t| LIST_TERMINATOR.iter().any(|kw| t == *kw))
+            {
+                break;
+            }
+            let Some(mut cmd) = self.parse_pipe()? else {
+                break;
+            };
             if self.tokens.next_if_eq(Punct::And)?.is_some() {
                 cmd.background = true;
             }
             list.push(cmd);
-            if self.tokens.next_if_eq(Punct::Semicolon)?.is_none() {
+            if !self.next_separator()? {
                 break;
             }
         }
         Ok(list)
     }
 
+    fn skip_newlines(&mut self) -> Result<(), ParseError> {
+        while self.tokens.next_if_eq(Punct::Newline)?.is_some() {}
+        Ok(())
+    }
+
+    fn next_separator(&mut self) -> Result<bool, ParseError> {
+        let sep = self
+            .tokens
+            .next_if(|t| *t == Punct::Semicolon || *t == Punct::Newline)?;
+        Ok(sep.is_some())
+    }
+
+    fn next_keyword(&mut self, keyword: &str) -> Result<bool, ParseError> {
+        Ok(self.tokens.next_if(|t| t == keyword)?.is_some())
+    }
+
+    fn expect_keyword(&mut self, keyword: &'static str) -> Result<(), ParseError> {
+        if !self.next_keyword(keyword)? {
+            return Err(ParseError::MissingKeyword(keyword));
+        }
+        Ok(())
+    }
+
     fn parse_pipe(&mut self) -> Result<Option<Command<'a>>, ParseError> {
         let mut cmd = try_opt!(self.parse_exec());
         if self.tokens.next_if_eq(Punct::Pipe)?.is_some() {
+            self.skip_newlines()?;
             cmd = Command::pipe(cmd, try_opt!(self.parse_pipe()));
         }
         if self.tokens.next_if_eq(Punct::AndAnd)?.is_some() {
+            self.skip_newlines()?;
             cmd = Command::logical_and(cmd, try_opt!(self.parse_pipe()));
         }
         if self.tokens.next_if_eq(Punct::OrOr)?.is_some() {
+            self.skip_newlines()?;
             cmd = Command::logical_or(cmd, try_opt!(self.parse_pipe()));
         }
         Ok(Some(cmd))
@@ -161,10 +212,79 @@ impl<'a> Parser<'a> {
         Ok(Some(Command::subshell(cmd, redirect)))
     }
 
+    fn parse_if(&mut self) -> Result<Command<'a>, ParseError> {
+        // `if` is consumed by caller
+        let mut branches = vec![];
+        loop {
+            let cond = self.parse_line()?;
+            self.expect_keyword("then")?;
+            let body = self.parse_line()?;
+            branches.push(Branch { cond, body });
+            if !self.next_keyword("elif")? {
+                break;
+            }
+        }
+        let else_body = self
+            .next_keyword("else")?
+            .then(|| self.parse_line())
+            .transpose()?;
+        self.expect_keyword("fi")?;
+        Ok(Command::if_(branches, else_body))
+    }
+
+    fn parse_while(&mut self) -> Result<Command<'a>, ParseError> {
+        // `while` is consumed by caller
+        let cond = self.parse_line()?;
+        self.expect_keyword("do")?;
+        let body = self.parse_line()?;
+        self.expect_keyword("done")?;
+        Ok(Command::while_(cond, body))
+    }
+
+    fn parse_for(&mut self) -> Result<Command<'a>, ParseError> {
+        // `for` is consumed by caller
+        let var = match self.tokens.next()? {
+            Some(Token::Str(word)) => word
+                .as_literal()
+                .and_then(OsStr::to_str)
+                .filter(|name| tokenizer::is_var_name(name.as_bytes()))
+                .map(String::from),
+            _ => None,
+        };
+        let Some(var) = var else {
+            return Err(ParseError::InvalidForVariable);
+        };
+        self.skip_newlines()?;
+        self.expect_keyword("in")?;
+
+        let mut items = vec![];
+        while let Some(Token::Str(item)) = self.tokens.next_if(|t| matches!(t, Token::Str(_)))? {
+            items.push(item);
+        }
+        if !self.next_separator()? {
+            return Err(ParseError::MissingPunct(Punct::Semicolon));
+        }
+        self.skip_newlines()?;
+
+        self.expect_keyword("do")?;
+        let body = self.parse_line()?;
+        self.expect_keyword("done")?;
+        Ok(Command::for_(var, items, body))
+    }
+
     fn parse_exec(&mut self) -> Result<Option<Command<'a>>, ParseError> {
         if self.tokens.next_if_eq(Punct::LParen)?.is_some() {
             return self.parse_subshell();
         }
+        if self.next_keyword("if")? {
+            return self.parse_if().map(Some);
+        }
+        if self.next_keyword("while")? {
+            return self.parse_while().map(Some);
+        }
+        if self.next_keyword("for")? {
+            return self.parse_for().map(Some);
+        }
 
         let mut argv = vec![];
         let mut redirect = Redirect::new();
@@ -174,9 +294,8 @@ impl<'a> Parser<'a> {
             .tokens
             .next_if(|t| t.as_punct().is_none_or(|p| !EXEC_TERMINATOR.contains(&p)))?
         {
-            let arg: Cow<OsStr> = match tok {
-                Token::Str(Cow::Borrowed(arg)) => OsStr::new(arg).into(),
-                Token::Str(Cow::Owned(arg)) => arg.into(),
+            let arg = match tok {
+                Token::Str(arg) => arg,
                 Token::Punct(p) => return Err(ParseError::UnexpectedPunct(p)),
             };
             argv.push(arg);
@@ -185,13 +304,60 @@ impl<'a> Parser<'a> {
         if argv.is_empty() {
             return Ok(None);
         }
+
+        // A command consisting only of assignments sets shell variables.
+        if redirect.stdin.is_none()
+            && redirect.stdout.is_none()
+            && let Some(vars) = argv.iter().map(split_assignment).collect()
+        {
+            return Ok(Some(Command::assign(vars)));
+        }
         Ok(Some(Command::exec(argv, redirect)))
     }
 }
 
+/// Splits `name=value` into the variable name and the value.
+fn split_assignment<'a>(word: &Word<'a>) -> Option<(String, Word<'a>)> {
+    let split = |s: &[u8]| {
+        let eq = s.iter().position(|c| *c == b'=')?;
+        let name = &s[..eq];
+        if !tokenizer::is_var_name(name) {
+            return None;
+        }
+        let name = core::str::from_utf8(name).ok()?;
+        Some((String::from(name), eq + 1))
+    };
+
+    match word {
+        Word::Literal(Cow::Borrowed(s)) => {
+            let (name, start) = split(s.as_bytes())?;
+            let value = OsStr::from_bytes(&s.as_bytes()[start..]);
+            Some((name, Word::Literal(Cow::Borrowed(value))))
+        }
+        Word::Literal(Cow::Owned(s)) => {
+            let (name, start) = split(s.as_bytes())?;
+            let value = OsString::from_vec(s.as_bytes()[start..].into());
+            Some((name, Word::Literal(Cow::Owned(value))))
+        }
+        Word::Expand(parts) => {
+            let Some((WordPart::Literal(first), rest)) = parts.split_first() else {
+                return None;
+            };
+            let (name, start) = split(first.as_bytes())?;
+            let mut value = vec![];
+            if start < first.len() {
+                let first = OsString::from_vec(first.as_bytes()[start..].into());
+                value.push(WordPart::Literal(first));
+            }
+            value.extend(rest.iter().cloned());
+            Some((name, Word::Expand(value)))
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use alloc::vec::Vec;
+    use alloc::{string::ToString as _, vec::Vec};
 
     use super::*;
     use crate::command::CommandKind;
@@ -212,9 +378,15 @@ mod tests {
         expected_stdin: Option<&str>,
         expected_stdout: Option<(&str, OutputMode)>,
     ) {
-        assert_eq!(redirect.stdin.as_deref(), expected_stdin.map(OsStr::new));
         assert_eq!(
-            redirect.stdout.as_ref().map(|(s, m)| (s.as_ref(), *m)),
+            redirect.stdin.as_ref().and_then(Word::as_literal),
+            expected_stdin.map(OsStr::new)
+        );
+        assert_eq!(
+            redirect
+                .stdout
+                .as_ref()
+                .and_then(|(s, m)| Some((s.as_literal()?, *m))),
             expected_stdout.map(|(s, m)| (OsStr::new(s), m))
         );
     }
@@ -397,4 +569,81 @@ mod tests {
         );
         expect_exec_common(cmd1, &["echo", "world"], None, None, true);
     }
+
+    #[test]
+    fn test_parse_newline() {
+        let [cmd0, cmd1] = parse_ok("\necho hello &\n\necho world\n");
+        expect_exec_common(cmd0, &["echo", "hello"], None, None, true);
+        expect_exec(cmd1, &["echo", "world"]);
+    }
+
+    #[test]
+    fn test_parse_assign() {
+        let [cmd] = parse_ok("a=1 b=");
+        let CommandKind::Assign { vars } = *cmd.kind else {
+            panic!("Expected Assign, found {:#?}", cmd.kind);
+        };
+        let vars = vars
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_literal().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(vars, [("a", OsStr::new("1")), ("b", OsStr::new(""))]);
+
+        let [cmd] = parse_ok("a=1 echo");
+        expect_exec(cmd, &["a=1", "echo"]);
+    }
+
+    #[test]
+    fn test_parse_if() {
+        let [cmd] = parse_ok("if a; then b; elif c\nthen d; else e; fi");
+        let CommandKind::If {
+            branches,
+            else_body,
+        } = *cmd.kind
+        else {
+            panic!("Expected If, found {:#?}", cmd.kind);
+        };
+        let [b0, b1]: [Branch; 2] = branches.try_into().unwrap();
+        let ([cond], [body]) = (b0.cond.try_into().unwrap(), b0.body.try_into().unwrap());
+        expect_exec(cond, &["a"]);
+        expect_exec(body, &["b"]);
+        let ([cond], [body]) = (b1.cond.try_into().unwrap(), b1.body.try_into().unwrap());
+        expect_exec(cond, &["c"]);
+        expect_exec(body, &["d"]);
+        let [else_cmd]: [Command; 1] = else_body.unwrap().try_into().unwrap();
+        expect_exec(else_cmd, &["e"]);
+
+        assert!(matches!(
+            parse("if a; then b"),
+            Err(ParseError::MissingKeyword("fi"))
+        ));
+    }
+
+    #[test]
+    fn test_parse_while_for() {
+        let [cmd] = parse_ok("while a; do b; done");
+        let CommandKind::While { cond, body } = *cmd.kind else {
+            panic!("Expected While, found {:#?}", cmd.kind);
+        };
+        let ([cond], [body]) = (cond.try_into().unwrap(), body.try_into().unwrap());
+        expect_exec(cond, &["a"]);
+        expect_exec(body, &["b"]);
+
+        let [cmd] = parse_ok("for i in x y\ndo\necho $i\ndone");
+        let CommandKind::For { var, items, body } = *cmd.kind else {
+            panic!("Expected For, found {:#?}", cmd.kind);
+        };
+        assert_eq!(var, "i");
+        assert_eq!(items, [OsStr::new("x"), OsStr::new("y")]);
+        let [body]: [Command; 1] = body.try_into().unwrap();
+        let CommandKind::Exec { argv, .. } = *body.kind else {
+            panic!("Expected Exec, found {:#?}", body.kind);
+        };
+        assert_eq!(argv[1].to_string(), "${i}");
+
+        assert!(matches!(
+            parse("for 1 in x; do a; done"),
+            Err(ParseError::InvalidForVariable)
+        ));
+    }
 }
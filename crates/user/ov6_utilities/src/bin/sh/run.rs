@@ -1,9 +1,9 @@
-use alloc::{borrow::Cow, vec::Vec};
 use core::convert::Infallible;
 
 use ov6_user_lib::{
     error::Ov6Error,
     fs::File,
+    os::ov6::syscall::{self, InterruptAction},
     os_str::{OsStr, OsString},
     path::Path,
     process::{self, ChildWithIo, ExitStatus, ProcessBuilder, Stdio},
@@ -12,7 +12,8 @@ use ov6_utilities::{message, message_err};
 
 use crate::{
     builtin,
-    command::{Command, CommandKind, OutputMode, Redirect},
+    command::{Branch, Command, CommandKind, OutputMode, Redirect},
+    shell::Shell,
 };
 
 pub(super) trait ToCode {
@@ -47,16 +48,16 @@ impl ToCode for RunError {
 }
 
 impl Redirect<'_> {
-    fn open(self) -> Result<ProcessBuilder, RunError> {
+    fn open(&self, sh: &Shell) -> Result<ProcessBuilder, RunError> {
         let mut builder = ProcessBuilder::new();
-        if let Some(file) = self.stdin {
-            let stdin = File::open(Path::new(&file)).map_err(|err| RunError::OpenFile {
-                file: file.into_owned(),
-                err,
-            })?;
+        if let Some(file) = &self.stdin {
+            let file = sh.expand_single(file);
+            let stdin =
+                File::open(Path::new(&file)).map_err(|err| RunError::OpenFile { file, err })?;
             builder.stdin(Stdio::Fd(stdin.into()));
         }
-        if let Some((file, mode)) = self.stdout {
+        if let Some((file, mode)) = &self.stdout {
+            let file = sh.expand_single(file);
             let mut options = File::options();
             match mode {
                 OutputMode::Truncate => options.write(true).create(true).truncate(true),
@@ -64,40 +65,55 @@ impl Redirect<'_> {
             };
             let stdout = options
                 .open(Path::new(&file))
-                .map_err(|err| RunError::OpenFile {
-                    file: file.into_owned(),
-                    err,
-                })?;
+                .map_err(|err| RunError::OpenFile { file, err })?;
             builder.stdout(Stdio::Fd(stdout.into()));
         }
         Ok(builder)
     }
 }
 
-pub(super) fn run(cmd: Command<'_>) -> Result<ExitStatus, RunError> {
-    match *cmd.kind {
+pub(super) fn run(sh: &mut Shell, cmd: &Command<'_>) -> Result<ExitStatus, RunError> {
+    match &*cmd.kind {
         CommandKind::Subshell { list, redirect } => {
-            let builder = redirect.open()?;
-            let child = spawn_fn(builder, || Ok(run_list(list)))?;
+            let builder = redirect.open(sh)?;
+            let child = spawn_fn(builder, || Ok(run_list(sh, list)))?;
             wait(child, cmd.background)
         }
         CommandKind::Exec { argv, redirect } => {
-            if let Some(status) = builtin::run_builtin(&argv, cmd.background)? {
+            let argv = sh.expand_all(argv);
+            if argv.is_empty() {
+                return Ok(ExitStatus::new(0));
+            }
+            if let Some(status) = builtin::run_builtin(sh, &argv, cmd.background)? {
                 return Ok(status);
             }
-            run_external(&argv, redirect, cmd.background)
+            run_external(sh, &argv, redirect, cmd.background)
+        }
+        CommandKind::Assign { vars } => {
+            for (name, value) in vars {
+                let value = sh.expand_single(value);
+                sh.set_var(name, value);
+            }
+            Ok(ExitStatus::new(0))
+        }
+        CommandKind::If { .. } | CommandKind::While { .. } | CommandKind::For { .. } => {
+            if cmd.background {
+                let child = spawn_fn(ProcessBuilder::new(), || Ok(run_compound(sh, &cmd.kind)))?;
+                return wait(child, cmd.background);
+            }
+            Ok(run_compound(sh, &cmd.kind))
         }
         CommandKind::Pipe { left, right } => {
             let mut left_builder = ProcessBuilder::new();
             left_builder.stdout(Stdio::Pipe);
-            let mut left = spawn_fn(left_builder, || run(left));
+            let mut left = spawn_fn(left_builder, || run(sh, left));
             let left_out = left.as_mut().ok().and_then(|l| l.stdout.take());
 
             let mut right_builder = ProcessBuilder::new();
             if let Some(left_out) = left_out {
                 right_builder.stdin(Stdio::Fd(left_out.into()));
             }
-            let right = spawn_fn(right_builder, || run(right));
+            let right = spawn_fn(right_builder, || run(sh, right));
 
             if let Err(e) = left.and_then(|left| wait(left, cmd.background)) {
                 message_err!(e);
@@ -105,33 +121,69 @@ pub(super) fn run(cmd: Command<'_>) -> Result<ExitStatus, RunError> {
             wait(right?, cmd.background)
         }
         CommandKind::LogicalAnd { left, right } => {
-            let left_status = run(left)?;
+            let left_status = run(sh, left)?;
             if !left_status.success() {
                 return Ok(left_status);
             }
-            run(right)
+            run(sh, right)
         }
         CommandKind::LogicalOr { left, right } => {
-            match run(left) {
+            match run(sh, left) {
                 Ok(status) if status.success() => return Ok(status),
                 Ok(_status) => {}
                 Err(e) => message_err!(e),
             }
-            run(right)
+            run(sh, right)
         }
     }
 }
 
-pub(super) fn run_list(list: Vec<Command<'_>>) -> ExitStatus {
+/// Runs `if`, `while` or `for` command in the current shell.
+fn run_compound(sh: &mut Shell, kind: &CommandKind<'_>) -> ExitStatus {
+    match kind {
+        CommandKind::If {
+            branches,
+            else_body,
+        } => {
+            for Branch { cond, body } in branches {
+                if run_list(sh, cond).success() {
+                    return run_list(sh, body);
+                }
+            }
+            else_body
+                .as_ref()
+                .map_or_else(|| ExitStatus::new(0), |body| run_list(sh, body))
+        }
+        CommandKind::While { cond, body } => {
+            let mut status = ExitStatus::new(0);
+            while run_list(sh, cond).success() {
+                status = run_list(sh, body);
+            }
+            status
+        }
+        CommandKind::For { var, items, body } => {
+            let mut status = ExitStatus::new(0);
+            for item in sh.expand_all(items) {
+                sh.set_var(var, item);
+                status = run_list(sh, body);
+            }
+            status
+        }
+        _ => unreachable!(),
+    }
+}
+
+pub(super) fn run_list(sh: &mut Shell, list: &[Command<'_>]) -> ExitStatus {
     let mut status = ExitStatus::new(0);
     for cmd in list {
-        status = match run(cmd) {
+        status = match run(sh, cmd) {
             Ok(s) => s,
             Err(e) => {
                 message_err!(e);
                 ExitStatus::new(e.to_code())
             }
         };
+        sh.set_status(status);
     }
     status
 }
@@ -142,6 +194,8 @@ where
 {
     builder
         .spawn_fn(|| {
+            // the shell's interrupt handler is not for the commands.
+            let _ = syscall::set_interrupt_action(InterruptAction::Terminate);
             let code = match f() {
                 Ok(status) => status.to_code(),
                 Err(e) => {
@@ -167,16 +221,19 @@ pub(super) fn wait(mut child: ChildWithIo, background: bool) -> Result<ExitStatu
 }
 
 fn run_external(
-    argv: &[Cow<'_, OsStr>],
-    redirect: Redirect<'_>,
+    sh: &Shell,
+    argv: &[OsString],
+    redirect: &Redirect<'_>,
     background: bool,
 ) -> Result<ExitStatus, RunError> {
-    let builder = redirect.open()?;
+    let builder = redirect.open(sh)?;
     let child = spawn_fn(builder, || {
-        let _: Infallible = process::exec(&argv[0], argv).map_err(|err| RunError::Exec {
-            arg0: argv[0].clone().into_owned(),
-            err,
-        })?;
+        let search_path = OsStr::new(process::DEFAULT_PATH);
+        let _: Infallible =
+            process::exec_search(&argv[0], argv, search_path).map_err(|err| RunError::Exec {
+                arg0: argv[0].clone(),
+                err,
+            })?;
         unreachable!()
     })?;
     wait(child, background)
@@ -0,0 +1,159 @@
+use alloc::{collections::BTreeMap, format, string::String, vec, vec::Vec};
+
+use ov6_user_lib::{os_str::OsString, process::ExitStatus};
+
+use crate::tokenizer::{Word, WordPart};
+
+/// State of the shell shared by the commands.
+pub(super) struct Shell {
+    vars: BTreeMap<String, OsString>,
+    /// Positional parameters. The first element is `$0`.
+    args: Vec<OsString>,
+    /// Exit status of the last command (`$?`).
+    status: ExitStatus,
+}
+
+impl Shell {
+    pub(super) fn new(args: Vec<OsString>) -> Self {
+        Self {
+            vars: BTreeMap::new(),
+            args,
+            status: ExitStatus::new(0),
+        }
+    }
+
+    pub(super) fn status(&self) -> ExitStatus {
+        self.status
+    }
+
+    pub(super) fn set_status(&mut self, status: ExitStatus) {
+        self.status = status;
+    }
+
+    pub(super) fn set_var(&mut self, name: &str, value: OsString) {
+        self.vars.insert(String::from(name), value);
+    }
+
+    /// Returns the value of the parameter `name`, or `None` if it is unset.
+    fn param(&self, name: &str) -> Option<OsString> {
+        let value = match name {
+            "?" => format!("{}", self.status.code()).into(),
+            "#" => format!("{}", self.args.len().saturating_sub(1)).into(),
+            "@" | "*" => {
+                let mut value = OsString::new();
+                for (i, arg) in self.args.iter().skip(1).enumerate() {
+                    if i > 0 {
+                        value.push(" ");
+                    }
+                    value.push(arg);
+                }
+                value
+            }
+            _ if name.bytes().all(|c| c.is_ascii_digit()) => {
+                let idx = name.parse::<usize>().ok()?;
+                self.args.get(idx)?.clone()
+            }
+            _ => self.vars.get(name)?.clone(),
+        };
+        Some(value)
+    }
+
+    /// Expands the parameters in `word`.
+    ///
+    /// The values of parameters outside double quotes are split into fields
+    /// at whitespace, so the word may expand to any number of fields.
+    pub(super) fn expand(&self, word: &Word<'_>) -> Vec<OsString> {
+        self.expand_fields(word, true)
+    }
+
+    /// Expands the parameters in `word` without field splitting.
+    pub(super) fn expand_single(&self, word: &Word<'_>) -> OsString {
+        self.expand_fields(word, false)
+            .into_iter()
+            .next()
+            .unwrap_or_default()
+    }
+
+    pub(super) fn expand_all(&self, words: &[Word<'_>]) -> Vec<OsString> {
+        words.iter().flat_map(|word| self.expand(word)).collect()
+    }
+
+    fn expand_fields(&self, word: &Word<'_>, split: bool) -> Vec<OsString> {
+        let parts = match word {
+            Word::Literal(s) => return vec![s.to_os_string()],
+            Word::Expand(parts) => parts,
+        };
+
+        let mut fields = vec![];
+        let mut current: Option<Vec<u8>> = None;
+        for part in parts {
+            match part {
+                WordPart::Literal(s) => {
+                    current
+                        .get_or_insert_with(Vec::new)
+                        .extend_from_slice(s.as_bytes());
+                }
+                WordPart::Param { name, quoted } if *quoted || !split => {
+                    let value = self.param(name).unwrap_or_default();
+                    current
+                        .get_or_insert_with(Vec::new)
+                        .extend_from_slice(value.as_bytes());
+                }
+                WordPart::Param { name, .. } => {
+                    let Some(value) = self.param(name) else {
+                        continue;
+                    };
+                    let pieces = value.as_bytes().split(u8::is_ascii_whitespace);
+                    for (i, piece) in pieces.enumerate() {
+                        if i > 0 {
+                            fields.extend(current.take().map(OsString::from_vec));
+                        }
+                        if !piece.is_empty() {
+                            current
+                                .get_or_insert_with(Vec::new)
+                                .extend_from_slice(piece);
+                        }
+                    }
+                }
+            }
+        }
+        fields.extend(current.map(OsString::from_vec));
+        fields
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ov6_user_lib::os_str::OsStr;
+
+    use super::*;
+    use crate::tokenizer::{Token, Tokenizer};
+
+    #[track_caller]
+    fn expand(sh: &Shell, input: &str) -> Vec<OsString> {
+        let Some(Ok(Token::Str(word))) = Tokenizer::new(input).next() else {
+            panic!("not a word: {input}");
+        };
+        sh.expand(&word)
+    }
+
+    #[test]
+    fn test_expand() {
+        let mut sh = Shell::new(vec![OsStr::new("sh").into(), OsStr::new("arg1").into()]);
+        sh.set_var("A", OsStr::new("a").into());
+        sh.set_var("LIST", OsStr::new(" x  y ").into());
+        sh.set_var("EMPTY", OsStr::new("").into());
+        sh.set_status(ExitStatus::new(3));
+
+        assert_eq!(expand(&sh, "$A"), ["a"]);
+        assert_eq!(expand(&sh, "${A}b"), ["ab"]);
+        assert_eq!(expand(&sh, "'$A'"), ["$A"]);
+        assert_eq!(expand(&sh, r"\$A"), ["$A"]);
+        assert_eq!(expand(&sh, "$?:$#:$0:$1"), ["3:1:sh:arg1"]);
+        assert_eq!(expand(&sh, "p$LIST"), ["p", "x", "y"]);
+        assert_eq!(expand(&sh, "\"$LIST\""), [" x  y "]);
+        assert!(expand(&sh, "$EMPTY").is_empty());
+        assert!(expand(&sh, "$UNSET").is_empty());
+        assert_eq!(expand(&sh, "\"$UNSET\""), [""]);
+    }
+}
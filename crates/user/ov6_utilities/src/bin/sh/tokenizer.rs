@@ -1,20 +1,23 @@
 use alloc::{borrow::Cow, vec::Vec};
-use core::{fmt, slice};
+use core::{fmt, mem, slice};
 
 use ov6_user_lib::os_str::{OsStr, OsString};
 
 const SYMBOLS: &[u8] = b"<|>&;()";
 
+/// Special parameters, which are expanded from the shell state.
+const SPECIAL_PARAMS: &[u8] = b"?#@*";
+
 #[derive(Debug, Clone, PartialEq, Eq, derive_more::From)]
 pub enum Token<'s> {
-    Str(Cow<'s, OsStr>),
+    Str(Word<'s>),
     Punct(Punct),
 }
 
 impl fmt::Display for Token<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::Str(s) => fmt::Display::fmt(&OsStr::new(s).display(), f),
+            Self::Str(s) => fmt::Display::fmt(s, f),
             Self::Punct(p) => fmt::Display::fmt(p, f),
         }
     }
@@ -23,7 +26,7 @@ impl fmt::Display for Token<'_> {
 impl PartialEq<str> for Token<'_> {
     fn eq(&self, other: &str) -> bool {
         match self {
-            Token::Str(s) => OsStr::new(s) == other,
+            Token::Str(s) => s.as_literal().is_some_and(|s| s == other),
             Token::Punct(_) => false,
         }
     }
@@ -48,12 +51,127 @@ impl Token<'_> {
 
     pub fn into_owned(self) -> Token<'static> {
         match self {
-            Self::Str(s) => Token::Str(s.into_owned().into()),
+            Self::Str(s) => Token::Str(s.into_owned()),
             Self::Punct(p) => Token::Punct(p),
         }
     }
 }
 
+/// A word, which may contain parameter expansions (`$name` or `${name}`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Word<'s> {
+    /// A word without expansions.
+    Literal(Cow<'s, OsStr>),
+    /// A word containing expansions.
+    Expand(Vec<WordPart<'s>>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WordPart<'s> {
+    Literal(OsString),
+    /// A parameter, with whether it appears inside double quotes.
+    Param {
+        name: Cow<'s, str>,
+        quoted: bool,
+    },
+}
+
+impl<'s> From<&'s OsStr> for Word<'s> {
+    fn from(s: &'s OsStr) -> Self {
+        Self::Literal(s.into())
+    }
+}
+
+impl PartialEq<&OsStr> for Word<'_> {
+    fn eq(&self, other: &&OsStr) -> bool {
+        self.as_literal() == Some(*other)
+    }
+}
+
+impl fmt::Display for Word<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Literal(s) => fmt::Display::fmt(&s.display(), f),
+            Self::Expand(parts) => {
+                for part in parts {
+                    match part {
+                        WordPart::Literal(s) => fmt::Display::fmt(&s.display(), f)?,
+                        WordPart::Param { name, .. } => write!(f, "${{{name}}}")?,
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Word<'_> {
+    /// Returns the word as a string if it contains no expansions.
+    pub fn as_literal(&self) -> Option<&OsStr> {
+        match self {
+            Self::Literal(s) => Some(s),
+            Self::Expand(_) => None,
+        }
+    }
+
+    pub fn into_owned(self) -> Word<'static> {
+        match self {
+            Self::Literal(s) => Word::Literal(s.into_owned().into()),
+            Self::Expand(parts) => Word::Expand(
+                parts
+                    .into_iter()
+                    .map(|part| match part {
+                        WordPart::Literal(s) => WordPart::Literal(s),
+                        WordPart::Param { name, quoted } => WordPart::Param {
+                            name: name.into_owned().into(),
+                            quoted,
+                        },
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Returns `true` if `name` can be used as a variable name.
+pub fn is_var_name(name: &[u8]) -> bool {
+    let Some((first, rest)) = name.split_first() else {
+        return false;
+    };
+    (first.is_ascii_alphabetic() || *first == b'_')
+        && rest.iter().all(|c| c.is_ascii_alphanumeric() || *c == b'_')
+}
+
+/// Parses a parameter name following `$`.
+///
+/// Returns the name and the number of bytes consumed.
+fn parse_param(s: &[u8]) -> Option<(&str, usize)> {
+    let (name, len) = match *s.first()? {
+        b'{' => {
+            let end = s.iter().position(|c| *c == b'}')?;
+            let name = &s[1..end];
+            let valid = is_var_name(name)
+                || (!name.is_empty() && name.iter().all(u8::is_ascii_digit))
+                || (name.len() == 1 && SPECIAL_PARAMS.contains(&name[0]));
+            if !valid {
+                return None;
+            }
+            (name, end + 1)
+        }
+        c if c.is_ascii_digit() || SPECIAL_PARAMS.contains(&c) => (&s[..1], 1),
+        c if c.is_ascii_alphabetic() || c == b'_' => {
+            let len = s
+                .iter()
+                .position(|c| !c.is_ascii_alphanumeric() && *c != b'_')
+                .unwrap_or(s.len());
+            (&s[..len], len)
+        }
+        _ => return None,
+    };
+    // parameter names consist of ASCII characters
+    Some((core::str::from_utf8(name).ok()?, len))
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Punct {
     Pipe,
@@ -66,6 +184,7 @@ pub enum Punct {
     Lt,
     Gt,
     GtGt,
+    Newline,
 }
 
 impl Punct {
@@ -81,6 +200,7 @@ impl Punct {
             Self::Lt => "<",
             Self::Gt => ">",
             Self::GtGt => ">>",
+            Self::Newline => "newline",
         }
     }
 }
@@ -160,7 +280,8 @@ impl<'a> Tokenizer<'a> {
         }
     }
 
-    fn next_str(&mut self) -> Result<Option<Cow<'a, OsStr>>, TokenizeError> {
+    #[expect(clippy::too_many_lines)]
+    fn next_str(&mut self) -> Result<Option<Word<'a>>, TokenizeError> {
         let start = self.chars.as_slice();
         if start.is_empty() {
             return Ok(None);
@@ -206,6 +327,14 @@ impl<'a> Tokenizer<'a> {
                 needs_allocation = true;
                 continue;
             }
+            if self
+                .chars
+                .next_if(|c| !in_single_quotes && c == b'$')
+                .is_some()
+            {
+                needs_allocation = true;
+                continue;
+            }
             if self
                 .chars
                 .next_if(|c| {
@@ -233,14 +362,17 @@ impl<'a> Tokenizer<'a> {
         let input = &start[..input_len];
 
         if !needs_allocation {
-            return Ok(Some(Cow::Borrowed(OsStr::from_bytes(input))));
+            return Ok(Some(Word::Literal(Cow::Borrowed(OsStr::from_bytes(input)))));
         }
 
+        let mut parts = Vec::new();
         let mut result = Vec::with_capacity(input.len());
         let mut escaped = false;
 
         // Constructing the actual string
-        for ch in input.iter().copied() {
+        let mut rest = input;
+        while let Some((&ch, tail)) = rest.split_first() {
+            rest = tail;
             if escaped {
                 result.push(ch);
                 escaped = false;
@@ -258,15 +390,49 @@ impl<'a> Tokenizer<'a> {
                 in_single_quotes = !in_single_quotes;
                 continue;
             }
+            if ch == b'$'
+                && !in_single_quotes
+                && let Some((name, len)) = parse_param(rest)
+            {
+                rest = &rest[len..];
+                if !result.is_empty() {
+                    let literal = OsString::from_vec(mem::take(&mut result));
+                    parts.push(WordPart::Literal(literal));
+                }
+                parts.push(WordPart::Param {
+                    name: Cow::Borrowed(name),
+                    quoted: in_double_quotes,
+                });
+                continue;
+            }
             result.push(ch);
         }
 
-        Ok(Some(Cow::Owned(OsString::from_vec(result))))
+        if parts.is_empty() {
+            return Ok(Some(Word::Literal(Cow::Owned(OsString::from_vec(result)))));
+        }
+        if !result.is_empty() {
+            parts.push(WordPart::Literal(OsString::from_vec(result)));
+        }
+        Ok(Some(Word::Expand(parts)))
     }
 
     fn next_token(&mut self) -> Result<Option<Token<'a>>, TokenizeError> {
-        while self.chars.next_if(|c| c.is_ascii_whitespace()).is_some() {}
-        let token: Token<'_> = if self.chars.next_if_eq(b'|').is_some() {
+        loop {
+            while self
+                .chars
+                .next_if(|c| c.is_ascii_whitespace() && c != b'\n')
+                .is_some()
+            {}
+            // Skip a comment up to the end of line.
+            if self.chars.next_if_eq(b'#').is_none() {
+                break;
+            }
+            while self.chars.next_if(|c| c != b'\n').is_some() {}
+        }
+        let token: Token<'_> = if self.chars.next_if_eq(b'\n').is_some() {
+            Punct::Newline.into()
+        } else if self.chars.next_if_eq(b'|').is_some() {
             if self.chars.next_if_eq(b'|').is_some() {
                 Punct::OrOr.into()
             } else {
@@ -309,6 +475,8 @@ impl<'a> Iterator for Tokenizer<'a> {
 
 #[cfg(test)]
 mod tests {
+    use alloc::vec;
+
     use super::*;
 
     #[track_caller]
@@ -405,6 +573,54 @@ mod tests {
         assert!(s.next().is_none());
     }
 
+    #[test]
+    fn test_param() {
+        let mut s = Tokenizer::new(r#"a$b "${c}d" '$e' \$f $ $1x"#);
+        let expected = [
+            Word::Expand(vec![
+                WordPart::Literal(OsStr::new("a").into()),
+                WordPart::Param {
+                    name: "b".into(),
+                    quoted: false,
+                },
+            ]),
+            Word::Expand(vec![
+                WordPart::Param {
+                    name: "c".into(),
+                    quoted: true,
+                },
+                WordPart::Literal(OsStr::new("d").into()),
+            ]),
+            OsStr::new("$e").into(),
+            OsStr::new("$f").into(),
+            OsStr::new("$").into(),
+            Word::Expand(vec![
+                WordPart::Param {
+                    name: "1".into(),
+                    quoted: false,
+                },
+                WordPart::Literal(OsStr::new("x").into()),
+            ]),
+        ];
+        for word in expected {
+            assert_eq!(s.next().unwrap().unwrap(), Token::Str(word));
+        }
+        assert!(s.next().is_none());
+    }
+
+    #[test]
+    fn test_comment() {
+        let mut s = Tokenizer::new("echo a#b # comment ; echo");
+        assert_next_is_str(&mut s, "echo");
+        assert_next_is_str(&mut s, "a#b");
+        assert!(s.next().is_none());
+
+        let mut s = Tokenizer::new("#!/bin/sh\necho");
+        assert_next_is_punct(&mut s, Punct::Newline);
+        assert_next_is_str(&mut s, "echo");
+        assert!(s.next().is_none());
+    }
+
     #[test]
     fn test_punctuations() {
         let mut s = Tokenizer::new("|&;()");
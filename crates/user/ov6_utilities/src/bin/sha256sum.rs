@@ -0,0 +1,222 @@
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use ov6_user_lib::{
+    error::Ov6Error,
+    fs::File,
+    hash::{Digest, Sha256},
+    io::{self, Read},
+    os_str::OsStr,
+    println, process,
+};
+use ov6_utilities::{
+    args::{Arg, Opt, Parser},
+    message, message_err,
+};
+
+const OPTS: &[Opt] = &[Opt::flag("check")
+    .short('c')
+    .help("Read checksums from the files and check them")];
+
+fn digest<R>(mut input: R) -> Result<Digest, Ov6Error>
+where
+    R: Read,
+{
+    let mut hasher = Sha256::new();
+    let mut buf = [0; 1024];
+    loop {
+        let n = input.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher.finish())
+}
+
+fn digest_path(path: &OsStr) -> Result<Digest, Ov6Error> {
+    if path == "-" {
+        digest(io::stdin().lock())
+    } else {
+        digest(File::open(path)?)
+    }
+}
+
+fn parse_hex(s: &[u8]) -> Option<Digest> {
+    let mut digest = [0; 32];
+    if s.len() != digest.len() * 2 {
+        return None;
+    }
+    for (b, pair) in digest.iter_mut().zip(s.chunks_exact(2)) {
+        let hi = char::from(pair[0]).to_digit(16)?;
+        let lo = char::from(pair[1]).to_digit(16)?;
+        *b = u8::try_from(hi * 16 + lo).unwrap();
+    }
+    Some(Digest(digest))
+}
+
+/// Parses a line of a checksum file, `<digest>  <path>`.
+///
+/// The path may also be preceded by ` *`, which marks binary mode in the
+/// output of other implementations.
+fn parse_check_line(line: &[u8]) -> Option<(Digest, &OsStr)> {
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    let sep = line.iter().position(|&b| b == b' ')?;
+    let (hex, rest) = line.split_at(sep);
+    let path = rest
+        .strip_prefix(b"  ")
+        .or_else(|| rest.strip_prefix(b" *"))?;
+    if path.is_empty() {
+        return None;
+    }
+    Some((parse_hex(hex)?, OsStr::from_bytes(path)))
+}
+
+/// Reads the whole contents of `path`, or of the standard input if it is `-`.
+fn read_path(path: &OsStr) -> Result<Vec<u8>, Ov6Error> {
+    let mut contents = Vec::new();
+    if path == "-" {
+        io::stdin().lock().read_to_end(&mut contents)?;
+    } else {
+        File::open(path)?.read_to_end(&mut contents)?;
+    }
+    Ok(contents)
+}
+
+/// Checks the checksums listed in `path`, returning whether all matched.
+fn check(path: &OsStr) -> bool {
+    let contents = match read_path(path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            message_err!(e, "cannot read '{}'", path.display());
+            return false;
+        }
+    };
+
+    let mut ok = true;
+    let mut failed = 0;
+    for line in contents.split(|&b| b == b'\n').filter(|l| !l.is_empty()) {
+        let Some((expected, target)) = parse_check_line(line) else {
+            message!("'{}': improperly formatted checksum line", path.display());
+            ok = false;
+            continue;
+        };
+        match digest_path(target) {
+            Ok(actual) if actual == expected => {
+                println!("{}: OK", target.display());
+            }
+            Ok(_) => {
+                println!("{}: FAILED", target.display());
+                failed += 1;
+            }
+            Err(e) => {
+                message_err!(e, "cannot read '{}'", target.display());
+                println!("{}: FAILED open or read", target.display());
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        message!("WARNING: {failed} computed checksum(s) did NOT match");
+        ok = false;
+    }
+    ok
+}
+
+fn main() {
+    let mut check_mode = false;
+    let mut paths = Vec::new();
+
+    for arg in Parser::new(OPTS, "[file...]") {
+        match arg {
+            Arg::Flag("check") => check_mode = true,
+            Arg::Positional(path) => paths.push(path),
+            _ => unreachable!(),
+        }
+    }
+    if paths.is_empty() {
+        paths.push(OsStr::new("-"));
+    }
+
+    let mut status = 0;
+    for path in paths {
+        if check_mode {
+            if !check(path) {
+                status = 1;
+            }
+            continue;
+        }
+        match digest_path(path) {
+            Ok(digest) => {
+                println!("{digest}  {}", path.display());
+            }
+            Err(e) => {
+                message_err!(e, "cannot read '{}'", path.display());
+                status = 1;
+            }
+        }
+    }
+    process::exit(status);
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{format, string::ToString as _};
+
+    use ov6_user_lib::hash::sha256;
+
+    use super::*;
+
+    #[test]
+    fn test_sha256() {
+        assert_eq!(
+            sha256(b"").to_string(),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            sha256(b"abc").to_string(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        // the padding needs a second block
+        assert_eq!(
+            sha256(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq").to_string(),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+
+        let data = b"0123456789".repeat(100);
+        let mut hasher = Sha256::new();
+        for chunk in data.chunks(7) {
+            hasher.update(chunk);
+        }
+        assert_eq!(hasher.finish(), sha256(&data));
+        assert_eq!(digest(&data[..]).unwrap(), sha256(&data));
+    }
+
+    #[test]
+    fn test_parse_check_line() {
+        let digest = sha256(b"abc");
+        let line = format!("{digest}  dir/file");
+        assert_eq!(
+            parse_check_line(line.as_bytes()),
+            Some((digest, OsStr::new("dir/file")))
+        );
+        let line = format!("{digest} *file with spaces\r");
+        assert_eq!(
+            parse_check_line(line.as_bytes()),
+            Some((digest, OsStr::new("file with spaces")))
+        );
+        let upper = format!("{digest}  file").to_ascii_uppercase();
+        assert_eq!(
+            parse_check_line(upper.as_bytes()).map(|(d, _)| d),
+            Some(digest)
+        );
+
+        assert_eq!(parse_check_line(format!("{digest} file").as_bytes()), None);
+        assert_eq!(parse_check_line(format!("{digest}  ").as_bytes()), None);
+        assert_eq!(parse_check_line(b"abcd  file"), None);
+        assert_eq!(parse_check_line(b""), None);
+    }
+}
@@ -0,0 +1,53 @@
+#![no_std]
+
+use ov6_user_lib::{
+    os::ov6::syscall::{self, ShutdownRequest},
+    os_str::OsStr,
+};
+use ov6_utilities::{
+    OrExit as _,
+    args::{Arg, Opt, Parser},
+    exit_err,
+};
+
+const OPTS: &[Opt] = &[
+    Opt::flag("halt")
+        .short('h')
+        .help("Halt the system (default)"),
+    Opt::flag("reboot").short('r').help("Reboot the system"),
+];
+
+fn parse_code(parser: &Parser, s: &OsStr) -> u16 {
+    let Some(code) = s.to_str().and_then(|s| s.parse().ok()) else {
+        parser.usage_error(format_args!("invalid code '{}'", s.display()));
+    };
+    code
+}
+
+fn main() {
+    let mut reboot = false;
+    let mut code = None;
+
+    let mut parser = Parser::new(OPTS, "[code]");
+    while let Some(arg) = parser.next() {
+        match arg {
+            Arg::Flag("halt") => reboot = false,
+            Arg::Flag("reboot") => reboot = true,
+            Arg::Positional(s) if code.is_none() => code = Some(parse_code(&parser, s)),
+            Arg::Positional(s) => {
+                parser.usage_error(format_args!("unexpected argument '{}'", s.display()));
+            }
+            Arg::Flag(_) | Arg::Value(..) => unreachable!(),
+        }
+    }
+
+    let req = match (reboot, code) {
+        (false, code) => ShutdownRequest::Halt(code.unwrap_or(0)),
+        (true, None) => ShutdownRequest::Reboot,
+        (true, Some(_)) => parser.usage_error(format_args!("exit code cannot be used with '-r'")),
+    };
+
+    // init stops the processes and commits the file system before shutting
+    // down.
+    syscall::request_shutdown(req).or_exit(|e| exit_err!(e, "shutdown failed"));
+}
@@ -0,0 +1,72 @@
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use ov6_user_lib::{
+    fs::{self, StatType},
+    path::Path,
+    println, process,
+};
+use ov6_utilities::{
+    args::{Arg, Opt, Parser},
+    message_err,
+};
+
+const OPTS: &[Opt] = &[];
+
+fn type_name(ty: StatType) -> &'static str {
+    match ty {
+        StatType::Dir => "directory",
+        StatType::File => "regular file",
+        StatType::Dev => "device",
+        StatType::Socket => "socket",
+    }
+}
+
+fn main() {
+    let mut paths = Vec::new();
+
+    let mut parser = Parser::new(OPTS, "<file...>");
+    for arg in &mut parser {
+        match arg {
+            Arg::Positional(path) => paths.push(Path::new(path)),
+            Arg::Flag(_) | Arg::Value(..) => unreachable!(),
+        }
+    }
+
+    if paths.is_empty() {
+        parser.usage_error(format_args!("missing operand"));
+    }
+
+    let mut status = 0;
+    for path in paths {
+        let meta = match fs::metadata(path) {
+            Ok(meta) => meta,
+            Err(e) => {
+                message_err!(e, "cannot stat '{}'", path.display());
+                status = 1;
+                continue;
+            }
+        };
+        println!("  File: {}", path.display());
+        println!(
+            "  Size: {:<10} Blocks: {:<10} {}",
+            meta.size(),
+            meta.blocks(),
+            type_name(meta.ty())
+        );
+        println!(
+            "Device: {:<10} Inode: {:<11} Links: {}",
+            meta.dev(),
+            meta.ino(),
+            meta.nlink()
+        );
+        if meta.is_device() {
+            println!("Device type: {},{}", meta.major(), meta.minor());
+        }
+    }
+
+    process::exit(status);
+}
@@ -0,0 +1,111 @@
+#![no_std]
+
+extern crate alloc;
+
+use alloc::{format, string::String, vec::Vec};
+
+use ov6_user_lib::{
+    env, eprintln,
+    error::Ov6Error,
+    os::ov6::syscall::{self, PtraceRegs, PtraceStop, SyscallCode, SyscallError},
+    os_str::OsStr,
+    process::{self, ProcId, ProcessBuilder},
+};
+use ov6_utilities::{OrExit as _, exit_err, message, usage_and_exit};
+
+// registers holding the system call number, arguments and return values.
+const A0: usize = 10;
+const A1: usize = 11;
+const A2: usize = 12;
+const A7: usize = 17;
+
+fn get_regs(pid: ProcId) -> PtraceRegs {
+    syscall::ptrace_get_regs(pid)
+        .or_exit(|e| exit_err!(e, "cannot get registers of process '{pid}'"))
+}
+
+fn format_entry(regs: &PtraceRegs) -> String {
+    let n = regs.x[A7];
+    let name =
+        SyscallCode::from_repr(n).map_or_else(|| format!("syscall_{n}"), |code| format!("{code}"));
+    format!(
+        "{name}({:#x}, {:#x}, {:#x})",
+        regs.x[A0], regs.x[A1], regs.x[A2]
+    )
+}
+
+fn format_return(code: Option<SyscallCode>, regs: &PtraceRegs) -> String {
+    let (a0, a1) = (regs.x[A0], regs.x[A1]);
+    if a0 == usize::MAX {
+        return SyscallError::from_repr(a1.cast_signed()).map_or_else(
+            || format!("-1 (unknown error {})", a1.cast_signed()),
+            |e| format!("-1 ({})", Ov6Error::from(e)),
+        );
+    }
+    // a successful `exec` returns the arguments of `main` instead.
+    if code == Some(SyscallCode::Exec) {
+        return String::from("0");
+    }
+    format!("{a1}")
+}
+
+fn main() {
+    let mut args = env::args_os();
+    let _ = args.next(); // skip the program name
+
+    let args = args.collect::<Vec<_>>();
+    if args.is_empty() {
+        usage_and_exit!("command...");
+    }
+
+    let arg0 = args[0];
+    let mut child = ProcessBuilder::new()
+        .spawn_fn(|| {
+            syscall::ptrace_traceme().or_exit(|e| exit_err!(e, "cannot start tracing"));
+            let Err(e) = process::exec_search(arg0, &args, OsStr::new(process::DEFAULT_PATH));
+            exit_err!(e, "failed to execute '{}'", arg0.display());
+        })
+        .or_exit(|e| exit_err!(e, "cannot spawn child process"));
+    let pid = child.id();
+
+    // the system call the process has entered, printed when it returns.
+    let mut entry = None;
+    loop {
+        let stop =
+            syscall::ptrace_wait(pid).or_exit(|e| exit_err!(e, "cannot wait for process '{pid}'"));
+        match stop {
+            PtraceStop::SyscallEntry => {
+                let regs = get_regs(pid);
+                entry = Some((SyscallCode::from_repr(regs.x[A7]), format_entry(&regs)));
+            }
+            PtraceStop::SyscallExit => {
+                let regs = get_regs(pid);
+                if let Some((code, entry)) = entry.take() {
+                    eprintln!("{entry} = {}", format_return(code, &regs));
+                }
+            }
+            PtraceStop::Breakpoint => {
+                let regs = get_regs(pid);
+                message!("process '{pid}' hit a breakpoint at {:#x}", regs.pc);
+                child
+                    .kill()
+                    .or_exit(|e| exit_err!(e, "cannot kill process '{pid}'"));
+                continue;
+            }
+            PtraceStop::Exited => break,
+            PtraceStop::Attach | PtraceStop::Exec | PtraceStop::Step => {}
+        }
+        syscall::ptrace_syscall(pid).or_exit(|e| exit_err!(e, "cannot resume process '{pid}'"));
+    }
+
+    // `exit` does not return.
+    if let Some((_code, entry)) = entry {
+        eprintln!("{entry} = ?");
+    }
+
+    let status = child
+        .wait()
+        .or_exit(|e| exit_err!(e, "cannot wait for process '{pid}'"));
+    eprintln!("+++ exited with {} +++", status.code());
+    process::exit(status.code());
+}
@@ -0,0 +1,651 @@
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use dataview::{Pod, PodMethods as _};
+use ov6_user_lib::{
+    env, eprintln,
+    error::Ov6Error,
+    fs::{self, File},
+    io::{self, Read, Write},
+    os_str::OsStr,
+    path::{Component, Path, PathBuf},
+    println, process,
+};
+use ov6_utilities::{
+    OrExit as _,
+    args::{Arg, Opt, Parser},
+    compress::{Lz4Reader, Lz4Writer},
+    exit, exit_err, message, message_err,
+};
+
+const OPTS: &[Opt] = &[
+    Opt::flag("create").short('c').help("Create a new archive"),
+    Opt::flag("extract")
+        .short('x')
+        .help("Extract files from an archive"),
+    Opt::flag("list")
+        .short('t')
+        .help("List the contents of an archive"),
+    Opt::value("file", "ARCHIVE")
+        .short('f')
+        .help("Use ARCHIVE, or standard input/output if '-'"),
+    Opt::value("directory", "DIR")
+        .short('C')
+        .help("Change to DIR before doing anything"),
+    Opt::flag("verbose")
+        .short('v')
+        .help("List the files processed"),
+    Opt::flag("lz4").help("Compress or decompress the archive with LZ4"),
+];
+
+/// Size of a header and of the unit the data is padded to.
+const BLOCK_SIZE: usize = 512;
+
+const MAGIC: [u8; 6] = *b"ustar\0";
+const VERSION: [u8; 2] = *b"00";
+
+const TYPE_FILE: u8 = b'0';
+const TYPE_FILE_OLD: u8 = b'\0';
+const TYPE_DIR: u8 = b'5';
+
+/// A ustar header.
+///
+/// Numbers are stored as NUL-terminated octal strings.
+#[derive(Pod)]
+#[repr(C)]
+struct Header {
+    name: [u8; 100],
+    mode: [u8; 8],
+    uid: [u8; 8],
+    gid: [u8; 8],
+    size: [u8; 12],
+    mtime: [u8; 12],
+    checksum: [u8; 8],
+    typeflag: u8,
+    linkname: [u8; 100],
+    magic: [u8; 6],
+    version: [u8; 2],
+    uname: [u8; 32],
+    gname: [u8; 32],
+    devmajor: [u8; 8],
+    devminor: [u8; 8],
+    prefix: [u8; 155],
+    pad: [u8; 12],
+}
+const _: () = assert!(size_of::<Header>() == BLOCK_SIZE);
+
+impl Header {
+    /// Creates a header of a file or a directory at `path`.
+    ///
+    /// Returns `None` if `path` cannot be stored in a header.
+    fn new(path: &[u8], typeflag: u8, size: u64) -> Option<Self> {
+        let (prefix, name) = split_path(path)?;
+        let mut header = Self::zeroed();
+        header.name[..name.len()].copy_from_slice(name);
+        header.prefix[..prefix.len()].copy_from_slice(prefix);
+        let mode = if typeflag == TYPE_DIR { 0o755 } else { 0o644 };
+        write_octal(&mut header.mode, mode)?;
+        write_octal(&mut header.uid, 0)?;
+        write_octal(&mut header.gid, 0)?;
+        write_octal(&mut header.size, size)?;
+        // ov6 has no file times
+        write_octal(&mut header.mtime, 0)?;
+        header.typeflag = typeflag;
+        header.magic = MAGIC;
+        header.version = VERSION;
+
+        let checksum = header.compute_checksum();
+        write_octal(&mut header.checksum[..7], u64::from(checksum))?;
+        header.checksum[7] = b' ';
+        Some(header)
+    }
+
+    /// Returns the sum of the bytes of the header, with the checksum field
+    /// counted as spaces.
+    fn compute_checksum(&self) -> u32 {
+        let sum: u32 = self.as_bytes().iter().map(|&b| u32::from(b)).sum();
+        let field: u32 = self.checksum.iter().map(|&b| u32::from(b)).sum();
+        sum - field + u32::from(b' ') * 8
+    }
+
+    /// Returns `true` if the header is all zeros, which marks the end of the
+    /// archive.
+    fn is_end(&self) -> bool {
+        self.as_bytes().iter().all(|&b| b == 0)
+    }
+
+    fn is_valid(&self) -> bool {
+        self.magic[..5] == MAGIC[..5]
+            && parse_octal(&self.checksum)
+                .is_some_and(|sum| sum == u64::from(self.compute_checksum()))
+    }
+
+    fn path(&self) -> Vec<u8> {
+        let mut path = Vec::new();
+        let prefix = until_nul(&self.prefix);
+        if !prefix.is_empty() {
+            path.extend_from_slice(prefix);
+            path.push(b'/');
+        }
+        path.extend_from_slice(until_nul(&self.name));
+        path
+    }
+
+    fn size(&self) -> Option<u64> {
+        parse_octal(&self.size)
+    }
+}
+
+/// Splits `path` into the prefix and name fields of a header.
+fn split_path(path: &[u8]) -> Option<(&[u8], &[u8])> {
+    const NAME_MAX: usize = 100;
+    const PREFIX_MAX: usize = 155;
+
+    if path.len() <= NAME_MAX {
+        return Some((&[], path));
+    }
+    // the separator between the prefix and the name is not stored
+    let min_sep = path.len() - NAME_MAX - 1;
+    let sep = path
+        .iter()
+        .enumerate()
+        .skip(min_sep)
+        .take(PREFIX_MAX + 1 - min_sep.min(PREFIX_MAX + 1))
+        .find(|&(_, &b)| b == b'/')
+        .map(|(i, _)| i)?;
+    Some((&path[..sep], &path[sep + 1..]))
+}
+
+fn until_nul(field: &[u8]) -> &[u8] {
+    let len = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    &field[..len]
+}
+
+/// Writes `value` to `field` as zero-padded octal digits followed by a NUL.
+fn write_octal(field: &mut [u8], mut value: u64) -> Option<()> {
+    let (nul, digits) = field.split_last_mut()?;
+    *nul = 0;
+    for digit in digits.iter_mut().rev() {
+        *digit = b'0' + u8::try_from(value % 8).unwrap();
+        value /= 8;
+    }
+    (value == 0).then_some(())
+}
+
+/// Parses an octal number terminated by a NUL or a space.
+fn parse_octal(field: &[u8]) -> Option<u64> {
+    let field = field.trim_ascii_start();
+    let len = field
+        .iter()
+        .position(|&b| b == 0 || b == b' ')
+        .unwrap_or(field.len());
+    let digits = &field[..len];
+    if digits.is_empty() {
+        return None;
+    }
+    digits.iter().try_fold(0_u64, |value, &b| {
+        let digit = (b as char).to_digit(8)?;
+        value.checked_mul(8)?.checked_add(u64::from(digit))
+    })
+}
+
+/// Returns the number of bytes padding data of `size` bytes to a block.
+fn padding(size: u64) -> usize {
+    let rem = usize::try_from(size % BLOCK_SIZE as u64).unwrap();
+    (BLOCK_SIZE - rem) % BLOCK_SIZE
+}
+
+/// Returns `path` as stored in an archive, without `/` at the start.
+fn archive_path(path: &Path) -> Option<&[u8]> {
+    let bytes = path.as_os_str().as_bytes();
+    let start = bytes.iter().position(|&b| b != b'/')?;
+    Some(&bytes[start..])
+}
+
+struct Archiver<W> {
+    out: W,
+    verbose: bool,
+    status: i32,
+}
+
+impl<W> Archiver<W>
+where
+    W: Write,
+{
+    fn write(&mut self, data: &[u8]) {
+        self.out
+            .write_all(data)
+            .or_exit(|e| exit_err!(e, "cannot write archive"));
+    }
+
+    fn error(&mut self, e: &Ov6Error, path: &Path, what: &str) {
+        message_err!(e, "cannot {what} '{}'", path.display());
+        self.status = 1;
+    }
+
+    /// Adds `path` and its descendants to the archive.
+    fn add(&mut self, path: &Path) {
+        let meta = match fs::metadata(path) {
+            Ok(meta) => meta,
+            Err(e) => return self.error(&e, path, "stat"),
+        };
+        let Some(name) = archive_path(path) else {
+            // the root directory itself has no entry, but its contents do
+            return self.add_children(path);
+        };
+
+        if meta.is_dir() {
+            let mut name = name.to_vec();
+            if name.last() != Some(&b'/') {
+                name.push(b'/');
+            }
+            if !self.add_header(path, &name, TYPE_DIR, 0) {
+                return;
+            }
+            self.add_children(path);
+        } else if meta.is_file() {
+            self.add_file(path, name, meta.size());
+        } else {
+            message!("skipping special file '{}'", path.display());
+        }
+    }
+
+    fn add_header(&mut self, path: &Path, name: &[u8], typeflag: u8, size: u64) -> bool {
+        let Some(header) = Header::new(name, typeflag, size) else {
+            message!("file name too long: '{}'", path.display());
+            self.status = 1;
+            return false;
+        };
+        if self.verbose {
+            eprintln!("{}", path.display());
+        }
+        self.write(header.as_bytes());
+        true
+    }
+
+    fn add_children(&mut self, path: &Path) {
+        // Read all entries before descending so that the directory is not kept
+        // open during the recursion.
+        let names = match fs::read_dir(path) {
+            Ok(entries) => entries
+                .filter_map(Result::ok)
+                .map(|ent| ent.name().to_os_string())
+                .collect::<Vec<_>>(),
+            Err(e) => return self.error(&e, path, "open directory"),
+        };
+        for name in names {
+            self.add(&path.join(name));
+        }
+    }
+
+    fn add_file(&mut self, path: &Path, name: &[u8], size: u64) {
+        let mut file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) => return self.error(&e, path, "open"),
+        };
+        if !self.add_header(path, name, TYPE_FILE, size) {
+            return;
+        }
+
+        // the header has the size already, so exactly `size` bytes are written
+        let mut buf = [0; BLOCK_SIZE];
+        let mut rest = size;
+        while rest > 0 {
+            let len = usize::try_from(rest.min(BLOCK_SIZE as u64)).unwrap();
+            let buf = &mut buf[..len];
+            if let Err(e) = file.read_exact(buf) {
+                self.error(&e, path, "read");
+                buf.fill(0);
+            }
+            self.write(buf);
+            rest -= len as u64;
+        }
+        self.write(&[0; BLOCK_SIZE][..padding(size)]);
+    }
+
+    fn finish(mut self) -> (W, i32) {
+        self.write(&[0; BLOCK_SIZE * 2]);
+        self.out
+            .flush()
+            .or_exit(|e| exit_err!(e, "cannot write archive"));
+        (self.out, self.status)
+    }
+}
+
+fn create<W>(out: W, paths: &[&Path], verbose: bool) -> (W, i32)
+where
+    W: Write,
+{
+    let mut archiver = Archiver {
+        out,
+        verbose,
+        status: 0,
+    };
+    for path in paths {
+        archiver.add(path);
+    }
+    archiver.finish()
+}
+
+fn create_lz4<W>(out: W, paths: &[&Path], verbose: bool) -> i32
+where
+    W: Write,
+{
+    let out = Lz4Writer::new(out).or_exit(|e| exit_err!(e, "cannot write archive"));
+    let (out, status) = create(out, paths, verbose);
+    out.finish()
+        .or_exit(|e| exit_err!(e, "cannot write archive"));
+    status
+}
+
+/// Returns the path to extract an entry to, or `None` if the path may climb
+/// out of the current directory.
+fn extract_path(name: &[u8]) -> Option<PathBuf> {
+    let path = Path::new(OsStr::from_bytes(name));
+    let mut out = PathBuf::new();
+    for comp in path.components() {
+        match comp {
+            Component::RootDir | Component::CurDir => {}
+            Component::ParentDir => return None,
+            Component::Normal(name) => out.push(name),
+        }
+    }
+    (!out.as_os_str().is_empty()).then_some(out)
+}
+
+/// Reads `size` bytes of data and the padding after it, passing the data to
+/// `f`.
+fn read_data<R, F>(input: &mut R, size: u64, mut f: F)
+where
+    R: Read,
+    F: FnMut(&[u8]),
+{
+    let mut buf = [0; BLOCK_SIZE];
+    let mut rest = size;
+    while rest > 0 {
+        let len = usize::try_from(rest.min(BLOCK_SIZE as u64)).unwrap();
+        input
+            .read_exact(&mut buf[..len])
+            .or_exit(|e| exit_err!(e, "cannot read archive"));
+        f(&buf[..len]);
+        rest -= len as u64;
+    }
+    input
+        .read_exact(&mut buf[..padding(size)])
+        .or_exit(|e| exit_err!(e, "cannot read archive"));
+}
+
+fn extract<R>(mut input: R, list: bool, verbose: bool) -> i32
+where
+    R: Read,
+{
+    let mut status = 0;
+    let mut header = Header::zeroed();
+    loop {
+        input
+            .read_exact(header.as_bytes_mut())
+            .or_exit(|e| exit_err!(e, "cannot read archive"));
+        if header.is_end() {
+            break;
+        }
+        if !header.is_valid() {
+            exit!("invalid archive header");
+        }
+        let name = header.path();
+        let name_str = OsStr::from_bytes(&name).display();
+        let Some(size) = header.size() else {
+            exit!("invalid size of '{name_str}'");
+        };
+
+        if list {
+            if verbose {
+                println!("{size:>8} {name_str}");
+            } else {
+                println!("{name_str}");
+            }
+            read_data(&mut input, size, |_| {});
+            continue;
+        }
+
+        let Some(path) = extract_path(&name) else {
+            message!("skipping unsafe path '{name_str}'");
+            status = 1;
+            read_data(&mut input, size, |_| {});
+            continue;
+        };
+        if verbose {
+            eprintln!("{}", path.display());
+        }
+
+        match header.typeflag {
+            TYPE_DIR => {
+                match fs::create_dir(&path) {
+                    Ok(()) | Err(Ov6Error::AlreadyExists) => {}
+                    Err(e) => {
+                        message_err!(e, "cannot create directory '{}'", path.display());
+                        status = 1;
+                    }
+                }
+                read_data(&mut input, size, |_| {});
+            }
+            TYPE_FILE | TYPE_FILE_OLD => {
+                let mut file = File::create(&path)
+                    .inspect_err(|e| {
+                        message_err!(e, "cannot create '{}'", path.display());
+                        status = 1;
+                    })
+                    .ok();
+                read_data(&mut input, size, |data| {
+                    if let Some(f) = &mut file
+                        && let Err(e) = f.write_all(data)
+                    {
+                        message_err!(e, "cannot write '{}'", path.display());
+                        status = 1;
+                        file = None;
+                    }
+                });
+            }
+            _ => {
+                message!("skipping unsupported entry '{name_str}'");
+                status = 1;
+                read_data(&mut input, size, |_| {});
+            }
+        }
+    }
+    status
+}
+
+fn extract_lz4<R>(input: R, list: bool, verbose: bool) -> i32
+where
+    R: Read,
+{
+    let mut input = Lz4Reader::new(input);
+    let status = extract(&mut input, list, verbose);
+    // read the rest of the stream to check the checksum at its end
+    let mut buf = [0; BLOCK_SIZE];
+    while input.read(&mut buf).or_exit(|e| {
+        if let Some(e) = input.error() {
+            exit_err!(e, "cannot decompress archive");
+        }
+        exit_err!(e, "cannot read archive")
+    }) > 0
+    {}
+    status
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Create,
+    Extract,
+    List,
+}
+
+fn main() {
+    let mut mode = None;
+    let mut archive = None;
+    let mut directory = None;
+    let mut verbose = false;
+    let mut lz4 = false;
+    let mut paths = Vec::new();
+
+    let mut parser = Parser::new(OPTS, "[path...]");
+    while let Some(arg) = parser.next() {
+        let new_mode = match arg {
+            Arg::Flag("create") => Mode::Create,
+            Arg::Flag("extract") => Mode::Extract,
+            Arg::Flag("list") => Mode::List,
+            Arg::Flag("verbose") => {
+                verbose = true;
+                continue;
+            }
+            Arg::Flag("lz4") => {
+                lz4 = true;
+                continue;
+            }
+            Arg::Value("file", file) => {
+                archive = Some(Path::new(file));
+                continue;
+            }
+            Arg::Value("directory", dir) => {
+                directory = Some(Path::new(dir));
+                continue;
+            }
+            Arg::Positional(path) => {
+                paths.push(Path::new(path));
+                continue;
+            }
+            _ => unreachable!(),
+        };
+        if mode.is_some_and(|mode| mode != new_mode) {
+            parser.usage_error(format_args!("only one of -c, -x and -t can be given"));
+        }
+        mode = Some(new_mode);
+    }
+
+    let Some(mode) = mode else {
+        parser.usage_error(format_args!("one of -c, -x and -t must be given"));
+    };
+    let Some(archive) = archive else {
+        parser.usage_error(format_args!("archive must be given with -f"));
+    };
+    if mode == Mode::Create && paths.is_empty() {
+        parser.usage_error(format_args!("no files to archive"));
+    }
+    if mode != Mode::Create && !paths.is_empty() {
+        parser.usage_error(format_args!("unexpected argument"));
+    }
+    let to_stdio = archive == Path::new("-");
+
+    // the archive is opened before changing the directory, so that a relative
+    // path to it refers to the directory `tar` is run in
+    let status = match mode {
+        Mode::Create => {
+            let file = (!to_stdio).then(|| {
+                File::create(archive)
+                    .or_exit(|e| exit_err!(e, "cannot create '{}'", archive.display()))
+            });
+            if let Some(dir) = directory {
+                env::set_current_directory(dir)
+                    .or_exit(|e| exit_err!(e, "cannot change directory to '{}'", dir.display()));
+            }
+            match (file, lz4) {
+                (Some(file), false) => create(file, &paths, verbose).1,
+                (Some(file), true) => create_lz4(file, &paths, verbose),
+                (None, false) => create(io::stdout().lock(), &paths, verbose).1,
+                (None, true) => create_lz4(io::stdout().lock(), &paths, verbose),
+            }
+        }
+        Mode::Extract | Mode::List => {
+            let file = (!to_stdio).then(|| {
+                File::open(archive).or_exit(|e| exit_err!(e, "cannot open '{}'", archive.display()))
+            });
+            if let Some(dir) = directory {
+                env::set_current_directory(dir)
+                    .or_exit(|e| exit_err!(e, "cannot change directory to '{}'", dir.display()));
+            }
+            let list = mode == Mode::List;
+            match (file, lz4) {
+                (Some(file), false) => extract(file, list, verbose),
+                (Some(file), true) => extract_lz4(file, list, verbose),
+                (None, false) => extract(io::stdin().lock(), list, verbose),
+                (None, true) => extract_lz4(io::stdin().lock(), list, verbose),
+            }
+        }
+    };
+
+    process::exit(status);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_octal() {
+        let mut field = [0xff; 8];
+        assert_eq!(write_octal(&mut field, 0o755), Some(()));
+        assert_eq!(&field, b"0000755\0");
+        assert_eq!(parse_octal(&field), Some(0o755));
+        assert_eq!(parse_octal(b"  644 \0"), Some(0o644));
+        assert_eq!(parse_octal(b"\0\0\0"), None);
+        assert_eq!(parse_octal(b"0009\0"), None);
+        assert_eq!(write_octal(&mut [0; 3], 0o100), None);
+    }
+
+    #[test]
+    fn test_split_path() {
+        assert_eq!(split_path(b"a/b"), Some((&b""[..], &b"a/b"[..])));
+
+        let long = [b"d".repeat(150), b"f".repeat(90)].join(&b'/');
+        let (prefix, name) = split_path(&long).unwrap();
+        assert_eq!(prefix, b"d".repeat(150));
+        assert_eq!(name, b"f".repeat(90));
+
+        // no separator that leaves the name short enough
+        assert_eq!(split_path(&b"f".repeat(101)), None);
+        let long = [b"d".repeat(10), b"f".repeat(101)].join(&b'/');
+        assert_eq!(split_path(&long), None);
+        // prefix too long
+        let long = [b"d".repeat(156), b"f".repeat(10)].join(&b'/');
+        assert_eq!(split_path(&long), None);
+    }
+
+    #[test]
+    fn test_header() {
+        let header = Header::new(b"dir/file", TYPE_FILE, 1234).unwrap();
+        assert!(header.is_valid());
+        assert!(!header.is_end());
+        assert_eq!(header.path(), b"dir/file");
+        assert_eq!(header.size(), Some(1234));
+        assert_eq!(&header.size, b"00000002322\0");
+
+        let mut broken = Header::zeroed();
+        broken.as_bytes_mut().copy_from_slice(header.as_bytes());
+        broken.name[0] = b'x';
+        assert!(!broken.is_valid());
+
+        let long = [b"d".repeat(150), b"f".repeat(90)].join(&b'/');
+        let header = Header::new(&long, TYPE_FILE, 0).unwrap();
+        assert_eq!(header.path(), long);
+        assert!(Header::zeroed().is_end());
+    }
+
+    #[test]
+    fn test_padding() {
+        assert_eq!(padding(0), 0);
+        assert_eq!(padding(1), 511);
+        assert_eq!(padding(512), 0);
+        assert_eq!(padding(513), 511);
+    }
+
+    #[test]
+    fn test_extract_path() {
+        assert_eq!(extract_path(b"a/b/"), Some(PathBuf::from("a/b")));
+        assert_eq!(extract_path(b"/a/./b"), Some(PathBuf::from("a/b")));
+        assert_eq!(extract_path(b"a/../../b"), None);
+        assert_eq!(extract_path(b"/"), None);
+    }
+}
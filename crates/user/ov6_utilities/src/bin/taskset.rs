@@ -0,0 +1,108 @@
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+
+use alloc::{string::String, vec::Vec};
+use core::fmt::Write as _;
+
+use ov6_user_lib::{
+    env,
+    os::ov6::syscall::{self, CpuSet, MAX_CPUS},
+    os_str::OsStr,
+    println, process,
+};
+use ov6_utilities::{OrExit as _, exit, exit_err, usage_and_exit};
+
+/// Parses a list of CPU IDs and ranges, such as `0,2-3`.
+fn parse_cpu_list(s: &str) -> Option<CpuSet> {
+    let mut set = CpuSet::EMPTY;
+    for item in s.split(',') {
+        let (start, end) = if let Some((start, end)) = item.split_once('-') {
+            (start.parse().ok()?, end.parse().ok()?)
+        } else {
+            let cpu = item.parse().ok()?;
+            (cpu, cpu)
+        };
+        if start > end || end >= MAX_CPUS {
+            return None;
+        }
+        for cpu in start..=end {
+            set.insert(cpu);
+        }
+    }
+    Some(set)
+}
+
+fn format_cpu_list(set: CpuSet) -> String {
+    let mut s = String::new();
+    for (i, cpu) in set.iter().enumerate() {
+        if i > 0 {
+            s.push(',');
+        }
+        write!(s, "{cpu}").unwrap();
+    }
+    s
+}
+
+fn cpu_list_arg(arg: &OsStr) -> CpuSet {
+    arg.to_str()
+        .and_then(parse_cpu_list)
+        .unwrap_or_else(|| exit!("invalid CPU list '{}'", arg.display()))
+}
+
+fn main() {
+    let mut args = env::args_os();
+    let _ = args.next(); // skip the program name
+
+    if args.len() < 2 {
+        usage_and_exit!("<cpus> <command...> | -p <pid> [cpus]");
+    }
+
+    let first = args.next().unwrap();
+    if first == "-p" {
+        let pid = args.next().unwrap();
+        let Some(pid) = pid.to_str().and_then(|s| s.parse().ok()) else {
+            exit!("invalid pid '{}'", pid.display());
+        };
+        if let Some(cpus) = args.next() {
+            let set = cpu_list_arg(cpus);
+            syscall::set_affinity(pid, set)
+                .or_exit(|e| exit_err!(e, "cannot set affinity of process '{pid}'"));
+        } else {
+            let set = syscall::affinity(pid)
+                .or_exit(|e| exit_err!(e, "cannot get affinity of process '{pid}'"));
+            println!("{}", format_cpu_list(set));
+        }
+        return;
+    }
+
+    let set = cpu_list_arg(first);
+    syscall::set_affinity(process::id(), set).or_exit(|e| exit_err!(e, "cannot set affinity"));
+
+    let args = args.collect::<Vec<_>>();
+    let arg0 = args.first().unwrap();
+    let Err(e) = process::exec_search(arg0, &args, OsStr::new(process::DEFAULT_PATH));
+    exit_err!(e, "failed to exec '{}'", arg0.display());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cpu_list() {
+        assert_eq!(parse_cpu_list("0"), Some(CpuSet::single(0)));
+        assert_eq!(parse_cpu_list("0,2-3").map(CpuSet::bits), Some(0b1101));
+        assert_eq!(parse_cpu_list("1-1,1"), Some(CpuSet::single(1)));
+        assert_eq!(parse_cpu_list(""), None);
+        assert_eq!(parse_cpu_list("3-2"), None);
+        assert_eq!(parse_cpu_list("0,"), None);
+        assert_eq!(parse_cpu_list(&alloc::format!("{MAX_CPUS}")), None);
+    }
+
+    #[test]
+    fn test_format_cpu_list() {
+        assert_eq!(format_cpu_list(CpuSet::EMPTY), "");
+        assert_eq!(format_cpu_list(CpuSet::from_bits(0b1101).unwrap()), "0,2,3");
+    }
+}
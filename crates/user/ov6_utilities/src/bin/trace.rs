@@ -8,6 +8,7 @@ use core::str::FromStr as _;
 use ov6_user_lib::{
     env,
     os::ov6::syscall::{self, SyscallCode},
+    os_str::OsStr,
     process,
 };
 use ov6_utilities::{exit, message_err, usage_and_exit};
@@ -49,7 +50,10 @@ fn main() {
         }
         let Ok(syscall) =
             SyscallCode::from_str(part).map_err(|_e| exit!("invalid mask '{}'", part));
-        mask |= 1 << syscall as usize;
+        let Some(bit) = 1_u64.checked_shl(syscall as u32) else {
+            exit!("system call '{}' cannot be traced", part);
+        };
+        mask |= bit;
     }
 
     let args = args.collect::<Vec<_>>();
@@ -57,6 +61,6 @@ fn main() {
     syscall::trace(mask);
 
     let arg0 = args.first().unwrap();
-    let Err(e) = process::exec(arg0, &args);
+    let Err(e) = process::exec_search(arg0, &args, OsStr::new(process::DEFAULT_PATH));
     message_err!(e, "failed to exec '{}'", arg0.display());
 }
@@ -0,0 +1,474 @@
+//! A simple debugger built on the process tracing system calls.
+//!
+//! The debugged program is started stopped at its entry point. Breakpoints are
+//! set by replacing the instruction at the address with `c.ebreak`, and the
+//! original instruction is put back while the program steps over it.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::{format, string::String, vec::Vec};
+
+use ov6_line_editor::LineEditor;
+use ov6_user_lib::{
+    error::Ov6Error,
+    fs::{self, File},
+    io::Read as _,
+    os::ov6::syscall::{self, PtraceRegs, PtraceStop},
+    os_str::OsStr,
+    path::{Path, PathBuf},
+    println,
+    process::{self, ChildWithIo, ProcId, ProcessBuilder},
+};
+use ov6_utilities::{
+    OrExit as _,
+    args::{Arg, Opt, Parser},
+    exit_err, message, message_err,
+};
+
+use self::symbols::Symbols;
+
+mod symbols;
+
+const OPTS: &[Opt] = &[Opt::value("eval-command", "COMMAND")
+    .short('e')
+    .help("Run COMMAND instead of reading commands from the input")];
+
+const HELP: &str = "\
+break [LOCATION]  set a breakpoint at LOCATION, or list the breakpoints
+delete N          delete breakpoint N
+continue          resume the program
+step [N]          execute N instructions
+regs              show the registers
+x ADDR [N]        show N words of memory at ADDR
+bt                show the backtrace
+kill              kill the program
+quit              kill the program and exit";
+
+const C_EBREAK: u16 = 0x9002;
+
+const RA: usize = 1;
+const FP: usize = 8;
+
+const REG_NAMES: [&str; 32] = [
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2", "a3", "a4",
+    "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4",
+    "t5", "t6",
+];
+
+/// Maximum number of frames shown by `bt`.
+const MAX_FRAMES: usize = 32;
+
+#[derive(Debug)]
+struct Breakpoint {
+    id: usize,
+    addr: usize,
+    /// Instruction halfword replaced by `c.ebreak`.
+    orig: u16,
+}
+
+struct Debugger {
+    child: ChildWithIo,
+    pid: ProcId,
+    symbols: Symbols,
+    breakpoints: Vec<Breakpoint>,
+    next_id: usize,
+    /// Whether the program is stopped at an `ebreak` it contains itself.
+    at_ebreak: bool,
+    exited: bool,
+}
+
+/// Finds the program file `name` in the same way as `exec_search`.
+fn find_program(name: &OsStr) -> Option<PathBuf> {
+    if name.as_bytes().contains(&b'/') {
+        return Some(Path::new(name).to_path_buf());
+    }
+    process::DEFAULT_PATH
+        .split(':')
+        .map(|dir| Path::new(if dir.is_empty() { "." } else { dir }).join(name))
+        .find(|path| fs::metadata(path).is_ok())
+}
+
+fn load_symbols(name: &OsStr) -> Symbols {
+    let Some(path) = find_program(name) else {
+        return Symbols::default();
+    };
+    let mut data = Vec::new();
+    if let Err(e) = File::open(&path).and_then(|mut file| file.read_to_end(&mut data)) {
+        message_err!(e, "cannot read '{}'", path.display());
+        return Symbols::default();
+    }
+    Symbols::parse(&data).unwrap_or_else(|e| {
+        message!("no symbols in '{}': {e}", path.display());
+        Symbols::default()
+    })
+}
+
+fn parse_number(s: &str) -> Option<usize> {
+    s.strip_prefix("0x")
+        .map_or_else(|| s.parse().ok(), |hex| usize::from_str_radix(hex, 16).ok())
+}
+
+/// Returns the length of the instruction whose first halfword is `insn`.
+fn insn_len(insn: u16) -> usize {
+    if insn & 0b11 == 0b11 { 4 } else { 2 }
+}
+
+impl Debugger {
+    fn new(args: &[&OsStr]) -> Self {
+        let symbols = load_symbols(args[0]);
+        let child = ProcessBuilder::new()
+            .spawn_fn(|| {
+                syscall::ptrace_traceme().or_exit(|e| exit_err!(e, "cannot start tracing"));
+                let Err(e) = process::exec_search(args[0], args, OsStr::new(process::DEFAULT_PATH));
+                exit_err!(e, "failed to execute '{}'", args[0].display());
+            })
+            .or_exit(|e| exit_err!(e, "cannot spawn child process"));
+        let pid = child.id();
+        let mut debugger = Self {
+            child,
+            pid,
+            symbols,
+            breakpoints: Vec::new(),
+            next_id: 1,
+            at_ebreak: false,
+            exited: false,
+        };
+        debugger.wait();
+        debugger
+    }
+
+    fn describe(&self, addr: usize) -> String {
+        match self.symbols.lookup(addr) {
+            Some((name, 0)) => format!("{addr:#x} in {name}"),
+            Some((name, offset)) => format!("{addr:#x} in {name}+{offset:#x}"),
+            None => format!("{addr:#x}"),
+        }
+    }
+
+    fn location(&self, s: &str) -> Option<usize> {
+        parse_number(s).or_else(|| self.symbols.find(s))
+    }
+
+    fn regs(&self) -> Result<PtraceRegs, Ov6Error> {
+        syscall::ptrace_get_regs(self.pid)
+    }
+
+    fn read_u16(&self, addr: usize) -> Result<u16, Ov6Error> {
+        let offset = addr % 8;
+        let bytes = syscall::ptrace_peek(self.pid, addr - offset)?.to_le_bytes();
+        Ok(u16::from_le_bytes([bytes[offset], bytes[offset + 1]]))
+    }
+
+    fn write_u16(&self, addr: usize, value: u16) -> Result<(), Ov6Error> {
+        let offset = addr % 8;
+        let mut bytes = syscall::ptrace_peek(self.pid, addr - offset)?.to_le_bytes();
+        bytes[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+        syscall::ptrace_poke(self.pid, addr - offset, usize::from_le_bytes(bytes))
+    }
+
+    /// Waits for the program to stop, and reports where it stopped.
+    fn wait(&mut self) {
+        let stop = syscall::ptrace_wait(self.pid)
+            .or_exit(|e| exit_err!(e, "cannot wait for process '{}'", self.pid));
+        self.at_ebreak = false;
+        if stop == PtraceStop::Exited {
+            self.exited = true;
+            let status = self
+                .child
+                .wait()
+                .or_exit(|e| exit_err!(e, "cannot wait for process '{}'", self.pid));
+            println!("program exited with status {}", status.code());
+            return;
+        }
+
+        let pc = self.regs().map_or(0, |regs| regs.pc);
+        let at = self.describe(pc);
+        match stop {
+            PtraceStop::Breakpoint => {
+                if let Some(bp) = self.breakpoints.iter().find(|bp| bp.addr == pc) {
+                    println!("breakpoint {}, {at}", bp.id);
+                } else {
+                    self.at_ebreak = true;
+                    println!("program hit ebreak, {at}");
+                }
+            }
+            PtraceStop::Exec => {
+                println!("program stopped at entry, {at}");
+            }
+            PtraceStop::Step => {
+                println!("{at}");
+            }
+            PtraceStop::Attach | PtraceStop::SyscallEntry | PtraceStop::SyscallExit => {
+                println!("program stopped, {at}");
+            }
+            PtraceStop::Exited => unreachable!(),
+        }
+    }
+
+    /// Executes the instruction at the program counter without stopping at
+    /// a breakpoint set on it.
+    ///
+    /// Returns `false` if the program has exited.
+    fn step_over_breakpoint(&mut self) -> Result<bool, Ov6Error> {
+        let mut regs = self.regs()?;
+        if self.at_ebreak {
+            // skip the `ebreak` in the program, which would trap again.
+            regs.pc += insn_len(self.read_u16(regs.pc)?);
+            syscall::ptrace_set_regs(self.pid, &regs)?;
+            self.at_ebreak = false;
+            return Ok(true);
+        }
+
+        let Some(bp) = self.breakpoints.iter().find(|bp| bp.addr == regs.pc) else {
+            return Ok(true);
+        };
+        let (addr, orig) = (bp.addr, bp.orig);
+        self.write_u16(addr, orig)?;
+        syscall::ptrace_step(self.pid)?;
+        if syscall::ptrace_wait(self.pid)? == PtraceStop::Exited {
+            self.exited = true;
+            let status = self.child.wait()?;
+            println!("program exited with status {}", status.code());
+            return Ok(false);
+        }
+        self.write_u16(addr, C_EBREAK)?;
+        Ok(true)
+    }
+
+    fn cmd_break(&mut self, arg: Option<&str>) -> Result<(), Ov6Error> {
+        let Some(arg) = arg else {
+            for bp in &self.breakpoints {
+                println!("{}: {}", bp.id, self.describe(bp.addr));
+            }
+            return Ok(());
+        };
+        let Some(addr) = self.location(arg) else {
+            println!("unknown location '{arg}'");
+            return Ok(());
+        };
+        if !addr.is_multiple_of(2) {
+            println!("misaligned address {addr:#x}");
+            return Ok(());
+        }
+        if self.breakpoints.iter().any(|bp| bp.addr == addr) {
+            println!("breakpoint already set at {addr:#x}");
+            return Ok(());
+        }
+
+        let orig = self.read_u16(addr)?;
+        self.write_u16(addr, C_EBREAK)?;
+        let id = self.next_id;
+        self.next_id += 1;
+        self.breakpoints.push(Breakpoint { id, addr, orig });
+        println!("breakpoint {id} at {}", self.describe(addr));
+        Ok(())
+    }
+
+    fn cmd_delete(&mut self, arg: Option<&str>) -> Result<(), Ov6Error> {
+        let Some(i) = arg
+            .and_then(parse_number)
+            .and_then(|id| self.breakpoints.iter().position(|bp| bp.id == id))
+        else {
+            println!("no such breakpoint");
+            return Ok(());
+        };
+        let bp = self.breakpoints.remove(i);
+        self.write_u16(bp.addr, bp.orig)
+    }
+
+    fn cmd_continue(&mut self) -> Result<(), Ov6Error> {
+        if self.step_over_breakpoint()? {
+            syscall::ptrace_cont(self.pid)?;
+            self.wait();
+        }
+        Ok(())
+    }
+
+    fn cmd_step(&mut self, arg: Option<&str>) -> Result<(), Ov6Error> {
+        let count = arg.and_then(parse_number).unwrap_or(1);
+        for _ in 0..count {
+            let pc = self.regs()?.pc;
+            if self.at_ebreak || self.breakpoints.iter().any(|bp| bp.addr == pc) {
+                if !self.step_over_breakpoint()? {
+                    break;
+                }
+                println!("{}", self.describe(self.regs()?.pc));
+                continue;
+            }
+            syscall::ptrace_step(self.pid)?;
+            self.wait();
+            if self.exited {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn cmd_regs(&self) -> Result<(), Ov6Error> {
+        let regs = self.regs()?;
+        println!("pc   {}", self.describe(regs.pc));
+        for (i, chunk) in regs.x.chunks(4).enumerate().map(|(i, c)| (i * 4, c)) {
+            let line = chunk
+                .iter()
+                .zip(&REG_NAMES[i..])
+                .map(|(value, name)| format!("{name:<4} {value:#018x}"))
+                .collect::<Vec<_>>();
+            println!("{}", line.join("  "));
+        }
+        Ok(())
+    }
+
+    fn cmd_examine(&self, addr: Option<&str>, count: Option<&str>) -> Result<(), Ov6Error> {
+        let Some(addr) = addr.and_then(|a| self.location(a)) else {
+            println!("unknown location");
+            return Ok(());
+        };
+        let count = count.and_then(parse_number).unwrap_or(4);
+        let start = addr & !7;
+        for addr in (start..).step_by(8).take(count) {
+            let word = syscall::ptrace_peek(self.pid, addr)?;
+            println!("{addr:#x}: {word:#018x}");
+        }
+        Ok(())
+    }
+
+    fn cmd_backtrace(&self) -> Result<(), Ov6Error> {
+        let regs = self.regs()?;
+        let mut frames = Vec::from([regs.pc]);
+        // `ra` holds the return address only until the prologue of the
+        // function saves it and calls another function, so it is used only
+        // when the program stops at the start of a function.
+        if matches!(self.symbols.lookup(regs.pc), Some((_, 0))) {
+            frames.push(regs.x[RA]);
+        }
+
+        // each frame saves the return address at `fp - 8`, and the frame
+        // pointer of the caller at `fp - 16`.
+        let mut fp = regs.x[FP];
+        while frames.len() < MAX_FRAMES && fp >= 16 && fp.is_multiple_of(8) {
+            let Ok(ra) = syscall::ptrace_peek(self.pid, fp - 8) else {
+                break;
+            };
+            let Ok(prev) = syscall::ptrace_peek(self.pid, fp - 16) else {
+                break;
+            };
+            if ra == 0 {
+                break;
+            }
+            frames.push(ra);
+            if prev <= fp {
+                break;
+            }
+            fp = prev;
+        }
+
+        for (i, addr) in frames.into_iter().enumerate() {
+            println!("#{i} {}", self.describe(addr));
+        }
+        Ok(())
+    }
+
+    fn kill(&mut self) {
+        if self.exited {
+            return;
+        }
+        let res = self.child.kill().and_then(|()| self.child.wait());
+        if let Err(e) = res {
+            message_err!(e, "cannot kill process '{}'", self.pid);
+        }
+        self.exited = true;
+    }
+
+    /// Runs a command line, and returns `false` if the debugger should exit.
+    fn run(&mut self, line: &str) -> bool {
+        let mut words = line.split_whitespace();
+        let Some(cmd) = words.next() else {
+            return true;
+        };
+        let (arg1, arg2) = (words.next(), words.next());
+
+        let needs_program = !matches!(cmd, "help" | "h" | "quit" | "q");
+        if needs_program && self.exited {
+            println!("the program is not running");
+            return true;
+        }
+
+        let res = match cmd {
+            "break" | "b" => self.cmd_break(arg1),
+            "delete" | "d" => self.cmd_delete(arg1),
+            "continue" | "c" => self.cmd_continue(),
+            "step" | "s" => self.cmd_step(arg1),
+            "regs" => self.cmd_regs(),
+            "x" => self.cmd_examine(arg1, arg2),
+            "bt" => self.cmd_backtrace(),
+            "kill" => {
+                self.kill();
+                Ok(())
+            }
+            "quit" | "q" => return false,
+            "help" | "h" => {
+                println!("{HELP}");
+                Ok(())
+            }
+            _ => {
+                println!("unknown command '{cmd}', try 'help'");
+                Ok(())
+            }
+        };
+        if let Err(e) = res {
+            message_err!(e, "{cmd}");
+        }
+        true
+    }
+}
+
+fn main() {
+    let mut commands = Vec::new();
+    let mut args = Vec::new();
+    let mut parser = Parser::new(OPTS, "program [args...]");
+    for arg in &mut parser {
+        match arg {
+            Arg::Value("eval-command", cmd) => commands.push(cmd),
+            Arg::Positional(arg) => args.push(arg),
+            Arg::Flag(_) | Arg::Value(..) => unreachable!(),
+        }
+    }
+    if args.is_empty() {
+        parser.usage_error(format_args!("missing program"));
+    }
+
+    let mut debugger = Debugger::new(&args);
+    if debugger.symbols.is_empty() {
+        message!("no symbols loaded, locations must be given as addresses");
+    }
+
+    if commands.is_empty() {
+        let mut editor = LineEditor::new();
+        loop {
+            let line = match editor.read_line("(udb) ") {
+                Ok(Some(line)) => line,
+                Ok(None) => break,
+                Err(e) if e.is_interrupted() => continue,
+                Err(e) => exit_err!(e, "cannot read input"),
+            };
+            if !debugger.run(&line) {
+                break;
+            }
+        }
+    } else {
+        for cmd in commands {
+            let Some(cmd) = cmd.to_str() else {
+                message!("command is not valid UTF-8");
+                continue;
+            };
+            println!("(udb) {cmd}");
+            if !debugger.run(cmd) {
+                break;
+            }
+        }
+    }
+
+    debugger.kill();
+}
@@ -0,0 +1,224 @@
+//! Function symbols read from the symbol table of an ELF file.
+//!
+//! Most programs are installed with their symbol table stripped, in which case
+//! addresses are shown without names.
+
+use alloc::{borrow::ToOwned as _, string::String, vec::Vec};
+
+const ELF_MAGIC: &[u8] = b"\x7fELF";
+const ELFCLASS64: u8 = 2;
+
+const SHT_SYMTAB: u32 = 2;
+const STT_FUNC: u8 = 2;
+const SYM_SIZE: usize = 24;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SymbolError {
+    #[error("not a 64-bit ELF file")]
+    NotElf,
+    #[error("malformed ELF file")]
+    Malformed,
+    #[error("no symbol table")]
+    NoSymbolTable,
+}
+
+#[derive(Debug)]
+struct Symbol {
+    addr: usize,
+    size: usize,
+    name: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Section {
+    ty: u32,
+    offset: usize,
+    size: usize,
+    link: u32,
+}
+
+/// Function symbols of a program, sorted by address.
+#[derive(Debug, Default)]
+pub struct Symbols {
+    syms: Vec<Symbol>,
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset.checked_add(2)?)?;
+    Some(u16::from_le_bytes(bytes.try_into().ok()?))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from_le_bytes(bytes.try_into().ok()?))
+}
+
+fn read_usize(data: &[u8], offset: usize) -> Option<usize> {
+    let bytes = data.get(offset..offset.checked_add(8)?)?;
+    usize::try_from(u64::from_le_bytes(bytes.try_into().ok()?)).ok()
+}
+
+fn read_section(data: &[u8], offset: usize) -> Option<Section> {
+    Some(Section {
+        ty: read_u32(data, offset + 4)?,
+        offset: read_usize(data, offset + 24)?,
+        size: read_usize(data, offset + 32)?,
+        link: read_u32(data, offset + 40)?,
+    })
+}
+
+fn section_data(data: &[u8], section: Section) -> Option<&[u8]> {
+    data.get(section.offset..section.offset.checked_add(section.size)?)
+}
+
+impl Symbols {
+    /// Reads the function symbols from the ELF file `data`.
+    pub fn parse(data: &[u8]) -> Result<Self, SymbolError> {
+        if data.get(..4) != Some(ELF_MAGIC) || data.get(4) != Some(&ELFCLASS64) {
+            return Err(SymbolError::NotElf);
+        }
+        let shoff = read_usize(data, 0x28).ok_or(SymbolError::Malformed)?;
+        let shentsize = read_u16(data, 0x3a).ok_or(SymbolError::Malformed)?;
+        let shnum = read_u16(data, 0x3c).ok_or(SymbolError::Malformed)?;
+        let section = |i: usize| read_section(data, shoff + i * usize::from(shentsize));
+
+        let symtab = (0..usize::from(shnum))
+            .map(section)
+            .collect::<Option<Vec<_>>>()
+            .ok_or(SymbolError::Malformed)?
+            .into_iter()
+            .find(|s| s.ty == SHT_SYMTAB)
+            .ok_or(SymbolError::NoSymbolTable)?;
+        let strtab = usize::try_from(symtab.link)
+            .ok()
+            .and_then(section)
+            .ok_or(SymbolError::Malformed)?;
+        let strs = section_data(data, strtab).ok_or(SymbolError::Malformed)?;
+        let entries = section_data(data, symtab).ok_or(SymbolError::Malformed)?;
+
+        let mut syms = entries
+            .chunks_exact(SYM_SIZE)
+            .filter(|sym| sym[4] & 0xf == STT_FUNC)
+            .filter_map(|sym| {
+                let name = usize::try_from(read_u32(sym, 0)?).ok()?;
+                let name = strs.get(name..)?.split(|b| *b == 0).next()?;
+                let name = str::from_utf8(name).ok()?;
+                let addr = read_usize(sym, 8)?;
+                let size = read_usize(sym, 16)?;
+                (addr != 0).then(|| Symbol {
+                    addr,
+                    size,
+                    name: demangle(name),
+                })
+            })
+            .collect::<Vec<_>>();
+        syms.sort_unstable_by_key(|sym| sym.addr);
+        Ok(Self { syms })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.syms.is_empty()
+    }
+
+    /// Returns the address of the function `name`.
+    ///
+    /// `name` is either the full path of the function (e.g. `prog::main`) or
+    /// its last component (e.g. `main`).
+    pub fn find(&self, name: &str) -> Option<usize> {
+        let last = |sym: &&Symbol| sym.name.rsplit("::").next() == Some(name);
+        self.syms
+            .iter()
+            .find(|sym| sym.name == name)
+            .or_else(|| self.syms.iter().find(last))
+            .map(|sym| sym.addr)
+    }
+
+    /// Returns the name of the function containing `addr` and the offset of
+    /// `addr` from its start.
+    pub fn lookup(&self, addr: usize) -> Option<(&str, usize)> {
+        let i = self.syms.partition_point(|sym| sym.addr <= addr);
+        let sym = self.syms[..i].last()?;
+        let offset = addr - sym.addr;
+        (offset < sym.size.max(1)).then_some((sym.name.as_str(), offset))
+    }
+}
+
+/// Demangles a symbol name in the legacy Rust mangling scheme.
+///
+/// Other names, including those in the v0 scheme, are returned as is.
+fn demangle(name: &str) -> String {
+    let Some(mut rest) = name.strip_prefix("_ZN") else {
+        return name.to_owned();
+    };
+
+    let mut segments = Vec::new();
+    while let Some(digits) = rest.find(|c: char| !c.is_ascii_digit()).filter(|n| *n > 0) {
+        let Some(segment) = rest[..digits]
+            .parse::<usize>()
+            .ok()
+            .and_then(|len| rest.get(digits..digits + len))
+        else {
+            return name.to_owned();
+        };
+        segments.push(segment);
+        rest = &rest[digits + segment.len()..];
+    }
+    if rest != "E" || segments.is_empty() {
+        return name.to_owned();
+    }
+
+    // drop the hash appended to make the symbol unique
+    if segments.last().is_some_and(|s| is_hash(s)) {
+        segments.pop();
+    }
+
+    let mut demangled = String::new();
+    for (i, segment) in segments.iter().enumerate() {
+        if i > 0 {
+            demangled.push_str("::");
+        }
+        unescape(&mut demangled, segment);
+    }
+    demangled
+}
+
+fn is_hash(segment: &str) -> bool {
+    segment
+        .strip_prefix('h')
+        .is_some_and(|hex| hex.len() == 16 && hex.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+fn unescape(out: &mut String, mut segment: &str) {
+    const ESCAPES: &[(&str, &str)] = &[
+        ("$LT$", "<"),
+        ("$GT$", ">"),
+        ("$RF$", "&"),
+        ("$BP$", "*"),
+        ("$C$", ","),
+        ("$u20$", " "),
+        ("$u27$", "'"),
+        ("$u5b$", "["),
+        ("$u5d$", "]"),
+        ("$u7b$", "{"),
+        ("$u7d$", "}"),
+        ("$u7e$", "~"),
+        ("..", "::"),
+    ];
+
+    // a leading `_` escapes a segment starting with `$`
+    if segment.starts_with("_$") {
+        segment = &segment[1..];
+    }
+    'outer: while !segment.is_empty() {
+        for (escaped, c) in ESCAPES {
+            if let Some(rest) = segment.strip_prefix(escaped) {
+                out.push_str(c);
+                segment = rest;
+                continue 'outer;
+            }
+        }
+        let c = segment.chars().next().unwrap();
+        out.push(c);
+        segment = &segment[c.len_utf8()..];
+    }
+}
@@ -1,11 +1,68 @@
 #![no_std]
 
-use core::time::Duration;
+use core::{fmt, time::Duration};
 
-use ov6_user_lib::{os::ov6::syscall, println};
+use ov6_user_lib::{
+    os::ov6::syscall::{self, LOAD_FSHIFT},
+    println,
+};
+use ov6_utilities::{
+    OrExit as _,
+    args::{Arg, Opt, Parser},
+    exit_err,
+};
+
+const OPTS: &[Opt] = &[Opt::flag("cpus")
+    .short('c')
+    .help("Show the utilization of each CPU since boot")];
+
+/// A load average in fixed point, displayed with two decimals.
+struct Load(u64);
+
+impl fmt::Display for Load {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hundredths = (self.0 * 100 + (1 << (LOAD_FSHIFT - 1))) >> LOAD_FSHIFT;
+        write!(f, "{}.{:02}", hundredths / 100, hundredths % 100)
+    }
+}
 
 fn main() {
-    let nanos = syscall::uptime();
-    let uptime = Duration::from_nanos(nanos);
-    println!("{}.{:09}", uptime.as_secs(), uptime.subsec_nanos());
+    let mut cpus = false;
+    let mut parser = Parser::new(OPTS, "");
+    while let Some(arg) = parser.next() {
+        match arg {
+            Arg::Flag("cpus") => cpus = true,
+            Arg::Positional(s) => {
+                parser.usage_error(format_args!("unexpected argument '{}'", s.display()));
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    let info = syscall::get_system_info().or_exit(|e| exit_err!(e, "cannot get system info"));
+    let load = info.load;
+
+    let secs = Duration::from_nanos(load.uptime_nanos).as_secs();
+    let [avg1, avg5, avg15] = load.averages.map(Load);
+    println!(
+        "up {}:{:02}:{:02}, {} cpus, load average: {avg1}, {avg5}, {avg15}",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60,
+        load.num_cpus,
+    );
+
+    if cpus {
+        for (id, (busy, idle)) in load
+            .busy_ticks
+            .iter()
+            .zip(load.idle_ticks)
+            .take(load.num_cpus)
+            .enumerate()
+        {
+            let total = (busy + idle).max(1);
+            let permille = busy * 1000 / total;
+            println!("cpu{id}: {}.{}% busy", permille / 10, permille % 10);
+        }
+    }
 }
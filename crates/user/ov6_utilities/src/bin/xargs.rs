@@ -32,7 +32,7 @@ const STACK_SIZE_LIMIT: usize = USER_STACK_SIZE / 8;
 fn exec(argv: &[Cow<'_, OsStr>]) {
     loop {
         let res = ProcessBuilder::new().spawn_fn(|| {
-            let Err(e) = process::exec(&argv[0], argv);
+            let Err(e) = process::exec_search(&argv[0], argv, OsStr::new(process::DEFAULT_PATH));
             exit_err!(e, "failed to execute '{}'", argv[0].display());
         });
         match res {
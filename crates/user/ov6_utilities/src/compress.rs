@@ -0,0 +1,142 @@
+//! LZ4 compressed streams.
+//!
+//! Used by `tar` to read and write compressed archives.
+
+use alloc::vec::Vec;
+
+use lz4::{DecompressError, FrameDecoder, FrameEncoder};
+use ov6_user_lib::{
+    error::Ov6Error,
+    io::{Read, Write},
+};
+
+/// A writer compressing the data written to it into an LZ4 frame.
+///
+/// [`Lz4Writer::finish`] must be called to write the end of the frame.
+pub struct Lz4Writer<W> {
+    inner: W,
+    encoder: FrameEncoder,
+    buf: Vec<u8>,
+}
+
+impl<W> Lz4Writer<W>
+where
+    W: Write,
+{
+    pub fn new(mut inner: W) -> Result<Self, Ov6Error> {
+        let mut buf = Vec::new();
+        let encoder = FrameEncoder::new(&mut buf);
+        inner.write_all(&buf)?;
+        buf.clear();
+        Ok(Self {
+            inner,
+            encoder,
+            buf,
+        })
+    }
+
+    /// Writes the rest of the frame and returns the underlying writer.
+    pub fn finish(self) -> Result<W, Ov6Error> {
+        let Self {
+            mut inner,
+            encoder,
+            mut buf,
+        } = self;
+        encoder.finish(&mut buf);
+        inner.write_all(&buf)?;
+        inner.flush()?;
+        Ok(inner)
+    }
+}
+
+impl<W> Write for Lz4Writer<W>
+where
+    W: Write,
+{
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Ov6Error> {
+        self.encoder.encode(buf, &mut self.buf);
+        if !self.buf.is_empty() {
+            let res = self.inner.write_all(&self.buf);
+            self.buf.clear();
+            res?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Ov6Error> {
+        // a partially filled block cannot be written until the frame ends
+        self.inner.flush()
+    }
+}
+
+/// A reader decompressing LZ4 frames read from the underlying reader.
+///
+/// Malformed data is reported as [`Ov6Error::InvalidData`], and the cause
+/// is kept in [`Lz4Reader::error`].
+pub struct Lz4Reader<R> {
+    inner: R,
+    decoder: FrameDecoder,
+    buf: Vec<u8>,
+    pos: usize,
+    eof: bool,
+    error: Option<DecompressError>,
+}
+
+impl<R> Lz4Reader<R>
+where
+    R: Read,
+{
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            decoder: FrameDecoder::new(),
+            buf: Vec::new(),
+            pos: 0,
+            eof: false,
+            error: None,
+        }
+    }
+
+    /// Returns the cause of the last [`Ov6Error::InvalidData`] returned.
+    pub fn error(&self) -> Option<DecompressError> {
+        self.error
+    }
+
+    fn invalid_data(&mut self, e: DecompressError) -> Ov6Error {
+        self.error = Some(e);
+        Ov6Error::InvalidData
+    }
+}
+
+impl<R> Read for Lz4Reader<R>
+where
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Ov6Error> {
+        while self.pos == self.buf.len() {
+            if self.eof || buf.is_empty() {
+                return Ok(0);
+            }
+            self.buf.clear();
+            self.pos = 0;
+
+            let mut input = [0; 1024];
+            let n = self.inner.read(&mut input)?;
+            if n == 0 {
+                self.eof = true;
+                if let Err(e) = self.decoder.finish() {
+                    return Err(self.invalid_data(e));
+                }
+                return Ok(0);
+            }
+            if let Err(e) = self.decoder.decode(&input[..n], &mut self.buf) {
+                return Err(self.invalid_data(e));
+            }
+        }
+
+        let len = usize::min(buf.len(), self.buf.len() - self.pos);
+        buf[..len].copy_from_slice(&self.buf[self.pos..][..len]);
+        self.pos += len;
+        Ok(len)
+    }
+}
@@ -0,0 +1,94 @@
+//! Block access to file system images.
+//!
+//! Used by `newfs` and `fsck` to build and check a file system stored in a
+//! file, without mounting it.
+
+use dataview::{Pod, PodMethods as _};
+use ov6_fs_types::{BlockNo, FS_BLOCK_SIZE, Inode, InodeBlock, InodeNo, SuperBlock};
+use ov6_user_lib::{error::Ov6Error, fs::File};
+
+const BLOCK_SIZE: u64 = FS_BLOCK_SIZE as u64;
+
+/// A file system image read and written by blocks.
+pub struct Image {
+    file: File,
+}
+
+impl Image {
+    #[must_use]
+    pub fn new(file: File) -> Self {
+        Self { file }
+    }
+
+    /// Reads the block `bn` into `data`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `data` is not a block in size.
+    pub fn read_block<T>(&self, bn: BlockNo, data: &mut T) -> Result<(), Ov6Error>
+    where
+        T: Pod + ?Sized,
+    {
+        let data = data.as_bytes_mut();
+        assert_eq!(data.len(), FS_BLOCK_SIZE);
+        self.file
+            .read_exact_at(data, u64::from(bn.value()) * BLOCK_SIZE)
+    }
+
+    /// Writes `data` to the block `bn`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `data` is not a block in size.
+    pub fn write_block<T>(&self, bn: BlockNo, data: &T) -> Result<(), Ov6Error>
+    where
+        T: Pod + ?Sized,
+    {
+        let data = data.as_bytes();
+        assert_eq!(data.len(), FS_BLOCK_SIZE);
+        self.file
+            .write_all_at(data, u64::from(bn.value()) * BLOCK_SIZE)
+    }
+
+    pub fn read_super_block(&self) -> Result<SuperBlock, Ov6Error> {
+        let mut buf = [0; FS_BLOCK_SIZE];
+        self.read_block(SuperBlock::SUPER_BLOCK_NO, &mut buf)?;
+        let mut sb = SuperBlock::zeroed();
+        let len = sb.as_bytes().len();
+        sb.as_bytes_mut().copy_from_slice(&buf[..len]);
+        Ok(sb)
+    }
+
+    pub fn write_super_block(&self, sb: &SuperBlock) -> Result<(), Ov6Error> {
+        let mut buf = [0; FS_BLOCK_SIZE];
+        let sb = sb.as_bytes();
+        buf[..sb.len()].copy_from_slice(sb);
+        self.write_block(SuperBlock::SUPER_BLOCK_NO, &buf)
+    }
+
+    pub fn read_inode(&self, sb: &SuperBlock, ino: InodeNo) -> Result<Inode, Ov6Error> {
+        let mut block = InodeBlock::zeroed();
+        self.read_block(sb.inode_block(ino), &mut block)?;
+        let mut inode = Inode::zeroed();
+        inode
+            .as_bytes_mut()
+            .copy_from_slice(block.inode(ino).as_bytes());
+        Ok(inode)
+    }
+
+    pub fn write_inode(
+        &self,
+        sb: &SuperBlock,
+        ino: InodeNo,
+        inode: &Inode,
+    ) -> Result<(), Ov6Error> {
+        let bn = sb.inode_block(ino);
+        let mut block = InodeBlock::zeroed();
+        self.read_block(bn, &mut block)?;
+        block
+            .inode_mut(ino)
+            .as_bytes_mut()
+            .copy_from_slice(inode.as_bytes());
+        self.write_block(bn, &block)
+    }
+}
@@ -1,7 +1,13 @@
 #![no_std]
 
+extern crate alloc;
+
 use core::convert::Infallible;
 
+pub mod args;
+pub mod compress;
+pub mod fs_image;
+
 #[macro_export]
 macro_rules! message {
     ($($msg:tt)*) => {
@@ -6,6 +6,7 @@
 use std::{
     env,
     fs::File,
+    hash::{BuildHasher as _, RandomState},
     io::{self, Read as _, Seek as _, SeekFrom, Write as _},
     mem,
     path::Path,
@@ -14,35 +15,92 @@ use std::{
 
 use dataview::{Pod, PodMethods as _};
 use ov6_fs_types::{
-    BITS_PER_BLOCK, BlockNo, DIR_SIZE, DirEntry, FS_BLOCK_SIZE, INODE_PER_BLOCK, Inode, InodeNo,
-    MAX_FILE, NUM_DIRECT_REFS, NUM_INDIRECT_REFS, SuperBlock, T_DIR, T_FILE,
+    BITS_PER_BLOCK, BlockNo, BmapBlock, DIR_SIZE, DirEntry, FS_BLOCK_SIZE, FS_LABEL_MAX,
+    FsFeatures, FsUuid, INODE_PER_BLOCK, IndirectBlock, Inode, InodeNo, Le, MAX_FILE, MAX_ORPHANS,
+    NUM_DIRECT_REFS, SuperBlock, T_DIR, T_FILE, encode_label,
 };
 use ov6_kernel_params::{FS_LOG_SIZE, FS_SIZE, NUM_FS_INODES};
 use ov6_types::os_str::OsStr;
-use safe_cast::{SafeFrom as _, to_u32, to_u64};
+use safe_cast::{NarrowInto as _, SafeFrom as _, to_u32, to_u64};
 
 const _: () = const {
     assert!(FS_BLOCK_SIZE % size_of::<Inode>() == 0);
     assert!(FS_BLOCK_SIZE % size_of::<DirEntry>() == 0);
+    // the inode map fits in a block
+    assert!(NUM_FS_INODES <= BITS_PER_BLOCK);
 };
 
-fn main() -> io::Result<()> {
-    let args = env::args().collect::<Vec<String>>();
-    if args.len() < 2 {
-        eprintln!("Usage: {} fs.img files...", args[0]);
-        process::exit(1);
+fn usage(prog: &str) -> ! {
+    eprintln!(
+        "Usage: {prog} [--label <label>] [--uuid <uuid>] fs.img files... [--dir <dir> files...]..."
+    );
+    eprintln!();
+    eprintln!("Files following `--dir <dir>` are placed in the directory `/<dir>`.");
+    eprintln!("A random UUID is generated if `--uuid` is not given.");
+    process::exit(1);
+}
+
+/// Generates a random (version 4) UUID.
+fn random_uuid() -> FsUuid {
+    let state = RandomState::new();
+    let mut bytes = [0; 16];
+    for (i, chunk) in bytes.chunks_mut(8).enumerate() {
+        chunk.copy_from_slice(&state.hash_one(i).to_le_bytes());
     }
+    bytes[6] = (bytes[6] & 0x0f) | 0x40; // version 4
+    bytes[8] = (bytes[8] & 0x3f) | 0x80; // variant 1
+    FsUuid::from_bytes(bytes)
+}
 
-    let image_file = &args[1];
-    let contents = &args[2..];
+fn main() -> io::Result<()> {
+    let args = env::args().collect::<Vec<String>>();
+    let prog = &args[0];
+
+    let mut label = [0; FS_LABEL_MAX];
+    let mut uuid = None;
+    let mut contents = args[1..].iter();
+    let image_file = loop {
+        let Some(arg) = contents.next() else {
+            usage(prog);
+        };
+        match arg.as_str() {
+            "--label" => {
+                label = contents
+                    .next()
+                    .and_then(|s| encode_label(s.as_bytes()))
+                    .unwrap_or_else(|| usage(prog));
+            }
+            "--uuid" => {
+                let parsed = contents.next().and_then(|s| FsUuid::parse(s));
+                uuid = Some(parsed.unwrap_or_else(|| usage(prog)));
+            }
+            _ => break arg,
+        }
+    };
+    let uuid = uuid.unwrap_or_else(random_uuid);
 
-    let mut fs = FileSystem::new(Path::new(image_file))?;
+    let mut fs = FileSystem::new(Path::new(image_file), uuid, label)?;
     fs.clear_all_sections()?;
     fs.write_super_block()?;
-    let root_ino = fs.create_directory()?;
+    let root_ino = fs.create_directory(None)?;
     assert_eq!(root_ino, InodeNo::ROOT);
 
-    for name in contents {
+    let mut dirs = vec![root_ino];
+    let mut dir_ino = root_ino;
+    while let Some(name) = contents.next() {
+        if name == "--dir" {
+            let Some(dir_name) = contents
+                .next()
+                .filter(|s| !s.is_empty() && !s.contains('/'))
+            else {
+                usage(prog);
+            };
+            dir_ino = fs.create_directory(Some(root_ino))?;
+            fs.add_directory_entry(root_ino, dir_ino, dir_name.as_str())?;
+            dirs.push(dir_ino);
+            continue;
+        }
+
         let path = Path::new(name);
         let mut short_name = path.file_name().unwrap().to_str().unwrap();
         short_name = short_name.strip_prefix("user/").unwrap_or(short_name);
@@ -51,18 +109,20 @@ fn main() -> io::Result<()> {
         let mut buf = vec![];
         File::open(path)?.read_to_end(&mut buf)?;
         let ino = fs.create_file(&buf)?;
-        fs.add_directory_entry(root_ino, ino, short_name)?;
+        fs.add_directory_entry(dir_ino, ino, short_name)?;
     }
 
-    // fix size of root inode dir
-    let mut inode = Inode::zeroed();
-    fs.read_inode(root_ino, &mut inode)?;
-    let size = u32::from_le(inode.size);
-    let size = size.next_multiple_of(to_u32!(FS_BLOCK_SIZE));
-    inode.size = size.to_le();
-    fs.write_inode(root_ino, &inode)?;
+    // fix size of directories
+    for ino in dirs {
+        let mut inode = Inode::zeroed();
+        fs.read_inode(ino, &mut inode)?;
+        let size = inode.size.get().next_multiple_of(to_u32!(FS_BLOCK_SIZE));
+        inode.size.set(size);
+        fs.write_inode(ino, &inode)?;
+    }
 
     fs.write_bitmap()?;
+    fs.write_inode_bitmap()?;
 
     Ok(())
 }
@@ -70,9 +130,10 @@ fn main() -> io::Result<()> {
 struct FileSystem {
     img: File,
     num_bmap_blocks: u32,
+    num_ibmap_blocks: u32,
     num_inode_blocks: u32,
     num_log_blocks: u32,
-    /// Number of meta blocks (boot, sb, nlog, inode, bitmap)
+    /// Number of meta blocks (boot, sb, nlog, inode, inode map, bitmap)
     num_meta_blocks: u32,
     /// Number of data blocks
     num_blocks: u32,
@@ -84,7 +145,7 @@ struct FileSystem {
 }
 
 impl FileSystem {
-    fn new(image_file: &Path) -> io::Result<Self> {
+    fn new(image_file: &Path, uuid: FsUuid, label: [u8; FS_LABEL_MAX]) -> io::Result<Self> {
         let total_blocks = to_u32!(FS_SIZE);
         let mut fs = Self {
             img: File::options()
@@ -94,6 +155,7 @@ impl FileSystem {
                 .create(true)
                 .open(image_file)?,
             num_bmap_blocks: to_u32!(FS_SIZE / BITS_PER_BLOCK + 1),
+            num_ibmap_blocks: to_u32!(NUM_FS_INODES / BITS_PER_BLOCK + 1),
             num_inode_blocks: to_u32!(NUM_FS_INODES / INODE_PER_BLOCK + 1),
             num_log_blocks: to_u32!(FS_LOG_SIZE),
             num_meta_blocks: 0,
@@ -105,27 +167,47 @@ impl FileSystem {
             sb: SuperBlock::zeroed(),
         };
 
-        fs.num_meta_blocks = 2 + fs.num_log_blocks + fs.num_inode_blocks + fs.num_bmap_blocks;
+        fs.num_meta_blocks =
+            2 + fs.num_log_blocks + fs.num_inode_blocks + fs.num_ibmap_blocks + fs.num_bmap_blocks;
         fs.num_blocks = total_blocks - fs.num_meta_blocks;
         fs.next_free_block = BlockNo::new(fs.num_meta_blocks);
 
         fs.sb = SuperBlock {
-            magic: SuperBlock::FS_MAGIC,
-            size: fs.total_blocks,
-            nblocks: fs.num_blocks,
-            ninodes: fs.num_inodes,
-            nlog: fs.num_log_blocks,
-            logstart: 2_u32,
-            inodestart: (2 + fs.num_log_blocks),
-            bmapstart: (2 + fs.num_log_blocks + fs.num_inode_blocks),
+            magic: Le::<u32>::new(SuperBlock::FS_MAGIC),
+            size: Le::<u32>::new(fs.total_blocks),
+            nblocks: Le::<u32>::new(fs.num_blocks),
+            ninodes: Le::<u32>::new(fs.num_inodes),
+            nlog: Le::<u32>::new(fs.num_log_blocks),
+            logstart: Le::<u32>::new(2),
+            inodestart: Le::<u32>::new(2 + fs.num_log_blocks),
+            bmapstart: Le::<u32>::new(
+                2 + fs.num_log_blocks + fs.num_inode_blocks + fs.num_ibmap_blocks,
+            ),
+            state: Le::<u32>::new(SuperBlock::STATE_CLEAN),
+            mount_count: Le::<u32>::new(0),
+            last_mount_time: Le::<u64>::new(0),
+            uuid,
+            label,
+            version: Le::<u32>::new(SuperBlock::FS_VERSION),
+            features: FsFeatures {
+                ro_compat: Le::<u32>::new(
+                    FsFeatures::RO_COMPAT_INODE_BITMAP | FsFeatures::RO_COMPAT_ORPHAN_LIST,
+                ),
+                ..FsFeatures::default()
+            },
+            ibmapstart: Le::<u32>::new(2 + fs.num_log_blocks + fs.num_inode_blocks),
+            reserved: Le::<u32>::new(0),
+            orphans: [Le::<u32>::new(0); MAX_ORPHANS],
         };
 
+        eprintln!("uuid {uuid} label '{}'", fs.sb.label().display());
         eprintln!(
-            "nmeta {} (boot, super, log blocks {} inode blocsk {}, bitmap blocks {}) blocks {} \
-             total {}",
+            "nmeta {} (boot, super, log blocks {} inode blocsk {}, inode map blocks {}, bitmap \
+             blocks {}) blocks {} total {}",
             fs.num_meta_blocks,
             fs.num_log_blocks,
             fs.num_inode_blocks,
+            fs.num_ibmap_blocks,
             fs.num_bmap_blocks,
             fs.num_blocks,
             fs.total_blocks,
@@ -142,30 +224,31 @@ impl FileSystem {
     }
 
     fn write_super_block(&mut self) -> io::Result<()> {
-        let sb = SuperBlock {
-            magic: self.sb.magic.to_le(),
-            size: self.sb.size.to_le(),
-            nblocks: self.sb.nblocks.to_le(),
-            ninodes: self.sb.ninodes.to_le(),
-            nlog: self.sb.nlog.to_le(),
-            logstart: self.sb.logstart.to_le(),
-            inodestart: self.sb.inodestart.to_le(),
-            bmapstart: self.sb.bmapstart.to_le(),
-        };
-
         let mut buf = [0_u8; FS_BLOCK_SIZE];
-        let sb_bytes = sb.as_bytes();
+        let sb_bytes = self.sb.as_bytes();
         buf[..sb_bytes.len()].copy_from_slice(sb_bytes);
         self.write_section(BlockNo::new(1), &buf)?;
 
         Ok(())
     }
 
-    fn create_directory(&mut self) -> io::Result<InodeNo> {
+    /// Creates a directory whose `..` refers to `parent`.
+    ///
+    /// If `parent` is `None`, `..` refers to the directory itself (root
+    /// directory).
+    fn create_directory(&mut self, parent: Option<InodeNo>) -> io::Result<InodeNo> {
         let dir_ino = self.alloc_inode(T_DIR)?;
 
         self.add_directory_entry(dir_ino, dir_ino, ".")?;
-        self.add_directory_entry(dir_ino, dir_ino, "..")?;
+        self.add_directory_entry(dir_ino, parent.unwrap_or(dir_ino), "..")?;
+
+        if let Some(parent) = parent {
+            // account for the `..` link, as the kernel does when creating a directory
+            let mut inode = Inode::zeroed();
+            self.read_inode(parent, &mut inode)?;
+            inode.nlink.set(inode.nlink.get() + 1);
+            self.write_inode(parent, &inode)?;
+        }
 
         Ok(dir_ino)
     }
@@ -182,8 +265,19 @@ impl FileSystem {
     {
         let name = name.as_ref();
         assert!(name.len() < DIR_SIZE);
+
+        let mut inode = Inode::zeroed();
+        self.read_inode(dir_ino, &mut inode)?;
+        let mut dir = ImageDir { fs: self, inode };
+        if ov6_fs_path::lookup(&mut dir, name)?.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("duplicate directory entry '{}'", name.display()),
+            ));
+        }
+
         let mut de = DirEntry::zeroed();
-        de.set_ino(Some(InodeNo::new(ino.value().to_le())));
+        de.set_ino(Some(ino));
         de.set_name(name);
         self.append_inode(dir_ino, &de)?;
         Ok(())
@@ -198,7 +292,20 @@ impl FileSystem {
             buf[i / 8] |= 1 << (i % 8);
         }
         println!("balloc: write bitmap block at sector {}", self.sb.bmapstart);
-        self.write_section(BlockNo::new(self.sb.bmapstart), &buf)?;
+        self.write_section(BlockNo::new(self.sb.bmapstart.get()), &buf)?;
+
+        Ok(())
+    }
+
+    fn write_inode_bitmap(&mut self) -> io::Result<()> {
+        let mut bmap = BmapBlock::zeroed();
+
+        // inode 0 is never used
+        let used = self.next_free_inode.as_index();
+        for i in 0..used {
+            bmap.allocate(i);
+        }
+        self.write_section(BlockNo::new(self.sb.ibmapstart.get()), &bmap)?;
 
         Ok(())
     }
@@ -254,9 +361,9 @@ impl FileSystem {
         self.next_free_inode = InodeNo::new(self.next_free_inode.value() + 1);
 
         let inode = Inode {
-            ty: ty.to_le(),
-            nlink: 1_u16.to_le(),
-            size: 0_u32.to_le(),
+            ty: Le::<u16>::new(ty),
+            nlink: Le::<u16>::new(1),
+            size: Le::<u32>::new(0),
             ..Inode::zeroed()
         };
         self.write_inode(ino, &inode)?;
@@ -277,29 +384,32 @@ impl FileSystem {
 
         let mut inode = Inode::zeroed();
         self.read_inode(ino, &mut inode)?;
-        let mut file_off = usize::safe_from(u32::from_le(inode.size));
+        let mut file_off = usize::safe_from(inode.size.get());
         // println!("append ino {ino} at off {file_off} {} sz", data.len());
 
         while !data.is_empty() {
             let file_bidx = file_off / FS_BLOCK_SIZE;
             assert!(file_bidx < MAX_FILE);
             let bn = if file_bidx < NUM_DIRECT_REFS {
-                if inode.addrs[file_bidx] == 0 {
-                    inode.addrs[file_bidx] = self.alloc_block().value().to_le();
+                if inode.addrs[file_bidx].get() == 0 {
+                    inode.addrs[file_bidx].set(self.alloc_block().value());
                 }
-                BlockNo::new(u32::from_le(inode.addrs[file_bidx]))
+                BlockNo::new(inode.addrs[file_bidx].get())
             } else {
-                if inode.addrs[NUM_DIRECT_REFS] == 0 {
-                    inode.addrs[NUM_DIRECT_REFS] = self.alloc_block().value().to_le();
+                if inode.addrs[NUM_DIRECT_REFS].get() == 0 {
+                    inode.addrs[NUM_DIRECT_REFS].set(self.alloc_block().value());
                 }
-                let ind_bn = BlockNo::new(u32::from_le(inode.addrs[NUM_DIRECT_REFS]));
-                let mut ind_buf = [0; NUM_INDIRECT_REFS];
-                self.read_section(ind_bn, &mut ind_buf)?;
-                if ind_buf[file_bidx - NUM_DIRECT_REFS] == 0 {
-                    ind_buf[file_bidx - NUM_DIRECT_REFS] = self.alloc_block().value().to_le();
-                    self.write_section(ind_bn, &ind_buf)?;
+                let ind_bn = BlockNo::new(inode.addrs[NUM_DIRECT_REFS].get());
+                let mut ind = IndirectBlock::zeroed();
+                self.read_section(ind_bn, &mut ind)?;
+                if let Some(bn) = ind.get(file_bidx - NUM_DIRECT_REFS) {
+                    bn
+                } else {
+                    let bn = self.alloc_block();
+                    ind.set(file_bidx - NUM_DIRECT_REFS, Some(bn));
+                    self.write_section(ind_bn, &ind)?;
+                    bn
                 }
-                BlockNo::new(u32::from_le(ind_buf[file_bidx - NUM_DIRECT_REFS]))
             };
 
             let mut buf = [0_u8; FS_BLOCK_SIZE];
@@ -315,8 +425,48 @@ impl FileSystem {
             data = &data[copy_len..];
         }
 
-        inode.size = u32::try_from(file_off).unwrap().to_le();
+        inode.size.set(file_off.narrow_into());
         self.write_inode(ino, &inode)?;
         Ok(())
     }
 }
+
+/// Contents of a directory in the image being built.
+struct ImageDir<'a> {
+    fs: &'a mut FileSystem,
+    inode: Inode,
+}
+
+impl ov6_fs_path::DirContent for ImageDir<'_> {
+    type Error = io::Error;
+
+    fn size(&self) -> usize {
+        usize::safe_from(self.inode.size.get())
+    }
+
+    fn read_entry(&mut self, off: usize) -> Result<DirEntry, Self::Error> {
+        let file_bidx = off / FS_BLOCK_SIZE;
+        let mut addrs = [None; NUM_DIRECT_REFS + 1];
+        self.inode.read_addrs(&mut addrs);
+        let bn = if file_bidx < NUM_DIRECT_REFS {
+            addrs[file_bidx]
+        } else if let Some(ind_bn) = addrs[NUM_DIRECT_REFS] {
+            let mut ind = IndirectBlock::zeroed();
+            self.fs.read_section(ind_bn, &mut ind)?;
+            ind.get(file_bidx - NUM_DIRECT_REFS)
+        } else {
+            None
+        };
+
+        // holes read as free entries
+        let mut de = DirEntry::zeroed();
+        if let Some(bn) = bn {
+            let mut buf = [0_u8; FS_BLOCK_SIZE];
+            self.fs.read_section(bn, &mut buf)?;
+            let start = off % FS_BLOCK_SIZE;
+            de.as_bytes_mut()
+                .copy_from_slice(&buf[start..][..size_of::<DirEntry>()]);
+        }
+        Ok(de)
+    }
+}
@@ -0,0 +1,116 @@
+//! Read access to ov6 file system images.
+//!
+//! This module lets tests inspect files that ov6 wrote to its disk image,
+//! after the QEMU instance has exited. Transactions left in the log are not
+//! replayed, so the file system should have been shut down cleanly.
+
+use std::{fs::File, os::unix::fs::FileExt as _, path::Path};
+
+use anyhow::{Context as _, bail, ensure};
+use dataview::{Pod, PodMethods as _};
+use ov6_fs_types::{
+    BlockNo, DirEntry, FS_BLOCK_SIZE, IndirectBlock, InodeBlock, InodeNo, NUM_DIRECT_REFS,
+    NUM_INDIRECT_REFS, SuperBlock, T_DIR, T_FILE,
+};
+
+/// A file system image opened for reading.
+pub struct FsImage {
+    file: File,
+    sb: SuperBlock,
+}
+
+impl FsImage {
+    /// Opens the file system image at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the image cannot be read or has no ov6 file
+    /// system.
+    pub fn open(path: &Path) -> Result<Self, anyhow::Error> {
+        let file = File::open(path).context("open fs.img failed")?;
+        let mut image = Self {
+            file,
+            sb: SuperBlock::zeroed(),
+        };
+        let mut buf = [0; FS_BLOCK_SIZE];
+        image.read_block(SuperBlock::SUPER_BLOCK_NO, &mut buf)?;
+        let len = image.sb.as_bytes().len();
+        image.sb.as_bytes_mut().copy_from_slice(&buf[..len]);
+        ensure!(
+            image.sb.magic.get() == SuperBlock::FS_MAGIC,
+            "invalid file system magic"
+        );
+        Ok(image)
+    }
+
+    /// Returns the contents of the regular file at the absolute `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file does not exist, is not a regular file, or
+    /// cannot be read.
+    pub fn read_file(&self, path: &str) -> Result<Vec<u8>, anyhow::Error> {
+        let mut ino = InodeNo::ROOT;
+        for name in path.split('/').filter(|name| !name.is_empty()) {
+            let (ty, dir) = self.read_inode(ino)?;
+            ensure!(ty == T_DIR, "'{path}': not a directory");
+            ino = dir
+                .chunks_exact(size_of::<DirEntry>())
+                .find_map(|chunk| {
+                    let mut de = DirEntry::zeroed();
+                    de.as_bytes_mut().copy_from_slice(chunk);
+                    de.ino().filter(|_| de.name().as_bytes() == name.as_bytes())
+                })
+                .with_context(|| format!("'{path}': no such file"))?;
+        }
+        let (ty, data) = self.read_inode(ino)?;
+        ensure!(ty == T_FILE, "'{path}': not a regular file");
+        Ok(data)
+    }
+
+    fn read_block<T>(&self, bn: BlockNo, data: &mut T) -> Result<(), anyhow::Error>
+    where
+        T: Pod + ?Sized,
+    {
+        let data = data.as_bytes_mut();
+        assert_eq!(data.len(), FS_BLOCK_SIZE);
+        let offset = u64::from(bn.value()) * u64::try_from(FS_BLOCK_SIZE).unwrap();
+        self.file
+            .read_exact_at(data, offset)
+            .with_context(|| format!("read block {bn} failed"))
+    }
+
+    /// Returns the type and the contents of the inode `ino`.
+    fn read_inode(&self, ino: InodeNo) -> Result<(u16, Vec<u8>), anyhow::Error> {
+        let mut ib = InodeBlock::zeroed();
+        self.read_block(self.sb.inode_block(ino), &mut ib)?;
+        let inode = ib.inode(ino);
+        let ty = inode.ty.get();
+        let size = usize::try_from(inode.size.get()).unwrap();
+        let mut addrs = [None; NUM_DIRECT_REFS + 1];
+        inode.read_addrs(&mut addrs);
+
+        let mut blocks = addrs[..NUM_DIRECT_REFS].to_vec();
+        if let Some(bn) = addrs[NUM_DIRECT_REFS] {
+            let mut indirect = IndirectBlock::zeroed();
+            self.read_block(bn, &mut indirect)?;
+            blocks.extend((0..NUM_INDIRECT_REFS).map(|i| indirect.get(i)));
+        }
+
+        let mut data = Vec::with_capacity(size);
+        let mut buf = [0; FS_BLOCK_SIZE];
+        for bn in blocks {
+            if data.len() >= size {
+                break;
+            }
+            let Some(bn) = bn else {
+                bail!("inode {ino}: missing data block");
+            };
+            self.read_block(bn, &mut buf)?;
+            let len = usize::min(size - data.len(), FS_BLOCK_SIZE);
+            data.extend_from_slice(&buf[..len]);
+        }
+        ensure!(data.len() == size, "inode {ino}: file too large");
+        Ok((ty, data))
+    }
+}
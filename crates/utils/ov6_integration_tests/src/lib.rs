@@ -8,8 +8,13 @@ use std::path::Path;
 
 use tokio::process::Command;
 
-pub use self::{gdb::Gdb, qemu::Qemu, runner::Runner};
+pub use self::{
+    gdb::Gdb,
+    qemu::{Qemu, QemuExitStatus},
+    runner::Runner,
+};
 
+pub mod fs_image;
 mod gdb;
 pub mod helper;
 pub mod logged_command;
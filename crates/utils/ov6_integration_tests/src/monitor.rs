@@ -1,14 +1,16 @@
 //! Monitoring utilities for QEMU and GDB in integration tests.
 //!
 //! This module provides functions to monitor the boot process, interact with
-//! the QEMU instance, and run integration test commands.
+//! the QEMU instance, and run integration test commands. [`Session`] provides
+//! expect-style scripting of the console.
 
-use std::process::ExitStatus;
+use std::collections::HashMap;
 
-use anyhow::Context as _;
+use anyhow::{Context as _, anyhow};
+use regex::Regex;
 use tokio::time::{self, Duration};
 
-use crate::{Gdb, Qemu, Runner};
+use crate::{Gdb, Qemu, QemuExitStatus, Runner};
 
 /// A constant message indicating the kernel boot process.
 pub const BOOT_MSG: &str = "ov6 kernel is booting";
@@ -83,7 +85,7 @@ pub async fn run_test<F, T>(
     r: Runner,
     timeout: Duration,
     f: F,
-) -> Result<(ExitStatus, String, T), anyhow::Error>
+) -> Result<(QemuExitStatus, String, T), anyhow::Error>
 where
     F: AsyncFnOnce(&Qemu, &Gdb) -> Result<T, anyhow::Error>,
 {
@@ -96,3 +98,197 @@ where
     .await
     .context("test timeout")?
 }
+
+/// The number of bytes of the console output shown when an expectation fails.
+const TRANSCRIPT_TAIL: usize = 2048;
+
+/// An expect-style scripting session on the QEMU console.
+///
+/// A session keeps a cursor into the console output. Each successful
+/// [`expect`](Self::expect) moves the cursor past the matched text, so
+/// subsequent expectations only see later output. When an expectation times
+/// out, the returned error contains the output that was not matched, so that
+/// failing tests show what the console printed instead.
+pub struct Session<'a> {
+    qemu: &'a Qemu,
+    pos: usize,
+    timeout: Duration,
+}
+
+/// Text matched by [`Session::expect`].
+#[derive(Debug, Clone)]
+pub struct Match {
+    groups: Vec<Option<String>>,
+    names: HashMap<String, usize>,
+}
+
+impl Match {
+    fn new(re: &Regex, caps: &regex::Captures<'_>) -> Self {
+        let groups = caps
+            .iter()
+            .map(|m| m.map(|m| m.as_str().to_owned()))
+            .collect();
+        let names = re
+            .capture_names()
+            .enumerate()
+            .filter_map(|(i, name)| Some((name?.to_owned(), i)))
+            .collect();
+        Self { groups, names }
+    }
+
+    /// Returns the whole matched text.
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        self.get(0).unwrap_or_default()
+    }
+
+    /// Returns the text matched by the capture group `i`.
+    ///
+    /// Returns `None` if the group does not exist or did not participate in
+    /// the match.
+    #[must_use]
+    pub fn get(&self, i: usize) -> Option<&str> {
+        self.groups.get(i)?.as_deref()
+    }
+
+    /// Returns the text matched by the named capture group `name`.
+    #[must_use]
+    pub fn name(&self, name: &str) -> Option<&str> {
+        self.get(*self.names.get(name)?)
+    }
+}
+
+impl<'a> Session<'a> {
+    /// The default timeout of each expectation.
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Creates a session that expects output from `output_start`.
+    #[must_use]
+    pub fn new(qemu: &'a Qemu, output_start: usize) -> Self {
+        Self {
+            qemu,
+            pos: output_start,
+            timeout: Self::DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Sets the timeout used by [`expect`](Self::expect) and the methods
+    /// built on it.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Returns the position of the cursor in the console output.
+    #[must_use]
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Moves the cursor to the end of the output received so far.
+    pub fn skip_output(&mut self) {
+        self.pos = self.qemu.stdout_pos();
+    }
+
+    /// Waits until the output after the cursor matches the regular expression
+    /// `pattern`, and moves the cursor past the match.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `pattern` is invalid, or if the output does not
+    /// match within the timeout. The error includes the unmatched output.
+    pub async fn expect(&mut self, pattern: &str) -> Result<Match, anyhow::Error> {
+        let re = Regex::new(pattern).with_context(|| format!("invalid pattern {pattern:?}"))?;
+        let mut found = None;
+        let res = time::timeout(
+            self.timeout,
+            self.qemu.wait_output(self.pos, |s| {
+                let Some(caps) = re.captures(s) else {
+                    return false;
+                };
+                #[expect(clippy::missing_panics_doc, reason = "infallible")]
+                let end = caps.get(0).unwrap().end();
+                found = Some((end, Match::new(&re, &caps)));
+                true
+            }),
+        )
+        .await;
+        let Ok(res) = res else {
+            return Err(self.failure(&format!("expected {pattern:?}")));
+        };
+        res?;
+        let (end, m) = found.context("output matched but no match recorded")?;
+        self.pos += end;
+        Ok(m)
+    }
+
+    /// Waits until the output after the cursor contains `s`, and moves the
+    /// cursor past it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `s` is not output within the timeout.
+    pub async fn expect_str(&mut self, s: &str) -> Result<(), anyhow::Error> {
+        self.expect(&regex::escape(s)).await?;
+        Ok(())
+    }
+
+    /// Waits for a shell prompt.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no prompt is output within the timeout.
+    pub async fn expect_prompt(&mut self) -> Result<(), anyhow::Error> {
+        self.expect_str("$ ").await
+    }
+
+    /// Sends raw bytes to the console.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the console input is closed.
+    pub async fn send(&mut self, bytes: &[u8]) -> Result<(), anyhow::Error> {
+        self.qemu
+            .stdin_tx()
+            .ok_or_else(|| anyhow!("QEMU stdin channel is closed"))?
+            .send(bytes.to_vec())
+            .await?;
+        Ok(())
+    }
+
+    /// Sends `line` followed by a newline to the console.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the console input is closed.
+    pub async fn send_line(&mut self, line: &str) -> Result<(), anyhow::Error> {
+        self.send(format!("{line}\n").as_bytes()).await
+    }
+
+    /// Waits for a shell prompt and runs `command`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no prompt is output within the timeout or if the
+    /// console input is closed.
+    pub async fn run_command(&mut self, command: &str) -> Result<(), anyhow::Error> {
+        self.expect_prompt().await?;
+        self.send_line(command).await
+    }
+
+    /// Builds an error describing a failed expectation, with the output after
+    /// the cursor.
+    fn failure(&self, what: &str) -> anyhow::Error {
+        let stdout = self.qemu.stdout().lock().unwrap();
+        let pending = &stdout[self.pos..];
+        let mut tail_start = pending.len().saturating_sub(TRANSCRIPT_TAIL);
+        while !pending.is_char_boundary(tail_start) {
+            tail_start += 1;
+        }
+        let omitted = if tail_start > 0 { "[...]\n" } else { "" };
+        anyhow!(
+            "{what} within {:?}, but got:\n{omitted}{}",
+            self.timeout,
+            &pending[tail_start..]
+        )
+    }
+}
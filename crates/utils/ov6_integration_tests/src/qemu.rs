@@ -5,8 +5,9 @@
 //! with QEMU's standard input, output, and lifecycle.
 
 use std::{
+    fs,
     path::{Path, PathBuf},
-    process::ExitStatus,
+    process::{self, ExitStatus},
     sync::{Arc, Mutex},
 };
 
@@ -24,7 +25,67 @@ pub struct Qemu {
     /// The underlying logged command managing the QEMU process.
     command: LoggedCommand,
     /// The path to the QEMU monitor socket.
-    monitor_sock: PathBuf,
+    monitor_sock: TempPath,
+    /// The path to the GDB socket.
+    _gdb_sock: TempPath,
+    /// The path to the file QEMU's exit code is written to.
+    exit_code_path: PathBuf,
+}
+
+/// A path to a file that is removed when dropped.
+///
+/// Used for the sockets, which live outside the test workspace and would
+/// otherwise be left behind when a test fails or times out.
+struct TempPath(PathBuf);
+
+impl TempPath {
+    /// Removes a stale file at `path` and returns a guard for it.
+    fn new(path: PathBuf) -> Self {
+        let _ = fs::remove_file(&path);
+        Self(path)
+    }
+}
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// The exit status of a QEMU instance.
+///
+/// The kernel terminates the VM through the test finisher device with an exit
+/// code (`halt`, `abort`, or a kernel panic), and QEMU exits with that code.
+/// Since QEMU runs under `make`, which reports any failure as its own exit
+/// status 2, the code is passed back through a file instead.
+#[derive(Debug, Clone, Copy)]
+pub struct QemuExitStatus {
+    /// The exit status of `make`.
+    make_status: ExitStatus,
+    /// The exit code of QEMU, if it exited by itself.
+    code: Option<i32>,
+}
+
+impl QemuExitStatus {
+    /// Returns `true` if the VM was terminated with the exit code 0.
+    #[must_use]
+    pub fn success(&self) -> bool {
+        self.code == Some(0)
+    }
+
+    /// Returns the exit code the VM was terminated with.
+    ///
+    /// Returns `None` if QEMU was killed or did not start.
+    #[must_use]
+    pub fn code(&self) -> Option<i32> {
+        self.code
+    }
+
+    /// Returns the exit status of the `make` process that ran QEMU.
+    #[must_use]
+    pub fn make_status(&self) -> ExitStatus {
+        self.make_status
+    }
 }
 
 impl Qemu {
@@ -38,6 +99,10 @@ impl Qemu {
     ///
     /// Returns an error if the QEMU process fails to start or if any required
     /// resources cannot be initialized.
+    ///
+    /// If `virtual_time` is `true`, the kernel is booted with the virtual
+    /// clock and QEMU drives the clock by the instruction count.
+    #[expect(clippy::too_many_arguments)]
     pub fn new(
         runner_id: usize,
         project_root: &Path,
@@ -46,18 +111,37 @@ impl Qemu {
         qemu_fs: &Path,
         gdb_sock: &Path,
         monitor_sock: PathBuf,
+        virtual_time: bool,
     ) -> Result<Self, anyhow::Error> {
+        let exit_code_path = workspace_dir.join(format!(
+            "ov6.{}.{}.qemu.exit_code",
+            process::id(),
+            runner_id
+        ));
+        let _ = fs::remove_file(&exit_code_path);
+        // each instance dumps its network traffic into its own workspace.
+        let pcap_path =
+            workspace_dir.join(format!("ov6.{}.{}.packets.pcap", process::id(), runner_id));
+        let monitor_sock = TempPath::new(monitor_sock);
+        let gdb_sock = TempPath::new(gdb_sock.to_owned());
+
         let mut command = crate::make_command(project_root);
         command.args([
             "qemu-gdb-noinit",
             &format!("QEMU_KERNEL={}", qemu_kernel.display()),
             &format!("QEMU_FS={}", qemu_fs.display()),
-            &format!("GDB_SOCK={}", gdb_sock.display()),
+            &format!("GDB_SOCK={}", gdb_sock.0.display()),
             "QEMU_MONITOR_FWD=1",
-            &format!("QEMU_MONITOR_SOCK={}", monitor_sock.display()),
+            &format!("QEMU_MONITOR_SOCK={}", monitor_sock.0.display()),
+            &format!("QEMU_EXIT_CODE={}", exit_code_path.display()),
+            &format!("QEMU_PCAP={}", pcap_path.display()),
+            // let the host choose free ports so that instances do not collide.
             "FWD_PORT1=0",
             "FWD_PORT2=0",
         ]);
+        if virtual_time {
+            command.args(["QEMU_APPEND=virtual_time", "QEMU_ICOUNT=shift=0,sleep=off"]);
+        }
 
         let command = LoggedCommand::new(command, runner_id, "qemu", workspace_dir)
             .context("spawn qemu failed")?;
@@ -65,6 +149,8 @@ impl Qemu {
         Ok(Self {
             command,
             monitor_sock,
+            _gdb_sock: gdb_sock,
+            exit_code_path,
         })
     }
 
@@ -73,7 +159,7 @@ impl Qemu {
     /// This socket is used for communicating with the QEMU monitor.
     #[must_use]
     pub fn monitor_sock(&self) -> &Path {
-        &self.monitor_sock
+        &self.monitor_sock.0
     }
 
     /// Returns a sender for writing to the QEMU process's standard input.
@@ -139,7 +225,12 @@ impl Qemu {
     ///
     /// Returns an error if the process fails to terminate cleanly or if any
     /// subprocess tasks fail.
-    pub async fn wait_terminate(self) -> Result<(ExitStatus, String), anyhow::Error> {
-        self.command.wait_terminate().await
+    pub async fn wait_terminate(self) -> Result<(QemuExitStatus, String), anyhow::Error> {
+        let (make_status, stdout) = self.command.wait_terminate().await?;
+        let code = fs::read_to_string(&self.exit_code_path)
+            .ok()
+            .and_then(|s| s.trim().parse().ok());
+        let status = QemuExitStatus { make_status, code };
+        Ok((status, stdout))
     }
 }
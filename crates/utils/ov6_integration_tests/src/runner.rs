@@ -7,6 +7,7 @@
 use std::{
     env,
     fs::{self, File},
+    io,
     path::{Path, PathBuf},
     process,
     sync::atomic::{AtomicUsize, Ordering},
@@ -28,6 +29,11 @@ static RUNNER_ID: AtomicUsize = AtomicUsize::new(0);
 ///
 /// The `Runner` struct manages the workspace, QEMU instance, and GDB
 /// connection required for running integration tests.
+///
+/// Each test gets its own workspace directory with private copies of the
+/// kernel and the disk image, its own log, packet dump and socket files, and
+/// host ports chosen by the OS, so tests can run their guests in parallel
+/// (e.g. `cargo test -- --test-threads=N`).
 pub struct Runner {
     /// The unique ID of the runner.
     id: usize,
@@ -39,6 +45,8 @@ pub struct Runner {
     kernel_path: PathBuf,
     /// The path to the filesystem image.
     fs_path: PathBuf,
+    /// Whether the kernel runs with the deterministic virtual clock.
+    virtual_time: bool,
 }
 
 impl Runner {
@@ -85,6 +93,7 @@ impl Runner {
             workspace_dir,
             kernel_path,
             fs_path,
+            virtual_time: false,
         })
     }
 
@@ -106,6 +115,58 @@ impl Runner {
         &self.workspace_dir
     }
 
+    /// Returns the path to the file system image used by the test.
+    #[must_use]
+    pub fn fs_path(&self) -> &Path {
+        &self.fs_path
+    }
+
+    /// Enables or disables the deterministic virtual time mode.
+    ///
+    /// When enabled, the kernel is booted with the `virtual_time` option and
+    /// QEMU runs with `-icount`, so the clock advances with the executed
+    /// instructions instead of the host's wall clock. Timing-sensitive tests
+    /// then observe the same timings on every run, regardless of the host load.
+    pub fn set_virtual_time(&mut self, enabled: bool) {
+        self.virtual_time = enabled;
+    }
+
+    /// Grows the disk image to `len` bytes.
+    ///
+    /// The file system on it keeps its size until it is resized in ov6.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the disk image cannot be resized.
+    pub fn grow_disk(&self, len: u64) -> Result<(), anyhow::Error> {
+        let file = File::options()
+            .write(true)
+            .open(&self.fs_path)
+            .context("open fs.img failed")?;
+        ensure!(
+            len >= file.metadata().context("stat fs.img failed")?.len(),
+            "cannot shrink fs.img"
+        );
+        file.set_len(len).context("resize fs.img failed")?;
+        Ok(())
+    }
+
+    /// Returns a runner booting the same kernel and disk image again.
+    ///
+    /// Launched after the guest of `self` has exited, it boots the file
+    /// system left by that guest, e.g. to test the recovery from a crash.
+    #[must_use]
+    pub fn next_boot(&self) -> Self {
+        Self {
+            id: RUNNER_ID.fetch_add(1, Ordering::Relaxed),
+            project_root: self.project_root,
+            workspace_dir: self.workspace_dir.clone(),
+            kernel_path: self.kernel_path.clone(),
+            fs_path: self.fs_path.clone(),
+            virtual_time: self.virtual_time,
+        }
+    }
+
     /// Launches the QEMU and GDB instances for the test.
     ///
     /// This function initializes the QEMU and GDB instances, waits for the
@@ -132,6 +193,7 @@ impl Runner {
             &self.fs_path,
             &gdb_sock,
             qemu_monitor_sock,
+            self.virtual_time,
         )?;
 
         let mut gdb = Gdb::connect(gdb_sock).await?;
@@ -156,6 +218,13 @@ fn setup_workspace(
     project_root: &Path,
     workspace_dir: &Path,
 ) -> Result<(PathBuf, PathBuf), anyhow::Error> {
+    // start from an empty workspace, so that the images and logs left by a
+    // previous (possibly failed) run do not mix with this one.
+    match fs::remove_dir_all(workspace_dir) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e).context("clean workspace failed"),
+    }
     fs::create_dir_all(workspace_dir).context("create workspace failed")?;
 
     let lockfile_path = project_root
@@ -184,8 +253,6 @@ fn setup_workspace(
     let kernel_dst = workspace_dir.join("kernel");
     let fs_dst = workspace_dir.join("fs.img");
 
-    let _ = fs::remove_file(&kernel_dst);
-    let _ = fs::remove_file(&fs_dst);
     fs::copy(&kernel_src, &kernel_dst).context("copy kernel failed")?;
     fs::copy(&fs_src, &fs_dst).context("copy fs.img failed")?;
 
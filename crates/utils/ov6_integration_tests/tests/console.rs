@@ -0,0 +1,61 @@
+#![cfg(test)]
+
+use std::time::Duration;
+
+use ov6_integration_tests::{
+    monitor::{self, Session},
+    runner,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Time to wait for the shell to start the command before typing Ctrl-C.
+const COMMAND_START_DELAY: Duration = Duration::from_millis(500);
+
+const CTRL_C: &[u8] = b"\x03";
+
+#[cfg_attr(miri, ignore)]
+#[tokio::test]
+async fn ctrl_c_terminates_command() -> Result<(), anyhow::Error> {
+    let r = runner!("ctrl_c_terminates_command").await?;
+    let (exit_status, stdout, ()) = monitor::run_test(r, TIMEOUT, async |qemu, _gdb| {
+        let mut session = Session::new(qemu, 0);
+        session.run_command("sleep 1000").await?;
+        tokio::time::sleep(COMMAND_START_DELAY).await;
+        session.send(CTRL_C).await?;
+        let m = session
+            .expect(r"command exited with status (?<status>-?\d+)")
+            .await?;
+        assert_eq!(m.name("status"), Some("-1"));
+        session.run_command("echo shell alive").await?;
+        session.run_command("halt").await?;
+        Ok(())
+    })
+    .await?;
+    assert!(exit_status.success());
+    assert!(stdout.contains("^C"));
+    assert!(stdout.contains("command exited with status -1"));
+    assert!(stdout.lines().any(|s| s == "shell alive"));
+    Ok(())
+}
+
+#[cfg_attr(miri, ignore)]
+#[tokio::test]
+async fn ctrl_c_cancels_line() -> Result<(), anyhow::Error> {
+    let r = runner!("ctrl_c_cancels_line").await?;
+    let (exit_status, stdout, ()) = monitor::run_test(r, TIMEOUT, async |qemu, _gdb| {
+        let mut session = Session::new(qemu, 0);
+        session.expect_prompt().await?;
+        session.send(b"echo cancelled").await?;
+        session.send(CTRL_C).await?;
+        session.expect_str("^C").await?;
+        session.run_command("echo accepted").await?;
+        session.run_command("halt").await?;
+        Ok(())
+    })
+    .await?;
+    assert!(exit_status.success());
+    assert!(!stdout.lines().any(|s| s == "cancelled"));
+    assert!(stdout.lines().any(|s| s == "accepted"));
+    Ok(())
+}
@@ -20,3 +20,22 @@ async fn cowtest() -> Result<(), anyhow::Error> {
     assert!(!stdout.contains("FAILED"));
     Ok(())
 }
+
+#[cfg_attr(miri, ignore)]
+#[tokio::test]
+async fn cowtest_bench() -> Result<(), anyhow::Error> {
+    let r = runner!("cowtest_bench").await?;
+    let (exit_status, stdout, ()) = monitor::run_test(r, TIMEOUT, async |qemu, _gdb| {
+        monitor::run_commands(qemu, 0, ["cowtest -T bench"]).await?;
+        Ok(())
+    })
+    .await?;
+    assert!(exit_status.success());
+    for line in stdout.lines().filter(|s| s.contains("ns/page")) {
+        println!("{line}");
+    }
+    assert!(stdout.contains("zero: "));
+    assert!(stdout.contains("copy on write: "));
+    assert!(stdout.contains("PASSED"));
+    Ok(())
+}
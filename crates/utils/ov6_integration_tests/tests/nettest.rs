@@ -123,6 +123,14 @@ async fn rxburst() -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+#[cfg_attr(miri, ignore)]
+#[tokio::test]
+async fn rxflood() -> Result<(), anyhow::Error> {
+    let r = runner!("rxflood").await?;
+    test_with_command(r, "rxflood", false, true).await?;
+    Ok(())
+}
+
 #[cfg_attr(miri, ignore)]
 #[tokio::test]
 async fn tx() -> Result<(), anyhow::Error> {
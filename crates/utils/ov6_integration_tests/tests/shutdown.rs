@@ -20,6 +20,21 @@ async fn halt() -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+#[cfg_attr(miri, ignore)]
+#[tokio::test]
+async fn halt_with_code() -> Result<(), anyhow::Error> {
+    let r = runner!("halt_with_code").await?;
+    let (exit_status, stdout, ()) = monitor::run_test(r, TIMEOUT, async |qemu, _gdb| {
+        monitor::run_commands(qemu, 0, ["halt 3"]).await?;
+        Ok(())
+    })
+    .await?;
+    assert_eq!(exit_status.code(), Some(3));
+    assert!(!exit_status.make_status().success());
+    assert!(stdout.contains("halt requested"));
+    Ok(())
+}
+
 #[cfg_attr(miri, ignore)]
 #[tokio::test]
 async fn abort() -> Result<(), anyhow::Error> {
@@ -29,7 +44,7 @@ async fn abort() -> Result<(), anyhow::Error> {
         Ok(())
     })
     .await?;
-    assert_eq!(exit_status.code(), Some(2)); // make exit status
+    assert_eq!(exit_status.code(), Some(255)); // default code of abort
     assert!(stdout.contains("abort requested"));
     Ok(())
 }
@@ -49,3 +64,55 @@ async fn reboot() -> Result<(), anyhow::Error> {
     assert!(stdout.contains("reboot requested"));
     Ok(())
 }
+
+#[cfg_attr(miri, ignore)]
+#[tokio::test]
+async fn init_respawn_sh() -> Result<(), anyhow::Error> {
+    let r = runner!("init_respawn_sh").await?;
+    let (exit_status, stdout, ()) = monitor::run_test(r, TIMEOUT, async |qemu, _gdb| {
+        monitor::run_commands(qemu, 0, ["exit", "halt"]).await?;
+        Ok(())
+    })
+    .await?;
+    assert!(exit_status.success());
+    assert!(stdout.contains("rc: startup complete"));
+    assert_eq!(stdout.matches("init: starting sh").count(), 2);
+    assert!(stdout.contains("init: syncing file system"));
+    assert!(stdout.contains("halt requested"));
+    Ok(())
+}
+
+#[cfg_attr(miri, ignore)]
+#[tokio::test]
+async fn shutdown_halt() -> Result<(), anyhow::Error> {
+    let r = runner!("shutdown_halt").await?;
+    let (exit_status, stdout, ()) = monitor::run_test(r, TIMEOUT, async |qemu, _gdb| {
+        monitor::run_commands(qemu, 0, ["shutdown -h"]).await?;
+        Ok(())
+    })
+    .await?;
+    assert!(exit_status.success());
+    assert!(stdout.contains("halt requested"));
+    assert!(stdout.contains("ov6 - syncing file system"));
+    assert!(stdout.contains("ov6 - resetting devices"));
+    Ok(())
+}
+
+#[cfg_attr(miri, ignore)]
+#[tokio::test]
+async fn shutdown_reboot() -> Result<(), anyhow::Error> {
+    let r = runner!("shutdown_reboot").await?;
+    let (exit_status, stdout, ()) = monitor::run_test(r, TIMEOUT, async |qemu, _gdb| {
+        let before_reboot = monitor::run_commands(qemu, 0, ["shutdown -r"]).await?;
+        monitor::wait_boot(qemu, before_reboot).await?;
+        monitor::run_commands(qemu, before_reboot, ["halt"]).await?;
+        Ok(())
+    })
+    .await?;
+    assert!(exit_status.success());
+    assert!(stdout.contains("reboot requested"));
+    assert_eq!(stdout.matches("ov6 - syncing file system").count(), 2);
+    // the file system is marked clean before rebooting
+    assert!(!stdout.contains("not cleanly unmounted"));
+    Ok(())
+}
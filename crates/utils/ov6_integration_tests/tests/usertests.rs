@@ -96,6 +96,24 @@ async fn misc() -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+/// A read below the user stack is reported as a likely stack overflow.
+#[cfg_attr(miri, ignore)]
+#[tokio::test]
+async fn stack_fault_report() -> Result<(), anyhow::Error> {
+    let r = runner!("stack_fault_report").await?;
+    let (exit_status, stdout, ()) = monitor::run_test(r, TIMEOUT, async |qemu, _gdb| {
+        monitor::run_commands(qemu, 0, ["usertests -T misc::stack"]).await?;
+        Ok(())
+    })
+    .await?;
+    assert!(exit_status.success());
+    assert!(stdout.contains("PASSED"));
+    assert!(stdout.contains(": page not mapped"));
+    assert!(stdout.contains("nearest region: stack"));
+    assert!(stdout.contains("likely a stack overflow"));
+    Ok(())
+}
+
 mod slow_fs {
     use super::*;
 
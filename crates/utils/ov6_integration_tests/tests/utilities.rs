@@ -1,8 +1,8 @@
 #![cfg(test)]
 
-use std::time::Duration;
+use std::{fs, process::Command, time::Duration};
 
-use ov6_integration_tests::{helper, monitor, runner};
+use ov6_integration_tests::{fs_image::FsImage, helper, monitor, runner};
 
 const TIMEOUT: Duration = Duration::from_secs(30);
 
@@ -252,3 +252,912 @@ async fn sh_or() -> Result<(), anyhow::Error> {
     assert!(!lines.contains(&"c"));
     Ok(())
 }
+
+#[cfg_attr(miri, ignore)]
+#[tokio::test]
+async fn ls_long_sort_columns() -> Result<(), anyhow::Error> {
+    let r = runner!("ls_long_sort_columns").await?;
+    let dir = helper::random_str(8);
+    let (exit_status, stdout, ()) = monitor::run_test(r, TIMEOUT, async |qemu, _gdb| {
+        monitor::run_commands(
+            qemu,
+            0,
+            [
+                &format!("mkdir {dir}"),
+                &format!("echo a > {dir}/small"),
+                &format!("echo aaaaaaaaaaaaaaaa > {dir}/large"),
+                &format!("echo > {dir}/.hidden"),
+                &format!("ls -lS {dir}"),
+                &format!("ls {dir}"),
+                &format!("ls -1a {dir}"),
+                "halt",
+            ],
+        )
+        .await?;
+        Ok(())
+    })
+    .await?;
+    assert!(exit_status.success());
+    let lines = stdout.lines().collect::<Vec<_>>();
+    let large = lines.iter().position(|s| *s == "- 1 17 large").unwrap();
+    let small = lines.iter().position(|s| *s == "- 1  2 small").unwrap();
+    assert!(large < small);
+    assert!(lines.contains(&"large  small"));
+    assert!(lines.contains(&".hidden"));
+    assert!(lines.contains(&".."));
+    Ok(())
+}
+
+#[cfg_attr(miri, ignore)]
+#[tokio::test]
+async fn sh_tab_completion() -> Result<(), anyhow::Error> {
+    let r = runner!("sh_tab_completion").await?;
+    let file = helper::random_str(8);
+    let content = helper::random_str(8);
+    let (exit_status, stdout, ()) = monitor::run_test(r, TIMEOUT, async |qemu, _gdb| {
+        monitor::run_commands(
+            qemu,
+            0,
+            [
+                &format!("echo {content} > {file}"),
+                &format!("ca\t{}\t", &file[..6]),
+                "halt",
+            ],
+        )
+        .await?;
+        Ok(())
+    })
+    .await?;
+    assert!(exit_status.success());
+    assert!(stdout.lines().any(|line| line == content));
+    Ok(())
+}
+
+#[cfg_attr(miri, ignore)]
+#[tokio::test]
+async fn mkdir_parents() -> Result<(), anyhow::Error> {
+    let r = runner!("mkdir_parents").await?;
+    let dirs = [
+        helper::random_str(8),
+        helper::random_str(8),
+        helper::random_str(8),
+    ];
+    let needle = helper::random_str(8);
+    let (exit_status, stdout, ()) = monitor::run_test(r, TIMEOUT, async |qemu, _gdb| {
+        monitor::run_commands(
+            qemu,
+            0,
+            [
+                &format!("mkdir -p {}/{}/{}", dirs[0], dirs[1], dirs[2]),
+                &format!("mkdir --parents {}/{}", dirs[0], dirs[1]),
+                &format!("echo > {}/{}/{}/{}", dirs[0], dirs[1], dirs[2], needle),
+                &format!("find {} {needle}", dirs[0]),
+                "halt",
+            ],
+        )
+        .await?;
+        Ok(())
+    })
+    .await?;
+    assert!(exit_status.success());
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert!(lines.contains(&format!("{}/{}/{}/{}", dirs[0], dirs[1], dirs[2], needle).as_str()));
+    assert!(!lines.iter().any(|s| s.contains("cannot create directory")));
+    Ok(())
+}
+
+#[cfg_attr(miri, ignore)]
+#[tokio::test]
+async fn grep_invert_match() -> Result<(), anyhow::Error> {
+    let r = runner!("grep_invert_match").await?;
+    let (exit_status, stdout, ()) = monitor::run_test(r, TIMEOUT, async |qemu, _gdb| {
+        monitor::run_commands(
+            qemu,
+            0,
+            [
+                "(echo apple; echo banana; echo cherry) | grep -v an",
+                "halt",
+            ],
+        )
+        .await?;
+        Ok(())
+    })
+    .await?;
+    assert!(exit_status.success());
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert!(lines.contains(&"apple"));
+    assert!(lines.contains(&"cherry"));
+    assert!(!lines.contains(&"banana"));
+    Ok(())
+}
+
+#[cfg_attr(miri, ignore)]
+#[tokio::test]
+async fn rm_help() -> Result<(), anyhow::Error> {
+    let r = runner!("rm_help").await?;
+    let (exit_status, stdout, ()) = monitor::run_test(r, TIMEOUT, async |qemu, _gdb| {
+        monitor::run_commands(qemu, 0, ["rm --help", "halt"]).await?;
+        Ok(())
+    })
+    .await?;
+    assert!(exit_status.success());
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert!(lines.iter().any(|s| s.starts_with("Usage: rm")));
+    assert!(lines.iter().any(|s| s.contains("-f, --force")));
+    Ok(())
+}
+
+#[cfg_attr(miri, ignore)]
+#[tokio::test]
+async fn sh_command_search_path() -> Result<(), anyhow::Error> {
+    let r = runner!("sh_command_search_path").await?;
+    let dir = helper::random_str(8);
+    let msg = helper::random_str(8);
+    let (exit_status, stdout, ()) = monitor::run_test(r, TIMEOUT, async |qemu, _gdb| {
+        monitor::run_commands(
+            qemu,
+            0,
+            [
+                &format!("mkdir {dir}"),
+                &format!("cd {dir}"),
+                &format!("echo {msg}"),
+                "ls -1 /bin",
+                "halt",
+            ],
+        )
+        .await?;
+        Ok(())
+    })
+    .await?;
+    assert!(exit_status.success());
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert!(lines.contains(&msg.as_str()));
+    assert!(lines.contains(&"echo"));
+    assert!(lines.contains(&"usertests"));
+    Ok(())
+}
+
+#[cfg_attr(miri, ignore)]
+#[tokio::test]
+async fn sh_script() -> Result<(), anyhow::Error> {
+    let r = runner!("sh_script").await?;
+    let script = helper::random_str(8);
+    let msg1 = helper::random_str(8);
+    let msg2 = helper::random_str(8);
+    let msg3 = helper::random_str(8);
+    let (exit_status, stdout, ()) = monitor::run_test(r, TIMEOUT, async |qemu, _gdb| {
+        monitor::run_commands(
+            qemu,
+            0,
+            [
+                &format!("echo '#!/bin/sh' > {script}"),
+                &format!("echo '# comment' >> {script}"),
+                &format!("echo echo {msg1} >> {script}"),
+                &format!("./{script}"),
+                &format!("sh {script} && echo {msg2}"),
+                &format!("sh -c 'echo {msg3}; false' || echo failed"),
+                "halt",
+            ],
+        )
+        .await?;
+        Ok(())
+    })
+    .await?;
+    assert!(exit_status.success());
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert_eq!(lines.iter().filter(|s| **s == msg1).count(), 2);
+    assert!(lines.contains(&msg2.as_str()));
+    assert!(lines.contains(&msg3.as_str()));
+    assert!(lines.contains(&"failed"));
+    Ok(())
+}
+
+#[cfg_attr(miri, ignore)]
+#[tokio::test]
+async fn sh_control_flow() -> Result<(), anyhow::Error> {
+    let r = runner!("sh_control_flow").await?;
+    let (exit_status, stdout, ()) = monitor::run_test(r, TIMEOUT, async |qemu, _gdb| {
+        monitor::run_commands(
+            qemu,
+            0,
+            [
+                r#"n=; while [ "$n" != xxx ]; do n=x$n; echo count-$n; done"#,
+                "if [ 1 -eq 2 ]; then echo bad; elif [ 2 -ge 2 ]; then echo elif-ok; fi",
+                "if false; then echo bad; else echo else-ok; fi",
+                r#"list='p q'; for x in $list "$list"; do echo "field-$x"; done"#,
+                "if test -d /bin && test ! -f /bin; then echo is-dir; fi",
+                "false; echo status-$?",
+                "halt",
+            ],
+        )
+        .await?;
+        Ok(())
+    })
+    .await?;
+    assert!(exit_status.success());
+    let lines = stdout.lines().collect::<Vec<_>>();
+    for expected in [
+        "count-x",
+        "count-xx",
+        "count-xxx",
+        "elif-ok",
+        "else-ok",
+        "field-p",
+        "field-q",
+        "field-p q",
+        "is-dir",
+        "status-1",
+    ] {
+        assert!(lines.contains(&expected), "missing {expected}");
+    }
+    assert!(!lines.contains(&"bad"));
+    Ok(())
+}
+
+#[cfg_attr(miri, ignore)]
+#[tokio::test]
+async fn df() -> Result<(), anyhow::Error> {
+    let r = runner!("df").await?;
+    let (exit_status, stdout, ()) = monitor::run_test(r, TIMEOUT, async |qemu, _gdb| {
+        monitor::run_commands(qemu, 0, ["df", "halt"]).await?;
+        Ok(())
+    })
+    .await?;
+    assert!(exit_status.success());
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert!(lines.iter().any(|s| s.starts_with("Label")));
+    assert!(lines.iter().any(|s| s.starts_with("ov6-root")));
+    Ok(())
+}
+
+#[cfg_attr(miri, ignore)]
+#[tokio::test]
+async fn stat() -> Result<(), anyhow::Error> {
+    let r = runner!("stat").await?;
+    let (exit_status, stdout, ()) = monitor::run_test(r, TIMEOUT, async |qemu, _gdb| {
+        monitor::run_commands(
+            qemu,
+            0,
+            [
+                "echo hello > a",
+                "stat a console /",
+                "stat nonexistent || echo stat failed",
+                "halt",
+            ],
+        )
+        .await?;
+        Ok(())
+    })
+    .await?;
+    assert!(exit_status.success());
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert!(lines.contains(&"  File: a"));
+    assert!(lines.contains(&"  Size: 6          Blocks: 1          regular file"));
+    assert!(lines.contains(&"  File: console"));
+    assert!(lines.contains(&"Device type: 1,0"));
+    assert!(lines.iter().any(|s| s.ends_with(" directory")));
+    assert!(lines.contains(&"stat failed"));
+    Ok(())
+}
+
+#[cfg_attr(miri, ignore)]
+#[tokio::test]
+async fn du() -> Result<(), anyhow::Error> {
+    let r = runner!("du").await?;
+    let (exit_status, stdout, ()) = monitor::run_test(r, TIMEOUT, async |qemu, _gdb| {
+        monitor::run_commands(
+            qemu,
+            0,
+            [
+                "mkdir dutest",
+                "mkdir dutest/sub",
+                "echo hello > dutest/sub/a",
+                "ln dutest/sub/a dutest/b",
+                "du dutest",
+                "du -s -h dutest",
+                "halt",
+            ],
+        )
+        .await?;
+        Ok(())
+    })
+    .await?;
+    assert!(exit_status.success());
+    let lines = stdout.lines().collect::<Vec<_>>();
+    // each directory and the file use one block; the hard link is counted once
+    assert!(lines.contains(&"2\tdutest/sub"));
+    assert!(lines.contains(&"3\tdutest"));
+    assert!(lines.contains(&"3.0K\tdutest"));
+    Ok(())
+}
+
+#[cfg_attr(miri, ignore)]
+#[tokio::test]
+async fn rmdir() -> Result<(), anyhow::Error> {
+    let r = runner!("rmdir").await?;
+    let (exit_status, stdout, ()) = monitor::run_test(r, TIMEOUT, async |qemu, _gdb| {
+        monitor::run_commands(
+            qemu,
+            0,
+            [
+                "mkdir -p rmd/a/b",
+                "echo x > rmd/f",
+                "rm rmd/a/b || echo rm failed",
+                "rmdir rmd || echo rmdir failed",
+                "rm -d rmd/a/b && echo rm -d ok",
+                "rmdir -p rmd/a || echo rmdir -p failed",
+                "rm rmd/f",
+                "rmdir rmd && echo rmdir ok",
+                "halt",
+            ],
+        )
+        .await?;
+        Ok(())
+    })
+    .await?;
+    assert!(exit_status.success());
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert!(lines.contains(&"rm failed"));
+    assert!(lines.contains(&"rmdir failed"));
+    assert!(lines.contains(&"rm -d ok"));
+    assert!(lines.contains(&"rmdir -p failed"));
+    assert!(lines.contains(&"rmdir ok"));
+    Ok(())
+}
+
+#[cfg_attr(miri, ignore)]
+#[tokio::test]
+async fn cp() -> Result<(), anyhow::Error> {
+    let r = runner!("cp").await?;
+    let (exit_status, stdout, ()) = monitor::run_test(r, TIMEOUT, async |qemu, _gdb| {
+        monitor::run_commands(
+            qemu,
+            0,
+            [
+                "mkdir cpd",
+                "echo hello > cpd/a",
+                "cp cpd/a cpd/b",
+                "cat cpd/b",
+                "mkdir cpd/sub",
+                "cp cpd/a cpd/b cpd/sub",
+                "cat cpd/sub/a cpd/sub/b",
+                "cp cpd/a cpd/b cpd/c || echo cp failed",
+                "cp README cpd/readme",
+                "wc README cpd/readme",
+                "halt",
+            ],
+        )
+        .await?;
+        Ok(())
+    })
+    .await?;
+    assert!(exit_status.success());
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert_eq!(lines.iter().filter(|l| **l == "hello").count(), 3);
+    assert!(lines.contains(&"cp failed"));
+    let wc = lines
+        .iter()
+        .filter(|l| l.ends_with("README") || l.ends_with("cpd/readme"))
+        .map(|l| l.split_whitespace().take(3).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    assert_eq!(wc.len(), 2);
+    assert_eq!(wc[0], wc[1]);
+    Ok(())
+}
+
+#[cfg_attr(miri, ignore)]
+#[tokio::test]
+async fn newfs_fsck() -> Result<(), anyhow::Error> {
+    let r = runner!("newfs_fsck").await?;
+    let (exit_status, stdout, ()) = monitor::run_test(r, TIMEOUT, async |qemu, _gdb| {
+        monitor::run_commands(
+            qemu,
+            0,
+            [
+                "newfs -L scratch -s 200 disk.img",
+                "fsck disk.img && echo fsck ok",
+                "newfs -s 10 small.img || echo newfs failed",
+                "fsck README || echo fsck failed",
+                "halt",
+            ],
+        )
+        .await?;
+        Ok(())
+    })
+    .await?;
+    assert!(exit_status.success());
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert!(lines.contains(&"blocks 200 (meta 39, data 161) inodes 64"));
+    assert!(lines.contains(&"1/63 inodes, 1/161 blocks, 0 problems"));
+    assert!(lines.contains(&"fsck ok"));
+    assert!(lines.contains(&"newfs failed"));
+    assert!(lines.contains(&"fsck failed"));
+    Ok(())
+}
+
+#[cfg_attr(miri, ignore)]
+#[tokio::test]
+async fn resizefs() -> Result<(), anyhow::Error> {
+    let r = runner!("resizefs").await?;
+    // the image holds a file system of 2000 blocks
+    r.grow_disk(4000 * 1024)?;
+    let (exit_status, stdout, ()) = monitor::run_test(r, TIMEOUT, async |qemu, _gdb| {
+        monitor::run_commands(
+            qemu,
+            0,
+            [
+                "resizefs -s 1000 || echo shrink failed",
+                "resizefs -s 3000",
+                "resizefs -s 5000 || echo grow failed",
+                "resizefs",
+                "df",
+                "halt",
+            ],
+        )
+        .await?;
+        Ok(())
+    })
+    .await?;
+    assert!(exit_status.success());
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert!(lines.contains(&"shrink failed"));
+    assert!(lines.contains(&"resized to 3000 blocks"));
+    assert!(lines.contains(&"grow failed"));
+    assert!(lines.contains(&"resized to 4000 blocks"));
+    assert!(
+        lines
+            .iter()
+            .any(|s| s.starts_with("ov6-root") && s.split_whitespace().nth(2) == Some("4000"))
+    );
+    Ok(())
+}
+
+#[cfg_attr(miri, ignore)]
+#[tokio::test]
+async fn remount() -> Result<(), anyhow::Error> {
+    let r = runner!("remount").await?;
+    let (exit_status, stdout, ()) = monitor::run_test(r, TIMEOUT, async |qemu, _gdb| {
+        monitor::run_commands(
+            qemu,
+            0,
+            [
+                "remount -r",
+                "mkdir rodir || echo mkdir failed",
+                "ls README && echo read ok",
+                "remount -w",
+                "mkdir rodir && echo mkdir ok",
+                "halt",
+            ],
+        )
+        .await?;
+        Ok(())
+    })
+    .await?;
+    assert!(exit_status.success());
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert!(lines.contains(&"remounted read-only"));
+    assert!(lines.contains(&"mkdir failed"));
+    assert!(lines.contains(&"read ok"));
+    assert!(lines.contains(&"remounted read-write"));
+    assert!(lines.contains(&"mkdir ok"));
+    Ok(())
+}
+
+#[cfg_attr(miri, ignore)]
+#[tokio::test]
+async fn mount() -> Result<(), anyhow::Error> {
+    let r = runner!("mount").await?;
+    let (exit_status, stdout, ()) = monitor::run_test(r, TIMEOUT, async |qemu, _gdb| {
+        monitor::run_commands(
+            qemu,
+            0,
+            [
+                "mkdir mnt",
+                "mount -s 8192 tmpfs mnt",
+                "echo hello > mnt/file",
+                "cat mnt/file",
+                "df mnt",
+                "mount tmpfs README || echo mount failed",
+                "halt",
+            ],
+        )
+        .await?;
+        Ok(())
+    })
+    .await?;
+    assert!(exit_status.success());
+    let lines = stdout.lines().collect::<Vec<_>>();
+    // mounted by /etc/rc
+    assert!(lines.contains(&"mounted tmpfs on /tmp"));
+    assert!(lines.contains(&"mounted tmpfs on mnt"));
+    assert!(lines.contains(&"hello"));
+    // 8192 bytes are two pages
+    assert!(
+        lines
+            .iter()
+            .any(|s| s.starts_with("tmpfs") && s.split_whitespace().nth(2) == Some("2"))
+    );
+    assert!(lines.contains(&"mount failed"));
+    Ok(())
+}
+
+#[cfg_attr(miri, ignore)]
+#[tokio::test]
+async fn orphan_after_crash() -> Result<(), anyhow::Error> {
+    let r = runner!("orphan_after_crash").await?;
+    let next = r.next_boot();
+    // crash while an unlinked file is still open
+    let (exit_status, _stdout, ()) = monitor::run_test(r, TIMEOUT, async |qemu, _gdb| {
+        monitor::run_commands(
+            qemu,
+            0,
+            [
+                "cp README orphan",
+                "sleep 100 < orphan &",
+                "sleep 1",
+                "rm orphan",
+                "abort",
+            ],
+        )
+        .await?;
+        Ok(())
+    })
+    .await?;
+    assert_eq!(exit_status.code(), Some(255));
+
+    let (exit_status, stdout, ()) = monitor::run_test(next, TIMEOUT, async |qemu, _gdb| {
+        monitor::run_commands(qemu, 0, ["remount -r", "fsck disk0", "halt"]).await?;
+        Ok(())
+    })
+    .await?;
+    assert!(exit_status.success());
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert!(
+        lines
+            .iter()
+            .any(|s| s.starts_with("fs: quick check:") && s.ends_with(", 0 problems"))
+    );
+    assert!(lines.contains(&"fs: freed 1 orphan inodes"));
+    // the output of fsck
+    assert!(
+        lines
+            .iter()
+            .any(|s| !s.starts_with("fs:") && s.ends_with(", 0 problems"))
+    );
+    Ok(())
+}
+
+#[cfg_attr(miri, ignore)]
+#[tokio::test]
+async fn tar() -> Result<(), anyhow::Error> {
+    let r = runner!("tar").await?;
+    let fs_path = r.fs_path().to_owned();
+    let extract_dir = r.workspace_dir().join("tar");
+    let dir = helper::random_str(8);
+    let long_name = "n".repeat(13);
+    let (exit_status, stdout, ()) = monitor::run_test(r, TIMEOUT, async |qemu, _gdb| {
+        monitor::run_commands(
+            qemu,
+            0,
+            [
+                &format!("mkdir {dir}"),
+                &format!("echo hello > {dir}/a"),
+                &format!("mkdir {dir}/sub"),
+                &format!("echo hello world > {dir}/sub/{long_name}"),
+                &format!("tar -cf /archive.tar {dir}"),
+                "mkdir out",
+                "tar -x -f archive.tar -C out",
+                &format!("cat out/{dir}/sub/{long_name}"),
+                "tar -tvf archive.tar",
+                "halt",
+            ],
+        )
+        .await?;
+        Ok(())
+    })
+    .await?;
+    assert!(exit_status.success());
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert!(lines.contains(&"hello world"));
+    assert!(lines.contains(&format!("       6 {dir}/a").as_str()));
+    assert!(lines.contains(&format!("       0 {dir}/sub/").as_str()));
+
+    // the archive created in ov6 can be extracted by the host tar
+    let archive = FsImage::open(&fs_path)?.read_file("/archive.tar")?;
+    let _ = fs::remove_dir_all(&extract_dir);
+    fs::create_dir_all(&extract_dir)?;
+    fs::write(extract_dir.join("archive.tar"), archive)?;
+    let status = Command::new("tar")
+        .args(["-x", "-f", "archive.tar"])
+        .current_dir(&extract_dir)
+        .status()?;
+    assert!(status.success());
+    let extracted = extract_dir.join(&dir);
+    assert_eq!(fs::read(extracted.join("a"))?, b"hello\n");
+    assert_eq!(
+        fs::read(extracted.join("sub").join(&long_name))?,
+        b"hello world\n"
+    );
+    Ok(())
+}
+
+#[cfg_attr(miri, ignore)]
+#[tokio::test]
+async fn lz4() -> Result<(), anyhow::Error> {
+    let r = runner!("lz4").await?;
+    let fs_path = r.fs_path().to_owned();
+    let work_dir = r.workspace_dir().join("lz4");
+    let dir = helper::random_str(8);
+    let (exit_status, stdout, ()) = monitor::run_test(r, TIMEOUT, async |qemu, _gdb| {
+        monitor::run_commands(
+            qemu,
+            0,
+            [
+                "cp /bin/sh /big",
+                "lz4 /big",
+                "lz4 -dk /big.lz4",
+                &format!("mkdir {dir}"),
+                &format!("echo hello > {dir}/a"),
+                &format!("tar --lz4 -cf /archive.tar.lz4 {dir}"),
+                "tar --lz4 -tvf /archive.tar.lz4",
+                "halt",
+            ],
+        )
+        .await?;
+        Ok(())
+    })
+    .await?;
+    assert!(exit_status.success());
+    let expected = format!("       6 {dir}/a");
+    assert!(stdout.lines().any(|line| line == expected));
+
+    let image = FsImage::open(&fs_path)?;
+    let original = image.read_file("/bin/sh")?;
+    assert_eq!(image.read_file("/big")?, original);
+    let compressed = image.read_file("/big.lz4")?;
+    assert!(compressed.len() < original.len());
+
+    // the files compressed in ov6 can be decompressed by the host lz4
+    let _ = fs::remove_dir_all(&work_dir);
+    fs::create_dir_all(&work_dir)?;
+    fs::write(work_dir.join("big.lz4"), compressed)?;
+    fs::write(
+        work_dir.join("archive.tar.lz4"),
+        image.read_file("/archive.tar.lz4")?,
+    )?;
+    let status = Command::new("lz4")
+        .args(["-d", "-m", "big.lz4", "archive.tar.lz4"])
+        .current_dir(&work_dir)
+        .status()?;
+    assert!(status.success());
+    assert_eq!(fs::read(work_dir.join("big"))?, original);
+    let status = Command::new("tar")
+        .args(["-x", "-f", "archive.tar"])
+        .current_dir(&work_dir)
+        .status()?;
+    assert!(status.success());
+    assert_eq!(fs::read(work_dir.join(&dir).join("a"))?, b"hello\n");
+    Ok(())
+}
+
+#[cfg_attr(miri, ignore)]
+#[tokio::test]
+async fn sha256sum() -> Result<(), anyhow::Error> {
+    let r = runner!("sha256sum").await?;
+    let fs_path = r.fs_path().to_owned();
+    let work_dir = r.workspace_dir().join("sha256sum");
+    let (exit_status, stdout, ()) = monitor::run_test(r, TIMEOUT, async |qemu, _gdb| {
+        monitor::run_commands(
+            qemu,
+            0,
+            [
+                "sha256sum /bin/sh README",
+                "sha256sum /bin/sh README > /sums",
+                "sha256sum -c /sums",
+                "echo hello > /a",
+                "sha256sum /a > /a.sum",
+                "echo world > /a",
+                "sha256sum -c /a.sum",
+                "halt",
+            ],
+        )
+        .await?;
+        Ok(())
+    })
+    .await?;
+    assert!(exit_status.success());
+    let lines = stdout.lines().collect::<Vec<_>>();
+
+    // the digests computed in ov6 match the ones computed by the host
+    let _ = fs::remove_dir_all(&work_dir);
+    fs::create_dir_all(&work_dir)?;
+    let image = FsImage::open(&fs_path)?;
+    for (path, name) in [("/bin/sh", "/bin/sh"), ("/README", "README")] {
+        let file = work_dir.join("file");
+        fs::write(&file, image.read_file(path)?)?;
+        let output = Command::new("sha256sum").arg(&file).output()?;
+        assert!(output.status.success());
+        let output = String::from_utf8(output.stdout)?;
+        let digest = output.split_whitespace().next().unwrap();
+        assert!(lines.contains(&format!("{digest}  {name}").as_str()));
+    }
+
+    assert!(lines.contains(&"/bin/sh: OK"));
+    assert!(lines.contains(&"README: OK"));
+    assert!(lines.contains(&"/a: FAILED"));
+    Ok(())
+}
+
+#[cfg_attr(miri, ignore)]
+#[tokio::test]
+async fn umask() -> Result<(), anyhow::Error> {
+    let r = runner!("umask").await?;
+    let (exit_status, stdout, ()) = monitor::run_test(r, TIMEOUT, async |qemu, _gdb| {
+        monitor::run_commands(
+            qemu,
+            0,
+            [
+                "umask",
+                "umask 027",
+                "umask",
+                // the child shell inherits the mask across fork and exec, and
+                // changing it there does not affect the parent
+                "sh -c 'umask; umask 077; umask'",
+                "umask",
+                "umask 1000 || echo invalid",
+                "halt",
+            ],
+        )
+        .await?;
+        Ok(())
+    })
+    .await?;
+    assert!(exit_status.success());
+    let masks = stdout
+        .lines()
+        .filter(|line| line.len() == 4 && line.bytes().all(|b| b.is_ascii_digit()))
+        .collect::<Vec<_>>();
+    assert_eq!(masks, ["0022", "0027", "0027", "0077", "0027"]);
+    assert!(stdout.lines().any(|line| line == "invalid"));
+    Ok(())
+}
+
+#[cfg_attr(miri, ignore)]
+#[tokio::test]
+async fn sh_test_access() -> Result<(), anyhow::Error> {
+    let r = runner!("sh_test_access").await?;
+    let (exit_status, stdout, ()) = monitor::run_test(r, TIMEOUT, async |qemu, _gdb| {
+        monitor::run_commands(
+            qemu,
+            0,
+            [
+                "test -x /bin/sh && echo sh-x",
+                "test -r README && test -w README && echo readme-rw",
+                "test -x console || echo console-not-x",
+                "test -r no-such-file || echo missing-not-r",
+                "halt",
+            ],
+        )
+        .await?;
+        Ok(())
+    })
+    .await?;
+    assert!(exit_status.success());
+    let lines = stdout.lines().collect::<Vec<_>>();
+    for expected in ["sh-x", "readme-rw", "console-not-x", "missing-not-r"] {
+        assert!(lines.contains(&expected), "missing {expected:?}");
+    }
+    Ok(())
+}
+
+#[cfg_attr(miri, ignore)]
+#[tokio::test]
+async fn strace() -> Result<(), anyhow::Error> {
+    let r = runner!("strace").await?;
+    let (exit_status, stdout, ()) = monitor::run_test(r, TIMEOUT, async |qemu, _gdb| {
+        monitor::run_commands(
+            qemu,
+            0,
+            ["strace echo hi", "strace cat no-such-file", "halt"],
+        )
+        .await?;
+        Ok(())
+    })
+    .await?;
+    assert!(exit_status.success());
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert!(lines.contains(&"hi"));
+    // return values are read from the registers at the system call exit
+    assert!(
+        lines
+            .iter()
+            .any(|line| line.starts_with("write(0x1, ") && !line.ends_with("= ?"))
+    );
+    assert!(
+        lines
+            .iter()
+            .any(|line| line.starts_with("open") && line.contains(") = -1 ("))
+    );
+    // `exit` does not return
+    assert!(
+        lines
+            .iter()
+            .any(|line| line.starts_with("exit(0x0, ") && line.ends_with(" = ?"))
+    );
+    assert!(lines.contains(&"+++ exited with 0 +++"));
+    Ok(())
+}
+
+#[cfg_attr(miri, ignore)]
+#[tokio::test]
+async fn kallsyms() -> Result<(), anyhow::Error> {
+    let r = runner!("kallsyms").await?;
+    let (exit_status, stdout, ()) = monitor::run_test(r, TIMEOUT, async |qemu, _gdb| {
+        monitor::run_commands(qemu, 0, ["kallsyms | grep ' boot$'", "kallsyms 0", "halt"]).await?;
+        Ok(())
+    })
+    .await?;
+    assert!(exit_status.success());
+    let lines = stdout.lines().collect::<Vec<_>>();
+    // the kernel is entered at `boot`, at the start of the image, which KASLR
+    // moves from its load address to a random megapage
+    let boot = lines
+        .iter()
+        .find_map(|line| line.strip_suffix(" boot"))
+        .unwrap();
+    let boot = usize::from_str_radix(boot, 16)?;
+    assert_ne!(boot, 0x8000_0000);
+    assert!(boot.is_multiple_of(0x20_0000));
+    assert!(lines.contains(&"0x0 ?"));
+    Ok(())
+}
+
+#[cfg_attr(miri, ignore)]
+#[tokio::test]
+async fn udb() -> Result<(), anyhow::Error> {
+    let r = runner!("udb").await?;
+    let (exit_status, stdout, ()) = monitor::run_test(r, TIMEOUT, async |qemu, _gdb| {
+        monitor::run_commands(
+            qemu,
+            0,
+            [
+                "udb -e 'break compute' -e continue -e bt -e 'step 2' -e continue udbtarget",
+                "halt",
+            ],
+        )
+        .await?;
+        Ok(())
+    })
+    .await?;
+    assert!(exit_status.success());
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert!(lines.iter().any(
+        |line| line.starts_with("breakpoint 1 at ") && line.ends_with(" in udbtarget::compute")
+    ));
+    assert!(
+        lines
+            .iter()
+            .any(|line| line.starts_with("breakpoint 1, ")
+                && line.ends_with(" in udbtarget::compute"))
+    );
+    // the caller frames are found from the frame pointers
+    assert!(
+        lines
+            .iter()
+            .any(|line| line.starts_with("#0 ") && line.ends_with(" in udbtarget::compute"))
+    );
+    assert!(
+        lines
+            .iter()
+            .any(|line| line.starts_with("#1 ") && line.contains(" in udbtarget::outer+"))
+    );
+    // stepping from the breakpoint executes the original instruction
+    assert!(
+        lines
+            .iter()
+            .any(|line| line.starts_with("0x") && line.contains(" in udbtarget::compute+"))
+    );
+    assert!(lines.contains(&"result 55"));
+    assert!(lines.contains(&"program exited with status 0"));
+    Ok(())
+}
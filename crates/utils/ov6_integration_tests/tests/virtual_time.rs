@@ -0,0 +1,59 @@
+#![cfg(test)]
+
+use std::time::Duration;
+
+use ov6_integration_tests::{monitor, runner};
+
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+#[cfg_attr(miri, ignore)]
+#[tokio::test]
+async fn virtual_time_boot() -> Result<(), anyhow::Error> {
+    let mut r = runner!("virtual_time_boot").await?;
+    r.set_virtual_time(true);
+    let (exit_status, stdout, ()) = monitor::run_test(r, TIMEOUT, async |qemu, _gdb| {
+        monitor::run_commands(qemu, 0, ["sleep 1", "uptime", "halt"]).await?;
+        Ok(())
+    })
+    .await?;
+    assert!(exit_status.success());
+    assert!(stdout.contains("boot options: virtual_time"));
+    assert!(stdout.contains("virtual time enabled"));
+    assert!(stdout.contains("up 0:00:"));
+    Ok(())
+}
+
+#[cfg_attr(miri, ignore)]
+#[tokio::test]
+async fn virtual_time_sleep_race() -> Result<(), anyhow::Error> {
+    let mut r = runner!("virtual_time_sleep_race").await?;
+    r.set_virtual_time(true);
+    let (exit_status, _stdout, ()) = monitor::run_test(r, TIMEOUT, async |qemu, _gdb| {
+        monitor::run_commands(
+            qemu,
+            0,
+            ["(sleep 3; abort) &; (sleep 10; abort) &; (sleep 1; halt)"],
+        )
+        .await?;
+        Ok(())
+    })
+    .await?;
+    assert!(exit_status.success());
+    Ok(())
+}
+
+#[cfg_attr(miri, ignore)]
+#[tokio::test]
+async fn virtual_time_alarmtest() -> Result<(), anyhow::Error> {
+    let mut r = runner!("virtual_time_alarmtest").await?;
+    r.set_virtual_time(true);
+    let (exit_status, stdout, ()) = monitor::run_test(r, TIMEOUT, async |qemu, _gdb| {
+        monitor::run_commands(qemu, 0, ["alarmtest -T"]).await?;
+        Ok(())
+    })
+    .await?;
+    assert!(exit_status.success());
+    assert!(stdout.contains("PASSED"));
+    assert!(!stdout.contains("FAILED"));
+    Ok(())
+}
@@ -232,6 +232,19 @@ fn main() {
                 thread::sleep(Duration::from_secs(1));
             }
         }
+        "rxflood" => {
+            // sending bursts of packets as fast as possible to 2000.
+            let sock = bind_server(&command, server_port);
+            for i in 0.. {
+                for j in 0..256 {
+                    let txt = format!("packet {}", i * 256 + j);
+                    sock.send_to(txt.as_bytes(), ("127.0.0.1", fwd_port1))
+                        .unwrap();
+                }
+                println!("burst {i}");
+                thread::sleep(Duration::from_millis(100));
+            }
+        }
         "tx" => {
             let sock = bind_server(&command, server_port);
             listen_start(&command);